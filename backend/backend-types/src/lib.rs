@@ -1,6 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use shengji_core::{game_state, interactive};
+use shengji_core::{achievements::Achievement, game_state, interactive, notification};
+use shengji_mechanics::types::PlayerID;
+
+pub mod state_diff;
+pub mod versioned_state;
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
@@ -8,6 +12,11 @@ pub enum GameMessage {
     State {
         state: game_state::GameState,
     },
+    /// The changes since the last `State` (or `StateDiff`) sent to this
+    /// client, which are usually much smaller than the whole state.
+    StateDiff {
+        diff: Vec<state_diff::StateDiffOp>,
+    },
     Message {
         from: String,
         message: String,
@@ -29,7 +38,286 @@ pub enum GameMessage {
     Kicked {
         target: String,
     },
+    /// Sent to a player once they've joined, so that they can reclaim their
+    /// seat if they reconnect.
+    Session {
+        player_id: PlayerID,
+        name: String,
+        token: String,
+    },
+    /// Sent to the rooms of a tournament's previous round once the players
+    /// advancing from it have been seated at a new table.
+    TournamentTable {
+        tournament_id: String,
+        round: usize,
+        room_name: String,
+        players: Vec<String>,
+    },
+    TournamentFinished {
+        tournament_id: String,
+        winners: Vec<String>,
+    },
+    /// Sent to the rooms of a duplicate tournament's previous board once
+    /// every table has been dealt the next one.
+    DuplicateBoard {
+        tournament_id: String,
+        board: usize,
+        room_name: String,
+        players: Vec<String>,
+    },
+    /// How everyone in a duplicate tournament did compared to the players
+    /// who sat in the same seat at the other tables, best first.
+    DuplicateReport {
+        tournament_id: String,
+        standings: Vec<DuplicateStanding>,
+    },
+    /// How long the player whose turn it is has left to act, if anyone is
+    /// being timed. The timer doesn't run while the game is paused.
+    TurnTimer {
+        player_id: Option<PlayerID>,
+        remaining_ms: u64,
+        paused: bool,
+    },
+    /// The room is hosted by another server, which the client should
+    /// connect to instead.
+    Redirect {
+        websocket_url: String,
+    },
+    /// The career statistics of a player in the room, sent when they join
+    /// and after each game. Only claimed nicknames have them.
+    CareerStats {
+        name: String,
+        stats: CareerCard,
+    },
+    /// A player in the room unlocked an achievement for the first time.
+    AchievementUnlocked {
+        name: String,
+        achievement: Achievement,
+        description: String,
+    },
+    /// Something happened in the game which the client might want to call
+    /// attention to. Sent just before the `State` it led to.
+    Notification {
+        notification: notification::Notification,
+    },
+    /// Sent every few seconds. The client should answer with a
+    /// `UserMessage::Pong` carrying the same timestamp, or it'll be
+    /// disconnected.
+    Ping {
+        sent_at_ms: u64,
+    },
+    /// The round-trip times of the players connected to the room, as of
+    /// their last `Pong`. Sent along with each `Ping`.
+    Latencies {
+        latencies: Vec<PlayerLatency>,
+    },
+    /// The recent tricks of the game in progress, oldest first, in answer
+    /// to a `UserMessage::RequestTrickHistory`.
+    TrickHistory {
+        tricks: Vec<game_state::play_phase::CompletedTrick>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerLatency {
+    pub player_id: PlayerID,
+    pub rtt_ms: u64,
+}
+
+/// A compact summary of a player's career, for showing alongside their name.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CareerCard {
+    pub rounds_played: u64,
+    pub landlord_defense_rate: Option<f64>,
+    pub average_points_captured: Option<f64>,
+    pub biggest_throw_forfeited: u64,
+    /// From games in ranked rooms, if the player has played in any.
+    pub rating: Option<f64>,
+}
+
+/// A player's result over all of the boards of a duplicate tournament.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicateStanding {
+    pub name: String,
+    pub table: usize,
+    pub seat: usize,
+    /// The levels the player's team gained (or conceded, if negative) on
+    /// each board, less the average for the same seat across every table.
+    pub score: f64,
+}
+
+/// How messages are encoded for a client, before they're compressed. Clients
+/// pick one when they join a room; messages sent before then are JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    /// Much smaller than JSON, since field names aren't included.
+    MessagePack,
+}
+
+impl WireFormat {
+    pub fn encode(self, msg: &GameMessage) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(match self {
+            WireFormat::Json => serde_json::to_vec(msg)?,
+            WireFormat::MessagePack => rmp_serde::to_vec(msg)?,
+        })
+    }
+
+    /// Decodes a message in whichever format it was encoded in. Messages are
+    /// always JSON objects or MessagePack maps, so they can't be confused.
+    pub fn decode(data: &[u8]) -> Result<GameMessage, anyhow::Error> {
+        Ok(match data.first() {
+            Some(b'{') => serde_json::from_slice(data)?,
+            _ => rmp_serde::from_slice(data)?,
+        })
+    }
+}
+
+/// The first message a client sends, to join a room.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JoinRoom {
+    pub room_name: String,
+    pub name: String,
+    /// A token from a previous `GameMessage::Session`, used to reclaim the
+    /// same seat after a reconnect.
+    #[serde(default)]
+    pub reconnect_token: Option<String>,
+    /// Join as a spectator, who can watch the game but never play.
+    #[serde(default)]
+    pub spectate: bool,
+    /// A secret generated by the client. Joining with one claims the name,
+    /// so that it (and its statistics) can only be used from this device.
+    #[serde(default)]
+    pub device_key: Option<String>,
+    /// How to encode the messages sent to this client.
+    #[serde(default)]
+    pub wire_format: WireFormat,
+    /// The IDs of the zstd dictionaries the client can decompress with.
+    #[serde(default)]
+    pub zstd_dictionaries: Vec<u32>,
+    /// Whether the client can apply `GameMessage::StateDiff`s.
+    #[serde(default)]
+    pub state_diffs: bool,
+}
+
+/// The messages a client sends once it's joined a room.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum UserMessage {
+    Message(String),
+    Action(interactive::Action),
+    Kick(PlayerID),
+    /// Kicks the player and keeps them from rejoining. Only the host can
+    /// ban other users.
+    Ban(PlayerID),
+    Beep,
+    ReadyCheck,
+    Ready,
+    /// The answer to a `GameMessage::Ping`.
+    Pong {
+        sent_at_ms: u64,
+    },
+    /// Asks for the recent tricks of the game in progress, which are sent
+    /// back to just this connection as a `GameMessage::TrickHistory`.
+    RequestTrickHistory,
 }
 
 /// zstd dictionary, compressed with zstd.
 pub const ZSTD_ZSTD_DICT: &[u8] = include_bytes!("../dict.zstd");
+
+/// The ID of `ZSTD_ZSTD_DICT`, which is used for clients that don't say which
+/// dictionaries they have.
+pub const LEGACY_ZSTD_DICT_ID: u32 = 0x0cec_dc9d;
+
+/// Every dictionary messages might be compressed with, oldest first, as
+/// (dictionary ID, dictionary compressed with zstd). zstd writes the ID of
+/// the dictionary into the header of every frame compressed with it, which is
+/// how clients pick the dictionary to decompress a message with, so the ID
+/// doubles as the dictionary's version.
+///
+/// Dictionaries are trained from recorded traffic by `shengji-zstd-dict`.
+/// Never remove one while clients might still be using it.
+pub const ZSTD_DICTS: &[(u32, &[u8])] = &[(LEGACY_ZSTD_DICT_ID, ZSTD_ZSTD_DICT)];
+
+/// The newest dictionary out of `dict_ids` which we know about.
+pub fn newest_zstd_dict(dict_ids: &[u32]) -> Option<u32> {
+    ZSTD_DICTS
+        .iter()
+        .rev()
+        .map(|(id, _)| *id)
+        .find(|id| dict_ids.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use shengji_core::interactive::{Action, InteractiveGame};
+
+    use super::{state_diff, GameMessage, WireFormat};
+
+    #[test]
+    fn test_message_pack_round_trip() {
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        let mut game = InteractiveGame::new();
+        let mut msgs = vec![];
+        for name in ["p1", "p2", "p3", "p4"] {
+            msgs.extend(game.register(name.to_string()).unwrap().1);
+        }
+        msgs.extend(
+            game.interact_with_seed(Action::StartGame, super::PlayerID(0), 0, &logger)
+                .unwrap(),
+        );
+        let mut messages = vec![GameMessage::State {
+            state: game.dump_state_for_player(super::PlayerID(1)).unwrap(),
+        }];
+        messages.extend(
+            msgs.into_iter()
+                .map(|(data, message)| GameMessage::Broadcast { data, message }),
+        );
+
+        for msg in messages {
+            let json = serde_json::to_value(&msg).unwrap();
+            let packed = WireFormat::MessagePack.encode(&msg).unwrap();
+            assert!(packed.len() < WireFormat::Json.encode(&msg).unwrap().len());
+            let decoded = WireFormat::decode(&packed).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_state_diffs() {
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        let mut game = InteractiveGame::new();
+        for name in ["p1", "p2", "p3", "p4"] {
+            game.register(name.to_string()).unwrap();
+        }
+        let mut states = vec![game.dump_state_for_player(super::PlayerID(1)).unwrap()];
+        game.interact_with_seed(Action::StartGame, super::PlayerID(0), 0, &logger)
+            .unwrap();
+        states.push(game.dump_state_for_player(super::PlayerID(1)).unwrap());
+        for _ in 0..12 {
+            // Whoever's turn it is draws.
+            (0..4)
+                .find(|id| {
+                    game.interact(Action::DrawCard, super::PlayerID(*id), &logger)
+                        .is_ok()
+                })
+                .unwrap();
+            states.push(game.dump_state_for_player(super::PlayerID(1)).unwrap());
+        }
+
+        let mut client = serde_json::to_value(&states[0]).unwrap();
+        for pair in states.windows(2) {
+            let old = serde_json::to_value(&pair[0]).unwrap();
+            let new = serde_json::to_value(&pair[1]).unwrap();
+            let msg = GameMessage::StateDiff {
+                diff: state_diff::diff(&old, &new),
+            };
+            let packed = WireFormat::MessagePack.encode(&msg).unwrap();
+            match WireFormat::decode(&packed).unwrap() {
+                GameMessage::StateDiff { diff } => state_diff::apply(&mut client, diff).unwrap(),
+                _ => unreachable!(),
+            }
+            assert_eq!(client, new);
+        }
+    }
+}
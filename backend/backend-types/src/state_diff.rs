@@ -0,0 +1,126 @@
+use anyhow::{anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A step into a JSON value: a key of an object, or an index into an array.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// One change to a game state, as serialized to JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum StateDiffOp {
+    /// Replaces the value at `path`. If `path` ends in a new key of an object
+    /// or one past the end of an array, the value is added instead.
+    Set {
+        path: Vec<PathSegment>,
+        value: Value,
+    },
+    /// Removes a key from an object.
+    Remove { path: Vec<PathSegment> },
+    /// Shortens the array at `path` to `len` elements.
+    Truncate { path: Vec<PathSegment>, len: usize },
+}
+
+/// Computes the changes which turn `old` into `new`.
+pub fn diff(old: &Value, new: &Value) -> Vec<StateDiffOp> {
+    let mut ops = vec![];
+    diff_into(&mut vec![], old, new, &mut ops);
+    ops
+}
+
+fn diff_into(path: &mut Vec<PathSegment>, old: &Value, new: &Value, ops: &mut Vec<StateDiffOp>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (k, v) in new {
+                path.push(PathSegment::Key(k.clone()));
+                match old.get(k) {
+                    Some(old_v) => diff_into(path, old_v, v, ops),
+                    None => ops.push(StateDiffOp::Set {
+                        path: path.clone(),
+                        value: v.clone(),
+                    }),
+                }
+                path.pop();
+            }
+            for k in old.keys().filter(|k| !new.contains_key(*k)) {
+                let mut path = path.clone();
+                path.push(PathSegment::Key(k.clone()));
+                ops.push(StateDiffOp::Remove { path });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (idx, v) in new.iter().enumerate() {
+                path.push(PathSegment::Index(idx));
+                match old.get(idx) {
+                    Some(old_v) => diff_into(path, old_v, v, ops),
+                    None => ops.push(StateDiffOp::Set {
+                        path: path.clone(),
+                        value: v.clone(),
+                    }),
+                }
+                path.pop();
+            }
+            if old.len() > new.len() {
+                ops.push(StateDiffOp::Truncate {
+                    path: path.clone(),
+                    len: new.len(),
+                });
+            }
+        }
+        _ if old != new => ops.push(StateDiffOp::Set {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        _ => (),
+    }
+}
+
+/// Applies changes computed by `diff` to `state`.
+pub fn apply(state: &mut Value, ops: Vec<StateDiffOp>) -> Result<(), anyhow::Error> {
+    for op in ops {
+        match op {
+            StateDiffOp::Set { mut path, value } => match path.pop() {
+                None => *state = value,
+                Some(last) => match (lookup(state, &path)?, last) {
+                    (Value::Object(o), PathSegment::Key(k)) => {
+                        o.insert(k, value);
+                    }
+                    (Value::Array(a), PathSegment::Index(idx)) if idx < a.len() => {
+                        a[idx] = value;
+                    }
+                    (Value::Array(a), PathSegment::Index(idx)) if idx == a.len() => {
+                        a.push(value);
+                    }
+                    _ => bail!("Can't set {:?}", path),
+                },
+            },
+            StateDiffOp::Remove { mut path } => match (path.pop(), lookup(state, &path)?) {
+                (Some(PathSegment::Key(k)), Value::Object(o)) => {
+                    o.remove(&k);
+                }
+                _ => bail!("Can't remove {:?}", path),
+            },
+            StateDiffOp::Truncate { path, len } => match lookup(state, &path)? {
+                Value::Array(a) => a.truncate(len),
+                _ => bail!("Can't truncate {:?}", path),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'a>(state: &'a mut Value, path: &[PathSegment]) -> Result<&'a mut Value, anyhow::Error> {
+    path.iter().try_fold(state, |v, segment| {
+        match (v, segment) {
+            (Value::Object(o), PathSegment::Key(k)) => o.get_mut(k),
+            (Value::Array(a), PathSegment::Index(idx)) => a.get_mut(*idx),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("No value at {:?}", path))
+    })
+}
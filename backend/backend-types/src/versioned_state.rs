@@ -0,0 +1,153 @@
+//! Serializes a `GameState` along with the version of its schema, so that
+//! snapshots written by older servers can be brought up to date when they're
+//! loaded. Use it with `#[serde(with = "shengji_types::versioned_state")]`.
+//!
+//! Whenever a change to the game state would keep older snapshots from
+//! deserializing (renaming or restructuring a field, rather than adding one
+//! with `#[serde(default)]`), bump `STATE_SCHEMA_VERSION` and add a migration
+//! to the end of `MIGRATIONS` which rewrites the previous version's JSON.
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use shengji_core::game_state::GameState;
+
+/// The version of the game state schema written by this server.
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
+/// Rewrites a serialized game state from one schema version to the next.
+pub type Migration = fn(&mut Value) -> Result<(), anyhow::Error>;
+
+/// `MIGRATIONS[i]` migrates a game state from version `i + 1` to `i + 2`.
+/// Snapshots written before states were versioned are version 1.
+pub const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Serialize)]
+struct Versioned<'a> {
+    schema_version: u32,
+    state: &'a GameState,
+}
+
+pub fn serialize<S: Serializer>(state: &GameState, serializer: S) -> Result<S::Ok, S::Error> {
+    Versioned {
+        schema_version: STATE_SCHEMA_VERSION,
+        state,
+    }
+    .serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GameState, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    from_value(value).map_err(serde::de::Error::custom)
+}
+
+/// Loads a game state which was serialized by this or an older server.
+pub fn from_value(value: Value) -> Result<GameState, anyhow::Error> {
+    let (version, mut state) = split_version(value)?;
+    migrate(&mut state, version, MIGRATIONS)?;
+    Ok(serde_json::from_value(state)?)
+}
+
+/// Unversioned snapshots are a bare `GameState`, which is always an object
+/// keyed by its phase, so they can't be mistaken for a versioned one.
+fn split_version(value: Value) -> Result<(u32, Value), anyhow::Error> {
+    match value {
+        Value::Object(mut o) if o.contains_key("schema_version") => {
+            let version = o
+                .remove("schema_version")
+                .and_then(|v| v.as_u64())
+                .ok_or_else(|| anyhow!("Invalid schema version"))?;
+            let state = o
+                .remove("state")
+                .ok_or_else(|| anyhow!("Missing game state"))?;
+            Ok((version as u32, state))
+        }
+        value => Ok((1, value)),
+    }
+}
+
+fn migrate(state: &mut Value, version: u32, migrations: &[Migration]) -> Result<(), anyhow::Error> {
+    let latest = migrations.len() as u32 + 1;
+    if version == 0 || version > latest {
+        bail!(
+            "Can't load a game state with schema version {}, the latest is {}",
+            version,
+            latest
+        );
+    }
+    for migration in &migrations[version as usize - 1..] {
+        migration(state)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use shengji_core::game_state::GameState;
+    use shengji_core::interactive::InteractiveGame;
+
+    use super::{migrate, STATE_SCHEMA_VERSION};
+
+    #[derive(Serialize, Deserialize)]
+    struct Snapshot {
+        #[serde(with = "super")]
+        game: GameState,
+    }
+
+    #[test]
+    fn test_load_versioned_and_unversioned_states() {
+        let mut game = InteractiveGame::new();
+        for name in ["p1", "p2"] {
+            game.register(name.to_string()).unwrap();
+        }
+        let snapshot = Snapshot {
+            game: game.dump_state().unwrap(),
+        };
+        let expected = serde_json::to_value(&snapshot.game).unwrap();
+
+        let versioned = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(
+            versioned["game"]["schema_version"],
+            json!(STATE_SCHEMA_VERSION)
+        );
+        let loaded: Snapshot = serde_json::from_value(versioned.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&loaded.game).unwrap(), expected);
+
+        let unversioned = json!({ "game": expected.clone() });
+        let loaded: Snapshot = serde_json::from_value(unversioned).unwrap();
+        assert_eq!(serde_json::to_value(&loaded.game).unwrap(), expected);
+
+        let mut too_new = versioned;
+        too_new["game"]["schema_version"] = json!(STATE_SCHEMA_VERSION + 1);
+        assert!(serde_json::from_value::<Snapshot>(too_new).is_err());
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        fn rename(state: &mut Value) -> Result<(), anyhow::Error> {
+            let old = state.as_object_mut().unwrap().remove("old").unwrap();
+            state["new"] = old;
+            Ok(())
+        }
+        fn double(state: &mut Value) -> Result<(), anyhow::Error> {
+            state["new"] = json!(state["new"].as_u64().unwrap() * 2);
+            Ok(())
+        }
+        let migrations = [rename, double];
+
+        let mut state = json!({ "old": 1 });
+        migrate(&mut state, 1, &migrations).unwrap();
+        assert_eq!(state, json!({ "new": 2 }));
+
+        let mut state = json!({ "new": 1 });
+        migrate(&mut state, 2, &migrations).unwrap();
+        assert_eq!(state, json!({ "new": 2 }));
+
+        let mut state = json!({ "new": 1 });
+        migrate(&mut state, 3, &migrations).unwrap();
+        assert_eq!(state, json!({ "new": 1 }));
+        assert!(migrate(&mut state, 4, &migrations).is_err());
+    }
+}
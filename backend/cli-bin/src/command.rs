@@ -0,0 +1,80 @@
+//! The commands which can be typed at the prompt.
+
+use anyhow::{anyhow, bail, Error};
+use shengji_core::interactive::Action;
+use shengji_mechanics::notation::parse_cards;
+use shengji_types::UserMessage;
+
+pub const HELP: &str = "\
+Cards are written like 10♠ or 10S, with LJ and HJ for the jokers.
+  start                 start the game
+  draw                  draw a card
+  reveal                reveal a card from the kitty to pick trump
+  bid <cards>           bid with the cards, e.g. bid 2H 2H
+  takeback-bid          take back your last bid
+  pickup                pick up the kitty
+  putdown               put down the kitty
+  kitty <card>          move a card from your hand to the kitty
+  hand <card>           move a card from the kitty to your hand
+  begin                 begin playing tricks
+  play <cards>          play the cards, e.g. play AH AH KH KH
+  end                   collect the trick
+  takeback              take back the cards you just played
+  new                   score the game and start a new one
+  ready                 say you're ready to start
+  say <text>            send a chat message
+  raw <json>            send any action, e.g. raw {\"SetNumDecks\": 3}
+  state                 show the game again
+  help                  show this message
+  quit                  leave the room";
+
+pub enum Command {
+    Send(UserMessage),
+    ShowState,
+    Help,
+    Quit,
+}
+
+pub fn parse_command(line: &str) -> Result<Command, Error> {
+    let line = line.trim();
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    let action = match command {
+        "state" => return Ok(Command::ShowState),
+        "help" | "?" => return Ok(Command::Help),
+        "quit" | "exit" => return Ok(Command::Quit),
+        "say" => return Ok(Command::Send(UserMessage::Message(rest.to_string()))),
+        "ready" => return Ok(Command::Send(UserMessage::Ready)),
+        "start" => Action::StartGame,
+        "draw" => Action::DrawCard,
+        "reveal" => Action::RevealCard,
+        "bid" => {
+            let cards = parse_cards(rest)?;
+            if cards.iter().any(|c| *c != cards[0]) {
+                bail!("All of the cards in a bid must be the same");
+            }
+            Action::Bid(cards[0], cards.len())
+        }
+        "takeback-bid" => Action::TakeBackBid,
+        "pickup" => Action::PickUpKitty,
+        "putdown" => Action::PutDownKitty,
+        "kitty" => Action::MoveCardToKitty(single_card(rest)?),
+        "hand" => Action::MoveCardToHand(single_card(rest)?),
+        "begin" => Action::BeginPlay,
+        "play" => Action::PlayCards(parse_cards(rest)?),
+        "end" => Action::EndTrick,
+        "takeback" => Action::TakeBackCards,
+        "new" => Action::StartNewGame,
+        "raw" => serde_json::from_str(rest)?,
+        "" => bail!("Type a command, or help to see them all"),
+        c => bail!("Unknown command {}, type help to see them all", c),
+    };
+    Ok(Command::Send(UserMessage::Action(action)))
+}
+
+fn single_card(notation: &str) -> Result<shengji_mechanics::types::Card, Error> {
+    match parse_cards(notation)?.as_slice() {
+        [card] => Ok(*card),
+        _ => Err(anyhow!("Expected a single card")),
+    }
+}
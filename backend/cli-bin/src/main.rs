@@ -0,0 +1,148 @@
+use std::env;
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Error};
+use futures::{SinkExt, StreamExt};
+use shengji_core::game_state::GameState;
+use shengji_mechanics::types::PlayerID;
+use shengji_types::{
+    GameMessage, JoinRoom, UserMessage, WireFormat, LEGACY_ZSTD_DICT_ID, ZSTD_DICTS,
+};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_tungstenite::tungstenite::Message;
+
+mod command;
+mod render;
+
+use command::{parse_command, Command, HELP};
+
+/// The largest dictionary that the server compresses with.
+const MAX_DICT_SIZE: usize = 112_640;
+
+/// Plays in a room from the terminal, over the same websocket protocol as the
+/// browser. Everything is printed as plain text, so it works with a screen
+/// reader, and it's handy for poking at the protocol.
+///
+/// Usage: shengji-cli <websocket URL> <room name> <player name>
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() != 4 {
+        eprintln!(
+            "usage: {} <websocket URL, e.g. ws://localhost:3030/api> <room name> <player name>",
+            args[0]
+        );
+        std::process::exit(1);
+    }
+    let dicts = ZSTD_DICTS
+        .iter()
+        .map(|(id, dict)| Ok((*id, zstd::bulk::decompress(dict, MAX_DICT_SIZE)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let (socket, _) = tokio_tungstenite::connect_async(args[1].as_str()).await?;
+    let (mut tx, mut rx) = socket.split();
+    let join = JoinRoom {
+        room_name: args[2].clone(),
+        name: args[3].clone(),
+        reconnect_token: None,
+        spectate: false,
+        device_key: None,
+        wire_format: WireFormat::Json,
+        zstd_dictionaries: dicts.iter().map(|(id, _)| *id).collect(),
+        state_diffs: false,
+    };
+    tx.send(Message::Text(serde_json::to_string(&join)?))
+        .await?;
+    println!(
+        "Joining {} as {}, type help to see the commands",
+        join.room_name, join.name
+    );
+
+    let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+    let mut state: Option<GameState> = None;
+    let mut me: Option<PlayerID> = None;
+    loop {
+        tokio::select! {
+            msg = rx.next() => {
+                let msg = match msg {
+                    Some(Ok(Message::Binary(data))) => decode(&dicts, &data)?,
+                    Some(Ok(Message::Text(data))) => WireFormat::decode(data.as_bytes())?,
+                    Some(Ok(Message::Close(_))) | None => {
+                        println!("Disconnected");
+                        return Ok(());
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => bail!(e),
+                };
+                match msg {
+                    GameMessage::State { state: s } => {
+                        println!("{}", render::render(&s, me));
+                        state = Some(s);
+                    }
+                    GameMessage::Session { player_id, .. } => me = Some(player_id),
+                    GameMessage::Message { from, message } => println!("{from}: {message}"),
+                    GameMessage::Broadcast { message, .. } => println!("{message}"),
+                    GameMessage::Error(e) => println!("Error: {e}"),
+                    GameMessage::Header { messages } => {
+                        messages.iter().for_each(|m| println!("{m}"))
+                    }
+                    GameMessage::Beep { target } => println!("{target}, it's your turn"),
+                    GameMessage::ReadyCheck { from } => {
+                        println!("{from} is asking if everyone is ready, type ready if you are")
+                    }
+                    GameMessage::Kicked { target } => {
+                        println!("{target} was kicked");
+                        if target == join.name {
+                            return Ok(());
+                        }
+                    }
+                    GameMessage::Redirect { websocket_url } => {
+                        println!("This room is hosted at {websocket_url}, connect there instead");
+                        return Ok(());
+                    }
+                    _ => (),
+                }
+            }
+            line = stdin.next_line() => {
+                let line = match line? {
+                    Some(line) => line,
+                    None => return Ok(()),
+                };
+                match parse_command(&line) {
+                    Ok(Command::Send(msg)) => send(&mut tx, &msg).await?,
+                    Ok(Command::ShowState) => match &state {
+                        Some(s) => println!("{}", render::render(s, me)),
+                        None => println!("The game hasn't been received yet"),
+                    },
+                    Ok(Command::Help) => println!("{HELP}"),
+                    Ok(Command::Quit) => return Ok(()),
+                    Err(e) => println!("{e}"),
+                }
+            }
+        }
+    }
+}
+
+async fn send(
+    tx: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    msg: &UserMessage,
+) -> Result<(), Error> {
+    tx.send(Message::Text(serde_json::to_string(msg)?)).await?;
+    Ok(())
+}
+
+/// Decompresses a message from the server with whichever dictionary it says
+/// it was compressed with.
+fn decode(dicts: &[(u32, Vec<u8>)], data: &[u8]) -> Result<GameMessage, Error> {
+    let id = zstd::zstd_safe::get_dict_id_from_frame(data)
+        .map(|id| id.get())
+        .unwrap_or(LEGACY_ZSTD_DICT_ID);
+    let dict = dicts
+        .iter()
+        .find(|(d, _)| *d == id)
+        .map(|(_, dict)| dict)
+        .ok_or_else(|| anyhow!("Unknown zstd dictionary {}", id))?;
+    let mut decompressed = vec![];
+    zstd::stream::read::Decoder::with_dictionary(data, dict)?.read_to_end(&mut decompressed)?;
+    WireFormat::decode(&decompressed)
+}
@@ -0,0 +1,129 @@
+//! Renders the game as plain text, one fact per line, so that it reads well
+//! in a terminal and through a screen reader.
+
+use shengji_core::game_state::GameState;
+use shengji_core::phase::Phase;
+use shengji_mechanics::hands::Hands;
+use shengji_mechanics::trick::Trick;
+use shengji_mechanics::types::{Card, PlayerID, Trump};
+
+pub fn render(state: &GameState, me: Option<PlayerID>) -> String {
+    let name = |id: PlayerID| player_name(state, id);
+    let mut lines = vec![format!("Phase: {}", Phase::of(state))];
+    lines.push(format!(
+        "Players: {}",
+        state
+            .propagated()
+            .players()
+            .iter()
+            .map(|p| format!("{} (rank {})", p.name, p.rank().as_str()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+
+    match state {
+        GameState::Initialize(_) => (),
+        GameState::Draw(p) => {
+            for bid in p.bids() {
+                lines.push(format!(
+                    "{} bid {}",
+                    name(bid.id),
+                    format_cards(&vec![bid.card; bid.count])
+                ));
+            }
+            if let Some(me) = me {
+                lines.push(format!("Your hand: {}", format_hand(p.hands(), me)));
+            }
+        }
+        GameState::Exchange(p) => {
+            lines.push(format!("Trump: {}", format_trump(p.trump())));
+            lines.push(format!("Landlord: {}", name(p.landlord())));
+            lines.push(format!("Exchanging: {}", name(p.exchanger())));
+            if me == Some(p.exchanger()) {
+                lines.push(format!("Kitty: {}", format_cards(p.kitty())));
+            }
+            if let Some(me) = me {
+                lines.push(format!("Your hand: {}", format_hand(p.hands(), me)));
+            }
+        }
+        GameState::Play(p) => {
+            lines.push(format!("Trump: {}", format_trump(p.trump())));
+            lines.push(format!(
+                "Landlord's team: {}",
+                p.landlords_team()
+                    .iter()
+                    .map(|id| name(*id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            lines.extend(format_trick(p.trick(), &name));
+            if let Some(me) = me {
+                lines.push(format!("Your hand: {}", format_hand(p.hands(), me)));
+            }
+        }
+    }
+
+    if let Ok(next) = state.next_player() {
+        if Some(next) == me {
+            lines.push("It's your turn".to_string());
+        } else {
+            lines.push(format!("Waiting for {}", name(next)));
+        }
+    }
+    lines.join("\n")
+}
+
+fn player_name(state: &GameState, id: PlayerID) -> String {
+    state
+        .propagated()
+        .players()
+        .iter()
+        .find(|p| p.id == id)
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| format!("player {}", id.0))
+}
+
+fn format_trick(trick: &Trick, name: &impl Fn(PlayerID) -> String) -> Vec<String> {
+    let mut lines = trick
+        .played_cards()
+        .iter()
+        .map(|played| format!("{} played {}", name(played.id), format_cards(&played.cards)))
+        .collect::<Vec<_>>();
+    if let Some(winner) = trick.current_winner() {
+        lines.push(format!("{} is winning the trick", name(winner)));
+    }
+    lines
+}
+
+pub fn format_trump(trump: Trump) -> String {
+    match trump {
+        Trump::Standard { suit, number } => format!("{}{}", number.as_str(), suit.as_char()),
+        Trump::NoTrump {
+            number: Some(number),
+        } => format!("no trump, {}s", number.as_str()),
+        Trump::NoTrump { number: None } => "no trump".to_string(),
+    }
+}
+
+pub fn format_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|c| format!("{c:?}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The player's hand, sorted as it would be in the browser, or nothing if the
+/// hand isn't visible to us.
+fn format_hand(hands: &Hands, id: PlayerID) -> String {
+    let trump = hands.trump().unwrap_or(Trump::NoTrump { number: None });
+    let mut cards = match hands.get(id) {
+        Ok(hand) => hand
+            .iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(*card, *count))
+            .collect::<Vec<_>>(),
+        Err(_) => return "(hidden)".to_string(),
+    };
+    cards.sort_by(|a, b| trump.compare(*a, *b));
+    format_cards(&cards)
+}
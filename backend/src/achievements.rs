@@ -0,0 +1,65 @@
+use slog::{error, Logger};
+use tokio::sync::broadcast;
+
+use shengji_core::achievements::Achievement;
+use shengji_core::game_log::GameLog;
+use shengji_types::GameMessage;
+use storage::{SqliteStorage, SqliteUsers, Storage};
+
+use crate::events::{RoomEvent, RoomEvents};
+use crate::serving_types::VersionedGame;
+
+/// Looks back over each finished game for achievements, which are unlocked
+/// for the players who've claimed their nicknames and announced to the room
+/// the first time they're reached.
+pub async fn record_achievements(
+    logger: Logger,
+    users: SqliteUsers,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::GameFinished {
+                room_name,
+                replay: Some(replay),
+                ..
+            }) => {
+                let log = match GameLog::from_replay(&replay) {
+                    Ok(log) => log,
+                    Err(e) => {
+                        error!(logger, "Failed to read back game"; "error" => format!("{e:?}"));
+                        continue;
+                    }
+                };
+                for (name, achievement) in Achievement::reached_in(&log) {
+                    match users.unlock_achievement(&name, achievement.key()) {
+                        Ok(true) => {
+                            let _ = backend_storage
+                                .clone()
+                                .publish(
+                                    room_name.as_bytes().to_vec(),
+                                    GameMessage::AchievementUnlocked {
+                                        name,
+                                        achievement,
+                                        description: achievement.description().to_string(),
+                                    },
+                                )
+                                .await;
+                        }
+                        Ok(false) => (),
+                        Err(e) => {
+                            error!(logger, "Failed to unlock achievement"; "error" => format!("{e:?}"))
+                        }
+                    }
+                }
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
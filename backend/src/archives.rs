@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+
+use storage::SqliteStorage;
+
+use crate::{serving_types::VersionedGame, ADMIN_TOKEN};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, &'static str)>;
+
+#[derive(Debug, Serialize)]
+pub struct ArchivedRoom {
+    room_name: String,
+    version: u64,
+    size: usize,
+    compressed_size: usize,
+    archived_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeArchives {
+    older_than_days: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Purged {
+    num_purged: usize,
+}
+
+/// Only operators who know `ADMIN_TOKEN` can use the admin endpoints, like
+/// managing archives. Without it, nobody can.
+pub(crate) fn authorize(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match (ADMIN_TOKEN.as_deref(), token) {
+        (Some(expected), Some(token)) if expected == token => Ok(()),
+        _ => Err((StatusCode::UNAUTHORIZED, "not authorized")),
+    }
+}
+
+pub async fn list_archives(
+    headers: HeaderMap,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ApiResult<Vec<ArchivedRoom>> {
+    authorize(&headers)?;
+    let archived = backend_storage
+        .list_archived()
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to list archives"))?;
+    Ok(Json(
+        archived
+            .into_iter()
+            .map(|a| ArchivedRoom {
+                room_name: String::from_utf8_lossy(&a.key).into_owned(),
+                version: a.version,
+                size: a.size,
+                compressed_size: a.compressed_size,
+                archived_at: a.archived_at,
+            })
+            .collect(),
+    ))
+}
+
+pub async fn purge_archive(
+    headers: HeaderMap,
+    Path(room_name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ApiResult<Purged> {
+    authorize(&headers)?;
+    match backend_storage.purge_archived(room_name.as_bytes()) {
+        Ok(true) => Ok(Json(Purged { num_purged: 1 })),
+        Ok(false) => Err((StatusCode::NOT_FOUND, "room is not archived")),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "failed to purge archive")),
+    }
+}
+
+pub async fn purge_archives(
+    headers: HeaderMap,
+    Query(req): Query<PurgeArchives>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ApiResult<Purged> {
+    authorize(&headers)?;
+    let num_purged = backend_storage
+        .purge_archived_older_than(Duration::from_secs(
+            req.older_than_days.saturating_mul(24 * 3600),
+        ))
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to purge archives",
+            )
+        })?;
+    Ok(Json(Purged { num_purged }))
+}
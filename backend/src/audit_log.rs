@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Mutex;
+
+use axum::{extract::Path, http::HeaderMap, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::types::PlayerID;
+
+use crate::{archives::authorize, utils::now_ms, AUDIT_LOG_PATH};
+
+/// How many entries are kept in memory, across all rooms.
+const MAX_ENTRIES: usize = 50_000;
+/// Once the persisted log gets this big, it's moved aside to `<path>.1`,
+/// replacing the previous one, and a new one is started.
+const MAX_LOG_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What happened to a message received from a client.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "reason")]
+pub enum AuditOutcome {
+    Accepted,
+    /// The message was understood, but the game didn't allow it.
+    Rejected(String),
+    RateLimited,
+    Malformed(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    pub room_name: String,
+    pub ws_id: usize,
+    /// Unset for messages received before the player joined the room.
+    pub player_id: Option<PlayerID>,
+    pub name: String,
+    /// The hashed IP address and device key of the connection, which can be
+    /// compared across entries but not reversed.
+    pub identities: Vec<String>,
+    pub message_type: String,
+    /// The message as it was received. Requests to join aren't kept, since
+    /// they include the device key.
+    pub message: Option<String>,
+    pub outcome: AuditOutcome,
+}
+
+struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+    file: Option<(File, u64)>,
+}
+
+lazy_static::lazy_static! {
+    static ref AUDIT_LOG: Mutex<AuditLog> = Mutex::new(AuditLog {
+        entries: VecDeque::new(),
+        file: AUDIT_LOG_PATH.as_ref().and_then(|path| open_log(path)),
+    });
+}
+
+fn open_log(path: &str) -> Option<(File, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .ok()?;
+    let len = file.metadata().ok()?.len();
+    Some((file, len))
+}
+
+/// Identifies a client connection in the audit log.
+#[derive(Clone, Debug)]
+pub struct ConnectionAudit {
+    pub room_name: String,
+    pub ws_id: usize,
+    pub player_id: Option<PlayerID>,
+    pub name: String,
+    pub identities: Vec<String>,
+}
+
+impl ConnectionAudit {
+    /// Appends an entry for a message received on this connection to the
+    /// audit log, and to `AUDIT_LOG_PATH` if it's set.
+    pub fn record(&self, message_type: &str, message: Option<&[u8]>, outcome: AuditOutcome) {
+        let entry = AuditEntry {
+            timestamp_ms: now_ms(),
+            room_name: self.room_name.clone(),
+            ws_id: self.ws_id,
+            player_id: self.player_id,
+            name: self.name.clone(),
+            identities: self.identities.clone(),
+            message_type: message_type.to_string(),
+            message: message.map(|m| String::from_utf8_lossy(m).into_owned()),
+            outcome,
+        };
+        let mut log = AUDIT_LOG.lock().unwrap();
+        if let (Some(path), Some((file, len))) = (AUDIT_LOG_PATH.as_ref(), log.file.as_mut()) {
+            if let Ok(mut line) = serde_json::to_vec(&entry) {
+                line.push(b'\n');
+                if file.write_all(&line).is_ok() {
+                    *len += line.len() as u64;
+                }
+            }
+            if *len >= MAX_LOG_BYTES {
+                let _ = fs::rename(path, format!("{path}.1"));
+                log.file = open_log(path);
+            }
+        }
+        if log.entries.len() >= MAX_ENTRIES {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(entry);
+    }
+}
+
+/// Every entry for the room which is still in the audit log, oldest first.
+/// If the log is persisted, that includes the rotated-out file too.
+fn room_entries(room_name: &str) -> Vec<AuditEntry> {
+    let path = match AUDIT_LOG_PATH.as_ref() {
+        Some(path) => path,
+        None => {
+            let log = AUDIT_LOG.lock().unwrap();
+            return log
+                .entries
+                .iter()
+                .filter(|e| e.room_name == room_name)
+                .cloned()
+                .collect();
+        }
+    };
+    let mut entries = vec![];
+    for path in [format!("{path}.1"), path.to_string()].iter() {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        entries.extend(
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+                .filter(|e| e.room_name == room_name),
+        );
+    }
+    entries
+}
+
+/// The audit trail for the room, for operators looking into reports of
+/// multi-accounting or cheating.
+pub async fn room_audit_log(
+    headers: HeaderMap,
+    Path(room_name): Path<String>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, &'static str)> {
+    authorize(&headers)?;
+    Ok(Json(
+        tokio::task::spawn_blocking(move || room_entries(&room_name))
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "failed to read audit log",
+                )
+            })?,
+    ))
+}
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use slog::{error, o, Logger};
+use tokio::sync::{broadcast, oneshot};
+
+use shengji_core::bot::SimpleBot;
+use shengji_types::GameMessage;
+use storage::SqliteStorage;
+
+use crate::{
+    events::{RoomEvent, RoomEvents},
+    serving_types::VersionedGame,
+    utils::{execute_operation, now_ms},
+};
+
+/// Bots don't have a websocket, so there's nobody to send errors to.
+const BOT_WS_ID: usize = 0;
+
+/// Gives the people at the table a moment to see what happened before the
+/// bots move.
+const BOT_DELAY: Duration = Duration::from_millis(800);
+
+/// Makes the moves of every bot seated in a room, one at a time, after each
+/// change to the room.
+pub async fn run_bots(
+    logger: Logger,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    // Rooms with a bot move already on the way. Anything that happens
+    // before it's made is taken into account when it is.
+    let pending = Arc::new(Mutex::new(HashSet::new()));
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::TurnChanged { room_name, .. }) => {
+                if !pending.lock().unwrap().insert(room_name.clone()) {
+                    continue;
+                }
+                tokio::task::spawn(act_for_bots(
+                    logger.new(o!("room" => room_name.clone())),
+                    room_name,
+                    pending.clone(),
+                    backend_storage.clone(),
+                    events.clone(),
+                ));
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn act_for_bots(
+    logger: Logger,
+    room_name: String,
+    pending: Arc<Mutex<HashSet<String>>>,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    tokio::time::sleep(BOT_DELAY).await;
+    pending.lock().unwrap().remove(&room_name);
+
+    let (acted_tx, acted_rx) = oneshot::channel();
+    let room_name_ = room_name.clone();
+    execute_operation(
+        BOT_WS_ID,
+        &room_name,
+        backend_storage,
+        move |game, _, _, replay, _| {
+            let msgs = game.act_for_bots(&SimpleBot, replay, now_ms(), &logger)?;
+            let _ = acted_tx.send((
+                RoomEvent::from_messages(&room_name_, game, replay, &msgs),
+                game.timed_turn(),
+                game.is_paused(),
+            ));
+            Ok(msgs
+                .into_iter()
+                .map(|(data, message)| GameMessage::Broadcast { data, message })
+                .collect())
+        },
+        "act for bots",
+    )
+    .await;
+    // Keep going until none of the bots have anything left to do.
+    if let Ok((room_events, turn, paused)) = acted_rx.await {
+        for event in room_events {
+            let _ = events.send(event);
+        }
+        let _ = events.send(RoomEvent::TurnChanged {
+            room_name,
+            turn,
+            paused,
+        });
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use slog::{error, info, Logger};
+
+use shengji_types::GameMessage;
+use storage::{RedisRooms, RedisStorageError, SqliteStorage, Storage};
+
+use crate::serving_types::VersionedGame;
+
+/// How long a claim on a room lasts if it isn't refreshed, e.g. because the
+/// instance which hosted the room went away.
+const CLAIM_TTL: Duration = Duration::from_secs(60);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+/// How long the last saved state of a room is kept for the next instance to
+/// host it.
+const SNAPSHOT_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Lets several servers share the load behind a load balancer. Each room is
+/// hosted by exactly one instance, which is tracked in Redis; clients which
+/// join a room on the wrong instance are redirected to the right one.
+///
+/// The host saves each room's state to Redis whenever it refreshes its
+/// claim, and whichever instance hosts the room next starts from there. If
+/// an instance goes away, up to `REFRESH_INTERVAL` of changes to its rooms
+/// are lost. An instance which finds that another one has taken over a room
+/// redirects its clients there and forgets its own copy.
+///
+/// Set `REDIS_URL` and `INSTANCE_WEBSOCKET_URL` (this instance's own
+/// websocket address) to enable it, and share `RECONNECT_SECRET` between the
+/// instances so that reconnection tokens work on all of them. Otherwise,
+/// this instance hosts every room.
+#[derive(Clone)]
+pub struct Cluster {
+    logger: Logger,
+    rooms: Option<RedisRooms>,
+    instance_url: String,
+}
+
+impl Cluster {
+    pub async fn from_env(logger: &Logger) -> Self {
+        let single_node = Cluster {
+            logger: logger.clone(),
+            rooms: None,
+            instance_url: String::new(),
+        };
+        let (redis_url, instance_url) = match (
+            std::env::var("REDIS_URL"),
+            std::env::var("INSTANCE_WEBSOCKET_URL"),
+        ) {
+            (Ok(redis_url), Ok(instance_url)) => (redis_url, instance_url),
+            (Ok(_), Err(_)) => {
+                error!(
+                    logger,
+                    "REDIS_URL requires INSTANCE_WEBSOCKET_URL, running as a single node"
+                );
+                return single_node;
+            }
+            _ => return single_node,
+        };
+        let rooms = match redis::Client::open(redis_url) {
+            Ok(client) => RedisRooms::new(client, CLAIM_TTL).await,
+            Err(e) => Err(e.into()),
+        };
+        match rooms {
+            Ok(rooms) => {
+                info!(logger, "Sharing rooms with other instances"; "instance" => &instance_url);
+                Cluster {
+                    logger: logger.clone(),
+                    rooms: Some(rooms),
+                    instance_url,
+                }
+            }
+            Err(e) => {
+                error!(logger, "Failed to connect to Redis, running as a single node"; "error" => format!("{e:?}"));
+                single_node
+            }
+        }
+    }
+
+    /// The websocket address of the instance hosting `room`, if it isn't this
+    /// one. Rooms nobody hosts yet are claimed by this instance, which picks
+    /// up from the state the last host saved.
+    pub async fn route<S, E>(
+        &self,
+        room: &str,
+        backend_storage: S,
+    ) -> Result<Option<String>, RedisStorageError>
+    where
+        S: Storage<VersionedGame, E>,
+        E: std::fmt::Debug + Send,
+    {
+        let rooms = match self.rooms {
+            Some(ref rooms) => rooms,
+            None => return Ok(None),
+        };
+        let owner = rooms.claim(room.as_bytes(), &self.instance_url).await?;
+        if owner != self.instance_url {
+            return Ok(Some(owner));
+        }
+        if let Err(e) = take_over(rooms, room, backend_storage).await {
+            error!(self.logger, "Failed to restore room"; "room" => room, "error" => format!("{e:?}"));
+        }
+        Ok(None)
+    }
+}
+
+/// Replaces this instance's copy of `room` with the one saved by the last
+/// instance to host it, if that one is newer.
+async fn take_over<S, E>(
+    rooms: &RedisRooms,
+    room: &str,
+    backend_storage: S,
+) -> Result<(), anyhow::Error>
+where
+    S: Storage<VersionedGame, E>,
+    E: std::fmt::Debug + Send,
+{
+    let snapshot = match rooms.snapshot(room.as_bytes()).await? {
+        Some(snapshot) => snapshot,
+        None => return Ok(()),
+    };
+    let mut snapshot = serde_json::from_slice::<VersionedGame>(&snapshot)?;
+    let local = backend_storage
+        .clone()
+        .get(room.as_bytes().to_vec())
+        .await
+        .map_err(|e| anyhow!("{e:?}"))?;
+    if snapshot.monotonic_id <= local.monotonic_id {
+        return Ok(());
+    }
+    // Websocket IDs only mean something on the instance which handed them
+    // out.
+    snapshot.associated_websockets = local.associated_websockets;
+    backend_storage
+        .put_cas(local.monotonic_id, snapshot)
+        .await
+        .map_err(|e| anyhow!("{e:?}"))
+}
+
+/// Keeps this instance's claims on the rooms it hosts from expiring, and
+/// saves their states for whichever instance hosts them next. Rooms which
+/// another instance has taken over are handed over to it.
+pub async fn refresh_claims(
+    logger: Logger,
+    cluster: Cluster,
+    backend_storage: SqliteStorage<VersionedGame>,
+) {
+    let rooms = match cluster.rooms {
+        Some(ref rooms) => rooms,
+        None => return,
+    };
+    // The version of each room which was last saved, so that rooms which
+    // haven't changed aren't saved again.
+    let mut saved = HashMap::new();
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let keys = match backend_storage.clone().get_all_keys().await {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!(logger, "Failed to list rooms"; "error" => format!("{e:?}"));
+                continue;
+            }
+        };
+        let hosted = keys.iter().cloned().collect::<HashSet<_>>();
+        saved.retain(|key, _| hosted.contains(key));
+        for key in keys {
+            let room = String::from_utf8_lossy(&key).into_owned();
+            match rooms.claim(&key, &cluster.instance_url).await {
+                Ok(owner) if owner != cluster.instance_url => {
+                    info!(logger, "Room was taken over by another instance";
+                        "room" => &room,
+                        "owner" => &owner
+                    );
+                    let _ = backend_storage
+                        .clone()
+                        .publish(
+                            key.clone(),
+                            GameMessage::Redirect {
+                                websocket_url: owner,
+                            },
+                        )
+                        .await;
+                    saved.remove(&key);
+                    if let Err(e) = backend_storage.clone().evict(&key).await {
+                        error!(logger, "Failed to forget room"; "room" => &room, "error" => format!("{e:?}"));
+                    }
+                }
+                Ok(_) => {
+                    let state = match backend_storage.clone().get(key.clone()).await {
+                        Ok(state) => state,
+                        Err(e) => {
+                            error!(logger, "Failed to load room"; "room" => &room, "error" => format!("{e:?}"));
+                            continue;
+                        }
+                    };
+                    if saved.get(&key) == Some(&state.monotonic_id) {
+                        continue;
+                    }
+                    let saving = match serde_json::to_vec(&state) {
+                        Ok(snapshot) => rooms.save_snapshot(&key, snapshot, SNAPSHOT_TTL).await,
+                        Err(e) => {
+                            error!(logger, "Failed to serialize room"; "room" => &room, "error" => format!("{e:?}"));
+                            continue;
+                        }
+                    };
+                    match saving {
+                        Ok(()) => {
+                            saved.insert(key, state.monotonic_id);
+                        }
+                        Err(e) => {
+                            error!(logger, "Failed to save room"; "room" => &room, "error" => format!("{e:?}"));
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(logger, "Failed to refresh room claim"; "error" => format!("{e:?}"));
+                    break;
+                }
+            }
+        }
+    }
+}
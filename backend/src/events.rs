@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use slog::{error, Logger};
+use tokio::sync::broadcast;
+
+use shengji_core::game_state::{play_phase::PlayerGameFinishedResult, TimedTurn};
+use shengji_core::interactive::{BroadcastMessage, InteractiveGame};
+use shengji_core::message::MessageVariant;
+use shengji_core::replay::Replay;
+use shengji_mechanics::types::PlayerID;
+use storage::{CareerRound, GameResult, SqliteGameResults, SqliteStorage, SqliteUsers, Storage};
+
+use crate::{serving_types::VersionedGame, utils::career_stats_message};
+
+/// Things which happen in rooms that other parts of the server care about.
+#[derive(Clone, Debug)]
+pub enum RoomEvent {
+    GameStarted {
+        room_name: String,
+    },
+    GameFinished {
+        room_name: String,
+        results: HashMap<String, PlayerGameFinishedResult>,
+        /// The points collected by the team attacking the landlord.
+        non_landlords_points: isize,
+        /// Whether the outcome should change the players' ratings.
+        ranked: bool,
+        /// Everything which happened over the course of the game.
+        replay: Option<Arc<Replay>>,
+    },
+    /// The last game of a match finished.
+    MatchComplete {
+        room_name: String,
+        winners: Vec<PlayerID>,
+    },
+    /// The room may be waiting on a different turn, e.g. because someone
+    /// played or the settings changed, or may have been paused or resumed.
+    TurnChanged {
+        room_name: String,
+        turn: Option<TimedTurn>,
+        paused: bool,
+    },
+}
+
+impl RoomEvent {
+    /// The events announced by the messages resulting from an action on
+    /// `game`, whose current or most recent game is recorded in `replay`.
+    pub fn from_messages(
+        room_name: &str,
+        game: &InteractiveGame,
+        replay: &Option<Replay>,
+        msgs: &[(BroadcastMessage, String)],
+    ) -> Vec<RoomEvent> {
+        // The summary is sent along with the results of the game.
+        let non_landlords_points = msgs
+            .iter()
+            .find_map(|(data, _)| match data.variant() {
+                MessageVariant::EndOfGameSummary {
+                    non_landlords_points,
+                    ..
+                } => Some(*non_landlords_points),
+                _ => None,
+            })
+            .unwrap_or(0);
+        msgs.iter()
+            .flat_map(|(data, _)| match data.variant() {
+                MessageVariant::StartingGame => Some(RoomEvent::GameStarted {
+                    room_name: room_name.to_string(),
+                }),
+                MessageVariant::GameFinished { result } => Some(RoomEvent::GameFinished {
+                    room_name: room_name.to_string(),
+                    results: result.clone(),
+                    non_landlords_points,
+                    ranked: game.is_ranked(),
+                    replay: replay.clone().map(Arc::new),
+                }),
+                MessageVariant::MatchComplete { winners, .. } => Some(RoomEvent::MatchComplete {
+                    room_name: room_name.to_string(),
+                    winners: winners.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+pub type RoomEvents = broadcast::Sender<RoomEvent>;
+
+pub fn room_events() -> RoomEvents {
+    broadcast::channel(1024).0
+}
+
+/// Keeps the statistics of claimed nicknames up to date, including their
+/// ratings for games in ranked rooms, and records the results of every game.
+/// Players' updated career statistics are shown to their rooms.
+pub async fn record_user_stats(
+    logger: Logger,
+    users: SqliteUsers,
+    game_results: SqliteGameResults,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::GameFinished {
+                room_name,
+                results,
+                non_landlords_points,
+                ranked,
+                ..
+            }) => {
+                if ranked {
+                    let team = |defending: bool| {
+                        results
+                            .iter()
+                            .filter(|(_, r)| r.is_defending == defending)
+                            .map(|(name, _)| name.clone())
+                            .collect::<Vec<_>>()
+                    };
+                    let defenders_won = results.values().any(|r| r.is_defending && r.won_game);
+                    if let Err(e) =
+                        users.record_rated_game(&team(true), &team(false), defenders_won)
+                    {
+                        error!(logger, "Failed to update ratings"; "error" => format!("{e:?}"));
+                    }
+                }
+                let mut records = vec![];
+                for (player_name, result) in results {
+                    let level = serde_json::to_string(&result.rank).unwrap_or_default();
+                    if let Err(e) =
+                        users.record_game(&player_name, result.won_game, result.ranks_up, &level)
+                    {
+                        error!(logger, "Failed to record game"; "error" => format!("{e:?}"));
+                    }
+                    let round = CareerRound {
+                        is_landlord: result.is_landlord,
+                        won: result.won_game,
+                        points_captured: result.points_captured,
+                        largest_failed_throw: result.largest_failed_throw,
+                    };
+                    match users
+                        .record_round(&player_name, round)
+                        .and_then(|()| career_stats_message(&users, &player_name))
+                    {
+                        Ok(Some(msg)) => {
+                            let _ = backend_storage
+                                .clone()
+                                .publish(room_name.as_bytes().to_vec(), msg)
+                                .await;
+                        }
+                        Ok(None) => (),
+                        Err(e) => {
+                            error!(logger, "Failed to record career stats"; "error" => format!("{e:?}"))
+                        }
+                    }
+                    records.push(GameResult {
+                        room_name: room_name.clone(),
+                        player: player_name,
+                        is_landlord: result.is_landlord,
+                        is_defending: result.is_defending,
+                        won: result.won_game,
+                        ranks_up: result.ranks_up as u64,
+                        level,
+                        attacking_points: non_landlords_points as i64,
+                    });
+                }
+                if let Err(e) = game_results.record(&records) {
+                    error!(logger, "Failed to record game results"; "error" => format!("{e:?}"));
+                }
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use slog::{error, Logger};
+use tokio::sync::broadcast;
+
+use shengji_core::game_log::GameLog;
+use shengji_core::replay::Replay;
+use shengji_core::settings::{GameModeSettings, PropagatedState};
+use storage::{FinishedGame, PastGame, SqliteGameHistory};
+
+use crate::events::{RoomEvent, RoomEvents};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, &'static str)>;
+
+/// What a game was played with, as listed in the players' histories.
+#[derive(Debug, Serialize)]
+struct SettingsSummary {
+    game_mode: GameModeSettings,
+    num_decks: usize,
+    ranked: bool,
+    /// The names of the other settings which were changed from their
+    /// defaults.
+    changed_settings: Vec<String>,
+}
+
+impl SettingsSummary {
+    fn new(settings: &PropagatedState) -> Self {
+        SettingsSummary {
+            game_mode: settings.game_mode(),
+            num_decks: settings.num_decks(),
+            ranked: settings.ranked(),
+            changed_settings: PropagatedState::default()
+                .diff_settings(settings)
+                .map(|changes| changes.into_iter().map(|c| c.setting).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Keeps every finished game, with its replay, in the history of each of the
+/// players who played it.
+pub async fn record_game_history(
+    logger: Logger,
+    game_history: SqliteGameHistory,
+    events: RoomEvents,
+) {
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::GameFinished {
+                room_name,
+                results,
+                non_landlords_points,
+                replay,
+                ..
+            }) => {
+                let mut players = results.keys().cloned().collect::<Vec<_>>();
+                players.sort();
+                let settings = replay
+                    .as_ref()
+                    .map(|r| SettingsSummary::new(r.initial_state.propagated()));
+                let game = FinishedGame {
+                    room_name,
+                    winners: players
+                        .iter()
+                        .filter(|p| results[*p].won_game)
+                        .cloned()
+                        .collect(),
+                    landlord: players.iter().find(|p| results[*p].is_landlord).cloned(),
+                    players,
+                    settings: serde_json::to_string(&settings).unwrap_or_default(),
+                    attacking_points: non_landlords_points as i64,
+                    replay: replay.and_then(|r| serde_json::to_string(&*r).ok()),
+                };
+                if let Err(e) = game_history.record(&game) {
+                    error!(logger, "Failed to record game history"; "error" => format!("{e:?}"));
+                }
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameHistoryQuery {
+    /// Only list games older than this one, to page through the history.
+    before: Option<i64>,
+    #[serde(default = "default_num_games")]
+    limit: usize,
+}
+
+fn default_num_games() -> usize {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameHistoryEntry {
+    #[serde(flatten)]
+    game: PastGame,
+    replay_url: Option<String>,
+    game_log_url: Option<String>,
+}
+
+/// The player's past games, newest first, whether or not they've claimed
+/// their nickname.
+pub async fn list_games(
+    Path(nickname): Path<String>,
+    Query(query): Query<GameHistoryQuery>,
+    Extension(game_history): Extension<SqliteGameHistory>,
+) -> ApiResult<Vec<GameHistoryEntry>> {
+    match game_history.games_for_player(&nickname, query.before, query.limit.min(100)) {
+        Ok(games) => Ok(Json(
+            games
+                .into_iter()
+                .map(|game| GameHistoryEntry {
+                    replay_url: Some(format!("/api/games/{}/replay", game.id))
+                        .filter(|_| game.has_replay),
+                    game_log_url: Some(format!("/api/games/{}/game_log", game.id))
+                        .filter(|_| game.has_replay),
+                    game,
+                })
+                .collect(),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to look up game history",
+        )),
+    }
+}
+
+fn load_replay(
+    game_history: &SqliteGameHistory,
+    id: i64,
+) -> Result<Replay, (StatusCode, &'static str)> {
+    match game_history.replay(id) {
+        Ok(Some(replay)) => serde_json::from_str(&replay)
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load replay")),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "no replay for this game")),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "failed to load replay")),
+    }
+}
+
+/// The replay of a past game, in the same format as `/replay/:room_name`.
+pub async fn get_replay(
+    Path(id): Path<i64>,
+    Extension(game_history): Extension<SqliteGameHistory>,
+) -> ApiResult<Replay> {
+    Ok(Json(load_replay(&game_history, id)?))
+}
+
+/// A structured summary of a past game, in the same format as
+/// `/game_log/:room_name`.
+pub async fn get_game_log(
+    Path(id): Path<i64>,
+    Extension(game_history): Extension<SqliteGameHistory>,
+) -> ApiResult<GameLog> {
+    let replay = load_replay(&game_history, id)?;
+    GameLog::from_replay(&replay)
+        .map(Json)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to replay game"))
+}
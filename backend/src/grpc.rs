@@ -0,0 +1,191 @@
+//! The gRPC interface in `proto/shengji.proto`. Each call to `Play` is handled
+//! exactly like a websocket connection: the client's messages are translated
+//! into the websocket protocol, and the server's messages back out of it, so
+//! the two can't drift apart.
+
+use std::io::Read;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+
+use anyhow::{bail, Error};
+use futures::{Stream, StreamExt};
+use shengji_mechanics::types::PlayerID;
+use shengji_types::{
+    GameMessage, JoinRoom, UserMessage, WireFormat, LEGACY_ZSTD_DICT_ID, ZSTD_ZSTD_DICT,
+};
+use slog::{debug, error, o};
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::{metrics, server::NEXT_USER_ID, shengji_handler, ShengjiServer, ROOT_LOGGER};
+
+pub mod proto {
+    tonic::include_proto!("shengji");
+}
+
+use proto::{client_message, game_server::Game, server_message, ClientMessage, ServerMessage};
+
+lazy_static::lazy_static! {
+    /// The messages sent for gRPC clients are always compressed with this
+    /// dictionary, since that's the only one they say they have.
+    static ref LEGACY_ZSTD_DICT: Vec<u8> = zstd::bulk::decompress(ZSTD_ZSTD_DICT, 112_640).unwrap();
+
+    /// Where to serve the gRPC interface, if anywhere.
+    pub(crate) static ref GRPC_ADDR: Option<std::net::SocketAddr> = {
+        std::env::var("GRPC_ADDR").ok().and_then(|a| a.parse().ok())
+    };
+}
+
+#[derive(Clone)]
+pub struct GrpcService {
+    server: ShengjiServer,
+}
+
+impl GrpcService {
+    pub(crate) fn new(server: ShengjiServer) -> Self {
+        Self { server }
+    }
+}
+
+type ServerMessageStream = Pin<Box<dyn Stream<Item = Result<ServerMessage, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Game for GrpcService {
+    type PlayStream = ServerMessageStream;
+
+    async fn play(
+        &self,
+        request: Request<Streaming<ClientMessage>>,
+    ) -> Result<Response<Self::PlayStream>, Status> {
+        let ip = request
+            .remote_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_default();
+        let ip_identity = self.server.sessions.identity(ip.as_bytes());
+        let ws_id = NEXT_USER_ID.fetch_add(1, Ordering::Relaxed);
+        let logger = ROOT_LOGGER.new(o!("ws_id" => ws_id, "transport" => "grpc"));
+        let mut incoming = request.into_inner();
+
+        let logger_ = logger.clone();
+        let (tx2, rx2) = mpsc::unbounded_channel();
+        tokio::task::spawn(async move {
+            while let Some(msg) = incoming.next().await {
+                match msg.map_err(Error::from).and_then(to_websocket_message) {
+                    Ok(msg) => {
+                        if tx2.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!(logger_, "Failed to fetch message"; "error" => format!("{e:?}"));
+                        break;
+                    }
+                }
+            }
+            debug!(logger_, "Ending rx task");
+        });
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let server = self.server.clone();
+        metrics::CONNECTED_CLIENTS.inc();
+        tokio::task::spawn(async move {
+            shengji_handler::entrypoint(
+                tx,
+                rx2,
+                ws_id,
+                ip_identity,
+                logger,
+                server.backend_storage,
+                server.stats,
+                server.sessions,
+                server.users,
+                server.events,
+                server.cluster,
+            )
+            .await;
+            metrics::CONNECTED_CLIENTS.dec();
+        });
+
+        let outgoing = futures::stream::unfold(rx, |mut rx| async move {
+            let msg = rx.recv().await?;
+            let msg = from_websocket_message(&msg).map_err(|e| Status::internal(e.to_string()));
+            Some((msg, rx))
+        });
+        Ok(Response::new(Box::pin(outgoing)))
+    }
+}
+
+/// Encodes the client's message as it would have been sent over the
+/// websocket.
+fn to_websocket_message(msg: ClientMessage) -> Result<Vec<u8>, Error> {
+    use client_message::Message;
+
+    let msg = match msg.message {
+        Some(Message::Join(join)) => {
+            return Ok(serde_json::to_vec(&JoinRoom {
+                room_name: join.room_name,
+                name: join.name,
+                reconnect_token: join.reconnect_token,
+                spectate: join.spectate,
+                device_key: join.device_key,
+                wire_format: WireFormat::Json,
+                zstd_dictionaries: vec![LEGACY_ZSTD_DICT_ID],
+                state_diffs: false,
+            })?)
+        }
+        Some(Message::ActionJson(action)) => UserMessage::Action(serde_json::from_str(&action)?),
+        Some(Message::Chat(message)) => UserMessage::Message(message),
+        Some(Message::Kick(id)) => UserMessage::Kick(PlayerID(id as usize)),
+        Some(Message::Ban(id)) => UserMessage::Ban(PlayerID(id as usize)),
+        Some(Message::Beep(_)) => UserMessage::Beep,
+        Some(Message::ReadyCheck(_)) => UserMessage::ReadyCheck,
+        Some(Message::Ready(_)) => UserMessage::Ready,
+        None => bail!("Empty message"),
+    };
+    Ok(serde_json::to_vec(&msg)?)
+}
+
+/// Decodes a message which would have been sent over the websocket.
+fn from_websocket_message(data: &[u8]) -> Result<ServerMessage, Error> {
+    use server_message::Message;
+
+    let mut decompressed = vec![];
+    zstd::stream::read::Decoder::with_dictionary(data, &LEGACY_ZSTD_DICT)?
+        .read_to_end(&mut decompressed)?;
+    let msg = match WireFormat::decode(&decompressed)? {
+        GameMessage::State { state } => Message::StateJson(serde_json::to_string(&state)?),
+        GameMessage::Message { from, message } => {
+            Message::Chat(proto::ChatMessage { from, message })
+        }
+        GameMessage::Broadcast { data, message } => Message::Broadcast(proto::Broadcast {
+            data_json: serde_json::to_string(&data)?,
+            message,
+        }),
+        GameMessage::Beep { target } => Message::Beep(target),
+        GameMessage::ReadyCheck { from } => Message::ReadyCheck(from),
+        GameMessage::Error(e) => Message::Error(e),
+        GameMessage::Header { messages } => Message::Header(proto::Header { messages }),
+        GameMessage::Kicked { target } => Message::Kicked(target),
+        GameMessage::Session {
+            player_id,
+            name,
+            token,
+        } => Message::Session(proto::Session {
+            player_id: player_id.0 as u64,
+            name,
+            token,
+        }),
+        GameMessage::TurnTimer {
+            player_id,
+            remaining_ms,
+            paused,
+        } => Message::TurnTimer(proto::TurnTimer {
+            player_id: player_id.map(|id| id.0 as u64),
+            remaining_ms,
+            paused,
+        }),
+        GameMessage::Redirect { websocket_url } => Message::Redirect(websocket_url),
+        msg => Message::OtherJson(serde_json::to_string(&msg)?),
+    };
+    Ok(ServerMessage { message: Some(msg) })
+}
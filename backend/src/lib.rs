@@ -0,0 +1,150 @@
+#![deny(warnings)]
+
+//! The shengji server, as a library which can be embedded in another axum
+//! application:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), anyhow::Error> {
+//! let server = shengji::ShengjiServer::builder().build().await?;
+//! let app = axum::Router::new()
+//!     .route("/healthz", axum::routing::get(|| async { "ok" }))
+//!     .merge(server.router());
+//! axum::Server::bind(&"0.0.0.0:8080".parse()?)
+//!     .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! or run on its own, as the `shengji` binary does:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), anyhow::Error> {
+//! let listener = std::net::TcpListener::bind("0.0.0.0:3030")?;
+//! shengji::ShengjiServer::builder().serve(listener).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `grpc` feature, the game can also be played over the gRPC
+//! interface in `proto/shengji.proto`, with `ShengjiServer::grpc_service`.
+//!
+//! The rest of the server's configuration is read from the environment.
+
+use std::collections::HashMap;
+
+use shengji_mechanics::types::FULL_DECK;
+use shengji_types::ZSTD_DICTS;
+use slog::{o, Drain, Logger};
+
+mod achievements;
+mod archives;
+mod audit_log;
+mod bots;
+mod cluster;
+mod events;
+mod game_history;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod metrics;
+mod rate_limit;
+mod reconnect;
+mod replay_handler;
+mod rest_api;
+mod server;
+mod serving_types;
+mod shengji_handler;
+mod snapshots;
+mod spectator_delay;
+mod state_diffs;
+mod state_dump;
+mod tournament;
+mod traffic_samples;
+mod turn_timer;
+mod utils;
+mod webhooks;
+
+use rate_limit::RateLimitConfig;
+use serving_types::CardsBlob;
+
+pub use server::{ShengjiServer, ShengjiServerBuilder};
+pub use serving_types::VersionedGame;
+pub use storage::SqliteStorage;
+
+lazy_static::lazy_static! {
+    static ref CARDS_JSON: CardsBlob = CardsBlob {
+        cards: FULL_DECK.iter().map(|c| c.as_info()).collect()
+    };
+
+    /// The logger which everything in the server logs to.
+    pub static ref ROOT_LOGGER: Logger = {
+        #[cfg(not(feature = "dynamic"))]
+        let drain = slog_bunyan::default(std::io::stdout());
+        #[cfg(feature = "dynamic")]
+        let drain = slog_term::FullFormat::new(slog_term::TermDecorator::new().build()).build();
+
+        let version = std::env::var("VERSION").unwrap_or_else(|_| "unknown_dev".to_string());
+
+        Logger::root(
+            slog_async::Async::new(drain.fuse()).build().fuse(),
+            o!("version" => version)
+        )
+    };
+
+    static ref ZSTD_COMPRESSORS: HashMap<u32, std::sync::Mutex<zstd::bulk::Compressor<'static>>> = {
+        ZSTD_DICTS.iter().map(|(id, dict)| {
+            // default zstd dictionary size is 112_640
+            let comp = zstd::bulk::Compressor::with_dictionary(0, &zstd::bulk::decompress(dict, 112_640).unwrap()).unwrap();
+            (*id, std::sync::Mutex::new(comp))
+        }).collect()
+    };
+
+    static ref VERSION: String = {
+        std::env::var("VERSION").unwrap_or_else(|_| "unknown_dev".to_string())
+    };
+
+    static ref DUMP_PATH: String = {
+        std::env::var("DUMP_PATH").unwrap_or_else(|_| "/tmp/shengji_state.json".to_string())
+    };
+    static ref STORAGE_PATH: String = {
+        std::env::var("STORAGE_PATH").unwrap_or_else(|_| "/tmp/shengji_state.sqlite".to_string())
+    };
+    static ref MESSAGE_PATH: String = {
+        std::env::var("MESSAGE_PATH").unwrap_or_else(|_| "/tmp/shengji_messages.json".to_string())
+    };
+    static ref WEBSOCKET_HOST: Option<String> = {
+        std::env::var("WEBSOCKET_HOST").ok()
+    };
+    static ref ADMIN_TOKEN: Option<String> = {
+        std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty())
+    };
+    static ref ARCHIVE_IDLE_MINUTES: Option<u64> = {
+        std::env::var("ARCHIVE_IDLE_MINUTES").ok().and_then(|m| m.parse().ok())
+    };
+    static ref RATE_LIMITS: RateLimitConfig = RateLimitConfig::from_env();
+    static ref AUDIT_LOG_PATH: Option<String> = {
+        std::env::var("AUDIT_LOG_PATH").ok().filter(|p| !p.is_empty())
+    };
+    static ref TRAFFIC_SAMPLE_PATH: Option<String> = {
+        std::env::var("TRAFFIC_SAMPLE_PATH").ok()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CARDS_JSON;
+
+    static CARDS_JSON_FROM_FILE: &str = include_str!("../../frontend/src/generated/cards.json");
+
+    #[test]
+    fn test_cards_json_compatibility() {
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &serde_json::to_string(&*CARDS_JSON).unwrap()
+            )
+            .unwrap(),
+            serde_json::from_str::<serde_json::Value>(CARDS_JSON_FROM_FILE).unwrap(),
+            "Run `yarn download-cards-json` with the backend running to sync the generated cards.json file"
+        );
+    }
+}
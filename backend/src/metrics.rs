@@ -0,0 +1,107 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension};
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    TextEncoder,
+};
+
+use shengji_core::interactive::Action;
+use storage::{SqliteStorage, Storage};
+
+use crate::serving_types::{UserMessage, VersionedGame};
+
+lazy_static::lazy_static! {
+    pub static ref CONNECTED_CLIENTS: IntGauge = register_int_gauge!(
+        "shengji_connected_clients",
+        "Number of open websocket connections"
+    )
+    .unwrap();
+    static ref ACTIVE_ROOMS: IntGauge = register_int_gauge!(
+        "shengji_active_rooms",
+        "Number of rooms currently in memory"
+    )
+    .unwrap();
+    static ref ONLINE_PLAYERS: IntGauge = register_int_gauge!(
+        "shengji_online_players",
+        "Number of players subscribed to a room"
+    )
+    .unwrap();
+    pub static ref MESSAGES_RECEIVED: IntCounterVec = register_int_counter_vec!(
+        "shengji_messages_received_total",
+        "Messages received from clients, by type",
+        &["type"]
+    )
+    .unwrap();
+    pub static ref MESSAGES_SENT: IntCounter = register_int_counter!(
+        "shengji_messages_sent_total",
+        "Messages sent to clients"
+    )
+    .unwrap();
+    pub static ref COMPRESSION_RATIO: Histogram = register_histogram!(
+        "shengji_zstd_compression_ratio",
+        "Uncompressed size divided by compressed size of outgoing messages",
+        vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]
+    )
+    .unwrap();
+    pub static ref MESSAGE_HANDLING_SECONDS: HistogramVec = register_histogram_vec!(
+        "shengji_message_handling_seconds",
+        "Time taken to handle a message from a client, by type",
+        &["type"]
+    )
+    .unwrap();
+    pub static ref GAME_ACTION_SECONDS: HistogramVec = register_histogram_vec!(
+        "shengji_game_action_seconds",
+        "Time taken to apply a game action, by action",
+        &["action"]
+    )
+    .unwrap();
+}
+
+pub fn user_message_type(msg: &UserMessage) -> &'static str {
+    match msg {
+        UserMessage::Message(_) => "Message",
+        UserMessage::Action(_) => "Action",
+        UserMessage::Kick(_) => "Kick",
+        UserMessage::Ban(_) => "Ban",
+        UserMessage::Beep => "Beep",
+        UserMessage::ReadyCheck => "ReadyCheck",
+        UserMessage::Ready => "Ready",
+        UserMessage::Pong { .. } => "Pong",
+        UserMessage::RequestTrickHistory => "RequestTrickHistory",
+    }
+}
+
+/// The name of the action's variant, as it appears on the wire.
+pub fn action_name(action: &Action) -> String {
+    match serde_json::to_value(action) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(serde_json::Value::Object(o)) => o.keys().next().cloned().unwrap_or_default(),
+        _ => "unknown".to_string(),
+    }
+}
+
+pub async fn metrics(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> impl IntoResponse {
+    if let Ok((num_rooms, num_players)) = backend_storage.stats().await {
+        ACTIVE_ROOMS.set(num_rooms as i64);
+        ONLINE_PLAYERS.set(num_players as i64);
+    }
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => (
+            StatusCode::OK,
+            [(
+                http::header::CONTENT_TYPE,
+                encoder.format_type().to_string(),
+            )],
+            buffer,
+        ),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(http::header::CONTENT_TYPE, "text/plain".to_string())],
+            b"failed to encode metrics".to_vec(),
+        ),
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+/// A token bucket: up to `burst` messages can be sent at once, refilling at
+/// `per_second`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Budget {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Budgets keyed by message type, i.e. `Message`, `Beep`, `Action`.
+    pub budgets: HashMap<String, Budget>,
+    /// Budget for message types which aren't listed in `budgets`.
+    pub default_budget: Budget,
+    /// How many times a connection can exceed its budget before it's muted.
+    pub violations_before_mute: u32,
+    pub mute_secs: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let budget = |burst, per_second| Budget { burst, per_second };
+        RateLimitConfig {
+            budgets: vec![
+                ("Message".to_string(), budget(5.0, 1.0)),
+                ("Beep".to_string(), budget(2.0, 0.2)),
+                ("ReadyCheck".to_string(), budget(1.0, 0.1)),
+                ("Ready".to_string(), budget(2.0, 0.5)),
+                ("Kick".to_string(), budget(2.0, 0.2)),
+                ("Ban".to_string(), budget(2.0, 0.2)),
+                ("Action".to_string(), budget(20.0, 10.0)),
+            ]
+            .into_iter()
+            .collect(),
+            default_budget: budget(10.0, 5.0),
+            violations_before_mute: 10,
+            mute_secs: 60,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Reads the config as JSON from the `RATE_LIMITS` environment variable,
+    /// falling back to the defaults.
+    pub fn from_env() -> Self {
+        std::env::var("RATE_LIMITS")
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub enum RateLimited {
+    OverBudget,
+    Muted { remaining: Duration },
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Rate limits for a single connection.
+pub struct RateLimiter {
+    config: &'static RateLimitConfig,
+    buckets: HashMap<&'static str, Bucket>,
+    violations: u32,
+    muted_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &'static RateLimitConfig) -> Self {
+        RateLimiter {
+            config,
+            buckets: HashMap::new(),
+            violations: 0,
+            muted_until: None,
+        }
+    }
+
+    /// Spends a token for the message type, if the connection has one
+    /// available. Game actions are never muted, so that a muted player can
+    /// keep playing.
+    pub fn check(&mut self, msg_type: &'static str) -> Result<(), RateLimited> {
+        let now = Instant::now();
+        if let Some(muted_until) = self.muted_until {
+            if now >= muted_until {
+                self.muted_until = None;
+                self.violations = 0;
+            } else if msg_type != "Action" {
+                return Err(RateLimited::Muted {
+                    remaining: muted_until - now,
+                });
+            }
+        }
+
+        let budget = self
+            .config
+            .budgets
+            .get(msg_type)
+            .copied()
+            .unwrap_or(self.config.default_budget);
+        let bucket = self.buckets.entry(msg_type).or_insert(Bucket {
+            tokens: budget.burst,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * budget.per_second).min(budget.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            self.violations += 1;
+            if self.violations >= self.config.violations_before_mute {
+                let duration = Duration::from_secs(self.config.mute_secs);
+                self.muted_until = Some(now + duration);
+                return Err(RateLimited::Muted {
+                    remaining: duration,
+                });
+            }
+            Err(RateLimited::OverBudget)
+        }
+    }
+}
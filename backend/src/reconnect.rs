@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use tokio::sync::Mutex;
+
+use shengji_mechanics::types::PlayerID;
+use shengji_types::PlayerLatency;
+use storage::{SqliteStorage, SqliteStorageError};
+
+use crate::serving_types::VersionedGame;
+
+/// How long after a player's last websocket disconnects that their
+/// reconnection token can still be used to reclaim their seat.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Default)]
+struct Session {
+    connections: usize,
+    disconnected_at: Option<Instant>,
+    /// Hashed identities (IP address, device key) of the player's
+    /// connections, so that they can be banned.
+    identities: HashSet<String>,
+    /// The round-trip time of the player's most recent heartbeat.
+    rtt_ms: Option<u64>,
+}
+
+/// Issues signed reconnection tokens, and tracks which players are (or were
+/// recently) connected so that the tokens are only honored within the grace
+/// period. Each room's ban list is kept with the room itself.
+#[derive(Clone)]
+pub struct Sessions {
+    secret: Arc<Vec<u8>>,
+    sessions: Arc<Mutex<HashMap<(String, PlayerID), Session>>>,
+}
+
+impl Sessions {
+    /// Uses the `RECONNECT_SECRET` environment variable as the signing key if
+    /// it's set, so that servers can share it. Otherwise, a random key is
+    /// generated once and kept in `backend_storage`, so that tokens and saved
+    /// games survive restarts.
+    pub fn from_env(
+        backend_storage: &SqliteStorage<VersionedGame>,
+    ) -> Result<Self, SqliteStorageError> {
+        let secret = match std::env::var("RECONNECT_SECRET") {
+            Ok(s) if !s.is_empty() => s.into_bytes(),
+            _ => backend_storage.secret("reconnect", || {
+                let mut secret = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                secret
+            })?,
+        };
+        Ok(Self::new(secret))
+    }
+
+    pub fn new(secret: Vec<u8>) -> Self {
+        Sessions {
+            secret: Arc::new(secret),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Hashes something which identifies a client, e.g. its IP address, so
+    /// that it can be compared against ban lists without being stored.
+    pub fn identity(&self, value: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).unwrap();
+        mac.update(b"identity.");
+        mac.update(value);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// A key for sealing saved games, so that only servers with the same
+    /// secret can read them back.
+    pub fn sealing_key(&self) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).unwrap();
+        mac.update(b"sealing_key.");
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn sign(&self, room: &str, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).unwrap();
+        mac.update(room.as_bytes());
+        mac.update(b".");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// Creates a token which lets the holder reclaim `player_id`'s seat in
+    /// `room`.
+    pub fn issue(&self, room: &str, player_id: PlayerID) -> String {
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let payload = format!("{}.{}", player_id.0, issued_at);
+        let signature = hex::encode(self.sign(room, &payload).finalize().into_bytes());
+        format!("{payload}.{signature}")
+    }
+
+    /// Returns the player that the token was issued to, if it's validly
+    /// signed for this room, the player hasn't been banned from it, and they
+    /// are either still connected or disconnected within the grace period.
+    pub async fn verify(
+        &self,
+        room: &str,
+        bans: &HashSet<String>,
+        token: &str,
+    ) -> Option<PlayerID> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let signature = hex::decode(signature).ok()?;
+        self.sign(room, payload).verify_slice(&signature).ok()?;
+        let player_id = PlayerID(payload.split_once('.')?.0.parse().ok()?);
+        if bans.contains(&player_key(player_id)) {
+            return None;
+        }
+
+        let sessions = self.sessions.lock().await;
+        let session = sessions.get(&(room.to_string(), player_id))?;
+        match session.disconnected_at {
+            Some(t) if session.connections == 0 && t.elapsed() > GRACE_PERIOD => None,
+            _ => Some(player_id),
+        }
+    }
+
+    pub async fn connected(&self, room: &str, player_id: PlayerID, identities: &[String]) {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.entry((room.to_string(), player_id)).or_default();
+        session.connections += 1;
+        session.disconnected_at = None;
+        session.identities.extend(identities.iter().cloned());
+    }
+
+    pub async fn disconnected(&self, room: &str, player_id: PlayerID) {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get_mut(&(room.to_string(), player_id)) {
+            session.connections = session.connections.saturating_sub(1);
+            if session.connections == 0 {
+                session.disconnected_at = Some(Instant::now());
+                session.rtt_ms = None;
+            }
+        }
+    }
+
+    pub async fn record_latency(&self, room: &str, player_id: PlayerID, rtt_ms: u64) {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get_mut(&(room.to_string(), player_id)) {
+            session.rtt_ms = Some(rtt_ms);
+        }
+    }
+
+    /// The latest round-trip times of the players connected to the room.
+    pub async fn latencies(&self, room: &str) -> Vec<PlayerLatency> {
+        let mut latencies = self
+            .sessions
+            .lock()
+            .await
+            .iter()
+            .filter(|((r, _), session)| r == room && session.connections > 0)
+            .filter_map(|((_, player_id), session)| {
+                Some(PlayerLatency {
+                    player_id: *player_id,
+                    rtt_ms: session.rtt_ms?,
+                })
+            })
+            .collect::<Vec<_>>();
+        latencies.sort_by_key(|l| l.player_id.0);
+        latencies
+    }
+
+    /// The identities the player has connected to the room with.
+    pub async fn identities(&self, room: &str, player_id: PlayerID) -> HashSet<String> {
+        self.sessions
+            .lock()
+            .await
+            .get(&(room.to_string(), player_id))
+            .map(|s| s.identities.clone())
+            .unwrap_or_default()
+    }
+
+    /// Forgets players whose grace period has expired.
+    pub async fn prune(&self) {
+        self.sessions.lock().await.retain(|_, session| {
+            session.connections > 0
+                || session
+                    .disconnected_at
+                    .map(|t| t.elapsed() <= GRACE_PERIOD)
+                    .unwrap_or(true)
+        });
+    }
+}
+
+/// Adds the player to the room's bans: their reconnection tokens stop
+/// working, and nobody can join with their name or from any of the
+/// identities they connected with.
+pub fn ban(
+    bans: &mut HashSet<String>,
+    player_id: PlayerID,
+    name: &str,
+    identities: HashSet<String>,
+) {
+    bans.extend(identities);
+    bans.insert(player_key(player_id));
+    bans.insert(name_key(name));
+}
+
+/// Whether someone joining with this name from these identities has been
+/// banned from the room.
+pub fn is_banned(bans: &HashSet<String>, name: &str, identities: &[String]) -> bool {
+    bans.contains(&name_key(name)) || identities.iter().any(|i| bans.contains(i))
+}
+
+fn player_key(player_id: PlayerID) -> String {
+    format!("player:{}", player_id.0)
+}
+
+fn name_key(name: &str) -> String {
+    format!("name:{name}")
+}
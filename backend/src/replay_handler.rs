@@ -0,0 +1,102 @@
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use serde::Serialize;
+
+use shengji_core::game_log::GameLog;
+use shengji_core::game_state::GameState;
+use shengji_core::replay::{Replay, ReplayStep};
+use shengji_types::state_diff::{self, StateDiffOp};
+use storage::{SqliteStorage, Storage};
+
+use crate::serving_types::VersionedGame;
+
+type ReplayResult<T> = Result<Json<T>, (StatusCode, &'static str)>;
+
+/// Replays reveal every hand, so they're only available once the game they
+/// recorded is no longer being played.
+async fn finished_replay(
+    backend_storage: SqliteStorage<VersionedGame>,
+    room_name: String,
+) -> Result<Replay, (StatusCode, &'static str)> {
+    let versioned_game = backend_storage
+        .get(room_name.into_bytes())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load game"))?;
+    match (versioned_game.replay, versioned_game.game) {
+        (Some(replay), GameState::Initialize(_)) => Ok(replay),
+        (Some(_), _) => Err((StatusCode::FORBIDDEN, "game is still in progress")),
+        (None, _) => Err((StatusCode::NOT_FOUND, "no replay for this room")),
+    }
+}
+
+pub async fn get_replay(
+    Path(room_name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ReplayResult<Replay> {
+    Ok(Json(finished_replay(backend_storage, room_name).await?))
+}
+
+pub async fn get_replay_step(
+    Path((room_name, step)): Path<(String, usize)>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ReplayResult<GameState> {
+    let replay = finished_replay(backend_storage, room_name).await?;
+    if step > replay.len() {
+        return Err((StatusCode::NOT_FOUND, "replay doesn't have that many steps"));
+    }
+    replay
+        .state_at(step)
+        .map(Json)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to replay game"))
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepDiff {
+    #[serde(flatten)]
+    step: ReplayStep,
+    /// The changes the action made to the serialized state.
+    diff: Vec<StateDiffOp>,
+}
+
+/// The state before the action at `step` and the changes it made, so that
+/// reports like "the wrong player won trick 12" can be tracked down to the
+/// action which caused them.
+pub async fn get_replay_step_diff(
+    Path((room_name, step)): Path<(String, usize)>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ReplayResult<StepDiff> {
+    let replay = finished_replay(backend_storage, room_name).await?;
+    if step >= replay.len() {
+        return Err((StatusCode::NOT_FOUND, "replay doesn't have that many steps"));
+    }
+    let step = replay
+        .step(step)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to replay game"))?;
+    let (before, after) = match (
+        serde_json::to_value(&step.before),
+        serde_json::to_value(&step.after),
+    ) {
+        (Ok(before), Ok(after)) => (before, after),
+        _ => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to serialize game",
+            ))
+        }
+    };
+    Ok(Json(StepDiff {
+        diff: state_diff::diff(&before, &after),
+        step,
+    }))
+}
+
+/// A structured summary of the room's most recent game, for players to
+/// download once it's over.
+pub async fn get_game_log(
+    Path(room_name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ReplayResult<GameLog> {
+    let replay = finished_replay(backend_storage, room_name).await?;
+    GameLog::from_replay(&replay)
+        .map(Json)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to replay game"))
+}
@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+
+use shengji_core::game_state::{initialize_phase::InitializePhase, GameState};
+use shengji_core::interactive::EventLog;
+use shengji_core::replay::Replay;
+use shengji_core::settings::{GameModeSettings, GameVisibility, PropagatedState};
+use storage::{
+    LevelChange, PlayerStats, PresetError, SqliteGameResults, SqlitePresets, SqliteStorage,
+    SqliteStorageError, SqliteUsers, Storage, UnlockedAchievement, User,
+};
+
+use crate::cluster::Cluster;
+use crate::reconnect::Sessions;
+use crate::serving_types::VersionedGame;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, &'static str)>;
+
+#[derive(Debug, Serialize)]
+pub struct RoomSummary {
+    room_name: String,
+    phase: &'static str,
+    num_players: usize,
+    num_observers: usize,
+    num_spectators: usize,
+    game_mode: GameModeSettings,
+    num_decks: usize,
+    game_visibility: GameVisibility,
+}
+
+impl RoomSummary {
+    fn new(room_name: String, game: &GameState) -> Self {
+        RoomSummary {
+            room_name,
+            phase: match game {
+                GameState::Initialize(_) => "Initialize",
+                GameState::Draw(_) => "Draw",
+                GameState::Exchange(_) => "Exchange",
+                GameState::Play(_) => "Play",
+            },
+            num_players: game.players().len(),
+            num_observers: game.observers().len(),
+            num_spectators: game.spectators().len(),
+            game_mode: game.game_mode(),
+            num_decks: game.num_decks(),
+            game_visibility: game.game_visibility(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoom {
+    /// Settings in the same shape as `/default_settings.json`. Any players
+    /// included in them are ignored.
+    #[serde(default)]
+    settings: Option<PropagatedState>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedRoom {
+    room_name: String,
+}
+
+pub async fn list_rooms(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ApiResult<Vec<RoomSummary>> {
+    let keys = backend_storage
+        .clone()
+        .get_all_keys()
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to list rooms"))?;
+    let mut rooms = vec![];
+    for room_name in keys {
+        if let Ok(versioned_game) = backend_storage.clone().get(room_name.clone()).await {
+            if let GameVisibility::Public = versioned_game.game.game_visibility() {
+                if let Ok(name) = String::from_utf8(room_name) {
+                    rooms.push(RoomSummary::new(name, &versioned_game.game));
+                }
+            }
+        }
+    }
+    rooms.sort_by_key(|r| (-(r.num_players as isize), r.room_name.clone()));
+    Ok(Json(rooms))
+}
+
+pub async fn create_room(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(cluster): Extension<Cluster>,
+    Json(req): Json<CreateRoom>,
+) -> ApiResult<CreatedRoom> {
+    let init = match req.settings {
+        Some(settings) => InitializePhase::from_settings(settings)
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid settings"))?,
+        None => InitializePhase::new(),
+    };
+    let room_name = new_room(backend_storage, &cluster, GameState::Initialize(init), None)
+        .await
+        .map_err(|_| (StatusCode::CONFLICT, "room already exists"))?;
+    Ok(Json(CreatedRoom { room_name }))
+}
+
+/// Creates a room with a random name, returning the name.
+pub async fn new_room(
+    backend_storage: SqliteStorage<VersionedGame>,
+    cluster: &Cluster,
+    game: GameState,
+    replay: Option<Replay>,
+) -> Result<String, SqliteStorageError> {
+    // Room names are 16 hex characters, same as the ones the frontend
+    // generates.
+    let room_name = format!("{:016x}", rand::random::<u64>());
+    backend_storage
+        .clone()
+        .put_cas(
+            0,
+            VersionedGame {
+                room_name: room_name.as_bytes().to_vec(),
+                game,
+                associated_websockets: HashMap::new(),
+                monotonic_id: 1,
+                replay,
+                bans: HashSet::new(),
+                events: EventLog::default(),
+            },
+        )
+        .await?;
+    // Players are about to join, so make sure they end up here. If this
+    // fails, the claim will be retried when claims are next refreshed.
+    let _ = cluster.route(&room_name, backend_storage).await;
+    Ok(room_name)
+}
+
+pub async fn room_status(
+    Path(room_name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> ApiResult<RoomSummary> {
+    let versioned_game = backend_storage
+        .get(room_name.as_bytes().to_vec())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load room"))?;
+    // Storage hands out an empty game for rooms which don't exist yet.
+    if versioned_game.monotonic_id == 0 {
+        return Err((StatusCode::NOT_FOUND, "room not found"));
+    }
+    Ok(Json(RoomSummary::new(room_name, &versioned_game.game)))
+}
+
+pub async fn user_stats(
+    Path(nickname): Path<String>,
+    Extension(users): Extension<SqliteUsers>,
+) -> ApiResult<User> {
+    match users.get(&nickname) {
+        Ok(Some(user)) => Ok(Json(user)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "no account with that name")),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to look up account",
+        )),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerStatsResponse {
+    #[serde(flatten)]
+    stats: PlayerStats,
+    landlord_win_rate: Option<f64>,
+    defending_win_rate: Option<f64>,
+    attacking_win_rate: Option<f64>,
+    /// Only players who've claimed their nickname and played in a ranked
+    /// room have a rating.
+    rating: Option<f64>,
+}
+
+/// Statistics aggregated over every game the player has finished, whether or
+/// not they've claimed their nickname.
+pub async fn player_stats(
+    Path(nickname): Path<String>,
+    Extension(game_results): Extension<SqliteGameResults>,
+    Extension(users): Extension<SqliteUsers>,
+) -> ApiResult<PlayerStatsResponse> {
+    let rating = users.career(&nickname).map(|c| c.and_then(|c| c.rating));
+    match (game_results.player_stats(&nickname), rating) {
+        (Ok(stats), Ok(rating)) => Ok(Json(PlayerStatsResponse {
+            landlord_win_rate: stats.landlord_win_rate(),
+            defending_win_rate: stats.defending_win_rate(),
+            attacking_win_rate: stats.attacking_win_rate(),
+            rating,
+            stats,
+        })),
+        _ => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to look up statistics",
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LevelProgressionQuery {
+    #[serde(default = "default_num_games")]
+    limit: usize,
+}
+
+fn default_num_games() -> usize {
+    100
+}
+
+/// The player's rank after each of their most recent games, oldest first.
+pub async fn level_progression(
+    Path(nickname): Path<String>,
+    Query(query): Query<LevelProgressionQuery>,
+    Extension(game_results): Extension<SqliteGameResults>,
+) -> ApiResult<Vec<LevelChange>> {
+    match game_results.level_progression(&nickname, query.limit.min(1000)) {
+        Ok(changes) => Ok(Json(changes)),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to look up statistics",
+        )),
+    }
+}
+
+/// The achievements the player has unlocked, which only players who've
+/// claimed their nicknames can do.
+pub async fn achievements(
+    Path(nickname): Path<String>,
+    Extension(users): Extension<SqliteUsers>,
+) -> ApiResult<Vec<UnlockedAchievement>> {
+    match users.achievements(&nickname) {
+        Ok(achievements) => Ok(Json(achievements)),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to look up achievements",
+        )),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresetSummary {
+    name: String,
+    owner: String,
+    updated_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavePreset {
+    /// The room whose settings should be saved.
+    room_name: String,
+    /// The reconnection token the host was issued when they joined the room.
+    token: String,
+}
+
+pub async fn list_presets(
+    Extension(presets): Extension<SqlitePresets>,
+) -> ApiResult<Vec<PresetSummary>> {
+    let presets = presets
+        .list()
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to list presets"))?;
+    Ok(Json(
+        presets
+            .into_iter()
+            .map(|p| PresetSummary {
+                name: p.name,
+                owner: p.owner,
+                updated_at: p.updated_at,
+            })
+            .collect(),
+    ))
+}
+
+pub async fn get_preset(
+    Path(name): Path<String>,
+    Extension(presets): Extension<SqlitePresets>,
+) -> ApiResult<PropagatedState> {
+    let preset = presets
+        .get(&name)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load preset"))?
+        .ok_or((StatusCode::NOT_FOUND, "preset not found"))?;
+    serde_json::from_str(&preset.settings)
+        .map(Json)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load preset"))
+}
+
+/// Saves the settings of the host's room under `name`, so that they can be
+/// loaded into other rooms.
+pub async fn save_preset(
+    Path(name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(sessions): Extension<Sessions>,
+    Extension(presets): Extension<SqlitePresets>,
+    Json(req): Json<SavePreset>,
+) -> ApiResult<PresetSummary> {
+    if name.is_empty() || name.len() > 64 {
+        return Err((StatusCode::BAD_REQUEST, "invalid preset name"));
+    }
+    let versioned_game = backend_storage
+        .get(req.room_name.clone().into_bytes())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load room"))?;
+    let player_id = sessions
+        .verify(&req.room_name, &versioned_game.bans, &req.token)
+        .await
+        .ok_or((StatusCode::FORBIDDEN, "not in the room"))?;
+    let game = versioned_game.game;
+    if game.host() != Some(player_id) {
+        return Err((StatusCode::FORBIDDEN, "only the host can save presets"));
+    }
+    let owner = game
+        .player_name(player_id)
+        .map_err(|_| (StatusCode::FORBIDDEN, "not in the room"))?
+        .to_string();
+
+    // Presets are shared with everyone, so they shouldn't carry anything
+    // specific to this room.
+    let mut settings = InitializePhase::from_settings(game.propagated().clone())
+        .map_err(|_| (StatusCode::BAD_REQUEST, "invalid settings"))?
+        .propagated()
+        .clone();
+    settings
+        .set_webhook_url(None)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "invalid settings"))?;
+    let settings = serde_json::to_string(&settings)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to save preset"))?;
+
+    match presets.save(&name, &owner, &settings) {
+        Ok(p) => Ok(Json(PresetSummary {
+            name: p.name,
+            owner: p.owner,
+            updated_at: p.updated_at,
+        })),
+        Err(PresetError::NameTaken) => Err((
+            StatusCode::CONFLICT,
+            "a preset with this name belongs to someone else",
+        )),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "failed to save preset")),
+    }
+}
@@ -0,0 +1,518 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocketUpgrade},
+        ConnectInfo,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Redirect},
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use slog::{debug, error, info, o};
+use tokio::sync::{mpsc, Mutex};
+
+#[cfg(feature = "dynamic")]
+use axum::routing::get_service;
+#[cfg(not(feature = "dynamic"))]
+use axum::{
+    body::{Empty, Full},
+    extract::Path,
+    response::Response,
+};
+#[cfg(feature = "dynamic")]
+use tower_http::services::ServeDir;
+
+#[cfg(feature = "grpc")]
+use crate::grpc::{proto::game_server::GameServer, GrpcService, GRPC_ADDR};
+use shengji_core::settings;
+use storage::{SqlitePresets, SqliteStorage, SqliteUsers, Storage};
+
+use crate::{
+    achievements, archives, audit_log, bots,
+    cluster::{self, Cluster},
+    events::{self, RoomEvents},
+    game_history, metrics,
+    reconnect::Sessions,
+    replay_handler, rest_api,
+    serving_types::VersionedGame,
+    shengji_handler, snapshots,
+    state_dump::{self, InMemoryStats},
+    tournament::{self, Tournaments},
+    turn_timer, webhooks, CARDS_JSON, ROOT_LOGGER, STORAGE_PATH, VERSION, WEBSOCKET_HOST,
+};
+
+/// Our global unique user id counter.
+pub(crate) static NEXT_USER_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Configures a `ShengjiServer`.
+#[derive(Default)]
+pub struct ShengjiServerBuilder {
+    storage: Option<SqliteStorage<VersionedGame>>,
+    serve_frontend: Option<bool>,
+}
+
+impl ShengjiServerBuilder {
+    /// Where to keep the rooms, users and presets. By default, they're kept
+    /// in the SQLite database at `STORAGE_PATH`.
+    pub fn storage(mut self, storage: SqliteStorage<VersionedGame>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Whether to serve the web frontend alongside the API, which is the
+    /// default. Turn it off to serve the frontend from somewhere else.
+    pub fn serve_frontend(mut self, serve_frontend: bool) -> Self {
+        self.serve_frontend = Some(serve_frontend);
+        self
+    }
+
+    /// Loads the saved games into storage and starts the server's background
+    /// tasks, like the turn timers and the bots. These need a tokio runtime.
+    pub async fn build(self) -> Result<ShengjiServer, anyhow::Error> {
+        let backend_storage = match self.storage {
+            Some(storage) => storage,
+            None => state_dump::open_storage()?,
+        };
+        let stats = state_dump::load_state(backend_storage.clone()).await?;
+        let sessions = Sessions::from_env(&backend_storage)?;
+        let server = ShengjiServer {
+            users: backend_storage.users(),
+            presets: backend_storage.presets(),
+            backend_storage,
+            stats,
+            sessions,
+            events: events::room_events(),
+            tournaments: Tournaments::default(),
+            cluster: Cluster::from_env(&ROOT_LOGGER).await,
+            serve_frontend: self.serve_frontend.unwrap_or(true),
+        };
+        server.spawn_background_tasks();
+        Ok(server)
+    }
+
+    /// Builds the server and serves it on `listener` until it fails.
+    pub async fn serve(self, listener: TcpListener) -> Result<(), anyhow::Error> {
+        self.build().await?.serve(listener).await
+    }
+}
+
+/// A running shengji server. Its routes can be served on their own with
+/// `serve`, or mounted inside another axum application with `router`.
+#[derive(Clone)]
+pub struct ShengjiServer {
+    pub(crate) backend_storage: SqliteStorage<VersionedGame>,
+    pub(crate) stats: Arc<Mutex<InMemoryStats>>,
+    pub(crate) sessions: Sessions,
+    pub(crate) users: SqliteUsers,
+    presets: SqlitePresets,
+    pub(crate) events: RoomEvents,
+    tournaments: Tournaments,
+    pub(crate) cluster: Cluster,
+    serve_frontend: bool,
+}
+
+impl ShengjiServer {
+    pub fn builder() -> ShengjiServerBuilder {
+        ShengjiServerBuilder::default()
+    }
+
+    /// The server's routes, which expect to be mounted at the root of the
+    /// application, since that's where the frontend looks for them. The
+    /// application must be served with
+    /// `into_make_service_with_connect_info::<SocketAddr>()`, which the
+    /// websocket uses to tell clients apart.
+    pub fn router(&self) -> Router {
+        let app = Router::new()
+            .route("/api", get(handle_websocket))
+            .route(
+                "/default_settings.json",
+                get(|| async { Json(settings::PropagatedState::default()) }),
+            )
+            .route("/full_state.json", get(state_dump::dump_state))
+            .route("/stats", get(get_stats))
+            .route("/metrics", get(metrics::metrics))
+            .route("/runtime.js", get(runtime_settings))
+            .route("/cards.json", get(|| async { Json(CARDS_JSON.clone()) }))
+            .route(
+                "/rules",
+                get(|| async { Redirect::permanent("/rules.html") }),
+            )
+            .route("/public_games.json", get(state_dump::public_games))
+            .route(
+                "/api/rooms",
+                get(rest_api::list_rooms).post(rest_api::create_room),
+            )
+            .route("/api/rooms/:room_name", get(rest_api::room_status))
+            .route("/api/rooms/:room_name/save", post(snapshots::save_game))
+            .route("/api/saved_games", post(snapshots::restore_game))
+            .route("/api/imported_games", post(snapshots::import_game))
+            .route("/api/users/:nickname", get(rest_api::user_stats))
+            .route("/api/stats/players/:nickname", get(rest_api::player_stats))
+            .route(
+                "/api/stats/players/:nickname/levels",
+                get(rest_api::level_progression),
+            )
+            .route(
+                "/api/stats/players/:nickname/achievements",
+                get(rest_api::achievements),
+            )
+            .route(
+                "/api/stats/players/:nickname/games",
+                get(game_history::list_games),
+            )
+            .route("/api/games/:id/replay", get(game_history::get_replay))
+            .route("/api/games/:id/game_log", get(game_history::get_game_log))
+            .route("/api/presets", get(rest_api::list_presets))
+            .route(
+                "/api/presets/:name",
+                get(rest_api::get_preset).post(rest_api::save_preset),
+            )
+            .route("/api/tournaments", post(tournament::create_tournament))
+            .route("/api/tournaments/:id", get(tournament::tournament_status))
+            .route(
+                "/api/tournaments/:id/register",
+                post(tournament::register_for_tournament),
+            )
+            .route(
+                "/api/tournaments/:id/start",
+                post(tournament::start_tournament),
+            )
+            .route("/replay/:room_name", get(replay_handler::get_replay))
+            .route(
+                "/replay/:room_name/:step",
+                get(replay_handler::get_replay_step),
+            )
+            .route(
+                "/replay/:room_name/:step/diff",
+                get(replay_handler::get_replay_step_diff),
+            )
+            .route("/game_log/:room_name", get(replay_handler::get_game_log))
+            .route(
+                "/admin/archives",
+                get(archives::list_archives).delete(archives::purge_archives),
+            )
+            .route(
+                "/admin/archives/:room_name",
+                delete(archives::purge_archive),
+            )
+            .route("/admin/audit/:room_name", get(audit_log::room_audit_log));
+
+        let app = if self.serve_frontend {
+            with_frontend(app)
+        } else {
+            app
+        };
+
+        app.layer(Extension(self.backend_storage.clone()))
+            .layer(Extension(self.stats.clone()))
+            .layer(Extension(self.sessions.clone()))
+            .layer(Extension(self.users.clone()))
+            .layer(Extension(self.presets.clone()))
+            .layer(Extension(self.backend_storage.game_results()))
+            .layer(Extension(self.backend_storage.game_history()))
+            .layer(Extension(self.events.clone()))
+            .layer(Extension(self.tournaments.clone()))
+            .layer(Extension(self.cluster.clone()))
+    }
+
+    /// The gRPC interface to the game, which can be served with tonic
+    /// alongside the routes from `router`.
+    #[cfg(feature = "grpc")]
+    pub fn grpc_service(&self) -> GameServer<GrpcService> {
+        GameServer::new(GrpcService::new(self.clone()))
+    }
+
+    /// Serves the server's routes on `listener` until it fails. The gRPC
+    /// interface is served too, on `GRPC_ADDR`, if it's set.
+    pub async fn serve(self, listener: TcpListener) -> Result<(), anyhow::Error> {
+        #[cfg(feature = "grpc")]
+        if let Some(addr) = *GRPC_ADDR {
+            let service = self.grpc_service();
+            tokio::task::spawn(async move {
+                if let Err(e) = tonic::transport::Server::builder()
+                    .add_service(service)
+                    .serve(addr)
+                    .await
+                {
+                    error!(ROOT_LOGGER, "Failed to serve gRPC"; "error" => format!("{e:?}"));
+                }
+            });
+        }
+        axum::Server::from_tcp(listener)?
+            .serve(
+                self.router()
+                    .into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn spawn_background_tasks(&self) {
+        tokio::task::spawn(periodically_dump_state(
+            self.backend_storage.clone(),
+            self.stats.clone(),
+            self.sessions.clone(),
+        ));
+        tokio::task::spawn(periodically_checkpoint_state(self.backend_storage.clone()));
+        tokio::task::spawn(events::record_user_stats(
+            ROOT_LOGGER.new(o!("task" => "record_user_stats")),
+            self.users.clone(),
+            self.backend_storage.game_results(),
+            self.backend_storage.clone(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(achievements::record_achievements(
+            ROOT_LOGGER.new(o!("task" => "record_achievements")),
+            self.users.clone(),
+            self.backend_storage.clone(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(game_history::record_game_history(
+            ROOT_LOGGER.new(o!("task" => "record_game_history")),
+            self.backend_storage.game_history(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(tournament::advance_tournaments(
+            ROOT_LOGGER.new(o!("task" => "advance_tournaments")),
+            self.tournaments.clone(),
+            self.backend_storage.clone(),
+            self.cluster.clone(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(turn_timer::run_turn_timers(
+            ROOT_LOGGER.new(o!("task" => "run_turn_timers")),
+            self.backend_storage.clone(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(bots::run_bots(
+            ROOT_LOGGER.new(o!("task" => "run_bots")),
+            self.backend_storage.clone(),
+            self.events.clone(),
+        ));
+        tokio::task::spawn(cluster::refresh_claims(
+            ROOT_LOGGER.new(o!("task" => "refresh_claims")),
+            self.cluster.clone(),
+            self.backend_storage.clone(),
+        ));
+        tokio::task::spawn(webhooks::deliver_webhooks(
+            ROOT_LOGGER.new(o!("task" => "deliver_webhooks")),
+            self.backend_storage.clone(),
+            self.events.clone(),
+        ));
+    }
+}
+
+#[cfg(feature = "dynamic")]
+fn with_frontend(app: Router) -> Router {
+    app.fallback_service(get_service(
+        ServeDir::new("../frontend/dist").fallback(ServeDir::new("../favicon")),
+    ))
+}
+
+#[cfg(not(feature = "dynamic"))]
+fn with_frontend(app: Router) -> Router {
+    app.route(
+        "/",
+        get(|| async { serve_static_routes(Path("index.html".to_string())).await }),
+    )
+    .route("/*path", get(serve_static_routes))
+}
+
+async fn runtime_settings() -> impl IntoResponse {
+    let body = match WEBSOCKET_HOST.as_ref() {
+        Some(s) => format!(
+            "window._WEBSOCKET_HOST = \"{}\";window._VERSION = \"{}\";",
+            s, *VERSION,
+        ),
+        None => format!(
+            "window._WEBSOCKET_HOST = null;window._VERSION = \"{}\";",
+            *VERSION
+        ),
+    };
+    (
+        [(http::header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        body,
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameStats {
+    num_games_created: u64,
+    num_active_games: usize,
+    num_players_online_now: usize,
+    sha: &'static str,
+}
+
+async fn get_stats(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+) -> Result<Json<GameStats>, &'static str> {
+    let num_games_created = backend_storage
+        .clone()
+        .get_states_created()
+        .await
+        .map_err(|_| "failed to get number of games created")?;
+    let (num_active_games, num_players_online_now) = backend_storage
+        .clone()
+        .stats()
+        .await
+        .map_err(|_| "failed to get number of active games and online players")?;
+    Ok(Json(GameStats {
+        num_games_created,
+        num_players_online_now,
+        num_active_games,
+        sha: &VERSION,
+    }))
+}
+
+async fn periodically_dump_state(
+    backend_storage: SqliteStorage<VersionedGame>,
+    stats: Arc<Mutex<InMemoryStats>>,
+    sessions: Sessions,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        sessions.prune().await;
+        let _ =
+            state_dump::dump_state(Extension(backend_storage.clone()), Extension(stats.clone()))
+                .await;
+    }
+}
+
+async fn periodically_checkpoint_state(backend_storage: SqliteStorage<VersionedGame>) {
+    let logger = ROOT_LOGGER.new(o!("storage_path" => &*STORAGE_PATH));
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+    loop {
+        interval.tick().await;
+        if let Err(e) = backend_storage.clone().checkpoint().await {
+            error!(logger, "Failed to checkpoint state"; "error" => format!("{e:?}"));
+        }
+    }
+}
+
+/// The client's IP address, taking the reverse proxy into account if there
+/// is one.
+fn client_ip(addr: SocketAddr, headers: &HeaderMap) -> String {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| addr.ip().to_string())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_websocket(
+    ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(stats): Extension<Arc<Mutex<InMemoryStats>>>,
+    Extension(sessions): Extension<Sessions>,
+    Extension(users): Extension<SqliteUsers>,
+    Extension(events): Extension<RoomEvents>,
+    Extension(cluster): Extension<Cluster>,
+) -> impl IntoResponse {
+    let ip_identity = sessions.identity(client_ip(addr, &headers).as_bytes());
+    ws.on_upgrade(|ws| {
+        let ws_id = NEXT_USER_ID.fetch_add(1, Ordering::Relaxed);
+        let logger = ROOT_LOGGER.new(o!("ws_id" => ws_id));
+        info!(logger, "Websocket connection initialized");
+        // Split the socket into a sender and receive of messages.
+        let (mut user_ws_tx, mut user_ws_rx) = ws.split();
+
+        // Use an unbounded channel to handle buffering and flushing of messages
+        // to the websocket...
+        let logger_ = logger.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::task::spawn(async move {
+            while let Some(v) = rx.recv().await {
+                let _ = user_ws_tx.send(Message::Binary(v)).await;
+            }
+            debug!(logger_, "Ending tx task");
+        });
+
+        // And another channel to receive messages from the websocket
+        let logger_ = logger.clone();
+        let (tx2, rx2) = mpsc::unbounded_channel();
+        tokio::task::spawn(async move {
+            loop {
+                // Stop reading once the handler has given up on the client,
+                // so that the socket gets closed.
+                let result = tokio::select! {
+                    result = user_ws_rx.next() => match result {
+                        Some(result) => result,
+                        None => break,
+                    },
+                    _ = tx2.closed() => break,
+                };
+                match result {
+                    Ok(Message::Close(_)) => {
+                        break;
+                    }
+                    Ok(Message::Binary(r)) => {
+                        let _ = tx2.send(r);
+                    }
+                    Ok(Message::Text(r)) => {
+                        let _ = tx2.send(r.into_bytes());
+                    }
+                    Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => (),
+                    Err(e) => {
+                        error!(logger_, "Failed to fetch message"; "error" => format!("{e:?}"));
+                        break;
+                    }
+                }
+            }
+            debug!(logger_, "Ending rx task");
+        });
+
+        metrics::CONNECTED_CLIENTS.inc();
+        async move {
+            shengji_handler::entrypoint(
+                tx,
+                rx2,
+                ws_id,
+                ip_identity,
+                logger,
+                backend_storage,
+                stats,
+                sessions,
+                users,
+                events,
+                cluster,
+            )
+            .await;
+            metrics::CONNECTED_CLIENTS.dec();
+        }
+    })
+}
+
+#[cfg(not(feature = "dynamic"))]
+async fn serve_static_routes(Path(path): Path<String>) -> impl IntoResponse {
+    static DIST: include_dir::Dir<'_> = include_dir::include_dir!("frontend/dist");
+    static FAVICON: include_dir::Dir<'_> = include_dir::include_dir!("favicon");
+    let mime_type = mime_guess::from_path(&path).first_or_text_plain();
+
+    match DIST.get_file(&path).or_else(|| FAVICON.get_file(&path)) {
+        Some(f) => Response::builder()
+            .status(axum::http::StatusCode::OK)
+            .header(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_str(mime_type.as_ref()).unwrap(),
+            )
+            .body(axum::body::boxed(Full::from(f.contents())))
+            .unwrap(),
+        None => Response::builder()
+            .status(axum::http::StatusCode::NOT_FOUND)
+            .body(axum::body::boxed(Empty::new()))
+            .unwrap(),
+    }
+}
@@ -1,18 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use shengji_core::interactive::Action;
+use shengji_core::interactive::EventLog;
+use shengji_core::replay::Replay;
 use shengji_mechanics::types::{CardInfo, PlayerID};
 use shengji_types::GameMessage;
+pub use shengji_types::{JoinRoom, UserMessage};
 use storage::State;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VersionedGame {
     pub(crate) room_name: Vec<u8>,
+    #[serde(with = "shengji_types::versioned_state")]
     pub(crate) game: shengji_core::game_state::GameState,
     pub(crate) associated_websockets: HashMap<PlayerID, Vec<usize>>,
     pub(crate) monotonic_id: u64,
+    /// A recording of the most recently started game in this room.
+    #[serde(default)]
+    pub(crate) replay: Option<Replay>,
+    /// The hashed identities, names and seats which are banned from the
+    /// room, so that bans last exactly as long as the room does.
+    #[serde(default)]
+    pub(crate) bans: HashSet<String>,
+    /// Every change made to the game since the room was created, or since
+    /// the log last started over.
+    #[serde(default)]
+    pub(crate) events: EventLog,
 }
 
 impl State for VersionedGame {
@@ -34,26 +48,13 @@ impl State for VersionedGame {
             ),
             associated_websockets: HashMap::new(),
             monotonic_id: 0,
+            replay: None,
+            bans: HashSet::new(),
+            events: EventLog::default(),
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct JoinRoom {
-    pub(crate) room_name: String,
-    pub(crate) name: String,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub enum UserMessage {
-    Message(String),
-    Action(Action),
-    Kick(PlayerID),
-    Beep,
-    ReadyCheck,
-    Ready,
-}
-
 #[derive(Clone, Serialize)]
 pub struct CardsBlob {
     pub cards: Vec<CardInfo>,
@@ -1,38 +1,118 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
+use sha2::{Digest, Sha256};
 use slog::{debug, error, info, o, Logger};
 use tokio::sync::{mpsc, oneshot, Mutex};
 
-use shengji_core::interactive::InteractiveGame;
-use shengji_mechanics::types::PlayerID;
-use shengji_types::GameMessage;
-use storage::Storage;
+use shengji_core::game_state::GameState;
+use shengji_core::interactive::{Action, InteractiveGame};
+use shengji_core::message::MessageVariant;
+use shengji_mechanics::types::{PlayerID, Rank};
+use shengji_types::{newest_zstd_dict, GameMessage, WireFormat, LEGACY_ZSTD_DICT_ID};
+use storage::{SqliteUsers, Storage, UserError};
 
 use crate::{
+    audit_log::{AuditOutcome, ConnectionAudit},
+    cluster::Cluster,
+    events::{RoomEvent, RoomEvents},
+    metrics::{
+        action_name, user_message_type, COMPRESSION_RATIO, GAME_ACTION_SECONDS, MESSAGES_RECEIVED,
+        MESSAGES_SENT, MESSAGE_HANDLING_SECONDS,
+    },
+    rate_limit::{RateLimited, RateLimiter},
+    reconnect::{self, Sessions},
     serving_types::{JoinRoom, UserMessage, VersionedGame},
+    spectator_delay::SpectatorDelay,
+    state_diffs::StateDiffs,
     state_dump::InMemoryStats,
-    utils::{execute_immutable_operation, execute_operation},
-    ZSTD_COMPRESSOR,
+    traffic_samples,
+    utils::{career_stats_message, execute_immutable_operation, execute_operation, now_ms},
+    RATE_LIMITS, ZSTD_COMPRESSORS,
 };
 
+/// How often clients are pinged.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long to wait to hear anything from a client before deciding that its
+/// connection is dead.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(35);
+
+/// How to encode and compress the messages sent to a client.
+#[derive(Clone, Copy)]
+struct Encoding {
+    wire_format: WireFormat,
+    zstd_dict_id: u32,
+    state_diffs: bool,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding {
+            wire_format: WireFormat::Json,
+            zstd_dict_id: LEGACY_ZSTD_DICT_ID,
+            state_diffs: false,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn entrypoint<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
     tx: mpsc::UnboundedSender<Vec<u8>>,
     rx: mpsc::UnboundedReceiver<Vec<u8>>,
     ws_id: usize,
+    ip_identity: String,
     logger: Logger,
     backend_storage: S,
     stats: Arc<Mutex<InMemoryStats>>,
+    sessions: Sessions,
+    users: SqliteUsers,
+    events: RoomEvents,
+    cluster: Cluster,
 ) {
-    let _ = handle_user_connected(tx, rx, ws_id, logger, backend_storage, stats).await;
+    let _ = handle_user_connected(
+        tx,
+        rx,
+        ws_id,
+        ip_identity,
+        logger,
+        backend_storage,
+        stats,
+        sessions,
+        users,
+        events,
+        cluster,
+    )
+    .await;
+}
+
+/// Who has been banned from the room.
+async fn room_bans<S: Storage<VersionedGame, E>, E: Send>(
+    backend_storage: &S,
+    room: &str,
+) -> HashSet<String> {
+    backend_storage
+        .clone()
+        .get(room.as_bytes().to_vec())
+        .await
+        .map(|versioned_game| versioned_game.bans)
+        .unwrap_or_default()
 }
 
 async fn send_to_user(
     tx: &'_ mpsc::UnboundedSender<Vec<u8>>,
     msg: &GameMessage,
+    encoding: Encoding,
 ) -> Result<(), anyhow::Error> {
-    if let Ok(j) = serde_json::to_vec(&msg) {
-        if let Ok(s) = ZSTD_COMPRESSOR.lock().unwrap().compress(&j) {
+    if let Ok(j) = encoding.wire_format.encode(msg) {
+        traffic_samples::record(&j);
+        let compressor = &ZSTD_COMPRESSORS[&encoding.zstd_dict_id];
+        if let Ok(s) = compressor.lock().unwrap().compress(&j) {
+            if !s.is_empty() {
+                COMPRESSION_RATIO.observe(j.len() as f64 / s.len() as f64);
+            }
             if tx.send(s).is_ok() {
+                MESSAGES_SENT.inc();
                 return Ok(());
             }
         }
@@ -40,31 +120,133 @@ async fn send_to_user(
     Err(anyhow::anyhow!("Unable to send message to user {:?}", msg))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_user_connected<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
     tx: mpsc::UnboundedSender<Vec<u8>>,
     mut rx: mpsc::UnboundedReceiver<Vec<u8>>,
     ws_id: usize,
+    ip_identity: String,
     logger: Logger,
     backend_storage: S,
     stats: Arc<Mutex<InMemoryStats>>,
+    sessions: Sessions,
+    users: SqliteUsers,
+    events: RoomEvents,
+    cluster: Cluster,
 ) -> Result<(), anyhow::Error> {
-    let (room, name) = loop {
+    let (room, name, reconnect_token, spectate, level, identities, encoding) = loop {
         if let Some(msg) = rx.recv().await {
             let err = match serde_json::from_slice(&msg) {
-                Ok(JoinRoom { room_name, name }) if room_name.len() == 16 && name.len() < 32 => {
-                    break (room_name, name);
+                Ok(JoinRoom {
+                    room_name,
+                    name,
+                    reconnect_token,
+                    spectate,
+                    device_key,
+                    wire_format,
+                    zstd_dictionaries,
+                    state_diffs,
+                }) if room_name.len() == 16 && name.len() < 32 => {
+                    // Clients which don't say which dictionaries they have
+                    // only have the original one.
+                    let encoding = Encoding {
+                        wire_format,
+                        zstd_dict_id: newest_zstd_dict(&zstd_dictionaries)
+                            .unwrap_or(LEGACY_ZSTD_DICT_ID),
+                        state_diffs,
+                    };
+                    let mut identities = vec![ip_identity.clone()];
+                    identities.extend(device_key.as_ref().map(|k| sessions.identity(k.as_bytes())));
+                    let key_hash = device_key.map(|k| Sha256::digest(k.as_bytes()).to_vec());
+                    let audit = ConnectionAudit {
+                        room_name: room_name.clone(),
+                        ws_id,
+                        player_id: None,
+                        name: name.clone(),
+                        identities: identities.clone(),
+                    };
+                    let bans = room_bans(&backend_storage, &room_name).await;
+                    if reconnect::is_banned(&bans, &name, &identities) {
+                        let err = "You've been banned from this room".to_string();
+                        audit.record("join", None, AuditOutcome::Rejected(err.clone()));
+                        GameMessage::Error(err)
+                    } else {
+                        match users.authenticate(&name, key_hash.as_deref()) {
+                            Ok(account) => {
+                                // Returning players pick up at the rank they
+                                // finished their last game with.
+                                let level = account
+                                    .and_then(|a| a.level)
+                                    .and_then(|l| serde_json::from_str::<Rank>(&l).ok());
+                                break (
+                                    room_name,
+                                    name,
+                                    reconnect_token,
+                                    spectate,
+                                    level,
+                                    identities,
+                                    encoding,
+                                );
+                            }
+                            Err(UserError::NicknameClaimed) => {
+                                let err = format!(
+                                    "{name} belongs to someone else, please pick another name"
+                                );
+                                audit.record("join", None, AuditOutcome::Rejected(err.clone()));
+                                GameMessage::Error(err)
+                            }
+                            Err(e) => {
+                                error!(logger, "Failed to look up account"; "error" => format!("{e:?}"));
+                                break (
+                                    room_name,
+                                    name,
+                                    reconnect_token,
+                                    spectate,
+                                    None,
+                                    identities,
+                                    encoding,
+                                );
+                            }
+                        }
+                    }
                 }
                 Ok(_) => GameMessage::Error("invalid room or name".to_string()),
                 Err(err) => GameMessage::Error(format!("couldn't deserialize message {err:?}")),
             };
 
-            send_to_user(&tx, &err).await?;
+            send_to_user(&tx, &err, Encoding::default()).await?;
         } else {
             Err(anyhow::anyhow!("no message on socket"))?;
         }
     };
 
+    let mut audit = ConnectionAudit {
+        room_name: room.clone(),
+        ws_id,
+        player_id: None,
+        name: name.clone(),
+        identities,
+    };
+    audit.record("join", None, AuditOutcome::Accepted);
+
     let logger = logger.new(o!("room" => room.clone(), "name" => name.clone()));
+    match cluster.route(&room, backend_storage.clone()).await {
+        Ok(Some(websocket_url)) => {
+            info!(logger, "Redirecting to the instance hosting the room"; "url" => &websocket_url);
+            send_to_user(&tx, &GameMessage::Redirect { websocket_url }, encoding).await?;
+            return Ok(());
+        }
+        Ok(None) => (),
+        // Better to risk hosting the room twice than to not host it at all.
+        Err(e) => error!(logger, "Failed to look up room"; "error" => format!("{e:?}")),
+    }
+    let reattach = match reconnect_token {
+        Some(token) => {
+            let bans = room_bans(&backend_storage, &room).await;
+            sessions.verify(&room, &bans, &token).await
+        }
+        None => None,
+    };
 
     let subscription = match backend_storage
         .clone()
@@ -76,6 +258,7 @@ async fn handle_user_connected<S: Storage<VersionedGame, E>, E: std::fmt::Debug
             let _ = send_to_user(
                 &tx,
                 &GameMessage::Error(format!("Failed to join room: {e:?}")),
+                encoding,
             )
             .await;
             return Err(anyhow::anyhow!("Failed to join room {:?}", e));
@@ -84,18 +267,23 @@ async fn handle_user_connected<S: Storage<VersionedGame, E>, E: std::fmt::Debug
 
     // Subscribe to messages for the room. After this point, we should
     // no longer use tx! It's owned by the backend storage.
-    let (subscribe_player_id_tx, subscribe_player_id_rx) = oneshot::channel::<PlayerID>();
+    let (subscribe_player_id_tx, subscribe_player_id_rx) = oneshot::channel::<(PlayerID, String)>();
     tokio::task::spawn(player_subscribe_task(
         logger.clone(),
-        name.clone(),
         tx.clone(),
         subscribe_player_id_rx,
         subscription,
+        encoding,
+        room.clone(),
+        sessions.clone(),
     ));
 
-    let (player_id, join_span) = register_user(
+    let (player_id, name, join_span) = register_user(
         logger.clone(),
-        name.clone(),
+        name,
+        reattach,
+        spectate,
+        level,
         ws_id,
         room.clone(),
         backend_storage.clone(),
@@ -106,7 +294,34 @@ async fn handle_user_connected<S: Storage<VersionedGame, E>, E: std::fmt::Debug
 
     let logger = logger.new(o!("player_id" => player_id.0));
     info!(logger, "Successfully registered user");
-    let _ = subscribe_player_id_tx.send(player_id);
+    audit.player_id = Some(player_id);
+    audit.name = name.clone();
+    let _ = subscribe_player_id_tx.send((player_id, name.clone()));
+
+    sessions
+        .connected(&room, player_id, &audit.identities)
+        .await;
+    let _ = backend_storage
+        .clone()
+        .publish_to_single_subscriber(
+            room.as_bytes().to_vec(),
+            ws_id,
+            GameMessage::Session {
+                player_id,
+                name: name.clone(),
+                token: sessions.issue(&room, player_id),
+            },
+        )
+        .await;
+    publish_career_stats(
+        &logger,
+        &users,
+        &room,
+        ws_id,
+        &name,
+        backend_storage.clone(),
+    )
+    .await;
 
     run_game_for_player(
         logger.clone(),
@@ -115,43 +330,122 @@ async fn handle_user_connected<S: Storage<VersionedGame, E>, E: std::fmt::Debug
         room.clone(),
         name,
         backend_storage.clone(),
+        sessions.clone(),
+        events,
+        audit,
         rx,
     )
     .await;
 
     // user_ws_rx stream will keep processing as long as the user stays
     // connected. Once they disconnect, then...
+    sessions.disconnected(&room, player_id).await;
     user_disconnected(room, ws_id, backend_storage, logger, join_span).await;
     Ok(())
 }
 
 async fn player_subscribe_task(
     logger_: Logger,
-    name_: String,
     tx: mpsc::UnboundedSender<Vec<u8>>,
-    subscribe_player_id_rx: oneshot::Receiver<PlayerID>,
+    subscribe_player_id_rx: oneshot::Receiver<(PlayerID, String)>,
     mut subscription: mpsc::UnboundedReceiver<GameMessage>,
+    encoding: Encoding,
+    room: String,
+    sessions: Sessions,
 ) {
     debug!(logger_, "Subscribed to messages");
-    if let Ok(player_id) = subscribe_player_id_rx.await {
+    if let Ok((player_id, name_)) = subscribe_player_id_rx.await {
         let logger_ = logger_.new(o!("player_id" => player_id.0));
         debug!(logger_, "Received player ID");
-        while let Some(v) = subscription.recv().await {
+        let mut spectator_delay = SpectatorDelay::default();
+        // Diffs are taken after any delay, against what the client has
+        // actually seen.
+        let mut state_diffs = StateDiffs::default();
+        // Broadcasts go to everyone, so they're redacted against the last
+        // state this player was sent.
+        let mut last_state: Option<GameState> = None;
+        let mut encode = |v| {
+            if encoding.state_diffs {
+                state_diffs.encode(v)
+            } else {
+                v
+            }
+        };
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            let v = tokio::select! {
+                v = subscription.recv() => match v {
+                    Some(v) => v,
+                    None => break,
+                },
+                v = spectator_delay.next() => {
+                    if send_to_user(&tx, &encode(v), encoding).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                _ = heartbeat.tick() => {
+                    // Heartbeats skip the spectator delay, so that it
+                    // doesn't count towards the round-trip time.
+                    let ping = GameMessage::Ping {
+                        sent_at_ms: now_ms(),
+                    };
+                    let latencies = GameMessage::Latencies {
+                        latencies: sessions.latencies(&room).await,
+                    };
+                    if send_to_user(&tx, &ping, encoding).await.is_err()
+                        || send_to_user(&tx, &latencies, encoding).await.is_err()
+                    {
+                        break;
+                    }
+                    continue;
+                }
+            };
             let should_send = match &v {
                 GameMessage::State { .. }
+                | GameMessage::StateDiff { .. }
                 | GameMessage::Broadcast { .. }
                 | GameMessage::Message { .. }
                 | GameMessage::Error(_)
-                | GameMessage::Header { .. } => true,
+                | GameMessage::Header { .. }
+                | GameMessage::Session { .. }
+                | GameMessage::TournamentTable { .. }
+                | GameMessage::TournamentFinished { .. }
+                | GameMessage::DuplicateBoard { .. }
+                | GameMessage::DuplicateReport { .. }
+                | GameMessage::TurnTimer { .. }
+                | GameMessage::Redirect { .. }
+                | GameMessage::CareerStats { .. }
+                | GameMessage::AchievementUnlocked { .. }
+                | GameMessage::Notification { .. }
+                | GameMessage::Ping { .. }
+                | GameMessage::Latencies { .. }
+                | GameMessage::TrickHistory { .. } => true,
                 GameMessage::Beep { target } | GameMessage::Kicked { target } => *target == name_,
                 GameMessage::ReadyCheck { from } => *from != name_,
             };
             let v = if should_send {
                 if let GameMessage::State { state } = v {
+                    // There's nothing to give away before the game starts.
+                    let delay = match state {
+                        GameState::Initialize(_) => 0,
+                        // Coaches need to keep up with their student.
+                        _ if state.coached_player(player_id).is_some() => 0,
+                        _ if state.is_spectator(player_id) => state.spectator_delay_secs(),
+                        _ => 0,
+                    };
+                    spectator_delay.set_delay(Duration::from_secs(delay as u64));
+                    last_state = Some(state.clone());
                     let g = InteractiveGame::new_from_state(state);
                     g.dump_state_for_player(player_id)
                         .ok()
                         .map(|state| GameMessage::State { state })
+                } else if let GameMessage::Broadcast { data, message } = v {
+                    let (data, message) = last_state
+                        .as_ref()
+                        .and_then(|state| data.redacted_for_player(state, player_id))
+                        .unwrap_or((data, message));
+                    Some(GameMessage::Broadcast { data, message })
                 } else {
                     Some(v)
                 }
@@ -159,8 +453,8 @@ async fn player_subscribe_task(
                 None
             };
 
-            if let Some(v) = v {
-                if send_to_user(&tx, &v).await.is_err() {
+            if let Some(v) = v.and_then(|v| spectator_delay.push(v)) {
+                if send_to_user(&tx, &encode(v), encoding).await.is_err() {
                     break;
                 }
             }
@@ -169,23 +463,82 @@ async fn player_subscribe_task(
     debug!(logger_, "Subscription task completed");
 }
 
+/// Shows the room the career statistics of a player who just joined, and
+/// shows them everyone else's.
+async fn publish_career_stats<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
+    logger: &Logger,
+    users: &SqliteUsers,
+    room: &str,
+    ws_id: usize,
+    name: &str,
+    backend_storage: S,
+) {
+    let game = match backend_storage.clone().get(room.as_bytes().to_vec()).await {
+        Ok(versioned_game) => versioned_game.game,
+        Err(e) => {
+            error!(logger, "Failed to load room"; "error" => format!("{e:?}"));
+            return;
+        }
+    };
+    let propagated = game.propagated();
+    for p in propagated.players().iter().chain(propagated.observers()) {
+        let msg = match career_stats_message(users, &p.name) {
+            Ok(Some(msg)) => msg,
+            Ok(None) => continue,
+            Err(e) => {
+                error!(logger, "Failed to look up career stats"; "error" => format!("{e:?}"));
+                continue;
+            }
+        };
+        let key = room.as_bytes().to_vec();
+        let _ = if p.name == name {
+            backend_storage.clone().publish(key, msg).await
+        } else {
+            backend_storage
+                .clone()
+                .publish_to_single_subscriber(key, ws_id, msg)
+                .await
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn register_user<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
     logger: Logger,
     name: String,
+    reattach: Option<PlayerID>,
+    spectate: bool,
+    level: Option<Rank>,
     ws_id: usize,
     room: String,
     backend_storage: S,
     stats: Arc<Mutex<InMemoryStats>>,
-) -> Result<(PlayerID, u64), ()> {
+) -> Result<(PlayerID, String, u64), ()> {
     let (player_id_tx, player_id_rx) = oneshot::channel();
     let logger_ = logger.clone();
-    let name_ = name.clone();
     execute_operation(
         ws_id,
         &room,
         backend_storage.clone(),
-        move |g, version, associated_websockets| {
-            let (assigned_player_id, register_msgs) = g.register(name_)?;
+        move |g, version, associated_websockets, _, _| {
+            // A valid reconnection token puts the player back into their
+            // previous seat, regardless of the name they joined with.
+            let (assigned_player_id, mut register_msgs) = match reattach.map(|id| g.reattach(id)) {
+                Some(Ok(r)) => r,
+                _ if spectate => g.register_spectator(name)?,
+                _ => g.register(name)?,
+            };
+            let newly_joined = register_msgs
+                .iter()
+                .any(|(data, _)| matches!(data.variant(), MessageVariant::JoinedGame { .. }));
+            if let (Some(level), true) = (level, newly_joined) {
+                // The rank might not be in this room's rank sequence, in
+                // which case the player just starts at the default.
+                if let Ok(msgs) = g.interact(Action::SetRank(level), assigned_player_id, &logger_) {
+                    register_msgs.extend(msgs);
+                }
+            }
+            let name_ = g.player_name(assigned_player_id)?.to_owned();
             info!(logger_, "Joining room"; "player_id" => assigned_player_id.0);
             let mut clients_to_disconnect = vec![];
             let clients = associated_websockets.entry(assigned_player_id).or_default();
@@ -197,7 +550,7 @@ async fn register_user<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
             clients.push(ws_id);
 
             player_id_tx
-                .send((assigned_player_id, version, clients_to_disconnect))
+                .send((assigned_player_id, name_, version, clients_to_disconnect))
                 .map_err(|_| anyhow::anyhow!("Couldn't send player ID back".to_owned()))?;
             Ok(register_msgs
                 .into_iter()
@@ -223,7 +576,7 @@ async fn register_user<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
         )
         .await;
 
-    if let Ok((player_id, ws_id, websockets_to_disconnect)) = player_id_rx.await {
+    if let Ok((player_id, name, ws_id, websockets_to_disconnect)) = player_id_rx.await {
         for id in websockets_to_disconnect {
             info!(logger, "Disconnnecting existing client"; "kicked_ws_id" => id);
             let _ = backend_storage
@@ -237,12 +590,13 @@ async fn register_user<S: Storage<VersionedGame, E>, E: std::fmt::Debug + Send>(
                 )
                 .await;
         }
-        Ok((player_id, ws_id))
+        Ok((player_id, name, ws_id))
     } else {
         Err(())
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_game_for_player<S: Storage<VersionedGame, E>, E: Send + std::fmt::Debug>(
     logger: Logger,
     ws_id: usize,
@@ -250,36 +604,101 @@ async fn run_game_for_player<S: Storage<VersionedGame, E>, E: Send + std::fmt::D
     room: String,
     name: String,
     backend_storage: S,
+    sessions: Sessions,
+    events: RoomEvents,
+    audit: ConnectionAudit,
     mut rx: mpsc::UnboundedReceiver<Vec<u8>>,
 ) {
     debug!(logger, "Entering main game loop");
+    let mut rate_limiter = RateLimiter::new(&RATE_LIMITS);
     // Handle the main game loop
-    while let Some(result) = rx.recv().await {
+    loop {
+        let result = match tokio::time::timeout(HEARTBEAT_TIMEOUT, rx.recv()).await {
+            Ok(Some(result)) => result,
+            Ok(None) => break,
+            Err(_) => {
+                info!(logger, "Disconnecting unresponsive client");
+                break;
+            }
+        };
         match serde_json::from_slice::<UserMessage>(&result) {
+            Ok(UserMessage::Pong { sent_at_ms }) => {
+                MESSAGES_RECEIVED.with_label_values(&["Pong"]).inc();
+                sessions
+                    .record_latency(&room, player_id, now_ms().saturating_sub(sent_at_ms))
+                    .await;
+            }
             Ok(msg) => {
-                if let Err(e) = handle_user_action(
+                let msg_type = user_message_type(&msg);
+                MESSAGES_RECEIVED.with_label_values(&[msg_type]).inc();
+                if let Err(limited) = rate_limiter.check(msg_type) {
+                    audit.record(msg_type, Some(&result), AuditOutcome::RateLimited);
+                    let err = match limited {
+                        RateLimited::OverBudget => {
+                            format!("You're sending too many {msg_type} messages, slow down!")
+                        }
+                        RateLimited::Muted { remaining } => {
+                            debug!(logger, "Connection muted"; "msg_type" => msg_type);
+                            format!(
+                                "You've been muted for {} more seconds",
+                                remaining.as_secs().max(1)
+                            )
+                        }
+                    };
+                    let _ = backend_storage
+                        .clone()
+                        .publish_to_single_subscriber(
+                            room.as_bytes().to_vec(),
+                            ws_id,
+                            GameMessage::Error(err),
+                        )
+                        .await;
+                    continue;
+                }
+                let _timer = MESSAGE_HANDLING_SECONDS
+                    .with_label_values(&[msg_type])
+                    .start_timer();
+                match handle_user_action(
                     logger.clone(),
                     ws_id,
                     player_id,
                     &room,
                     name.clone(),
                     backend_storage.clone(),
+                    sessions.clone(),
+                    events.clone(),
                     msg,
                 )
                 .await
                 {
-                    let _ = backend_storage
-                        .clone()
-                        .publish_to_single_subscriber(
-                            room.as_bytes().to_vec(),
-                            ws_id,
-                            GameMessage::Error(format!("Unexpected error {e:?}")),
-                        )
-                        .await;
+                    Ok(None) => audit.record(msg_type, Some(&result), AuditOutcome::Accepted),
+                    Ok(Some(rejection)) => {
+                        audit.record(msg_type, Some(&result), AuditOutcome::Rejected(rejection))
+                    }
+                    Err(e) => {
+                        audit.record(
+                            msg_type,
+                            Some(&result),
+                            AuditOutcome::Rejected(format!("Unexpected error {e:?}")),
+                        );
+                        let _ = backend_storage
+                            .clone()
+                            .publish_to_single_subscriber(
+                                room.as_bytes().to_vec(),
+                                ws_id,
+                                GameMessage::Error(format!("Unexpected error {e:?}")),
+                            )
+                            .await;
+                    }
                 }
             }
             Err(e) => {
                 error!(logger, "Failed to deserialize message"; "error" => format!("{e:?}"));
+                audit.record(
+                    "malformed",
+                    Some(&result),
+                    AuditOutcome::Malformed(format!("{e:?}")),
+                );
                 let _ = backend_storage
                     .clone()
                     .publish_to_single_subscriber(
@@ -294,6 +713,7 @@ async fn run_game_for_player<S: Storage<VersionedGame, E>, E: Send + std::fmt::D
     debug!(logger, "Exiting main game loop");
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
     logger: Logger,
     ws_id: usize,
@@ -301,9 +721,14 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
     room_name: &str,
     name: String,
     backend_storage: S,
+    sessions: Sessions,
+    events: RoomEvents,
     msg: UserMessage,
-) -> Result<(), E> {
-    match msg {
+) -> Result<Option<String>, E> {
+    let rejection = match msg {
+        // Heartbeats are handled as they're received, so that they're never
+        // rate-limited.
+        UserMessage::Pong { .. } => None,
         UserMessage::Beep => {
             execute_immutable_operation(
                 ws_id,
@@ -324,7 +749,23 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                 },
                 "send appropriate beep",
             )
-            .await;
+            .await
+        }
+        UserMessage::RequestTrickHistory => {
+            let versioned_game = backend_storage
+                .clone()
+                .get(room_name.as_bytes().to_vec())
+                .await?;
+            backend_storage
+                .publish_to_single_subscriber(
+                    room_name.as_bytes().to_vec(),
+                    ws_id,
+                    GameMessage::TrickHistory {
+                        tricks: versioned_game.game.trick_history_for(caller),
+                    },
+                )
+                .await?;
+            None
         }
         UserMessage::Message(m) => {
             backend_storage
@@ -336,6 +777,7 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                     },
                 )
                 .await?;
+            None
         }
         UserMessage::ReadyCheck => {
             backend_storage
@@ -354,6 +796,7 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                     GameMessage::ReadyCheck { from: name },
                 )
                 .await?;
+            None
         }
         UserMessage::Ready => {
             backend_storage
@@ -365,6 +808,7 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                     },
                 )
                 .await?;
+            None
         }
         UserMessage::Kick(id) => {
             info!(logger, "Kicking user"; "other" => id.0);
@@ -372,7 +816,7 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                 ws_id,
                 room_name,
                 backend_storage,
-                move |game, _, _| {
+                move |game, _, _, _, _| {
                     let kicked_player_name = game.player_name(id)?.to_owned();
                     game.kick(caller, id)?;
                     Ok(vec![GameMessage::Kicked {
@@ -381,26 +825,85 @@ async fn handle_user_action<S: Storage<VersionedGame, E>, E: Send>(
                 },
                 "kick user",
             )
-            .await;
+            .await
         }
-        UserMessage::Action(action) => {
+        UserMessage::Ban(id) => {
+            info!(logger, "Banning user"; "other" => id.0);
+            let identities = sessions.identities(room_name, id).await;
             execute_operation(
                 ws_id,
                 room_name,
                 backend_storage,
-                move |game, _, _| {
-                    Ok(game
-                        .interact(action, caller, &logger)?
+                move |game, _, _, _, bans| {
+                    let banned_player_name = game.player_name(id)?.to_owned();
+                    let msgs = game.ban(caller, id)?;
+                    reconnect::ban(bans, id, &banned_player_name, identities);
+                    Ok(msgs
+                        .into_iter()
+                        .map(|(data, message)| GameMessage::Broadcast { data, message })
+                        .chain(std::iter::once(GameMessage::Kicked {
+                            target: banned_player_name,
+                        }))
+                        .collect())
+                },
+                "ban user",
+            )
+            .await
+        }
+        UserMessage::Action(action) => {
+            let _timer = GAME_ACTION_SECONDS
+                .with_label_values(&[&action_name(&action)])
+                .start_timer();
+            let (room_events_tx, room_events_rx) = oneshot::channel();
+            let (turn_tx, turn_rx) = oneshot::channel();
+            let logger_ = logger.clone();
+            let room_name_ = room_name.to_string();
+            let rejection = execute_operation(
+                ws_id,
+                room_name,
+                backend_storage,
+                move |game, _, _, replay, _| {
+                    let msgs =
+                        game.interact_recorded(action, caller, replay, now_ms(), &logger_)?;
+                    let _ = turn_tx.send((game.timed_turn(), game.is_paused()));
+                    let _ = room_events_tx.send(RoomEvent::from_messages(
+                        &room_name_,
+                        game,
+                        replay,
+                        &msgs,
+                    ));
+                    let mut kicked = vec![];
+                    for (data, _) in &msgs {
+                        if let MessageVariant::KickVotePassed { name } = data.variant() {
+                            kicked.push(GameMessage::Kicked {
+                                target: name.clone(),
+                            });
+                        }
+                    }
+                    Ok(msgs
                         .into_iter()
                         .map(|(data, message)| GameMessage::Broadcast { data, message })
+                        .chain(kicked)
                         .collect())
                 },
                 "handle user action",
             )
             .await;
+            for event in room_events_rx.await.unwrap_or_default() {
+                // Nobody may be listening, which is fine.
+                let _ = events.send(event);
+            }
+            if let Ok((turn, paused)) = turn_rx.await {
+                let _ = events.send(RoomEvent::TurnChanged {
+                    room_name: room_name.to_string(),
+                    turn,
+                    paused,
+                });
+            }
+            rejection
         }
-    }
-    Ok(())
+    };
+    Ok(rejection)
 }
 
 async fn user_disconnected<S: Storage<VersionedGame, E>, E: Send>(
@@ -414,7 +917,7 @@ async fn user_disconnected<S: Storage<VersionedGame, E>, E: Send>(
         ws_id,
         &room,
         backend_storage.clone(),
-        move |_, _, associated_websockets| {
+        move |_, _, associated_websockets, _, _| {
             for ws in associated_websockets.values_mut() {
                 ws.retain(|w| *w != ws_id);
             }
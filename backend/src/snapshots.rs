@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Error};
+use axum::{
+    body::Bytes,
+    extract::Path,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use shengji_core::replay::{replay_from_log, Replay};
+use shengji_core::snapshot::GameSnapshot;
+use storage::{SqliteStorage, Storage};
+
+use crate::{
+    cluster::Cluster, reconnect::Sessions, rest_api::new_room, serving_types::VersionedGame,
+    utils::now_ms,
+};
+
+/// The start of every saved game, so that other files are rejected early.
+const MAGIC: &[u8] = b"shengji-saved-game\n";
+const NONCE_LEN: usize = 12;
+
+type ApiResult<T> = Result<T, (StatusCode, &'static str)>;
+
+/// Compresses and encrypts the snapshot, so that the players' hands can't be
+/// read out of the file, nor the game changed before it's restored.
+fn seal(key: &[u8], snapshot: &GameSnapshot) -> Result<Vec<u8>, Error> {
+    let data = zstd::encode_all(&snapshot.to_canonical_json()?[..], 3)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), &data[..])
+        .map_err(|_| anyhow!("failed to seal game"))?;
+    Ok([MAGIC, &nonce, &sealed].concat())
+}
+
+fn unseal(key: &[u8], data: &[u8]) -> Result<GameSnapshot, Error> {
+    let data = match data.strip_prefix(MAGIC) {
+        Some(data) if data.len() > NONCE_LEN => data,
+        _ => bail!("not a saved game"),
+    };
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let data = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| anyhow!("saved game was modified, or saved by another server"))?;
+    GameSnapshot::from_json(&zstd::decode_all(&data[..])?)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveGame {
+    /// The reconnection token the host was issued when they joined the room.
+    token: String,
+}
+
+/// Saves the room's game, including any game in progress, to a file which
+/// the host can restore into a new room later on.
+pub async fn save_game(
+    Path(room_name): Path<String>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(sessions): Extension<Sessions>,
+    Json(req): Json<SaveGame>,
+) -> ApiResult<impl IntoResponse> {
+    let versioned_game = backend_storage
+        .get(room_name.clone().into_bytes())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to load room"))?;
+    let player_id = sessions
+        .verify(&room_name, &versioned_game.bans, &req.token)
+        .await
+        .ok_or((StatusCode::FORBIDDEN, "not in the room"))?;
+    if versioned_game.game.host() != Some(player_id) {
+        return Err((StatusCode::FORBIDDEN, "only the host can save the game"));
+    }
+    let snapshot = GameSnapshot::new(versioned_game.game, versioned_game.replay, now_ms());
+    let data = seal(&sessions.sealing_key(), &snapshot)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to save game"))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"shengji-{room_name}.game\""),
+            ),
+        ],
+        data,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoredGame {
+    room_name: String,
+}
+
+/// Restores a saved game into a new room. Players get their seats back by
+/// joining with the names they had when the game was saved.
+pub async fn restore_game(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(sessions): Extension<Sessions>,
+    Extension(cluster): Extension<Cluster>,
+    data: Bytes,
+) -> ApiResult<Json<RestoredGame>> {
+    let snapshot = unseal(&sessions.sealing_key(), &data)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "invalid saved game"))?;
+    let room_name = new_room(backend_storage, &cluster, snapshot.state, snapshot.replay)
+        .await
+        .map_err(|_| (StatusCode::CONFLICT, "room already exists"))?;
+    Ok(Json(RestoredGame { room_name }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportGame {
+    /// The replay exported from `/replay/:room_name` on the old server.
+    replay: Replay,
+    /// Names from the replay mapped to the names the players will join the
+    /// new room with. Anyone who isn't listed keeps their seat under the
+    /// same name.
+    #[serde(default)]
+    seats: HashMap<String, String>,
+}
+
+/// Rebuilds the game from an exported replay into a new room, e.g. to move a
+/// game in progress to another server.
+pub async fn import_game(
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(cluster): Extension<Cluster>,
+    Json(req): Json<ImportGame>,
+) -> ApiResult<Json<RestoredGame>> {
+    let (state, replay) = replay_from_log(&req.replay, &req.seats)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "replay could not be played back"))?;
+    let room_name = new_room(backend_storage, &cluster, state, Some(replay))
+        .await
+        .map_err(|_| (StatusCode::CONFLICT, "room already exists"))?;
+    Ok(Json(RestoredGame { room_name }))
+}
@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use shengji_types::GameMessage;
+
+/// A spectator who falls this far behind has the oldest updates dropped.
+const MAX_QUEUED: usize = 1024;
+
+/// Holds back the game updates sent to a spectator, so that they see the
+/// table some time after the players do. Chat and other messages aren't
+/// held back.
+#[derive(Default)]
+pub struct SpectatorDelay {
+    delay: Duration,
+    queue: VecDeque<(Instant, GameMessage)>,
+}
+
+impl SpectatorDelay {
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Queues `msg` if it should be delayed, or hands it back to be sent
+    /// right away.
+    pub fn push(&mut self, msg: GameMessage) -> Option<GameMessage> {
+        let delayable = matches!(
+            msg,
+            GameMessage::State { .. }
+                | GameMessage::Broadcast { .. }
+                | GameMessage::TurnTimer { .. }
+                | GameMessage::TrickHistory { .. }
+                | GameMessage::Notification { .. }
+        );
+        // Updates which are already queued have to go out first.
+        if !delayable || (self.delay.is_zero() && self.queue.is_empty()) {
+            return Some(msg);
+        }
+        if self.queue.len() >= MAX_QUEUED {
+            self.queue.pop_front();
+        }
+        self.queue.push_back((Instant::now() + self.delay, msg));
+        None
+    }
+
+    /// Waits until the oldest queued update is due. Never finishes if
+    /// nothing is queued.
+    pub async fn next(&mut self) -> GameMessage {
+        match self.queue.front() {
+            Some((due, _)) => tokio::time::sleep_until(*due).await,
+            None => std::future::pending().await,
+        }
+        self.queue.pop_front().unwrap().1
+    }
+}
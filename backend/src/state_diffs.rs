@@ -0,0 +1,37 @@
+use serde_json::Value;
+
+use shengji_types::{state_diff, GameMessage};
+
+/// Send the whole state at least this often, so that a client which somehow
+/// got out of sync doesn't stay that way.
+const KEYFRAME_INTERVAL: usize = 32;
+
+/// Replaces the states sent to a client with the changes since the previous
+/// one, since most actions only change a small part of the state.
+#[derive(Default)]
+pub struct StateDiffs {
+    last: Option<Value>,
+    since_keyframe: usize,
+}
+
+impl StateDiffs {
+    pub fn encode(&mut self, msg: GameMessage) -> GameMessage {
+        let value = match msg {
+            GameMessage::State { ref state } => serde_json::to_value(state).ok(),
+            _ => return msg,
+        };
+        let last = std::mem::replace(&mut self.last, value);
+        match (last, &self.last) {
+            (Some(last), Some(value)) if self.since_keyframe < KEYFRAME_INTERVAL => {
+                self.since_keyframe += 1;
+                GameMessage::StateDiff {
+                    diff: state_diff::diff(&last, value),
+                }
+            }
+            _ => {
+                self.since_keyframe = 0;
+                msg
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{Extension, Json};
 use serde::{Deserialize, Serialize};
@@ -7,14 +8,15 @@ use slog::{error, info, o, Logger};
 use tokio::sync::Mutex;
 
 use shengji_core::game_state::GameState;
+use shengji_core::interactive::EventLog;
 use shengji_core::settings::GameVisibility;
-use shengji_types::GameMessage;
-use storage::{HashMapStorage, Storage};
+use shengji_types::{versioned_state, GameMessage, ZSTD_ZSTD_DICT};
+use storage::{ArchivePolicy, SqliteStorage, Storage};
 
 use crate::{
     serving_types::VersionedGame,
     utils::{try_read_file, try_read_file_opt, write_state_to_disk},
-    DUMP_PATH, MESSAGE_PATH, ROOT_LOGGER,
+    ARCHIVE_IDLE_MINUTES, DUMP_PATH, MESSAGE_PATH, ROOT_LOGGER, STORAGE_PATH,
 };
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -29,6 +31,11 @@ impl InMemoryStats {
     }
 }
 
+/// A game in the state dump, which is written along with its schema version
+/// so that it can be migrated if it's loaded by a newer server.
+#[derive(Serialize)]
+pub struct DumpedGame(#[serde(with = "shengji_types::versioned_state")] GameState);
+
 #[derive(Serialize, Deserialize)]
 pub struct PublicGameInfo {
     name: String,
@@ -47,14 +54,22 @@ pub async fn load_dump_file<S: Storage<VersionedGame, E>, E: Send + std::fmt::De
         None => return Ok(0),
     };
 
+    // Games which were checkpointed to storage are newer than the ones in the
+    // dump, so only load the ones which storage doesn't know about.
     let futures = dump.into_iter().map(|(room_name, v)| {
-        serde_json::from_value(v).map(|game| {
-            backend_storage.clone().put(VersionedGame {
-                room_name: room_name.as_bytes().to_vec(),
-                game,
-                associated_websockets: HashMap::new(),
-                monotonic_id: 1,
-            })
+        versioned_state::from_value(v).map(|game| {
+            backend_storage.clone().put_cas(
+                0,
+                VersionedGame {
+                    room_name: room_name.as_bytes().to_vec(),
+                    game,
+                    associated_websockets: HashMap::new(),
+                    monotonic_id: 1,
+                    replay: None,
+                    bans: HashSet::new(),
+                    events: EventLog::default(),
+                },
+            )
         })
     });
 
@@ -62,19 +77,37 @@ pub async fn load_dump_file<S: Storage<VersionedGame, E>, E: Send + std::fmt::De
         if let Ok(()) = f?.await {
             num_games_loaded += 1;
         } else {
-            error!(logger, "Failed to upsert initial game state");
+            info!(logger, "Skipped game which is already in storage");
         }
     }
 
     Ok(num_games_loaded)
 }
 
-pub async fn load_state(
-) -> Result<(HashMapStorage<VersionedGame>, Arc<Mutex<InMemoryStats>>), anyhow::Error> {
-    let backend_storage = HashMapStorage::new(ROOT_LOGGER.new(o!("component" => "storage")));
+/// Opens the storage at `STORAGE_PATH`.
+pub fn open_storage() -> Result<SqliteStorage<VersionedGame>, anyhow::Error> {
+    let backend_storage = SqliteStorage::open(
+        ROOT_LOGGER.new(o!("component" => "storage", "storage_path" => &*STORAGE_PATH)),
+        &*STORAGE_PATH,
+    )?;
+    // Archives are compressed with the same dictionary as the messages we
+    // send to clients.
+    Ok(match *ARCHIVE_IDLE_MINUTES {
+        Some(minutes) => backend_storage.with_archive_policy(ArchivePolicy {
+            idle_after: Duration::from_secs(minutes.saturating_mul(60)),
+            dictionary: zstd::bulk::decompress(ZSTD_ZSTD_DICT, 112_640)?,
+        }),
+        None => backend_storage,
+    })
+}
 
+/// Loads any games left in the state dump into `backend_storage`, along with
+/// the header messages.
+pub async fn load_state(
+    backend_storage: SqliteStorage<VersionedGame>,
+) -> Result<Arc<Mutex<InMemoryStats>>, anyhow::Error> {
     let init_logger = ROOT_LOGGER.new(o!("dump_path" => &*DUMP_PATH));
-    match load_dump_file(init_logger.clone(), backend_storage.clone()).await {
+    match load_dump_file(init_logger.clone(), backend_storage).await {
         Ok(n) => {
             info!(init_logger, "Loaded games from state dump"; "num_games" => n);
         }
@@ -96,14 +129,14 @@ pub async fn load_state(
         }
     }
 
-    Ok((backend_storage, stats))
+    Ok(stats)
 }
 
 pub async fn dump_state(
-    Extension(backend_storage): Extension<HashMapStorage<VersionedGame>>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
     Extension(stats): Extension<Arc<Mutex<InMemoryStats>>>,
-) -> Result<Json<HashMap<String, GameState>>, &'static str> {
-    let mut state_dump: HashMap<String, GameState> = HashMap::new();
+) -> Result<Json<HashMap<String, DumpedGame>>, &'static str> {
+    let mut state_dump: HashMap<String, DumpedGame> = HashMap::new();
 
     let header_messages = try_read_file::<Vec<String>>(&MESSAGE_PATH)
         .await
@@ -141,7 +174,7 @@ pub async fn dump_state(
             num_players += versioned_game.game.players().len();
             num_observers += versioned_game.game.observers().len();
             if let Ok(name) = String::from_utf8(room_name.clone()) {
-                state_dump.insert(name, versioned_game.game);
+                state_dump.insert(name, DumpedGame(versioned_game.game));
             }
             num_processed_games += 1;
         } else {
@@ -185,7 +218,7 @@ pub async fn dump_state(
 }
 
 pub async fn public_games(
-    Extension(backend_storage): Extension<HashMapStorage<VersionedGame>>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
 ) -> Result<Json<Vec<PublicGameInfo>>, &'static str> {
     let mut public_games: Vec<PublicGameInfo> = Vec::new();
 
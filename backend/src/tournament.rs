@@ -0,0 +1,604 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use slog::{error, info, o, Logger};
+use tokio::sync::{broadcast, Mutex};
+
+use shengji_core::game_state::{
+    initialize_phase::InitializePhase, play_phase::PlayerGameFinishedResult, GameState,
+};
+use shengji_core::settings::{PropagatedState, MAX_DEAL_SEED};
+use shengji_types::{DuplicateStanding, GameMessage};
+use storage::{SqliteStorage, Storage};
+
+use crate::{
+    cluster::Cluster,
+    events::{RoomEvent, RoomEvents},
+    rest_api::new_room,
+    serving_types::VersionedGame,
+};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, &'static str)>;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Table {
+    room_name: String,
+    players: Vec<String>,
+    /// Filled in once the first game at the table has finished.
+    winners: Option<Vec<String>>,
+    /// In duplicate tournaments, the levels gained by the player in each
+    /// seat, or conceded if negative.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scores: Option<Vec<isize>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Round {
+    tables: Vec<Table>,
+    /// Players who couldn't be seated this round, and advance automatically.
+    byes: Vec<String>,
+    /// The seed every table in a duplicate tournament was dealt this board
+    /// with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum TournamentFormat {
+    /// Whoever wins at their table advances to the next round.
+    #[default]
+    Knockout,
+    /// Every table plays the same deals with the same seating, and players
+    /// are compared with the people who sat in the same seat elsewhere.
+    Duplicate { boards: usize },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub enum TournamentStatus {
+    Registering,
+    InProgress,
+    Finished {
+        winners: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        standings: Option<Vec<DuplicateStanding>>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Tournament {
+    id: String,
+    #[serde(skip_serializing)]
+    organizer_key: String,
+    name: String,
+    table_size: usize,
+    format: TournamentFormat,
+    #[serde(skip_serializing)]
+    settings: Option<PropagatedState>,
+    registrants: Vec<String>,
+    rounds: Vec<Round>,
+    status: TournamentStatus,
+}
+
+impl Tournament {
+    fn current_round(&self) -> Option<&Round> {
+        match self.status {
+            TournamentStatus::InProgress => self.rounds.last(),
+            _ => None,
+        }
+    }
+
+    fn has_room(&self, room_name: &str) -> bool {
+        self.current_round()
+            .map(|r| r.tables.iter().any(|t| t.room_name == room_name))
+            .unwrap_or(false)
+    }
+}
+
+/// Tournaments coordinate games across several rooms: players register,
+/// get seated at tables, and whoever wins at their table advances to the
+/// next round. In duplicate tournaments, the tables instead stay together
+/// and play the same boards as each other. Tournaments only live in memory.
+#[derive(Clone, Default)]
+pub struct Tournaments {
+    tournaments: Arc<Mutex<HashMap<String, Tournament>>>,
+}
+
+impl Tournaments {
+    /// Splits the players into tables at random, leaving whoever doesn't
+    /// fill a table with a bye.
+    fn draw_tables(
+        tournament: &Tournament,
+        mut players: Vec<String>,
+    ) -> (Vec<Vec<String>>, Vec<String>) {
+        players.shuffle(&mut rand::thread_rng());
+        let num_tables = players.len() / tournament.table_size;
+        let byes = players.split_off(num_tables * tournament.table_size);
+        let tables = players
+            .chunks(tournament.table_size)
+            .map(|t| t.to_vec())
+            .collect();
+        (tables, byes)
+    }
+
+    async fn open_room(
+        backend_storage: SqliteStorage<VersionedGame>,
+        cluster: &Cluster,
+        init: InitializePhase,
+    ) -> Result<String, anyhow::Error> {
+        new_room(backend_storage, cluster, GameState::Initialize(init), None)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create room: {:?}", e))
+    }
+
+    fn initial_settings(tournament: &Tournament) -> Result<InitializePhase, anyhow::Error> {
+        Ok(match &tournament.settings {
+            Some(settings) => InitializePhase::from_settings(settings.clone())?,
+            None => InitializePhase::new(),
+        })
+    }
+
+    /// Seats the players in rooms of their own, leaving whoever doesn't fill
+    /// a table with a bye.
+    async fn seat(
+        backend_storage: SqliteStorage<VersionedGame>,
+        cluster: &Cluster,
+        tournament: &Tournament,
+        players: Vec<String>,
+    ) -> Result<Round, anyhow::Error> {
+        let (seating, byes) = Self::draw_tables(tournament, players);
+        let mut tables = vec![];
+        for players in seating {
+            let init = Self::initial_settings(tournament)?;
+            let room_name = Self::open_room(backend_storage.clone(), cluster, init).await?;
+            tables.push(Table {
+                room_name,
+                players,
+                winners: None,
+                scores: None,
+            });
+        }
+        Ok(Round {
+            tables,
+            byes,
+            seed: None,
+        })
+    }
+
+    /// Opens a room for each table of a duplicate tournament, with everyone
+    /// already in their seat and the same deal seed at every table, so that
+    /// each seat is dealt the same cards everywhere.
+    async fn deal_board(
+        backend_storage: SqliteStorage<VersionedGame>,
+        cluster: &Cluster,
+        tournament: &Tournament,
+        seating: Vec<Vec<String>>,
+        byes: Vec<String>,
+    ) -> Result<Round, anyhow::Error> {
+        let seed = rand::random::<u64>() & MAX_DEAL_SEED;
+        let mut tables = vec![];
+        for players in seating {
+            let mut init = Self::initial_settings(tournament)?;
+            init.set_deal_seed(Some(seed))?;
+            for name in &players {
+                init.add_player(name.clone())?;
+            }
+            let room_name = Self::open_room(backend_storage.clone(), cluster, init).await?;
+            tables.push(Table {
+                room_name,
+                players,
+                winners: None,
+                scores: None,
+            });
+        }
+        Ok(Round {
+            tables,
+            byes,
+            seed: Some(seed),
+        })
+    }
+
+    /// Records the winners of a finished game if it was played at one of the
+    /// tournament's tables, and seats the next round once every table in
+    /// the current one is done.
+    async fn game_finished(
+        &self,
+        logger: &Logger,
+        backend_storage: SqliteStorage<VersionedGame>,
+        cluster: &Cluster,
+        room_name: &str,
+        results: HashMap<String, PlayerGameFinishedResult>,
+    ) -> Result<(), anyhow::Error> {
+        let mut tournaments = self.tournaments.lock().await;
+        let tournament = match tournaments.values_mut().find(|t| t.has_room(room_name)) {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let round = tournament.rounds.last_mut().unwrap();
+        let table = round
+            .tables
+            .iter_mut()
+            .find(|t| t.room_name == room_name)
+            .unwrap();
+        if table.winners.is_some() {
+            return Ok(());
+        }
+        let winners = results
+            .iter()
+            .filter(|(_, r)| r.won_game)
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        table.winners = Some(
+            table
+                .players
+                .iter()
+                .filter(|p| winners.contains(*p))
+                .cloned()
+                .collect(),
+        );
+        if let TournamentFormat::Duplicate { .. } = tournament.format {
+            table.scores = Some(seat_scores(&table.players, &results));
+        }
+        if round.tables.iter().any(|t| t.winners.is_none()) {
+            return Ok(());
+        }
+
+        let previous_rooms = round
+            .tables
+            .iter()
+            .map(|t| t.room_name.clone())
+            .collect::<Vec<_>>();
+        let mut advancing = round.byes.clone();
+        for table in &round.tables {
+            advancing.extend(table.winners.iter().flatten().cloned());
+        }
+        let logger = logger.new(o!("tournament_id" => tournament.id.clone()));
+        let tournament_id = tournament.id.clone();
+
+        let messages = if let TournamentFormat::Duplicate { boards } = tournament.format {
+            Self::next_board(
+                &logger,
+                backend_storage.clone(),
+                cluster,
+                tournament,
+                boards,
+            )
+            .await?
+        } else if advancing.len() < tournament.table_size {
+            info!(logger, "Tournament finished"; "num_winners" => advancing.len());
+            tournament.status = TournamentStatus::Finished {
+                winners: advancing.clone(),
+                standings: None,
+            };
+            vec![GameMessage::TournamentFinished {
+                tournament_id,
+                winners: advancing,
+            }]
+        } else {
+            let round = Self::seat(backend_storage.clone(), cluster, tournament, advancing).await?;
+            info!(logger, "Seated next round"; "round" => tournament.rounds.len() + 1);
+            let messages = round
+                .tables
+                .iter()
+                .map(|t| GameMessage::TournamentTable {
+                    tournament_id: tournament_id.clone(),
+                    round: tournament.rounds.len() + 1,
+                    room_name: t.room_name.clone(),
+                    players: t.players.clone(),
+                })
+                .collect();
+            tournament.rounds.push(round);
+            messages
+        };
+        drop(tournaments);
+
+        for room in previous_rooms {
+            for msg in &messages {
+                let _ = backend_storage
+                    .clone()
+                    .publish(room.as_bytes().to_vec(), msg.clone())
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deals the next board of a duplicate tournament to the same tables,
+    /// or compares everyone's results if that was the last one.
+    async fn next_board(
+        logger: &Logger,
+        backend_storage: SqliteStorage<VersionedGame>,
+        cluster: &Cluster,
+        tournament: &mut Tournament,
+        boards: usize,
+    ) -> Result<Vec<GameMessage>, anyhow::Error> {
+        let tournament_id = tournament.id.clone();
+        if tournament.rounds.len() < boards {
+            let round = tournament.rounds.last().unwrap();
+            let seating = round.tables.iter().map(|t| t.players.clone()).collect();
+            let byes = round.byes.clone();
+            let round =
+                Self::deal_board(backend_storage, cluster, tournament, seating, byes).await?;
+            info!(logger, "Dealt next board"; "board" => tournament.rounds.len() + 1);
+            let messages = round
+                .tables
+                .iter()
+                .map(|t| GameMessage::DuplicateBoard {
+                    tournament_id: tournament_id.clone(),
+                    board: tournament.rounds.len() + 1,
+                    room_name: t.room_name.clone(),
+                    players: t.players.clone(),
+                })
+                .collect();
+            tournament.rounds.push(round);
+            return Ok(messages);
+        }
+
+        let standings = compare_seats(&tournament.rounds);
+        let winners = standings
+            .iter()
+            .take_while(|s| s.score >= standings[0].score)
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        info!(logger, "Duplicate tournament finished"; "num_winners" => winners.len());
+        tournament.status = TournamentStatus::Finished {
+            winners: winners.clone(),
+            standings: Some(standings.clone()),
+        };
+        Ok(vec![
+            GameMessage::TournamentFinished {
+                tournament_id: tournament_id.clone(),
+                winners,
+            },
+            GameMessage::DuplicateReport {
+                tournament_id,
+                standings,
+            },
+        ])
+    }
+}
+
+/// The levels each seat's team gained at a table, or, for the losing team,
+/// the levels the winners gained, as a negative number.
+fn seat_scores(
+    players: &[String],
+    results: &HashMap<String, PlayerGameFinishedResult>,
+) -> Vec<isize> {
+    let gained = results
+        .values()
+        .filter(|r| r.won_game)
+        .map(|r| r.ranks_up as isize)
+        .max()
+        .unwrap_or(0);
+    players
+        .iter()
+        .map(|p| match results.get(p) {
+            Some(r) if r.won_game => r.ranks_up as isize,
+            _ => -gained,
+        })
+        .collect()
+}
+
+/// Compares every player with the others who sat in the same seat on each
+/// board, since they were all dealt the same cards.
+fn compare_seats(boards: &[Round]) -> Vec<DuplicateStanding> {
+    let seating = match boards.first() {
+        Some(board) => &board.tables,
+        None => return vec![],
+    };
+    let mut standings = vec![];
+    for (table, t) in seating.iter().enumerate() {
+        for (seat, name) in t.players.iter().enumerate() {
+            let score = boards
+                .iter()
+                .map(|board| {
+                    let scores = board
+                        .tables
+                        .iter()
+                        .map(|t| t.scores.as_ref().map(|s| s[seat]).unwrap_or(0))
+                        .collect::<Vec<_>>();
+                    let average = scores.iter().sum::<isize>() as f64 / scores.len() as f64;
+                    scores[table] as f64 - average
+                })
+                .sum();
+            standings.push(DuplicateStanding {
+                name: name.clone(),
+                table,
+                seat,
+                score,
+            });
+        }
+    }
+    standings.sort_by(|a, b| b.score.total_cmp(&a.score));
+    standings
+}
+
+/// Advances tournaments as their games finish.
+pub async fn advance_tournaments(
+    logger: Logger,
+    tournaments: Tournaments,
+    backend_storage: SqliteStorage<VersionedGame>,
+    cluster: Cluster,
+    events: RoomEvents,
+) {
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::GameFinished {
+                room_name, results, ..
+            }) => {
+                if let Err(e) = tournaments
+                    .game_finished(
+                        &logger,
+                        backend_storage.clone(),
+                        &cluster,
+                        &room_name,
+                        results,
+                    )
+                    .await
+                {
+                    error!(logger, "Failed to advance tournament"; "error" => format!("{e:?}"));
+                }
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn default_table_size() -> usize {
+    4
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTournament {
+    name: String,
+    #[serde(default = "default_table_size")]
+    table_size: usize,
+    /// Settings for every room in the tournament, in the same shape as
+    /// `/default_settings.json`.
+    #[serde(default)]
+    settings: Option<PropagatedState>,
+    #[serde(default)]
+    format: TournamentFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedTournament {
+    id: String,
+    /// Needed to start the tournament; only the organizer should have it.
+    organizer_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterForTournament {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartTournament {
+    organizer_key: String,
+}
+
+pub async fn create_tournament(
+    Extension(tournaments): Extension<Tournaments>,
+    Json(req): Json<CreateTournament>,
+) -> ApiResult<CreatedTournament> {
+    if !(2..=16).contains(&req.table_size) {
+        return Err((StatusCode::BAD_REQUEST, "table size must be from 2 to 16"));
+    }
+    if let TournamentFormat::Duplicate { boards } = req.format {
+        if !(1..=32).contains(&boards) {
+            return Err((StatusCode::BAD_REQUEST, "boards must be from 1 to 32"));
+        }
+    }
+    if let Some(settings) = &req.settings {
+        InitializePhase::from_settings(settings.clone())
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid settings"))?;
+    }
+    let id = format!("{:016x}", rand::random::<u64>());
+    let organizer_key = format!("{:032x}", rand::random::<u128>());
+    tournaments.tournaments.lock().await.insert(
+        id.clone(),
+        Tournament {
+            id: id.clone(),
+            organizer_key: organizer_key.clone(),
+            name: req.name,
+            table_size: req.table_size,
+            format: req.format,
+            settings: req.settings,
+            registrants: vec![],
+            rounds: vec![],
+            status: TournamentStatus::Registering,
+        },
+    );
+    Ok(Json(CreatedTournament { id, organizer_key }))
+}
+
+pub async fn tournament_status(
+    Path(id): Path<String>,
+    Extension(tournaments): Extension<Tournaments>,
+) -> ApiResult<Tournament> {
+    match tournaments.tournaments.lock().await.get(&id) {
+        Some(t) => Ok(Json(t.clone())),
+        None => Err((StatusCode::NOT_FOUND, "tournament not found")),
+    }
+}
+
+pub async fn register_for_tournament(
+    Path(id): Path<String>,
+    Extension(tournaments): Extension<Tournaments>,
+    Json(req): Json<RegisterForTournament>,
+) -> ApiResult<Tournament> {
+    let mut tournaments = tournaments.tournaments.lock().await;
+    let tournament = tournaments
+        .get_mut(&id)
+        .ok_or((StatusCode::NOT_FOUND, "tournament not found"))?;
+    if !matches!(tournament.status, TournamentStatus::Registering) {
+        return Err((StatusCode::CONFLICT, "tournament has already started"));
+    }
+    if req.name.is_empty() || req.name.len() >= 32 {
+        return Err((StatusCode::BAD_REQUEST, "invalid name"));
+    }
+    if tournament.registrants.contains(&req.name) {
+        return Err((StatusCode::CONFLICT, "name is already registered"));
+    }
+    tournament.registrants.push(req.name);
+    Ok(Json(tournament.clone()))
+}
+
+pub async fn start_tournament(
+    Path(id): Path<String>,
+    Extension(tournaments): Extension<Tournaments>,
+    Extension(backend_storage): Extension<SqliteStorage<VersionedGame>>,
+    Extension(cluster): Extension<Cluster>,
+    Json(req): Json<StartTournament>,
+) -> ApiResult<Tournament> {
+    let mut tournaments = tournaments.tournaments.lock().await;
+    let tournament = tournaments
+        .get_mut(&id)
+        .ok_or((StatusCode::NOT_FOUND, "tournament not found"))?;
+    if tournament.organizer_key != req.organizer_key {
+        return Err((StatusCode::FORBIDDEN, "only the organizer can start it"));
+    }
+    if !matches!(tournament.status, TournamentStatus::Registering) {
+        return Err((StatusCode::CONFLICT, "tournament has already started"));
+    }
+    if tournament.registrants.len() < tournament.table_size {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "not enough players to fill a table",
+        ));
+    }
+    let round = match tournament.format {
+        TournamentFormat::Knockout => {
+            Tournaments::seat(
+                backend_storage,
+                &cluster,
+                tournament,
+                tournament.registrants.clone(),
+            )
+            .await
+        }
+        TournamentFormat::Duplicate { .. } => {
+            let (seating, byes) =
+                Tournaments::draw_tables(tournament, tournament.registrants.clone());
+            if seating.len() < 2 {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "duplicate tournaments need at least two tables",
+                ));
+            }
+            Tournaments::deal_board(backend_storage, &cluster, tournament, seating, byes).await
+        }
+    }
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "failed to seat players"))?;
+    tournament.rounds.push(round);
+    tournament.status = TournamentStatus::InProgress;
+    Ok(Json(tournament.clone()))
+}
@@ -0,0 +1,48 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use rand::Rng;
+
+use crate::TRAFFIC_SAMPLE_PATH;
+
+/// Record roughly one in this many messages.
+const SAMPLE_ONE_IN: u32 = 100;
+/// Stop recording once the samples take up this much space, which is far more
+/// than a dictionary needs to be trained from.
+const MAX_SAMPLE_BYTES: u64 = 64 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    static ref SAMPLES: Option<Mutex<(File, u64)>> = {
+        let path = TRAFFIC_SAMPLE_PATH.as_ref()?;
+        let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
+        let len = file.metadata().ok()?.len();
+        Some(Mutex::new((file, len)))
+    };
+}
+
+/// Records a sample of the messages we send, before they're compressed, so
+/// that new zstd dictionaries can be trained on them with `shengji-zstd-dict`.
+/// Only does anything if `TRAFFIC_SAMPLE_PATH` is set.
+///
+/// Each sample is written as its length, as a little-endian `u32`, followed by
+/// the message itself.
+pub fn record(msg: &[u8]) {
+    let samples = match *SAMPLES {
+        Some(ref samples) => samples,
+        None => return,
+    };
+    if rand::thread_rng().gen_range(0..SAMPLE_ONE_IN) != 0 {
+        return;
+    }
+    let mut samples = samples.lock().unwrap();
+    let (ref mut file, ref mut len) = *samples;
+    if *len >= MAX_SAMPLE_BYTES {
+        return;
+    }
+    let mut sample = (msg.len() as u32).to_le_bytes().to_vec();
+    sample.extend_from_slice(msg);
+    if file.write_all(&sample).is_ok() {
+        *len += sample.len() as u64;
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use slog::{debug, error, o, Logger};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+use shengji_core::game_state::TimedTurn;
+use shengji_types::GameMessage;
+use storage::{SqliteStorage, Storage};
+
+use crate::{
+    events::{RoomEvent, RoomEvents},
+    serving_types::VersionedGame,
+    utils::{execute_operation, now_ms},
+};
+
+/// The timeout isn't on behalf of any websocket, so there's nobody to send
+/// errors (e.g. the turn having moved on) to.
+const TIMER_WS_ID: usize = 0;
+
+struct RoomTimer {
+    turn: TimedTurn,
+    /// How long was left as of `since`.
+    remaining: Duration,
+    since: Instant,
+    /// Counting down to the timeout, unless the game is paused.
+    task: Option<JoinHandle<()>>,
+}
+
+impl RoomTimer {
+    fn new(turn: TimedTurn) -> Self {
+        RoomTimer {
+            turn,
+            remaining: Duration::from_secs(turn.secs as u64),
+            since: Instant::now(),
+            task: None,
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        match self.task {
+            Some(_) => self.remaining.saturating_sub(self.since.elapsed()),
+            None => self.remaining,
+        }
+    }
+
+    fn pause(&mut self) {
+        let remaining = self.remaining();
+        if let Some(task) = self.task.take() {
+            task.abort();
+            self.remaining = remaining;
+        }
+    }
+
+    fn resume(
+        &mut self,
+        logger: &Logger,
+        room_name: &str,
+        backend_storage: &SqliteStorage<VersionedGame>,
+        events: &RoomEvents,
+    ) {
+        if self.task.is_none() {
+            self.since = Instant::now();
+            self.task = Some(tokio::task::spawn(time_out_after(
+                logger.new(o!("room" => room_name.to_string())),
+                room_name.to_string(),
+                self.turn,
+                self.remaining,
+                backend_storage.clone(),
+                events.clone(),
+            )));
+        }
+    }
+
+    fn message(&self) -> GameMessage {
+        GameMessage::TurnTimer {
+            player_id: Some(self.turn.player),
+            remaining_ms: self.remaining().as_millis() as u64,
+            paused: self.task.is_none(),
+        }
+    }
+}
+
+/// Keeps one timer per room for whoever the room is waiting on, and applies
+/// the room's timeout policy when it runs out. Pausing the game freezes the
+/// timer until it's resumed.
+pub async fn run_turn_timers(
+    logger: Logger,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    let mut timers: HashMap<String, RoomTimer> = HashMap::new();
+    let mut rx = events.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RoomEvent::TurnChanged {
+                room_name,
+                turn,
+                paused,
+            }) => {
+                let mut timer = match (timers.remove(&room_name), turn) {
+                    (Some(timer), Some(turn)) if timer.turn == turn => {
+                        if paused == timer.task.is_none() {
+                            timers.insert(room_name, timer);
+                            continue;
+                        }
+                        timer
+                    }
+                    (previous, turn) => {
+                        let had_timer = previous.is_some();
+                        if let Some(task) = previous.and_then(|t| t.task) {
+                            task.abort();
+                        }
+                        match turn {
+                            Some(turn) => RoomTimer::new(turn),
+                            None if had_timer => {
+                                let msg = GameMessage::TurnTimer {
+                                    player_id: None,
+                                    remaining_ms: 0,
+                                    paused: false,
+                                };
+                                let _ = backend_storage
+                                    .clone()
+                                    .publish(room_name.as_bytes().to_vec(), msg)
+                                    .await;
+                                continue;
+                            }
+                            None => continue,
+                        }
+                    }
+                };
+                if paused {
+                    timer.pause();
+                } else {
+                    timer.resume(&logger, &room_name, &backend_storage, &events);
+                }
+                let msg = timer.message();
+                timers.insert(room_name.clone(), timer);
+                let _ = backend_storage
+                    .clone()
+                    .publish(room_name.as_bytes().to_vec(), msg)
+                    .await;
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn time_out_after(
+    logger: Logger,
+    room_name: String,
+    turn: TimedTurn,
+    duration: Duration,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    tokio::time::sleep(duration).await;
+    debug!(logger, "Turn timed out"; "player_id" => turn.player.0);
+
+    let (turn_tx, turn_rx) = oneshot::channel();
+    let logger_ = logger.clone();
+    execute_operation(
+        TIMER_WS_ID,
+        &room_name,
+        backend_storage,
+        move |game, _, _, replay, _| {
+            let msgs = game.time_out(turn, replay, now_ms(), &logger_)?;
+            let _ = turn_tx.send((game.timed_turn(), game.is_paused()));
+            Ok(msgs
+                .into_iter()
+                .map(|(data, message)| GameMessage::Broadcast { data, message })
+                .collect())
+        },
+        "time out turn",
+    )
+    .await;
+    if let Ok((turn, paused)) = turn_rx.await {
+        let _ = events.send(RoomEvent::TurnChanged {
+            room_name,
+            turn,
+            paused,
+        });
+    }
+}
@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use shengji_core::interactive::InteractiveGame;
+use shengji_core::notification::Notification;
+use shengji_core::replay::Replay;
 use shengji_mechanics::types::PlayerID;
-use shengji_types::GameMessage;
-use storage::Storage;
+use shengji_types::{CareerCard, GameMessage};
+use storage::{SqliteUsers, Storage, UserError};
 
 use crate::serving_types::VersionedGame;
 
@@ -39,13 +42,41 @@ pub async fn write_state_to_disk<M: serde::ser::Serialize>(
     Ok(())
 }
 
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The message showing a player's career statistics to their room, if
+/// they've claimed their nickname.
+pub fn career_stats_message(
+    users: &SqliteUsers,
+    name: &str,
+) -> Result<Option<GameMessage>, UserError> {
+    Ok(users.career(name)?.map(|stats| GameMessage::CareerStats {
+        name: name.to_string(),
+        stats: CareerCard {
+            rounds_played: stats.rounds_played,
+            landlord_defense_rate: stats.landlord_defense_rate(),
+            average_points_captured: stats.average_points_captured(),
+            biggest_throw_forfeited: stats.biggest_throw_forfeited,
+            rating: stats.rating,
+        },
+    }))
+}
+
+/// Runs `operation` against the room's game without changing it. Returns the
+/// error which was sent to the user, if it failed.
 pub async fn execute_immutable_operation<S, E, F>(
     ws_id: usize,
     room_name: &str,
     backend_storage: S,
     operation: F,
     action_description: &'static str,
-) -> bool
+) -> Option<String>
 where
     S: Storage<VersionedGame, E>,
     E: Send,
@@ -66,38 +97,35 @@ where
                         room_name: versioned_game.room_name,
                         monotonic_id: versioned_game.monotonic_id,
                         associated_websockets: versioned_game.associated_websockets,
+                        replay: versioned_game.replay,
+                        bans: versioned_game.bans,
+                        events: versioned_game.events,
                     },
                     msgs,
                 ))
             },
         )
         .await;
-    match res {
-        Ok(_) => true,
-        Err(EitherError::E(_)) => {
-            let err = GameMessage::Error(format!("Failed to {action_description}"));
-            let _ = backend_storage
-                .publish_to_single_subscriber(room_name_, ws_id, err)
-                .await;
-            false
-        }
-        Err(EitherError::E2(msg)) => {
-            let err = GameMessage::Error(format!("Failed to {action_description}: {msg}"));
-            let _ = backend_storage
-                .publish_to_single_subscriber(room_name_, ws_id, err)
-                .await;
-            false
-        }
-    }
+    let err = match res {
+        Ok(_) => return None,
+        Err(EitherError::E(_)) => format!("Failed to {action_description}"),
+        Err(EitherError::E2(msg)) => format!("Failed to {action_description}: {msg}"),
+    };
+    let _ = backend_storage
+        .publish_to_single_subscriber(room_name_, ws_id, GameMessage::Error(err.clone()))
+        .await;
+    Some(err)
 }
 
+/// Runs `operation` against the room's game and saves the result. Returns
+/// the error which was sent to the user, if it failed.
 pub async fn execute_operation<S, E, F>(
     ws_id: usize,
     room_name: &str,
     backend_storage: S,
     operation: F,
     action_description: &'static str,
-) -> bool
+) -> Option<String>
 where
     S: Storage<VersionedGame, E>,
     E: Send,
@@ -105,6 +133,8 @@ where
             &mut InteractiveGame,
             u64,
             &mut HashMap<PlayerID, Vec<usize>>,
+            &mut Option<Replay>,
+            &mut HashSet<String>,
         ) -> Result<Vec<GameMessage>, anyhow::Error>
         + Send
         + 'static,
@@ -116,15 +146,35 @@ where
         .execute_operation_with_messages::<EitherError<E>, _>(
             room_name_.clone(),
             move |versioned_game| {
+                let before = versioned_game.game.clone();
                 let mut g = InteractiveGame::new_from_state(versioned_game.game);
                 let mut associated_websockets = versioned_game.associated_websockets;
+                let mut replay = versioned_game.replay;
+                let mut bans = versioned_game.bans;
                 let mut msgs = operation(
                     &mut g,
                     versioned_game.monotonic_id,
                     &mut associated_websockets,
+                    &mut replay,
+                    &mut bans,
                 )
                 .map_err(EitherError::E2)?;
+                let mut events = versioned_game.events;
+                events.record(&before, &g);
                 let game = g.into_state();
+                let notifications = Notification::between(
+                    &before,
+                    &game,
+                    msgs.iter().filter_map(|m| match m {
+                        GameMessage::Broadcast { data, .. } => Some(data.variant()),
+                        _ => None,
+                    }),
+                );
+                msgs.extend(
+                    notifications
+                        .into_iter()
+                        .map(|notification| GameMessage::Notification { notification }),
+                );
                 msgs.push(GameMessage::State {
                     state: game.clone(),
                 });
@@ -134,29 +184,24 @@ where
                         game,
                         associated_websockets,
                         monotonic_id: versioned_game.monotonic_id + 1,
+                        replay,
+                        bans,
+                        events,
                     },
                     msgs,
                 ))
             },
         )
         .await;
-    match res {
-        Ok(_) => true,
-        Err(EitherError::E(_)) => {
-            let err = GameMessage::Error(format!("Failed to {action_description}"));
-            let _ = backend_storage
-                .publish_to_single_subscriber(room_name_, ws_id, err)
-                .await;
-            false
-        }
-        Err(EitherError::E2(msg)) => {
-            let err = GameMessage::Error(format!("Failed to {action_description}: {msg}"));
-            let _ = backend_storage
-                .publish_to_single_subscriber(room_name_, ws_id, err)
-                .await;
-            false
-        }
-    }
+    let err = match res {
+        Ok(_) => return None,
+        Err(EitherError::E(_)) => format!("Failed to {action_description}"),
+        Err(EitherError::E2(msg)) => format!("Failed to {action_description}: {msg}"),
+    };
+    let _ = backend_storage
+        .publish_to_single_subscriber(room_name_, ws_id, GameMessage::Error(err.clone()))
+        .await;
+    Some(err)
 }
 
 enum EitherError<E> {
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use hyper::client::connect::dns::{GaiResolver, Name};
+use hyper::{client::HttpConnector, service::Service, Body, Client, Method, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use slog::{debug, error, info, o, Logger};
+use tokio::sync::{broadcast, mpsc};
+
+use shengji_core::game_state::play_phase::PlayerGameFinishedResult;
+use storage::{SqliteStorage, Storage};
+
+use crate::{
+    events::{RoomEvent, RoomEvents},
+    serving_types::VersionedGame,
+    utils::now_ms,
+};
+
+const MAX_ATTEMPTS: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const QUEUE_SIZE: usize = 1024;
+
+/// The JSON body posted to each webhook.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum WebhookPayload {
+    GameStarted {
+        room_name: String,
+        timestamp_ms: u64,
+        players: Vec<String>,
+    },
+    GameFinished {
+        room_name: String,
+        timestamp_ms: u64,
+        results: HashMap<String, PlayerGameFinishedResult>,
+    },
+    MatchComplete {
+        room_name: String,
+        timestamp_ms: u64,
+        winners: Vec<String>,
+    },
+}
+
+type WebhookClient = Client<HttpsConnector<HttpConnector<Resolver>>>;
+
+struct Delivery {
+    url: String,
+    body: Vec<u8>,
+    /// Room webhooks are set by whoever hosts the room, so they can only be
+    /// delivered to public addresses. The operator's URLs can be anywhere.
+    public_only: bool,
+}
+
+/// Posts room lifecycle events to the room's own webhook, if the host set
+/// one, and to every URL in `WEBHOOK_URLS` (comma-separated).
+///
+/// Deliveries are queued and retried with exponential backoff. Room webhooks
+/// are only delivered to public addresses, so that hosts can't use the server
+/// to reach its private network.
+pub async fn deliver_webhooks(
+    logger: Logger,
+    backend_storage: SqliteStorage<VersionedGame>,
+    events: RoomEvents,
+) {
+    let operator_urls = std::env::var("WEBHOOK_URLS")
+        .map(|urls| {
+            urls.split(',')
+                .map(|u| u.trim().to_string())
+                .filter(|u| !u.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+    tokio::task::spawn(send_deliveries(logger.clone(), rx));
+
+    let mut events_rx = events.subscribe();
+    loop {
+        let event = match events_rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                error!(logger, "Dropped room events"; "num_events" => n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let room_name = match &event {
+            RoomEvent::GameStarted { room_name }
+            | RoomEvent::GameFinished { room_name, .. }
+            | RoomEvent::MatchComplete { room_name, .. } => room_name.clone(),
+            RoomEvent::TurnChanged { .. } => continue,
+        };
+        let game = match backend_storage
+            .clone()
+            .get(room_name.as_bytes().to_vec())
+            .await
+        {
+            Ok(versioned_game) => versioned_game.game,
+            Err(_) => {
+                error!(logger, "Failed to load room for webhook"; "room" => &room_name);
+                continue;
+            }
+        };
+        let urls = operator_urls
+            .iter()
+            .map(|u| (u.clone(), false))
+            .chain(game.webhook_url().map(|u| (u.to_string(), true)))
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            continue;
+        }
+
+        let timestamp_ms = now_ms();
+        let payload = match event {
+            RoomEvent::GameStarted { .. } => WebhookPayload::GameStarted {
+                room_name,
+                timestamp_ms,
+                players: game.players().iter().map(|p| p.name.clone()).collect(),
+            },
+            RoomEvent::GameFinished { results, .. } => WebhookPayload::GameFinished {
+                room_name,
+                timestamp_ms,
+                results,
+            },
+            RoomEvent::MatchComplete { winners, .. } => WebhookPayload::MatchComplete {
+                room_name,
+                timestamp_ms,
+                winners: winners
+                    .into_iter()
+                    .flat_map(|id| game.player_name(id).ok().map(|n| n.to_string()))
+                    .collect(),
+            },
+            RoomEvent::TurnChanged { .. } => continue,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!(logger, "Failed to serialize webhook"; "error" => format!("{e:?}"));
+                continue;
+            }
+        };
+        for (url, public_only) in urls {
+            let delivery = Delivery {
+                url,
+                body: body.clone(),
+                public_only,
+            };
+            if tx.try_send(delivery).is_err() {
+                error!(logger, "Webhook queue is full, dropping delivery");
+            }
+        }
+    }
+}
+
+async fn send_deliveries(logger: Logger, mut rx: mpsc::Receiver<Delivery>) {
+    let client = webhook_client(false);
+    let public_client = webhook_client(true);
+    while let Some(delivery) = rx.recv().await {
+        let logger = logger.new(o!("url" => delivery.url.clone()));
+        let uri = match delivery.url.parse::<Uri>() {
+            Ok(uri) if matches!(uri.scheme_str(), Some("http" | "https")) => uri,
+            _ => {
+                error!(
+                    logger,
+                    "Only http:// and https:// webhooks can be delivered"
+                );
+                continue;
+            }
+        };
+        // Addresses written into the URL aren't looked up, so the resolver
+        // never sees them.
+        let host = uri.host().unwrap_or_default();
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        if delivery.public_only && literal.parse().is_ok_and(|ip| !is_public(ip)) {
+            error!(logger, "Webhooks can't be delivered to private addresses");
+            continue;
+        }
+        let client = if delivery.public_only {
+            public_client.clone()
+        } else {
+            client.clone()
+        };
+        tokio::task::spawn(deliver_with_retries(logger, client, delivery));
+    }
+}
+
+fn webhook_client(public_only: bool) -> WebhookClient {
+    let mut http = HttpConnector::new_with_resolver(Resolver {
+        inner: GaiResolver::new(),
+        public_only,
+    });
+    http.enforce_http(false);
+    let https = HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(http);
+    Client::builder().build(https)
+}
+
+/// Looks up hosts like hyper's default resolver, but can leave out anything
+/// that isn't a public address. Filtering the addresses which are actually
+/// connected to means a host can't resolve to somewhere else after it's been
+/// checked.
+#[derive(Clone)]
+struct Resolver {
+    inner: GaiResolver,
+    public_only: bool,
+}
+
+impl Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let lookup = self.inner.call(name);
+        let public_only = self.public_only;
+        Box::pin(async move {
+            let addrs = lookup
+                .await?
+                .filter(|addr| !public_only || is_public(addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err("host has no public addresses".into());
+            }
+            Ok(addrs.into_iter())
+        })
+    }
+}
+
+/// Whether `ip` is reachable on the internet, rather than being loopback,
+/// link-local, private or otherwise reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space for carrier-grade NAT.
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+async fn deliver_with_retries(logger: Logger, client: WebhookClient, delivery: Delivery) {
+    let mut backoff = Duration::from_secs(1);
+    for attempt in 1..=MAX_ATTEMPTS {
+        match deliver(&client, &delivery).await {
+            Ok(()) => {
+                debug!(logger, "Delivered webhook"; "attempt" => attempt);
+                return;
+            }
+            Err(e) => {
+                info!(logger, "Failed to deliver webhook"; "attempt" => attempt, "error" => e);
+            }
+        }
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+    error!(logger, "Gave up delivering webhook"; "attempts" => MAX_ATTEMPTS);
+}
+
+async fn deliver(client: &WebhookClient, delivery: &Delivery) -> Result<(), String> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(&delivery.url)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .header(http::header::USER_AGENT, "shengji-webhooks")
+        .body(Body::from(delivery.body.clone()))
+        .map_err(|e| e.to_string())?;
+    let response = tokio::time::timeout(REQUEST_TIMEOUT, client.request(request))
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("status {}", response.status()))
+    }
+}
@@ -0,0 +1,74 @@
+use std::env;
+
+use shengji_types::{ZSTD_DICTS, ZSTD_ZSTD_DICT};
+
+/// The largest dictionary that the server is willing to decompress.
+const MAX_DICT_SIZE: usize = 112_640;
+/// Dictionary IDs outside of this range are reserved by zstd.
+const MIN_DICT_ID: u32 = 32_768;
+const MAX_DICT_ID: u32 = (1 << 31) - 1;
+const DICT_MAGIC: [u8; 4] = [0x37, 0xa4, 0x30, 0xec];
+
+/// Trains a new zstd dictionary from the traffic recorded by a server running
+/// with `TRAFFIC_SAMPLE_PATH` set, and writes it out compressed, ready to be
+/// added to `ZSTD_DICTS`.
+///
+/// Usage: shengji-zstd-dict <samples> <dictionary ID> <output>
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() != 4 {
+        eprintln!("usage: {} <samples> <dictionary ID> <output>", args[0]);
+        std::process::exit(1);
+    }
+    let samples = read_samples(&std::fs::read(&args[1]).unwrap());
+    let dict_id: u32 = args[2].parse().unwrap();
+    assert!(
+        (MIN_DICT_ID..=MAX_DICT_ID).contains(&dict_id),
+        "dictionary IDs must be between {MIN_DICT_ID} and {MAX_DICT_ID}"
+    );
+    assert!(
+        ZSTD_DICTS.iter().all(|(id, _)| *id != dict_id),
+        "dictionary ID {dict_id} is already in use"
+    );
+
+    let mut dict = zstd::dict::from_samples(&samples, MAX_DICT_SIZE).unwrap();
+    // The ID is the version of the dictionary, so we pick it rather than
+    // letting zstd pick one at random.
+    assert_eq!(dict[..4], DICT_MAGIC);
+    dict[4..8].copy_from_slice(&dict_id.to_le_bytes());
+
+    let legacy = zstd::bulk::decompress(ZSTD_ZSTD_DICT, MAX_DICT_SIZE).unwrap();
+    println!(
+        "{} samples, {} bytes: {} bytes with the original dictionary, {} bytes with the new one",
+        samples.len(),
+        samples.iter().map(|s| s.len()).sum::<usize>(),
+        compressed_size(&samples, &legacy),
+        compressed_size(&samples, &dict),
+    );
+
+    std::fs::write(&args[3], zstd::bulk::compress(&dict, 19).unwrap()).unwrap();
+}
+
+/// Splits up samples which were written as their length, as a little-endian
+/// `u32`, followed by the sample itself.
+fn read_samples(mut data: &[u8]) -> Vec<Vec<u8>> {
+    let mut samples = vec![];
+    while data.len() >= 4 {
+        let len = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+        if data.len() < 4 + len {
+            // The server was probably stopped while writing this sample.
+            break;
+        }
+        samples.push(data[4..4 + len].to_vec());
+        data = &data[4 + len..];
+    }
+    samples
+}
+
+fn compressed_size(samples: &[Vec<u8>], dict: &[u8]) -> usize {
+    let mut compressor = zstd::bulk::Compressor::with_dictionary(0, dict).unwrap();
+    samples
+        .iter()
+        .map(|s| compressor.compress(s).unwrap().len())
+        .sum()
+}
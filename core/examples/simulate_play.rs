@@ -199,6 +199,7 @@ fn main() {
                                     ),
                                     format.iter().cloned(),
                                     s.propagated().trick_draw_policy(),
+                                    trick_format.tractor_requirements(),
                                 );
 
                                 playable.next().map(|u| {
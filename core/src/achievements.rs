@@ -0,0 +1,189 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::types::{Number, Rank};
+
+use crate::game_log::{GameLog, GameLogEvent};
+
+/// A milestone which a player can reach over the course of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum Achievement {
+    /// Won a game in which they led five or more cards at once.
+    FiveCardThrow,
+    /// Won a game on the landlord's team at rank A.
+    DefendedOnAce,
+    /// Was on the landlord's team when it captured 200 or more points.
+    DefendersCaptured200,
+}
+
+impl Achievement {
+    /// The key the achievement is stored under.
+    pub fn key(self) -> &'static str {
+        match self {
+            Achievement::FiveCardThrow => "five_card_throw",
+            Achievement::DefendedOnAce => "defended_on_ace",
+            Achievement::DefendersCaptured200 => "defenders_captured_200",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::FiveCardThrow => "won a round with a 5-card throw",
+            Achievement::DefendedOnAce => "defended on A",
+            Achievement::DefendersCaptured200 => "captured 200+ points as defenders",
+        }
+    }
+
+    /// The achievements reached in a finished game, by player name. Returns
+    /// nothing if the game isn't over yet.
+    pub fn reached_in(log: &GameLog) -> Vec<(String, Achievement)> {
+        let result = match &log.result {
+            Some(result) => result,
+            None => return vec![],
+        };
+        let won = |name: &str| result.players.get(name).map(|r| r.won_game) == Some(true);
+        let mut reached = vec![];
+
+        // Failed throws are recorded as the cards which were actually
+        // played, so any lead of five cards succeeded.
+        let mut num_played = 0;
+        let mut big_leads = vec![];
+        for event in &log.events {
+            match event {
+                GameLogEvent::Played { player, cards, .. } => {
+                    if num_played == 0 && cards.len() >= 5 {
+                        big_leads.push(*player);
+                    }
+                    num_played += 1;
+                }
+                GameLogEvent::TookBackPlay { player, .. } => {
+                    num_played -= 1;
+                    if num_played == 0 {
+                        big_leads.retain(|p| p != player);
+                    }
+                }
+                GameLogEvent::TrickWon { .. } => num_played = 0,
+                _ => (),
+            }
+        }
+        for player in log.settings.players() {
+            if big_leads.contains(&player.id) && won(&player.name) {
+                reached.push((player.name.clone(), Achievement::FiveCardThrow));
+            }
+        }
+
+        let defenders_points = result
+            .players
+            .values()
+            .filter(|r| r.is_defending)
+            .map(|r| r.points_captured)
+            .sum::<usize>();
+        let mut names = result.players.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let r = &result.players[name];
+            if r.is_defending && r.won_game && r.rank == Rank::Number(Number::Ace) {
+                reached.push((name.clone(), Achievement::DefendedOnAce));
+            }
+            if r.is_defending && defenders_points >= 200 {
+                reached.push((name.clone(), Achievement::DefendersCaptured200));
+            }
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shengji_mechanics::types::{cards::*, Card, Number, PlayerID, Rank, Trump};
+
+    use crate::game_log::{GameLog, GameLogEvent, GameLogResult};
+    use crate::game_state::initialize_phase::InitializePhase;
+    use crate::game_state::play_phase::PlayerGameFinishedResult;
+
+    use super::Achievement;
+
+    fn result(is_defending: bool, points_captured: usize) -> PlayerGameFinishedResult {
+        PlayerGameFinishedResult {
+            won_game: is_defending,
+            is_defending,
+            is_landlord: false,
+            ranks_up: 0,
+            confetti: false,
+            rank: Rank::Number(Number::Ace),
+            points_captured,
+            largest_failed_throw: 0,
+        }
+    }
+
+    fn played(player: PlayerID, cards: &[Card]) -> GameLogEvent {
+        GameLogEvent::Played {
+            timestamp_ms: 0,
+            player,
+            cards: cards.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_achievements() {
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+
+        let mut log = GameLog {
+            started_at_ms: 0,
+            started_by: p1,
+            settings: init.propagated().clone(),
+            events: vec![
+                played(p1, &[S_3, S_4, S_5, S_6, S_8]),
+                played(p2, &[S_9, S_10, S_J, S_Q, S_K]),
+                played(p3, &[S_9, S_10, S_J, S_Q, S_K]),
+                GameLogEvent::TrickWon {
+                    timestamp_ms: 0,
+                    winner: p3,
+                    points: 20,
+                },
+                played(p3, &[H_3, H_4, H_5, H_6, H_8]),
+                GameLogEvent::TookBackPlay {
+                    timestamp_ms: 0,
+                    player: p3,
+                },
+                played(p3, &[H_3]),
+            ],
+            deal: None,
+            result: None,
+        };
+        assert!(Achievement::reached_in(&log).is_empty());
+
+        log.result = Some(GameLogResult {
+            landlord: p1,
+            landlords_team: vec![p1, p3],
+            trump: Trump::NoTrump {
+                number: Some(Number::Ace),
+            },
+            non_landlords_points: 0,
+            kitty: vec![],
+            players: vec![
+                ("p1".to_string(), result(true, 0)),
+                ("p2".to_string(), result(false, 0)),
+                ("p3".to_string(), result(true, 200)),
+            ]
+            .into_iter()
+            .collect(),
+            scoring: vec![],
+        });
+        // Following with five cards doesn't count, nor does a lead which was
+        // taken back.
+        assert_eq!(
+            Achievement::reached_in(&log),
+            vec![
+                ("p1".to_string(), Achievement::FiveCardThrow),
+                ("p1".to_string(), Achievement::DefendedOnAce),
+                ("p1".to_string(), Achievement::DefendersCaptured200),
+                ("p3".to_string(), Achievement::DefendedOnAce),
+                ("p3".to_string(), Achievement::DefendersCaptured200),
+            ]
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use shengji_mechanics::bidding::Bid;
+use shengji_mechanics::types::{Card, EffectiveSuit, PlayerID, Trump, FULL_DECK};
+
+use crate::game_state::draw_phase::DrawPhase;
+use crate::game_state::exchange_phase::ExchangePhase;
+use crate::game_state::play_phase::PlayPhase;
+use crate::game_state::GameState;
+use crate::interactive::Action;
+use crate::settings::{FriendSelection, GameMode};
+
+/// Following a trick with more legal plays than this falls back to the
+/// lowest legal play, rather than listing all of them.
+const MAX_FOLLOWS: usize = 1000;
+
+/// A strategy for a player whose moves are made by the server. Each decision
+/// is made from what the player can see of the game.
+pub trait Bot {
+    /// The bid to make, if any, while cards are being drawn or once they've
+    /// all been drawn.
+    fn bid(&self, phase: &DrawPhase, id: PlayerID) -> Option<Bid>;
+
+    /// The cards to bury in the kitty, chosen from the exchanger's hand and
+    /// the kitty they picked up.
+    fn kitty(&self, phase: &ExchangePhase, id: PlayerID) -> Vec<Card>;
+
+    /// The friends to call, when the landlord still needs to pick them.
+    fn friends(&self, phase: &ExchangePhase, id: PlayerID) -> Option<Vec<FriendSelection>> {
+        choose_friends(phase, id)
+    }
+
+    /// The cards to play, when it's `id`'s turn.
+    fn play(&self, phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>>;
+
+    /// The actions `id` would like to take in `state`, most preferred first.
+    /// The first one which turns out to be legal is taken.
+    fn actions(&self, state: &GameState, id: PlayerID) -> Vec<Action> {
+        let mut actions = vec![];
+        // Votes which wait on every player would otherwise never finish.
+        if let Some(vote) = state.undo_vote() {
+            if vote.requester != id && !vote.approvals.contains(&id) {
+                actions.push(Action::VoteOnUndo(true));
+            }
+        }
+        if let Some(proposal) = state.settings_proposal() {
+            if proposal.proposer != id && !proposal.approvals.contains(&id) {
+                actions.push(Action::VoteOnSettings(true));
+            }
+        }
+        if let Some(pause) = state.pause() {
+            // Leave it to the people at the table to decide when to resume.
+            if !pause.resume_votes.is_empty() && !pause.resume_votes.contains(&id) {
+                actions.push(Action::VoteToResume);
+            }
+            return actions;
+        }
+        let next_player = state.next_player().ok();
+        match state.for_player(id) {
+            GameState::Initialize(_) => (),
+            GameState::Draw(p) => {
+                if let Some(bid) = self.bid(&p, id) {
+                    actions.push(Action::Bid(bid.card, bid.count));
+                }
+                if next_player == Some(id) {
+                    if !p.deck().is_empty() {
+                        actions.push(Action::DrawCard);
+                    } else if !p.bids().is_empty() {
+                        actions.push(Action::PickUpKitty);
+                    }
+                }
+            }
+            GameState::Exchange(p) => {
+                if p.exchanger() == id && !p.finalized() {
+                    let mut to_hand = Card::count(p.kitty().iter().copied());
+                    let mut to_kitty = HashMap::new();
+                    for card in self.kitty(&p, id) {
+                        match to_hand.get_mut(&card) {
+                            Some(n) if *n > 0 => *n -= 1,
+                            _ => *to_kitty.entry(card).or_insert(0) += 1,
+                        }
+                    }
+                    if let Some(card) = Card::cards(to_hand.iter()).next() {
+                        actions.push(Action::MoveCardToHand(*card));
+                    }
+                    if let Some(card) = Card::cards(to_kitty.iter()).next() {
+                        actions.push(Action::MoveCardToKitty(*card));
+                    }
+                    actions.push(Action::PutDownKitty);
+                }
+                if p.landlord() == id {
+                    if let Some(friends) = self.friends(&p, id) {
+                        actions.push(Action::SetFriends(friends));
+                    }
+                    actions.push(Action::BeginPlay);
+                }
+            }
+            GameState::Play(p) if p.game_finished() => (),
+            GameState::Play(p) => match next_player {
+                Some(next) if next == id => {
+                    if let Some(cards) = self.play(&p, id) {
+                        actions.push(Action::PlayCards(cards));
+                    }
+                }
+                Some(_) => (),
+                // Bots clear away the tricks they win.
+                None => {
+                    if p.trick().complete().ok().map(|t| t.winner) == Some(id) {
+                        actions.push(Action::EndTrick);
+                    }
+                }
+            },
+        }
+        actions
+    }
+}
+
+/// Makes the simplest legal move available: it bids only if nobody else
+/// has, keeps the kitty it was dealt, and plays its lowest cards.
+pub struct SimpleBot;
+
+impl Bot for SimpleBot {
+    fn bid(&self, phase: &DrawPhase, id: PlayerID) -> Option<Bid> {
+        if !phase.deck().is_empty() || !phase.bids().is_empty() {
+            return None;
+        }
+        phase
+            .valid_bids(id)
+            .unwrap_or_default()
+            .into_iter()
+            .max_by_key(|b| b.count)
+    }
+
+    fn kitty(&self, phase: &ExchangePhase, _: PlayerID) -> Vec<Card> {
+        phase.kitty().to_vec()
+    }
+
+    fn play(&self, phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>> {
+        phase.lowest_legal_play(id).ok()
+    }
+}
+
+/// Plays each trick on its own merits: it bids in its longest suit as soon
+/// as it can, buries its least valuable cards, leads its highest off-suit
+/// cards, and follows with the cheapest play which wins the trick. When its
+/// team is already winning, it gives the trick as many points as it can.
+pub struct GreedyBot;
+
+impl Bot for GreedyBot {
+    fn bid(&self, phase: &DrawPhase, id: PlayerID) -> Option<Bid> {
+        if phase.bids().last().map(|b| b.id) == Some(id) {
+            return None;
+        }
+        let hand = Card::cards(phase.hands().get(id).ok()?.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        phase
+            .valid_bids(id)
+            .unwrap_or_default()
+            .into_iter()
+            .max_by_key(|b| {
+                let suit_length = hand
+                    .iter()
+                    .filter(|c| c.suit().is_some() && c.suit() == b.card.suit())
+                    .count();
+                (b.count, suit_length)
+            })
+    }
+
+    fn kitty(&self, phase: &ExchangePhase, id: PlayerID) -> Vec<Card> {
+        let trump = phase.trump();
+        let mut cards = exchanger_cards(phase, id);
+        cards.sort_by_key(|c| {
+            (
+                bury_forbidden(phase, *c),
+                trump.effective_suit(*c) == EffectiveSuit::Trump,
+                c.points().unwrap_or(0),
+                card_value(trump, *c),
+            )
+        });
+        cards.truncate(phase.kitty_size());
+        cards
+    }
+
+    fn play(&self, phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>> {
+        if phase.trick().trick_format().is_none() {
+            return greedy_lead(phase, id);
+        }
+        let follows = match legal_follows(phase, id) {
+            Some(follows) if !follows.is_empty() => follows,
+            _ => return phase.lowest_legal_play(id).ok(),
+        };
+        let trump = phase.trump();
+        let points = |cards: &[Card]| cards.iter().flat_map(|c| c.points()).sum::<usize>();
+        let value = |cards: &[Card]| cards.iter().map(|c| card_value(trump, *c)).sum::<usize>();
+
+        let team = phase.landlords_team();
+        let team_winning = phase
+            .trick()
+            .current_winner()
+            .map(|w| team.contains(&w) == team.contains(&id))
+            .unwrap_or(false);
+        if team_winning {
+            return follows
+                .into_iter()
+                .max_by_key(|f| (points(f), std::cmp::Reverse(value(f))));
+        }
+        let wins = |cards: &[Card]| {
+            let mut p = phase.clone();
+            p.play_cards(id, cards).is_ok() && p.trick().current_winner() == Some(id)
+        };
+        let (winning, losing): (Vec<_>, Vec<_>) = follows.into_iter().partition(|f| wins(f));
+        if winning.is_empty() {
+            losing.into_iter().min_by_key(|f| (points(f), value(f)))
+        } else {
+            winning.into_iter().min_by_key(|f| value(f))
+        }
+    }
+}
+
+/// Picks uniformly among its legal moves. Its choices only depend on the
+/// seed and what it can see of the game, so a game played by random bots can
+/// be reproduced.
+pub struct RandomBot {
+    seed: u64,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Bot for RandomBot {
+    fn bid(&self, phase: &DrawPhase, id: PlayerID) -> Option<Bid> {
+        if phase.bids().last().map(|b| b.id) == Some(id) {
+            return None;
+        }
+        let hand = sorted_hand(phase.hands().get(id).ok()?);
+        let mut rng = seeded_rng(
+            self.seed,
+            (id, hand, phase.deck().len(), phase.bids().len()),
+        );
+        let bids = phase.valid_bids(id).unwrap_or_default();
+        // Someone has to bid once all of the cards have been drawn.
+        let must_bid = phase.deck().is_empty() && phase.bids().is_empty();
+        if must_bid || rng.gen_bool(0.25) {
+            bids.choose(&mut rng).cloned()
+        } else {
+            None
+        }
+    }
+
+    fn kitty(&self, phase: &ExchangePhase, id: PlayerID) -> Vec<Card> {
+        let mut cards = exchanger_cards(phase, id);
+        cards.sort_by_key(|c| c.as_char());
+        // The cards on offer don't change during the exchange, so this picks
+        // the same kitty every time it's asked.
+        let mut rng = seeded_rng(self.seed, (id, &cards));
+        cards.shuffle(&mut rng);
+        cards.sort_by_key(|c| bury_forbidden(phase, *c));
+        cards.truncate(phase.kitty_size());
+        cards
+    }
+
+    fn play(&self, phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>> {
+        let hand = phase.hands().get(id).ok()?;
+        let played = phase
+            .trick()
+            .played_cards()
+            .iter()
+            .map(|p| (p.id, p.cards.clone()))
+            .collect::<Vec<_>>();
+        let mut rng = seeded_rng(self.seed, (id, sorted_hand(hand), played));
+        if phase.trick().trick_format().is_none() {
+            let (card, count) = hand
+                .iter()
+                .filter(|(_, n)| **n > 0)
+                .collect::<Vec<_>>()
+                .choose(&mut rng)
+                .copied()?;
+            return Some(vec![*card; rng.gen_range(1..=*count)]);
+        }
+        match legal_follows(phase, id) {
+            Some(follows) if !follows.is_empty() => follows.choose(&mut rng).cloned(),
+            _ => phase.lowest_legal_play(id).ok(),
+        }
+    }
+}
+
+/// Leads every copy of the best off-suit card, or of the weakest trump if
+/// there's nothing else.
+pub(crate) fn greedy_lead(phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>> {
+    let trump = phase.trump();
+    let (card, count) = phase
+        .hands()
+        .get(id)
+        .ok()?
+        .iter()
+        .filter(|(_, n)| **n > 0)
+        .max_by_key(|(c, n)| {
+            let is_trump = trump.effective_suit(**c) == EffectiveSuit::Trump;
+            let value = card_value(trump, **c) as isize;
+            (!is_trump, if is_trump { -value } else { value }, **n)
+        })?;
+    Some(vec![*card; *count])
+}
+
+/// A random number generator which only depends on `seed` and `context`, so
+/// that bots make the same choice every time they're asked about the same
+/// situation.
+pub(crate) fn seeded_rng(seed: u64, context: impl Hash) -> StdRng {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    context.hash(&mut hasher);
+    StdRng::seed_from_u64(hasher.finish())
+}
+
+/// Every legal way to follow the current trick, unless there are too many to
+/// list quickly.
+pub(crate) fn legal_follows(phase: &PlayPhase, id: PlayerID) -> Option<Vec<Vec<Card>>> {
+    let tf = phase.trick().trick_format()?;
+    let hand = phase.hands().get(id).ok()?;
+    let in_suit = hand
+        .iter()
+        .filter(|(c, _)| tf.trump().effective_suit(**c) == tf.suit())
+        .map(|(_, n)| *n)
+        .sum::<usize>();
+    let total = hand.values().sum::<usize>();
+    // Treating copies of a card as distinct overestimates the number of
+    // plays, which is fine for a limit.
+    let num_plays = if in_suit >= tf.size() {
+        binomial(in_suit, tf.size())
+    } else {
+        binomial(total - in_suit, tf.size() - in_suit)
+    };
+    if num_plays > MAX_FOLLOWS {
+        return None;
+    }
+    Some(phase.legal_follows(id).ok()?.collect())
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1usize, |acc, i| acc.saturating_mul(n - i) / (i + 1))
+}
+
+/// The cards in the exchanger's hand and the kitty, which together are the
+/// cards they can choose to bury.
+fn exchanger_cards(phase: &ExchangePhase, id: PlayerID) -> Vec<Card> {
+    let mut cards = phase.kitty().to_vec();
+    if let Ok(hand) = phase.hands().get(id) {
+        cards.extend(Card::cards(hand.iter()).copied());
+    }
+    cards
+}
+
+fn bury_forbidden(phase: &ExchangePhase, card: Card) -> bool {
+    let policy = phase.propagated().kitty_bury_policy();
+    (policy.forbids_trump() && phase.trump().effective_suit(card) == EffectiveSuit::Trump)
+        || (policy.forbids_points() && card.points().is_some())
+}
+
+/// How valuable a card is to hold on to: any trump is worth more than any
+/// off-suit card, and otherwise cards are ranked within their suit.
+fn card_value(trump: Trump, card: Card) -> usize {
+    let suit = trump.effective_suit(card);
+    let rank = FULL_DECK
+        .iter()
+        .filter(|c| trump.effective_suit(**c) == suit && trump.compare(**c, card) == Ordering::Less)
+        .count();
+    if suit == EffectiveSuit::Trump {
+        FULL_DECK.len() + rank
+    } else {
+        rank
+    }
+}
+
+pub(crate) fn sorted_hand(hand: &HashMap<Card, usize>) -> Vec<Card> {
+    let mut cards = Card::cards(hand.iter()).copied().collect::<Vec<_>>();
+    cards.sort_by_key(|c| c.as_char());
+    cards
+}
+
+/// Picks the highest cards which are allowed as friends, if the landlord
+/// still needs to pick them.
+fn choose_friends(p: &ExchangePhase, id: PlayerID) -> Option<Vec<FriendSelection>> {
+    let num_friends = match p.game_mode() {
+        GameMode::FindingFriends {
+            num_friends,
+            friends,
+        } if friends.len() != *num_friends => *num_friends,
+        _ => return None,
+    };
+    let trump = p.trump();
+    let mut candidates = FULL_DECK
+        .iter()
+        .copied()
+        .filter(|c| trump.effective_suit(*c) != EffectiveSuit::Trump)
+        .map(|card| FriendSelection {
+            card,
+            initial_skip: 0,
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|f| std::cmp::Reverse(f.card.number()));
+    candidates.windows(num_friends).find_map(|friends| {
+        let mut p = p.clone();
+        p.set_friends(id, friends.iter().copied()).ok()?;
+        Some(friends.to_vec())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use crate::game_state::{initialize_phase::InitializePhase, GameState};
+    use crate::interactive::{Action, InteractiveGame};
+    use crate::settings::GameModeSettings;
+
+    use super::{Bot, GreedyBot, RandomBot, SimpleBot};
+
+    #[test]
+    fn test_bots_play_a_game() {
+        let logger = Logger::root(Discard, o!());
+        let random = RandomBot::new(3);
+        let strategies: [&dyn Bot; 3] = [&SimpleBot, &GreedyBot, &random];
+        let game_modes = [
+            GameModeSettings::Tractor,
+            GameModeSettings::FindingFriends { num_friends: None },
+        ];
+        for (game_mode, strategy) in game_modes
+            .iter()
+            .flat_map(|m| strategies.iter().map(move |s| (*m, *s)))
+        {
+            let mut init = InitializePhase::new();
+            let host = init.add_player("host".into()).unwrap().0;
+            init.make_observer(host).unwrap();
+            let bots = (0..4)
+                .map(|_| init.add_bot().unwrap().0)
+                .collect::<Vec<_>>();
+            assert_eq!(init.host(), Some(host));
+            init.set_game_mode(game_mode).unwrap();
+
+            let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+            game.interact_with_seed(Action::StartGame, bots[0], 42, &logger)
+                .unwrap();
+            let mut replay = None;
+            while game.act_for_bots(strategy, &mut replay, 0, &logger).is_ok() {}
+            match game.dump_state().unwrap() {
+                GameState::Play(p) => assert!(p.game_finished()),
+                _ => panic!("Expected the bots to finish the game"),
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::hands::Hands;
+use shengji_mechanics::scoring::{explain_level_deltas, GameScoreResult};
+use shengji_mechanics::types::{Card, PlayerID, Trump};
+
+use crate::game_state::play_phase::{PlayPhase, PlayerGameFinishedResult};
+use crate::game_state::GameState;
+use crate::interactive::{Action, InteractiveGame};
+use crate::message::MessageVariant;
+use crate::replay::{quiet, Replay};
+use crate::settings::PropagatedState;
+
+/// Something which happened over the course of a game, as recorded in a
+/// `GameLog`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum GameLogEvent {
+    Bid {
+        timestamp_ms: u64,
+        player: PlayerID,
+        card: Card,
+        count: usize,
+    },
+    TookBackBid {
+        timestamp_ms: u64,
+        player: PlayerID,
+    },
+    Played {
+        timestamp_ms: u64,
+        player: PlayerID,
+        cards: Vec<Card>,
+    },
+    ThrowFailed {
+        timestamp_ms: u64,
+        player: PlayerID,
+        original_cards: Vec<Card>,
+        better_player: Option<PlayerID>,
+    },
+    TookBackPlay {
+        timestamp_ms: u64,
+        player: PlayerID,
+    },
+    TrickWon {
+        timestamp_ms: u64,
+        winner: PlayerID,
+        points: usize,
+    },
+    Undone {
+        timestamp_ms: u64,
+        player: PlayerID,
+    },
+}
+
+/// The cards each player was dealt, before the exchange.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GameLogDeal {
+    pub hands: HashMap<PlayerID, Vec<Card>>,
+    pub kitty: Vec<Card>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GameLogResult {
+    pub landlord: PlayerID,
+    pub landlords_team: Vec<PlayerID>,
+    pub trump: Trump,
+    pub non_landlords_points: isize,
+    /// The cards the landlord put down during the exchange.
+    pub kitty: Vec<Card>,
+    /// How the game turned out for each player, by name.
+    pub players: HashMap<String, PlayerGameFinishedResult>,
+    /// The levels won by each team at each point threshold, as used to
+    /// score the game.
+    pub scoring: Vec<(isize, GameScoreResult)>,
+}
+
+/// A structured record of a single game, meant to be downloaded once the
+/// game is over.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GameLog {
+    pub started_at_ms: u64,
+    pub started_by: PlayerID,
+    /// The room's settings and players when the game was started.
+    pub settings: PropagatedState,
+    pub events: Vec<GameLogEvent>,
+    /// Only included once the game is over, since it reveals every hand.
+    pub deal: Option<GameLogDeal>,
+    pub result: Option<GameLogResult>,
+}
+
+impl GameLog {
+    /// Plays back the replay to reconstruct what happened in the game.
+    pub fn from_replay(replay: &Replay) -> Result<Self, Error> {
+        let logger = quiet();
+        let mut game = InteractiveGame::new_from_state(replay.initial_state.clone());
+        game.interact_with_seed(Action::StartGame, replay.started_by, replay.seed, &logger)?;
+
+        let mut events = vec![];
+        let mut deal = None;
+        let mut final_play = None;
+        let mut players = None;
+        for entry in &replay.entries {
+            if let (Action::StartNewGame, GameState::Play(p)) = (&entry.action, game.state()) {
+                final_play = Some(p.clone());
+            }
+            let msgs = game.interact(entry.action.clone(), entry.actor, &logger)?;
+            let timestamp_ms = entry.timestamp_ms;
+            for (msg, _) in msgs {
+                let player = msg.actor();
+                events.extend(match msg.variant() {
+                    MessageVariant::MadeBid { card, count } => Some(GameLogEvent::Bid {
+                        timestamp_ms,
+                        player,
+                        card: *card,
+                        count: *count,
+                    }),
+                    MessageVariant::TookBackBid => Some(GameLogEvent::TookBackBid {
+                        timestamp_ms,
+                        player,
+                    }),
+                    MessageVariant::PlayedCards { cards } => Some(GameLogEvent::Played {
+                        timestamp_ms,
+                        player,
+                        cards: cards.clone(),
+                    }),
+                    MessageVariant::ThrowFailed {
+                        original_cards,
+                        better_player,
+                    } => Some(GameLogEvent::ThrowFailed {
+                        timestamp_ms,
+                        player,
+                        original_cards: original_cards.clone(),
+                        better_player: *better_player,
+                    }),
+                    MessageVariant::TookBackPlay => Some(GameLogEvent::TookBackPlay {
+                        timestamp_ms,
+                        player,
+                    }),
+                    MessageVariant::TrickWon { winner, points } => Some(GameLogEvent::TrickWon {
+                        timestamp_ms,
+                        winner: *winner,
+                        points: *points,
+                    }),
+                    MessageVariant::UndoApproved { requester } => Some(GameLogEvent::Undone {
+                        timestamp_ms,
+                        player: *requester,
+                    }),
+                    MessageVariant::GameFinished { result } => {
+                        players = Some(result.clone());
+                        None
+                    }
+                    _ => None,
+                });
+            }
+            if let (None, GameState::Exchange(e)) = (&deal, game.state()) {
+                deal = Some(GameLogDeal {
+                    hands: dealt_hands(game.state(), e.hands())?,
+                    kitty: e.kitty().to_vec(),
+                });
+            }
+        }
+
+        let finished = matches!(game.state(), GameState::Initialize(_));
+        let result = match (final_play, players) {
+            (Some(p), Some(players)) if finished => Some(Self::result(&p, players)?),
+            _ => None,
+        };
+        Ok(GameLog {
+            started_at_ms: replay.started_at_ms,
+            started_by: replay.started_by,
+            settings: replay.initial_state.propagated().clone(),
+            events,
+            deal: if finished { deal } else { None },
+            result,
+        })
+    }
+
+    fn result(
+        p: &PlayPhase,
+        players: HashMap<String, PlayerGameFinishedResult>,
+    ) -> Result<GameLogResult, Error> {
+        let (non_landlords_points, _) = p.calculate_points();
+        Ok(GameLogResult {
+            landlord: p.landlord(),
+            landlords_team: p.landlords_team().to_vec(),
+            trump: p.trump(),
+            non_landlords_points,
+            kitty: p.kitty().to_vec(),
+            players,
+            scoring: explain_level_deltas(
+                &p.propagated().game_scoring_parameters,
+                p.decks(),
+                p.landlord_team_size(),
+            )?,
+        })
+    }
+}
+
+fn dealt_hands(state: &GameState, hands: &Hands) -> Result<HashMap<PlayerID, Vec<Card>>, Error> {
+    state
+        .players()
+        .iter()
+        .map(|p| Ok((p.id, hands._get_cards(p.id)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use shengji_mechanics::types::PlayerID;
+
+    use crate::game_state::GameState;
+    use crate::interactive::{Action, InteractiveGame};
+    use crate::replay::Replay;
+
+    use super::{GameLog, GameLogEvent};
+
+    /// Has whichever player can take the action take it.
+    fn act(
+        game: &mut InteractiveGame,
+        replay: &mut Option<Replay>,
+        players: &[PlayerID],
+        action: Action,
+    ) -> bool {
+        let logger = Logger::root(Discard, o!());
+        players.iter().any(|p| {
+            game.interact_recorded(action.clone(), *p, replay, 0, &logger)
+                .is_ok()
+        })
+    }
+
+    #[test]
+    fn test_game_log() {
+        let mut game = InteractiveGame::new();
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut replay = None;
+
+        assert!(act(&mut game, &mut replay, &players, Action::StartGame));
+        while act(&mut game, &mut replay, &players, Action::DrawCard) {}
+        // Nobody bid, so the trump comes from the bottom.
+        while !act(&mut game, &mut replay, &players, Action::PickUpKitty) {
+            assert!(act(&mut game, &mut replay, &players, Action::RevealCard));
+        }
+        // Partway through, the hands aren't revealed yet.
+        let log = GameLog::from_replay(replay.as_ref().unwrap()).unwrap();
+        assert!(log.deal.is_none() && log.result.is_none());
+
+        assert!(act(&mut game, &mut replay, &players, Action::PutDownKitty));
+        assert!(act(&mut game, &mut replay, &players, Action::BeginPlay));
+        loop {
+            let action = match game.dump_state().unwrap() {
+                GameState::Play(p) if p.game_finished() => break,
+                GameState::Play(p) => match p.next_player() {
+                    Ok(next) => Action::PlayCards(p.lowest_legal_play(next).unwrap()),
+                    Err(_) => Action::EndTrick,
+                },
+                _ => panic!("Expected to be playing"),
+            };
+            assert!(act(&mut game, &mut replay, &players, action));
+        }
+        assert!(act(&mut game, &mut replay, &players, Action::StartNewGame));
+
+        let log = GameLog::from_replay(replay.as_ref().unwrap()).unwrap();
+        let deal = log.deal.as_ref().unwrap();
+        assert_eq!(deal.hands.len(), 4);
+        assert_eq!(
+            deal.hands.values().map(|h| h.len()).sum::<usize>() + deal.kitty.len(),
+            108
+        );
+        let count = |f: fn(&GameLogEvent) -> bool| log.events.iter().filter(|e| f(e)).count();
+        let plays = count(|e| matches!(e, GameLogEvent::Played { .. }));
+        let tricks = count(|e| matches!(e, GameLogEvent::TrickWon { .. }));
+        assert_eq!(plays, tricks * 4);
+        let result = log.result.as_ref().unwrap();
+        assert_eq!(result.players.len(), 4);
+        assert!(!result.scoring.is_empty());
+    }
+}
@@ -1,14 +1,19 @@
+use std::collections::HashMap;
+
 use anyhow::{anyhow, bail, Error};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use shengji_mechanics::bidding::Bid;
+use shengji_mechanics::bidding::{Bid, BidPolicy};
 use shengji_mechanics::deck::Deck;
 use shengji_mechanics::hands::Hands;
 use shengji_mechanics::types::{Card, PlayerID, Rank, Trump};
 
 use crate::message::MessageVariant;
-use crate::settings::{FirstLandlordSelectionPolicy, GameMode, KittyBidPolicy, PropagatedState};
+use crate::settings::{
+    FirstDealLandlordPolicy, FirstLandlordSelectionPolicy, GameMode, KittyBidPolicy,
+    PropagatedState,
+};
 
 use crate::game_state::exchange_phase::ExchangePhase;
 use crate::game_state::initialize_phase::InitializePhase;
@@ -24,9 +29,22 @@ pub struct DrawPhase {
     #[serde(default)]
     autobid: Option<Bid>,
     position: usize,
+    /// The player who becomes the landlord if nobody bids and there's no
+    /// landlord yet. This is the player who drew first, unless someone else
+    /// drew the card picked by the first-deal landlord policy.
+    #[serde(default)]
+    dealer: Option<PlayerID>,
+    /// The card which made the dealer the landlord-in-waiting, if the
+    /// landlord is picked by the first player to draw a specific card.
+    #[serde(default)]
+    dealer_card: Option<Card>,
     kitty: Vec<Card>,
     #[serde(default)]
     revealed_cards: usize,
+    /// How many cards were left in the deck when the last bid was made. A
+    /// bid can only be taken back until the next card is drawn.
+    #[serde(default)]
+    last_bid_deck_len: Option<usize>,
     level: Option<Rank>,
     #[serde(default)]
     removed_cards: Vec<Card>,
@@ -50,6 +68,8 @@ impl DrawPhase {
     ) -> Self {
         DrawPhase {
             hands: Hands::new(propagated.players.iter().map(|p| p.id)),
+            dealer: propagated.players.get(position).map(|p| p.id),
+            dealer_card: None,
             deck,
             kitty,
             propagated,
@@ -61,6 +81,7 @@ impl DrawPhase {
             removed_cards,
             bids: Vec::new(),
             revealed_cards: 0,
+            last_bid_deck_len: None,
             autobid: None,
             player_requested_reset: None,
         }
@@ -82,6 +103,50 @@ impl DrawPhase {
         &self.deck
     }
 
+    pub fn decks(&self) -> &[Deck] {
+        &self.decks
+    }
+
+    pub fn hands(&self) -> &Hands {
+        &self.hands
+    }
+
+    pub fn bids(&self) -> &[Bid] {
+        &self.bids
+    }
+
+    /// Who would become the landlord if drawing finished now, if anyone.
+    pub fn landlord(&self) -> Option<PlayerID> {
+        if self.propagated.landlord.is_some() {
+            return self.propagated.landlord;
+        }
+        let (first_bid, winning_bid) = Bid::first_and_winner(&self.bids, self.autobid).ok()?;
+        Some(match self.propagated.first_landlord_selection_policy {
+            FirstLandlordSelectionPolicy::ByWinningBid => winning_bid.id,
+            FirstLandlordSelectionPolicy::ByFirstBid => first_bid.id,
+        })
+    }
+
+    /// The bids which `id` could make with the cards they've drawn so far.
+    pub fn valid_bids(&self, id: PlayerID) -> Result<Vec<Bid>, Error> {
+        if self.revealed_cards > 0 {
+            return Ok(vec![]);
+        }
+        Bid::valid_bids(
+            id,
+            &self.bids,
+            &self.hands,
+            &self.propagated.players,
+            self.propagated.landlord,
+            0,
+            self.propagated.bid_policy,
+            self.propagated.bid_reinforcement_policy,
+            self.propagated.joker_bid_policy,
+            self.propagated.no_trump_only_from,
+            self.num_decks,
+        )
+    }
+
     pub fn kitty(&self) -> &[Card] {
         &self.kitty
     }
@@ -101,6 +166,65 @@ impl DrawPhase {
         &mut self.kitty
     }
 
+    /// Rearranges the deck so that each player draws the given cards and the
+    /// kitty holds the given ones, e.g. to set up a tutorial. The rest of the
+    /// cards are dealt in the order they were shuffled.
+    pub fn arrange_deal(
+        &mut self,
+        hands: &HashMap<PlayerID, Vec<Card>>,
+        kitty: &[Card],
+    ) -> Result<(), Error> {
+        let num_players = self.propagated.players.len();
+        if !self.hands.is_empty() || !self.deck.len().is_multiple_of(num_players) {
+            bail!("The deal can only be arranged before anyone draws");
+        }
+        let hand_size = self.deck.len() / num_players;
+        if kitty.len() > self.kitty.len() {
+            bail!("The kitty only has {} cards", self.kitty.len());
+        }
+
+        let mut remaining = self.deck.clone();
+        remaining.extend(self.kitty.iter().copied());
+        let mut take = |card: Card| match remaining.iter().position(|c| *c == card) {
+            Some(idx) => {
+                remaining.remove(idx);
+                Ok(card)
+            }
+            None => Err(anyhow!("There aren't enough {:?} to deal", card)),
+        };
+        let mut new_kitty = kitty
+            .iter()
+            .map(|c| take(*c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut dealt = vec![vec![]; num_players];
+        for (id, cards) in hands {
+            let idx = match self.propagated.players.iter().position(|p| p.id == *id) {
+                Some(idx) => idx,
+                None => bail!("Only players can be dealt cards"),
+            };
+            if cards.len() > hand_size {
+                bail!("Each player is only dealt {} cards", hand_size);
+            }
+            dealt[idx] = cards.iter().map(|c| take(*c)).collect::<Result<_, _>>()?;
+        }
+
+        let mut remaining = remaining.into_iter();
+        for hand in &mut dealt {
+            hand.extend(remaining.by_ref().take(hand_size - hand.len()));
+        }
+        new_kitty.extend(remaining);
+
+        // Cards are drawn from the end of the deck, starting from the
+        // current position.
+        let mut deck = (0..hand_size * num_players)
+            .map(|i| dealt[(self.position + i) % num_players].pop().unwrap())
+            .collect::<Vec<_>>();
+        deck.reverse();
+        self.deck = deck;
+        self.kitty = new_kitty;
+        Ok(())
+    }
+
     pub fn add_observer(&mut self, name: String) -> Result<PlayerID, Error> {
         self.propagated.add_observer(name)
     }
@@ -125,14 +249,28 @@ impl DrawPhase {
         }
     }
 
-    pub fn draw_card(&mut self, id: PlayerID) -> Result<(), Error> {
+    pub fn draw_card(&mut self, id: PlayerID) -> Result<Vec<MessageVariant>, Error> {
         if id != self.propagated.players[self.position].id {
             bail!("not your turn!");
         }
         if let Some(next_card) = self.deck.pop() {
             self.hands.add(id, Some(next_card))?;
             self.position = (self.position + 1) % self.propagated.players.len();
-            Ok(())
+
+            let mut msgs = vec![];
+            if let FirstDealLandlordPolicy::FirstToDrawCard { card } =
+                self.propagated.first_deal_landlord_policy
+            {
+                if self.propagated.landlord.is_none()
+                    && self.dealer_card.is_none()
+                    && next_card == card
+                {
+                    self.dealer = Some(id);
+                    self.dealer_card = Some(card);
+                    msgs.push(MessageVariant::FirstDealLandlordSelected { player: id, card });
+                }
+            }
+            Ok(msgs)
         } else {
             bail!("no cards left in deck")
         }
@@ -148,6 +286,7 @@ impl DrawPhase {
         let id = self
             .propagated
             .landlord
+            .or(self.dealer)
             .ok_or_else(|| anyhow!("can't reveal card if landlord hasn't been selected yet"))?;
 
         let landlord_level = self
@@ -195,6 +334,17 @@ impl DrawPhase {
                     epoch: 0,
                 });
             }
+            KittyBidPolicy::FirstSuitedCard
+                if !card.is_joker() || self.revealed_cards >= self.kitty.len() - 1 =>
+            {
+                // If every card in the bottom is a joker, play no-trump.
+                self.autobid = Some(Bid {
+                    count: 1,
+                    id,
+                    card,
+                    epoch: 0,
+                });
+            }
             KittyBidPolicy::FirstCardOfLevelOrHighest
                 if self.revealed_cards >= self.kitty.len() - 1 =>
             {
@@ -228,7 +378,11 @@ impl DrawPhase {
         if self.revealed_cards > 0 {
             return false;
         }
-        Bid::bid(
+        // Bidding closes as soon as the first bid lands.
+        if self.propagated.bid_policy == BidPolicy::FirstBidWins && !self.bids.is_empty() {
+            return false;
+        }
+        let valid = Bid::bid(
             id,
             card,
             count,
@@ -240,13 +394,26 @@ impl DrawPhase {
             self.propagated.bid_policy,
             self.propagated.bid_reinforcement_policy,
             self.propagated.joker_bid_policy,
+            self.propagated.no_trump_only_from,
             self.num_decks,
             0,
-        )
+        );
+        if valid {
+            self.last_bid_deck_len = Some(self.deck.len());
+        }
+        valid
     }
 
     pub fn take_back_bid(&mut self, id: PlayerID) -> Result<(), Error> {
-        Bid::take_back_bid(id, self.propagated.bid_takeback_policy, &mut self.bids, 0)
+        let mut bids = self.bids.clone();
+        Bid::take_back_bid(id, self.propagated.bid_takeback_policy, &mut bids, 0)?;
+        if self.last_bid_deck_len != Some(self.deck.len()) {
+            bail!("Can't take back a bid once the next card has been drawn");
+        }
+        self.bids = bids;
+        // The bid before it may have been made several cards ago.
+        self.last_bid_deck_len = None;
+        Ok(())
     }
 
     pub fn done_drawing(&self) -> bool {
@@ -289,15 +456,24 @@ impl DrawPhase {
                 // repeated in some. It's OK because the bid calculation is
                 // fast.
                 let (_, winning_bid) = Bid::first_and_winner(&self.bids, self.autobid)?;
+                let no_trump_only = Bid::is_no_trump_only(
+                    Some(Rank::Number(landlord_level)),
+                    self.propagated.no_trump_only_from,
+                );
                 match winning_bid.card {
                     Card::Unknown => bail!("can't bid with unknown cards!"),
-                    Card::SmallJoker | Card::BigJoker => Trump::NoTrump {
-                        number: Some(landlord_level),
-                    },
-                    Card::Suited { suit, .. } => Trump::Standard {
+                    card if self.propagated.jokers_only_trump_policy.applies(card) => {
+                        Trump::NoTrump { number: None }
+                    }
+                    Card::Suited { suit, .. } if !no_trump_only => Trump::Standard {
                         suit,
                         number: landlord_level,
                     },
+                    // Cards revealed from the bottom can still be suited, but
+                    // the game is played in no-trump regardless.
+                    Card::SmallJoker | Card::BigJoker | Card::Suited { .. } => Trump::NoTrump {
+                        number: Some(landlord_level),
+                    },
                 }
             }
         };
@@ -3,11 +3,12 @@ use std::collections::HashSet;
 use anyhow::{anyhow, bail, Error};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use shengji_mechanics::bidding::Bid;
 use shengji_mechanics::deck::Deck;
 use shengji_mechanics::hands::Hands;
-use shengji_mechanics::types::{Card, Number, PlayerID, Rank, Trump};
+use shengji_mechanics::types::{Card, EffectiveSuit, Number, PlayerID, Rank, Trump};
 
 use crate::message::MessageVariant;
 use crate::settings::{
@@ -25,6 +26,18 @@ macro_rules! bail_unwrap {
     };
 }
 
+/// Reasons a card may not be buried in the bottom under the room's
+/// `KittyBuryPolicy`.
+#[derive(Error, Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub enum BuryError {
+    #[error("point cards can't be buried in the bottom ({})", _0.as_char())]
+    PointCard(Card),
+    #[error("trump cards can't be buried in the bottom ({})", _0.as_char())]
+    TrumpCard(Card),
+    #[error("at most {max} points can be buried in the bottom, not {attempted}")]
+    TooManyPoints { attempted: usize, max: usize },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExchangePhase {
     propagated: PropagatedState,
@@ -102,10 +115,75 @@ impl ExchangePhase {
             bail!("cards already finalized")
         }
         self.hands.remove(self.exchanger, Some(card))?;
+        if let Err(e) = self.check_bury(card) {
+            self.hands.add(self.exchanger, Some(card))?;
+            return Err(e.into());
+        }
         self.kitty.push(card);
+        // Cards without points are always let in, since they might be
+        // replacing point cards which were dealt into the bottom.
+        if card.points().is_some() {
+            if let Err(e) = self.check_kitty_points() {
+                self.kitty.pop();
+                self.hands.add(self.exchanger, Some(card))?;
+                return Err(e.into());
+            }
+        }
         Ok(())
     }
 
+    fn bury_error(&self, card: Card) -> Option<BuryError> {
+        let policy = self.propagated.kitty_bury_policy;
+        if policy.forbids_trump() && self.trump.effective_suit(card) == EffectiveSuit::Trump {
+            Some(BuryError::TrumpCard(card))
+        } else if policy.forbids_points() && card.points().is_some() {
+            Some(BuryError::PointCard(card))
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether the card may be buried, given the exchanger's current
+    /// hand. Restricted cards are only allowed once the exchanger has no
+    /// other cards left to bury.
+    fn check_bury(&self, card: Card) -> Result<(), BuryError> {
+        match self.bury_error(card) {
+            Some(e) => {
+                let has_alternative = self
+                    .hands
+                    .get(self.exchanger)
+                    .map(|hand| hand.keys().any(|c| self.bury_error(*c).is_none()))
+                    .unwrap_or(false);
+                if has_alternative {
+                    Err(e)
+                } else {
+                    Ok(())
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the points in the bottom against the room's limit, unless the
+    /// exchanger has no cards without points left to swap in.
+    fn check_kitty_points(&self) -> Result<(), BuryError> {
+        let max = match self.propagated.max_kitty_points {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        let attempted = self.kitty.iter().flat_map(|c| c.points()).sum::<usize>();
+        let has_alternative = self
+            .hands
+            .get(self.exchanger)
+            .map(|hand| hand.keys().any(|c| c.points().is_none()))
+            .unwrap_or(false);
+        if attempted > max && has_alternative {
+            Err(BuryError::TooManyPoints { attempted, max })
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn move_card_to_hand(&mut self, id: PlayerID, card: Card) -> Result<(), Error> {
         if self.exchanger != id {
             bail!("not the exchanger")
@@ -149,8 +227,10 @@ impl ExchangePhase {
 
             friends.clear();
 
+            let restrictions = self.propagated.friend_selection_restrictions;
             for friend in friend_set.iter() {
                 if FriendSelectionPolicy::TrumpsIncluded != self.propagated.friend_selection_policy
+                    || restrictions.no_trumps
                 {
                     if friend.card.is_joker() || friend.card.number() == self.trump.number() {
                         if let Some(n) = self.trump.number() {
@@ -163,9 +243,28 @@ impl ExchangePhase {
                         bail!("you can't pick a trump suit as your friend")
                     }
                 }
-                if friend.initial_skip >= self.num_decks {
+                let num_copies = if self.decks.is_empty() {
+                    self.num_decks
+                } else {
+                    self.decks
+                        .iter()
+                        .filter(|d| d.includes_card(friend.card))
+                        .count()
+                };
+                if friend.initial_skip >= num_copies {
                     bail!("need to pick a card that exists!")
                 }
+                if let Some(max_occurrence) = restrictions.max_occurrence {
+                    if friend.initial_skip >= max_occurrence {
+                        bail!(
+                            "you can only pick up to copy #{} of a card as your friend",
+                            max_occurrence
+                        )
+                    }
+                }
+                if restrictions.no_point_cards && friend.card.points().is_some() {
+                    bail!("you can't pick a point card as your friend");
+                }
 
                 if let FriendSelectionPolicy::HighestCardNotAllowed =
                     self.propagated.friend_selection_policy
@@ -223,6 +322,10 @@ impl ExchangePhase {
         if self.kitty.len() != self.kitty_size {
             bail!("incorrect number of cards in the bottom")
         }
+        for card in &self.kitty {
+            self.check_bury(*card)?;
+        }
+        self.check_kitty_points()?;
         self.finalized = true;
         Ok(())
     }
@@ -241,18 +344,28 @@ impl ExchangePhase {
         if id != winning_bid.id {
             bail!("Only the winner of the bid can pick up the cards")
         }
-        self.trump = match winning_bid.card {
-            Card::Unknown => bail!("can't bid with unknown cards!"),
-            Card::SmallJoker | Card::BigJoker => Trump::NoTrump {
-                number: self.trump.number(),
-            },
-            Card::Suited { suit, .. } => Trump::Standard {
-                suit,
-                number: self
-                    .trump
-                    .number()
-                    .expect("Shouldn't have trump number if there are bids"),
-            },
+        // The trump number may have been dropped if only jokers were trump,
+        // so look it up from the landlord's rank instead.
+        let number = match self
+            .propagated
+            .players
+            .iter()
+            .find(|p| p.id == self.landlord)
+            .map(|p| p.rank())
+        {
+            Some(Rank::Number(number)) => Some(number),
+            _ => None,
+        };
+        self.trump = match (winning_bid.card, number) {
+            (Card::Unknown, _) => bail!("can't bid with unknown cards!"),
+            (card, _) if self.propagated.jokers_only_trump_policy.applies(card) => {
+                Trump::NoTrump { number: None }
+            }
+            (Card::SmallJoker, _) | (Card::BigJoker, _) => Trump::NoTrump { number },
+            (Card::Suited { suit, .. }, Some(number)) => Trump::Standard { suit, number },
+            (Card::Suited { .. }, None) => {
+                bail!("can't bid a suit when the landlord's rank is no-trump")
+            }
         };
         self.finalized = false;
         self.epoch += 1;
@@ -277,6 +390,7 @@ impl ExchangePhase {
             self.propagated.bid_policy,
             self.propagated.bid_reinforcement_policy,
             self.propagated.joker_bid_policy,
+            self.propagated.no_trump_only_from,
             self.num_decks,
             self.epoch,
         )
@@ -301,10 +415,38 @@ impl ExchangePhase {
         self.landlord
     }
 
+    pub fn game_mode(&self) -> &GameMode {
+        &self.game_mode
+    }
+
     pub fn hands(&self) -> &Hands {
         &self.hands
     }
 
+    pub fn kitty(&self) -> &[Card] {
+        &self.kitty
+    }
+
+    pub fn kitty_size(&self) -> usize {
+        self.kitty_size
+    }
+
+    pub fn decks(&self) -> &[Deck] {
+        &self.decks
+    }
+
+    pub fn removed_cards(&self) -> &[Card] {
+        &self.removed_cards
+    }
+
+    pub fn exchanger(&self) -> PlayerID {
+        self.exchanger
+    }
+
+    pub fn finalized(&self) -> bool {
+        self.finalized
+    }
+
     pub fn trump(&self) -> Trump {
         self.trump
     }
@@ -335,6 +477,10 @@ impl ExchangePhase {
         if self.kitty.len() != self.kitty_size {
             bail!("incorrect number of cards in the bottom")
         }
+        for card in &self.kitty {
+            self.check_bury(*card)?;
+        }
+        self.check_kitty_points()?;
         if let GameMode::FindingFriends {
             num_friends,
             ref friends,
@@ -373,6 +519,9 @@ impl ExchangePhase {
                 .collect(),
             GameMode::FindingFriends { .. } => vec![self.landlord],
         };
+        let landlord_bid_size = Bid::first_and_winner(&self.bids, self.autobid)
+            .map(|(_, winning_bid)| winning_bid.count)
+            .unwrap_or(0);
 
         PlayPhase::new(
             self.propagated.clone(),
@@ -386,6 +535,7 @@ impl ExchangePhase {
             landlords_team,
             self.removed_cards.clone(),
             self.decks.clone(),
+            landlord_bid_size,
         )
     }
 
@@ -2,15 +2,19 @@ use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
 
 use anyhow::{anyhow, bail, Error};
-use rand::{seq::SliceRandom, RngCore};
+use rand::{rngs::StdRng, seq::SliceRandom, RngCore, SeedableRng};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use shengji_mechanics::types::{Card, Number, PlayerID, Rank, ALL_SUITS};
 
-use crate::settings::{GameMode, GameModeSettings, GameStartPolicy, PropagatedState};
+use crate::settings::{
+    FirstDealLandlordPolicy, GameMode, GameModeSettings, GameStartPolicy, PropagatedState,
+    MAX_DEAL_SEED,
+};
 
 use crate::game_state::DrawPhase;
+use crate::pace::PaceTracker;
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InitializePhase {
@@ -24,10 +28,46 @@ impl InitializePhase {
         }
     }
 
-    pub fn from_propagated(propagated: PropagatedState) -> Self {
+    pub fn from_propagated(mut propagated: PropagatedState) -> Self {
+        // Pauses, undos and proposed settings only apply to the game that
+        // was in progress.
+        propagated.pause = None;
+        propagated.undo_vote = None;
+        propagated.undo_point = None;
+        propagated.settings_proposal = None;
         Self { propagated }
     }
 
+    /// Creates a fresh room from settings which may have been exported from
+    /// another room, dropping anything specific to that room's players.
+    pub fn from_settings(mut settings: PropagatedState) -> Result<Self, Error> {
+        settings.players.clear();
+        settings.observers.clear();
+        settings.spectators.clear();
+        settings.host = None;
+        settings.bots.clear();
+        settings.coaches.clear();
+        settings.kick_vote = None;
+        settings.pause = None;
+        settings.undo_vote = None;
+        settings.undo_point = None;
+        settings.settings_proposal = None;
+        settings.landlord = None;
+        settings.num_games_finished = 0;
+        settings.num_matches_finished = 0;
+        settings.shuffle_seed = None;
+        settings.pace = PaceTracker::default();
+        if let FirstDealLandlordPolicy::AssignedByHost { .. } = settings.first_deal_landlord_policy
+        {
+            settings.first_deal_landlord_policy = FirstDealLandlordPolicy::Random;
+        }
+        let chat_link = settings.chat_link.take();
+        settings.set_chat_link(chat_link)?;
+        let deal_seed = settings.deal_seed.take();
+        settings.set_deal_seed(deal_seed)?;
+        Ok(Self::from_propagated(settings))
+    }
+
     pub fn propagated(&self) -> &PropagatedState {
         &self.propagated
     }
@@ -37,6 +77,13 @@ impl InitializePhase {
     }
 
     pub fn start(&self, id: PlayerID) -> Result<DrawPhase, Error> {
+        self.start_with_seed(id, rand::random())
+    }
+
+    /// Starts the game, shuffling the deck with the provided seed so that
+    /// the deal can be reproduced, unless the host fixed the deal.
+    pub fn start_with_seed(&self, id: PlayerID, seed: u64) -> Result<DrawPhase, Error> {
+        let seed = self.propagated.deal_seed.unwrap_or(seed & MAX_DEAL_SEED);
         if self.propagated.players.len() < 4 {
             bail!("not enough players")
         }
@@ -47,6 +94,30 @@ impl InitializePhase {
             bail!("Only the landlord can start the game")
         }
 
+        if self.propagated.fixed_partnerships {
+            let num_players = self.propagated.players.len();
+            if num_players != 4 && num_players != 6 {
+                bail!("fixed partnerships require four or six players")
+            }
+            let players = &self.propagated.players;
+            if players
+                .iter()
+                .zip(players.iter().skip(2))
+                .any(|(a, b)| a.rank() != b.rank())
+            {
+                bail!("partners must have the same rank")
+            }
+        }
+
+        if let Some(p) = self
+            .propagated
+            .players
+            .iter()
+            .find(|p| !self.propagated.rank_in_sequence(p.rank()))
+        {
+            bail!("{} isn't one of the ranks being played", p.rank().as_str())
+        }
+
         let game_mode = match self.propagated.game_mode {
             GameModeSettings::FindingFriends {
                 num_friends: Some(num_friends),
@@ -67,11 +138,19 @@ impl InitializePhase {
             }
         };
 
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let first_drawer = match self.propagated.first_deal_landlord_policy {
+            FirstDealLandlordPolicy::AssignedByHost { player } => Some(player),
+            FirstDealLandlordPolicy::Random | FirstDealLandlordPolicy::FirstToDrawCard { .. } => {
+                None
+            }
+        };
 
         let position = self
             .propagated
             .landlord
+            .or(first_drawer)
             .and_then(|landlord| {
                 self.propagated
                     .players
@@ -126,7 +205,7 @@ impl InitializePhase {
 
                 let min_number: Number = decks
                     .iter()
-                    .map(|d| d.min)
+                    .flat_map(|d| d.lowest_number())
                     .min()
                     .ok_or_else(|| anyhow!("no minimum value in deck?"))?;
 
@@ -136,7 +215,16 @@ impl InitializePhase {
                     Some(Rank::Number(level)) if level == min_number => {
                         // If the minimum value isn't an A, this will be reasonable, otherwise
                         // it'll remove a trump card from the deck...
-                        min_number.successor().unwrap_or(min_number)
+                        let mut n = min_number;
+                        loop {
+                            match n.successor() {
+                                Some(nn) if decks.iter().any(|d| d.includes_number(nn)) => {
+                                    break nn
+                                }
+                                Some(nn) => n = nn,
+                                None => break min_number,
+                            }
+                        }
                     }
                     Some(_) => min_number,
                     None => {
@@ -154,7 +242,9 @@ impl InitializePhase {
                         bad_levels.insert(Number::King);
                         let mut n = min_number;
                         loop {
-                            if !bad_levels.contains(&n) {
+                            if !bad_levels.contains(&n)
+                                && decks.iter().any(|d| d.includes_number(n))
+                            {
                                 break n;
                             }
                             n = match n.successor() {
@@ -169,6 +259,7 @@ impl InitializePhase {
                 };
 
                 let mut suit_idx = ALL_SUITS.len() - 1;
+                let mut num_misses = 0;
 
                 while deck.len() % num_players != size % num_players {
                     let card_to_remove = Card::Suited {
@@ -186,7 +277,11 @@ impl InitializePhase {
                         Some(idx) => {
                             deck.remove(idx);
                             removed_cards.push(card_to_remove);
+                            num_misses = 0;
                         }
+                        // Suits may have been removed from the deck, so keep
+                        // looking in the others.
+                        None if num_misses + 1 < ALL_SUITS.len() => num_misses += 1,
                         // Note: we would only hit this case if there are fewer decks than players,
                         // which should be prevented in the settings layer.
                         None => bail!(format!(
@@ -208,7 +303,9 @@ impl InitializePhase {
             }
         };
 
-        let propagated = self.propagated.clone();
+        let mut propagated = self.propagated.clone();
+        propagated.shuffle_seed = Some(seed);
+        propagated.pace = PaceTracker::default();
 
         Ok(DrawPhase::new(
             propagated,
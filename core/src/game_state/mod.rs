@@ -1,13 +1,16 @@
+use std::collections::HashMap;
 use std::ops::Deref;
 
 use anyhow::{bail, Error};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use shengji_mechanics::types::PlayerID;
+use shengji_mechanics::types::{Card, PlayerID};
 
 use crate::message::MessageVariant;
-use crate::settings::PropagatedState;
+use crate::pace::PaceTracker;
+use crate::settings::{Coaching, KickVote, Pause, PropagatedState, SettingsProposal, UndoVote};
+use crate::settings_diff::{check_mid_game_settings, SettingsPatch};
 
 pub mod draw_phase;
 pub mod exchange_phase;
@@ -17,7 +20,7 @@ pub mod play_phase;
 use draw_phase::DrawPhase;
 use exchange_phase::ExchangePhase;
 use initialize_phase::InitializePhase;
-use play_phase::PlayPhase;
+use play_phase::{CompletedTrick, PlayPhase};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -28,6 +31,24 @@ pub enum GameState {
     Play(PlayPhase),
 }
 
+/// A player's turn, as far as turn timers are concerned.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TimedTurn {
+    pub player: PlayerID,
+    pub secs: usize,
+    /// Advances as the turn moves on, so that consecutive turns taken by the
+    /// same player can be told apart.
+    pub step: usize,
+}
+
+/// The state from before a player's play or bid, which the game goes back
+/// to if everyone agrees to undo it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoPoint {
+    player: PlayerID,
+    state: Box<GameState>,
+}
+
 impl GameState {
     pub fn next_player(&self) -> Result<PlayerID, Error> {
         match self {
@@ -38,6 +59,36 @@ impl GameState {
         }
     }
 
+    /// The turn the room is waiting on, if the room has a turn timer for
+    /// the current phase.
+    pub fn timed_turn(&self) -> Option<TimedTurn> {
+        let timers = self.turn_timers;
+        let (secs, step) = match self {
+            GameState::Initialize(_) => return None,
+            GameState::Draw(p) => (timers.bid_secs?, p.deck().len()),
+            GameState::Exchange(_) => (timers.exchange_secs?, 0),
+            GameState::Play(p) => (timers.play_secs?, p.trick().played_cards().len()),
+        };
+        Some(TimedTurn {
+            player: self.next_player().ok()?,
+            secs,
+            step,
+        })
+    }
+
+    pub fn mark_idle(&mut self, id: PlayerID) {
+        let propagated = self.propagated_mut();
+        if !propagated.idle_players.contains(&id) {
+            propagated.idle_players.push(id);
+        }
+    }
+
+    pub fn mark_active(&mut self, id: PlayerID) {
+        if self.idle_players.contains(&id) {
+            self.propagated_mut().idle_players.retain(|p| *p != id);
+        }
+    }
+
     pub fn propagated(&self) -> &'_ PropagatedState {
         match self {
             GameState::Initialize(p) => p.propagated(),
@@ -47,6 +98,35 @@ impl GameState {
         }
     }
 
+    fn propagated_mut(&mut self) -> &'_ mut PropagatedState {
+        match self {
+            GameState::Initialize(p) => p.propagated_mut(),
+            GameState::Draw(p) => p.propagated_mut(),
+            GameState::Exchange(p) => p.propagated_mut(),
+            GameState::Play(p) => p.propagated_mut(),
+        }
+    }
+
+    /// The landlord of the game in progress, or of the next game if none
+    /// has started. While cards are being drawn, this is whoever is winning
+    /// the bidding.
+    pub fn current_landlord(&self) -> Option<PlayerID> {
+        match self {
+            GameState::Initialize(p) => p.landlord,
+            GameState::Draw(p) => p.landlord(),
+            GameState::Exchange(p) => Some(p.landlord()),
+            GameState::Play(p) => Some(p.landlord()),
+        }
+    }
+
+    pub(crate) fn pace_mut(&mut self) -> &mut PaceTracker {
+        &mut self.propagated_mut().pace
+    }
+
+    pub fn rename_players(&mut self, names: &HashMap<String, String>) -> Result<(), Error> {
+        self.propagated_mut().rename_players(names)
+    }
+
     pub fn is_player(&self, id: PlayerID) -> bool {
         self.propagated().players.iter().any(|p| p.id == id)
     }
@@ -57,7 +137,12 @@ impl GameState {
                 return Ok(&p.name);
             }
         }
-        for p in &self.propagated().observers {
+        for p in self
+            .propagated()
+            .observers
+            .iter()
+            .chain(&self.propagated().spectators)
+        {
             if p.id == id {
                 return Ok(&p.name);
             }
@@ -71,7 +156,12 @@ impl GameState {
                 return Ok(p.id);
             }
         }
-        for p in &self.propagated().observers {
+        for p in self
+            .propagated()
+            .observers
+            .iter()
+            .chain(&self.propagated().spectators)
+        {
             if p.name == name {
                 return Ok(p.id);
             }
@@ -81,6 +171,9 @@ impl GameState {
 
     pub fn register(&mut self, name: String) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
         if let Ok(pid) = self.player_id(&name) {
+            if self.is_spectator(pid) {
+                bail!("{} is spectating this game", name);
+            }
             return Ok((
                 pid,
                 vec![MessageVariant::JoinedGameAgain {
@@ -97,7 +190,55 @@ impl GameState {
         }
     }
 
+    /// Joins the game as a spectator, who can watch but never play.
+    pub fn register_spectator(
+        &mut self,
+        name: String,
+    ) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
+        if let Ok(pid) = self.player_id(&name) {
+            if !self.is_spectator(pid) {
+                bail!("{} is already playing in this game", name);
+            }
+            return Ok((
+                pid,
+                vec![MessageVariant::JoinedGameAgain {
+                    player: pid,
+                    game_shadowing_policy: self.game_shadowing_policy,
+                }],
+            ));
+        }
+        let id = self.propagated_mut().add_spectator(name)?;
+        Ok((id, vec![]))
+    }
+
+    /// Reattaches a returning player to their existing seat, regardless of
+    /// the name they joined with.
+    pub fn reattach(&self, id: PlayerID) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
+        self.player_name(id)?;
+        Ok((
+            id,
+            vec![MessageVariant::JoinedGameAgain {
+                player: id,
+                game_shadowing_policy: self.game_shadowing_policy,
+            }],
+        ))
+    }
+
     pub fn kick(&mut self, id: PlayerID) -> Result<Vec<MessageVariant>, Error> {
+        let propagated = self.propagated_mut();
+        if propagated.host == Some(id) {
+            propagated.host = None;
+        }
+        if propagated.kick_vote.as_ref().map(|v| v.target) == Some(id) {
+            propagated.kick_vote = None;
+        }
+        propagated.bots.retain(|b| *b != id);
+        propagated
+            .coaches
+            .retain(|c| c.coach != id && c.student != id);
+        if self.is_spectator(id) {
+            return self.propagated_mut().remove_spectator(id).map(|()| vec![]);
+        }
         match self {
             GameState::Initialize(ref mut p) => p.remove_player(id),
             GameState::Draw(ref mut p) => p.remove_observer(id).map(|()| vec![]),
@@ -106,6 +247,372 @@ impl GameState {
         }
     }
 
+    pub fn start_kick_vote(
+        &mut self,
+        actor: PlayerID,
+        target: PlayerID,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if actor == target {
+            bail!("You can't vote to kick yourself");
+        }
+        if let Some(vote) = self.propagated().kick_vote() {
+            bail!(
+                "There's already a vote to kick {}",
+                self.player_name(vote.target)?
+            );
+        }
+        self.player_name(target)?;
+        if self.is_player(target) && !matches!(self, GameState::Initialize(_)) {
+            bail!("Players can only be kicked before the game starts");
+        }
+        self.propagated_mut().kick_vote = Some(KickVote {
+            target,
+            approvals: vec![],
+            rejections: vec![],
+        });
+        let mut msgs = vec![MessageVariant::KickVoteStarted { target }];
+        msgs.extend(self.vote_on_kick(actor, true)?);
+        Ok(msgs)
+    }
+
+    /// Records a vote on the kick in progress. Everyone in the room apart
+    /// from spectators and the target gets a vote, and the kick goes ahead
+    /// once a majority of them approve.
+    pub fn vote_on_kick(
+        &mut self,
+        actor: PlayerID,
+        approve: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        let propagated = self.propagated();
+        let target = match propagated.kick_vote() {
+            Some(vote) => vote.target,
+            None => bail!("There's no vote to kick anyone"),
+        };
+        if actor == target {
+            bail!("You can't vote on your own kick");
+        }
+        let voters = propagated
+            .players
+            .iter()
+            .chain(propagated.observers.iter())
+            .map(|p| p.id)
+            .filter(|id| *id != target)
+            .collect::<Vec<_>>();
+        if !voters.contains(&actor) {
+            bail!("Only players and observers can vote");
+        }
+
+        let vote = self.propagated_mut().kick_vote.as_mut().unwrap();
+        vote.approvals.retain(|id| *id != actor);
+        vote.rejections.retain(|id| *id != actor);
+        if approve {
+            vote.approvals.push(actor);
+        } else {
+            vote.rejections.push(actor);
+        }
+        let approvals = vote
+            .approvals
+            .iter()
+            .filter(|id| voters.contains(id))
+            .count();
+        let rejections = vote
+            .rejections
+            .iter()
+            .filter(|id| voters.contains(id))
+            .count();
+
+        let mut msgs = vec![MessageVariant::KickVoteCast { approve }];
+        if approvals * 2 > voters.len() {
+            let name = self.player_name(target)?.to_owned();
+            self.propagated_mut().kick_vote = None;
+            msgs.push(MessageVariant::KickVotePassed { name });
+            msgs.extend(self.kick(target)?);
+        } else if rejections * 2 >= voters.len() {
+            self.propagated_mut().kick_vote = None;
+            msgs.push(MessageVariant::KickVoteFailed { target });
+        }
+        Ok(msgs)
+    }
+
+    pub fn request_to_coach(
+        &mut self,
+        coach: PlayerID,
+        student: PlayerID,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if !self.is_spectator(coach) {
+            bail!("Only spectators can coach");
+        }
+        if !self.is_player(student) {
+            bail!("Only players can be coached");
+        }
+        if self.coaches.iter().any(|c| c.coach == coach) {
+            bail!("You can only coach one player at a time");
+        }
+        self.propagated_mut().coaches.push(Coaching {
+            coach,
+            student,
+            approved: false,
+        });
+        Ok(vec![MessageVariant::CoachingRequested { student }])
+    }
+
+    /// Lets the coach see the student's hand, or stops them from seeing it.
+    /// Students can take back their approval at any time.
+    pub fn respond_to_coach(
+        &mut self,
+        student: PlayerID,
+        coach: PlayerID,
+        approve: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        let propagated = self.propagated_mut();
+        let coaching = match propagated
+            .coaches
+            .iter_mut()
+            .find(|c| c.coach == coach && c.student == student)
+        {
+            Some(coaching) => coaching,
+            None => bail!("They haven't asked to coach you"),
+        };
+        if approve {
+            coaching.approved = true;
+            Ok(vec![MessageVariant::CoachingStarted { coach }])
+        } else {
+            let approved = coaching.approved;
+            propagated
+                .coaches
+                .retain(|c| !(c.coach == coach && c.student == student));
+            Ok(vec![if approved {
+                MessageVariant::CoachingEnded { coach, student }
+            } else {
+                MessageVariant::CoachingDeclined { coach }
+            }])
+        }
+    }
+
+    pub fn stop_coaching(&mut self, coach: PlayerID) -> Result<Vec<MessageVariant>, Error> {
+        let student = match self.coaches.iter().find(|c| c.coach == coach) {
+            Some(c) => c.student,
+            None => bail!("You aren't coaching anyone"),
+        };
+        self.propagated_mut().coaches.retain(|c| c.coach != coach);
+        Ok(vec![MessageVariant::CoachingEnded { coach, student }])
+    }
+
+    pub fn request_pause(&mut self, actor: PlayerID) -> Result<Vec<MessageVariant>, Error> {
+        if let GameState::Initialize(_) = self {
+            bail!("Game has not started yet!");
+        }
+        if !self.is_player(actor) {
+            bail!("Only players can pause the game");
+        }
+        if self.pause().is_some() {
+            bail!("The game is already paused");
+        }
+        self.propagated_mut().pause = Some(Pause {
+            requested_by: actor,
+            resume_votes: vec![],
+        });
+        Ok(vec![MessageVariant::GamePaused])
+    }
+
+    /// Records a player's vote to resume the game, which resumes once a
+    /// majority of the players are ready.
+    pub fn vote_to_resume(&mut self, actor: PlayerID) -> Result<Vec<MessageVariant>, Error> {
+        if !self.is_player(actor) {
+            bail!("Only players can resume the game");
+        }
+        let players = self.players.iter().map(|p| p.id).collect::<Vec<_>>();
+        let pause = match self.propagated_mut().pause.as_mut() {
+            Some(pause) => pause,
+            None => bail!("The game isn't paused"),
+        };
+        if !pause.resume_votes.contains(&actor) {
+            pause.resume_votes.push(actor);
+        }
+        let votes = pause
+            .resume_votes
+            .iter()
+            .filter(|id| players.contains(id))
+            .count();
+
+        let mut msgs = vec![MessageVariant::ResumeVoteCast];
+        if votes * 2 > players.len() {
+            self.propagated_mut().pause = None;
+            msgs.push(MessageVariant::GameResumed);
+        }
+        Ok(msgs)
+    }
+
+    /// Records that `player` just moved the game along from `before`. Only
+    /// plays and bids can be undone, so anything else clears the undo point.
+    pub(crate) fn set_undo_point(&mut self, player: PlayerID, before: Option<GameState>) {
+        self.propagated_mut().undo_point = before.map(|mut state| {
+            let propagated = state.propagated_mut();
+            propagated.undo_vote = None;
+            propagated.undo_point = None;
+            UndoPoint {
+                player,
+                state: Box::new(state),
+            }
+        });
+    }
+
+    pub fn request_undo(&mut self, actor: PlayerID) -> Result<Vec<MessageVariant>, Error> {
+        if self.undo_vote().is_some() {
+            bail!("There's already a request to undo");
+        }
+        match self.undo_point {
+            Some(ref point) if point.player == actor => (),
+            _ => bail!("You can only undo your last play or bid, before anything else happens"),
+        }
+        self.propagated_mut().undo_vote = Some(UndoVote {
+            requester: actor,
+            approvals: vec![],
+        });
+        let mut msgs = vec![MessageVariant::UndoRequested];
+        msgs.extend(self.resolve_undo()?);
+        Ok(msgs)
+    }
+
+    /// Records a vote on the undo in progress. Every player other than the
+    /// requester has to approve; a single rejection cancels it.
+    pub fn vote_on_undo(
+        &mut self,
+        actor: PlayerID,
+        approve: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        let requester = match self.undo_vote() {
+            Some(vote) => vote.requester,
+            None => bail!("There's no request to undo"),
+        };
+        if actor == requester {
+            bail!("You can't vote on your own undo");
+        }
+        if !self.is_player(actor) {
+            bail!("Only players can vote");
+        }
+        let mut msgs = vec![MessageVariant::UndoVoteCast { approve }];
+        if approve {
+            let vote = self.propagated_mut().undo_vote.as_mut().unwrap();
+            if !vote.approvals.contains(&actor) {
+                vote.approvals.push(actor);
+            }
+            msgs.extend(self.resolve_undo()?);
+        } else {
+            self.propagated_mut().undo_vote = None;
+            msgs.push(MessageVariant::UndoRejected);
+        }
+        Ok(msgs)
+    }
+
+    /// Rolls the game back to the undo point once everyone has approved.
+    /// Everything outside of the game itself, like who is in the room, is
+    /// kept as it is now.
+    fn resolve_undo(&mut self) -> Result<Vec<MessageVariant>, Error> {
+        let vote = match self.undo_vote() {
+            Some(vote) => vote,
+            None => return Ok(vec![]),
+        };
+        let requester = vote.requester;
+        if self
+            .players
+            .iter()
+            .any(|p| p.id != requester && !vote.approvals.contains(&p.id))
+        {
+            return Ok(vec![]);
+        }
+        let mut propagated = self.propagated().clone();
+        let point = match propagated.undo_point.take() {
+            Some(point) => point,
+            None => bail!("There's nothing to undo"),
+        };
+        propagated.undo_vote = None;
+        let mut state = *point.state;
+        *state.propagated_mut() = propagated;
+        *self = state;
+        Ok(vec![MessageVariant::UndoApproved { requester }])
+    }
+
+    pub fn propose_settings(
+        &mut self,
+        actor: PlayerID,
+        patch: SettingsPatch,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if let GameState::Initialize(_) = self {
+            bail!("Settings can be changed directly before the game starts");
+        }
+        if !self.is_player(actor) {
+            bail!("Only players can propose new settings");
+        }
+        if self.settings_proposal().is_some() {
+            bail!("There's already a proposal to change the settings");
+        }
+        check_mid_game_settings(&patch)?;
+        let changes = self.propagated().clone().merge_settings(&patch)?;
+        if changes.is_empty() {
+            bail!("Those are already the settings");
+        }
+        self.propagated_mut().settings_proposal = Some(SettingsProposal {
+            proposer: actor,
+            patch,
+            approvals: vec![],
+        });
+        let mut msgs = vec![MessageVariant::SettingsProposed { changes }];
+        msgs.extend(self.resolve_settings_proposal()?);
+        Ok(msgs)
+    }
+
+    /// Records a vote on the proposed settings. Every player other than the
+    /// proposer has to approve; a single rejection cancels it.
+    pub fn vote_on_settings(
+        &mut self,
+        actor: PlayerID,
+        approve: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        let proposer = match self.settings_proposal() {
+            Some(proposal) => proposal.proposer,
+            None => bail!("There's no proposal to change the settings"),
+        };
+        if actor == proposer {
+            bail!("You can't vote on your own proposal");
+        }
+        if !self.is_player(actor) {
+            bail!("Only players can vote");
+        }
+        let mut msgs = vec![MessageVariant::SettingsProposalVoteCast { approve }];
+        if approve {
+            let proposal = self.propagated_mut().settings_proposal.as_mut().unwrap();
+            if !proposal.approvals.contains(&actor) {
+                proposal.approvals.push(actor);
+            }
+            msgs.extend(self.resolve_settings_proposal()?);
+        } else {
+            self.propagated_mut().settings_proposal = None;
+            msgs.push(MessageVariant::SettingsProposalRejected);
+        }
+        Ok(msgs)
+    }
+
+    /// Applies the proposed settings once everyone has approved.
+    fn resolve_settings_proposal(&mut self) -> Result<Vec<MessageVariant>, Error> {
+        let proposal = match self.settings_proposal() {
+            Some(proposal) => proposal,
+            None => return Ok(vec![]),
+        };
+        if self
+            .players
+            .iter()
+            .any(|p| p.id != proposal.proposer && !proposal.approvals.contains(&p.id))
+        {
+            return Ok(vec![]);
+        }
+        let patch = proposal.patch.clone();
+        let propagated = self.propagated_mut();
+        let changes = propagated.merge_settings(&patch)?;
+        propagated.settings_proposal = None;
+        Ok(vec![MessageVariant::SettingsProposalApproved { changes }])
+    }
+
     pub fn set_chat_link(&mut self, chat_link: Option<String>) -> Result<(), Error> {
         match self {
             GameState::Initialize(ref mut p) => p.propagated_mut().set_chat_link(chat_link),
@@ -164,22 +671,104 @@ impl GameState {
         Ok(vec![])
     }
 
+    /// Checks that every card which was dealt is accounted for exactly once:
+    /// in the deck, someone's hand, the kitty, or the cards which have been
+    /// played or removed. Games which didn't record their decks always pass.
+    pub fn check_cards_accounted_for(&self) -> Result<(), Error> {
+        let (decks, hands, mut cards) = match self {
+            GameState::Initialize(_) => return Ok(()),
+            GameState::Draw(p) => (
+                p.decks(),
+                p.hands(),
+                p.deck()
+                    .iter()
+                    .chain(p.kitty())
+                    .chain(p.removed_cards())
+                    .copied()
+                    .collect::<Vec<_>>(),
+            ),
+            GameState::Exchange(p) => (
+                p.decks(),
+                p.hands(),
+                p.kitty().iter().chain(p.removed_cards()).copied().collect(),
+            ),
+            GameState::Play(p) => (
+                p.decks(),
+                p.hands(),
+                p.kitty()
+                    .iter()
+                    .chain(p.removed_cards())
+                    .chain(p.cards_played())
+                    .chain(
+                        p.trick()
+                            .played_cards()
+                            .iter()
+                            .flat_map(|pc| pc.cards.iter()),
+                    )
+                    .copied()
+                    .collect(),
+            ),
+        };
+        if decks.is_empty() {
+            return Ok(());
+        }
+        for player in &self.propagated().players {
+            if let Some(counts) = hands.counts(player.id) {
+                cards.extend(counts.iter().flat_map(|(c, n)| std::iter::repeat_n(*c, *n)));
+            }
+        }
+        if Card::count(cards) != Card::count(decks.iter().flat_map(|d| d.cards())) {
+            bail!("the cards in the game don't match the decks it was dealt from");
+        }
+        Ok(())
+    }
+
     pub fn for_player(&self, id: PlayerID) -> GameState {
         let mut s = self.clone();
+        s.propagated_mut().undo_point = None;
+        if s.host() != Some(id) {
+            s.propagated_mut().webhook_url = None;
+            s.propagated_mut().deal_seed = None;
+        }
+        // The seed gives away every card until the game is over.
+        if !matches!(s, GameState::Initialize(_)) {
+            s.propagated_mut().shuffle_seed = None;
+        }
+        // Coaches see the game as their student does.
+        let viewer = self.coached_player(id).unwrap_or(id);
         match s {
             GameState::Initialize { .. } => (),
             GameState::Draw(ref mut p) => {
-                p.destructively_redact_for_player(id);
+                p.destructively_redact_for_player(viewer);
             }
             GameState::Exchange(ref mut p) => {
-                p.destructively_redact_for_player(id);
+                p.destructively_redact_for_player(viewer);
             }
             GameState::Play(ref mut p) => {
-                p.destructively_redact_for_player(id);
+                p.destructively_redact_for_player(viewer);
             }
         }
         s
     }
+
+    /// Whether `id` can't see the attacking team's points yet.
+    pub fn attacking_points_hidden_from(&self, id: PlayerID) -> bool {
+        let viewer = self.coached_player(id).unwrap_or(id);
+        match self {
+            GameState::Play(p) => p.attacking_points_hidden_from(viewer),
+            _ => false,
+        }
+    }
+
+    /// The recent tricks of the game in progress which `id` can see, oldest
+    /// first. These aren't part of the state sent by `for_player`.
+    pub fn trick_history_for(&self, id: PlayerID) -> Vec<CompletedTrick> {
+        let viewer = self.coached_player(id).unwrap_or(id);
+        match self {
+            GameState::Play(p) => p.trick_history_for_player(viewer),
+            _ => vec![],
+        }
+    }
 }
 
 impl Deref for GameState {
@@ -193,14 +782,23 @@ impl Deref for GameState {
 #[cfg(test)]
 mod tests {
     use crate::settings::{
-        AdvancementPolicy, FriendSelection, FriendSelectionPolicy, GameMode, GameModeSettings,
-        KittyTheftPolicy,
+        AdvancementPolicy, AttackingPointsVisibility, FirstDealLandlordPolicy, FriendSelection,
+        FriendSelectionPolicy, FriendSelectionRestrictions, GameMode, GameModeSettings,
+        KittyBidPolicy, KittyBuryPolicy, KittyTheftPolicy, LandlordRotationPolicy, RematchPolicy,
+        ThrowPenalty, MAX_DEAL_SEED,
     };
 
+    use shengji_mechanics::bidding::{BidTakebackPolicy, JokerBidPolicy, JokersOnlyTrumpPolicy};
+    use shengji_mechanics::deck::Deck;
     use shengji_mechanics::player::Player;
-    use shengji_mechanics::types::{cards, Card, Number, PlayerID, Rank, FULL_DECK};
+    use shengji_mechanics::scoring::{GameScoringParameters, Shutout};
+    use shengji_mechanics::types::{cards, Card, Number, PlayerID, Rank, Suit, Trump, FULL_DECK};
 
-    use crate::game_state::{initialize_phase::InitializePhase, play_phase::PlayPhase};
+    use crate::game_state::{
+        exchange_phase::BuryError, initialize_phase::InitializePhase, play_phase::PlayPhase,
+        GameState,
+    };
+    use crate::interactive::BroadcastMessage;
     use crate::message::MessageVariant;
 
     const R2: Rank = Rank::Number(Number::Two);
@@ -288,6 +886,8 @@ mod tests {
                     true,
                     (PlayerID(0), starting_rank),
                     advance_policy,
+                    &[],
+                    None,
                     RNT,
                 );
                 let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
@@ -339,6 +939,8 @@ mod tests {
                     true,
                     (PlayerID(0), starting_rank),
                     advance_policy,
+                    &[],
+                    None,
                     RA,
                 );
                 let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
@@ -411,6 +1013,8 @@ mod tests {
                     true,
                     (PlayerID(0), starting_rank),
                     advance_policy,
+                    &[],
+                    None,
                     RNT,
                 );
                 let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
@@ -478,6 +1082,8 @@ mod tests {
                     true,
                     (PlayerID(0), p0_rank),
                     advance_policy,
+                    &[],
+                    None,
                     RNT,
                 );
                 let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
@@ -504,6 +1110,8 @@ mod tests {
             true,
             (PlayerID(0), p0_rank),
             AdvancementPolicy::Unrestricted,
+            &[],
+            None,
             RNT,
         );
         let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
@@ -521,12 +1129,94 @@ mod tests {
             true,
             (PlayerID(0), p0_rank),
             AdvancementPolicy::Unrestricted,
+            &[],
+            None,
             RNT,
         );
         let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
         assert_eq!(ranks, vec![R3, R2, R3, R2],);
     }
 
+    #[test]
+    fn test_advancement_gates() {
+        let mut p = init_players();
+        p[0].level = R10;
+        p[1].level = R9;
+        p[2].level = R10;
+        p[3].level = R8;
+
+        let _ = PlayPhase::compute_player_level_deltas(
+            p.iter_mut(),
+            3,
+            2,
+            &[PlayerID(0), PlayerID(2)],
+            true,
+            (PlayerID(0), R10),
+            AdvancementPolicy::FullyUnrestricted,
+            &[R10],
+            None,
+            RNT,
+        );
+        // The defenders pass the gate, but the attackers can't jump past it.
+        let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
+        assert_eq!(ranks, vec![RQ, R10, RQ, R10]);
+
+        p[2].level = R10;
+        let _ = PlayPhase::compute_player_level_deltas(
+            p.iter_mut(),
+            0,
+            1,
+            &[PlayerID(0), PlayerID(2)],
+            true,
+            (PlayerID(0), RQ),
+            AdvancementPolicy::FullyUnrestricted,
+            &[R10],
+            None,
+            RNT,
+        );
+        // Defending a game played at a different rank doesn't pass the gate.
+        let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
+        assert_eq!(ranks, vec![RK, R10, R10, R10]);
+    }
+
+    #[test]
+    fn test_rank_sequence() {
+        let sequence = vec![R2, R5, R10, RK, RA];
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        init.set_rank(p1, R3).unwrap();
+        assert!(init.set_rank_sequence(Some(vec![])).is_err());
+        assert!(init.set_rank_sequence(Some(vec![R2, R5, R2])).is_err());
+        init.set_rank_sequence(Some(sequence.clone())).unwrap();
+
+        // Ranks outside of the sequence aren't allowed.
+        assert_eq!(init.players()[0].rank(), R2);
+        assert!(init.set_rank(p1, R3).is_err());
+        assert!(init.set_max_rank(RNT).is_err());
+        assert_eq!(*init.propagated().max_rank, RA);
+
+        let mut p = init_players();
+        p[0].level = R5;
+        p[1].level = R10;
+        p[2].level = R5;
+        p[3].level = R10;
+        let _ = PlayPhase::compute_player_level_deltas(
+            p.iter_mut(),
+            3,
+            2,
+            &[PlayerID(0), PlayerID(2)],
+            true,
+            (PlayerID(0), R5),
+            AdvancementPolicy::Unrestricted,
+            &[],
+            Some(&sequence),
+            RA,
+        );
+        // The attacking team still has to defend on the last rank of the sequence.
+        let ranks = p.iter().map(|pp| pp.rank()).collect::<Vec<Rank>>();
+        assert_eq!(ranks, vec![RK, RA, RK, RA]);
+    }
+
     #[test]
     fn test_player_level_deltas() {
         let mut players = init_players();
@@ -540,6 +1230,8 @@ mod tests {
             true,
             (PlayerID(0), R5),
             AdvancementPolicy::Unrestricted,
+            &[],
+            None,
             RNT,
         );
         for p in &players {
@@ -555,6 +1247,8 @@ mod tests {
             true,
             (PlayerID(0), Rank::Number(Number::Ace)),
             AdvancementPolicy::DefendPoints,
+            &[],
+            None,
             RNT,
         );
         for p in &players {
@@ -571,6 +1265,8 @@ mod tests {
             true,
             (PlayerID(0), RA),
             AdvancementPolicy::DefendPoints,
+            &[],
+            None,
             RNT,
         );
         for p in &players {
@@ -591,6 +1287,8 @@ mod tests {
             true,
             (PlayerID(0), Rank::Number(Number::Ace)),
             AdvancementPolicy::DefendPoints,
+            &[],
+            None,
             RNT,
         );
 
@@ -631,6 +1329,194 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_no_trump_only_from() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_no_trump_only_from(Some(Number::Seven)).unwrap();
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        *draw.deck_mut() = vec![S_7, S_7, S_7, S_7];
+        draw.draw_card(p1).unwrap();
+        draw.draw_card(p2).unwrap();
+        draw.draw_card(p3).unwrap();
+        draw.draw_card(p4).unwrap();
+        assert!(!draw.bid(p1, S_7, 1));
+
+        // Nobody can bid, so the trump is revealed from the bottom, but the
+        // game is still played in no-trump.
+        draw.kitty_mut()[0] = H_3;
+        draw.reveal_card().unwrap();
+        let exchange = draw.advance(p1).unwrap();
+        assert_eq!(
+            exchange.trump(),
+            Trump::NoTrump {
+                number: Some(Number::Seven)
+            }
+        );
+
+        init.set_joker_bid_policy(JokerBidPolicy::Disabled).unwrap();
+        assert_eq!(init.propagated().no_trump_only_from, None);
+        assert!(init.set_no_trump_only_from(Some(Number::Ace)).is_err());
+    }
+
+    #[test]
+    fn test_reveal_first_suited_card_without_landlord() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        init.set_kitty_bid_policy(KittyBidPolicy::FirstSuitedCard)
+            .unwrap();
+
+        let mut draw = init.start(players[0]).unwrap();
+        let dealer = draw.next_player().unwrap();
+        *draw.deck_mut() = vec![S_7, S_7, S_7, S_7];
+        for _ in 0..4 {
+            let next = draw.next_player().unwrap();
+            draw.draw_card(next).unwrap();
+        }
+        draw.kitty_mut()[0] = Card::BigJoker;
+        draw.kitty_mut()[1] = H_3;
+
+        // Jokers are skipped until a suited card is revealed.
+        draw.reveal_card().unwrap();
+        assert!(draw.advance(dealer).is_err());
+        draw.reveal_card().unwrap();
+        assert!(draw.reveal_card().is_err());
+
+        assert_eq!(draw.next_player().unwrap(), dealer);
+        let exchange = draw.advance(dealer).unwrap();
+        assert_eq!(exchange.landlord(), dealer);
+        assert_eq!(
+            exchange.trump(),
+            Trump::Standard {
+                suit: Suit::Hearts,
+                number: Number::Two
+            }
+        );
+    }
+
+    #[test]
+    fn test_first_deal_landlord_policy() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        init.set_kitty_bid_policy(KittyBidPolicy::FirstCard)
+            .unwrap();
+
+        // The assigned player draws first, and is the landlord if nobody bids.
+        init.set_first_deal_landlord_policy(FirstDealLandlordPolicy::AssignedByHost {
+            player: players[2],
+        })
+        .unwrap();
+        let mut draw = init.start(players[0]).unwrap();
+        assert_eq!(draw.next_player().unwrap(), players[2]);
+        *draw.deck_mut() = vec![S_7, S_7, S_7, S_7];
+        for _ in 0..4 {
+            let next = draw.next_player().unwrap();
+            assert!(draw.draw_card(next).unwrap().is_empty());
+        }
+        draw.reveal_card().unwrap();
+        assert_eq!(draw.advance(players[2]).unwrap().landlord(), players[2]);
+
+        // The first player to draw the card is the landlord if nobody bids.
+        init.set_first_deal_landlord_policy(FirstDealLandlordPolicy::FirstToDrawCard { card: H_3 })
+            .unwrap();
+        let mut draw = init.start(players[0]).unwrap();
+        *draw.position_mut() = 0;
+        *draw.deck_mut() = vec![H_3, S_7, H_3, S_7];
+        let mut msgs = vec![];
+        for _ in 0..4 {
+            let next = draw.next_player().unwrap();
+            msgs.extend(draw.draw_card(next).unwrap());
+        }
+        match &msgs[..] {
+            [MessageVariant::FirstDealLandlordSelected { player, card }] => {
+                assert_eq!(*player, players[1]);
+                assert_eq!(*card, H_3);
+            }
+            _ => panic!("expected one landlord selection, got {:?}", msgs),
+        }
+        draw.reveal_card().unwrap();
+        assert_eq!(draw.next_player().unwrap(), players[1]);
+        assert!(draw.advance(players[0]).is_err());
+        assert_eq!(draw.advance(players[1]).unwrap().landlord(), players[1]);
+    }
+
+    #[test]
+    fn test_kitty_size_revalidation() {
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        for i in 2..=4 {
+            init.add_player(format!("p{i}")).unwrap();
+        }
+        assert!(init.set_kitty_size(Some(108)).is_err());
+        init.set_kitty_size(Some(12)).unwrap();
+
+        // The override is kept as long as it's still valid...
+        init.set_num_decks(Some(3)).unwrap();
+        assert_eq!(init.propagated().kitty_size, Some(12));
+        let p5 = init.add_player("p5".into()).unwrap().0;
+        assert_eq!(init.propagated().kitty_size, Some(12));
+        init.remove_player(p5).unwrap();
+
+        let draw = init.start(p1).unwrap();
+        assert_eq!(draw.kitty().len(), 12);
+
+        // ... but is reset once the deck is too small for it.
+        init.set_kitty_size(Some(100)).unwrap();
+        let short_deck = Deck {
+            min: Number::Ten,
+            ..Default::default()
+        };
+        init.set_special_decks(vec![short_deck.clone(), short_deck.clone(), short_deck])
+            .unwrap();
+        assert_eq!(init.propagated().kitty_size, None);
+    }
+
+    #[test]
+    fn test_custom_deck_exclusions() {
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        for i in 2..=4 {
+            init.add_player(format!("p{i}")).unwrap();
+        }
+        let deck = Deck {
+            exclude_numbers: vec![Number::Three, Number::Four],
+            exclude_suits: vec![Suit::Hearts],
+            ..Default::default()
+        };
+        init.set_special_decks(vec![deck.clone(), deck]).unwrap();
+        init.set_kitty_size(Some(8)).unwrap();
+
+        let draw = init.start(p1).unwrap();
+        assert_eq!(draw.kitty().len(), 8);
+        // 70 cards, less the two which are removed to make the deal even.
+        assert_eq!(draw.removed_cards().len(), 2);
+        assert_eq!(draw.deck().len() + draw.kitty().len(), 68);
+        assert!(draw
+            .deck()
+            .iter()
+            .chain(draw.kitty())
+            .chain(draw.removed_cards())
+            .all(|c| c.suit() != Some(Suit::Hearts)
+                && c.number() != Some(Number::Three)
+                && c.number() != Some(Number::Four)));
+    }
+
     #[test]
     fn test_bid_sequence() {
         let mut init = InitializePhase::new();
@@ -669,21 +1555,121 @@ mod tests {
     }
 
     #[test]
-    fn test_kitty_stealing_bid_sequence() {
+    fn test_bid_takeback() {
         let mut init = InitializePhase::new();
         let p1 = init.add_player("p1".into()).unwrap().0;
         let p2 = init.add_player("p2".into()).unwrap().0;
         let p3 = init.add_player("p3".into()).unwrap().0;
         let p4 = init.add_player("p4".into()).unwrap().0;
-        init.set_kitty_theft_policy(KittyTheftPolicy::AllowKittyTheft)
-            .unwrap();
         let mut draw = init.start(PlayerID(0)).unwrap();
-        // Hackily ensure that everyone can bid.
         *draw.deck_mut() = vec![
-            cards::S_2,
-            Card::SmallJoker,
-            Card::BigJoker,
-            cards::H_2,
+            cards::S_3,
+            cards::S_3,
+            cards::S_3,
+            cards::S_3,
+            cards::S_2,
+            Card::SmallJoker,
+            Card::BigJoker,
+            cards::H_2,
+        ];
+        *draw.position_mut() = 0;
+
+        draw.draw_card(p1).unwrap();
+        draw.draw_card(p2).unwrap();
+        draw.draw_card(p3).unwrap();
+        draw.draw_card(p4).unwrap();
+
+        assert!(draw.bid(p1, cards::H_2, 1));
+        draw.propagated_mut()
+            .set_bid_takeback_policy(BidTakebackPolicy::NoBidTakeback)
+            .unwrap();
+        assert!(draw.take_back_bid(p1).is_err());
+        draw.propagated_mut()
+            .set_bid_takeback_policy(BidTakebackPolicy::AllowBidTakeback)
+            .unwrap();
+        // Only the player who made the last bid can take it back.
+        assert!(draw.take_back_bid(p2).is_err());
+        draw.take_back_bid(p1).unwrap();
+        assert!(draw.bids().is_empty());
+
+        assert!(draw.bid(p1, cards::H_2, 1));
+        draw.draw_card(p1).unwrap();
+        assert!(draw.take_back_bid(p1).is_err());
+        assert_eq!(draw.bids().len(), 1);
+    }
+
+    #[test]
+    fn test_jokers_only_trump_policy() {
+        let cases = [
+            (
+                JokersOnlyTrumpPolicy::NoTrumpRankOnly,
+                Card::BigJoker,
+                Trump::NoTrump {
+                    number: Some(Number::Two),
+                },
+            ),
+            (
+                JokersOnlyTrumpPolicy::BigJokerBid,
+                Card::BigJoker,
+                Trump::NoTrump { number: None },
+            ),
+            (
+                JokersOnlyTrumpPolicy::BigJokerBid,
+                Card::SmallJoker,
+                Trump::NoTrump {
+                    number: Some(Number::Two),
+                },
+            ),
+            (
+                JokersOnlyTrumpPolicy::BigJokerBid,
+                cards::H_2,
+                Trump::Standard {
+                    suit: Suit::Hearts,
+                    number: Number::Two,
+                },
+            ),
+            (
+                JokersOnlyTrumpPolicy::Always,
+                cards::H_2,
+                Trump::NoTrump { number: None },
+            ),
+        ];
+        for (policy, card, trump) in cases.iter() {
+            let mut init = InitializePhase::new();
+            let players = (1..=4)
+                .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+                .collect::<Vec<_>>();
+            init.set_jokers_only_trump_policy(*policy).unwrap();
+            let mut draw = init.start(players[0]).unwrap();
+            *draw.deck_mut() = vec![*card; 8];
+            *draw.position_mut() = 0;
+            for _ in 0..2 {
+                for p in &players {
+                    draw.draw_card(*p).unwrap();
+                }
+            }
+            assert!(draw.bid(players[1], *card, 2));
+            let exchange = draw.advance(players[1]).unwrap();
+            assert_eq!(exchange.trump(), *trump, "{:?} with {:?}", policy, card);
+        }
+    }
+
+    #[test]
+    fn test_kitty_stealing_bid_sequence() {
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_kitty_theft_policy(KittyTheftPolicy::AllowKittyTheft)
+            .unwrap();
+        let mut draw = init.start(PlayerID(0)).unwrap();
+        // Hackily ensure that everyone can bid.
+        *draw.deck_mut() = vec![
+            cards::S_2,
+            Card::SmallJoker,
+            Card::BigJoker,
+            cards::H_2,
             cards::S_2,
             Card::SmallJoker,
             Card::BigJoker,
@@ -716,133 +1702,1676 @@ mod tests {
     }
 
     #[test]
-    fn test_tuple_protection_case() {
+    fn test_tuple_protection_case() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_trick_draw_policy(
+            shengji_mechanics::trick::TrickDrawPolicy::LongerTuplesProtected,
+        )
+        .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        let mut draw = init.start(PlayerID(0)).unwrap();
+
+        let p1_hand = [S_9, S_9, S_10, S_10, S_K, S_3, S_4, S_5, S_7, S_7, H_2];
+        let p2_hand = [S_3, S_3, S_5, S_5, S_7, S_8, S_J, S_Q, C_3, C_4, C_5];
+        let p3_hand = [S_3, S_5, S_10, S_J, S_Q, S_6, S_8, S_8, S_8, C_6, C_7];
+        let p4_hand = [S_6, S_6, S_6, C_8, C_9, C_10, C_J, C_Q, C_K, C_A, C_A];
+
+        let mut deck = vec![];
+        for i in 0..11 {
+            deck.push(p1_hand[i]);
+            deck.push(p2_hand[i]);
+            deck.push(p3_hand[i]);
+            deck.push(p4_hand[i]);
+        }
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+
+        for _ in 0..11 {
+            draw.draw_card(p1).unwrap();
+            draw.draw_card(p2).unwrap();
+            draw.draw_card(p3).unwrap();
+            draw.draw_card(p4).unwrap();
+        }
+
+        assert!(draw.bid(p1, cards::H_2, 1));
+
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+        play.play_cards(p1, &[S_9, S_9, S_10, S_10, S_K]).unwrap();
+        play.play_cards(p2, &[S_3, S_3, S_5, S_5, S_7]).unwrap();
+        play.play_cards(p3, &[S_3, S_5, S_10, S_J, S_Q]).unwrap();
+        play.play_cards(p4, &[S_6, S_6, S_6, C_8, C_9]).unwrap();
+    }
+
+    #[test]
+    fn test_set_friends() {
+        use cards::*;
+
+        let setup_exchange = |friend_selection_policy, bid: Card| {
+            let mut init = InitializePhase::new();
+            init.set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
+                .unwrap();
+            init.set_friend_selection_policy(friend_selection_policy)
+                .unwrap();
+            let p1 = init.add_player("p1".into()).unwrap().0;
+            let p2 = init.add_player("p2".into()).unwrap().0;
+            let p3 = init.add_player("p3".into()).unwrap().0;
+            let p4 = init.add_player("p4".into()).unwrap().0;
+            init.set_landlord(Some(p2)).unwrap();
+            init.set_rank(p2, Rank::Number(bid.number().unwrap()))
+                .unwrap();
+
+            let mut draw = init.start(PlayerID(1)).unwrap();
+            *draw.deck_mut() = vec![bid, bid, bid, bid];
+            draw.draw_card(p2).unwrap();
+            draw.draw_card(p3).unwrap();
+            draw.draw_card(p4).unwrap();
+            draw.draw_card(p1).unwrap();
+
+            assert!(draw.bid(p1, bid, 1));
+
+            (p2, draw.advance(p2).unwrap())
+        };
+
+        let test_cases = vec![
+            (
+                FriendSelectionPolicy::Unrestricted,
+                S_7,
+                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, true)],
+            ),
+            (
+                FriendSelectionPolicy::PointCardNotAllowed,
+                S_7,
+                vec![(C_K, false), (S_3, false), (C_3, true), (C_A, true)],
+            ),
+            (
+                FriendSelectionPolicy::PointCardNotAllowed,
+                S_A,
+                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, false)],
+            ),
+            (
+                FriendSelectionPolicy::HighestCardNotAllowed,
+                S_7,
+                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, false)],
+            ),
+            (
+                FriendSelectionPolicy::TrumpsIncluded,
+                S_7,
+                vec![(C_K, true), (S_3, true), (C_3, true), (C_A, true)],
+            ),
+        ];
+
+        for (friend_selection_policy, landlord_level, friends) in test_cases {
+            for (friend, ok) in friends {
+                let (p2, mut exchange) = setup_exchange(friend_selection_policy, landlord_level);
+
+                assert_eq!(
+                    exchange
+                        .set_friends(
+                            p2,
+                            vec![FriendSelection {
+                                card: friend,
+                                initial_skip: 0,
+                            }],
+                        )
+                        .is_ok(),
+                    ok,
+                    "Expected {:?} to be a {} friend for {:?}",
+                    friend,
+                    if ok { "legal" } else { "illegal" },
+                    friend_selection_policy
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_friends_with_restrictions() {
+        use cards::*;
+
+        let setup_exchange = |restrictions| {
+            let mut init = InitializePhase::new();
+            init.set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
+                .unwrap();
+            init.set_friend_selection_policy(FriendSelectionPolicy::TrumpsIncluded)
+                .unwrap();
+            let p1 = init.add_player("p1".into()).unwrap().0;
+            let p2 = init.add_player("p2".into()).unwrap().0;
+            let p3 = init.add_player("p3".into()).unwrap().0;
+            let p4 = init.add_player("p4".into()).unwrap().0;
+            init.set_num_decks(Some(3)).unwrap();
+            init.set_friend_selection_restrictions(restrictions)
+                .unwrap();
+            init.set_landlord(Some(p2)).unwrap();
+            init.set_rank(p2, Rank::Number(Number::Seven)).unwrap();
+
+            let mut draw = init.start(PlayerID(1)).unwrap();
+            *draw.deck_mut() = vec![S_7, S_7, S_7, S_7];
+            draw.draw_card(p2).unwrap();
+            draw.draw_card(p3).unwrap();
+            draw.draw_card(p4).unwrap();
+            draw.draw_card(p1).unwrap();
+
+            assert!(draw.bid(p1, S_7, 1));
+
+            (p2, draw.advance(p2).unwrap())
+        };
+
+        let test_cases = vec![
+            (
+                FriendSelectionRestrictions::default(),
+                vec![((C_K, 0), true), ((S_3, 2), true), ((C_3, 3), false)],
+            ),
+            (
+                FriendSelectionRestrictions {
+                    no_point_cards: true,
+                    ..Default::default()
+                },
+                vec![((C_K, 0), false), ((C_10, 1), false), ((C_3, 2), true)],
+            ),
+            (
+                FriendSelectionRestrictions {
+                    no_trumps: true,
+                    ..Default::default()
+                },
+                vec![
+                    ((S_3, 0), false),
+                    ((Card::BigJoker, 0), false),
+                    ((C_3, 0), true),
+                ],
+            ),
+            (
+                FriendSelectionRestrictions {
+                    max_occurrence: Some(2),
+                    ..Default::default()
+                },
+                vec![((C_3, 1), true), ((C_3, 2), false)],
+            ),
+        ];
+
+        for (restrictions, friends) in test_cases {
+            for ((friend, initial_skip), ok) in friends {
+                let (p2, mut exchange) = setup_exchange(restrictions);
+
+                assert_eq!(
+                    exchange
+                        .set_friends(
+                            p2,
+                            vec![FriendSelection {
+                                card: friend,
+                                initial_skip,
+                            }],
+                        )
+                        .is_ok(),
+                    ok,
+                    "Expected {:?} #{} to be a {} friend for {:?}",
+                    friend,
+                    initial_skip + 1,
+                    if ok { "legal" } else { "illegal" },
+                    restrictions
+                );
+            }
+        }
+
+        let mut init = InitializePhase::new();
+        for i in 0..4 {
+            init.add_player(format!("p{i}")).unwrap();
+        }
+        assert!(init
+            .set_friend_selection_restrictions(FriendSelectionRestrictions {
+                max_occurrence: Some(3),
+                ..Default::default()
+            })
+            .is_err());
+        init.set_num_decks(Some(3)).unwrap();
+        init.set_friend_selection_restrictions(FriendSelectionRestrictions {
+            max_occurrence: Some(3),
+            ..Default::default()
+        })
+        .unwrap();
+        init.set_num_decks(Some(2)).unwrap();
+        assert_eq!(
+            init.propagated()
+                .friend_selection_restrictions
+                .max_occurrence,
+            None
+        );
+    }
+
+    #[test]
+    fn test_fixed_partnerships() {
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
+            .unwrap();
+        assert!(init.set_fixed_partnerships(true).is_err());
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_fixed_partnerships(true).unwrap();
+
+        let players = (0..5)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        assert!(init.start(players[0]).is_err());
+        init.make_observer(players[4]).unwrap();
+
+        // Setting a rank also sets it for the player's partner.
+        init.set_rank(players[0], Rank::Number(Number::Five))
+            .unwrap();
+        assert_eq!(
+            init.partners(players[0]),
+            Some(vec![players[0], players[2]])
+        );
+        assert_eq!(init.players()[2].rank(), Rank::Number(Number::Five));
+        assert_eq!(init.players()[1].rank(), Rank::Number(Number::Two));
+        init.start(players[0]).unwrap();
+
+        // Observers aren't seated automatically, since that would break up the teams.
+        assert!(init.make_all_observers_into_players().unwrap().is_empty());
+        assert_eq!(init.players().len(), 4);
+
+        // Switching to Finding Friends turns off fixed partnerships.
+        init.set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
+            .unwrap();
+        assert_eq!(init.partners(players[0]), None);
+    }
+
+    #[test]
+    fn test_throw_penalty_points_per_card() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        assert!(init
+            .set_throw_penalty(ThrowPenalty::PointsPerCard { points: 0 })
+            .is_err());
+        init.set_throw_penalty(ThrowPenalty::PointsPerCard { points: 15 })
+            .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_K, H_3],
+            [H_A, C_2, C_3],
+            [H_4, C_4, C_5],
+            [H_6, C_6, C_8],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        // The throw fails because p2 has a higher heart, so the king is taken back.
+        play.play_cards(p1, &[H_K, H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_6]).unwrap();
+        play.finish_trick().unwrap();
+
+        assert_eq!(play.calculate_points(), (15, 0));
+
+        play.play_cards(p2, &[C_2]).unwrap();
+        play.play_cards(p3, &[C_4]).unwrap();
+        play.play_cards(p4, &[C_6]).unwrap();
+        play.play_cards(p1, &[S_7]).unwrap();
+        play.finish_trick().unwrap();
+
+        play.play_cards(p1, &[H_K]).unwrap();
+        play.play_cards(p2, &[C_3]).unwrap();
+        play.play_cards(p3, &[C_5]).unwrap();
+        play.play_cards(p4, &[C_8]).unwrap();
+        play.finish_trick().unwrap();
+
+        let (_, _, msgs) = play.finish_game().unwrap();
+        let result = msgs
+            .into_iter()
+            .find_map(|m| match m {
+                MessageVariant::GameFinished { result } => Some(result),
+                _ => None,
+            })
+            .unwrap();
+        // p1 won the last trick, so also captured the kitty's points, doubled.
+        let kitty_points = play.kitty().iter().flat_map(|c| c.points()).sum::<usize>();
+        assert_eq!(result["p1"].points_captured, 15 + 2 * kitty_points);
+        assert_eq!(result["p1"].largest_failed_throw, 1);
+        assert_eq!(result["p2"].points_captured, 0);
+        assert_eq!(result["p2"].largest_failed_throw, 0);
+    }
+
+    #[test]
+    fn test_hints() {
+        use crate::hints::{hints, Hint};
+        use cards::*;
+        use shengji_mechanics::types::EffectiveSuit;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_throw_penalty(ThrowPenalty::PointsPerCard { points: 15 })
+            .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_K, H_3],
+            [H_A, C_2, C_3],
+            [C_7, C_4, C_5],
+            [H_6, C_6, C_8],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        let hints_for =
+            |play: &PlayPhase, id: PlayerID, selected: &[Card]| match GameState::Play(play.clone())
+                .for_player(id)
+            {
+                GameState::Play(p) => hints(&p, id, selected).unwrap(),
+                _ => unreachable!(),
+            };
+
+        // Someone else could be holding a higher heart than the three.
+        assert_eq!(
+            hints_for(&play, p1, &[H_K, H_3]),
+            vec![Hint::RiskyThrow { penalty: 15 }]
+        );
+        assert_eq!(hints_for(&play, p1, &[H_K]), vec![]);
+        assert_eq!(hints_for(&play, p2, &[]), vec![]);
+
+        play.play_cards(p1, &[H_K, H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        assert_eq!(
+            hints_for(&play, p3, &[]),
+            vec![Hint::CanRuff {
+                suit: EffectiveSuit::Hearts
+            }]
+        );
+        play.play_cards(p3, &[C_4]).unwrap();
+        assert_eq!(
+            hints_for(&play, p4, &[]),
+            vec![Hint::TeamWinningTrick { points: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_kitty_bury_policy() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_kitty_bury_policy(KittyBuryPolicy::NoPointOrTrumpCards)
+            .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_K, C_3],
+            [H_A, C_2, C_4],
+            [H_4, C_6, C_8],
+            [H_6, C_9, C_J],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        let kitty_size = draw.kitty().len();
+        *draw.kitty_mut() = vec![D_5];
+        draw.kitty_mut().resize(kitty_size, D_3);
+        assert!(draw.bid(p1, S_7, 1));
+        let mut exchange = draw.advance(p1).unwrap();
+
+        // The five in the bottom has to be swapped out, since the landlord
+        // has a club to bury instead.
+        assert!(exchange.advance(p1).is_err());
+        let err = exchange.move_card_to_kitty(p1, H_K).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuryError>(),
+            Some(BuryError::PointCard(H_K))
+        ));
+        let err = exchange.move_card_to_kitty(p1, S_7).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuryError>(),
+            Some(BuryError::TrumpCard(S_7))
+        ));
+        exchange.move_card_to_hand(p1, D_5).unwrap();
+        exchange.move_card_to_kitty(p1, C_3).unwrap();
+
+        // With nothing else left to bury, restricted cards are allowed.
+        exchange.move_card_to_kitty(p1, H_K).unwrap();
+        exchange.move_card_to_hand(p1, H_K).unwrap();
+        exchange.advance(p1).unwrap();
+    }
+
+    #[test]
+    fn test_max_kitty_points() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_max_kitty_points(Some(10)).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_K, C_3],
+            [H_A, C_2, C_4],
+            [H_4, C_6, C_8],
+            [H_6, C_9, C_J],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        let kitty_size = draw.kitty().len();
+        *draw.kitty_mut() = vec![D_10, D_5];
+        draw.kitty_mut().resize(kitty_size, D_3);
+        assert!(draw.bid(p1, S_7, 1));
+        let mut exchange = draw.advance(p1).unwrap();
+
+        // The bottom was dealt more points than are allowed.
+        let err = exchange.advance(p1).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuryError>(),
+            Some(BuryError::TooManyPoints {
+                attempted: 15,
+                max: 10
+            })
+        ));
+        exchange.move_card_to_hand(p1, D_5).unwrap();
+        let err = exchange.move_card_to_kitty(p1, H_K).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BuryError>(),
+            Some(BuryError::TooManyPoints {
+                attempted: 20,
+                max: 10
+            })
+        ));
+        exchange.move_card_to_kitty(p1, C_3).unwrap();
+        exchange.advance(p1).unwrap();
+    }
+
+    #[test]
+    fn test_attacking_points_visibility() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_attacking_points_visibility(AttackingPointsVisibility::HiddenFromLandlordTeam)
+            .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_3, C_2],
+            [H_A, C_3, C_4],
+            [H_4, C_5, C_6],
+            [H_K, C_8, C_9],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_K]).unwrap();
+        play.finish_trick().unwrap();
+
+        let points_for = |id| {
+            let mut p = play.clone();
+            p.destructively_redact_for_player(id);
+            p.calculate_points().0
+        };
+        assert_eq!(points_for(p1), 0);
+        assert_eq!(points_for(p3), 0);
+        assert_eq!(points_for(p2), 10);
+        assert_eq!(points_for(p4), 10);
+    }
+
+    #[test]
+    fn test_no_shutout_when_ended_early() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [[S_7, H_3], [H_A, C_3], [H_4, C_5], [H_K, C_8]];
+        let mut deck = (0..2)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..2 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let play = exchange.advance(p1).unwrap();
+
+        // The attacking team hasn't won a trick yet.
+        assert_eq!(play.shutout(), Some(Shutout::Big));
+
+        let mut state = serde_json::to_value(&play).unwrap();
+        state["game_ended_early"] = serde_json::Value::Bool(true);
+        let play: PlayPhase = serde_json::from_value(state).unwrap();
+        assert_eq!(play.shutout(), None);
+    }
+
+    #[test]
+    fn test_trick_history() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_attacking_points_visibility(AttackingPointsVisibility::HiddenFromLandlordTeam)
+            .unwrap();
+        init.set_trick_history_length(Some(2)).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_3, C_2],
+            [H_A, C_3, C_4],
+            [H_4, C_5, C_6],
+            [H_K, C_8, C_9],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_K]).unwrap();
+        play.finish_trick().unwrap();
+        play.play_cards(p2, &[C_3]).unwrap();
+        play.play_cards(p3, &[C_5]).unwrap();
+        play.play_cards(p4, &[C_8]).unwrap();
+        play.play_cards(p1, &[C_2]).unwrap();
+        play.finish_trick().unwrap();
+
+        let history = play.trick_history_for_player(p2);
+        assert_eq!(
+            history
+                .iter()
+                .map(|t| (t.winner, t.points))
+                .collect::<Vec<_>>(),
+            vec![(p2, 10), (p4, 5)]
+        );
+        assert_eq!(history[1].played_cards[3].cards, vec![C_2]);
+        // The landlord's team would be able to add up the attacking team's
+        // points from the whole history.
+        assert_eq!(play.trick_history_for_player(p1).len(), 1);
+        // The history is fetched separately from the rest of the state.
+        let mut redacted = play.clone();
+        redacted.destructively_redact_for_player(p2);
+        assert!(redacted.trick_history_for_player(p2).is_empty());
+
+        play.play_cards(p4, &[C_9]).unwrap();
+        play.play_cards(p1, &[S_7]).unwrap();
+        play.play_cards(p2, &[C_4]).unwrap();
+        play.play_cards(p3, &[C_6]).unwrap();
+        play.finish_trick().unwrap();
+
+        let game = GameState::Play(play);
+        let history = game.trick_history_for(p1);
+        assert_eq!(
+            history.iter().map(|t| t.winner).collect::<Vec<_>>(),
+            vec![p4, p1]
+        );
+    }
+
+    #[test]
+    fn test_trick_won_points_hidden() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_attacking_points_visibility(AttackingPointsVisibility::HiddenFromLandlordTeam)
+            .unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [[S_7, H_3], [H_A, C_3], [H_4, C_5], [H_K, C_8]];
+        let mut deck = (0..2)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..2 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_K]).unwrap();
+        let variant = play
+            .finish_trick()
+            .unwrap()
+            .into_iter()
+            .find(|m| matches!(m, MessageVariant::TrickWon { .. }))
+            .unwrap();
+        let msg: BroadcastMessage = serde_json::from_value(serde_json::json!({
+            "actor": p1,
+            "actor_name": "p1",
+            "variant": variant,
+        }))
+        .unwrap();
+        let game = GameState::Play(play);
+
+        for id in [p1, p3] {
+            let (redacted, text) = msg.redacted_for_player(&game, id).unwrap();
+            assert!(matches!(
+                redacted.variant(),
+                MessageVariant::TrickWonPointsHidden { winner } if *winner == p2
+            ));
+            assert_eq!(text, "p2 wins the trick");
+            assert!(!serde_json::to_string(&redacted).unwrap().contains("10"));
+        }
+        assert!(msg.redacted_for_player(&game, p2).is_none());
+        assert!(msg.redacted_for_player(&game, p4).is_none());
+    }
+
+    #[test]
+    fn test_face_down_discards() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.set_face_down_discards(true).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, H_3, C_2],
+            [H_A, C_3, C_4],
+            [D_4, C_5, C_6],
+            [H_K, C_8, C_9],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        let msgs = play.play_cards(p3, &[D_4]).unwrap();
+        assert!(matches!(
+            &msgs[..],
+            [MessageVariant::PlayedCards { cards }] if cards == &[Card::Unknown]
+        ));
+        play.play_cards(p4, &[H_K]).unwrap();
+
+        let discard_for = |id| {
+            let mut p = play.clone();
+            p.destructively_redact_for_player(id);
+            p.trick().played_cards()[2].cards.clone()
+        };
+        assert_eq!(discard_for(p1), vec![Card::Unknown]);
+        assert_eq!(discard_for(p3), vec![D_4]);
+
+        let msgs = play.finish_trick().unwrap();
+        assert!(msgs.iter().any(|m| matches!(
+            m,
+            MessageVariant::FaceDownCardsRevealed { player, cards }
+                if *player == p3 && cards == &[D_4]
+        )));
+
+        // The discard stays face-down in the trick history.
+        let history = play.trick_history_for_player(p1);
+        assert_eq!(history[0].played_cards[0].cards, vec![H_3]);
+        assert_eq!(history[0].played_cards[2].cards, vec![Card::Unknown]);
+        let history = play.trick_history_for_player(p3);
+        assert_eq!(history[0].played_cards[2].cards, vec![D_4]);
+    }
+
+    #[test]
+    fn test_trick_history_hide_played_cards() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        init.hide_played_cards(true).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [[S_7, H_3], [H_A, C_3], [H_4, C_5], [H_K, C_8]];
+        let mut deck = (0..2)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..2 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 1));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_3]).unwrap();
+        play.play_cards(p2, &[H_A]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_K]).unwrap();
+        play.finish_trick().unwrap();
+
+        let history = play.trick_history_for_player(p1);
+        assert_eq!((history[0].winner, history[0].points), (p2, 10));
+        for played in &history[0].played_cards {
+            assert_eq!(played.cards, vec![Card::Unknown]);
+        }
+    }
+
+    #[test]
+    fn test_bid_defense_bonus() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        let mut gsp = GameScoringParameters::default();
+        gsp.bid_defense_bonus = 1;
+        init.set_game_scoring_parameters(gsp).unwrap();
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Seven)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_7, S_7, H_A],
+            [H_3, C_2, C_3],
+            [H_4, C_4, C_5],
+            [H_6, C_6, C_8],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_7, 2));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_A]).unwrap();
+        play.play_cards(p2, &[H_3]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_6]).unwrap();
+        play.finish_trick().unwrap();
+        play.play_cards(p1, &[S_7, S_7]).unwrap();
+        play.play_cards(p2, &[C_2, C_3]).unwrap();
+        play.play_cards(p3, &[C_4, C_5]).unwrap();
+        play.play_cards(p4, &[C_6, C_8]).unwrap();
+        play.finish_trick().unwrap();
+
+        // The landlord team goes up 3 levels for keeping the opposing team at
+        // 0, and one more for defending a two-card bid.
+        let (_, landlord_won, msgs) = play.finish_game().unwrap();
+        assert!(landlord_won);
+        assert!(msgs.iter().any(|m| matches!(
+            m,
+            MessageVariant::BidDefenseBonusEarned {
+                landlord_won: true,
+                bid_size: 2,
+                levels: 1,
+            }
+        )));
+        assert!(msgs.iter().any(|m| matches!(
+            m,
+            MessageVariant::RankAdvanced { player, new_rank }
+                if *player == p1 && *new_rank == Rank::Number(Number::Jack)
+        )));
+    }
+
+    #[test]
+    fn test_match_target_rank() {
+        use cards::*;
+
+        let mut init = InitializePhase::new();
+        init.set_game_mode(GameModeSettings::Tractor).unwrap();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        init.set_match_target_rank(Some(Rank::Number(Number::Ace)))
+            .unwrap();
+        init.set_rematch_policy(RematchPolicy::ResetRanks).unwrap();
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_rank(p1, Rank::Number(Number::Ace)).unwrap();
+
+        let mut draw = init.start(p1).unwrap();
+        let hands = [
+            [S_A, S_A, H_K],
+            [H_3, C_2, C_3],
+            [H_4, C_4, C_5],
+            [H_6, C_6, C_8],
+        ];
+        let mut deck = (0..3)
+            .flat_map(|i| hands.iter().map(move |h| h[i]))
+            .collect::<Vec<_>>();
+        deck.reverse();
+        *draw.deck_mut() = deck;
+        *draw.position_mut() = 0;
+        for _ in 0..3 {
+            for p in [p1, p2, p3, p4] {
+                draw.draw_card(p).unwrap();
+            }
+        }
+        assert!(draw.bid(p1, S_A, 2));
+        let exchange = draw.advance(p1).unwrap();
+        let mut play = exchange.advance(p1).unwrap();
+
+        play.play_cards(p1, &[H_K]).unwrap();
+        play.play_cards(p2, &[H_3]).unwrap();
+        play.play_cards(p3, &[H_4]).unwrap();
+        play.play_cards(p4, &[H_6]).unwrap();
+        play.finish_trick().unwrap();
+        play.play_cards(p1, &[S_A, S_A]).unwrap();
+        play.play_cards(p2, &[C_2, C_3]).unwrap();
+        play.play_cards(p3, &[C_4, C_5]).unwrap();
+        play.play_cards(p4, &[C_6, C_8]).unwrap();
+        play.finish_trick().unwrap();
+
+        // Defending the ace wins the match for the landlord's team, after
+        // which everyone starts over.
+        let (init, _, msgs) = play.finish_game().unwrap();
+        let standings = msgs
+            .iter()
+            .find_map(|m| match m {
+                MessageVariant::MatchComplete { winners, standings } => {
+                    assert_eq!(winners, &vec![p1, p3]);
+                    Some(standings.clone())
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(standings[0].player, p1);
+        assert!(standings[0].won_match);
+        assert_eq!(standings[0].rank, Rank::NoTrump);
+        assert!(!standings[3].won_match);
+        assert!(init
+            .propagated()
+            .players
+            .iter()
+            .all(|p| p.rank() == Rank::Number(Number::Two) && p.metalevel == 1));
+    }
+
+    #[test]
+    fn test_reattach() {
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let mut state = GameState::Initialize(init);
+
+        // A returning player keeps their seat, even if they'd join with a
+        // different name.
+        let (id, msgs) = state.reattach(p1).unwrap();
+        assert_eq!(id, p1);
+        assert!(matches!(
+            msgs[..],
+            [MessageVariant::JoinedGameAgain { player, .. }] if player == p1
+        ));
+        assert!(state.reattach(PlayerID(p1.0 + 1)).is_err());
+        assert_eq!(state.register("p1".into()).unwrap().0, p1);
+    }
+
+    #[test]
+    fn test_spectators() {
         use cards::*;
 
         let mut init = InitializePhase::new();
-        init.set_trick_draw_policy(
-            shengji_mechanics::trick::TrickDrawPolicy::LongerTuplesProtected,
-        )
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        init.set_kitty_bid_policy(KittyBidPolicy::FirstCard)
+            .unwrap();
+        init.set_first_deal_landlord_policy(FirstDealLandlordPolicy::AssignedByHost {
+            player: players[0],
+        })
         .unwrap();
+        let mut draw = init.start(players[0]).unwrap();
+        *draw.deck_mut() = vec![S_7, S_8, S_9, S_10];
+        for _ in 0..4 {
+            let next = draw.next_player().unwrap();
+            draw.draw_card(next).unwrap();
+        }
+        draw.reveal_card().unwrap();
+        let mut state = GameState::Exchange(draw.advance(players[0]).unwrap());
+
+        // Spectators can join mid-game, but can't take over a player's name.
+        let (spectator, msgs) = state.register_spectator("s".into()).unwrap();
+        assert!(msgs.is_empty());
+        assert!(state.register_spectator("p1".into()).is_err());
+        assert!(state.register("s".into()).is_err());
+        assert_eq!(state.register_spectator("s".into()).unwrap().0, spectator);
+        assert_eq!(state.spectators().len(), 1);
+        assert!(state.observers().is_empty());
+
+        // None of the hands are visible to the spectator.
+        match state.for_player(spectator) {
+            GameState::Exchange(p) => {
+                for id in &players {
+                    assert!(p
+                        .hands()
+                        .get(*id)
+                        .unwrap()
+                        .keys()
+                        .all(|c| *c == Card::Unknown));
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        // Once approved, a coach sees their student's hand, and only theirs.
+        assert!(state.request_to_coach(players[0], players[1]).is_err());
+        state.request_to_coach(spectator, players[1]).unwrap();
+        assert!(state.request_to_coach(spectator, players[2]).is_err());
+        assert!(state.respond_to_coach(players[2], spectator, true).is_err());
+        let visible = |state: &GameState| match state.for_player(spectator) {
+            GameState::Exchange(p) => players
+                .iter()
+                .filter(|id| {
+                    p.hands()
+                        .get(**id)
+                        .unwrap()
+                        .keys()
+                        .all(|c| *c != Card::Unknown)
+                })
+                .copied()
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert!(visible(&state).is_empty());
+        state.respond_to_coach(players[1], spectator, true).unwrap();
+        assert_eq!(visible(&state), vec![players[1]]);
+        state
+            .respond_to_coach(players[1], spectator, false)
+            .unwrap();
+        assert!(visible(&state).is_empty());
+        assert!(state.coaches().is_empty());
+
+        state.request_to_coach(spectator, players[1]).unwrap();
+        state.kick(spectator).unwrap();
+        assert!(state.spectators().is_empty());
+        assert!(state.coaches().is_empty());
+    }
+
+    #[test]
+    fn test_init_from_settings() {
+        let mut init = InitializePhase::new();
         let p1 = init.add_player("p1".into()).unwrap().0;
-        let p2 = init.add_player("p2".into()).unwrap().0;
-        let p3 = init.add_player("p3".into()).unwrap().0;
-        let p4 = init.add_player("p4".into()).unwrap().0;
-        let mut draw = init.start(PlayerID(0)).unwrap();
+        init.set_landlord(Some(p1)).unwrap();
+        init.set_kitty_bury_policy(KittyBuryPolicy::NoPointCards)
+            .unwrap();
+        init.set_first_deal_landlord_policy(FirstDealLandlordPolicy::AssignedByHost { player: p1 })
+            .unwrap();
 
-        let p1_hand = [S_9, S_9, S_10, S_10, S_K, S_3, S_4, S_5, S_7, S_7, H_2];
-        let p2_hand = [S_3, S_3, S_5, S_5, S_7, S_8, S_J, S_Q, C_3, C_4, C_5];
-        let p3_hand = [S_3, S_5, S_10, S_J, S_Q, S_6, S_8, S_8, S_8, C_6, C_7];
-        let p4_hand = [S_6, S_6, S_6, C_8, C_9, C_10, C_J, C_Q, C_K, C_A, C_A];
+        let mut fresh = InitializePhase::from_settings(init.propagated().clone()).unwrap();
+        assert!(fresh.players().is_empty());
+        assert_eq!(fresh.landlord(), None);
+        assert_eq!(fresh.kitty_bury_policy, KittyBuryPolicy::NoPointCards);
+        assert_eq!(
+            fresh.first_deal_landlord_policy,
+            FirstDealLandlordPolicy::Random
+        );
+        // IDs aren't reused, so stale references to the old players can't
+        // match anyone new.
+        assert_ne!(fresh.add_player("p2".into()).unwrap().0, p1);
+    }
 
-        let mut deck = vec![];
-        for i in 0..11 {
-            deck.push(p1_hand[i]);
-            deck.push(p2_hand[i]);
-            deck.push(p3_hand[i]);
-            deck.push(p4_hand[i]);
+    #[test]
+    fn test_settings_diff_and_merge() {
+        let mut init = InitializePhase::new();
+        init.add_player("p1".into()).unwrap();
+        let before = init.propagated().clone();
+        init.set_throw_penalty(ThrowPenalty::TenPointsPerAttempt)
+            .unwrap();
+        init.set_kitty_bury_policy(KittyBuryPolicy::NoPointCards)
+            .unwrap();
+        init.add_player("p2".into()).unwrap();
+
+        // Players joining isn't a change to the settings.
+        let changes = before.diff_settings(init.propagated()).unwrap();
+        assert_eq!(
+            changes.iter().map(|c| c.description()).collect::<Vec<_>>(),
+            vec![
+                "throw penalty from None to TenPointsPerAttempt",
+                "kitty bury policy from Unrestricted to NoPointCards"
+            ]
+        );
+
+        let patch = serde_json::json!({
+            "throw_penalty": "None",
+            "kitty_bury_policy": "NoPointCards",
+            "num_decks": 3,
+        });
+        let changes = init.merge_settings(patch.as_object().unwrap()).unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|c| c.setting.as_str())
+                .collect::<Vec<_>>(),
+            vec!["num_decks", "throw_penalty"]
+        );
+        assert_eq!(init.throw_penalty, ThrowPenalty::None);
+        assert_eq!(init.num_decks, Some(3));
+
+        // Patches are validated, and nothing is applied if any setting is
+        // invalid.
+        for patch in [
+            serde_json::json!({ "throw_penalty": "TenPointsPerAttempt", "num_decks": 0 }),
+            serde_json::json!({ "throw_penalty": "TenPointsPerAttempt", "players": [] }),
+            serde_json::json!({ "throw_penalty": "Lots" }),
+        ]
+        .iter()
+        {
+            assert!(init.merge_settings(patch.as_object().unwrap()).is_err());
+            assert_eq!(init.throw_penalty, ThrowPenalty::None);
         }
-        deck.reverse();
-        *draw.deck_mut() = deck;
-        *draw.position_mut() = 0;
+    }
 
-        for _ in 0..11 {
-            draw.draw_card(p1).unwrap();
-            draw.draw_card(p2).unwrap();
-            draw.draw_card(p3).unwrap();
-            draw.draw_card(p4).unwrap();
+    #[test]
+    fn test_turn_timers() {
+        use crate::interactive::{Action, InteractiveGame};
+        use crate::settings::{TimeoutPolicy, TurnTimers};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut timers = TurnTimers {
+            bid_secs: Some(1),
+            ..Default::default()
+        };
+        assert!(init.set_turn_timers(timers).is_err());
+        timers.bid_secs = Some(30);
+        init.set_turn_timers(timers).unwrap();
+
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init.clone()));
+        assert_eq!(game.dump_state().unwrap().timed_turn(), None);
+        game.interact(Action::StartGame, players[0], &logger)
+            .unwrap();
+        let turn = game.dump_state().unwrap().timed_turn().unwrap();
+        assert_eq!(turn.secs, 30);
+
+        // By default, running out of time just marks the player idle until
+        // they act again.
+        let msgs = game.time_out(turn, &mut None, 0, &logger).unwrap();
+        assert!(
+            matches!(msgs[..], [(ref b, _)] if matches!(b.variant(), MessageVariant::MarkedIdle))
+        );
+        assert_eq!(game.dump_state().unwrap().idle_players(), &[turn.player]);
+        game.interact(Action::DrawCard, turn.player, &logger)
+            .unwrap();
+        assert!(game.dump_state().unwrap().idle_players().is_empty());
+
+        // Stale timeouts are ignored.
+        assert!(game.time_out(turn, &mut None, 0, &logger).is_err());
+
+        timers.timeout_policy = TimeoutPolicy::AutoPlay;
+        init.set_turn_timers(timers).unwrap();
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+        game.interact(Action::StartGame, players[1], &logger)
+            .unwrap();
+        let turn = game.dump_state().unwrap().timed_turn().unwrap();
+        game.time_out(turn, &mut None, 0, &logger).unwrap();
+        let next = game.dump_state().unwrap().timed_turn().unwrap();
+        assert_ne!(next, turn);
+        assert!(game.dump_state().unwrap().idle_players().is_empty());
+    }
+
+    #[test]
+    fn test_webhook_url() {
+        use crate::interactive::{Action, InteractiveGame};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+        let url = Some("http://example.com/hook".to_string());
+        assert!(game
+            .interact(Action::SetWebhookUrl(url.clone()), players[1], &logger)
+            .is_err());
+        assert!(game
+            .interact(
+                Action::SetWebhookUrl(Some("ftp://example.com".into())),
+                players[0],
+                &logger
+            )
+            .is_err());
+        game.interact(Action::SetWebhookUrl(url), players[0], &logger)
+            .unwrap();
+
+        // Only the host can see the URL, and it isn't recorded for replays.
+        let state = game.dump_state_for_player(players[0]).unwrap();
+        assert_eq!(state.webhook_url(), Some("http://example.com/hook"));
+        let state = game.dump_state_for_player(players[1]).unwrap();
+        assert_eq!(state.webhook_url(), None);
+        let mut replay = None;
+        game.interact_recorded(Action::StartGame, players[0], &mut replay, 0, &logger)
+            .unwrap();
+        assert_eq!(replay.unwrap().initial_state.webhook_url(), None);
+        assert!(game.dump_state().unwrap().webhook_url().is_some());
+    }
+
+    #[test]
+    fn test_events_rebuild_state() {
+        use crate::interactive::{Action, GameEvent, InteractiveGame};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let initial = GameState::Initialize(InitializePhase::new());
+        let mut game = InteractiveGame::new_from_state(initial.clone());
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        game.register_spectator("watcher".to_string()).unwrap();
+        game.interact(Action::StartGame, players[0], &logger)
+            .unwrap();
+        for _ in 0..8 {
+            let next = game.next_player().unwrap();
+            game.interact(Action::DrawCard, next, &logger).unwrap();
         }
 
-        assert!(draw.bid(p1, cards::H_2, 1));
+        // Failed actions don't change anything, and aren't recorded.
+        let before = serde_json::to_value(game.dump_state().unwrap()).unwrap();
+        let next = game.next_player().unwrap();
+        let other = players.iter().find(|p| **p != next).unwrap();
+        assert!(game.interact(Action::DrawCard, *other, &logger).is_err());
+        assert!(game.ban(players[1], players[2]).is_err());
+        assert_eq!(
+            serde_json::to_value(game.dump_state().unwrap()).unwrap(),
+            before
+        );
+        assert_eq!(game.events().len(), 4 + 1 + 1 + 8);
+        assert!(matches!(
+            game.events()[5],
+            GameEvent::Acted {
+                action: Action::StartGame,
+                ..
+            }
+        ));
 
-        let exchange = draw.advance(p1).unwrap();
-        let mut play = exchange.advance(p1).unwrap();
-        play.play_cards(p1, &[S_9, S_9, S_10, S_10, S_K]).unwrap();
-        play.play_cards(p2, &[S_3, S_3, S_5, S_5, S_7]).unwrap();
-        play.play_cards(p3, &[S_3, S_5, S_10, S_J, S_Q]).unwrap();
-        play.play_cards(p4, &[S_6, S_6, S_6, C_8, C_9]).unwrap();
+        let rebuilt =
+            InteractiveGame::new_from_events(initial, game.events().to_vec(), &logger).unwrap();
+        assert_eq!(
+            serde_json::to_value(rebuilt.dump_state().unwrap()).unwrap(),
+            before
+        );
     }
 
     #[test]
-    fn test_set_friends() {
-        use cards::*;
+    fn test_event_log_rebuilds_across_loads() {
+        use crate::interactive::{Action, EventLog, InteractiveGame};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let mut log = EventLog::default();
+        let mut state = GameState::Initialize(InitializePhase::new());
+
+        // Each operation loads the game afresh, like the backend does.
+        let mut game = InteractiveGame::new_from_state(state.clone());
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        log.record(&state, &game);
+        state = game.into_state();
+
+        let mut game = InteractiveGame::new_from_state(state.clone());
+        game.interact(Action::StartGame, players[0], &logger)
+            .unwrap();
+        log.record(&state, &game);
+        state = game.into_state();
 
-        let setup_exchange = |friend_selection_policy, bid: Card| {
-            let mut init = InitializePhase::new();
-            init.set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
-                .unwrap();
-            init.set_friend_selection_policy(friend_selection_policy)
-                .unwrap();
-            let p1 = init.add_player("p1".into()).unwrap().0;
-            let p2 = init.add_player("p2".into()).unwrap().0;
-            let p3 = init.add_player("p3".into()).unwrap().0;
-            let p4 = init.add_player("p4".into()).unwrap().0;
-            init.set_landlord(Some(p2)).unwrap();
-            init.set_rank(p2, Rank::Number(bid.number().unwrap()))
-                .unwrap();
+        // Loads which don't change anything don't add to the log.
+        let game = InteractiveGame::new_from_state(state.clone());
+        log.record(&state, &game);
 
-            let mut draw = init.start(PlayerID(1)).unwrap();
-            *draw.deck_mut() = vec![bid, bid, bid, bid];
-            draw.draw_card(p2).unwrap();
-            draw.draw_card(p3).unwrap();
-            draw.draw_card(p4).unwrap();
-            draw.draw_card(p1).unwrap();
+        assert_eq!(log.events().len(), 5);
+        assert_eq!(
+            serde_json::to_value(log.rebuild(&logger).unwrap().dump_state().unwrap()).unwrap(),
+            serde_json::to_value(&state).unwrap()
+        );
+    }
 
-            assert!(draw.bid(p1, bid, 1));
+    #[test]
+    fn test_deal_seed() {
+        use crate::interactive::{Action, InteractiveGame};
+        use slog::{o, Discard, Logger};
 
-            (p2, draw.advance(p2).unwrap())
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+        assert!(game
+            .interact(Action::SetDealSeed(Some(7)), players[1], &logger)
+            .is_err());
+        assert!(game
+            .interact(Action::SetDealSeed(Some(1 << 60)), players[0], &logger)
+            .is_err());
+        game.interact(Action::SetDealSeed(Some(7)), players[0], &logger)
+            .unwrap();
+        assert!(game
+            .dump_state_for_player(players[1])
+            .unwrap()
+            .propagated()
+            .deal_seed
+            .is_none());
+
+        // Every table with the same seed gets the same deal, whatever seed
+        // they'd otherwise have been shuffled with.
+        let mut other = InteractiveGame::new_from_state(game.dump_state().unwrap());
+        game.interact_with_seed(Action::StartGame, players[0], 1, &logger)
+            .unwrap();
+        other
+            .interact_with_seed(Action::StartGame, players[0], 2, &logger)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(game.dump_state().unwrap()).unwrap(),
+            serde_json::to_value(other.dump_state().unwrap()).unwrap()
+        );
+
+        // The seed is only revealed once the game is over.
+        let state = game.dump_state().unwrap();
+        assert_eq!(state.shuffle_seed(), Some(7));
+        assert_eq!(state.for_player(players[0]).shuffle_seed(), None);
+        let mut draw = match state {
+            GameState::Draw(draw) => draw,
+            _ => unreachable!(),
         };
+        let init = players
+            .iter()
+            .find_map(|p| draw.request_reset(*p).unwrap().0)
+            .unwrap();
+        let state = GameState::Initialize(init);
+        assert_eq!(state.for_player(players[1]).shuffle_seed(), Some(7));
 
-        let test_cases = vec![
-            (
-                FriendSelectionPolicy::Unrestricted,
-                S_7,
-                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, true)],
-            ),
-            (
-                FriendSelectionPolicy::PointCardNotAllowed,
-                S_7,
-                vec![(C_K, false), (S_3, false), (C_3, true), (C_A, true)],
-            ),
-            (
-                FriendSelectionPolicy::PointCardNotAllowed,
-                S_A,
-                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, false)],
-            ),
-            (
-                FriendSelectionPolicy::HighestCardNotAllowed,
-                S_7,
-                vec![(C_K, true), (S_3, false), (C_3, true), (C_A, false)],
-            ),
-            (
-                FriendSelectionPolicy::TrumpsIncluded,
-                S_7,
-                vec![(C_K, true), (S_3, true), (C_3, true), (C_A, true)],
-            ),
-        ];
+        // Random seeds are kept small enough for the frontend to display.
+        let mut init = InitializePhase::new();
+        for i in 1..=4 {
+            init.add_player(format!("p{i}")).unwrap();
+        }
+        let draw = init.start_with_seed(players[0], u64::MAX).unwrap();
+        assert_eq!(draw.propagated().shuffle_seed(), Some(MAX_DEAL_SEED));
+    }
 
-        for (friend_selection_policy, landlord_level, friends) in test_cases {
-            for (friend, ok) in friends {
-                let (p2, mut exchange) = setup_exchange(friend_selection_policy, landlord_level);
+    #[test]
+    fn test_pause() {
+        use crate::interactive::{Action, InteractiveGame};
+        use slog::{o, Discard, Logger};
 
-                assert_eq!(
-                    exchange
-                        .set_friends(
-                            p2,
-                            vec![FriendSelection {
-                                card: friend,
-                                initial_skip: 0,
-                            }],
-                        )
-                        .is_ok(),
-                    ok,
-                    "Expected {:?} to be a {} friend for {:?}",
-                    friend,
-                    if ok { "legal" } else { "illegal" },
-                    friend_selection_policy
-                );
-            }
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+        assert!(game
+            .interact(Action::RequestPause, players[0], &logger)
+            .is_err());
+        game.interact(Action::StartGame, players[0], &logger)
+            .unwrap();
+        let turn = game.timed_turn();
+
+        game.interact(Action::RequestPause, players[1], &logger)
+            .unwrap();
+        assert!(game
+            .interact(Action::RequestPause, players[2], &logger)
+            .is_err());
+        let next = game.next_player().unwrap();
+        assert!(game.interact(Action::DrawCard, next, &logger).is_err());
+
+        // Half of the players isn't a majority, and voting twice doesn't
+        // count.
+        game.interact(Action::VoteToResume, players[0], &logger)
+            .unwrap();
+        game.interact(Action::VoteToResume, players[0], &logger)
+            .unwrap();
+        game.interact(Action::VoteToResume, players[1], &logger)
+            .unwrap();
+        assert!(game.dump_state().unwrap().pause().is_some());
+        let msgs = game
+            .interact(Action::VoteToResume, players[2], &logger)
+            .unwrap();
+        assert!(matches!(
+            msgs.last(),
+            Some((b, _)) if matches!(b.variant(), MessageVariant::GameResumed)
+        ));
+        assert_eq!(game.timed_turn(), turn);
+        game.interact(Action::DrawCard, next, &logger).unwrap();
+
+        // Resetting the game clears the pause.
+        game.interact(Action::RequestPause, players[1], &logger)
+            .unwrap();
+        game.interact(Action::ResetGame, players[0], &logger)
+            .unwrap();
+        game.interact(Action::ResetGame, players[1], &logger)
+            .unwrap();
+        assert!(game.dump_state().unwrap().pause().is_none());
+    }
+
+    #[test]
+    fn test_undo() {
+        use crate::interactive::{Action, InteractiveGame};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let p1 = init.add_player("p1".into()).unwrap().0;
+        let p2 = init.add_player("p2".into()).unwrap().0;
+        let p3 = init.add_player("p3".into()).unwrap().0;
+        let p4 = init.add_player("p4".into()).unwrap().0;
+        let mut draw = init.start(PlayerID(0)).unwrap();
+        // Cards are drawn from the end of the deck.
+        *draw.deck_mut() = vec![cards::D_2, cards::C_2, cards::S_2, cards::H_2];
+        *draw.position_mut() = 0;
+        for p in [p1, p2, p3, p4] {
+            draw.draw_card(p).unwrap();
+        }
+        let mut game = InteractiveGame::new_from_state(GameState::Draw(draw));
+
+        assert!(game.interact(Action::RequestUndo, p1, &logger).is_err());
+        game.interact(Action::Bid(cards::H_2, 1), p1, &logger)
+            .unwrap();
+        assert!(game.interact(Action::RequestUndo, p2, &logger).is_err());
+
+        // A single rejection cancels the undo.
+        game.interact(Action::RequestUndo, p1, &logger).unwrap();
+        assert!(game
+            .interact(Action::Bid(cards::S_2, 1), p2, &logger)
+            .is_err());
+        assert!(game
+            .interact(Action::VoteOnUndo(true), p1, &logger)
+            .is_err());
+        game.interact(Action::VoteOnUndo(true), p2, &logger)
+            .unwrap();
+        game.interact(Action::VoteOnUndo(false), p3, &logger)
+            .unwrap();
+        assert!(game.dump_state().unwrap().undo_vote().is_none());
+
+        // Everyone else approving rolls the bid back.
+        game.interact(Action::RequestUndo, p1, &logger).unwrap();
+        for p in [p2, p3] {
+            game.interact(Action::VoteOnUndo(true), p, &logger).unwrap();
+        }
+        match game.dump_state().unwrap() {
+            GameState::Draw(ref d) => assert_eq!(d.bids().len(), 1),
+            _ => panic!("Expected to still be drawing"),
+        }
+        let msgs = game
+            .interact(Action::VoteOnUndo(true), p4, &logger)
+            .unwrap();
+        assert!(matches!(
+            msgs.last(),
+            Some((b, _)) if matches!(b.variant(), MessageVariant::UndoApproved { requester } if *requester == p1)
+        ));
+        match game.dump_state().unwrap() {
+            GameState::Draw(ref d) => assert!(d.bids().is_empty()),
+            _ => panic!("Expected to still be drawing"),
+        }
+        assert!(game.dump_state().unwrap().undo_vote().is_none());
+
+        // Only the most recent move can be undone.
+        assert!(game.interact(Action::RequestUndo, p1, &logger).is_err());
+        game.interact(Action::Bid(cards::S_2, 1), p2, &logger)
+            .unwrap();
+        assert!(game.interact(Action::RequestUndo, p1, &logger).is_err());
+        game.interact(Action::RequestUndo, p2, &logger).unwrap();
+    }
+
+    #[test]
+    fn test_settings_proposal() {
+        use crate::interactive::{Action, InteractiveGame};
+        use slog::{o, Discard, Logger};
+
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let draw = init.start(PlayerID(0)).unwrap();
+        let mut game = InteractiveGame::new_from_state(GameState::Draw(draw));
+        let patch = |setting: &str, value: serde_json::Value| {
+            let mut patch = serde_json::Map::new();
+            patch.insert(setting.to_string(), value);
+            patch
+        };
+
+        // Settings which affect the cards can't be changed.
+        assert!(game
+            .interact(
+                Action::ProposeSettings(patch("num_decks", serde_json::json!(3))),
+                players[0],
+                &logger
+            )
+            .is_err());
+
+        // A single rejection cancels the proposal.
+        let penalty = patch("throw_penalty", serde_json::json!("TenPointsPerAttempt"));
+        game.interact(
+            Action::ProposeSettings(penalty.clone()),
+            players[0],
+            &logger,
+        )
+        .unwrap();
+        assert!(game
+            .interact(
+                Action::ProposeSettings(penalty.clone()),
+                players[1],
+                &logger
+            )
+            .is_err());
+        assert!(game
+            .interact(Action::VoteOnSettings(true), players[0], &logger)
+            .is_err());
+        game.interact(Action::VoteOnSettings(false), players[1], &logger)
+            .unwrap();
+        assert!(game.dump_state().unwrap().settings_proposal().is_none());
+
+        // Everyone else approving applies it.
+        game.interact(Action::ProposeSettings(penalty), players[0], &logger)
+            .unwrap();
+        for p in &players[1..3] {
+            game.interact(Action::VoteOnSettings(true), *p, &logger)
+                .unwrap();
         }
+        assert_eq!(game.dump_state().unwrap().throw_penalty, ThrowPenalty::None);
+        let msgs = game
+            .interact(Action::VoteOnSettings(true), players[3], &logger)
+            .unwrap();
+        assert!(matches!(
+            msgs.last(),
+            Some((b, _)) if matches!(b.variant(), MessageVariant::SettingsProposalApproved { .. })
+        ));
+        let state = game.dump_state().unwrap();
+        assert_eq!(state.throw_penalty, ThrowPenalty::TenPointsPerAttempt);
+        assert!(state.settings_proposal().is_none());
+    }
+
+    #[test]
+    fn test_kick_vote() {
+        let mut state = GameState::Initialize(InitializePhase::new());
+        let p1 = state.register("p1".into()).unwrap().0;
+        let p2 = state.register("p2".into()).unwrap().0;
+        let p3 = state.register("p3".into()).unwrap().0;
+        let p4 = state.register("p4".into()).unwrap().0;
+        assert_eq!(state.host(), Some(p1));
+
+        assert!(state.start_kick_vote(p1, p1).is_err());
+        assert!(state.vote_on_kick(p1, true).is_err());
+
+        // Two of the three voters rejecting means the vote can't pass.
+        state.start_kick_vote(p2, p4).unwrap();
+        assert!(state.start_kick_vote(p1, p3).is_err());
+        assert!(state.vote_on_kick(p4, false).is_err());
+        state.vote_on_kick(p1, false).unwrap();
+        let msgs = state.vote_on_kick(p3, false).unwrap();
+        assert!(matches!(
+            msgs.last(),
+            Some(MessageVariant::KickVoteFailed { target }) if *target == p4
+        ));
+        assert!(state.kick_vote().is_none());
+        assert_eq!(state.players().len(), 4);
+
+        // A majority approving kicks the player, and changing your mind
+        // replaces your earlier vote.
+        state.start_kick_vote(p1, p2).unwrap();
+        state.vote_on_kick(p1, false).unwrap();
+        state.vote_on_kick(p3, true).unwrap();
+        state.vote_on_kick(p1, true).unwrap();
+        assert!(!state.is_player(p2));
+        assert!(state.kick_vote().is_none());
+
+        // The host is handed to the next player when they leave.
+        state.kick(p1).unwrap();
+        assert_eq!(state.host(), Some(p3));
     }
 
     #[test]
@@ -1399,5 +3928,13 @@ mod tests {
             ],
             "Check that propagated players have the right new levels"
         );
+        assert_eq!(new_init_phase.propagated().landlord(), Some(p2));
+
+        // The rotation policy only applies when the landlord's team loses.
+        play.propagated_mut()
+            .set_landlord_rotation_policy(LandlordRotationPolicy::HighestBidder)
+            .unwrap();
+        let (new_init_phase, _, _) = play.finish_game().unwrap();
+        assert_eq!(new_init_phase.propagated().landlord(), Some(p2));
     }
 }
@@ -7,14 +7,19 @@ use serde::{Deserialize, Serialize};
 use shengji_mechanics::deck::Deck;
 use shengji_mechanics::hands::Hands;
 use shengji_mechanics::player::Player;
-use shengji_mechanics::scoring::{compute_level_deltas, next_threshold_reachable, GameScoreResult};
-use shengji_mechanics::trick::{PlayCards, PlayCardsMessage, Trick, TrickEnded, TrickUnit};
+use shengji_mechanics::scoring::{
+    compute_level_deltas, next_threshold_reachable, GameScoreResult, LandlordTeamSize, Shutout,
+};
+use shengji_mechanics::simulation::choose_greedy_follow;
+use shengji_mechanics::trick::{
+    PlayCards, PlayCardsMessage, PlayedCards, Trick, TrickEnded, TrickUnit,
+};
 use shengji_mechanics::types::{Card, PlayerID, Rank, Trump};
 
 use crate::message::MessageVariant;
 use crate::settings::{
-    AdvancementPolicy, GameMode, KittyPenalty, MultipleJoinPolicy, PlayTakebackPolicy,
-    PropagatedState, ThrowPenalty,
+    AdvancementPolicy, AttackingPointsVisibility, GameMode, LandlordRotationPolicy,
+    MultipleJoinPolicy, PlayTakebackPolicy, PropagatedState,
 };
 
 use crate::game_state::initialize_phase::InitializePhase;
@@ -36,6 +41,24 @@ pub struct PlayerGameFinishedResult {
     pub ranks_up: usize,
     pub confetti: bool,
     pub rank: Rank,
+    /// The points in the tricks the player won.
+    #[serde(default)]
+    pub points_captured: usize,
+    /// The most cards the player has had to take back from a failed throw.
+    #[serde(default)]
+    pub largest_failed_throw: usize,
+}
+
+/// A trick which has been won, as kept in the trick history.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CompletedTrick {
+    pub played_cards: Vec<PlayedCards>,
+    pub winner: PlayerID,
+    /// The points the winner took, including any from the kitty.
+    pub points: usize,
+    /// The players who discarded face-down in the trick.
+    #[serde(default)]
+    pub face_down: Vec<PlayerID>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -46,6 +69,11 @@ pub struct PlayPhase {
     hands: Hands,
     points: HashMap<PlayerID, Vec<Card>>,
     penalties: HashMap<PlayerID, usize>,
+    #[serde(default)]
+    tricks_won: HashMap<PlayerID, usize>,
+    /// The most cards each player has had to take back from a failed throw.
+    #[serde(default)]
+    largest_failed_throws: HashMap<PlayerID, usize>,
     kitty: Vec<Card>,
     landlord: PlayerID,
     landlords_team: Vec<PlayerID>,
@@ -53,12 +81,27 @@ pub struct PlayPhase {
     trump: Trump,
     trick: Trick,
     last_trick: Option<Trick>,
+    /// The most recent tricks, oldest first, up to the trick history length.
+    /// This isn't sent along with the rest of the state; players fetch it
+    /// when they want to look back over the game.
+    #[serde(default)]
+    trick_history: Vec<CompletedTrick>,
+    /// The cards played in every trick which has been finished so far.
+    #[serde(default)]
+    cards_played: Vec<Card>,
     game_ended_early: bool,
     #[serde(default)]
     removed_cards: Vec<Card>,
     #[serde(default)]
     decks: Vec<Deck>,
     player_requested_reset: Option<PlayerID>,
+    /// Set when redacting for a player who can't see the attacking team's
+    /// points yet.
+    #[serde(default)]
+    attacking_points_hidden: bool,
+    /// The number of cards in the bid which set the trump, if any.
+    #[serde(default)]
+    landlord_bid_size: usize,
 }
 
 impl PlayPhase {
@@ -75,6 +118,7 @@ impl PlayPhase {
         landlords_team: Vec<PlayerID>,
         removed_cards: Vec<Card>,
         decks: Vec<Deck>,
+        landlord_bid_size: usize,
     ) -> Result<Self, Error> {
         let landlord_idx = bail_unwrap!(propagated.players.iter().position(|p| p.id == landlord));
         Ok(PlayPhase {
@@ -91,6 +135,8 @@ impl PlayPhase {
                 .map(|p| (p.id, Vec::new()))
                 .collect(),
             penalties: propagated.players.iter().map(|p| (p.id, 0)).collect(),
+            tricks_won: propagated.players.iter().map(|p| (p.id, 0)).collect(),
+            largest_failed_throws: HashMap::new(),
             num_decks,
             game_mode,
             hands,
@@ -104,7 +150,11 @@ impl PlayPhase {
             decks,
             game_ended_early: false,
             last_trick: None,
+            trick_history: Vec::new(),
+            cards_played: Vec::new(),
             player_requested_reset: None,
+            attacking_points_hidden: false,
+            landlord_bid_size,
         })
     }
 
@@ -124,10 +174,44 @@ impl PlayPhase {
         &self.game_mode
     }
 
+    pub fn landlord(&self) -> PlayerID {
+        self.landlord
+    }
+
     pub fn landlords_team(&self) -> &[PlayerID] {
         &self.landlords_team
     }
 
+    pub fn trump(&self) -> Trump {
+        self.trump
+    }
+
+    pub fn kitty(&self) -> &[Card] {
+        &self.kitty
+    }
+
+    pub fn decks(&self) -> &[Deck] {
+        &self.decks
+    }
+
+    pub fn removed_cards(&self) -> &[Card] {
+        &self.removed_cards
+    }
+
+    /// How many players ended up on the landlord's team, against how many
+    /// the game mode called for. The team can be smaller than expected if a
+    /// friend was never revealed.
+    pub fn landlord_team_size(&self) -> LandlordTeamSize {
+        let actual = self.landlords_team.len();
+        match self.game_mode {
+            GameMode::FindingFriends { num_friends, .. } => LandlordTeamSize {
+                actual,
+                expected: num_friends + 1,
+            },
+            GameMode::Tractor => LandlordTeamSize::full(actual),
+        }
+    }
+
     pub fn trick(&self) -> &Trick {
         &self.trick
     }
@@ -144,13 +228,218 @@ impl PlayPhase {
         &mut self.propagated
     }
 
+    pub fn cards_played(&self) -> &[Card] {
+        &self.cards_played
+    }
+
+    /// The tricks in the history which `player` is allowed to see. If they
+    /// can't see the attacking team's points, only the last trick is
+    /// shown, since adding up the rest would give the points away. Cards
+    /// which were played face-down stay hidden.
+    pub fn trick_history_for_player(&self, player: PlayerID) -> Vec<CompletedTrick> {
+        let start = if self.attacking_points_hidden_from(player) {
+            self.trick_history.len().saturating_sub(1)
+        } else {
+            0
+        };
+        self.trick_history[start..]
+            .iter()
+            .cloned()
+            .map(|mut trick| {
+                for played in &mut trick.played_cards {
+                    if self.propagated.hide_played_cards
+                        || (played.id != player && trick.face_down.contains(&played.id))
+                    {
+                        for card in &mut played.cards {
+                            *card = Card::Unknown;
+                        }
+                    }
+                }
+                trick
+            })
+            .collect()
+    }
+
+    fn game_ongoing(&self) -> bool {
+        !self.game_ended_early && (!self.hands.is_empty() || !self.trick.played_cards().is_empty())
+    }
+
+    /// Whether `player` can't see how many points the attacking team has
+    /// taken so far.
+    pub fn attacking_points_hidden_from(&self, player: PlayerID) -> bool {
+        self.game_ongoing()
+            && match self.propagated.attacking_points_visibility {
+                AttackingPointsVisibility::Visible => false,
+                AttackingPointsVisibility::HiddenFromLandlordTeam => {
+                    self.landlords_team.contains(&player)
+                }
+                AttackingPointsVisibility::HiddenFromAll => true,
+            }
+    }
+
+    /// The cards which `id` can't account for: everything which was dealt,
+    /// less their own hand, the cards which have been played, and the kitty
+    /// if they exchanged it.
+    pub fn unseen_cards(&self, id: PlayerID) -> Result<Vec<Card>, Error> {
+        let mut seen = Card::count(
+            self.hands
+                .get(id)?
+                .iter()
+                .flat_map(|(c, n)| std::iter::repeat_n(*c, *n))
+                .chain(self.cards_played.iter().copied())
+                .chain(
+                    self.trick
+                        .played_cards()
+                        .iter()
+                        .flat_map(|pc| pc.cards.iter().copied()),
+                )
+                .chain(self.kitty.iter().copied())
+                .chain(self.removed_cards.iter().copied()),
+        );
+        Ok(self
+            .decks
+            .iter()
+            .flat_map(|d| d.cards())
+            .filter(|c| match seen.get_mut(c) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .collect())
+    }
+
+    /// Replaces the cards that `id` can't see, in the other players' hands
+    /// and the kitty, with `cards`, so that the rest of the game can be
+    /// played out as if they were the real ones.
+    pub fn determinize(
+        &mut self,
+        id: PlayerID,
+        cards: impl IntoIterator<Item = Card>,
+    ) -> Result<(), Error> {
+        let mut cards = cards.into_iter();
+        let others = self
+            .propagated
+            .players
+            .iter()
+            .map(|p| p.id)
+            .filter(|p| *p != id)
+            .collect::<Vec<_>>();
+        for other in others {
+            let num_unknown = self
+                .hands
+                .get(other)?
+                .get(&Card::Unknown)
+                .copied()
+                .unwrap_or(0);
+            let dealt = cards.by_ref().take(num_unknown).collect::<Vec<_>>();
+            if dealt.len() != num_unknown {
+                bail!("Not enough cards to fill the other players' hands");
+            }
+            self.hands
+                .remove(other, std::iter::repeat_n(Card::Unknown, num_unknown))?;
+            self.hands.add(other, dealt)?;
+        }
+        for card in self.kitty.iter_mut().filter(|c| **c == Card::Unknown) {
+            *card = cards
+                .next()
+                .ok_or_else(|| anyhow!("Not enough cards to fill the kitty"))?;
+        }
+        Ok(())
+    }
+
+    /// Whether leading with `cards` is a throw which one of the other
+    /// players might be able to beat, assuming that they could be holding any
+    /// of the cards that `id` hasn't seen. If so, returns the penalty that a
+    /// failed throw would cost.
+    pub fn throw_could_fail(&self, id: PlayerID, cards: &[Card]) -> Result<Option<usize>, Error> {
+        if self.trick.trick_format().is_some() || self.trick.next_player() != Some(id) {
+            bail!("Only the player leading the trick can throw");
+        }
+        let unseen = self.unseen_cards(id)?;
+        let mut world = self.clone();
+        // A single opponent holding every unseen card is the worst case.
+        if let Some(opponent) = world.trick.player_queue().find(|p| *p != id) {
+            let hand = Card::cards(world.hands.get(opponent)?.iter())
+                .copied()
+                .collect::<Vec<_>>();
+            world.hands.remove(opponent, hand)?;
+            world.hands.add(opponent, unseen)?;
+        }
+        world.play_cards(id, cards)?;
+        let failed_throw_size = world
+            .trick
+            .played_cards()
+            .first()
+            .map(|pc| pc.bad_throw_cards.len())
+            .unwrap_or(0);
+        Ok(if failed_throw_size > 0 {
+            Some(self.propagated.throw_penalty.points(failed_throw_size))
+        } else {
+            None
+        })
+    }
+
     pub fn can_play_cards(&self, id: PlayerID, cards: &[Card]) -> Result<(), Error> {
         if self.game_ended_early {
             bail!("Game has already ended; cards can't be played");
         }
-        Ok(self
-            .trick
-            .can_play_cards(id, &self.hands, cards, self.propagated.trick_draw_policy)?)
+        Ok(self.trick.can_play_cards(
+            id,
+            &self.hands,
+            cards,
+            self.propagated.trick_draw_policy,
+            self.propagated.trick_play_policy,
+            self.propagated.throw_evaluation_policy,
+            self.propagated.throw_tie_policy,
+        )?)
+    }
+
+    /// Every set of cards the player could legally play to follow the
+    /// current trick. Leads aren't restricted to a format, so this is only
+    /// available once someone has led. There can be exponentially many, so
+    /// callers should cap how many they take, or fall back to
+    /// `lowest_legal_play`.
+    pub fn legal_follows(
+        &self,
+        id: PlayerID,
+    ) -> Result<impl Iterator<Item = Vec<Card>> + '_, Error> {
+        if self.trick.trick_format().is_none() {
+            bail!("Nobody has led the trick yet");
+        }
+        Ok(self.trick.legal_follows(
+            id,
+            &self.hands,
+            self.propagated.trick_draw_policy,
+            self.propagated.trick_play_policy,
+            self.propagated.throw_evaluation_policy,
+            self.propagated.throw_tie_policy,
+        )?)
+    }
+
+    /// The cards to play on behalf of a player who ran out of time: the
+    /// lowest single card when leading, and otherwise the lowest cards which
+    /// follow the trick's format (and beat the winner, if they have to).
+    pub fn lowest_legal_play(&self, id: PlayerID) -> Result<Vec<Card>, Error> {
+        let hand = self.hands.get(id)?;
+        let trump = self.trick.trump();
+        let cards = match self.trick.trick_format() {
+            Some(tf) => {
+                let greedy = choose_greedy_follow(tf, hand, self.propagated.trick_draw_policy);
+                match self.can_play_cards(id, &greedy) {
+                    Ok(()) => greedy,
+                    Err(_) => self.legal_follows(id)?.next().unwrap_or(greedy),
+                }
+            }
+            None => Card::cards(hand.iter())
+                .copied()
+                .min_by(|a, b| trump.compare(*a, *b))
+                .into_iter()
+                .collect(),
+        };
+        self.can_play_cards(id, &cards)?;
+        Ok(cards)
     }
 
     pub fn play_cards(
@@ -170,18 +459,21 @@ impl PlayPhase {
         if self.game_ended_early {
             bail!("Game has already ended; cards can't be played");
         }
+        let face_down = self.propagated.face_down_discards && self.trick.is_discard(cards);
 
         let mut msgs = self.trick.play_cards(PlayCards {
             id,
             hands: &mut self.hands,
             cards,
             trick_draw_policy: self.propagated.trick_draw_policy,
+            trick_play_policy: self.propagated.trick_play_policy,
             throw_eval_policy: self.propagated.throw_evaluation_policy,
+            throw_tie_policy: self.propagated.throw_tie_policy,
             format_hint,
             hide_throw_halting_player: self.propagated.hide_throw_halting_player,
             tractor_requirements: self.propagated.tractor_requirements,
         })?;
-        if self.propagated.hide_played_cards {
+        if self.propagated.hide_played_cards || face_down {
             for msg in &mut msgs {
                 match msg {
                     PlayCardsMessage::PlayedCards { ref mut cards, .. } => {
@@ -222,9 +514,12 @@ impl PlayPhase {
         if self.propagated.play_takeback_policy == PlayTakebackPolicy::NoPlayTakeback {
             bail!("Taking back played cards is not allowed")
         }
-        Ok(self
-            .trick
-            .take_back(id, &mut self.hands, self.propagated.throw_evaluation_policy)?)
+        Ok(self.trick.take_back(
+            id,
+            &mut self.hands,
+            self.propagated.throw_evaluation_policy,
+            self.propagated.throw_tie_policy,
+        )?)
     }
 
     pub fn finish_trick(&mut self) -> Result<Vec<MessageVariant>, Error> {
@@ -238,23 +533,35 @@ impl PlayPhase {
             failed_throw_size,
         } = self.trick.complete()?;
 
-        let kitty_multipler = match self.propagated.kitty_penalty {
-            KittyPenalty::Times => 2 * largest_trick_unit_size,
-            KittyPenalty::Power => 2usize.pow(largest_trick_unit_size as u32),
-        };
+        let kitty_multipler = self
+            .propagated
+            .kitty_penalty
+            .multiplier(largest_trick_unit_size);
 
+        let penalty = self.propagated.throw_penalty.points(failed_throw_size);
         if failed_throw_size > 0 {
-            match self.propagated.throw_penalty {
-                ThrowPenalty::None => (),
-                ThrowPenalty::TenPointsPerAttempt => {
-                    if let Some(id) = self.trick.played_cards().first().map(|pc| pc.id) {
-                        *self.penalties.entry(id).or_insert(0) += 10;
-                    }
+            if let Some(id) = self.trick.played_cards().first().map(|pc| pc.id) {
+                let largest = self.largest_failed_throws.entry(id).or_insert(0);
+                *largest = (*largest).max(failed_throw_size);
+                if penalty > 0 {
+                    *self.penalties.entry(id).or_insert(0) += penalty;
                 }
             }
         }
 
         let mut msgs = vec![];
+        if self.propagated.face_down_discards && !self.propagated.hide_played_cards {
+            msgs.extend(
+                self.trick
+                    .played_cards()
+                    .iter()
+                    .filter(|played| self.trick.is_discard(&played.cards))
+                    .map(|played| MessageVariant::FaceDownCardsRevealed {
+                        player: played.id,
+                        cards: played.cards.clone(),
+                    }),
+            );
+        }
         if let GameMode::FindingFriends {
             ref mut friends, ..
         } = self.game_mode
@@ -298,6 +605,7 @@ impl PlayPhase {
                 }
             }
         }
+        *self.tricks_won.entry(winner).or_insert(0) += 1;
         let points = bail_unwrap!(self.points.get_mut(&winner));
         let kitty_points = self
             .kitty
@@ -312,6 +620,17 @@ impl PlayPhase {
                     cards: self.kitty.clone(),
                 });
             }
+            if self.propagated.reveal_kitty_card_by_card {
+                msgs.extend(
+                    self.kitty
+                        .iter()
+                        .map(|card| MessageVariant::KittyCardRevealed {
+                            card: *card,
+                            points: card.points().unwrap_or(0) * kitty_multipler,
+                            multiplier: kitty_multipler,
+                        }),
+                );
+            }
             for _ in 0..kitty_multipler {
                 new_points.extend(kitty_points.iter().copied());
             }
@@ -323,14 +642,15 @@ impl PlayPhase {
             }
         }
         let winner_idx = bail_unwrap!(self.propagated.players.iter().position(|p| p.id == winner));
+        let trick_points = new_points.iter().flat_map(|c| c.points()).sum::<usize>();
         if !new_points.is_empty() {
             let trump = self.trump;
-            let num_points = new_points.iter().flat_map(|c| c.points()).sum::<usize>();
+            let suit_order = self.propagated.suit_order;
             points.extend(new_points);
-            points.sort_by(|a, b| trump.compare(*a, *b));
+            points.sort_by(|a, b| suit_order.compare(trump, *a, *b));
             msgs.push(MessageVariant::TrickWon {
                 winner: self.propagated.players[winner_idx].id,
-                points: num_points,
+                points: trick_points,
             });
         } else {
             msgs.push(MessageVariant::TrickWon {
@@ -345,6 +665,31 @@ impl PlayPhase {
                 self.propagated.players[idx].id
             }),
         );
+        self.cards_played.extend(
+            self.trick
+                .played_cards()
+                .iter()
+                .flat_map(|pc| pc.cards.iter().copied()),
+        );
+        self.trick_history.push(CompletedTrick {
+            played_cards: self.trick.played_cards().to_vec(),
+            winner,
+            points: trick_points,
+            face_down: if self.propagated.face_down_discards {
+                self.trick
+                    .played_cards()
+                    .iter()
+                    .filter(|played| self.trick.is_discard(&played.cards))
+                    .map(|played| played.id)
+                    .collect()
+            } else {
+                vec![]
+            },
+        });
+        if let Some(length) = self.propagated.trick_history_length {
+            let excess = self.trick_history.len().saturating_sub(length);
+            self.trick_history.drain(..excess);
+        }
         self.last_trick = Some(std::mem::replace(&mut self.trick, new_trick));
 
         Ok(msgs)
@@ -359,9 +704,19 @@ impl PlayPhase {
         landlord_won: bool,
         landlord: (PlayerID, Rank),
         advancement_policy: AdvancementPolicy,
+        advancement_gates: &[Rank],
+        rank_sequence: Option<&[Rank]>,
         max_rank: Rank,
     ) -> Vec<MessageVariant> {
         let mut msgs = vec![];
+        let next_rank = |r: Rank| match rank_sequence {
+            Some(sequence) => sequence
+                .iter()
+                .position(|s| *s == r)
+                .and_then(|idx| sequence.get(idx + 1))
+                .copied(),
+            None => r.successor(),
+        };
 
         let result = players
             .map(|player| {
@@ -382,7 +737,7 @@ impl PlayPhase {
                         | (AdvancementPolicy::DefendPoints, r)
                         | (AdvancementPolicy::DefendPoints, r)
                             if r == max_rank
-                                || (r.successor() == Some(max_rank)
+                                || (next_rank(r) == Some(max_rank)
                                     && max_rank == Rank::NoTrump) =>
                         {
                             true
@@ -396,20 +751,28 @@ impl PlayPhase {
                         | (AdvancementPolicy::Unrestricted, _)
                         | (AdvancementPolicy::DefendPoints, _) => false,
                     };
+                    // Additional gates can only be passed by defending a game played at that
+                    // rank, regardless of the advancement policy.
+                    let is_gate = advancement_gates.contains(&player.rank());
+                    let must_defend = must_defend || is_gate;
                     // In order to advance past NoTrump, the landlord must also be defending
                     // NoTrump.
-                    let landlord_must_defend = must_defend && player.rank() == Rank::NoTrump;
+                    let landlord_must_defend =
+                        must_defend && (is_gate || player.rank() == Rank::NoTrump);
 
                     if must_defend
                         && (!is_defending
                             || bump_idx > 0
-                            || (landlord_must_defend && landlord.1 != Rank::NoTrump))
+                            || (landlord_must_defend && landlord.1 != player.rank()))
                     {
                         was_blocked = true;
                         break;
                     }
 
-                    player.advance(max_rank);
+                    match rank_sequence {
+                        Some(sequence) => player.advance_in_sequence(sequence),
+                        None => player.advance(max_rank),
+                    }
                     num_advances += 1;
                 }
                 if num_advances > 0 {
@@ -437,6 +800,8 @@ impl PlayPhase {
                             && is_defending
                             && initial_rank == max_rank,
                         rank: initial_rank,
+                        points_captured: 0,
+                        largest_failed_throw: 0,
                     },
                 )
             })
@@ -477,6 +842,50 @@ impl PlayPhase {
         (non_landlords_points, observed_points)
     }
 
+    /// How the game would turn out if it ended with the points taken so far,
+    /// leaving out any shutout bonus.
+    pub fn projected_score(&self) -> Result<GameScoreResult, Error> {
+        let (non_landlords_points, _) = self.calculate_points();
+        compute_level_deltas(
+            &self.propagated.game_scoring_parameters,
+            &self.decks,
+            non_landlords_points,
+            self.landlord_team_size(),
+            None,
+            self.landlord_bid_size,
+        )
+    }
+
+    /// Whether the attacking team was shut out, ignoring any penalties. A
+    /// game which was ended early is never a shutout, since the attacking
+    /// team didn't get to play out their hands.
+    pub fn shutout(&self) -> Option<Shutout> {
+        if self.game_ended_early {
+            return None;
+        }
+        let attacking = |id: &PlayerID| !self.landlords_team.contains(id);
+        let tricks_won = self
+            .tricks_won
+            .iter()
+            .filter(|(id, _)| attacking(id))
+            .map(|(_, n)| *n)
+            .sum::<usize>();
+        let points = self
+            .points
+            .iter()
+            .filter(|(id, _)| attacking(id))
+            .flat_map(|(_, cards)| cards)
+            .flat_map(|c| c.points())
+            .sum::<usize>();
+        if tricks_won == 0 {
+            Some(Shutout::Big)
+        } else if points == 0 {
+            Some(Shutout::Small)
+        } else {
+            None
+        }
+    }
+
     pub fn game_finished(&self) -> bool {
         self.game_ended_early || self.hands.is_empty() && self.trick.played_cards().is_empty()
     }
@@ -491,7 +900,8 @@ impl PlayPhase {
             &self.decks,
             non_landlords_points,
             observed_points,
-        )?;
+        )?
+        .reachable;
 
         if can_end_early {
             self.game_ended_early = true;
@@ -508,19 +918,9 @@ impl PlayPhase {
         }
 
         let (non_landlords_points, _) = self.calculate_points();
+        let shutout = self.shutout();
 
-        let mut smaller_landlord_team = false;
-
-        if let GameMode::FindingFriends {
-            num_friends,
-            friends: _,
-        } = &self.game_mode
-        {
-            let setting_team_size = *num_friends + 1;
-
-            let actual_team_size = self.landlords_team.len();
-            smaller_landlord_team = actual_team_size < setting_team_size;
-        }
+        let landlord_team_size = self.landlord_team_size();
 
         let mut propagated = self.propagated.clone();
 
@@ -533,24 +933,47 @@ impl PlayPhase {
             &propagated.game_scoring_parameters,
             &self.decks,
             non_landlords_points,
-            smaller_landlord_team,
+            landlord_team_size,
+            shutout,
+            self.landlord_bid_size,
         )?;
 
         msgs.push(MessageVariant::EndOfGameSummary {
             landlord_won,
             non_landlords_points,
+            pace: self.propagated.pace.summary(&self.propagated.players),
         });
 
         if bonus_level_earned {
             msgs.push(MessageVariant::BonusLevelEarned);
         };
 
+        if let Some(shutout) = shutout {
+            let levels = propagated
+                .game_scoring_parameters
+                .shutout_bonus(Some(shutout));
+            if landlord_won && levels > 0 {
+                msgs.push(MessageVariant::ShutoutBonusEarned { shutout, levels });
+            }
+        }
+
+        let bid_defense_levels = propagated
+            .game_scoring_parameters
+            .bid_defense_bonus(self.landlord_bid_size);
+        if bid_defense_levels > 0 {
+            msgs.push(MessageVariant::BidDefenseBonusEarned {
+                landlord_won,
+                bid_size: self.landlord_bid_size,
+                levels: bid_defense_levels,
+            });
+        }
+
         let landlord_idx = bail_unwrap!(propagated
             .players
             .iter()
             .position(|p| p.id == self.landlord));
 
-        msgs.extend(Self::compute_player_level_deltas(
+        let mut level_msgs = Self::compute_player_level_deltas(
             propagated.players.iter_mut(),
             non_landlord_level_bump,
             landlord_level_bump,
@@ -558,21 +981,66 @@ impl PlayPhase {
             landlord_won,
             (self.landlord, self.propagated.players[landlord_idx].level),
             propagated.advancement_policy,
+            &propagated.advancement_gates,
+            propagated.rank_sequence.as_deref(),
             *propagated.max_rank,
-        ));
+        );
+        for msg in &mut level_msgs {
+            if let MessageVariant::GameFinished { result } = msg {
+                for player in &self.propagated.players {
+                    if let Some(r) = result.get_mut(&player.name) {
+                        r.points_captured = self
+                            .points
+                            .get(&player.id)
+                            .into_iter()
+                            .flatten()
+                            .flat_map(|c| c.points())
+                            .sum();
+                        r.largest_failed_throw = self
+                            .largest_failed_throws
+                            .get(&player.id)
+                            .copied()
+                            .unwrap_or(0);
+                    }
+                }
+            }
+        }
+        msgs.extend(level_msgs);
+
+        let landlord_rank = self.propagated.players[landlord_idx].level;
+        if landlord_won && propagated.match_target_rank == Some(landlord_rank) {
+            msgs.extend(propagated.finish_match(&self.landlords_team));
+        }
 
-        let mut idx = (landlord_idx + 1) % propagated.players.len();
-        let (next_landlord, next_landlord_idx) = loop {
-            if landlord_won == self.landlords_team.contains(&propagated.players[idx].id) {
-                break (propagated.players[idx].id, idx);
+        let next_on_team = || {
+            let mut idx = (landlord_idx + 1) % propagated.players.len();
+            loop {
+                if landlord_won == self.landlords_team.contains(&propagated.players[idx].id) {
+                    break propagated.players[idx].id;
+                }
+                idx = (idx + 1) % propagated.players.len()
             }
-            idx = (idx + 1) % propagated.players.len()
+        };
+        let next_landlord = match propagated.landlord_rotation_policy {
+            _ if landlord_won => Some(next_on_team()),
+            LandlordRotationPolicy::NextOnTeam => Some(next_on_team()),
+            LandlordRotationPolicy::Clockwise => {
+                Some(propagated.players[(landlord_idx + 1) % propagated.players.len()].id)
+            }
+            LandlordRotationPolicy::WinnerOfLastTrick => Some(
+                self.last_trick
+                    .as_ref()
+                    .and_then(|t| t.complete().ok())
+                    .map(|t| t.winner)
+                    .unwrap_or_else(next_on_team),
+            ),
+            LandlordRotationPolicy::HighestBidder => None,
         };
 
-        msgs.push(MessageVariant::NewLandlordForNextGame {
-            landlord: propagated.players[next_landlord_idx].id,
-        });
-        propagated.set_landlord(Some(next_landlord))?;
+        if let Some(landlord) = next_landlord {
+            msgs.push(MessageVariant::NewLandlordForNextGame { landlord });
+        }
+        propagated.set_landlord(next_landlord)?;
         propagated.num_games_finished += 1;
         msgs.extend(propagated.make_all_observers_into_players()?);
 
@@ -630,11 +1098,27 @@ impl PlayPhase {
             }
         }
         // Don't redact at the end of the game.
-        let game_ongoing = !self.game_ended_early
-            && (!self.hands.is_empty() || !self.trick.played_cards().is_empty());
-        if game_ongoing {
+        let game_ongoing = self.game_ongoing();
+        if game_ongoing || self.propagated.is_spectator(player) {
             self.hands.destructively_redact_except_for_player(player);
         }
+        if self.propagated.face_down_discards {
+            self.trick
+                .destructively_redact_discards_except_for_player(player);
+        }
+        self.attacking_points_hidden = self.attacking_points_hidden_from(player);
+        self.trick_history.clear();
+        if self.attacking_points_hidden {
+            for (k, v) in self.points.iter_mut() {
+                if !self.landlords_team.contains(k) {
+                    v.clear();
+                }
+            }
+            // Penalties would also reveal the attacking team's total.
+            for v in self.penalties.values_mut() {
+                *v = 0;
+            }
+        }
         if game_ongoing && player != self.exchanger {
             for card in &mut self.kitty {
                 *card = Card::Unknown;
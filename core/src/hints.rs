@@ -0,0 +1,145 @@
+//! Annotations which point out what's going on in a trick, for a teaching
+//! overlay. They're computed from what the player can see of the game, so
+//! they never give away anything the player couldn't have worked out.
+
+use anyhow::Error;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use shengji_mechanics::trick::TrickUnit;
+use shengji_mechanics::types::{Card, EffectiveSuit, PlayerID};
+
+use crate::game_state::play_phase::PlayPhase;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Hint {
+    /// The player is out of the suit which was led, but has trump which
+    /// could win the trick.
+    CanRuff { suit: EffectiveSuit },
+    /// The player's team is winning the trick, so points played into it are
+    /// likely to be kept.
+    TeamWinningTrick { points: usize },
+    /// The selected cards are a throw which one of the other players might
+    /// be able to beat, costing `penalty` points.
+    RiskyThrow { penalty: usize },
+    /// Leading the selected trump could draw out the last `remaining` trump
+    /// cards which the other players might hold.
+    DrawsLastTrumps { remaining: usize },
+    /// None of the other players can have any trump left.
+    OthersOutOfTrump,
+}
+
+impl Hint {
+    pub fn description(&self) -> String {
+        match self {
+            Hint::CanRuff { suit } => format!(
+                "You are void in {} and can ruff with a trump",
+                suit_name(*suit)
+            ),
+            Hint::TeamWinningTrick { points: 0 } => {
+                "Your team is winning this trick, so it's a good time to play points".to_string()
+            }
+            Hint::TeamWinningTrick { points } => {
+                format!("Your team is winning this trick, which already has {points} points")
+            }
+            Hint::RiskyThrow { penalty: 0 } => {
+                "Someone may be able to beat this throw, which would force you to play less"
+                    .to_string()
+            }
+            Hint::RiskyThrow { penalty } => {
+                format!("Someone may be able to beat this throw, forfeiting {penalty} points")
+            }
+            Hint::DrawsLastTrumps { remaining } => {
+                format!("This could draw out the last {remaining} trump that the others might have")
+            }
+            Hint::OthersOutOfTrump => "Nobody else has any trump left".to_string(),
+        }
+    }
+}
+
+fn suit_name(suit: EffectiveSuit) -> &'static str {
+    match suit {
+        EffectiveSuit::Clubs => "♣",
+        EffectiveSuit::Diamonds => "♦",
+        EffectiveSuit::Spades => "♠",
+        EffectiveSuit::Hearts => "♥",
+        EffectiveSuit::Trump => "trump",
+        EffectiveSuit::Unknown => "that suit",
+    }
+}
+
+/// The hints for `id`, who's considering playing `selected`, which may be
+/// empty. Hints are only given on the player's own turn.
+pub fn hints(phase: &PlayPhase, id: PlayerID, selected: &[Card]) -> Result<Vec<Hint>, Error> {
+    let mut hints = vec![];
+    let trick = phase.trick();
+    if phase.game_finished() || trick.next_player() != Some(id) {
+        return Ok(hints);
+    }
+    let trump = phase.trump();
+    let hand = phase.hands().get(id)?;
+    let is_trump = |c: &Card| trump.effective_suit(*c) == EffectiveSuit::Trump;
+    let unseen_trump = phase
+        .unseen_cards(id)?
+        .into_iter()
+        .filter(|c| is_trump(c))
+        .count();
+
+    match trick.trick_format() {
+        Some(tf) => {
+            let void = !hand
+                .iter()
+                .any(|(c, n)| *n > 0 && trump.effective_suit(*c) == tf.suit());
+            if tf.suit() != EffectiveSuit::Trump && void && hand.keys().any(is_trump) {
+                hints.push(Hint::CanRuff { suit: tf.suit() });
+            }
+            let team = phase.landlords_team();
+            if let Some(winner) = trick.current_winner() {
+                if team.contains(&winner) == team.contains(&id) {
+                    let points = trick
+                        .played_cards()
+                        .iter()
+                        .flat_map(|pc| pc.cards.iter().flat_map(|c| c.points()))
+                        .sum();
+                    hints.push(Hint::TeamWinningTrick { points });
+                }
+            }
+        }
+        None => {
+            if unseen_trump == 0 {
+                hints.push(Hint::OthersOutOfTrump);
+            }
+            let one_suit = selected
+                .first()
+                .map(|first| {
+                    selected
+                        .iter()
+                        .all(|c| trump.effective_suit(*c) == trump.effective_suit(*first))
+                })
+                .unwrap_or(false);
+            if one_suit {
+                if is_trump(&selected[0]) && unseen_trump > 0 {
+                    let num_others = trick.player_queue().count() - 1;
+                    if unseen_trump <= selected.len() * num_others {
+                        hints.push(Hint::DrawsLastTrumps {
+                            remaining: unseen_trump,
+                        });
+                    }
+                }
+                let is_throw = TrickUnit::find_plays(
+                    trump,
+                    phase.propagated().tractor_requirements,
+                    selected.iter().copied(),
+                )
+                .into_iter()
+                .all(|units| units.len() > 1);
+                if is_throw {
+                    if let Ok(Some(penalty)) = phase.throw_could_fail(id, selected) {
+                        hints.push(Hint::RiskyThrow { penalty });
+                    }
+                }
+            }
+        }
+    }
+    Ok(hints)
+}
@@ -4,25 +4,39 @@ use serde::{Deserialize, Serialize};
 use slog::{debug, info, o, Logger};
 
 use shengji_mechanics::bidding::{
-    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy,
+    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy, JokersOnlyTrumpPolicy,
 };
 use shengji_mechanics::deck::Deck;
-use shengji_mechanics::scoring::GameScoringParameters;
+use shengji_mechanics::scoring::{GameScoringParameters, KittyPenalty};
 use shengji_mechanics::trick::{
-    ThrowEvaluationPolicy, TractorRequirements, TrickDrawPolicy, TrickUnit,
+    ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, TrickDrawPolicy, TrickPlayPolicy,
+    TrickUnit,
 };
-use shengji_mechanics::types::{Card, PlayerID, Rank};
+use shengji_mechanics::types::{Card, Number, PlayerID, Rank, SuitOrder};
 
-use crate::game_state::{initialize_phase::InitializePhase, GameState};
+use crate::bot::Bot;
+use crate::game_state::{
+    initialize_phase::InitializePhase, play_phase::CompletedTrick, GameState, TimedTurn,
+};
 use crate::message::MessageVariant;
+use crate::phase::{Phase, PhaseAction};
+use crate::replay::{quiet, Replay};
 use crate::settings::{
-    AdvancementPolicy, FirstLandlordSelectionPolicy, FriendSelection, FriendSelectionPolicy,
-    GameModeSettings, GameShadowingPolicy, GameStartPolicy, GameVisibility, KittyBidPolicy,
-    KittyPenalty, KittyTheftPolicy, MultipleJoinPolicy, PlayTakebackPolicy, PropagatedState,
-    ThrowPenalty,
+    AdvancementPolicy, AttackingPointsVisibility, FirstDealLandlordPolicy,
+    FirstLandlordSelectionPolicy, FriendSelection, FriendSelectionPolicy,
+    FriendSelectionRestrictions, GameModeSettings, GameShadowingPolicy, GameStartPolicy,
+    GameVisibility, KittyBidPolicy, KittyBuryPolicy, KittyTheftPolicy, LandlordRotationPolicy,
+    MultipleJoinPolicy, PlayTakebackPolicy, PropagatedState, RematchPolicy, ThrowPenalty,
+    TimeoutPolicy, TurnTimers,
 };
+use crate::settings_diff::SettingsPatch;
+/// A game whose state only ever changes by applying a `GameEvent`. The
+/// events applied since the game was loaded are kept, so that the current
+/// state can always be rebuilt from the one it was loaded with; an
+/// `EventLog` keeps them across loads.
 pub struct InteractiveGame {
     state: GameState,
+    events: Vec<GameEvent>,
 }
 
 impl InteractiveGame {
@@ -31,18 +45,139 @@ impl InteractiveGame {
     }
 
     pub fn new_from_state(state: GameState) -> Self {
-        Self { state }
+        Self {
+            state,
+            events: vec![],
+        }
+    }
+
+    /// Rebuilds a game by applying `events`, in order, to `state`.
+    pub fn new_from_events(
+        state: GameState,
+        events: impl IntoIterator<Item = GameEvent>,
+        logger: &Logger,
+    ) -> Result<Self, Error> {
+        let mut game = Self::new_from_state(state);
+        for event in events {
+            game.apply(event, logger)?;
+        }
+        Ok(game)
     }
 
     pub fn into_state(self) -> GameState {
         self.state
     }
 
+    pub(crate) fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// The events applied since the game was loaded, oldest first.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Applies `event` and records it, unless it fails, in which case the
+    /// state is left untouched.
+    fn apply(
+        &mut self,
+        event: GameEvent,
+        logger: &Logger,
+    ) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
+        let snapshot = self.state.clone();
+        match self.apply_to_state(&event, logger) {
+            Ok(result) => {
+                self.events.push(event);
+                Ok(result)
+            }
+            Err(e) => {
+                self.state = snapshot;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the player responsible for the event, along with what
+    /// happened.
+    fn apply_to_state(
+        &mut self,
+        event: &GameEvent,
+        logger: &Logger,
+    ) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
+        match *event {
+            GameEvent::Joined {
+                ref name,
+                spectator: false,
+            } => self.state.register(name.clone()),
+            GameEvent::Joined {
+                ref name,
+                spectator: true,
+            } => self.state.register_spectator(name.clone()),
+            GameEvent::Reattached { player_id } => self.state.reattach(player_id),
+            GameEvent::Kicked { actor, target } => {
+                if self.state.is_spectator(actor) {
+                    bail!("Spectators can't kick other players");
+                }
+                Ok((actor, self.state.kick(target)?))
+            }
+            GameEvent::Banned { actor, target } => {
+                if self.state.host() != Some(actor) {
+                    bail!("Only the host can ban other users");
+                }
+                if actor == target {
+                    bail!("You can't ban yourself");
+                }
+                let name = self.state.player_name(target)?.to_owned();
+                let mut msgs = vec![MessageVariant::Banned { name }];
+                msgs.extend(self.state.kick(target)?);
+                Ok((actor, msgs))
+            }
+            GameEvent::Acted {
+                actor,
+                ref action,
+                seed,
+            } => Ok((actor, self.act(action.clone(), actor, seed, logger)?)),
+            GameEvent::MarkedIdle { player } => {
+                if self.state.idle_players().contains(&player) {
+                    bail!("The player is already idle");
+                }
+                self.state.mark_idle(player);
+                Ok((player, vec![MessageVariant::MarkedIdle]))
+            }
+        }
+    }
+
     pub fn register(
         &mut self,
         name: String,
     ) -> Result<(PlayerID, Vec<(BroadcastMessage, String)>), Error> {
-        let (actor, msgs) = self.state.register(name)?;
+        let event = GameEvent::Joined {
+            name,
+            spectator: false,
+        };
+        let (actor, msgs) = self.apply(event, &quiet())?;
+
+        Ok((actor, self.hydrate_messages(actor, msgs)?))
+    }
+
+    pub fn register_spectator(
+        &mut self,
+        name: String,
+    ) -> Result<(PlayerID, Vec<(BroadcastMessage, String)>), Error> {
+        let event = GameEvent::Joined {
+            name,
+            spectator: true,
+        };
+        let (actor, msgs) = self.apply(event, &quiet())?;
+
+        Ok((actor, self.hydrate_messages(actor, msgs)?))
+    }
+
+    pub fn reattach(
+        &mut self,
+        id: PlayerID,
+    ) -> Result<(PlayerID, Vec<(BroadcastMessage, String)>), Error> {
+        let (actor, msgs) = self.apply(GameEvent::Reattached { player_id: id }, &quiet())?;
 
         Ok((actor, self.hydrate_messages(actor, msgs)?))
     }
@@ -52,7 +187,18 @@ impl InteractiveGame {
         actor: PlayerID,
         target: PlayerID,
     ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
-        let msgs = self.state.kick(target)?;
+        let (actor, msgs) = self.apply(GameEvent::Kicked { actor, target }, &quiet())?;
+        self.hydrate_messages(actor, msgs)
+    }
+
+    /// Kicks the target, for the server to keep them from rejoining. Only
+    /// the host can ban other users.
+    pub fn ban(
+        &mut self,
+        actor: PlayerID,
+        target: PlayerID,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        let (actor, msgs) = self.apply(GameEvent::Banned { actor, target }, &quiet())?;
         self.hydrate_messages(actor, msgs)
     }
 
@@ -68,6 +214,22 @@ impl InteractiveGame {
         Ok(self.state.for_player(id))
     }
 
+    pub fn trick_history_for_player(&self, id: PlayerID) -> Vec<CompletedTrick> {
+        self.state.trick_history_for(id)
+    }
+
+    pub fn timed_turn(&self) -> Option<TimedTurn> {
+        self.state.timed_turn()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.pause().is_some()
+    }
+
+    pub fn is_ranked(&self) -> bool {
+        self.state.ranked()
+    }
+
     pub fn next_player(&self) -> Result<PlayerID, Error> {
         self.state.next_player()
     }
@@ -76,13 +238,246 @@ impl InteractiveGame {
         self.state.player_name(player_id)
     }
 
-    #[allow(clippy::cognitive_complexity)]
     pub fn interact(
         &mut self,
         msg: Action,
         id: PlayerID,
         logger: &Logger,
     ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        self.interact_with_seed(msg, id, rand::random(), logger)
+    }
+
+    /// Like `interact`, but also records the action into `replay`. Starting
+    /// a game begins a new replay.
+    pub fn interact_recorded(
+        &mut self,
+        msg: Action,
+        id: PlayerID,
+        replay: &mut Option<Replay>,
+        timestamp_ms: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        let seed = rand::random();
+        let initial_state = match (&msg, &self.state) {
+            (Action::StartGame, GameState::Initialize(state)) => {
+                // Replays are public once the game is over, unlike the webhook.
+                let mut state = state.clone();
+                state.webhook_url = None;
+                Some(GameState::Initialize(state))
+            }
+            _ => None,
+        };
+        let in_progress = !matches!(self.state, GameState::Initialize(_));
+        let msgs = self.interact_timed(msg.clone(), id, seed, timestamp_ms, logger)?;
+        match initial_state {
+            Some(initial_state) => {
+                *replay = Some(Replay::new(initial_state, id, seed, timestamp_ms));
+            }
+            None if in_progress => {
+                if let Some(replay) = replay {
+                    replay.record(id, msg, timestamp_ms);
+                }
+            }
+            None => (),
+        }
+        Ok(msgs)
+    }
+
+    /// Like `interact_with_seed`, but also times the action for the pace
+    /// summary at the end of the game.
+    pub fn interact_timed(
+        &mut self,
+        msg: Action,
+        id: PlayerID,
+        seed: u64,
+        timestamp_ms: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        let in_progress = !matches!(self.state, GameState::Initialize(_));
+        let leading =
+            matches!(&self.state, GameState::Play(p) if p.trick().played_cards().is_empty());
+        let msgs = self.interact_with_seed(msg.clone(), id, seed, logger)?;
+        if in_progress {
+            self.track_pace(id, &msg, leading, timestamp_ms);
+        } else if !matches!(self.state, GameState::Initialize(_)) {
+            self.state.pace_mut().record_action(timestamp_ms);
+        }
+        Ok(msgs)
+    }
+
+    /// Times bids and plays, and the tricks which they make up, for the
+    /// summary at the end of the game.
+    fn track_pace(&mut self, id: PlayerID, action: &Action, leading: bool, timestamp_ms: u64) {
+        let trick_complete = match &self.state {
+            GameState::Play(p) => {
+                !p.trick().played_cards().is_empty() && p.trick().player_queue().next().is_none()
+            }
+            _ => false,
+        };
+        let pace = self.state.pace_mut();
+        match action {
+            Action::Bid(_, _) => pace.record_move(id, timestamp_ms),
+            Action::PlayCards(_) | Action::PlayCardsWithHint(_, _) => {
+                if leading {
+                    pace.start_trick();
+                }
+                pace.record_move(id, timestamp_ms);
+                if trick_complete {
+                    pace.finish_trick(timestamp_ms);
+                }
+            }
+            _ => pace.record_action(timestamp_ms),
+        }
+    }
+
+    /// Handles a player running out of time on `turn`, according to the
+    /// room's timeout policy.
+    pub fn time_out(
+        &mut self,
+        turn: TimedTurn,
+        replay: &mut Option<Replay>,
+        timestamp_ms: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        if self.state.timed_turn() != Some(turn) {
+            bail!("The turn has already moved on");
+        }
+        if self.state.pause().is_some() {
+            bail!("The game is paused");
+        }
+        let player = turn.player;
+        let mut msgs = vec![];
+        let mut acted = false;
+        if self.state.turn_timers().timeout_policy == TimeoutPolicy::AutoPlay {
+            let actions = match &self.state {
+                GameState::Draw(_) => vec![Action::DrawCard, Action::PickUpKitty],
+                GameState::Exchange(_) => vec![Action::PutDownKitty, Action::BeginPlay],
+                GameState::Play(p) => p
+                    .lowest_legal_play(player)
+                    .map(|cards| vec![Action::PlayCards(cards)])
+                    .unwrap_or_default(),
+                GameState::Initialize(_) => vec![],
+            };
+            for action in actions {
+                if let Ok(m) = self.interact_recorded(action, player, replay, timestamp_ms, logger)
+                {
+                    acted = true;
+                    msgs.extend(m);
+                }
+            }
+        }
+        if !acted {
+            let (_, msgs) = self.apply(GameEvent::MarkedIdle { player }, logger)?;
+            self.hydrate_messages(player, msgs)
+        } else {
+            let mut out = self.hydrate_messages(player, vec![MessageVariant::TimedOut])?;
+            out.extend(msgs);
+            Ok(out)
+        }
+    }
+
+    /// Has the first bot with something to do take its most preferred
+    /// legal action, as chosen by `strategy`.
+    pub fn act_for_bots(
+        &mut self,
+        strategy: &dyn Bot,
+        replay: &mut Option<Replay>,
+        timestamp_ms: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        for bot in self.state.bots().to_vec() {
+            for action in strategy.actions(&self.state, bot) {
+                if let Ok(msgs) = self.interact_recorded(action, bot, replay, timestamp_ms, logger)
+                {
+                    return Ok(msgs);
+                }
+            }
+        }
+        bail!("None of the bots have anything to do")
+    }
+
+    /// The phase the game is in.
+    pub fn phase(&self) -> Phase {
+        Phase::of(&self.state)
+    }
+
+    /// Takes an action which moves the game along, failing without changing
+    /// anything if the action isn't for the phase the game is in.
+    pub fn advance(
+        &mut self,
+        action: impl Into<PhaseAction>,
+        id: PlayerID,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        self.advance_with_seed(action, id, rand::random(), logger)
+    }
+
+    /// Like `advance`, but using `seed` to shuffle the deck if the action
+    /// starts the game.
+    pub fn advance_with_seed(
+        &mut self,
+        action: impl Into<PhaseAction>,
+        id: PlayerID,
+        seed: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        let action = action.into();
+        let phase = self.phase();
+        if action.phase() != phase {
+            bail!(
+                "That action is for the {} phase, but the game is in the {} phase",
+                action.phase(),
+                phase
+            );
+        }
+        self.interact_with_seed(action.into(), id, seed, logger)
+    }
+
+    /// Applies the action, using `seed` to shuffle the deck if it starts the
+    /// game.
+    pub fn interact_with_seed(
+        &mut self,
+        msg: Action,
+        id: PlayerID,
+        seed: u64,
+        logger: &Logger,
+    ) -> Result<Vec<(BroadcastMessage, String)>, Error> {
+        let event = GameEvent::Acted {
+            actor: id,
+            action: msg,
+            seed,
+        };
+        let (_, msgs) = self.apply(event, logger)?;
+        self.hydrate_messages(id, msgs)
+    }
+
+    #[allow(clippy::cognitive_complexity)]
+    fn act(
+        &mut self,
+        msg: Action,
+        id: PlayerID,
+        seed: u64,
+        logger: &Logger,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if self.state.is_spectator(id)
+            && !matches!(msg, Action::RequestToCoach(_) | Action::StopCoaching)
+        {
+            bail!("Spectators can't take actions");
+        }
+        let is_play = msg.is_play();
+        if is_play && self.state.pause().is_some() {
+            bail!("The game is paused");
+        }
+        if is_play && self.state.undo_vote().is_some() {
+            bail!("Wait for the vote on undoing the last move to finish");
+        }
+        let before = match msg {
+            Action::Bid(_, _) | Action::PlayCards(_) | Action::PlayCardsWithHint(_, _) => {
+                Some(self.state.clone())
+            }
+            _ => None,
+        };
+        self.state.mark_active(id);
         let logger = logger.new(o!(
             "num_players" => self.state.players.len(),
             "num_observers" => self.state.observers.len(),
@@ -99,14 +494,64 @@ impl InteractiveGame {
                 info!(logger, "Cancelling game reset request");
                 self.state.cancel_reset()?
             }
+            (Action::StartKickVote(target), _) => {
+                info!(logger, "Starting vote to kick"; "target" => target.0);
+                self.state.start_kick_vote(id, target)?
+            }
+            (Action::VoteOnKick(approve), _) => {
+                info!(logger, "Voting on kick"; "approve" => approve);
+                self.state.vote_on_kick(id, approve)?
+            }
+            (Action::RequestToCoach(student), _) => {
+                info!(logger, "Requesting to coach"; "student" => student.0);
+                self.state.request_to_coach(id, student)?
+            }
+            (Action::RespondToCoach(coach, approve), _) => {
+                info!(logger, "Responding to coach"; "coach" => coach.0, "approve" => approve);
+                self.state.respond_to_coach(id, coach, approve)?
+            }
+            (Action::StopCoaching, _) => {
+                info!(logger, "Stopping coaching");
+                self.state.stop_coaching(id)?
+            }
+            (Action::RequestUndo, _) => {
+                info!(logger, "Requesting undo");
+                self.state.request_undo(id)?
+            }
+            (Action::VoteOnUndo(approve), _) => {
+                info!(logger, "Voting on undo"; "approve" => approve);
+                self.state.vote_on_undo(id, approve)?
+            }
+            (Action::ProposeSettings(patch), _) => {
+                info!(logger, "Proposing settings"; "num_settings" => patch.len());
+                self.state.propose_settings(id, patch)?
+            }
+            (Action::VoteOnSettings(approve), _) => {
+                info!(logger, "Voting on settings"; "approve" => approve);
+                self.state.vote_on_settings(id, approve)?
+            }
+            (Action::RequestPause, _) => {
+                info!(logger, "Pausing game");
+                self.state.request_pause(id)?
+            }
+            (Action::VoteToResume, _) => {
+                info!(logger, "Voting to resume game");
+                self.state.vote_to_resume(id)?
+            }
             (Action::SetChatLink(ref link), _) => {
                 self.state.set_chat_link(link.clone())?;
                 vec![]
             }
+            (Action::SetWebhookUrl(ref url), GameState::Initialize(ref mut state)) => {
+                if state.host() != Some(id) {
+                    bail!("Only the host can set the webhook");
+                }
+                state.set_webhook_url(url.clone())?
+            }
             (Action::StartGame, GameState::Initialize(ref mut state)) => {
                 let s: &'_ PropagatedState = state;
                 info!(logger, "Starting game"; s);
-                self.state = GameState::Draw(state.start(id)?);
+                self.state = GameState::Draw(state.start_with_seed(id, seed)?);
                 vec![MessageVariant::StartingGame]
             }
             (Action::ReorderPlayers(ref players), GameState::Initialize(ref mut state)) => {
@@ -122,6 +567,14 @@ impl InteractiveGame {
                 info!(logger, "Making observer a player"; "id" => id.0);
                 state.make_player(id)?
             }
+            (Action::AddBot, GameState::Initialize(ref mut state)) => {
+                if state.host() != Some(id) {
+                    bail!("Only the host can add bots");
+                }
+                let (bot, msgs) = state.add_bot()?;
+                info!(logger, "Adding bot"; "bot" => bot.0);
+                msgs
+            }
             (Action::SetNumDecks(num_decks), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting number of decks"; "num_decks" => num_decks);
                 state.set_num_decks(num_decks)?
@@ -145,6 +598,10 @@ impl InteractiveGame {
                 state.set_max_rank(rank)?;
                 vec![MessageVariant::SetMaxRank { rank }]
             }
+            (Action::SetRankSequence(ref sequence), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting rank sequence"; "sequence" => format!("{sequence:?}"));
+                state.set_rank_sequence(sequence.clone())?
+            }
             (Action::SetKittySize(size), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting kitty size"; "size" => size);
                 state.set_kitty_size(size)?.into_iter().collect()
@@ -153,6 +610,13 @@ impl InteractiveGame {
                 info!(logger, "Setting friend selection policy"; "policy" => policy);
                 state.set_friend_selection_policy(policy)?
             }
+            (
+                Action::SetFriendSelectionRestrictions(restrictions),
+                GameState::Initialize(ref mut state),
+            ) => {
+                info!(logger, "Setting friend selection restrictions"; "restrictions" => restrictions);
+                state.set_friend_selection_restrictions(restrictions)?
+            }
             (Action::SetMultipleJoinPolicy(policy), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting multiple join policy"; "policy" => policy);
                 state.set_multiple_join_policy(policy)?
@@ -164,6 +628,14 @@ impl InteractiveGame {
                 info!(logger, "Setting first landlord selection policy"; "policy" => policy);
                 state.set_first_landlord_selection_policy(policy)?
             }
+            (Action::SetFirstDealLandlordPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting first deal landlord policy"; "policy" => policy);
+                state.set_first_deal_landlord_policy(policy)?
+            }
+            (Action::SetLandlordRotationPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting landlord rotation policy"; "policy" => policy);
+                state.set_landlord_rotation_policy(policy)?
+            }
             (Action::SetBidPolicy(policy), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting bid selection policy"; "policy" => policy);
                 state.set_bid_policy(policy)?
@@ -176,6 +648,14 @@ impl InteractiveGame {
                 info!(logger, "Setting joker bid selection policy"; "policy" => policy);
                 state.set_joker_bid_policy(policy)?
             }
+            (Action::SetNoTrumpOnlyFrom(level), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting no-trump-only level"; "level" => level.map(|l| l.as_str()));
+                state.set_no_trump_only_from(level)?
+            }
+            (Action::SetJokersOnlyTrumpPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting jokers-only trump policy"; "policy" => policy);
+                state.set_jokers_only_trump_policy(policy)?
+            }
             (
                 Action::SetShouldRevealKittyAtEndOfGame(should_reveal),
                 GameState::Initialize(ref mut state),
@@ -183,6 +663,13 @@ impl InteractiveGame {
                 info!(logger, "Setting should reveal kitty at end of game"; "should_reveal" => should_reveal);
                 state.set_should_reveal_kitty_at_end_of_game(should_reveal)?
             }
+            (
+                Action::SetRevealKittyCardByCard(card_by_card),
+                GameState::Initialize(ref mut state),
+            ) => {
+                info!(logger, "Setting reveal kitty card by card"; "card_by_card" => card_by_card);
+                state.set_reveal_kitty_card_by_card(card_by_card)?
+            }
             (Action::SetLandlord(landlord), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting landlord"; "landlord" => landlord.map(|l| l.0));
                 state.set_landlord(landlord)?;
@@ -220,14 +707,68 @@ impl InteractiveGame {
                 info!(logger, "Setting hide throw halting player"; "hide_throw_halting_player" => hide_throw_halting_player);
                 state.set_hide_throw_halting_player(hide_throw_halting_player)?
             }
+            (Action::SetFaceDownDiscards(face_down), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting face-down discards"; "face_down" => face_down);
+                state.set_face_down_discards(face_down)?
+            }
             (Action::SetGameMode(game_mode), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting game mode"; "game_mode" => game_mode.variant());
                 state.set_game_mode(game_mode)?
             }
+            (
+                Action::SetFixedPartnerships(fixed_partnerships),
+                GameState::Initialize(ref mut state),
+            ) => {
+                info!(logger, "Setting fixed partnerships"; "fixed_partnerships" => fixed_partnerships);
+                state.set_fixed_partnerships(fixed_partnerships)?
+            }
             (Action::SetGameVisibility(visibility), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting game visibility"; "visibility" => visibility);
                 state.set_game_visibility(visibility)?
             }
+            (
+                Action::SetAttackingPointsVisibility(visibility),
+                GameState::Initialize(ref mut state),
+            ) => {
+                info!(logger, "Setting attacking points visibility"; "visibility" => visibility);
+                state.set_attacking_points_visibility(visibility)?
+            }
+            (Action::SetMatchTargetRank(rank), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting match target rank"; "rank" => format!("{rank:?}"));
+                state.set_match_target_rank(rank)?
+            }
+            (Action::SetTurnTimers(timers), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting turn timers"; "timers" => timers);
+                state.set_turn_timers(timers)?
+            }
+            (Action::SetSpectatorDelay(secs), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting spectator delay"; "secs" => secs);
+                state.set_spectator_delay_secs(secs)?
+            }
+            (Action::SetRanked(ranked), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting ranked"; "ranked" => ranked);
+                state.set_ranked(ranked)?
+            }
+            (Action::SetDealSeed(seed), GameState::Initialize(ref mut state)) => {
+                if state.host() != Some(id) {
+                    bail!("Only the host can fix the deal");
+                }
+                info!(logger, "Setting deal seed");
+                state.set_deal_seed(seed)?
+            }
+            (Action::MergeSettings(ref patch), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Merging settings"; "num_settings" => patch.len());
+                let changes = state.merge_settings(patch)?;
+                if changes.is_empty() {
+                    vec![]
+                } else {
+                    vec![MessageVariant::SettingsChanged { changes }]
+                }
+            }
+            (Action::SetRematchPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting rematch policy"; "policy" => policy);
+                state.set_rematch_policy(policy)?
+            }
             (Action::SetKittyPenalty(kitty_penalty), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting kitty penalty"; "penalty" => kitty_penalty);
                 state.set_kitty_penalty(kitty_penalty)?
@@ -244,6 +785,10 @@ impl InteractiveGame {
                 info!(logger, "Setting advancement policy"; "policy" => policy);
                 state.set_advancement_policy(policy)?
             }
+            (Action::SetAdvancementGates(ref gates), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting advancement gates"; "gates" => format!("{gates:?}"));
+                state.set_advancement_gates(gates.clone())?
+            }
             (
                 Action::SetGameScoringParameters(ref parameters),
                 GameState::Initialize(ref mut state),
@@ -259,6 +804,14 @@ impl InteractiveGame {
                 info!(logger, "Setting throw evaluation policy"; "policy" => policy);
                 state.set_throw_evaluation_policy(policy)?
             }
+            (Action::SetThrowTiePolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting throw tie policy"; "policy" => policy);
+                state.set_throw_tie_policy(policy)?
+            }
+            (Action::SetTrickPlayPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting trick play policy"; "policy" => policy);
+                state.set_trick_play_policy(policy)?
+            }
             (Action::SetPlayTakebackPolicy(policy), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting play takeback policy"; "policy" => policy);
                 state.set_play_takeback_policy(policy)?
@@ -271,6 +824,22 @@ impl InteractiveGame {
                 info!(logger, "Setting kitty theft policy"; "policy" => policy);
                 state.set_kitty_theft_policy(policy)?
             }
+            (Action::SetKittyBuryPolicy(policy), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting kitty bury policy"; "policy" => policy);
+                state.set_kitty_bury_policy(policy)?
+            }
+            (Action::SetTrickHistoryLength(length), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting trick history length"; "length" => length);
+                state.set_trick_history_length(length)?
+            }
+            (Action::SetMaxKittyPoints(max), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting max kitty points"; "max" => max);
+                state.set_max_kitty_points(max)?
+            }
+            (Action::SetSuitOrder(order), GameState::Initialize(ref mut state)) => {
+                info!(logger, "Setting suit order"; "order" => order);
+                state.set_suit_order(order)?
+            }
             (Action::SetGameShadowingPolicy(policy), GameState::Initialize(ref mut state)) => {
                 info!(logger, "Setting user multiple game session policy"; "policy" => policy);
                 state.set_user_multiple_game_session_policy(policy)?
@@ -288,8 +857,7 @@ impl InteractiveGame {
             }
             (Action::DrawCard, GameState::Draw(ref mut state)) => {
                 debug!(logger, "Drawing card");
-                state.draw_card(id)?;
-                vec![]
+                state.draw_card(id)?
             }
             (Action::RevealCard, GameState::Draw(ref mut state)) => {
                 info!(logger, "Revealing card");
@@ -389,8 +957,11 @@ impl InteractiveGame {
             }
             _ => bail!("not supported in current phase"),
         };
+        if is_play {
+            self.state.set_undo_point(id, before);
+        }
 
-        self.hydrate_messages(id, msgs)
+        Ok(msgs)
     }
 
     fn hydrate_messages(
@@ -421,41 +992,83 @@ pub enum Action {
     ResetGame,
     MakeObserver(PlayerID),
     MakePlayer(PlayerID),
+    AddBot,
     SetChatLink(Option<String>),
+    SetWebhookUrl(Option<String>),
+    StartKickVote(PlayerID),
+    VoteOnKick(bool),
+    /// Asks a player to let this spectator see their hand.
+    RequestToCoach(PlayerID),
+    /// Lets the given spectator see this player's hand, or stops them from
+    /// seeing it.
+    RespondToCoach(PlayerID, bool),
+    StopCoaching,
+    RequestPause,
+    VoteToResume,
+    RequestUndo,
+    VoteOnUndo(bool),
     SetNumDecks(Option<usize>),
     SetSpecialDecks(Vec<Deck>),
     SetKittySize(Option<usize>),
     SetFriendSelectionPolicy(FriendSelectionPolicy),
+    SetFriendSelectionRestrictions(FriendSelectionRestrictions),
     SetMultipleJoinPolicy(MultipleJoinPolicy),
     SetFirstLandlordSelectionPolicy(FirstLandlordSelectionPolicy),
+    SetFirstDealLandlordPolicy(FirstDealLandlordPolicy),
+    SetLandlordRotationPolicy(LandlordRotationPolicy),
     SetBidPolicy(BidPolicy),
     SetBidReinforcementPolicy(BidReinforcementPolicy),
     SetJokerBidPolicy(JokerBidPolicy),
+    SetNoTrumpOnlyFrom(Option<Number>),
+    SetJokersOnlyTrumpPolicy(JokersOnlyTrumpPolicy),
     SetHideLandlordsPoints(bool),
     SetHidePlayedCards(bool),
     ReorderPlayers(Vec<PlayerID>),
     SetRank(Rank),
     SetMetaRank(usize),
     SetMaxRank(Rank),
+    SetRankSequence(Option<Vec<Rank>>),
     SetLandlord(Option<PlayerID>),
     SetLandlordEmoji(Option<String>),
     SetGameMode(GameModeSettings),
+    SetFixedPartnerships(bool),
     SetAdvancementPolicy(AdvancementPolicy),
+    SetAdvancementGates(Vec<Rank>),
     SetGameScoringParameters(GameScoringParameters),
     SetKittyPenalty(KittyPenalty),
     SetKittyBidPolicy(KittyBidPolicy),
     SetTrickDrawPolicy(TrickDrawPolicy),
     SetThrowPenalty(ThrowPenalty),
     SetThrowEvaluationPolicy(ThrowEvaluationPolicy),
+    SetThrowTiePolicy(ThrowTiePolicy),
+    SetTrickPlayPolicy(TrickPlayPolicy),
     SetPlayTakebackPolicy(PlayTakebackPolicy),
     SetBidTakebackPolicy(BidTakebackPolicy),
     SetKittyTheftPolicy(KittyTheftPolicy),
+    SetKittyBuryPolicy(KittyBuryPolicy),
+    SetMaxKittyPoints(Option<usize>),
+    SetTrickHistoryLength(Option<usize>),
+    SetSuitOrder(SuitOrder),
     SetGameShadowingPolicy(GameShadowingPolicy),
     SetGameStartPolicy(GameStartPolicy),
     SetShouldRevealKittyAtEndOfGame(bool),
+    SetRevealKittyCardByCard(bool),
     SetHideThrowHaltingPlayer(bool),
+    SetFaceDownDiscards(bool),
     SetTractorRequirements(TractorRequirements),
     SetGameVisibility(GameVisibility),
+    SetAttackingPointsVisibility(AttackingPointsVisibility),
+    SetMatchTargetRank(Option<Rank>),
+    SetRematchPolicy(RematchPolicy),
+    SetTurnTimers(TurnTimers),
+    SetSpectatorDelay(usize),
+    SetRanked(bool),
+    SetDealSeed(Option<u64>),
+    MergeSettings(SettingsPatch),
+    /// Proposes changing some of the settings in the middle of a game, which
+    /// only happens if every other player agrees.
+    ProposeSettings(SettingsPatch),
+    VoteOnSettings(bool),
     StartGame,
     DrawCard,
     RevealCard,
@@ -476,6 +1089,108 @@ pub enum Action {
     Beep,
 }
 
+impl Action {
+    /// Whether the action moves the game along, and so has to wait while
+    /// the game is paused.
+    fn is_play(&self) -> bool {
+        matches!(
+            self,
+            Action::DrawCard
+                | Action::RevealCard
+                | Action::Bid(_, _)
+                | Action::PickUpKitty
+                | Action::PutDownKitty
+                | Action::MoveCardToKitty(_)
+                | Action::MoveCardToHand(_)
+                | Action::SetFriends(_)
+                | Action::BeginPlay
+                | Action::PlayCards(_)
+                | Action::PlayCardsWithHint(_, _)
+                | Action::EndTrick
+                | Action::TakeBackCards
+                | Action::TakeBackBid
+                | Action::EndGameEarly
+                | Action::StartNewGame
+        )
+    }
+}
+
+/// A change to a game. Applying the same events to the same state always
+/// ends up in the same state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GameEvent {
+    Joined {
+        name: String,
+        spectator: bool,
+    },
+    Reattached {
+        player_id: PlayerID,
+    },
+    Kicked {
+        actor: PlayerID,
+        target: PlayerID,
+    },
+    Banned {
+        actor: PlayerID,
+        target: PlayerID,
+    },
+    /// A player took an action. `seed` shuffles the deck, if the action
+    /// started the game.
+    Acted {
+        actor: PlayerID,
+        action: Action,
+        seed: u64,
+    },
+    /// A player ran out of time, without anything being done for them.
+    MarkedIdle {
+        player: PlayerID,
+    },
+}
+
+/// Once a log holds this many events, it starts over from the latest state
+/// rather than growing for as long as the room lives.
+const MAX_LOGGED_EVENTS: usize = 1000;
+
+/// The events applied to a game across every time it was loaded, along with
+/// the state they were first applied to, so that the current state can be
+/// rebuilt by folding them over it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    /// The state before the first event, if anything has been recorded.
+    base: Option<GameState>,
+    events: Vec<GameEvent>,
+}
+
+impl EventLog {
+    /// Appends the events `game` applied since it was loaded from `before`.
+    pub fn record(&mut self, before: &GameState, game: &InteractiveGame) {
+        if game.events().is_empty() {
+            return;
+        }
+        if self.base.is_none() || self.events.len() + game.events().len() > MAX_LOGGED_EVENTS {
+            self.base = Some(before.clone());
+            self.events.clear();
+        }
+        self.events.extend(game.events().iter().cloned());
+    }
+
+    /// The recorded events, oldest first.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Rebuilds the game by applying every recorded event to the state the
+    /// log started from.
+    pub fn rebuild(&self, logger: &Logger) -> Result<InteractiveGame, Error> {
+        match self.base {
+            Some(ref base) => {
+                InteractiveGame::new_from_events(base.clone(), self.events.clone(), logger)
+            }
+            None => bail!("Nothing has been recorded"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BroadcastMessage {
     actor: PlayerID,
@@ -490,4 +1205,34 @@ impl BroadcastMessage {
     ) -> Result<String, Error> {
         self.variant.to_string(self.actor, player_name)
     }
+
+    pub fn actor(&self) -> PlayerID {
+        self.actor
+    }
+
+    pub fn variant(&self) -> &MessageVariant {
+        &self.variant
+    }
+
+    /// The message, and its text, as `id` should see it in `state`, if it
+    /// gives away anything they can't otherwise see.
+    pub fn redacted_for_player(
+        &self,
+        state: &GameState,
+        id: PlayerID,
+    ) -> Option<(BroadcastMessage, String)> {
+        let variant = match self.variant {
+            MessageVariant::TrickWon { winner, .. } if state.attacking_points_hidden_from(id) => {
+                MessageVariant::TrickWonPointsHidden { winner }
+            }
+            _ => return None,
+        };
+        let b = BroadcastMessage {
+            actor: self.actor,
+            actor_name: self.actor_name.clone(),
+            variant,
+        };
+        let s = b.to_string(|id| state.player_name(id)).ok()?;
+        Some((b, s))
+    }
 }
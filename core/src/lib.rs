@@ -5,7 +5,23 @@
 #![allow(clippy::upper_case_acronyms)]
 
 pub mod settings;
+pub mod settings_diff;
 
+pub mod achievements;
+pub mod bot;
+pub mod game_log;
 pub mod game_state;
+pub mod hints;
 pub mod interactive;
+pub mod mcts;
 pub mod message;
+pub mod notification;
+pub mod pace;
+pub mod phase;
+pub mod puzzle;
+pub mod replay;
+pub mod simulate;
+pub mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tutorial;
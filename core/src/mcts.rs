@@ -0,0 +1,238 @@
+//! A card-playing bot which searches for its best play with Monte Carlo tree
+//! search. Since the other players' hands are hidden, each simulation first
+//! deals the cards the bot hasn't seen at random, and then plays the game out
+//! from there.
+
+use rand::seq::SliceRandom;
+use shengji_mechanics::bidding::Bid;
+use shengji_mechanics::types::{Card, PlayerID};
+
+use crate::bot::{greedy_lead, legal_follows, seeded_rng, sorted_hand, Bot, GreedyBot};
+use crate::game_state::draw_phase::DrawPhase;
+use crate::game_state::exchange_phase::ExchangePhase;
+use crate::game_state::play_phase::PlayPhase;
+use crate::settings::FriendSelection;
+
+/// How hard an `MctsBot` tries to find a good play.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// The budget for each search.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MctsConfig {
+    /// The number of simulated games to play out for each decision.
+    pub num_simulations: usize,
+    /// How many tricks each simulation plays out before it's scored.
+    pub num_tricks: usize,
+    /// How much to favor trying out plays which haven't been simulated much,
+    /// over plays which have done well so far.
+    pub exploration: f64,
+}
+
+impl Difficulty {
+    pub fn config(self) -> MctsConfig {
+        let (num_simulations, num_tricks) = match self {
+            Difficulty::Easy => (16, 1),
+            Difficulty::Medium => (64, 3),
+            Difficulty::Hard => (256, usize::MAX),
+        };
+        MctsConfig {
+            num_simulations,
+            num_tricks,
+            exploration: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+/// Bids, exchanges and calls friends like a `GreedyBot`, but picks each
+/// play by simulating how the rest of the game might go after it. Every
+/// other player is assumed to play greedily.
+pub struct MctsBot {
+    config: MctsConfig,
+    seed: u64,
+}
+
+impl MctsBot {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self::with_config(difficulty.config(), seed)
+    }
+
+    pub fn with_config(config: MctsConfig, seed: u64) -> Self {
+        Self { config, seed }
+    }
+}
+
+impl Bot for MctsBot {
+    fn bid(&self, phase: &DrawPhase, id: PlayerID) -> Option<Bid> {
+        GreedyBot.bid(phase, id)
+    }
+
+    fn kitty(&self, phase: &ExchangePhase, id: PlayerID) -> Vec<Card> {
+        GreedyBot.kitty(phase, id)
+    }
+
+    fn friends(&self, phase: &ExchangePhase, id: PlayerID) -> Option<Vec<FriendSelection>> {
+        GreedyBot.friends(phase, id)
+    }
+
+    fn play(&self, phase: &PlayPhase, id: PlayerID) -> Option<Vec<Card>> {
+        let mut plays = candidate_plays(phase, id)?;
+        if plays.len() <= 1 {
+            return plays.pop();
+        }
+        let unseen = phase.unseen_cards(id).ok()?;
+        let played = phase
+            .trick()
+            .played_cards()
+            .iter()
+            .map(|p| (p.id, p.cards.clone()))
+            .collect::<Vec<_>>();
+        let mut rng = seeded_rng(
+            self.seed,
+            (
+                id,
+                sorted_hand(phase.hands().get(id).ok()?),
+                phase.cards_played().len(),
+                played,
+            ),
+        );
+
+        // Treat the choice of play as a multi-armed bandit, where pulling an
+        // arm simulates the rest of the game in a fresh deal.
+        let mut visits = vec![0usize; plays.len()];
+        let mut rewards = vec![0f64; plays.len()];
+        let mut unseen = unseen;
+        for total_visits in 0..self.config.num_simulations {
+            let arm = (0..plays.len())
+                .find(|i| visits[*i] == 0)
+                .unwrap_or_else(|| {
+                    let ln_n = (total_visits as f64).ln();
+                    (0..plays.len())
+                        .max_by(|a, b| {
+                            let ucb = |i: usize| {
+                                rewards[i] / visits[i] as f64
+                                    + self.config.exploration * (ln_n / visits[i] as f64).sqrt()
+                            };
+                            ucb(*a).total_cmp(&ucb(*b))
+                        })
+                        .unwrap()
+                });
+            unseen.shuffle(&mut rng);
+            let reward = simulate(phase, id, &plays[arm], &unseen, self.config.num_tricks);
+            visits[arm] += 1;
+            // A simulation which couldn't be played out still counts as a
+            // visit, so that the search doesn't get stuck retrying it.
+            rewards[arm] += reward.unwrap_or(-1.0);
+        }
+        let best = (0..plays.len()).max_by(|a, b| {
+            (visits[*a], rewards[*a] / visits[*a].max(1) as f64)
+                .partial_cmp(&(visits[*b], rewards[*b] / visits[*b].max(1) as f64))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        Some(plays.swap_remove(best))
+    }
+}
+
+/// The plays worth searching over: every legal follow, or, when leading,
+/// each card on its own and every copy of it together.
+fn candidate_plays(phase: &PlayPhase, id: PlayerID) -> Option<Vec<Vec<Card>>> {
+    if phase.trick().trick_format().is_some() {
+        return match legal_follows(phase, id) {
+            Some(follows) if !follows.is_empty() => Some(follows),
+            _ => phase.lowest_legal_play(id).ok().map(|cards| vec![cards]),
+        };
+    }
+    let mut cards = phase
+        .hands()
+        .get(id)
+        .ok()?
+        .iter()
+        .filter(|(_, n)| **n > 0)
+        .collect::<Vec<_>>();
+    cards.sort_by_key(|(c, _)| c.as_char());
+    let mut plays = vec![];
+    for (card, count) in cards {
+        plays.push(vec![*card]);
+        if *count > 1 {
+            plays.push(vec![*card; *count]);
+        }
+    }
+    Some(plays)
+}
+
+/// Plays `cards` in a game where `unseen` has been dealt to the other
+/// players, and then has everyone play greedily for `num_tricks` tricks.
+/// Returns the share of the points in play which `id`'s team gained,
+/// less the share which they gave up.
+fn simulate(
+    phase: &PlayPhase,
+    id: PlayerID,
+    cards: &[Card],
+    unseen: &[Card],
+    num_tricks: usize,
+) -> Option<f64> {
+    let mut world = phase.clone();
+    world.determinize(id, unseen.iter().copied()).ok()?;
+    let (initial_points, _) = world.calculate_points();
+    world.play_cards(id, cards).ok()?;
+
+    let mut tricks = 0;
+    while tricks < num_tricks && !world.game_finished() {
+        match world.trick().next_player() {
+            Some(next) => {
+                let play = if world.trick().trick_format().is_none() {
+                    greedy_lead(&world, next)?
+                } else {
+                    world.lowest_legal_play(next).ok()?
+                };
+                world.play_cards(next, &play).ok()?;
+            }
+            None => {
+                world.finish_trick().ok()?;
+                tricks += 1;
+            }
+        }
+    }
+
+    let (final_points, _) = world.calculate_points();
+    let total_points = world
+        .decks()
+        .iter()
+        .map(|d| d.points())
+        .sum::<usize>()
+        .max(1);
+    let gained = (final_points - initial_points) as f64 / total_points as f64;
+    if world.landlords_team().contains(&id) {
+        Some(-gained)
+    } else {
+        Some(gained)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use crate::bot::{Bot, GreedyBot};
+    use crate::settings::PropagatedState;
+    use crate::simulate::simulate;
+
+    use super::{Difficulty, MctsBot};
+
+    #[test]
+    fn test_mcts_bot_plays_a_game() {
+        let logger = Logger::root(Discard, o!());
+        let mcts = MctsBot::new(Difficulty::Easy, 5);
+        let strategies: [&dyn Bot; 4] = [&mcts, &GreedyBot, &mcts, &GreedyBot];
+        let result = simulate(PropagatedState::default(), &strategies, 11, &logger).unwrap();
+        let again = simulate(PropagatedState::default(), &strategies, 11, &logger).unwrap();
+        assert_eq!(
+            serde_json::to_value(&result.final_state).unwrap(),
+            serde_json::to_value(&again.final_state).unwrap()
+        );
+    }
+}
@@ -5,19 +5,25 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use shengji_mechanics::bidding::{
-    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy,
+    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy, JokersOnlyTrumpPolicy,
 };
 use shengji_mechanics::deck::Deck;
-use shengji_mechanics::scoring::GameScoringParameters;
-use shengji_mechanics::trick::{ThrowEvaluationPolicy, TractorRequirements, TrickDrawPolicy};
-use shengji_mechanics::types::{Card, PlayerID, Rank};
+use shengji_mechanics::scoring::{GameScoringParameters, KittyPenalty, Shutout};
+use shengji_mechanics::trick::{
+    ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, TrickDrawPolicy, TrickPlayPolicy,
+};
+use shengji_mechanics::types::{Card, Number, PlayerID, Rank, SuitOrder};
 
 use crate::game_state::play_phase::PlayerGameFinishedResult;
+use crate::pace::PaceSummary;
 use crate::settings::{
-    AdvancementPolicy, FirstLandlordSelectionPolicy, FriendSelectionPolicy, GameModeSettings,
-    GameShadowingPolicy, GameStartPolicy, GameVisibility, KittyBidPolicy, KittyPenalty,
-    KittyTheftPolicy, MultipleJoinPolicy, PlayTakebackPolicy, ThrowPenalty,
+    AdvancementPolicy, AttackingPointsVisibility, FirstDealLandlordPolicy,
+    FirstLandlordSelectionPolicy, FriendSelectionPolicy, FriendSelectionRestrictions,
+    GameModeSettings, GameShadowingPolicy, GameStartPolicy, GameVisibility, KittyBidPolicy,
+    KittyBuryPolicy, KittyTheftPolicy, LandlordRotationPolicy, MatchStanding, MultipleJoinPolicy,
+    PlayTakebackPolicy, RematchPolicy, ThrowPenalty, TimeoutPolicy, TurnTimers,
 };
+use crate::settings_diff::SettingChange;
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum MessageVariant {
@@ -29,6 +35,11 @@ pub enum MessageVariant {
         winner: PlayerID,
         points: usize,
     },
+    /// `TrickWon`, as seen by a player who can't see the attacking team's
+    /// points.
+    TrickWonPointsHidden {
+        winner: PlayerID,
+    },
     RankAdvanced {
         player: PlayerID,
         new_rank: Rank,
@@ -47,6 +58,13 @@ pub enum MessageVariant {
     EndOfGameKittyReveal {
         cards: Vec<Card>,
     },
+    /// One card in the bottom, revealed at the end of the game. `points`
+    /// includes the kitty multiplier.
+    KittyCardRevealed {
+        card: Card,
+        points: usize,
+        multiplier: usize,
+    },
     JoinedGame {
         player: PlayerID,
     },
@@ -61,21 +79,86 @@ pub enum MessageVariant {
     LeftGame {
         name: String,
     },
+    KickVoteStarted {
+        target: PlayerID,
+    },
+    KickVoteCast {
+        approve: bool,
+    },
+    KickVotePassed {
+        name: String,
+    },
+    KickVoteFailed {
+        target: PlayerID,
+    },
+    Banned {
+        name: String,
+    },
+    CoachingRequested {
+        student: PlayerID,
+    },
+    CoachingStarted {
+        coach: PlayerID,
+    },
+    CoachingDeclined {
+        coach: PlayerID,
+    },
+    CoachingEnded {
+        coach: PlayerID,
+        student: PlayerID,
+    },
+    GamePaused,
+    ResumeVoteCast,
+    GameResumed,
+    UndoRequested,
+    UndoVoteCast {
+        approve: bool,
+    },
+    UndoApproved {
+        requester: PlayerID,
+    },
+    UndoRejected,
+    SettingsProposed {
+        changes: Vec<SettingChange>,
+    },
+    SettingsProposalVoteCast {
+        approve: bool,
+    },
+    SettingsProposalApproved {
+        changes: Vec<SettingChange>,
+    },
+    SettingsProposalRejected,
     AdvancementPolicySet {
         policy: AdvancementPolicy,
     },
+    AdvancementGatesSet {
+        gates: Vec<Rank>,
+    },
     KittySizeSet {
         size: Option<usize>,
     },
     FriendSelectionPolicySet {
         policy: FriendSelectionPolicy,
     },
+    FriendSelectionRestrictionsSet {
+        restrictions: FriendSelectionRestrictions,
+    },
     MultipleJoinPolicySet {
         policy: MultipleJoinPolicy,
     },
     FirstLandlordSelectionPolicySet {
         policy: FirstLandlordSelectionPolicy,
     },
+    FirstDealLandlordPolicySet {
+        policy: FirstDealLandlordPolicy,
+    },
+    FirstDealLandlordSelected {
+        player: PlayerID,
+        card: Card,
+    },
+    LandlordRotationPolicySet {
+        policy: LandlordRotationPolicy,
+    },
     BidPolicySet {
         policy: BidPolicy,
     },
@@ -85,9 +168,18 @@ pub enum MessageVariant {
     JokerBidPolicySet {
         policy: JokerBidPolicy,
     },
+    NoTrumpOnlyFromSet {
+        level: Option<Number>,
+    },
+    JokersOnlyTrumpPolicySet {
+        policy: JokersOnlyTrumpPolicy,
+    },
     ShouldRevealKittyAtEndOfGameSet {
         should_reveal: bool,
     },
+    RevealKittyCardByCardSet {
+        card_by_card: bool,
+    },
     SpecialDecksSet {
         special_decks: Vec<Deck>,
     },
@@ -103,9 +195,55 @@ pub enum MessageVariant {
     KittyTheftPolicySet {
         policy: KittyTheftPolicy,
     },
+    KittyBuryPolicySet {
+        policy: KittyBuryPolicy,
+    },
+    TrickHistoryLengthSet {
+        length: Option<usize>,
+    },
+    MaxKittyPointsSet {
+        max: Option<usize>,
+    },
+    SuitOrderSet {
+        order: SuitOrder,
+    },
     GameVisibilitySet {
         visibility: GameVisibility,
     },
+    AttackingPointsVisibilitySet {
+        visibility: AttackingPointsVisibility,
+    },
+    MatchTargetRankSet {
+        rank: Option<Rank>,
+    },
+    RematchPolicySet {
+        policy: RematchPolicy,
+    },
+    TurnTimersSet {
+        timers: TurnTimers,
+    },
+    SpectatorDelaySet {
+        secs: usize,
+    },
+    RankedSet {
+        ranked: bool,
+    },
+    DealSeedSet {
+        fixed: bool,
+    },
+    SettingsChanged {
+        changes: Vec<SettingChange>,
+    },
+    TimedOut,
+    MarkedIdle,
+    WebhookSet {
+        enabled: bool,
+    },
+    MatchComplete {
+        winners: Vec<PlayerID>,
+        standings: Vec<MatchStanding>,
+    },
+    RanksResetForRematch,
     TookBackPlay,
     TookBackBid,
     PlayedCards {
@@ -136,6 +274,9 @@ pub enum MessageVariant {
     SetMaxRank {
         rank: Rank,
     },
+    RankSequenceSet {
+        sequence: Option<Vec<Rank>>,
+    },
     MadeBid {
         card: Card,
         count: usize,
@@ -155,6 +296,12 @@ pub enum MessageVariant {
     ThrowEvaluationPolicySet {
         policy: ThrowEvaluationPolicy,
     },
+    ThrowTiePolicySet {
+        policy: ThrowTiePolicy,
+    },
+    TrickPlayPolicySet {
+        policy: TrickPlayPolicy,
+    },
     PlayTakebackPolicySet {
         policy: PlayTakebackPolicy,
     },
@@ -179,13 +326,37 @@ pub enum MessageVariant {
         result: HashMap<String, PlayerGameFinishedResult>,
     },
     BonusLevelEarned,
+    ShutoutBonusEarned {
+        shutout: Shutout,
+        levels: usize,
+    },
+    BidDefenseBonusEarned {
+        landlord_won: bool,
+        bid_size: usize,
+        levels: usize,
+    },
     EndOfGameSummary {
         landlord_won: bool,
         non_landlords_points: isize,
+        /// How long each player took over their bids and plays.
+        #[serde(default)]
+        pace: PaceSummary,
     },
     HideThrowHaltingPlayer {
         set: bool,
     },
+    FaceDownDiscardsSet {
+        face_down: bool,
+    },
+    /// The cards a player discarded face-down, turned over once the trick
+    /// was won.
+    FaceDownCardsRevealed {
+        player: PlayerID,
+        cards: Vec<Card>,
+    },
+    FixedPartnershipsSet {
+        set: bool,
+    },
     TractorRequirementsChanged {
         tractor_requirements: TractorRequirements,
     },
@@ -209,6 +380,8 @@ impl MessageVariant {
                 format!("{} wins the trick, but gets no points :(", player_name(*winner)?),
             TrickWon { winner, points } =>
                 format!("{} wins the trick and gets {} points", player_name(*winner)?, points),
+            TrickWonPointsHidden { winner } =>
+                format!("{} wins the trick", player_name(*winner)?),
             RankAdvanced { player, new_rank } =>
                 format!("{} has advanced to rank {}", player_name(*player)?, new_rank.as_str()),
             AdvancementBlocked { player, rank } =>
@@ -228,12 +401,57 @@ impl MessageVariant {
             JoinedTeam { player, already_joined: true } =>
                 format!("{} tried to join the team, but was already a member", player_name(*player)?),
             LeftGame { ref name } => format!("{name} has left the game"),
+            KickVoteStarted { target } =>
+                format!("{} started a vote to kick {}", n?, player_name(*target)?),
+            KickVoteCast { approve: true } => format!("{} voted to kick", n?),
+            KickVoteCast { approve: false } => format!("{} voted not to kick", n?),
+            KickVotePassed { ref name } => format!("The vote passed, {name} has been kicked"),
+            KickVoteFailed { target } =>
+                format!("The vote to kick {} failed", player_name(*target)?),
+            Banned { ref name } => format!("{} banned {name} from the room", n?),
+            CoachingRequested { student } =>
+                format!("{} asked to coach {}", n?, player_name(*student)?),
+            CoachingStarted { coach } =>
+                format!("{} let {} see their hand", n?, player_name(*coach)?),
+            CoachingDeclined { coach } =>
+                format!("{} didn't let {} see their hand", n?, player_name(*coach)?),
+            CoachingEnded { coach, student } =>
+                format!("{} is no longer coaching {}", player_name(*coach)?, player_name(*student)?),
+            GamePaused => format!("{} paused the game", n?),
+            ResumeVoteCast => format!("{} is ready to resume", n?),
+            GameResumed => "The game has resumed".to_string(),
+            UndoRequested => format!("{} asked to undo their last move", n?),
+            UndoVoteCast { approve: true } => format!("{} approved the undo", n?),
+            UndoVoteCast { approve: false } => format!("{} rejected the undo", n?),
+            UndoApproved { requester } =>
+                format!("Everyone agreed, {}'s last move has been undone", player_name(*requester)?),
+            UndoRejected => "The undo was rejected".to_string(),
+            SettingsProposed { changes } => format!(
+                "{} proposed changing the {}",
+                n?,
+                changes.iter().map(|c| c.description()).collect::<Vec<_>>().join(", the ")
+            ),
+            SettingsProposalVoteCast { approve: true } => format!("{} approved the new settings", n?),
+            SettingsProposalVoteCast { approve: false } => format!("{} rejected the new settings", n?),
+            SettingsProposalApproved { changes } => format!(
+                "Everyone agreed to change the {}",
+                changes.iter().map(|c| c.description()).collect::<Vec<_>>().join(", the ")
+            ),
+            SettingsProposalRejected => "The new settings were rejected".to_string(),
             AdvancementPolicySet { policy: AdvancementPolicy::FullyUnrestricted } =>
                 format!("{} removed all advancement restrictions", n?),
             AdvancementPolicySet { policy: AdvancementPolicy::Unrestricted } =>
                 format!("{} required players to defend on A", n?),
             AdvancementPolicySet { policy: AdvancementPolicy::DefendPoints } =>
                 format!("{} required players to defend on points and A", n?),
+            AdvancementGatesSet { ref gates } if gates.is_empty() =>
+                format!("{} removed the additional ranks players must defend on", n?),
+            AdvancementGatesSet { ref gates } =>
+                format!(
+                    "{} required players to defend on {}",
+                    n?,
+                    gates.iter().map(|r| r.as_str()).collect::<Vec<_>>().join(", ")
+                ),
             GameScoringParametersChanged { .. } => format!("{} changed the game's scoring parameters", n?),
             KittySizeSet { size: Some(size) } => format!("{} set the number of cards in the bottom to {}", n?, size),
             KittySizeSet { size: None } => format!("{} set the number of cards in the bottom to default", n?),
@@ -245,6 +463,7 @@ impl MessageVariant {
                 format!("{} disallowed the highest non-trump card, as well as trump cards, from being selected as a friend", n?),
             FriendSelectionPolicySet { policy: FriendSelectionPolicy::PointCardNotAllowed } =>
                 format!("{} disallowed point cards, as well as trump cards, from being selected as a friend", n?),
+            FriendSelectionRestrictionsSet { .. } => format!("{} changed the friend selection restrictions", n?),
             MultipleJoinPolicySet { policy: MultipleJoinPolicy::Unrestricted } =>
                 format!("{} allowed players to join the team multiple times", n?),
             MultipleJoinPolicySet { policy: MultipleJoinPolicy::NoDoubleJoin } =>
@@ -253,12 +472,32 @@ impl MessageVariant {
                 format!("{} set winning bid to decide both landlord and trump", n?),
             FirstLandlordSelectionPolicySet { policy: FirstLandlordSelectionPolicy::ByFirstBid } =>
                 format!("{} set first bid to decide landlord, winning bid to decide trump", n?),
+            FirstDealLandlordPolicySet { policy: FirstDealLandlordPolicy::Random } =>
+                format!("{} set a random player to be the landlord if nobody bids in the first game", n?),
+            FirstDealLandlordPolicySet { policy: FirstDealLandlordPolicy::FirstToDrawCard { card } } =>
+                format!("{} set the first player to draw {} to be the landlord if nobody bids in the first game", n?, card.as_char()),
+            FirstDealLandlordPolicySet { policy: FirstDealLandlordPolicy::AssignedByHost { player } } =>
+                format!("{} set {} to be the landlord if nobody bids in the first game", n?, player_name(*player)?),
+            FirstDealLandlordSelected { player, card } =>
+                format!("{} drew {} and will be the landlord if nobody bids", player_name(*player)?, card.as_char()),
+            LandlordRotationPolicySet { policy: LandlordRotationPolicy::NextOnTeam } =>
+                format!("{} passed the landlord to the next player on the winning team when the landlord's team loses", n?),
+            LandlordRotationPolicySet { policy: LandlordRotationPolicy::Clockwise } =>
+                format!("{} passed the landlord clockwise when the landlord's team loses", n?),
+            LandlordRotationPolicySet { policy: LandlordRotationPolicy::WinnerOfLastTrick } =>
+                format!("{} passed the landlord to the winner of the last trick when the landlord's team loses", n?),
+            LandlordRotationPolicySet { policy: LandlordRotationPolicy::HighestBidder } =>
+                format!("{} let the highest bidder become landlord when the landlord's team loses", n?),
             BidPolicySet { policy: BidPolicy::JokerOrHigherSuit } =>
                 format!("{} allowed joker or higher suit bids to outbid non-joker bids with the same number of cards", n?),
             BidPolicySet { policy: BidPolicy::JokerOrGreaterLength } =>
                 format!("{} allowed joker bids to outbid non-joker bids with the same number of cards", n?),
             BidPolicySet { policy: BidPolicy::GreaterLength } =>
                 format!("{} required all bids to have more cards than the previous bids", n?),
+            BidPolicySet { policy: BidPolicy::GreaterLengthByMargin { margin } } =>
+                format!("{} required all bids to have at least {} more cards than the previous bids", n?, margin),
+            BidPolicySet { policy: BidPolicy::FirstBidWins } =>
+                format!("{} made the first bid win, with no overbidding", n?),
             BidReinforcementPolicySet { policy: BidReinforcementPolicy::ReinforceWhileWinning } =>
                 format!("{} allowed reinforcing the winning bid", n?),
             BidReinforcementPolicySet { policy: BidReinforcementPolicy::ReinforceWhileEquivalent } =>
@@ -273,10 +512,23 @@ impl MessageVariant {
                 format!("{} required no-trump bids to have at least two low or high jokers", n?),
             JokerBidPolicySet { policy: JokerBidPolicy::Disabled } =>
                 format!("{} disabled no-trump bids", n?),
+            NoTrumpOnlyFromSet { level: Some(level) } =>
+                format!("{} allowed only no-trump bids at rank {} and above", n?, level.as_str()),
+            NoTrumpOnlyFromSet { level: None } => format!("{} allowed suited bids at every rank", n?),
+            JokersOnlyTrumpPolicySet { policy: JokersOnlyTrumpPolicy::NoTrumpRankOnly } =>
+                format!("{} made only the jokers trump when playing no-trump rank", n?),
+            JokersOnlyTrumpPolicySet { policy: JokersOnlyTrumpPolicy::BigJokerBid } =>
+                format!("{} made only the jokers trump when the winning bid is made with big jokers", n?),
+            JokersOnlyTrumpPolicySet { policy: JokersOnlyTrumpPolicy::Always } =>
+                format!("{} made only the jokers trump in every round", n?),
             ShouldRevealKittyAtEndOfGameSet { should_reveal: true } =>
                 format!("{} enabled the kitty to be revealed at the end of each game", n?),
             ShouldRevealKittyAtEndOfGameSet { should_reveal: false } =>
                 format!("{} disabled the kitty from being revealed at the end of each game", n?),
+            RevealKittyCardByCardSet { card_by_card: true } =>
+                format!("{} made the kitty be revealed one card at a time at the end of each game", n?),
+            RevealKittyCardByCardSet { card_by_card: false } =>
+                format!("{} stopped revealing the kitty one card at a time", n?),
             NumDecksSet { num_decks: Some(num_decks) } =>
                 format!("{} set the number of decks to {}", n?, num_decks),
             NumDecksSet { num_decks: None } => format!("{} set the number of decks to default", n?),
@@ -301,6 +553,8 @@ impl MessageVariant {
                 format!("{} played {}", n?, cards.iter().map(|c| c.as_char()).collect::<String>()),
             EndOfGameKittyReveal { ref cards } =>
                 format!("{} in kitty", cards.iter().map(|c| c.as_char()).collect::<String>()),
+            KittyCardRevealed { card, points, multiplier } =>
+                format!("{} in kitty, worth {} points with a multiplier of {}", card.as_char(), points, multiplier),
             ThrowFailed { ref original_cards, better_player: Some(better_player) } =>
                 format!("{} tried to throw {}, but {} can beat it", n?, original_cards.iter().map(|c| c.as_char()).collect::<String>(), player_name(*better_player)?),
             ThrowFailed { ref original_cards, better_player: None } =>
@@ -315,19 +569,33 @@ impl MessageVariant {
             SetRank { rank } => format!("{} set their rank to {}", n?, rank.as_str()),
             SetMetaRank { metarank } => format!("{} set their meta-rank to {}", n?, metarank),
             SetMaxRank { rank} => format!("{} set the max rank to {}", n?, rank.as_str()),
+            RankSequenceSet { sequence: None } => format!("{} set the game to play every rank", n?),
+            RankSequenceSet { sequence: Some(ref sequence) } => format!(
+                "{} set the ranks to play to {}",
+                n?,
+                sequence.iter().map(|r| r.as_str()).collect::<Vec<_>>().join(", ")
+            ),
             MadeBid { card, count } => format!("{} bid {} {:?}", n?, count, card),
             KittyPenaltySet { kitty_penalty: KittyPenalty::Times } =>
                 format!("{} set the penalty for points in the bottom to twice the size of the last trick", n?),
             KittyPenaltySet { kitty_penalty: KittyPenalty::Power } =>
                 format!("{} set the penalty for points in the bottom to two to the power of the size of the last trick", n?),
+            KittyPenaltySet { kitty_penalty: KittyPenalty::Double } =>
+                format!("{} set the penalty for points in the bottom to twice the points", n?),
+            KittyPenaltySet { kitty_penalty: KittyPenalty::NoBonus } =>
+                format!("{} removed the penalty for points in the bottom", n?),
             ThrowPenaltySet { throw_penalty: ThrowPenalty::None } =>
                 format!("{} removed the throw penalty", n?),
             ThrowPenaltySet { throw_penalty: ThrowPenalty::TenPointsPerAttempt } =>
                 format!("{} set the throw penalty to 10 points per throw", n?),
+            ThrowPenaltySet { throw_penalty: ThrowPenalty::PointsPerCard { points } } =>
+                format!("{} set the throw penalty to {} points per card taken back, awarded to the other team", n?, points),
             KittyBidPolicySet { policy: KittyBidPolicy::FirstCard } =>
                 format!("{} set the bid-from-bottom policy to be the first card revealed", n?),
             KittyBidPolicySet { policy: KittyBidPolicy::FirstCardOfLevelOrHighest } =>
                 format!("{} set the bid-from-bottom policy to be the first card of the appropriate level, or the highest if none are found", n?),
+            KittyBidPolicySet { policy: KittyBidPolicy::FirstSuitedCard } =>
+                format!("{} set the bid-from-bottom policy to be the suit of the first non-joker card revealed", n?),
             TrickDrawPolicySet { policy: TrickDrawPolicy::NoProtections } =>
                 format!("{} removed all protections (pair can draw triple)", n?),
             TrickDrawPolicySet { policy: TrickDrawPolicy::NoFormatBasedDraw } =>
@@ -338,12 +606,24 @@ impl MessageVariant {
                 format!("{} protected tractors from being drawn out by non-tractors", n?),
             TrickDrawPolicySet { policy: TrickDrawPolicy::LongerTuplesProtectedAndOnlyDrawTractorOnTractor } =>
                 format!("{} protected longer tuples from being drawn out by shorter ones, and tractors from being drawn out by non-tractors", n?),
+            TrickDrawPolicySet { policy: TrickDrawPolicy::OnlyLargestThrownUnitDrawn } =>
+                format!("{} set throws to only draw out their largest unit", n?),
             ThrowEvaluationPolicySet { policy: ThrowEvaluationPolicy::All } =>
                 format!("{} set throws to be evaluated based on all of the cards", n?),
             ThrowEvaluationPolicySet { policy: ThrowEvaluationPolicy::Highest } =>
                 format!("{} set throws to be evaluated based on the highest card", n?),
             ThrowEvaluationPolicySet { policy: ThrowEvaluationPolicy::TrickUnitLength } =>
                 format!("{} set throws to be evaluated based on the longest component", n?),
+            ThrowEvaluationPolicySet { policy: ThrowEvaluationPolicy::AnyUnit } =>
+                format!("{} set throws to be beaten by beating any one component", n?),
+            ThrowTiePolicySet { policy: ThrowTiePolicy::LeaderWins } =>
+                format!("{} set tied plays to go to the earlier player", n?),
+            ThrowTiePolicySet { policy: ThrowTiePolicy::FollowerWins } =>
+                format!("{} set tied plays to go to the later player", n?),
+            TrickPlayPolicySet { policy: TrickPlayPolicy::AnyLegalPlay } =>
+                format!("{} allowed following with any legal play", n?),
+            TrickPlayPolicySet { policy: TrickPlayPolicy::MustBeatIfAble } =>
+                format!("{} required players to beat the winning play if they can", n?),
             PlayTakebackPolicySet { policy: PlayTakebackPolicy::AllowPlayTakeback } =>
                 format!("{} allowed taking back plays", n?),
             PlayTakebackPolicySet { policy: PlayTakebackPolicy::NoPlayTakeback } =>
@@ -356,6 +636,28 @@ impl MessageVariant {
                 format!("{} allowed stealing the bottom cards after the leader", n?),
             KittyTheftPolicySet { policy: KittyTheftPolicy::NoKittyTheft } =>
                 format!("{} disabled stealing the bottom cards after the leader", n?),
+            KittyBuryPolicySet { policy: KittyBuryPolicy::Unrestricted } =>
+                format!("{} allowed any card to be buried in the bottom", n?),
+            KittyBuryPolicySet { policy: KittyBuryPolicy::NoPointCards } =>
+                format!("{} forbade burying point cards in the bottom", n?),
+            KittyBuryPolicySet { policy: KittyBuryPolicy::NoTrumpCards } =>
+                format!("{} forbade burying trump cards in the bottom", n?),
+            KittyBuryPolicySet { policy: KittyBuryPolicy::NoPointOrTrumpCards } =>
+                format!("{} forbade burying point or trump cards in the bottom", n?),
+            TrickHistoryLengthSet { length: Some(0) } =>
+                format!("{} stopped keeping a history of past tricks", n?),
+            TrickHistoryLengthSet { length: Some(length) } =>
+                format!("{} set the trick history to keep the last {} tricks", n?, length),
+            TrickHistoryLengthSet { length: None } =>
+                format!("{} set the trick history to keep every trick", n?),
+            MaxKittyPointsSet { max: Some(max) } =>
+                format!("{} limited the points buried in the bottom to {}", n?, max),
+            MaxKittyPointsSet { max: None } =>
+                format!("{} removed the limit on points buried in the bottom", n?),
+            SuitOrderSet { order: SuitOrder::TrumpLast | SuitOrder::FixedSuitsTrumpLast } =>
+                format!("{} sorted trump cards after the other suits", n?),
+            SuitOrderSet { order: SuitOrder::TrumpFirst | SuitOrder::FixedSuitsTrumpFirst } =>
+                format!("{} sorted trump cards before the other suits", n?),
             GameShadowingPolicySet { policy: GameShadowingPolicy::AllowMultipleSessions } =>
                 format!("{} allowed players to be shadowed by joining with the same name", n?),
             GameShadowingPolicySet { policy: GameShadowingPolicy::SingleSessionOnly } =>
@@ -370,16 +672,89 @@ impl MessageVariant {
             GameFinished { result: _ } => "The game has finished".to_string(),
             GameEndedEarly => format!("{} ended the game early", n?),
             BonusLevelEarned => "Landlord team earned a bonus level for defending with a smaller team".to_string(),
-            EndOfGameSummary { landlord_won : true, non_landlords_points } =>
+            ShutoutBonusEarned { shutout: Shutout::Small, levels } =>
+                format!("Landlord team earned {levels} bonus level(s) for keeping the opposing team from winning any points"),
+            ShutoutBonusEarned { shutout: Shutout::Big, levels } =>
+                format!("Landlord team earned {levels} bonus level(s) for winning every trick"),
+            BidDefenseBonusEarned { landlord_won: true, bid_size, levels } =>
+                format!("Landlord team earned {levels} bonus level(s) for defending a bid of {bid_size} cards"),
+            BidDefenseBonusEarned { landlord_won: false, bid_size, levels } =>
+                format!("Opposing team earned {levels} bonus level(s) for defeating a bid of {bid_size} cards"),
+            EndOfGameSummary { landlord_won : true, non_landlords_points, .. } =>
                 format!("Landlord team won, opposing team only collected {non_landlords_points} points"),
-            EndOfGameSummary { landlord_won: false, non_landlords_points } =>
+            EndOfGameSummary { landlord_won: false, non_landlords_points, .. } =>
                 format!("Landlord team lost, opposing team collected {non_landlords_points} points"),
             HideThrowHaltingPlayer { set: true } => format!("{} hid the player who prevents throws", n?),
             HideThrowHaltingPlayer { set: false } => format!("{} un-hid the player who prevents throws", n?),
+            FaceDownDiscardsSet { face_down: true } => format!("{} made discards be played face-down", n?),
+            FaceDownDiscardsSet { face_down: false } => format!("{} made discards be played face-up", n?),
+            FaceDownCardsRevealed { player, ref cards } =>
+                format!("{} had discarded {}", player_name(*player)?, cards.iter().map(|c| c.as_char()).collect::<String>()),
+            FixedPartnershipsSet { set: true } => format!("{} fixed the partnerships across games", n?),
+            FixedPartnershipsSet { set: false } => format!("{} allowed partnerships to change between games", n?),
             TractorRequirementsChanged { tractor_requirements } =>
-                format!("{} required tractors to be at least {} cards wide by {} tuples long", n?, tractor_requirements.min_count, tractor_requirements.min_length),
+                format!(
+                    "{} required tractors to be at least {} cards wide by {} tuples long{}",
+                    n?,
+                    tractor_requirements.min_count,
+                    tractor_requirements.min_length,
+                    if tractor_requirements.trump_rank_breaks_tractors { ", without skipping over the trump rank" } else { "" },
+                ),
             GameVisibilitySet { visibility: GameVisibility::Public} => format!("{} listed the game publicly", n?),
             GameVisibilitySet { visibility: GameVisibility::Unlisted} => format!("{} unlisted the game", n?),
+            AttackingPointsVisibilitySet { visibility: AttackingPointsVisibility::Visible } =>
+                format!("{} made the attacking team's points visible", n?),
+            AttackingPointsVisibilitySet { visibility: AttackingPointsVisibility::HiddenFromLandlordTeam } =>
+                format!("{} hid the attacking team's points from the landlord's team until the end of the game", n?),
+            AttackingPointsVisibilitySet { visibility: AttackingPointsVisibility::HiddenFromAll } =>
+                format!("{} hid the attacking team's points until the end of the game", n?),
+            MatchTargetRankSet { rank: None } => format!("{} set the game to continue without a match target", n?),
+            MatchTargetRankSet { rank: Some(rank) } =>
+                format!("{} set the match to be won by the first team to defend rank {}", n?, rank.as_str()),
+            TurnTimersSet { timers: TurnTimers { bid_secs: None, exchange_secs: None, play_secs: None, .. } } =>
+                format!("{} turned off turn timers", n?),
+            TurnTimersSet { timers } => {
+                let describe = |secs: Option<usize>| secs.map(|s| format!("{s}s")).unwrap_or_else(|| "untimed".to_string());
+                format!(
+                    "{} set turn timers to {} for bidding, {} for the exchange and {} for playing, {}",
+                    n?,
+                    describe(timers.bid_secs),
+                    describe(timers.exchange_secs),
+                    describe(timers.play_secs),
+                    match timers.timeout_policy {
+                        TimeoutPolicy::MarkIdle => "marking slow players idle",
+                        TimeoutPolicy::AutoPlay => "playing for slow players",
+                    }
+                )
+            }
+            SpectatorDelaySet { secs: 0 } => format!("{} let spectators watch the game live", n?),
+            SpectatorDelaySet { secs } => format!("{} delayed what spectators see by {secs} seconds", n?),
+            RankedSet { ranked: true } => format!("{} made the room ranked", n?),
+            RankedSet { ranked: false } => format!("{} made the room unranked", n?),
+            DealSeedSet { fixed: true } => format!("{} fixed the deal, so every game is dealt the same way", n?),
+            DealSeedSet { fixed: false } => format!("{} made the deal random again", n?),
+            SettingsChanged { changes } => format!(
+                "{} changed the {}",
+                n?,
+                changes.iter().map(|c| c.description()).collect::<Vec<_>>().join(", the ")
+            ),
+            TimedOut => format!("{} ran out of time", n?),
+            MarkedIdle => format!("{} ran out of time and is now idle", n?),
+            WebhookSet { enabled: true } => format!("{} set a webhook to receive updates about this room", n?),
+            WebhookSet { enabled: false } => format!("{} removed the room's webhook", n?),
+            RematchPolicySet { policy: RematchPolicy::KeepRanks } =>
+                format!("{} set ranks to carry over after a match is won", n?),
+            RematchPolicySet { policy: RematchPolicy::ResetRanks } =>
+                format!("{} set ranks to reset after a match is won", n?),
+            MatchComplete { winners, standings: _ } => format!(
+                "{} won the match!",
+                winners
+                    .iter()
+                    .map(|p| player_name(*p))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            ),
+            RanksResetForRematch => "Ranks have been reset for a rematch".to_string(),
         })
     }
 }
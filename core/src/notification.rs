@@ -0,0 +1,186 @@
+//! Events in the game which clients might want to react to with a sound,
+//! toast or animation, so that they don't have to work them out by diffing
+//! one state against the next.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::types::{Card, PlayerID};
+
+use crate::game_state::GameState;
+use crate::message::MessageVariant;
+use crate::settings::AttackingPointsVisibility;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Notification {
+    TrickWon {
+        winner: PlayerID,
+        /// The points in the trick. Only sent if everyone can see the
+        /// attacking team's points.
+        points: Option<usize>,
+    },
+    /// A player joined the landlord's team by playing a called friend card.
+    FriendRevealed {
+        friend: PlayerID,
+    },
+    /// The points taken by the attacking team changed who would win the
+    /// game, or by how many levels, if it were to end now. Only sent if
+    /// everyone can see the attacking team's points.
+    ThresholdCrossed {
+        non_landlords_points: isize,
+        landlord_won: bool,
+        landlord_delta: usize,
+        non_landlord_delta: usize,
+    },
+    LandlordChanged {
+        landlord: Option<PlayerID>,
+    },
+    /// One card in the bottom, revealed at the end of the game. These are
+    /// sent in order, one for each card.
+    KittyCardRevealed {
+        card: Card,
+        points: usize,
+        multiplier: usize,
+    },
+}
+
+impl Notification {
+    /// The notifications for going from `before` to `after`, where `msgs`
+    /// are the messages broadcast along the way.
+    pub fn between<'a>(
+        before: &GameState,
+        after: &GameState,
+        msgs: impl IntoIterator<Item = &'a MessageVariant>,
+    ) -> Vec<Notification> {
+        let mut notifications = vec![];
+        let points_visible =
+            after.attacking_points_visibility == AttackingPointsVisibility::Visible;
+        for msg in msgs {
+            match *msg {
+                MessageVariant::TrickWon { winner, points } => {
+                    notifications.push(Notification::TrickWon {
+                        winner,
+                        points: Some(points).filter(|_| points_visible),
+                    })
+                }
+                MessageVariant::JoinedTeam {
+                    player,
+                    already_joined: false,
+                } => notifications.push(Notification::FriendRevealed { friend: player }),
+                MessageVariant::KittyCardRevealed {
+                    card,
+                    points,
+                    multiplier,
+                } => notifications.push(Notification::KittyCardRevealed {
+                    card,
+                    points,
+                    multiplier,
+                }),
+                _ => (),
+            }
+        }
+
+        if let (GameState::Play(b), GameState::Play(a)) = (before, after) {
+            if a.propagated().attacking_points_visibility == AttackingPointsVisibility::Visible {
+                if let (Ok(b_score), Ok(a_score)) = (b.projected_score(), a.projected_score()) {
+                    if b_score.landlord_won != a_score.landlord_won
+                        || b_score.landlord_delta != a_score.landlord_delta
+                        || b_score.non_landlord_delta != a_score.non_landlord_delta
+                    {
+                        notifications.push(Notification::ThresholdCrossed {
+                            non_landlords_points: a.calculate_points().0,
+                            landlord_won: a_score.landlord_won,
+                            landlord_delta: a_score.landlord_delta,
+                            non_landlord_delta: a_score.non_landlord_delta,
+                        });
+                    }
+                }
+            }
+        }
+
+        let landlord = after.current_landlord();
+        if before.current_landlord() != landlord {
+            notifications.push(Notification::LandlordChanged { landlord });
+        }
+
+        notifications
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shengji_mechanics::types::{cards, PlayerID};
+
+    use crate::game_state::{initialize_phase::InitializePhase, GameState};
+    use crate::message::MessageVariant;
+    use crate::settings::AttackingPointsVisibility;
+
+    use super::Notification;
+
+    #[test]
+    fn test_notifications_between() {
+        let mut init = InitializePhase::new();
+        for name in ["a", "b", "c", "d"] {
+            init.propagated_mut().add_player(name.to_string()).unwrap();
+        }
+        let before = GameState::Initialize(init.clone());
+        init.propagated_mut()
+            .set_landlord(Some(PlayerID(2)))
+            .unwrap();
+        let after = GameState::Initialize(init);
+
+        let msgs = vec![
+            MessageVariant::TrickWon {
+                winner: PlayerID(1),
+                points: 15,
+            },
+            MessageVariant::JoinedTeam {
+                player: PlayerID(3),
+                already_joined: false,
+            },
+            MessageVariant::JoinedTeam {
+                player: PlayerID(0),
+                already_joined: true,
+            },
+            MessageVariant::KittyCardRevealed {
+                card: cards::H_K,
+                points: 20,
+                multiplier: 2,
+            },
+        ];
+        assert_eq!(
+            Notification::between(&before, &after, &msgs),
+            vec![
+                Notification::TrickWon {
+                    winner: PlayerID(1),
+                    points: Some(15)
+                },
+                Notification::FriendRevealed {
+                    friend: PlayerID(3)
+                },
+                Notification::KittyCardRevealed {
+                    card: cards::H_K,
+                    points: 20,
+                    multiplier: 2
+                },
+                Notification::LandlordChanged {
+                    landlord: Some(PlayerID(2))
+                },
+            ]
+        );
+        assert_eq!(Notification::between(&after, &after, &[]), vec![]);
+
+        let mut init = InitializePhase::new();
+        init.set_attacking_points_visibility(AttackingPointsVisibility::HiddenFromAll)
+            .unwrap();
+        let hidden = GameState::Initialize(init);
+        assert_eq!(
+            Notification::between(&hidden, &hidden, &msgs[..1]),
+            vec![Notification::TrickWon {
+                winner: PlayerID(1),
+                points: None
+            }]
+        );
+    }
+}
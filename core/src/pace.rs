@@ -0,0 +1,161 @@
+//! How long players take over their bids and plays, so that the table can
+//! see how the game went at the end of it.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::player::Player;
+use shengji_mechanics::types::PlayerID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+struct MoveTimes {
+    player: PlayerID,
+    moves: usize,
+    total_ms: u64,
+    slowest_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TrickPace {
+    /// Counting from 1.
+    pub trick: usize,
+    pub ms: u64,
+}
+
+/// Timings for the game in progress. Each move is timed from the one before
+/// it, whoever made it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PaceTracker {
+    last_move_ms: Option<u64>,
+    trick_started_ms: Option<u64>,
+    tricks: usize,
+    moves: Vec<MoveTimes>,
+    slowest_trick: Option<TrickPace>,
+}
+
+impl PaceTracker {
+    /// Notes that something happened in the game, without timing it.
+    pub fn record_action(&mut self, timestamp_ms: u64) {
+        self.last_move_ms = Some(timestamp_ms);
+    }
+
+    /// Times a bid or play made by `player`.
+    pub fn record_move(&mut self, player: PlayerID, timestamp_ms: u64) {
+        if let Some(last) = self.last_move_ms {
+            let elapsed = timestamp_ms.saturating_sub(last);
+            let idx = match self.moves.iter().position(|m| m.player == player) {
+                Some(idx) => idx,
+                None => {
+                    self.moves.push(MoveTimes {
+                        player,
+                        moves: 0,
+                        total_ms: 0,
+                        slowest_ms: 0,
+                    });
+                    self.moves.len() - 1
+                }
+            };
+            let times = &mut self.moves[idx];
+            times.moves += 1;
+            times.total_ms += elapsed;
+            times.slowest_ms = times.slowest_ms.max(elapsed);
+        }
+        self.record_action(timestamp_ms);
+    }
+
+    /// Starts timing a trick from the last thing that happened before its
+    /// first play.
+    pub fn start_trick(&mut self) {
+        self.trick_started_ms = self.last_move_ms;
+    }
+
+    /// Stops timing the current trick as its last play is made.
+    pub fn finish_trick(&mut self, timestamp_ms: u64) {
+        self.tricks += 1;
+        if let Some(started) = self.trick_started_ms.take() {
+            let ms = timestamp_ms.saturating_sub(started);
+            if self.slowest_trick.map(|t| ms > t.ms).unwrap_or(true) {
+                self.slowest_trick = Some(TrickPace {
+                    trick: self.tricks,
+                    ms,
+                });
+            }
+        }
+    }
+
+    pub fn summary(&self, players: &[Player]) -> PaceSummary {
+        PaceSummary {
+            players: players
+                .iter()
+                .filter_map(|p| {
+                    let times = self.moves.iter().find(|m| m.player == p.id)?;
+                    Some(PlayerPace {
+                        player: p.id,
+                        name: p.name.clone(),
+                        moves: times.moves,
+                        average_ms: times.total_ms / times.moves as u64,
+                        slowest_ms: times.slowest_ms,
+                    })
+                })
+                .collect(),
+            slowest_trick: self.slowest_trick,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerPace {
+    pub player: PlayerID,
+    pub name: String,
+    /// The number of bids and plays the player made.
+    pub moves: usize,
+    pub average_ms: u64,
+    pub slowest_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PaceSummary {
+    pub players: Vec<PlayerPace>,
+    pub slowest_trick: Option<TrickPace>,
+}
+
+#[cfg(test)]
+mod tests {
+    use shengji_mechanics::player::Player;
+    use shengji_mechanics::types::PlayerID;
+
+    use super::{PaceTracker, TrickPace};
+
+    #[test]
+    fn test_pace() {
+        let players = vec![
+            Player::new(PlayerID(0), "a".to_string()),
+            Player::new(PlayerID(1), "b".to_string()),
+        ];
+        let mut pace = PaceTracker::default();
+        // Nothing has happened yet to time the first move from.
+        pace.record_move(PlayerID(0), 1_000);
+        pace.start_trick();
+        pace.record_move(PlayerID(0), 3_000);
+        pace.record_move(PlayerID(1), 9_000);
+        pace.finish_trick(9_000);
+        pace.record_action(10_000);
+        pace.start_trick();
+        pace.record_move(PlayerID(1), 11_000);
+        pace.record_move(PlayerID(0), 12_000);
+        pace.finish_trick(12_000);
+
+        let summary = pace.summary(&players);
+        assert_eq!(summary.players[0].moves, 2);
+        assert_eq!(summary.players[0].average_ms, 1_500);
+        assert_eq!(summary.players[1].average_ms, 3_500);
+        assert_eq!(summary.players[1].slowest_ms, 6_000);
+        assert_eq!(
+            summary.slowest_trick,
+            Some(TrickPace {
+                trick: 1,
+                ms: 8_000
+            })
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! The phases a game moves through, and the actions which move it along in
+//! each of them. These are a typed view onto `Action`, for tools which drive
+//! a game directly rather than through the server:
+//!
+//! ```text
+//! Initialize --StartGame--> Draw --PickUpKitty--> Exchange --BeginPlay--> Play
+//!     ^                                                                     |
+//!     +------StartNewGame------ Done <----------(last trick ends)-----------+
+//! ```
+//!
+//! Settings, votes and the like aren't part of any phase, and are still
+//! taken as an `Action` with `InteractiveGame::interact`.
+
+use std::fmt;
+
+use anyhow::{bail, Error};
+use serde::{Deserialize, Serialize};
+use shengji_mechanics::trick::TrickUnit;
+use shengji_mechanics::types::Card;
+
+use crate::game_state::GameState;
+use crate::interactive::Action;
+use crate::settings::FriendSelection;
+
+/// Where a game is, as far as which actions can be taken.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Phase {
+    /// Players are joining and the room's settings are being chosen.
+    Initialize,
+    /// Cards are being drawn, and players are bidding for trump.
+    Draw,
+    /// The landlord is exchanging cards with the kitty and calling friends.
+    Exchange,
+    /// Tricks are being played.
+    Play,
+    /// All the tricks have been played, or the game was ended early, and
+    /// the game is waiting to be scored.
+    Done,
+}
+
+impl Phase {
+    pub fn of(state: &GameState) -> Phase {
+        match state {
+            GameState::Initialize(_) => Phase::Initialize,
+            GameState::Draw(_) => Phase::Draw,
+            GameState::Exchange(_) => Phase::Exchange,
+            GameState::Play(p) if p.game_finished() => Phase::Done,
+            GameState::Play(_) => Phase::Play,
+        }
+    }
+
+    /// The phase which this one leads into.
+    pub fn next(self) -> Phase {
+        match self {
+            Phase::Initialize => Phase::Draw,
+            Phase::Draw => Phase::Exchange,
+            Phase::Exchange => Phase::Play,
+            Phase::Play => Phase::Done,
+            Phase::Done => Phase::Initialize,
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Initialize => "initialize",
+            Phase::Draw => "draw",
+            Phase::Exchange => "exchange",
+            Phase::Play => "play",
+            Phase::Done => "done",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum InitializeAction {
+    /// Deals the cards and moves on to the draw phase.
+    StartGame,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DrawAction {
+    DrawCard,
+    RevealCard,
+    Bid(Card, usize),
+    TakeBackBid,
+    /// Once every card has been drawn, has the landlord pick up the kitty
+    /// and moves on to the exchange phase.
+    PickUpKitty,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ExchangeAction {
+    Bid(Card, usize),
+    TakeBackBid,
+    PickUpKitty,
+    PutDownKitty,
+    MoveCardToKitty(Card),
+    MoveCardToHand(Card),
+    SetFriends(Vec<FriendSelection>),
+    /// Moves on to the play phase, once the kitty has been buried.
+    BeginPlay,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PlayAction {
+    PlayCards(Vec<Card>),
+    PlayCardsWithHint(Vec<Card>, Vec<TrickUnit>),
+    /// Collects the trick. Collecting the last trick ends the game.
+    EndTrick,
+    TakeBackCards,
+    EndGameEarly,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DoneAction {
+    /// Scores the game and goes back to the initialize phase.
+    StartNewGame,
+}
+
+/// An action which moves a game along, tagged with the phase it's taken in.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PhaseAction {
+    Initialize(InitializeAction),
+    Draw(DrawAction),
+    Exchange(ExchangeAction),
+    Play(PlayAction),
+    Done(DoneAction),
+}
+
+impl PhaseAction {
+    /// The phase in which the action can be taken.
+    pub fn phase(&self) -> Phase {
+        match self {
+            PhaseAction::Initialize(_) => Phase::Initialize,
+            PhaseAction::Draw(_) => Phase::Draw,
+            PhaseAction::Exchange(_) => Phase::Exchange,
+            PhaseAction::Play(_) => Phase::Play,
+            PhaseAction::Done(_) => Phase::Done,
+        }
+    }
+
+    /// Tags `action` with the phase it would be taken in, given the game is
+    /// currently in `phase`.
+    pub fn from_action(action: Action, phase: Phase) -> Result<PhaseAction, Error> {
+        Ok(match (action, phase) {
+            (Action::StartGame, Phase::Initialize) => {
+                PhaseAction::Initialize(InitializeAction::StartGame)
+            }
+            (Action::DrawCard, Phase::Draw) => PhaseAction::Draw(DrawAction::DrawCard),
+            (Action::RevealCard, Phase::Draw) => PhaseAction::Draw(DrawAction::RevealCard),
+            (Action::Bid(card, count), Phase::Draw) => {
+                PhaseAction::Draw(DrawAction::Bid(card, count))
+            }
+            (Action::TakeBackBid, Phase::Draw) => PhaseAction::Draw(DrawAction::TakeBackBid),
+            (Action::PickUpKitty, Phase::Draw) => PhaseAction::Draw(DrawAction::PickUpKitty),
+            (Action::Bid(card, count), Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::Bid(card, count))
+            }
+            (Action::TakeBackBid, Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::TakeBackBid)
+            }
+            (Action::PickUpKitty, Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::PickUpKitty)
+            }
+            (Action::PutDownKitty, Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::PutDownKitty)
+            }
+            (Action::MoveCardToKitty(card), Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::MoveCardToKitty(card))
+            }
+            (Action::MoveCardToHand(card), Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::MoveCardToHand(card))
+            }
+            (Action::SetFriends(friends), Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::SetFriends(friends))
+            }
+            (Action::BeginPlay, Phase::Exchange) => {
+                PhaseAction::Exchange(ExchangeAction::BeginPlay)
+            }
+            (Action::PlayCards(cards), Phase::Play) => {
+                PhaseAction::Play(PlayAction::PlayCards(cards))
+            }
+            (Action::PlayCardsWithHint(cards, hint), Phase::Play) => {
+                PhaseAction::Play(PlayAction::PlayCardsWithHint(cards, hint))
+            }
+            (Action::EndTrick, Phase::Play) => PhaseAction::Play(PlayAction::EndTrick),
+            (Action::TakeBackCards, Phase::Play) => PhaseAction::Play(PlayAction::TakeBackCards),
+            (Action::EndGameEarly, Phase::Play) => PhaseAction::Play(PlayAction::EndGameEarly),
+            (Action::StartNewGame, Phase::Done) => PhaseAction::Done(DoneAction::StartNewGame),
+            (action, phase) => bail!("{:?} can't be taken in the {} phase", action, phase),
+        })
+    }
+}
+
+impl From<PhaseAction> for Action {
+    fn from(action: PhaseAction) -> Action {
+        match action {
+            PhaseAction::Initialize(InitializeAction::StartGame) => Action::StartGame,
+            PhaseAction::Draw(DrawAction::DrawCard) => Action::DrawCard,
+            PhaseAction::Draw(DrawAction::RevealCard) => Action::RevealCard,
+            PhaseAction::Draw(DrawAction::Bid(card, count))
+            | PhaseAction::Exchange(ExchangeAction::Bid(card, count)) => Action::Bid(card, count),
+            PhaseAction::Draw(DrawAction::TakeBackBid)
+            | PhaseAction::Exchange(ExchangeAction::TakeBackBid) => Action::TakeBackBid,
+            PhaseAction::Draw(DrawAction::PickUpKitty)
+            | PhaseAction::Exchange(ExchangeAction::PickUpKitty) => Action::PickUpKitty,
+            PhaseAction::Exchange(ExchangeAction::PutDownKitty) => Action::PutDownKitty,
+            PhaseAction::Exchange(ExchangeAction::MoveCardToKitty(card)) => {
+                Action::MoveCardToKitty(card)
+            }
+            PhaseAction::Exchange(ExchangeAction::MoveCardToHand(card)) => {
+                Action::MoveCardToHand(card)
+            }
+            PhaseAction::Exchange(ExchangeAction::SetFriends(friends)) => {
+                Action::SetFriends(friends)
+            }
+            PhaseAction::Exchange(ExchangeAction::BeginPlay) => Action::BeginPlay,
+            PhaseAction::Play(PlayAction::PlayCards(cards)) => Action::PlayCards(cards),
+            PhaseAction::Play(PlayAction::PlayCardsWithHint(cards, hint)) => {
+                Action::PlayCardsWithHint(cards, hint)
+            }
+            PhaseAction::Play(PlayAction::EndTrick) => Action::EndTrick,
+            PhaseAction::Play(PlayAction::TakeBackCards) => Action::TakeBackCards,
+            PhaseAction::Play(PlayAction::EndGameEarly) => Action::EndGameEarly,
+            PhaseAction::Done(DoneAction::StartNewGame) => Action::StartNewGame,
+        }
+    }
+}
+
+impl From<InitializeAction> for PhaseAction {
+    fn from(action: InitializeAction) -> Self {
+        PhaseAction::Initialize(action)
+    }
+}
+
+impl From<DrawAction> for PhaseAction {
+    fn from(action: DrawAction) -> Self {
+        PhaseAction::Draw(action)
+    }
+}
+
+impl From<ExchangeAction> for PhaseAction {
+    fn from(action: ExchangeAction) -> Self {
+        PhaseAction::Exchange(action)
+    }
+}
+
+impl From<PlayAction> for PhaseAction {
+    fn from(action: PlayAction) -> Self {
+        PhaseAction::Play(action)
+    }
+}
+
+impl From<DoneAction> for PhaseAction {
+    fn from(action: DoneAction) -> Self {
+        PhaseAction::Done(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use crate::bot::{Bot, GreedyBot};
+    use crate::game_state::{initialize_phase::InitializePhase, GameState};
+    use crate::interactive::InteractiveGame;
+
+    use super::{DoneAction, DrawAction, InitializeAction, Phase, PhaseAction, PlayAction};
+
+    #[test]
+    fn test_advance_through_phases() {
+        let logger = Logger::root(Discard, o!());
+        let mut init = InitializePhase::new();
+        let seats = (0..4)
+            .map(|_| init.add_bot().unwrap().0)
+            .collect::<Vec<_>>();
+        let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+        assert_eq!(game.phase(), Phase::Initialize);
+
+        assert!(game
+            .advance(PlayAction::EndTrick, seats[0], &logger)
+            .is_err());
+        assert_eq!(game.phase(), Phase::Initialize);
+        game.advance_with_seed(InitializeAction::StartGame, seats[0], 3, &logger)
+            .unwrap();
+        assert_eq!(game.phase(), Phase::Draw);
+        assert!(game
+            .advance(InitializeAction::StartGame, seats[0], &logger)
+            .is_err());
+
+        let mut visited = vec![Phase::Initialize, Phase::Draw];
+        while game.phase() != Phase::Initialize {
+            let phase = game.phase();
+            let advanced = seats.iter().any(|id| {
+                // Bots leave it to the people at the table to start the next
+                // game.
+                if phase == Phase::Done {
+                    return game.advance(DoneAction::StartNewGame, *id, &logger).is_ok();
+                }
+                GreedyBot
+                    .actions(game.state(), *id)
+                    .into_iter()
+                    .filter_map(|a| PhaseAction::from_action(a, phase).ok())
+                    .any(|a| game.advance_with_seed(a, *id, 0, &logger).is_ok())
+            });
+            assert!(advanced, "Nobody could act in the {} phase", phase);
+            if *visited.last().unwrap() != game.phase() {
+                assert_eq!(phase.next(), game.phase());
+                visited.push(game.phase());
+            }
+        }
+        assert_eq!(
+            visited,
+            vec![
+                Phase::Initialize,
+                Phase::Draw,
+                Phase::Exchange,
+                Phase::Play,
+                Phase::Done,
+                Phase::Initialize
+            ]
+        );
+        assert!(game
+            .advance(DrawAction::DrawCard, seats[0], &logger)
+            .is_err());
+    }
+}
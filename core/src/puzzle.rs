@@ -0,0 +1,216 @@
+//! Single-trick puzzles: a fixed position in the middle of a game, with
+//! every hand known, and a goal for the trick which the solver's play has to
+//! guarantee, e.g. "win at least 20 points this trick".
+
+use anyhow::{bail, Error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::types::{Card, PlayerID};
+
+use crate::game_state::play_phase::PlayPhase;
+
+/// The most ways a player can have to follow a trick before the puzzle is
+/// too big to check exhaustively.
+const MAX_FOLLOWS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum PuzzleGoal {
+    /// The solver's team wins the trick.
+    WinTrick,
+    /// The solver's team wins the trick, with at least this many points in
+    /// it.
+    WinPoints { points: usize },
+    /// The other team wins no more than this many points from the trick.
+    ConcedeAtMost { points: usize },
+}
+
+impl PuzzleGoal {
+    pub fn description(&self) -> String {
+        match self {
+            PuzzleGoal::WinTrick => "Win this trick".to_string(),
+            PuzzleGoal::WinPoints { points } => {
+                format!("Win at least {points} points this trick")
+            }
+            PuzzleGoal::ConcedeAtMost { points: 0 } => {
+                "Don't give up any points this trick".to_string()
+            }
+            PuzzleGoal::ConcedeAtMost { points } => {
+                format!("Give up at most {points} points this trick")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Puzzle {
+    /// The game when the puzzle starts, with every hand visible. It has to
+    /// be the solver's turn.
+    pub position: PlayPhase,
+    pub solver: PlayerID,
+    pub goal: PuzzleGoal,
+}
+
+/// A play made by one of the players in the rest of the trick.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PuzzlePlay {
+    pub player: PlayerID,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PuzzleResult {
+    pub solved: bool,
+    /// How the rest of the table could play to keep the goal from being
+    /// met, if the attempt doesn't solve the puzzle.
+    pub refutation: Option<Vec<PuzzlePlay>>,
+}
+
+impl Puzzle {
+    /// Checks whether playing `cards` meets the goal however the opponents
+    /// follow, as long as the solver's teammates make the right plays.
+    pub fn check_attempt(&self, cards: &[Card]) -> Result<PuzzleResult, Error> {
+        if self.position.next_player()? != self.solver {
+            bail!("It isn't the solver's turn");
+        }
+        let mut position = self.position.clone();
+        position.play_cards(self.solver, cards)?;
+        let refutation = self.refute(&position)?;
+        Ok(PuzzleResult {
+            solved: refutation.is_none(),
+            refutation,
+        })
+    }
+
+    fn on_solvers_team(&self, position: &PlayPhase, id: PlayerID) -> bool {
+        let landlords_team = position.landlords_team();
+        landlords_team.contains(&id) == landlords_team.contains(&self.solver)
+    }
+
+    /// Searches the rest of the trick for a line of play which keeps the goal
+    /// from being met. The solver's teammates try to meet it, and everyone
+    /// else tries to stop them.
+    fn refute(&self, position: &PlayPhase) -> Result<Option<Vec<PuzzlePlay>>, Error> {
+        let next = match position.trick().player_queue().next() {
+            Some(next) => next,
+            None if self.goal_met(position)? => return Ok(None),
+            None => return Ok(Some(vec![])),
+        };
+        let teammate = self.on_solvers_team(position, next);
+        let mut refutation = None;
+        let follows = position
+            .legal_follows(next)?
+            .take(MAX_FOLLOWS + 1)
+            .collect::<Vec<_>>();
+        if follows.len() > MAX_FOLLOWS {
+            bail!("There are too many ways to follow the trick to check the puzzle");
+        }
+        for cards in follows {
+            let mut after = position.clone();
+            after.play_cards(next, &cards)?;
+            match self.refute(&after)? {
+                None if teammate => return Ok(None),
+                None => (),
+                Some(mut line) => {
+                    line.insert(
+                        0,
+                        PuzzlePlay {
+                            player: next,
+                            cards,
+                        },
+                    );
+                    if !teammate {
+                        return Ok(Some(line));
+                    }
+                    refutation.get_or_insert(line);
+                }
+            }
+        }
+        Ok(refutation)
+    }
+
+    fn goal_met(&self, position: &PlayPhase) -> Result<bool, Error> {
+        let ended = position.trick().complete()?;
+        let points = ended
+            .points
+            .iter()
+            .filter_map(|c| c.points())
+            .sum::<usize>();
+        let won = self.on_solvers_team(position, ended.winner);
+        Ok(match self.goal {
+            PuzzleGoal::WinTrick => won,
+            PuzzleGoal::WinPoints { points: goal } => won && points >= goal,
+            PuzzleGoal::ConcedeAtMost { points: goal } => won || points <= goal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use shengji_mechanics::types::cards::*;
+
+    use crate::game_state::initialize_phase::InitializePhase;
+
+    use super::{Puzzle, PuzzleGoal};
+
+    #[test]
+    fn test_puzzle() {
+        let mut init = InitializePhase::new();
+        let players = (1..=4)
+            .map(|i| init.add_player(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        init.set_num_decks(Some(1)).unwrap();
+        let mut draw = init.start_with_seed(players[0], 0).unwrap();
+        // Every spade other than the trump rank is dealt, so that everyone
+        // has to follow a spade lead.
+        let hands = vec![
+            vec![H_2, S_A, S_K, S_3],
+            vec![S_Q, S_J, S_4],
+            vec![S_10, S_9, S_5],
+            vec![S_8, S_7, S_6],
+        ];
+        draw.arrange_deal(
+            &players
+                .iter()
+                .copied()
+                .zip(hands)
+                .collect::<HashMap<_, _>>(),
+            &[],
+        )
+        .unwrap();
+        while !draw.deck().is_empty() {
+            let next = draw.next_player().unwrap();
+            draw.draw_card(next).unwrap();
+        }
+        assert!(draw.bid(players[0], H_2, 1));
+        let mut exchange = draw.advance(players[0]).unwrap();
+        exchange.finalize(players[0]).unwrap();
+        let position = exchange.advance(players[0]).unwrap();
+
+        let mut puzzle = Puzzle {
+            position,
+            solver: players[0],
+            goal: PuzzleGoal::WinPoints { points: 20 },
+        };
+        // Leading the king wins it along with the partner's ten.
+        let result = puzzle.check_attempt(&[S_K]).unwrap();
+        assert!(result.solved);
+        assert!(result.refutation.is_none());
+        // The ace wins too, but it isn't worth anything.
+        let result = puzzle.check_attempt(&[S_A]).unwrap();
+        assert!(!result.solved);
+        assert_eq!(result.refutation.unwrap().len(), 3);
+        assert!(puzzle.check_attempt(&[S_3]).unwrap().refutation.is_some());
+        assert!(puzzle.check_attempt(&[S_Q]).is_err());
+
+        puzzle.goal = PuzzleGoal::WinTrick;
+        assert!(puzzle.check_attempt(&[S_A]).unwrap().solved);
+        // The next player can take the three with any of their high spades.
+        let refutation = puzzle.check_attempt(&[S_3]).unwrap().refutation.unwrap();
+        assert_eq!(refutation[0].player, players[1]);
+        assert_ne!(refutation[0].cards, vec![S_4]);
+    }
+}
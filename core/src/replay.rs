@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use slog::{o, Discard, Logger};
+
+use shengji_mechanics::types::PlayerID;
+
+use crate::game_state::GameState;
+use crate::interactive::{Action, InteractiveGame};
+
+/// An action which was successfully applied to the game.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReplayEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub actor: PlayerID,
+    pub action: Action,
+}
+
+/// A single action in a replay, along with the states just before and just
+/// after it was applied.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReplayStep {
+    /// The number of actions which were applied before this one.
+    pub step: usize,
+    pub entry: ReplayEntry,
+    pub before: GameState,
+    pub after: GameState,
+}
+
+/// A log of a single game, from the moment it was started, which can be
+/// stepped through to reconstruct every intermediate state.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Replay {
+    /// The state just before the game was started, including all of the
+    /// settings.
+    pub initial_state: GameState,
+    pub started_by: PlayerID,
+    /// The seed used to shuffle the deck.
+    pub seed: u64,
+    pub started_at_ms: u64,
+    pub entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub fn new(
+        initial_state: GameState,
+        started_by: PlayerID,
+        seed: u64,
+        started_at_ms: u64,
+    ) -> Self {
+        Replay {
+            initial_state,
+            started_by,
+            seed,
+            started_at_ms,
+            entries: vec![],
+        }
+    }
+
+    pub fn record(&mut self, actor: PlayerID, action: Action, timestamp_ms: u64) {
+        self.entries.push(ReplayEntry {
+            timestamp_ms,
+            actor,
+            action,
+        });
+    }
+
+    /// The number of actions taken after the game was started.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn cursor(&self) -> Result<ReplayCursor<'_>, Error> {
+        let mut game = InteractiveGame::new_from_state(self.initial_state.clone());
+        game.interact_timed(
+            Action::StartGame,
+            self.started_by,
+            self.seed,
+            self.started_at_ms,
+            &quiet(),
+        )?;
+        Ok(ReplayCursor {
+            replay: self,
+            states: vec![game.into_state()],
+        })
+    }
+
+    /// The state after the first `step` actions were applied.
+    pub fn state_at(&self, step: usize) -> Result<GameState, Error> {
+        let mut cursor = self.cursor()?;
+        cursor.seek(step)?;
+        Ok(cursor.state().clone())
+    }
+
+    /// The action at `step` and its effect on the game, for tracking down
+    /// exactly where a game went wrong.
+    pub fn step(&self, step: usize) -> Result<ReplayStep, Error> {
+        let entry = match self.entries.get(step) {
+            Some(entry) => entry.clone(),
+            None => bail!("replay only has {} steps", self.len()),
+        };
+        let mut cursor = self.cursor()?;
+        cursor.seek(step)?;
+        let before = cursor.state().clone();
+        cursor.forward()?;
+        Ok(ReplayStep {
+            step,
+            entry,
+            before,
+            after: cursor.state().clone(),
+        })
+    }
+}
+
+/// Rebuilds a game from an exported replay, e.g. to move it to another
+/// server, returning the game as of its last action along with the replay to
+/// keep recording into. Players are renamed according to `seats`, from their
+/// names in the replay to the ones they'll join with; anyone not in `seats`
+/// keeps their name.
+pub fn replay_from_log(
+    log: &Replay,
+    seats: &HashMap<String, String>,
+) -> Result<(GameState, Replay), Error> {
+    let mut replay = log.clone();
+    replay.initial_state.rename_players(seats)?;
+    let state = replay.state_at(replay.len())?;
+    Ok((state, replay))
+}
+
+/// Steps through a replay. Position zero is the game just after it was
+/// started.
+pub struct ReplayCursor<'a> {
+    replay: &'a Replay,
+    states: Vec<GameState>,
+}
+
+impl<'a> ReplayCursor<'a> {
+    pub fn position(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.states[self.states.len() - 1]
+    }
+
+    /// Applies the next action, returning false if there are none left.
+    pub fn forward(&mut self) -> Result<bool, Error> {
+        let entry = match self.replay.entries.get(self.position()) {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        let mut game = InteractiveGame::new_from_state(self.state().clone());
+        game.interact_timed(
+            entry.action.clone(),
+            entry.actor,
+            rand::random(),
+            entry.timestamp_ms,
+            &quiet(),
+        )?;
+        self.states.push(game.into_state());
+        Ok(true)
+    }
+
+    /// Undoes the last action, returning false if already at the start.
+    pub fn backward(&mut self) -> bool {
+        if self.states.len() > 1 {
+            self.states.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn seek(&mut self, step: usize) -> Result<(), Error> {
+        if step > self.replay.len() {
+            bail!("replay only has {} steps", self.replay.len());
+        }
+        self.states.truncate(step + 1);
+        while self.position() < step {
+            self.forward()?;
+        }
+        Ok(())
+    }
+}
+
+/// A logger which discards everything, for games which aren't being run by
+/// the server.
+pub fn quiet() -> Logger {
+    Logger::root(Discard, o!())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use slog::{o, Discard, Logger};
+
+    use crate::game_state::GameState;
+    use crate::interactive::{Action, InteractiveGame};
+
+    use super::{replay_from_log, Replay};
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let logger = Logger::root(Discard, o!());
+        let mut game = InteractiveGame::new();
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut replay = None;
+
+        game.interact_recorded(Action::StartGame, players[0], &mut replay, 1, &logger)
+            .unwrap();
+        for t in 0..8 {
+            let next = game.next_player().unwrap();
+            game.interact_recorded(Action::DrawCard, next, &mut replay, t + 2, &logger)
+                .unwrap();
+        }
+        // Failed actions aren't recorded.
+        let next = game.next_player().unwrap();
+        let other = players.iter().find(|p| **p != next).unwrap();
+        assert!(game
+            .interact_recorded(Action::DrawCard, *other, &mut replay, 10, &logger)
+            .is_err());
+
+        let replay = replay.unwrap();
+        assert_eq!(replay.len(), 8);
+        let live = serde_json::to_value(game.dump_state().unwrap()).unwrap();
+        let replayed = serde_json::to_value(replay.state_at(8).unwrap()).unwrap();
+        assert_eq!(live, replayed);
+
+        let mut cursor = replay.cursor().unwrap();
+        let start = serde_json::to_value(cursor.state()).unwrap();
+        assert!(cursor.forward().unwrap());
+        assert!(cursor.backward());
+        assert!(!cursor.backward());
+        assert_eq!(serde_json::to_value(cursor.state()).unwrap(), start);
+        cursor.seek(8).unwrap();
+        assert!(!cursor.forward().unwrap());
+        assert!(matches!(cursor.state(), GameState::Draw(_)));
+        assert!(cursor.seek(9).is_err());
+
+        let step = replay.step(7).unwrap();
+        assert_eq!(
+            serde_json::to_value(&step.before).unwrap(),
+            serde_json::to_value(replay.state_at(7).unwrap()).unwrap()
+        );
+        assert_eq!(serde_json::to_value(&step.after).unwrap(), live);
+        assert!(matches!(step.entry.action, Action::DrawCard));
+        assert!(replay.step(8).is_err());
+    }
+
+    #[test]
+    fn test_replay_from_log() {
+        let logger = Logger::root(Discard, o!());
+        let mut game = InteractiveGame::new();
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut replay = None;
+        game.interact_recorded(Action::StartGame, players[0], &mut replay, 1, &logger)
+            .unwrap();
+        for t in 0..8 {
+            let next = game.next_player().unwrap();
+            game.interact_recorded(Action::DrawCard, next, &mut replay, t + 2, &logger)
+                .unwrap();
+        }
+        let exported = serde_json::to_string(&replay.unwrap()).unwrap();
+        let log: Replay = serde_json::from_str(&exported).unwrap();
+
+        let seats = vec![("p1".to_string(), "q1".to_string())]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let (state, rebuilt) = replay_from_log(&log, &seats).unwrap();
+        assert_eq!(state.player_id("q1").unwrap(), players[0]);
+        assert_eq!(state.player_id("p2").unwrap(), players[1]);
+        assert!(state.player_id("p1").is_err());
+        assert_eq!(rebuilt.len(), 8);
+        let mut live = game.dump_state().unwrap();
+        live.rename_players(&seats).unwrap();
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::to_value(&live).unwrap()
+        );
+
+        // Seats can't be given to someone who's already in the room.
+        let seats = vec![("p1".to_string(), "p2".to_string())]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        assert!(replay_from_log(&log, &seats).is_err());
+    }
+}
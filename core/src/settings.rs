@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
 use anyhow::{bail, Error};
@@ -8,15 +8,24 @@ use slog_derive::KV;
 use url::Url;
 
 use shengji_mechanics::bidding::{
-    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy,
+    BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy, JokersOnlyTrumpPolicy,
 };
 use shengji_mechanics::deck::Deck;
 use shengji_mechanics::player::Player;
-use shengji_mechanics::scoring::GameScoringParameters;
-use shengji_mechanics::trick::{ThrowEvaluationPolicy, TractorRequirements, TrickDrawPolicy};
-use shengji_mechanics::types::{Card, Number, PlayerID, Rank};
+use shengji_mechanics::scoring::{GameScoringParameters, KittyPenalty};
+use shengji_mechanics::trick::{
+    ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, TrickDrawPolicy, TrickPlayPolicy,
+};
+use shengji_mechanics::types::{Card, Number, PlayerID, Rank, SuitOrder};
 
+use crate::game_state::UndoPoint;
 use crate::message::MessageVariant;
+use crate::pace::PaceTracker;
+use crate::settings_diff::SettingsPatch;
+
+/// The largest seed a deal can be shuffled with, which is the largest integer
+/// that a JavaScript number holds exactly.
+pub const MAX_DEAL_SEED: u64 = (1 << 53) - 1;
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, JsonSchema, Hash, PartialEq, Eq)]
 pub struct Friend {
@@ -91,19 +100,27 @@ pub enum ThrowPenalty {
     #[default]
     None,
     TenPointsPerAttempt,
+    /// Awards `points` to the opposing team for each card in the failed throw
+    /// which had to be taken back.
+    PointsPerCard {
+        points: usize,
+    },
 }
 
 shengji_mechanics::impl_slog_value!(ThrowPenalty);
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
-pub enum KittyPenalty {
-    #[default]
-    Times,
-    Power,
+impl ThrowPenalty {
+    /// The points awarded to the opposing team for a throw which failed,
+    /// forcing `failed_throw_size` cards to be taken back.
+    pub fn points(self, failed_throw_size: usize) -> usize {
+        match self {
+            ThrowPenalty::None => 0,
+            ThrowPenalty::TenPointsPerAttempt => 10,
+            ThrowPenalty::PointsPerCard { points } => points * failed_throw_size,
+        }
+    }
 }
 
-shengji_mechanics::impl_slog_value!(KittyPenalty);
-
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum AdvancementPolicy {
     #[default]
@@ -125,6 +142,22 @@ pub enum FriendSelectionPolicy {
 
 shengji_mechanics::impl_slog_value!(FriendSelectionPolicy);
 
+/// Restrictions on friend selection which apply in addition to the
+/// `FriendSelectionPolicy`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub struct FriendSelectionRestrictions {
+    #[serde(default)]
+    pub no_point_cards: bool,
+    #[serde(default)]
+    pub no_trumps: bool,
+    /// The largest N for which the Nth played copy of a card may be selected
+    /// as a friend. Defaults to the number of decks.
+    #[serde(default)]
+    pub max_occurrence: Option<usize>,
+}
+
+shengji_mechanics::impl_slog_value!(FriendSelectionRestrictions);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum MultipleJoinPolicy {
     #[default]
@@ -143,11 +176,44 @@ pub enum FirstLandlordSelectionPolicy {
 
 shengji_mechanics::impl_slog_value!(FirstLandlordSelectionPolicy);
 
+/// Who becomes the landlord in the first game if nobody bids.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum FirstDealLandlordPolicy {
+    /// A random player draws first and becomes the landlord.
+    #[default]
+    Random,
+    /// The first player to draw the given card becomes the landlord. If
+    /// nobody draws it, the player who drew first does.
+    FirstToDrawCard { card: Card },
+    /// The host picks the landlord, who also draws first.
+    AssignedByHost { player: PlayerID },
+}
+
+shengji_mechanics::impl_slog_value!(FirstDealLandlordPolicy);
+
+/// Who starts the next game when the landlord's team loses. If the
+/// landlord's team wins, the next player on it always does.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum LandlordRotationPolicy {
+    /// The next player on the landlord's team.
+    #[default]
+    NextOnTeam,
+    /// The next player, regardless of team.
+    Clockwise,
+    /// Whoever won the last trick of the game.
+    WinnerOfLastTrick,
+    /// Nobody; the landlord is decided by bidding in the next game.
+    HighestBidder,
+}
+
+shengji_mechanics::impl_slog_value!(LandlordRotationPolicy);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum KittyBidPolicy {
     #[default]
     FirstCard,
     FirstCardOfLevelOrHighest,
+    FirstSuitedCard,
 }
 
 shengji_mechanics::impl_slog_value!(KittyBidPolicy);
@@ -170,6 +236,35 @@ pub enum KittyTheftPolicy {
 
 shengji_mechanics::impl_slog_value!(KittyTheftPolicy);
 
+/// Which cards the landlord may not bury in the bottom. Restricted cards can
+/// still be buried if the landlord has nothing else left to bury.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum KittyBuryPolicy {
+    #[default]
+    Unrestricted,
+    NoPointCards,
+    NoTrumpCards,
+    NoPointOrTrumpCards,
+}
+
+impl KittyBuryPolicy {
+    pub fn forbids_points(self) -> bool {
+        matches!(
+            self,
+            KittyBuryPolicy::NoPointCards | KittyBuryPolicy::NoPointOrTrumpCards
+        )
+    }
+
+    pub fn forbids_trump(self) -> bool {
+        matches!(
+            self,
+            KittyBuryPolicy::NoTrumpCards | KittyBuryPolicy::NoPointOrTrumpCards
+        )
+    }
+}
+
+shengji_mechanics::impl_slog_value!(KittyBuryPolicy);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum GameShadowingPolicy {
     #[default]
@@ -197,6 +292,107 @@ pub enum GameVisibility {
 
 shengji_mechanics::impl_slog_value!(GameVisibility);
 
+/// Who can see the points won by the attacking team before the game ends.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum AttackingPointsVisibility {
+    #[default]
+    Visible,
+    HiddenFromLandlordTeam,
+    HiddenFromAll,
+}
+
+shengji_mechanics::impl_slog_value!(AttackingPointsVisibility);
+
+/// What happens to the players' ranks once a match has been won.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum RematchPolicy {
+    #[default]
+    KeepRanks,
+    ResetRanks,
+}
+
+shengji_mechanics::impl_slog_value!(RematchPolicy);
+
+/// What the server does when a player runs out of time on their turn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum TimeoutPolicy {
+    #[default]
+    MarkIdle,
+    /// Draw, put down the kitty, or play the lowest legal cards on the
+    /// player's behalf. Falls back to marking them idle if that isn't
+    /// possible, e.g. because they still have to pick friends.
+    AutoPlay,
+}
+
+shengji_mechanics::impl_slog_value!(TimeoutPolicy);
+
+/// How many seconds players get for their turn in each phase. Phases
+/// without a limit are untimed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub struct TurnTimers {
+    #[serde(default)]
+    pub bid_secs: Option<usize>,
+    #[serde(default)]
+    pub exchange_secs: Option<usize>,
+    #[serde(default)]
+    pub play_secs: Option<usize>,
+    #[serde(default)]
+    pub timeout_policy: TimeoutPolicy,
+}
+
+shengji_mechanics::impl_slog_value!(TurnTimers);
+
+/// A player's position at the end of a match.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MatchStanding {
+    pub player: PlayerID,
+    pub rank: Rank,
+    pub metalevel: usize,
+    pub won_match: bool,
+}
+
+/// A vote to kick a player out of the room.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KickVote {
+    pub target: PlayerID,
+    pub approvals: Vec<PlayerID>,
+    pub rejections: Vec<PlayerID>,
+}
+
+/// A pause requested by one of the players. Nobody can play until a
+/// majority of the players vote to resume.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Pause {
+    pub requested_by: PlayerID,
+    pub resume_votes: Vec<PlayerID>,
+}
+
+/// A request to undo a player's most recent play or bid, which goes ahead
+/// once every other player approves.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UndoVote {
+    pub requester: PlayerID,
+    pub approvals: Vec<PlayerID>,
+}
+
+/// A proposal to change some of the settings in the middle of a game, which
+/// goes ahead once every other player approves.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SettingsProposal {
+    pub proposer: PlayerID,
+    pub patch: SettingsPatch,
+    pub approvals: Vec<PlayerID>,
+}
+
+/// A spectator who can see a single player's hand, e.g. to teach them the
+/// game. The coach only sees the hand once the player approves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Coaching {
+    pub coach: PlayerID,
+    pub student: PlayerID,
+    pub approved: bool,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct MaxRank(Rank);
 shengji_mechanics::impl_slog_value!(MaxRank);
@@ -219,6 +415,33 @@ pub struct PropagatedState {
     pub(crate) players: Vec<Player>,
     #[slog(skip)]
     pub(crate) observers: Vec<Player>,
+    /// Users who are only watching. Unlike observers, spectators never join
+    /// the game, and can't see any player's hand.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) spectators: Vec<Player>,
+    /// The first user to join the room, who can ban other users from it.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) host: Option<PlayerID>,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) kick_vote: Option<KickVote>,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) pause: Option<Pause>,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) undo_vote: Option<UndoVote>,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) settings_proposal: Option<SettingsProposal>,
+    /// The state before the most recent play or bid, if nothing else has
+    /// happened since. Never sent to clients.
+    #[slog(skip)]
+    #[serde(default)]
+    #[schemars(skip)]
+    pub(crate) undo_point: Option<UndoPoint>,
     #[slog(skip)]
     pub(crate) landlord: Option<PlayerID>,
     #[slog(skip)]
@@ -229,11 +452,15 @@ pub struct PropagatedState {
 
     pub(crate) game_mode: GameModeSettings,
     #[serde(default)]
+    pub(crate) fixed_partnerships: bool,
+    #[serde(default)]
     pub(crate) hide_landlord_points: bool,
     pub(crate) kitty_size: Option<usize>,
     #[serde(default)]
     pub(crate) friend_selection_policy: FriendSelectionPolicy,
     #[serde(default)]
+    pub(crate) friend_selection_restrictions: FriendSelectionRestrictions,
+    #[serde(default)]
     pub(crate) multiple_join_policy: MultipleJoinPolicy,
     pub(crate) num_decks: Option<usize>,
     // TODO: Find a way to log this properly.
@@ -245,30 +472,61 @@ pub struct PropagatedState {
     pub(crate) chat_link: Option<String>,
     #[serde(default)]
     pub(crate) advancement_policy: AdvancementPolicy,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) advancement_gates: Vec<Rank>,
     #[serde(default)]
     pub(crate) kitty_penalty: KittyPenalty,
     #[serde(default)]
     pub(crate) throw_penalty: ThrowPenalty,
     #[serde(default)]
     pub(crate) hide_played_cards: bool,
+    /// How many of the game's most recent tricks are kept for players to
+    /// look back over, or all of them if unset.
+    #[serde(default)]
+    pub(crate) trick_history_length: Option<usize>,
     #[serde(default)]
     pub(crate) kitty_bid_policy: KittyBidPolicy,
     #[serde(default)]
     pub(crate) kitty_theft_policy: KittyTheftPolicy,
     #[serde(default)]
+    pub(crate) kitty_bury_policy: KittyBuryPolicy,
+    /// The most points which may be buried in the bottom altogether. Like
+    /// the kitty bury policy, this doesn't apply if the landlord has no
+    /// other cards left to bury.
+    #[serde(default)]
+    pub(crate) max_kitty_points: Option<usize>,
+    #[serde(default)]
     pub(crate) trick_draw_policy: TrickDrawPolicy,
     #[serde(default)]
     pub(crate) throw_evaluation_policy: ThrowEvaluationPolicy,
     #[serde(default)]
+    pub(crate) throw_tie_policy: ThrowTiePolicy,
+    #[serde(default)]
+    pub(crate) trick_play_policy: TrickPlayPolicy,
+    #[serde(default)]
     pub(crate) first_landlord_selection_policy: FirstLandlordSelectionPolicy,
     #[serde(default)]
+    pub(crate) first_deal_landlord_policy: FirstDealLandlordPolicy,
+    #[serde(default)]
+    pub(crate) landlord_rotation_policy: LandlordRotationPolicy,
+    #[serde(default)]
     pub(crate) bid_policy: BidPolicy,
     #[serde(default)]
     pub(crate) bid_reinforcement_policy: BidReinforcementPolicy,
     #[serde(default)]
     pub(crate) joker_bid_policy: JokerBidPolicy,
     #[serde(default)]
+    pub(crate) no_trump_only_from: Option<Number>,
+    #[serde(default)]
+    pub(crate) jokers_only_trump_policy: JokersOnlyTrumpPolicy,
+    #[serde(default)]
     pub(crate) should_reveal_kitty_at_end_of_game: bool,
+    /// Announce the cards in the bottom one at a time at the end of the
+    /// game, each with the points it adds after the kitty multiplier, so
+    /// that clients can animate the reveal.
+    #[serde(default)]
+    pub(crate) reveal_kitty_card_by_card: bool,
     #[serde(default)]
     pub(crate) play_takeback_policy: PlayTakebackPolicy,
     #[serde(default)]
@@ -281,12 +539,73 @@ pub struct PropagatedState {
     pub(crate) game_scoring_parameters: GameScoringParameters,
     #[serde(default)]
     pub(crate) hide_throw_halting_player: bool,
+    /// Players who can't follow suit or trump play their discards
+    /// face-down, so that nobody else sees them until the trick is won.
+    #[serde(default)]
+    pub(crate) face_down_discards: bool,
     #[serde(default)]
     pub(crate) tractor_requirements: TractorRequirements,
     #[serde(default)]
+    pub(crate) suit_order: SuitOrder,
+    #[serde(default)]
     pub(crate) max_rank: MaxRank,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) rank_sequence: Option<Vec<Rank>>,
     #[serde(default)]
     pub(crate) game_visibility: GameVisibility,
+    #[serde(default)]
+    pub(crate) attacking_points_visibility: AttackingPointsVisibility,
+    /// When set, the first team to defend a game at this rank wins the match.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) match_target_rank: Option<Rank>,
+    #[serde(default)]
+    pub(crate) rematch_policy: RematchPolicy,
+    #[serde(default)]
+    pub(crate) num_matches_finished: usize,
+    #[serde(default)]
+    pub(crate) turn_timers: TurnTimers,
+    /// How many seconds behind the table spectators see the game, so that
+    /// they can't coach the players.
+    #[serde(default)]
+    pub(crate) spectator_delay_secs: usize,
+    /// Whether the outcomes of games in the room change the ratings of the
+    /// players who have claimed their nicknames.
+    #[serde(default)]
+    pub(crate) ranked: bool,
+    /// Shuffle the deck with this seed rather than a random one, so that the
+    /// same deal can be played again, e.g. at every table of a duplicate
+    /// tournament. Only the host can see it, since it gives away the deal.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) deal_seed: Option<u64>,
+    /// The seed which the deck of the current or most recent game was
+    /// shuffled with. Kept secret until the game is over.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) shuffle_seed: Option<u64>,
+    /// Players who ran out of time, until they next take an action.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) idle_players: Vec<PlayerID>,
+    /// Receives a JSON payload as games in the room start and finish. Only
+    /// the host can see it, since it may embed a secret.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) webhook_url: Option<String>,
+    /// Players whose moves are made by the server.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) bots: Vec<PlayerID>,
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) coaches: Vec<Coaching>,
+    /// How long everyone has taken over their moves in the current or most
+    /// recent game.
+    #[slog(skip)]
+    #[serde(default)]
+    pub(crate) pace: PaceTracker,
 }
 
 impl PropagatedState {
@@ -298,6 +617,114 @@ impl PropagatedState {
         &self.observers
     }
 
+    pub fn spectators(&self) -> &[Player] {
+        &self.spectators
+    }
+
+    pub fn is_spectator(&self, id: PlayerID) -> bool {
+        self.spectators.iter().any(|p| p.id == id)
+    }
+
+    /// Falls back to the first player for rooms which were created before
+    /// hosts were tracked, or whose host has left.
+    pub fn host(&self) -> Option<PlayerID> {
+        self.host.or_else(|| self.players.first().map(|p| p.id))
+    }
+
+    pub fn turn_timers(&self) -> TurnTimers {
+        self.turn_timers
+    }
+
+    pub fn spectator_delay_secs(&self) -> usize {
+        self.spectator_delay_secs
+    }
+
+    pub fn ranked(&self) -> bool {
+        self.ranked
+    }
+
+    pub fn shuffle_seed(&self) -> Option<u64> {
+        self.shuffle_seed
+    }
+
+    pub fn idle_players(&self) -> &[PlayerID] {
+        &self.idle_players
+    }
+
+    pub fn kick_vote(&self) -> Option<&KickVote> {
+        self.kick_vote.as_ref()
+    }
+
+    pub fn pause(&self) -> Option<&Pause> {
+        self.pause.as_ref()
+    }
+
+    pub fn undo_vote(&self) -> Option<&UndoVote> {
+        self.undo_vote.as_ref()
+    }
+
+    pub fn settings_proposal(&self) -> Option<&SettingsProposal> {
+        self.settings_proposal.as_ref()
+    }
+
+    pub fn bots(&self) -> &[PlayerID] {
+        &self.bots
+    }
+
+    pub fn coaches(&self) -> &[Coaching] {
+        &self.coaches
+    }
+
+    /// The player whose hand `coach` can see, if they've approved it.
+    pub fn coached_player(&self, coach: PlayerID) -> Option<PlayerID> {
+        self.coaches
+            .iter()
+            .find(|c| c.coach == coach && c.approved)
+            .map(|c| c.student)
+    }
+
+    pub fn is_bot(&self, id: PlayerID) -> bool {
+        self.bots.contains(&id)
+    }
+
+    fn name_taken(&self, name: &str) -> bool {
+        self.players
+            .iter()
+            .chain(self.observers.iter())
+            .chain(self.spectators.iter())
+            .any(|p| p.name == name)
+    }
+
+    /// Renames everyone in the room from the keys of `names` to the values,
+    /// leaving anyone who isn't in `names` as they were.
+    pub fn rename_players(&mut self, names: &HashMap<String, String>) -> Result<(), Error> {
+        for p in self
+            .players
+            .iter_mut()
+            .chain(self.observers.iter_mut())
+            .chain(self.spectators.iter_mut())
+        {
+            if let Some(name) = names.get(&p.name) {
+                p.name = name.clone();
+            }
+        }
+        let mut seen = HashSet::new();
+        for p in self
+            .players
+            .iter()
+            .chain(self.observers.iter())
+            .chain(self.spectators.iter())
+        {
+            if p.name.is_empty() {
+                bail!("players must have a name");
+            }
+            if !seen.insert(&p.name) {
+                bail!("more than one player would be named {}", p.name);
+            }
+        }
+        Ok(())
+    }
+
     pub fn landlord(&self) -> Option<PlayerID> {
         self.landlord
     }
@@ -306,6 +733,14 @@ impl PropagatedState {
         self.trick_draw_policy
     }
 
+    pub fn kitty_bury_policy(&self) -> KittyBuryPolicy {
+        self.kitty_bury_policy
+    }
+
+    pub fn jokers_only_trump_policy(&self) -> JokersOnlyTrumpPolicy {
+        self.jokers_only_trump_policy
+    }
+
     pub fn num_decks(&self) -> usize {
         self.num_decks.unwrap_or(self.players.len() / 2)
     }
@@ -314,6 +749,10 @@ impl PropagatedState {
         self.game_visibility
     }
 
+    pub fn game_mode(&self) -> GameModeSettings {
+        self.game_mode
+    }
+
     pub fn decks(&self) -> Result<Vec<Deck>, Error> {
         let mut decks = self.special_decks.clone();
         let num_decks = self.num_decks();
@@ -332,12 +771,51 @@ impl PropagatedState {
         game_mode: GameModeSettings,
     ) -> Result<Vec<MessageVariant>, Error> {
         self.game_mode = game_mode;
-        Ok(vec![MessageVariant::GameModeSet { game_mode }])
+        let mut msgs = vec![MessageVariant::GameModeSet { game_mode }];
+        if game_mode != GameModeSettings::Tractor {
+            msgs.extend(self.set_fixed_partnerships(false)?);
+        }
+        Ok(msgs)
+    }
+
+    pub fn set_fixed_partnerships(
+        &mut self,
+        fixed_partnerships: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if fixed_partnerships && self.game_mode != GameModeSettings::Tractor {
+            bail!("fixed partnerships are only available in Tractor")
+        }
+        if self.fixed_partnerships != fixed_partnerships {
+            self.fixed_partnerships = fixed_partnerships;
+            Ok(vec![MessageVariant::FixedPartnershipsSet {
+                set: fixed_partnerships,
+            }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// The players on the same team as `id` (including `id`), if partnerships
+    /// are fixed. Partners sit in alternating seats.
+    pub fn partners(&self, id: PlayerID) -> Option<Vec<PlayerID>> {
+        if !self.fixed_partnerships {
+            return None;
+        }
+        let position = self.players.iter().position(|p| p.id == id)?;
+        Some(
+            self.players
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| idx % 2 == position % 2)
+                .map(|(_, p)| p.id)
+                .collect(),
+        )
     }
 
     fn num_players_changed(&mut self) -> Result<Vec<MessageVariant>, Error> {
         let mut msgs = vec![];
         msgs.extend(self.set_num_decks(None)?);
+        msgs.extend(self.revalidate_kitty_size()?);
 
         if let GameModeSettings::FindingFriends {
             ref mut num_friends,
@@ -356,22 +834,43 @@ impl PropagatedState {
 
     pub fn add_player(&mut self, name: String) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
         let id = PlayerID(self.max_player_id);
-        if self.players.iter().any(|p| p.name == name)
-            || self.observers.iter().any(|p| p.name == name)
-        {
+        if self.name_taken(&name) {
             bail!("player with name already exists!")
         }
 
         let mut msgs = vec![MessageVariant::JoinedGame { player: id }];
 
         self.max_player_id += 1;
-        self.players.push(Player::new(id, name));
+        self.host.get_or_insert(id);
+        let mut player = Player::new(id, name);
+        if let Some(first) = self.rank_sequence.as_ref().and_then(|s| s.first()) {
+            player.set_rank(*first);
+        }
+        self.players.push(player);
 
         msgs.extend(self.num_players_changed()?);
         Ok((id, msgs))
     }
 
+    /// Seats a player whose moves are made by the server, under the first
+    /// free name of the form "Bot N".
+    pub fn add_bot(&mut self) -> Result<(PlayerID, Vec<MessageVariant>), Error> {
+        let name = (1..)
+            .map(|n| format!("Bot {n}"))
+            .find(|name| !self.name_taken(name))
+            .unwrap();
+        // Bots can't act as the host.
+        let host = self.host;
+        let (id, msgs) = self.add_player(name)?;
+        self.host = host;
+        self.bots.push(id);
+        Ok((id, msgs))
+    }
+
     pub fn reorder_players(&mut self, order: &[PlayerID]) -> Result<(), Error> {
+        if self.fixed_partnerships && self.num_games_finished > 0 {
+            bail!("can't change seats once fixed partnerships have played a game")
+        }
         let uniq = order.iter().cloned().collect::<HashSet<PlayerID>>();
         if uniq.len() != self.players.len() {
             bail!("Incorrect number of players");
@@ -389,13 +888,12 @@ impl PropagatedState {
 
     pub fn add_observer(&mut self, name: String) -> Result<PlayerID, Error> {
         let id = PlayerID(self.max_player_id);
-        if self.players.iter().any(|p| p.name == name)
-            || self.observers.iter().any(|p| p.name == name)
-        {
+        if self.name_taken(&name) {
             bail!("player with name already exists!")
         }
 
         self.max_player_id += 1;
+        self.host.get_or_insert(id);
         self.observers.push(Player::new(id, name));
         Ok(id)
     }
@@ -419,6 +917,25 @@ impl PropagatedState {
         Ok(())
     }
 
+    pub fn add_spectator(&mut self, name: String) -> Result<PlayerID, Error> {
+        let id = PlayerID(self.max_player_id);
+        if self.name_taken(&name) {
+            bail!("player with name already exists!")
+        }
+
+        self.max_player_id += 1;
+        self.spectators.push(Player::new(id, name));
+        Ok(id)
+    }
+
+    pub fn remove_spectator(&mut self, id: PlayerID) -> Result<(), Error> {
+        if !self.is_spectator(id) {
+            bail!("spectator not found")
+        }
+        self.spectators.retain(|p| p.id != id);
+        Ok(())
+    }
+
     pub fn set_chat_link(&mut self, chat_link: Option<String>) -> Result<(), Error> {
         if chat_link.as_ref().map(|link| link.len()).unwrap_or(0) >= 128 {
             bail!("link too long");
@@ -436,10 +953,42 @@ impl PropagatedState {
         Ok(())
     }
 
+    pub fn webhook_url(&self) -> Option<&str> {
+        self.webhook_url.as_deref()
+    }
+
+    pub fn set_webhook_url(
+        &mut self,
+        webhook_url: Option<String>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if let Some(ref webhook_url) = webhook_url {
+            if webhook_url.len() >= 256 {
+                bail!("link too long");
+            }
+            match Url::parse(webhook_url) {
+                Ok(u) if u.scheme() == "http" || u.scheme() == "https" => (),
+                Ok(_) => bail!("must be http or https URL"),
+                Err(_) => bail!("Invalid URL"),
+            }
+        }
+        let msgs = if webhook_url.is_some() != self.webhook_url.is_some() {
+            vec![MessageVariant::WebhookSet {
+                enabled: webhook_url.is_some(),
+            }]
+        } else {
+            vec![]
+        };
+        self.webhook_url = webhook_url;
+        Ok(msgs)
+    }
+
     pub fn set_special_decks(
         &mut self,
         special_decks: Vec<Deck>,
     ) -> Result<Vec<MessageVariant>, Error> {
+        if special_decks.iter().any(|d| d.is_empty()) {
+            bail!("Every deck needs to have at least one card")
+        }
         let mut messages = vec![];
         if special_decks.len() > self.num_decks() {
             messages.extend(self.set_num_decks(Some(special_decks.len()))?);
@@ -449,6 +998,7 @@ impl PropagatedState {
         messages.push(MessageVariant::SpecialDecksSet {
             special_decks: self.special_decks.clone(),
         });
+        messages.extend(self.revalidate_kitty_size()?);
         Ok(messages)
     }
 
@@ -474,7 +1024,20 @@ impl PropagatedState {
                 });
             }
 
-            msgs.extend(self.set_kitty_size(None)?);
+            msgs.extend(self.revalidate_kitty_size()?);
+            if self
+                .friend_selection_restrictions
+                .max_occurrence
+                .map(|n| n > self.num_decks())
+                .unwrap_or(false)
+            {
+                msgs.extend(self.set_friend_selection_restrictions(
+                    FriendSelectionRestrictions {
+                        max_occurrence: None,
+                        ..self.friend_selection_restrictions
+                    },
+                )?);
+            }
             if self
                 .game_scoring_parameters
                 .materialize(&self.decks()?)
@@ -494,33 +1057,53 @@ impl PropagatedState {
             return Ok(None);
         }
         if let Some(size) = kitty_size {
-            if self.players.is_empty() {
-                bail!("no players")
-            }
-            let decks = self.decks()?;
-            let deck_len = decks.iter().map(|d| d.len()).sum::<usize>();
-            if size >= deck_len {
-                bail!("kitty size too large")
-            }
-            let min = decks.iter().map(|d| d.min).min().unwrap_or(Number::Two);
-            let n_decks_with_min = decks.iter().filter(|d| d.includes_number(min)).count();
-
-            // We only allow removing four cards per deck (i.e. one per suit per deck), so check to
-            // make sure that things will work out.
-            let num_cards_to_remove = (deck_len - size) % self.players.len();
-            if num_cards_to_remove > n_decks_with_min * 4 {
-                bail!("kitty size requires removing too many cards");
-            }
-
-            self.kitty_size = Some(size);
-        } else {
-            self.kitty_size = None;
+            self.validate_kitty_size(size)?;
         }
+        self.kitty_size = kitty_size;
         Ok(Some(MessageVariant::KittySizeSet {
             size: self.kitty_size,
         }))
     }
 
+    fn validate_kitty_size(&self, size: usize) -> Result<(), Error> {
+        if self.players.is_empty() {
+            bail!("no players")
+        }
+        let decks = self.decks()?;
+        let deck_len = decks.iter().map(|d| d.len()).sum::<usize>();
+        // Every player needs to be dealt at least one card.
+        if size + self.players.len() > deck_len {
+            bail!("kitty size too large")
+        }
+        let min = decks
+            .iter()
+            .flat_map(|d| d.lowest_number())
+            .min()
+            .unwrap_or(Number::Two);
+        let n_cards_with_min = decks
+            .iter()
+            .filter(|d| d.includes_number(min))
+            .map(|d| d.suits().count())
+            .sum::<usize>();
+
+        // We only allow removing one card per suit per deck, so check to make sure that things
+        // will work out.
+        let num_cards_to_remove = (deck_len - size) % self.players.len();
+        if num_cards_to_remove > n_cards_with_min {
+            bail!("kitty size requires removing too many cards");
+        }
+        Ok(())
+    }
+
+    /// Resets the kitty size override to the default if it's no longer valid,
+    /// e.g. because the decks or the number of players changed.
+    fn revalidate_kitty_size(&mut self) -> Result<Option<MessageVariant>, Error> {
+        match self.kitty_size {
+            Some(size) if self.validate_kitty_size(size).is_err() => self.set_kitty_size(None),
+            _ => Ok(None),
+        }
+    }
+
     pub fn set_friend_selection_policy(
         &mut self,
         policy: FriendSelectionPolicy,
@@ -529,6 +1112,27 @@ impl PropagatedState {
         Ok(vec![MessageVariant::FriendSelectionPolicySet { policy }])
     }
 
+    pub fn set_friend_selection_restrictions(
+        &mut self,
+        restrictions: FriendSelectionRestrictions,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        match restrictions.max_occurrence {
+            Some(0) => bail!("friends must be selected by at least the first played card"),
+            Some(n) if n > self.num_decks() => {
+                bail!("can't select more copies of a card than there are decks")
+            }
+            _ => (),
+        }
+        if restrictions != self.friend_selection_restrictions {
+            self.friend_selection_restrictions = restrictions;
+            Ok(vec![MessageVariant::FriendSelectionRestrictionsSet {
+                restrictions,
+            }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_multiple_join_policy(
         &mut self,
         policy: MultipleJoinPolicy,
@@ -547,7 +1151,46 @@ impl PropagatedState {
         }])
     }
 
+    pub fn set_first_deal_landlord_policy(
+        &mut self,
+        policy: FirstDealLandlordPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy == self.first_deal_landlord_policy {
+            return Ok(vec![]);
+        }
+        match policy {
+            FirstDealLandlordPolicy::Random => (),
+            FirstDealLandlordPolicy::FirstToDrawCard { card } => {
+                if card == Card::Unknown {
+                    bail!("can't select the landlord with an unknown card")
+                }
+            }
+            FirstDealLandlordPolicy::AssignedByHost { player } => {
+                if !self.players.iter().any(|p| p.id == player) {
+                    bail!("player ID not found")
+                }
+            }
+        }
+        self.first_deal_landlord_policy = policy;
+        Ok(vec![MessageVariant::FirstDealLandlordPolicySet { policy }])
+    }
+
+    pub fn set_landlord_rotation_policy(
+        &mut self,
+        policy: LandlordRotationPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy != self.landlord_rotation_policy {
+            self.landlord_rotation_policy = policy;
+            Ok(vec![MessageVariant::LandlordRotationPolicySet { policy }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_bid_policy(&mut self, policy: BidPolicy) -> Result<Vec<MessageVariant>, Error> {
+        if let BidPolicy::GreaterLengthByMargin { margin: 0 } = policy {
+            bail!("overbids must have at least one more card")
+        }
         self.bid_policy = policy;
         Ok(vec![MessageVariant::BidPolicySet { policy }])
     }
@@ -565,7 +1208,34 @@ impl PropagatedState {
         policy: JokerBidPolicy,
     ) -> Result<Vec<MessageVariant>, Error> {
         self.joker_bid_policy = policy;
-        Ok(vec![MessageVariant::JokerBidPolicySet { policy }])
+        let mut msgs = vec![MessageVariant::JokerBidPolicySet { policy }];
+        if policy == JokerBidPolicy::Disabled {
+            msgs.extend(self.set_no_trump_only_from(None)?);
+        }
+        Ok(msgs)
+    }
+
+    pub fn set_no_trump_only_from(
+        &mut self,
+        level: Option<Number>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if level.is_some() && self.joker_bid_policy == JokerBidPolicy::Disabled {
+            bail!("no-trump bids are disabled")
+        }
+        if self.no_trump_only_from != level {
+            self.no_trump_only_from = level;
+            Ok(vec![MessageVariant::NoTrumpOnlyFromSet { level }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_jokers_only_trump_policy(
+        &mut self,
+        policy: JokersOnlyTrumpPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        self.jokers_only_trump_policy = policy;
+        Ok(vec![MessageVariant::JokersOnlyTrumpPolicySet { policy }])
     }
 
     pub fn set_should_reveal_kitty_at_end_of_game(
@@ -578,6 +1248,20 @@ impl PropagatedState {
         }])
     }
 
+    pub fn set_reveal_kitty_card_by_card(
+        &mut self,
+        card_by_card: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if card_by_card != self.reveal_kitty_card_by_card {
+            self.reveal_kitty_card_by_card = card_by_card;
+            Ok(vec![MessageVariant::RevealKittyCardByCardSet {
+                card_by_card,
+            }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_landlord(&mut self, landlord: Option<PlayerID>) -> Result<(), Error> {
         match landlord {
             Some(landlord) => {
@@ -614,10 +1298,25 @@ impl PropagatedState {
         })
     }
 
+    pub fn set_trick_history_length(
+        &mut self,
+        length: Option<usize>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if length != self.trick_history_length {
+            self.trick_history_length = length;
+            Ok(vec![MessageVariant::TrickHistoryLengthSet { length }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_throw_penalty(
         &mut self,
         penalty: ThrowPenalty,
     ) -> Result<Vec<MessageVariant>, Error> {
+        if let ThrowPenalty::PointsPerCard { points: 0 } = penalty {
+            bail!("Throw penalty must be at least one point per card");
+        }
         if penalty != self.throw_penalty {
             self.throw_penalty = penalty;
             Ok(vec![MessageVariant::ThrowPenaltySet {
@@ -678,6 +1377,30 @@ impl PropagatedState {
         }
     }
 
+    pub fn set_throw_tie_policy(
+        &mut self,
+        policy: ThrowTiePolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy != self.throw_tie_policy {
+            self.throw_tie_policy = policy;
+            Ok(vec![MessageVariant::ThrowTiePolicySet { policy }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_trick_play_policy(
+        &mut self,
+        policy: TrickPlayPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy != self.trick_play_policy {
+            self.trick_play_policy = policy;
+            Ok(vec![MessageVariant::TrickPlayPolicySet { policy }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_play_takeback_policy(
         &mut self,
         policy: PlayTakebackPolicy,
@@ -714,6 +1437,20 @@ impl PropagatedState {
         }
     }
 
+    pub fn set_advancement_gates(
+        &mut self,
+        mut gates: Vec<Rank>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        gates.sort();
+        gates.dedup();
+        if gates != self.advancement_gates {
+            self.advancement_gates = gates.clone();
+            Ok(vec![MessageVariant::AdvancementGatesSet { gates }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_game_scoring_parameters(
         &mut self,
         parameters: GameScoringParameters,
@@ -747,6 +1484,39 @@ impl PropagatedState {
         }
     }
 
+    pub fn set_suit_order(&mut self, order: SuitOrder) -> Result<Vec<MessageVariant>, Error> {
+        if order != self.suit_order {
+            self.suit_order = order;
+            Ok(vec![MessageVariant::SuitOrderSet { order }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_kitty_bury_policy(
+        &mut self,
+        policy: KittyBuryPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy != self.kitty_bury_policy {
+            self.kitty_bury_policy = policy;
+            Ok(vec![MessageVariant::KittyBuryPolicySet { policy }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_max_kitty_points(
+        &mut self,
+        max: Option<usize>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if max != self.max_kitty_points {
+            self.max_kitty_points = max;
+            Ok(vec![MessageVariant::MaxKittyPointsSet { max }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn set_game_visibility(
         &mut self,
         game_visibility: GameVisibility,
@@ -761,6 +1531,141 @@ impl PropagatedState {
         }
     }
 
+    pub fn set_attacking_points_visibility(
+        &mut self,
+        visibility: AttackingPointsVisibility,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if visibility != self.attacking_points_visibility {
+            self.attacking_points_visibility = visibility;
+            Ok(vec![MessageVariant::AttackingPointsVisibilitySet {
+                visibility,
+            }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_match_target_rank(
+        &mut self,
+        rank: Option<Rank>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if rank == self.match_target_rank {
+            return Ok(vec![]);
+        }
+        if let Some(rank) = rank {
+            if !self.rank_in_sequence(rank) {
+                bail!("The match target must be one of the ranks being played");
+            }
+        }
+        self.match_target_rank = rank;
+        Ok(vec![MessageVariant::MatchTargetRankSet { rank }])
+    }
+
+    pub fn set_turn_timers(&mut self, timers: TurnTimers) -> Result<Vec<MessageVariant>, Error> {
+        for secs in [timers.bid_secs, timers.exchange_secs, timers.play_secs]
+            .iter()
+            .flatten()
+        {
+            if !(5..=600).contains(secs) {
+                bail!("Turn timers must be between 5 and 600 seconds");
+            }
+        }
+        if timers != self.turn_timers {
+            self.turn_timers = timers;
+            Ok(vec![MessageVariant::TurnTimersSet { timers }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_spectator_delay_secs(&mut self, secs: usize) -> Result<Vec<MessageVariant>, Error> {
+        if secs > 300 {
+            bail!("Spectators can be delayed by at most 300 seconds");
+        }
+        if secs != self.spectator_delay_secs {
+            self.spectator_delay_secs = secs;
+            Ok(vec![MessageVariant::SpectatorDelaySet { secs }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_ranked(&mut self, ranked: bool) -> Result<Vec<MessageVariant>, Error> {
+        if ranked != self.ranked {
+            self.ranked = ranked;
+            Ok(vec![MessageVariant::RankedSet { ranked }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_deal_seed(&mut self, seed: Option<u64>) -> Result<Vec<MessageVariant>, Error> {
+        if seed.map(|s| s > MAX_DEAL_SEED).unwrap_or(false) {
+            bail!("Deal seeds can be at most {}", MAX_DEAL_SEED);
+        }
+        if seed != self.deal_seed {
+            self.deal_seed = seed;
+            Ok(vec![MessageVariant::DealSeedSet {
+                fixed: seed.is_some(),
+            }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn set_rematch_policy(
+        &mut self,
+        policy: RematchPolicy,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if policy != self.rematch_policy {
+            self.rematch_policy = policy;
+            Ok(vec![MessageVariant::RematchPolicySet { policy }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Ends the current match in favor of `winners`, reporting the final
+    /// standings and resetting ranks if the rematch policy calls for it.
+    pub(crate) fn finish_match(&mut self, winners: &[PlayerID]) -> Vec<MessageVariant> {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|p| MatchStanding {
+                player: p.id,
+                rank: p.rank(),
+                metalevel: p.metalevel,
+                won_match: winners.contains(&p.id),
+            })
+            .collect::<Vec<_>>();
+        standings.sort_by(|a, b| {
+            b.won_match
+                .cmp(&a.won_match)
+                .then(b.metalevel.cmp(&a.metalevel))
+                .then(b.rank.cmp(&a.rank))
+        });
+        self.num_matches_finished += 1;
+
+        let mut msgs = vec![MessageVariant::MatchComplete {
+            winners: winners.to_vec(),
+            standings,
+        }];
+        if self.rematch_policy == RematchPolicy::ResetRanks {
+            let first = self
+                .rank_sequence
+                .as_ref()
+                .and_then(|s| s.first())
+                .copied()
+                .unwrap_or(Rank::Number(Number::Two));
+            for player in self.players.iter_mut() {
+                player.set_rank(first);
+                player.set_meta_rank(1);
+            }
+            msgs.push(MessageVariant::RanksResetForRematch);
+        }
+        msgs
+    }
+
     pub fn set_user_multiple_game_session_policy(
         &mut self,
         policy: GameShadowingPolicy,
@@ -799,6 +1704,18 @@ impl PropagatedState {
         }
     }
 
+    pub fn set_face_down_discards(
+        &mut self,
+        face_down: bool,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if self.face_down_discards != face_down {
+            self.face_down_discards = face_down;
+            Ok(vec![MessageVariant::FaceDownDiscardsSet { face_down }])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn make_observer(&mut self, player_id: PlayerID) -> Result<Vec<MessageVariant>, Error> {
         if let Some(player) = self.players.iter().find(|p| p.id == player_id).cloned() {
             self.players.retain(|p| p.id != player_id);
@@ -823,7 +1740,9 @@ impl PropagatedState {
     }
 
     pub fn make_all_observers_into_players(&mut self) -> Result<Vec<MessageVariant>, Error> {
-        if self.observers.is_empty() {
+        // With fixed partnerships, observers can't join without breaking up
+        // the teams, so they have to wait to be seated explicitly.
+        if self.observers.is_empty() || self.fixed_partnerships {
             return Ok(vec![]);
         }
         let mut msgs = vec![];
@@ -836,12 +1755,20 @@ impl PropagatedState {
     }
 
     pub fn set_rank(&mut self, player_id: PlayerID, level: Rank) -> Result<(), Error> {
+        if !self.rank_in_sequence(level) {
+            bail!("{} isn't one of the ranks being played", level.as_str());
+        }
+        let team = self.partners(player_id).unwrap_or_else(|| vec![player_id]);
         match self.players.iter_mut().find(|p| p.id == player_id) {
             Some(ref mut player) => {
                 player.set_rank(level);
             }
             None => bail!("player not found"),
         }
+        // Partners always share a rank.
+        for player in self.players.iter_mut().filter(|p| team.contains(&p.id)) {
+            player.set_rank(level);
+        }
         Ok(())
     }
 
@@ -856,10 +1783,56 @@ impl PropagatedState {
     }
 
     pub fn set_max_rank(&mut self, level: Rank) -> Result<(), Error> {
+        if self.rank_sequence.is_some() {
+            bail!("The max rank is the last rank in the rank sequence");
+        }
         self.max_rank = MaxRank(level);
         Ok(())
     }
 
+    pub fn rank_in_sequence(&self, level: Rank) -> bool {
+        self.rank_sequence
+            .as_ref()
+            .map(|s| s.contains(&level))
+            .unwrap_or(true)
+    }
+
+    /// Restricts the ranks which are played to `sequence`, in order. Players
+    /// whose rank isn't in the sequence are moved to its first rank.
+    pub fn set_rank_sequence(
+        &mut self,
+        sequence: Option<Vec<Rank>>,
+    ) -> Result<Vec<MessageVariant>, Error> {
+        if sequence == self.rank_sequence {
+            return Ok(vec![]);
+        }
+        if let Some(ref sequence) = sequence {
+            let (first, last) = match (sequence.first(), sequence.last()) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => bail!("The rank sequence must have at least one rank"),
+            };
+            let mut distinct = sequence.clone();
+            distinct.sort();
+            distinct.dedup();
+            if distinct.len() != sequence.len() {
+                bail!("The rank sequence can't repeat ranks");
+            }
+            self.max_rank = MaxRank(last);
+            for player in self.players.iter_mut() {
+                if !sequence.contains(&player.rank()) {
+                    player.set_rank(first);
+                }
+            }
+            if let Some(target) = self.match_target_rank {
+                if !sequence.contains(&target) {
+                    self.match_target_rank = None;
+                }
+            }
+        }
+        self.rank_sequence = sequence.clone();
+        Ok(vec![MessageVariant::RankSequenceSet { sequence }])
+    }
+
     pub fn set_tractor_requirements(
         &mut self,
         tractor_requirements: TractorRequirements,
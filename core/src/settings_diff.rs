@@ -0,0 +1,230 @@
+//! Comparing and merging the settings of a room, e.g. to load a saved set of
+//! settings in one go, and to tell everyone in the room what it changed.
+
+use anyhow::{anyhow, bail, Error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::settings::PropagatedState;
+
+/// Some of the room's settings, keyed by their name in `PropagatedState`.
+/// Settings which aren't included are left as they were.
+pub type SettingsPatch = serde_json::Map<String, Value>;
+
+/// The settings which can be diffed and merged, in the order they're merged
+/// in, so that settings which are validated against others come after them.
+/// Room state like the players, and secrets like the webhook and the deal
+/// seed, aren't included.
+const SETTINGS: &[&str] = &[
+    "game_mode",
+    "fixed_partnerships",
+    "num_decks",
+    "special_decks",
+    "kitty_size",
+    "friend_selection_policy",
+    "friend_selection_restrictions",
+    "multiple_join_policy",
+    "hide_landlord_points",
+    "hide_played_cards",
+    "landlord_emoji",
+    "chat_link",
+    "rank_sequence",
+    "max_rank",
+    "advancement_policy",
+    "advancement_gates",
+    "kitty_penalty",
+    "throw_penalty",
+    "kitty_bid_policy",
+    "kitty_theft_policy",
+    "kitty_bury_policy",
+    "max_kitty_points",
+    "trick_history_length",
+    "trick_draw_policy",
+    "throw_evaluation_policy",
+    "throw_tie_policy",
+    "trick_play_policy",
+    "first_landlord_selection_policy",
+    "first_deal_landlord_policy",
+    "landlord_rotation_policy",
+    "bid_policy",
+    "bid_reinforcement_policy",
+    "joker_bid_policy",
+    "no_trump_only_from",
+    "jokers_only_trump_policy",
+    "should_reveal_kitty_at_end_of_game",
+    "reveal_kitty_card_by_card",
+    "play_takeback_policy",
+    "bid_takeback_policy",
+    "game_shadowing_policy",
+    "game_start_policy",
+    "game_scoring_parameters",
+    "hide_throw_halting_player",
+    "face_down_discards",
+    "tractor_requirements",
+    "suit_order",
+    "game_visibility",
+    "attacking_points_visibility",
+    "match_target_rank",
+    "rematch_policy",
+    "turn_timers",
+    "spectator_delay_secs",
+    "ranked",
+];
+
+/// The settings which can be changed once cards have been dealt, with the
+/// agreement of every player. The rest would change how the cards already
+/// in everyone's hands are dealt, ranked or scored.
+pub const MID_GAME_SETTINGS: &[&str] = &[
+    "hide_landlord_points",
+    "throw_penalty",
+    "attacking_points_visibility",
+    "turn_timers",
+];
+
+/// Checks that everything in `patch` can be changed in the middle of a game.
+pub fn check_mid_game_settings(patch: &SettingsPatch) -> Result<(), Error> {
+    if patch.is_empty() {
+        bail!("No settings were proposed");
+    }
+    if let Some(setting) = patch
+        .keys()
+        .find(|k| !MID_GAME_SETTINGS.contains(&k.as_str()))
+    {
+        bail!(
+            "The {} can't be changed once cards have been dealt",
+            setting.replace('_', " ")
+        );
+    }
+    Ok(())
+}
+
+/// A setting which differs between two sets of settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SettingChange {
+    pub setting: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl SettingChange {
+    /// E.g. "throw penalty from TenPointsPerAttempt to None".
+    pub fn description(&self) -> String {
+        format!(
+            "{} from {} to {}",
+            self.setting.replace('_', " "),
+            describe_value(&self.before),
+            describe_value(&self.after)
+        )
+    }
+}
+
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::Null => "unset".to_string(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+impl PropagatedState {
+    /// The settings which are different in `other`.
+    pub fn diff_settings(&self, other: &PropagatedState) -> Result<Vec<SettingChange>, Error> {
+        let before = serde_json::to_value(self)?;
+        let after = serde_json::to_value(other)?;
+        Ok(SETTINGS
+            .iter()
+            .filter(|setting| before[**setting] != after[**setting])
+            .map(|setting| SettingChange {
+                setting: setting.to_string(),
+                before: before[*setting].clone(),
+                after: after[*setting].clone(),
+            })
+            .collect())
+    }
+
+    /// Applies each of the settings in `patch`, with the same validation as
+    /// setting them one at a time. If any of them can't be applied, none of
+    /// them are. Returns the settings which changed.
+    pub fn merge_settings(&mut self, patch: &SettingsPatch) -> Result<Vec<SettingChange>, Error> {
+        if let Some(unknown) = patch.keys().find(|k| !SETTINGS.contains(&k.as_str())) {
+            bail!("{} isn't a setting which can be changed", unknown);
+        }
+        let mut merged = self.clone();
+        for setting in SETTINGS {
+            if let Some(value) = patch.get(*setting) {
+                merged
+                    .merge_setting(setting, value.clone())
+                    .map_err(|e| anyhow!("Couldn't set {}: {}", setting.replace('_', " "), e))?;
+            }
+        }
+        let changes = self.diff_settings(&merged)?;
+        *self = merged;
+        Ok(changes)
+    }
+
+    fn merge_setting(&mut self, setting: &str, value: Value) -> Result<(), Error> {
+        macro_rules! set {
+            ($setter:ident) => {{
+                self.$setter(serde_json::from_value(value)?)?;
+            }};
+        }
+        match setting {
+            "game_mode" => set!(set_game_mode),
+            "fixed_partnerships" => set!(set_fixed_partnerships),
+            "num_decks" => set!(set_num_decks),
+            "special_decks" => set!(set_special_decks),
+            "kitty_size" => set!(set_kitty_size),
+            "friend_selection_policy" => set!(set_friend_selection_policy),
+            "friend_selection_restrictions" => set!(set_friend_selection_restrictions),
+            "multiple_join_policy" => set!(set_multiple_join_policy),
+            "hide_landlord_points" => set!(hide_landlord_points),
+            "hide_played_cards" => set!(hide_played_cards),
+            "landlord_emoji" => set!(set_landlord_emoji),
+            "chat_link" => set!(set_chat_link),
+            "rank_sequence" => set!(set_rank_sequence),
+            "max_rank" => set!(set_max_rank),
+            "advancement_policy" => set!(set_advancement_policy),
+            "advancement_gates" => set!(set_advancement_gates),
+            "kitty_penalty" => set!(set_kitty_penalty),
+            "throw_penalty" => set!(set_throw_penalty),
+            "kitty_bid_policy" => set!(set_kitty_bid_policy),
+            "kitty_theft_policy" => set!(set_kitty_theft_policy),
+            "kitty_bury_policy" => set!(set_kitty_bury_policy),
+            "max_kitty_points" => set!(set_max_kitty_points),
+            "trick_history_length" => set!(set_trick_history_length),
+            "trick_draw_policy" => set!(set_trick_draw_policy),
+            "throw_evaluation_policy" => set!(set_throw_evaluation_policy),
+            "throw_tie_policy" => set!(set_throw_tie_policy),
+            "trick_play_policy" => set!(set_trick_play_policy),
+            "first_landlord_selection_policy" => set!(set_first_landlord_selection_policy),
+            "first_deal_landlord_policy" => set!(set_first_deal_landlord_policy),
+            "landlord_rotation_policy" => set!(set_landlord_rotation_policy),
+            "bid_policy" => set!(set_bid_policy),
+            "bid_reinforcement_policy" => set!(set_bid_reinforcement_policy),
+            "joker_bid_policy" => set!(set_joker_bid_policy),
+            "no_trump_only_from" => set!(set_no_trump_only_from),
+            "jokers_only_trump_policy" => set!(set_jokers_only_trump_policy),
+            "should_reveal_kitty_at_end_of_game" => set!(set_should_reveal_kitty_at_end_of_game),
+            "reveal_kitty_card_by_card" => set!(set_reveal_kitty_card_by_card),
+            "play_takeback_policy" => set!(set_play_takeback_policy),
+            "bid_takeback_policy" => set!(set_bid_takeback_policy),
+            "game_shadowing_policy" => set!(set_user_multiple_game_session_policy),
+            "game_start_policy" => set!(set_game_start_policy),
+            "game_scoring_parameters" => set!(set_game_scoring_parameters),
+            "hide_throw_halting_player" => set!(set_hide_throw_halting_player),
+            "face_down_discards" => set!(set_face_down_discards),
+            "tractor_requirements" => set!(set_tractor_requirements),
+            "suit_order" => set!(set_suit_order),
+            "game_visibility" => set!(set_game_visibility),
+            "attacking_points_visibility" => set!(set_attacking_points_visibility),
+            "match_target_rank" => set!(set_match_target_rank),
+            "rematch_policy" => set!(set_rematch_policy),
+            "turn_timers" => set!(set_turn_timers),
+            "spectator_delay_secs" => set!(set_spectator_delay_secs),
+            "ranked" => set!(set_ranked),
+            _ => bail!("{} isn't a setting which can be changed", setting),
+        }
+        Ok(())
+    }
+}
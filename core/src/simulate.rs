@@ -0,0 +1,154 @@
+//! Plays complete games without a server, with every seat controlled by a
+//! `Bot`, so that scoring policies can be balance-tested and strategies
+//! compared against each other.
+
+use anyhow::{bail, Error};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use shengji_mechanics::player::Player;
+use shengji_mechanics::types::PlayerID;
+use slog::Logger;
+
+use crate::bot::Bot;
+use crate::game_state::{initialize_phase::InitializePhase, GameState};
+use crate::interactive::{Action, GameEvent, InteractiveGame};
+use crate::settings::PropagatedState;
+
+/// How many actions a simulated game may take before it's assumed that the
+/// strategies are stuck.
+const MAX_ACTIONS: usize = 100_000;
+
+/// The outcome of a simulated game.
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    /// The state the game started from. Applying `events` to it with
+    /// `InteractiveGame::new_from_events` reproduces `final_state`.
+    pub initial_state: GameState,
+    pub events: Vec<GameEvent>,
+    /// The player in each seat, in the same order as the strategies.
+    pub seats: Vec<PlayerID>,
+    pub landlord: PlayerID,
+    pub landlords_team: Vec<PlayerID>,
+    pub landlord_won: bool,
+    pub non_landlords_points: isize,
+    /// The players and their ranks once the game has been scored.
+    pub players: Vec<Player>,
+    pub final_state: GameState,
+}
+
+/// Plays a single game with `settings`, where the player in each seat takes
+/// the actions proposed by the corresponding strategy. The same settings,
+/// strategies and seed always play out the same way.
+pub fn simulate(
+    settings: PropagatedState,
+    strategies: &[&dyn Bot],
+    seed: u64,
+    logger: &Logger,
+) -> Result<SimulationResult, Error> {
+    if strategies.is_empty() {
+        bail!("Need a strategy for at least one seat");
+    }
+    let mut init = InitializePhase::from_settings(settings)?;
+    let seats = strategies
+        .iter()
+        .map(|_| Ok(init.add_bot()?.0))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let initial_state = GameState::Initialize(init);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = InteractiveGame::new_from_state(initial_state.clone());
+    game.interact_with_seed(Action::StartGame, seats[0], rng.next_u64(), logger)?;
+
+    for _ in 0..MAX_ACTIONS {
+        if let GameState::Play(p) = game.state() {
+            if p.game_finished() {
+                let landlord = p.landlord();
+                let landlords_team = p.landlords_team().to_vec();
+                let (non_landlords_points, _) = p.calculate_points();
+                let (_, landlord_won, _) = p.finish_game()?;
+                game.interact_with_seed(Action::StartNewGame, landlord, rng.next_u64(), logger)?;
+                let players = game.state().propagated().players().to_vec();
+                return Ok(SimulationResult {
+                    initial_state,
+                    events: game.events().to_vec(),
+                    seats,
+                    landlord,
+                    landlords_team,
+                    landlord_won,
+                    non_landlords_points,
+                    players,
+                    final_state: game.into_state(),
+                });
+            }
+        }
+        if !act_for_any_seat(&mut game, &seats, strategies, &mut rng, logger) {
+            bail!("None of the strategies have anything to do");
+        }
+    }
+    bail!("The game didn't finish within {} actions", MAX_ACTIONS)
+}
+
+/// Has the first seat with something to do take its most preferred legal
+/// action. Returns whether anyone acted.
+fn act_for_any_seat(
+    game: &mut InteractiveGame,
+    seats: &[PlayerID],
+    strategies: &[&dyn Bot],
+    rng: &mut StdRng,
+    logger: &Logger,
+) -> bool {
+    for (id, strategy) in seats.iter().zip(strategies) {
+        for action in strategy.actions(game.state(), *id) {
+            if game
+                .interact_with_seed(action, *id, rng.next_u64(), logger)
+                .is_ok()
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use crate::bot::{Bot, SimpleBot};
+    use crate::game_state::GameState;
+    use crate::interactive::InteractiveGame;
+    use crate::settings::{GameModeSettings, PropagatedState};
+
+    use super::simulate;
+
+    #[test]
+    fn test_simulate_is_reproducible() {
+        let logger = Logger::root(Discard, o!());
+        let mut settings = PropagatedState::default();
+        settings
+            .set_game_mode(GameModeSettings::FindingFriends { num_friends: None })
+            .unwrap();
+        let strategies: [&dyn Bot; 5] = [&SimpleBot; 5];
+
+        let result = simulate(settings.clone(), &strategies, 7, &logger).unwrap();
+        assert_eq!(result.seats.len(), 5);
+        assert!(result.landlords_team.contains(&result.landlord));
+        match &result.final_state {
+            GameState::Initialize(s) => assert_eq!(s.propagated().num_games_finished, 1),
+            _ => panic!("Expected the game to be scored"),
+        }
+
+        let again = simulate(settings, &strategies, 7, &logger).unwrap();
+        assert_eq!(
+            serde_json::to_value(&again.final_state).unwrap(),
+            serde_json::to_value(&result.final_state).unwrap()
+        );
+        assert_eq!(again.non_landlords_points, result.non_landlords_points);
+
+        let replayed =
+            InteractiveGame::new_from_events(result.initial_state, result.events, &logger).unwrap();
+        assert_eq!(
+            serde_json::to_value(replayed.into_state()).unwrap(),
+            serde_json::to_value(&result.final_state).unwrap()
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::game_state::GameState;
+use crate::replay::Replay;
+
+/// The version of the snapshot format. Snapshots from other versions can't
+/// be restored.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A complete copy of a room's game, which can be saved to a file and later
+/// restored into a new room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub version: u32,
+    /// Milliseconds since the Unix epoch.
+    pub saved_at_ms: u64,
+    pub state: GameState,
+    /// The replay of the game in progress, if there is one, so that it can
+    /// still be reviewed once the restored game is over.
+    pub replay: Option<Replay>,
+}
+
+impl GameSnapshot {
+    pub fn new(state: GameState, replay: Option<Replay>, saved_at_ms: u64) -> Self {
+        GameSnapshot {
+            version: SNAPSHOT_VERSION,
+            saved_at_ms,
+            state,
+            replay,
+        }
+    }
+
+    /// Serializes the snapshot with the keys of every object sorted, so that
+    /// the same game is always saved as the same bytes.
+    pub fn to_canonical_json(&self) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(&canonicalize(serde_json::to_value(
+            self,
+        )?))?)
+    }
+
+    /// Reads a snapshot back, checking that it's from this version of the
+    /// format and that the game in it is consistent.
+    pub fn from_json(data: &[u8]) -> Result<Self, Error> {
+        let snapshot: GameSnapshot = serde_json::from_slice(data)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "snapshot is from version {} of the format, but only version {} can be restored",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+        for p in snapshot
+            .state
+            .players()
+            .iter()
+            .chain(snapshot.state.observers())
+            .chain(snapshot.state.spectators())
+        {
+            if !ids.insert(p.id) || !names.insert(&p.name) {
+                bail!("snapshot has more than one player named {}", p.name);
+            }
+        }
+        snapshot.state.check_cards_accounted_for()?;
+        Ok(snapshot)
+    }
+}
+
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, canonicalize(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        v => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Logger};
+
+    use crate::game_state::GameState;
+    use crate::interactive::{Action, InteractiveGame};
+
+    use super::GameSnapshot;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let logger = Logger::root(Discard, o!());
+        let mut game = InteractiveGame::new();
+        let players = (1..=4)
+            .map(|i| game.register(format!("p{i}")).unwrap().0)
+            .collect::<Vec<_>>();
+        let mut replay = None;
+        game.interact_recorded(Action::StartGame, players[0], &mut replay, 1, &logger)
+            .unwrap();
+        for t in 0..8 {
+            let next = game.next_player().unwrap();
+            game.interact_recorded(Action::DrawCard, next, &mut replay, t + 2, &logger)
+                .unwrap();
+        }
+
+        let snapshot = GameSnapshot::new(game.dump_state().unwrap(), replay, 100);
+        let data = snapshot.to_canonical_json().unwrap();
+        let restored = GameSnapshot::from_json(&data).unwrap();
+        assert_eq!(restored.to_canonical_json().unwrap(), data);
+        assert!(matches!(restored.state, GameState::Draw(_)));
+        assert_eq!(restored.replay.unwrap().len(), 8);
+
+        // A card which appears from nowhere is caught.
+        let mut value = serde_json::from_slice::<serde_json::Value>(&data).unwrap();
+        let kitty = value["state"]["Draw"]["kitty"].as_array_mut().unwrap();
+        kitty.push(kitty[0].clone());
+        let tampered = serde_json::to_vec(&value).unwrap();
+        assert!(GameSnapshot::from_json(&tampered).is_err());
+
+        value["version"] = serde_json::json!(0);
+        assert!(GameSnapshot::from_json(&serde_json::to_vec(&value).unwrap()).is_err());
+    }
+}
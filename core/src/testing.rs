@@ -0,0 +1,122 @@
+//! Proptest strategies for the settings in this crate, on top of the ones for
+//! the game mechanics in `shengji_mechanics::testing`. Enabled by the
+//! `testing` feature.
+
+use shengji_mechanics::impl_arbitrary_enum;
+use shengji_mechanics::testing::proptest;
+use shengji_mechanics::testing::proptest::prelude::*;
+
+use crate::settings::{
+    AdvancementPolicy, AttackingPointsVisibility, FriendSelectionPolicy,
+    FriendSelectionRestrictions, GameModeSettings, GameShadowingPolicy, GameStartPolicy,
+    GameVisibility, KittyBidPolicy, KittyBuryPolicy, KittyTheftPolicy, LandlordRotationPolicy,
+    MultipleJoinPolicy, PlayTakebackPolicy, RematchPolicy, ThrowPenalty,
+};
+
+impl Arbitrary for GameModeSettings {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<GameModeSettings>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(GameModeSettings::Tractor),
+            proptest::option::of(1usize..=3)
+                .prop_map(|num_friends| GameModeSettings::FindingFriends { num_friends }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for ThrowPenalty {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ThrowPenalty>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(ThrowPenalty::None),
+            Just(ThrowPenalty::TenPointsPerAttempt),
+            (5usize..=20).prop_map(|points| ThrowPenalty::PointsPerCard { points }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for FriendSelectionRestrictions {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<FriendSelectionRestrictions>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<bool>(),
+            any::<bool>(),
+            proptest::option::of(1usize..=3),
+        )
+            .prop_map(
+                |(no_point_cards, no_trumps, max_occurrence)| FriendSelectionRestrictions {
+                    no_point_cards,
+                    no_trumps,
+                    max_occurrence,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl_arbitrary_enum!(AdvancementPolicy {
+    Unrestricted,
+    FullyUnrestricted,
+    DefendPoints,
+});
+impl_arbitrary_enum!(FriendSelectionPolicy {
+    Unrestricted,
+    TrumpsIncluded,
+    HighestCardNotAllowed,
+    PointCardNotAllowed,
+});
+impl_arbitrary_enum!(MultipleJoinPolicy {
+    Unrestricted,
+    NoDoubleJoin,
+});
+impl_arbitrary_enum!(LandlordRotationPolicy {
+    NextOnTeam,
+    Clockwise,
+    WinnerOfLastTrick,
+    HighestBidder,
+});
+impl_arbitrary_enum!(KittyBidPolicy {
+    FirstCard,
+    FirstCardOfLevelOrHighest,
+    FirstSuitedCard,
+});
+impl_arbitrary_enum!(PlayTakebackPolicy {
+    AllowPlayTakeback,
+    NoPlayTakeback,
+});
+impl_arbitrary_enum!(KittyTheftPolicy {
+    AllowKittyTheft,
+    NoKittyTheft,
+});
+impl_arbitrary_enum!(KittyBuryPolicy {
+    Unrestricted,
+    NoPointCards,
+    NoTrumpCards,
+    NoPointOrTrumpCards,
+});
+impl_arbitrary_enum!(GameShadowingPolicy {
+    AllowMultipleSessions,
+    SingleSessionOnly,
+});
+impl_arbitrary_enum!(GameStartPolicy {
+    AllowAnyPlayer,
+    AllowLandlordOnly,
+});
+impl_arbitrary_enum!(GameVisibility { Public, Unlisted });
+impl_arbitrary_enum!(AttackingPointsVisibility {
+    Visible,
+    HiddenFromLandlordTeam,
+    HiddenFromAll,
+});
+impl_arbitrary_enum!(RematchPolicy {
+    KeepRanks,
+    ResetRanks,
+});
@@ -0,0 +1,263 @@
+//! Scripted games for teaching people how to play. A scenario fixes some or
+//! all of the deal, makes the other players' moves for them, and stops at
+//! checkpoints where the learner has to make the move it's teaching, e.g.
+//! "now follow suit with your lowest pair".
+//!
+//! Scenarios are written as JSON, so that new ones can be added without
+//! touching the game itself.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use shengji_mechanics::types::{Card, PlayerID};
+
+use crate::game_state::initialize_phase::InitializePhase;
+use crate::game_state::GameState;
+use crate::interactive::{Action, InteractiveGame};
+use crate::replay::quiet;
+use crate::settings_diff::SettingsPatch;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Scenario {
+    pub title: String,
+    /// The names of the players, in seating order. The first one is the
+    /// learner; the rest only make the moves they're given.
+    pub players: Vec<String>,
+    #[serde(default)]
+    pub settings: SettingsPatch,
+    /// Cards which each player has to be dealt, by name. Everything else is
+    /// dealt at random, shuffled with `seed`.
+    #[serde(default)]
+    pub hands: HashMap<String, Vec<Card>>,
+    /// Cards which have to end up in the kitty.
+    #[serde(default)]
+    pub kitty: Vec<Card>,
+    #[serde(default)]
+    pub seed: u64,
+    /// What happens once all of the cards have been drawn.
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ScenarioStep {
+    /// One of the other players makes a move.
+    Forced { player: String, action: Action },
+    /// The learner has to make one of the accepted moves to carry on.
+    Checkpoint {
+        instructions: String,
+        accept: Vec<Action>,
+        /// Why any other move is wrong.
+        explanation: String,
+    },
+}
+
+/// How the learner did at a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Feedback {
+    Correct,
+    Mistake { explanation: String },
+}
+
+/// A scenario in progress.
+pub struct Tutorial {
+    scenario: Scenario,
+    game: InteractiveGame,
+    players: Vec<PlayerID>,
+    step: usize,
+}
+
+impl Tutorial {
+    pub fn from_json(data: &str) -> Result<Self, Error> {
+        Self::new(serde_json::from_str(data)?)
+    }
+
+    /// Deals the scenario's cards, draws all of them, and plays through to
+    /// the first checkpoint.
+    pub fn new(scenario: Scenario) -> Result<Self, Error> {
+        let mut init = InitializePhase::new();
+        let players = scenario
+            .players
+            .iter()
+            .map(|name| Ok(init.add_player(name.clone())?.0))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let learner = match players.first() {
+            Some(learner) => *learner,
+            None => bail!("The scenario has no players"),
+        };
+        init.merge_settings(&scenario.settings)?;
+        let mut draw = init.start_with_seed(learner, scenario.seed)?;
+
+        let mut hands = HashMap::new();
+        for (name, cards) in &scenario.hands {
+            match scenario.players.iter().position(|p| p == name) {
+                Some(idx) => hands.insert(players[idx], cards.clone()),
+                None => bail!("{} isn't one of the players", name),
+            };
+        }
+        draw.arrange_deal(&hands, &scenario.kitty)?;
+        while !draw.deck().is_empty() {
+            let next = draw.next_player()?;
+            draw.draw_card(next)?;
+        }
+
+        let mut tutorial = Tutorial {
+            scenario,
+            game: InteractiveGame::new_from_state(GameState::Draw(draw)),
+            players,
+            step: 0,
+        };
+        tutorial.play_forced_steps()?;
+        Ok(tutorial)
+    }
+
+    pub fn title(&self) -> &str {
+        &self.scenario.title
+    }
+
+    pub fn learner(&self) -> PlayerID {
+        self.players[0]
+    }
+
+    /// The game as the learner sees it.
+    pub fn state(&self) -> Result<GameState, Error> {
+        self.game.dump_state_for_player(self.learner())
+    }
+
+    /// What the learner is asked to do next, or `None` once the scenario is
+    /// over.
+    pub fn instructions(&self) -> Option<&str> {
+        match self.scenario.steps.get(self.step) {
+            Some(ScenarioStep::Checkpoint { instructions, .. }) => Some(instructions),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.scenario.steps.len()
+    }
+
+    /// Makes the learner's move if it's one the checkpoint accepts, and then
+    /// plays through to the next checkpoint. Moves which aren't accepted
+    /// leave the game as it was.
+    pub fn act(&mut self, action: Action) -> Result<Feedback, Error> {
+        let (accept, explanation) = match self.scenario.steps.get(self.step) {
+            Some(ScenarioStep::Checkpoint {
+                accept,
+                explanation,
+                ..
+            }) => (accept, explanation),
+            _ => bail!("The tutorial is over"),
+        };
+        if !accept.iter().any(|a| same_action(a, &action)) {
+            return Ok(Feedback::Mistake {
+                explanation: explanation.clone(),
+            });
+        }
+        self.game.interact(action, self.learner(), &quiet())?;
+        self.step += 1;
+        self.play_forced_steps()?;
+        Ok(Feedback::Correct)
+    }
+
+    fn play_forced_steps(&mut self) -> Result<(), Error> {
+        while let Some(ScenarioStep::Forced { player, action }) = self.scenario.steps.get(self.step)
+        {
+            let id = match self.scenario.players.iter().position(|p| p == player) {
+                Some(idx) => self.players[idx],
+                None => bail!("{} isn't one of the players", player),
+            };
+            self.game
+                .interact(action.clone(), id, &quiet())
+                .map_err(|e| anyhow!("Step {} of the scenario failed: {}", self.step + 1, e))?;
+            self.step += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Whether two moves are the same, ignoring the order cards are played in.
+fn same_action(a: &Action, b: &Action) -> bool {
+    let cards = |action: &Action| match action {
+        Action::PlayCards(cards) | Action::PlayCardsWithHint(cards, _) => {
+            let mut counts = HashMap::<Card, usize>::new();
+            for card in cards {
+                *counts.entry(*card).or_default() += 1;
+            }
+            Some(counts)
+        }
+        _ => None,
+    };
+    match (cards(a), cards(b)) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => serde_json::to_value(a).ok() == serde_json::to_value(b).ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shengji_mechanics::types::cards::*;
+
+    use crate::game_state::GameState;
+    use crate::interactive::Action;
+
+    use super::{Feedback, Tutorial};
+
+    #[test]
+    fn test_follow_suit_tutorial() {
+        let scenario = serde_json::json!({
+            "title": "Following suit",
+            "players": ["you", "east", "north", "west"],
+            "hands": {
+                "you": [C_6, S_7],
+                "east": [H_2, C_3],
+                "north": [C_4],
+                "west": [C_5],
+            },
+            "steps": [
+                { "type": "Forced", "player": "east", "action": { "Bid": [H_2, 1] } },
+                { "type": "Forced", "player": "east", "action": "PickUpKitty" },
+                { "type": "Forced", "player": "east", "action": "PutDownKitty" },
+                { "type": "Forced", "player": "east", "action": "BeginPlay" },
+                { "type": "Forced", "player": "east", "action": { "PlayCards": [C_3] } },
+                { "type": "Forced", "player": "north", "action": { "PlayCards": [C_4] } },
+                { "type": "Forced", "player": "west", "action": { "PlayCards": [C_5] } },
+                {
+                    "type": "Checkpoint",
+                    "instructions": "Clubs were led, so play a club",
+                    "accept": [{ "PlayCards": [C_6] }],
+                    "explanation": "You have to follow suit while you still have clubs",
+                },
+            ],
+        });
+        let mut tutorial = Tutorial::from_json(&scenario.to_string()).unwrap();
+        assert_eq!(
+            tutorial.instructions(),
+            Some("Clubs were led, so play a club")
+        );
+        match tutorial.state().unwrap() {
+            GameState::Play(p) => {
+                assert!(p.hands().contains(tutorial.learner(), vec![C_6]).is_ok());
+                assert_eq!(p.trick().played_cards().len(), 3);
+            }
+            _ => panic!("Expected to be playing"),
+        }
+
+        assert!(matches!(
+            tutorial.act(Action::PlayCards(vec![S_7])).unwrap(),
+            Feedback::Mistake { .. }
+        ));
+        assert!(!tutorial.is_finished());
+        assert_eq!(
+            tutorial.act(Action::PlayCards(vec![C_6])).unwrap(),
+            Feedback::Correct
+        );
+        assert!(tutorial.is_finished());
+        assert!(tutorial.act(Action::EndTrick).is_err());
+    }
+}
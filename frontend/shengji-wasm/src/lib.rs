@@ -1,4 +1,7 @@
 use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::io::{Cursor, Read};
 
 use gloo_utils::format::JsValueSerdeExt;
@@ -6,37 +9,127 @@ use ruzstd::decoding::dictionary::Dictionary;
 use ruzstd::frame_decoder::FrameDecoder;
 use ruzstd::streaming_decoder::StreamingDecoder;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shengji_core::game_state::play_phase::PlayPhase;
+use shengji_core::hints::{hints, Hint};
+use shengji_core::puzzle::{Puzzle, PuzzleResult};
 use shengji_mechanics::types::Suit;
 use shengji_mechanics::{
-    bidding::{Bid, BidPolicy, BidReinforcementPolicy, JokerBidPolicy},
+    bidding::{Bid, BidPolicy, BidReinforcementPolicy, ExcludedBid, JokerBidPolicy},
     deck::Deck,
     hands::Hands,
+    language::Language,
+    notation::{format_trick_units, parse_trick_units, parse_unit_likes},
     ordered_card::OrderedCard,
     player::Player,
     scoring::{
-        self, compute_level_deltas, explain_level_deltas, GameScoreResult, GameScoringParameters,
+        self, compute_level_deltas, explain_level_deltas, explain_shutout_bonuses, kitty_bonus,
+        scoring_explanations, GameScoreResult, GameScoringParameters, KittyPenalty,
+        LandlordTeamSize, NextThresholdReachability, ScoringExplanation, Shutout,
     },
-    trick::{TractorRequirements, Trick, TrickDrawPolicy, TrickFormat, TrickUnit, UnitLike},
-    types::{Card, EffectiveSuit, PlayerID, Trump},
+    simulation::{simulate_trick, OpponentAssumptions, SimulateTrick, TrickSimulationResult},
+    trick::{
+        FindPlaysSearch, ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, Trick,
+        TrickDrawPolicy, TrickFormat, TrickPlayPolicy, TrickUnit, UnitDescription, UnitLike,
+    },
+    types::{Card, EffectiveSuit, Number, PlayerID, SuitOrder, Trump},
 };
-use shengji_types::ZSTD_ZSTD_DICT;
+use shengji_types::state_diff::{self, StateDiffOp};
+use shengji_types::{WireFormat, ZSTD_DICTS};
 use wasm_bindgen::prelude::*;
 
-thread_local! {
-    static ZSTD_DECODER: RefCell<Option<FrameDecoder>> = {
-        let mut reader = Cursor::new(ZSTD_ZSTD_DICT);
-        let mut decoder =
-            StreamingDecoder::new(&mut reader).map_err(|_| "Failed to construct decoder").unwrap();
-        let mut dict = Vec::new();
-        decoder
-            .read_to_end(&mut dict)
-            .map_err(|e| format!("Failed to decode data {:?}", e)).unwrap();
+pub mod local_game;
+
+/// Searches started with `start_find_viable_plays`, along with the language
+/// to describe their results in.
+type PendingSearches = HashMap<u32, (FindPlaysSearch, Language)>;
 
+thread_local! {
+    static ZSTD_DECODER: RefCell<FrameDecoder> = {
         let mut fd = FrameDecoder::new();
-        fd.add_dict(Dictionary::decode_dict(&dict).unwrap()).unwrap();
-        RefCell::new(Some(fd))
+        for (_, compressed) in ZSTD_DICTS {
+            let mut reader = Cursor::new(compressed);
+            let mut decoder =
+                StreamingDecoder::new(&mut reader).map_err(|_| "Failed to construct decoder").unwrap();
+            let mut dict = Vec::new();
+            decoder
+                .read_to_end(&mut dict)
+                .map_err(|e| format!("Failed to decode data {:?}", e)).unwrap();
+            fd.add_dict(Dictionary::decode_dict(&dict).unwrap()).unwrap();
+        }
+        RefCell::new(fd)
     };
+    static RESULT_CACHE: RefCell<ResultCache> = RefCell::new(ResultCache::default());
+    static PENDING_SEARCHES: RefCell<(u32, PendingSearches)> =
+        RefCell::new((0, HashMap::new()));
+}
+
+const RESULT_CACHE_CAPACITY: usize = 128;
+
+/// Following a big throw while void can have exponentially many legal plays,
+/// so only this many are listed.
+const MAX_LEGAL_PLAYS: usize = 1000;
+
+type ResultCacheKey = (&'static str, u64);
+
+/// A least-recently-used cache of computed responses, keyed by the name of the
+/// endpoint and a hash of the request.
+#[derive(Default)]
+struct ResultCache {
+    entries: HashMap<ResultCacheKey, serde_json::Value>,
+    recency: VecDeque<ResultCacheKey>,
+}
+
+impl ResultCache {
+    fn get(&mut self, key: &ResultCacheKey) -> Option<serde_json::Value> {
+        let value = self.entries.get(key)?.clone();
+        if let Some(idx) = self.recency.iter().position(|k| k == key) {
+            self.recency.remove(idx);
+        }
+        self.recency.push_back(*key);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: ResultCacheKey, value: serde_json::Value) {
+        if self.entries.insert(key, value).is_none() {
+            self.recency.push_back(key);
+        }
+        while self.recency.len() > RESULT_CACHE_CAPACITY {
+            if let Some(evicted) = self.recency.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+}
+
+/// Deserializes the request and runs `compute` on it, unless an identical
+/// request to the same endpoint has been answered recently.
+fn memoized<Req: DeserializeOwned, Resp: Serialize>(
+    endpoint: &'static str,
+    req: JsValue,
+    compute: impl FnOnce(Req) -> Result<Resp, JsValue>,
+) -> Result<JsValue, JsValue> {
+    let req: serde_json::Value = req.into_serde().map_err(|e| e.to_string())?;
+    let mut hasher = DefaultHasher::new();
+    req.to_string().hash(&mut hasher);
+    let key = (endpoint, hasher.finish());
+
+    let cached = RESULT_CACHE.with(|cache| cache.borrow_mut().get(&key));
+    let resp = match cached {
+        Some(resp) => resp,
+        None => {
+            let req = serde_json::from_value(req).map_err(|e| e.to_string())?;
+            let resp = serde_json::to_value(compute(req)?).map_err(|e| e.to_string())?;
+            RESULT_CACHE.with(|cache| cache.borrow_mut().insert(key, resp.clone()));
+            resp
+        }
+    };
+    Ok(JsValue::from_serde(&resp).map_err(|e| e.to_string())?)
+}
+
+#[wasm_bindgen]
+pub fn clear_cache() {
+    RESULT_CACHE.with(|cache| *cache.borrow_mut() = ResultCache::default());
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -44,6 +137,8 @@ pub struct FindViablePlaysRequest {
     trump: Trump,
     tractor_requirements: TractorRequirements,
     cards: Vec<Card>,
+    #[serde(default)]
+    language: Language,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -55,26 +150,103 @@ pub struct FindViablePlaysResult {
 pub struct FoundViablePlay {
     grouping: Vec<TrickUnit>,
     description: String,
+    units: Vec<UnitDescription>,
 }
 
 #[wasm_bindgen]
 pub fn find_viable_plays(req: JsValue) -> Result<JsValue, JsValue> {
-    let FindViablePlaysRequest {
-        trump,
-        cards,
-        tractor_requirements,
-    } = req.into_serde().map_err(|e| e.to_string())?;
-    let results = TrickUnit::find_plays(trump, tractor_requirements, cards)
+    memoized("find_viable_plays", req, |req: FindViablePlaysRequest| {
+        let FindViablePlaysRequest {
+            trump,
+            cards,
+            tractor_requirements,
+            language,
+        } = req;
+        let results = describe_plays(
+            TrickUnit::find_plays(trump, tractor_requirements, cards),
+            language,
+        );
+        Ok(FindViablePlaysResult { results })
+    })
+}
+
+fn describe_plays(
+    plays: impl IntoIterator<Item = Vec<TrickUnit>>,
+    language: Language,
+) -> Vec<FoundViablePlay> {
+    plays
         .into_iter()
         .map(|p| {
-            let description = UnitLike::multi_description(p.iter().map(UnitLike::from));
+            let units = p.iter().map(TrickUnit::describe).collect::<Vec<_>>();
             FoundViablePlay {
+                description: UnitDescription::format_many(units.iter().cloned(), language),
                 grouping: p,
-                description,
+                units,
             }
         })
-        .collect::<Vec<_>>();
-    Ok(JsValue::from_serde(&FindViablePlaysResult { results }).map_err(|e| e.to_string())?)
+        .collect()
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PollFindViablePlaysResponse {
+    done: bool,
+    results: Vec<FoundViablePlay>,
+}
+
+/// Starts a `find_viable_plays` computation which can be advanced
+/// incrementally with `poll_find_viable_plays`. Returns a handle for the
+/// computation.
+#[wasm_bindgen]
+pub fn start_find_viable_plays(req: JsValue) -> Result<u32, JsValue> {
+    let FindViablePlaysRequest {
+        trump,
+        cards,
+        tractor_requirements,
+        language,
+    } = req.into_serde().map_err(|e| e.to_string())?;
+    let search = FindPlaysSearch::new(trump, tractor_requirements, cards);
+
+    Ok(PENDING_SEARCHES.with(|pending| {
+        let (ref mut next_handle, ref mut searches) = *pending.borrow_mut();
+        let handle = *next_handle;
+        *next_handle = next_handle.wrapping_add(1);
+        searches.insert(handle, (search, language));
+        handle
+    }))
+}
+
+/// Performs at most `max_steps` units of work on the computation. Once
+/// `done` is set, the results are returned and the handle is released.
+#[wasm_bindgen]
+pub fn poll_find_viable_plays(handle: u32, max_steps: usize) -> Result<JsValue, JsValue> {
+    let finished = PENDING_SEARCHES.with(|pending| {
+        let (_, ref mut searches) = *pending.borrow_mut();
+        let (search, _) = searches
+            .get_mut(&handle)
+            .ok_or("No pending computation for handle")?;
+        if search.step(max_steps) {
+            Ok::<_, &str>(searches.remove(&handle))
+        } else {
+            Ok(None)
+        }
+    })?;
+
+    let response = match finished {
+        Some((search, language)) => PollFindViablePlaysResponse {
+            done: true,
+            results: describe_plays(search.into_plays(), language),
+        },
+        None => PollFindViablePlaysResponse {
+            done: false,
+            results: vec![],
+        },
+    };
+    Ok(JsValue::from_serde(&response).map_err(|e| e.to_string())?)
+}
+
+#[wasm_bindgen]
+pub fn cancel_find_viable_plays(handle: u32) {
+    PENDING_SEARCHES.with(|pending| pending.borrow_mut().1.remove(&handle));
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -83,6 +255,8 @@ pub struct DecomposeTrickFormatRequest {
     hands: Hands,
     player_id: PlayerID,
     trick_draw_policy: TrickDrawPolicy,
+    #[serde(default)]
+    language: Language,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -94,73 +268,231 @@ pub struct DecomposeTrickFormatResponse {
 pub struct DecomposedTrickFormat {
     format: Vec<UnitLike>,
     description: String,
+    units: Vec<UnitDescription>,
     playable: Vec<Card>,
     more_than_one: bool,
 }
 
 #[wasm_bindgen]
 pub fn decompose_trick_format(req: JsValue) -> Result<JsValue, JsValue> {
-    let DecomposeTrickFormatRequest {
-        trick_format,
-        hands,
-        player_id,
-        trick_draw_policy,
-    } = req.into_serde().map_err(|e| e.to_string())?;
+    memoized(
+        "decompose_trick_format",
+        req,
+        |req: DecomposeTrickFormatRequest| {
+            let DecomposeTrickFormatRequest {
+                trick_format,
+                hands,
+                player_id,
+                trick_draw_policy,
+                language,
+            } = req;
 
-    let hand = hands.get(player_id).map_err(|e| e.to_string())?;
-    let available_cards = Card::cards(
-        hand.iter()
-            .filter(|(c, _)| trick_format.trump().effective_suit(**c) == trick_format.suit()),
-    )
-    .copied()
-    .collect::<Vec<_>>();
-
-    let mut results: Vec<_> = trick_format
-        .decomposition(trick_draw_policy)
-        .map(|format| {
-            let description = UnitLike::multi_description(format.iter().cloned());
-            DecomposedTrickFormat {
-                format,
-                description,
-                playable: vec![],
-                more_than_one: false,
+            let available_cards = Card::cards(
+                hands
+                    .cards_in_effective_suit(player_id, trick_format.suit())
+                    .map_err(|e| e.to_string())?,
+            )
+            .copied()
+            .collect::<Vec<_>>();
+
+            let mut results: Vec<_> = trick_format
+                .decomposition(trick_draw_policy)
+                .map(|format| {
+                    let units = format.iter().map(UnitLike::describe).collect::<Vec<_>>();
+                    DecomposedTrickFormat {
+                        description: UnitDescription::format_many(units.iter().cloned(), language),
+                        format,
+                        units,
+                        playable: vec![],
+                        more_than_one: false,
+                    }
+                })
+                .collect();
+
+            for res in results.iter_mut() {
+                let mut iter = UnitLike::check_play(
+                    OrderedCard::make_map(available_cards.iter().copied(), trick_format.trump()),
+                    res.format.iter().cloned(),
+                    trick_draw_policy,
+                    trick_format.tractor_requirements(),
+                );
+
+                let playable = if let Some(units) = iter.next() {
+                    units
+                        .into_iter()
+                        .flat_map(|u| {
+                            u.into_iter()
+                                .flat_map(|(card, count)| std::iter::repeat(card.card).take(count))
+                                .collect::<Vec<_>>()
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                };
+
+                if !playable.is_empty() {
+                    res.playable = playable;
+                    res.more_than_one = iter.next().is_some();
+                    // Break after the first playable entry to reduce the compute cost of trying to find viable matches.
+                    break;
+                }
             }
-        })
-        .collect();
+            Ok(DecomposeTrickFormatResponse { results })
+        },
+    )
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FindLegalPlaysRequest {
+    trick: Trick,
+    hands: Hands,
+    player_id: PlayerID,
+    trick_draw_policy: TrickDrawPolicy,
+    #[serde(default)]
+    trick_play_policy: TrickPlayPolicy,
+    #[serde(default)]
+    throw_eval_policy: ThrowEvaluationPolicy,
+    #[serde(default)]
+    throw_tie_policy: ThrowTiePolicy,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FindLegalPlaysResponse {
+    plays: Vec<Vec<Card>>,
+}
 
-    for res in results.iter_mut() {
-        let mut iter = UnitLike::check_play(
-            OrderedCard::make_map(available_cards.iter().copied(), trick_format.trump()),
-            res.format.iter().cloned(),
+/// Every set of cards that the player could play to follow the trick, for
+/// when they aren't leading, up to `MAX_LEGAL_PLAYS` of them.
+#[wasm_bindgen]
+pub fn find_legal_plays(req: JsValue) -> Result<JsValue, JsValue> {
+    memoized("find_legal_plays", req, |req: FindLegalPlaysRequest| {
+        let FindLegalPlaysRequest {
+            trick,
+            hands,
+            player_id,
             trick_draw_policy,
-        );
+            trick_play_policy,
+            throw_eval_policy,
+            throw_tie_policy,
+        } = req;
+        let plays = trick
+            .legal_follows(
+                player_id,
+                &hands,
+                trick_draw_policy,
+                trick_play_policy,
+                throw_eval_policy,
+                throw_tie_policy,
+            )
+            .map_err(|e| e.to_string())?
+            .take(MAX_LEGAL_PLAYS)
+            .collect();
+        Ok(FindLegalPlaysResponse { plays })
+    })
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetHintsRequest {
+    /// The play phase, as seen by `player_id`.
+    state: PlayPhase,
+    player_id: PlayerID,
+    selected: Vec<Card>,
+}
 
-        let playable = if let Some(units) = iter.next() {
-            units
+#[derive(Serialize, JsonSchema)]
+pub struct GetHintsResponse {
+    hints: Vec<AnnotatedHint>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AnnotatedHint {
+    hint: Hint,
+    description: String,
+}
+
+/// Hints about the current trick for a teaching overlay, given the cards
+/// that the player has selected.
+#[wasm_bindgen]
+pub fn get_hints(req: JsValue) -> Result<JsValue, JsValue> {
+    memoized("get_hints", req, |req: GetHintsRequest| {
+        let GetHintsRequest {
+            state,
+            player_id,
+            selected,
+        } = req;
+        let hints = hints(&state, player_id, &selected).map_err(|e| e.to_string())?;
+        Ok(GetHintsResponse {
+            hints: hints
                 .into_iter()
-                .flat_map(|u| {
-                    u.into_iter()
-                        .flat_map(|(card, count)| std::iter::repeat(card.card).take(count))
-                        .collect::<Vec<_>>()
+                .map(|hint| AnnotatedHint {
+                    description: hint.description(),
+                    hint,
                 })
-                .collect()
-        } else {
-            vec![]
-        };
-
-        if !playable.is_empty() {
-            res.playable = playable;
-            res.more_than_one = iter.next().is_some();
-            // Break after the first playable entry to reduce the compute cost of trying to find viable matches.
-            break;
-        }
-    }
-    Ok(
-        JsValue::from_serde(&DecomposeTrickFormatResponse { results })
-            .map_err(|e| e.to_string())?,
+                .collect(),
+        })
+    })
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CheckPuzzleAttemptRequest {
+    puzzle: Puzzle,
+    cards: Vec<Card>,
+}
+
+/// Checks an attempt at a puzzle against every way the rest of the trick
+/// could be played, so that puzzles can be solved offline.
+#[wasm_bindgen]
+pub fn check_puzzle_attempt(req: JsValue) -> Result<JsValue, JsValue> {
+    memoized(
+        "check_puzzle_attempt",
+        req,
+        |req: CheckPuzzleAttemptRequest| {
+            Ok(req
+                .puzzle
+                .check_attempt(&req.cards)
+                .map_err(|e| e.to_string())?)
+        },
     )
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct ParsePlayNotationRequest {
+    notation: String,
+    /// If provided, the notation is also parsed into specific cards.
+    trump: Option<Trump>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ParsePlayNotationResponse {
+    format: Vec<UnitLike>,
+    description: String,
+    units: Option<Vec<TrickUnit>>,
+}
+
+#[wasm_bindgen]
+pub fn parse_play_notation(req: JsValue) -> Result<JsValue, JsValue> {
+    let ParsePlayNotationRequest { notation, trump } =
+        req.into_serde().map_err(|e| e.to_string())?;
+    let format = parse_unit_likes(&notation).map_err(|e| e.to_string())?;
+    let units = trump
+        .map(|trump| parse_trick_units(&notation, trump))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    Ok(JsValue::from_serde(&ParsePlayNotationResponse {
+        description: UnitLike::multi_description(format.iter().cloned()),
+        format,
+        units,
+    })
+    .map_err(|e| e.to_string())?)
+}
+
+#[wasm_bindgen]
+pub fn format_play_notation(req: JsValue) -> Result<String, JsValue> {
+    let units: Vec<TrickUnit> = req.into_serde().map_err(|e| e.to_string())?;
+    Ok(format_trick_units(&units))
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct CanPlayCardsRequest {
     trick: Trick,
@@ -168,6 +500,12 @@ pub struct CanPlayCardsRequest {
     hands: Hands,
     cards: Vec<Card>,
     trick_draw_policy: TrickDrawPolicy,
+    #[serde(default)]
+    trick_play_policy: TrickPlayPolicy,
+    #[serde(default)]
+    throw_eval_policy: ThrowEvaluationPolicy,
+    #[serde(default)]
+    throw_tie_policy: ThrowTiePolicy,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -183,15 +521,81 @@ pub fn can_play_cards(req: JsValue) -> Result<JsValue, JsValue> {
         hands,
         cards,
         trick_draw_policy,
+        trick_play_policy,
+        throw_eval_policy,
+        throw_tie_policy,
     } = req.into_serde().map_err(|e| e.to_string())?;
     Ok(JsValue::from_serde(&CanPlayCardsResponse {
         playable: trick
-            .can_play_cards(id, &hands, &cards, trick_draw_policy)
+            .can_play_cards(
+                id,
+                &hands,
+                &cards,
+                trick_draw_policy,
+                trick_play_policy,
+                throw_eval_policy,
+                throw_tie_policy,
+            )
             .is_ok(),
     })
     .map_err(|e| e.to_string())?)
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct SimulateTrickRequest {
+    trick: Trick,
+    id: PlayerID,
+    hands: Hands,
+    cards: Vec<Card>,
+    teammates: Vec<PlayerID>,
+    assumptions: OpponentAssumptions,
+    trick_draw_policy: TrickDrawPolicy,
+    #[serde(default)]
+    trick_play_policy: TrickPlayPolicy,
+    throw_eval_policy: ThrowEvaluationPolicy,
+    #[serde(default)]
+    throw_tie_policy: ThrowTiePolicy,
+    tractor_requirements: TractorRequirements,
+    num_simulations: usize,
+}
+
+#[wasm_bindgen]
+pub fn simulate_trick_completions(req: JsValue) -> Result<JsValue, JsValue> {
+    let SimulateTrickRequest {
+        trick,
+        id,
+        hands,
+        cards,
+        teammates,
+        assumptions,
+        trick_draw_policy,
+        trick_play_policy,
+        throw_eval_policy,
+        throw_tie_policy,
+        tractor_requirements,
+        num_simulations,
+    } = req.into_serde().map_err(|e| e.to_string())?;
+    let result = simulate_trick(
+        SimulateTrick {
+            trick: &trick,
+            id,
+            hands: &hands,
+            cards: &cards,
+            teammates: &teammates,
+            assumptions: &assumptions,
+            trick_draw_policy,
+            trick_play_policy,
+            throw_eval_policy,
+            throw_tie_policy,
+            tractor_requirements,
+            num_simulations,
+        },
+        &mut rand::thread_rng(),
+    )
+    .map_err(|e| format!("Failed to simulate trick: {:?}", e))?;
+    Ok(JsValue::from_serde(&result).map_err(|e| e.to_string())?)
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct FindValidBidsRequest {
     id: PlayerID,
@@ -203,12 +607,15 @@ pub struct FindValidBidsRequest {
     bid_policy: BidPolicy,
     bid_reinforcement_policy: BidReinforcementPolicy,
     joker_bid_policy: JokerBidPolicy,
+    #[serde(default)]
+    no_trump_only_from: Option<Number>,
     num_decks: usize,
 }
 
 #[derive(Serialize, JsonSchema)]
 pub struct FindValidBidsResult {
     results: Vec<Bid>,
+    excluded: Vec<ExcludedBid>,
 }
 
 #[wasm_bindgen]
@@ -227,6 +634,21 @@ pub fn find_valid_bids(req: JsValue) -> Result<JsValue, JsValue> {
             req.bid_policy,
             req.bid_reinforcement_policy,
             req.joker_bid_policy,
+            req.no_trump_only_from,
+            req.num_decks,
+        )
+        .unwrap_or_default(),
+        excluded: Bid::excluded_bids(
+            req.id,
+            &req.bids,
+            &req.hands,
+            &req.players,
+            req.landlord,
+            req.epoch,
+            req.bid_policy,
+            req.bid_reinforcement_policy,
+            req.joker_bid_policy,
+            req.no_trump_only_from,
             req.num_decks,
         )
         .unwrap_or_default(),
@@ -238,6 +660,8 @@ pub fn find_valid_bids(req: JsValue) -> Result<JsValue, JsValue> {
 pub struct SortAndGroupCardsRequest {
     trump: Trump,
     cards: Vec<Card>,
+    #[serde(default)]
+    suit_order: SuitOrder,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -253,10 +677,13 @@ pub struct SuitGroup {
 
 #[wasm_bindgen]
 pub fn sort_and_group_cards(req: JsValue) -> Result<JsValue, JsValue> {
-    let SortAndGroupCardsRequest { trump, mut cards } =
-        req.into_serde().map_err(|e| e.to_string())?;
+    let SortAndGroupCardsRequest {
+        trump,
+        mut cards,
+        suit_order,
+    } = req.into_serde().map_err(|e| e.to_string())?;
 
-    cards.sort_by(|a, b| trump.compare(*a, *b));
+    cards.sort_by(|a, b| suit_order.compare(trump, *a, *b));
 
     let mut results: Vec<SuitGroup> = vec![];
     for card in cards {
@@ -285,31 +712,44 @@ pub struct NextThresholdReachableRequest {
 }
 
 #[wasm_bindgen]
-pub fn next_threshold_reachable(req: JsValue) -> Result<bool, JsValue> {
+pub fn next_threshold_reachable(req: JsValue) -> Result<JsValue, JsValue> {
     let NextThresholdReachableRequest {
         decks,
         params,
         non_landlord_points,
         observed_points,
     } = req.into_serde().map_err(|e| e.to_string())?;
-    Ok(
+    let reachability =
         scoring::next_threshold_reachable(&params, &decks, non_landlord_points, observed_points)
-            .map_err(|_| "Failed to determine if next threshold is reachable")?,
-    )
+            .map_err(|_| "Failed to determine if next threshold is reachable")?;
+    Ok(JsValue::from_serde(&reachability).map_err(|e| e.to_string())?)
 }
 
 #[derive(Deserialize, JsonSchema)]
 pub struct ExplainScoringRequest {
     decks: Vec<Deck>,
     params: GameScoringParameters,
-    smaller_landlord_team_size: bool,
+    landlord_team_size: LandlordTeamSize,
+    #[serde(default)]
+    kitty_penalty: KittyPenalty,
+    #[serde(default)]
+    language: Language,
 }
 
 #[derive(Serialize, JsonSchema)]
 pub struct ExplainScoringResponse {
     results: Vec<ScoreSegment>,
+    shutout_results: Vec<ShutoutSegment>,
+    kitty_multipliers: Vec<KittyMultiplier>,
     total_points: isize,
     step_size: usize,
+    explanations: Vec<AnnotatedScoringExplanation>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AnnotatedScoringExplanation {
+    explanation: ScoringExplanation,
+    description: String,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -318,15 +758,33 @@ pub struct ScoreSegment {
     results: GameScoreResult,
 }
 
+#[derive(Serialize, JsonSchema)]
+pub struct ShutoutSegment {
+    shutout: Shutout,
+    results: GameScoreResult,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct KittyMultiplier {
+    largest_unit_size: usize,
+    multiplier: usize,
+}
+
 #[wasm_bindgen]
 pub fn explain_scoring(req: JsValue) -> Result<JsValue, JsValue> {
     let ExplainScoringRequest {
         decks,
         params,
-        smaller_landlord_team_size,
+        landlord_team_size,
+        kitty_penalty,
+        language,
     } = req.into_serde().map_err(|e| e.to_string())?;
-    let deltas = explain_level_deltas(&params, &decks, smaller_landlord_team_size)
+    let explanations = scoring_explanations(&params, &decks)
+        .map_err(|e| format!("Failed to explain scoring: {:?}", e))?;
+    let deltas = explain_level_deltas(&params, &decks, landlord_team_size)
         .map_err(|e| format!("Failed to explain scores: {:?}", e))?;
+    let shutout_deltas = explain_shutout_bonuses(&params, &decks, landlord_team_size)
+        .map_err(|e| format!("Failed to explain shutout bonuses: {:?}", e))?;
 
     Ok(JsValue::from_serde(&ExplainScoringResponse {
         results: deltas
@@ -336,10 +794,30 @@ pub fn explain_scoring(req: JsValue) -> Result<JsValue, JsValue> {
                 results: res,
             })
             .collect(),
+        shutout_results: shutout_deltas
+            .into_iter()
+            .map(|(shutout, res)| ShutoutSegment {
+                shutout,
+                results: res,
+            })
+            .collect(),
+        kitty_multipliers: (1..=decks.len())
+            .map(|largest_unit_size| KittyMultiplier {
+                largest_unit_size,
+                multiplier: kitty_penalty.multiplier(largest_unit_size),
+            })
+            .collect(),
         step_size: params
             .step_size(&decks)
             .map_err(|e| format!("Failed to compute step size: {:?}", e))?,
         total_points: decks.iter().map(|d| d.points() as isize).sum::<isize>(),
+        explanations: explanations
+            .into_iter()
+            .map(|explanation| AnnotatedScoringExplanation {
+                description: explanation.format(language),
+                explanation,
+            })
+            .collect(),
     })
     .map_err(|e| e.to_string())?)
 }
@@ -355,8 +833,12 @@ pub fn compute_deck_len(req: JsValue) -> Result<usize, JsValue> {
 pub struct ComputeScoreRequest {
     decks: Vec<Deck>,
     params: GameScoringParameters,
-    smaller_landlord_team_size: bool,
+    landlord_team_size: LandlordTeamSize,
     non_landlord_points: isize,
+    #[serde(default)]
+    shutout: Option<Shutout>,
+    #[serde(default)]
+    bid_size: usize,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -370,14 +852,18 @@ pub fn compute_score(req: JsValue) -> Result<JsValue, JsValue> {
     let ComputeScoreRequest {
         decks,
         params,
-        smaller_landlord_team_size,
+        landlord_team_size,
         non_landlord_points,
+        shutout,
+        bid_size,
     } = req.into_serde().map_err(|e| e.to_string())?;
     let score = compute_level_deltas(
         &params,
         &decks,
         non_landlord_points,
-        smaller_landlord_team_size,
+        landlord_team_size,
+        shutout,
+        bid_size,
     )
     .map_err(|_| "Failed to compute score")?;
     let next_threshold = params
@@ -393,6 +879,36 @@ pub fn compute_score(req: JsValue) -> Result<JsValue, JsValue> {
     .map_err(|e| e.to_string())?)
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct ComputeKittyBonusRequest {
+    kitty: Vec<Card>,
+    winning_unit: TrickUnit,
+    kitty_penalty: KittyPenalty,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ComputeKittyBonusResponse {
+    kitty_points: usize,
+    multiplier: usize,
+    bonus: usize,
+}
+
+#[wasm_bindgen]
+pub fn compute_kitty_bonus(req: JsValue) -> Result<JsValue, JsValue> {
+    let ComputeKittyBonusRequest {
+        kitty,
+        winning_unit,
+        kitty_penalty,
+    } = req.into_serde().map_err(|e| e.to_string())?;
+
+    Ok(JsValue::from_serde(&ComputeKittyBonusResponse {
+        kitty_points: kitty.iter().flat_map(|c| c.points()).sum(),
+        multiplier: kitty_penalty.multiplier(winning_unit.size()),
+        bonus: kitty_bonus(&kitty, kitty_penalty, winning_unit.size()),
+    })
+    .map_err(|e| e.to_string())?)
+}
+
 #[derive(Serialize, JsonSchema)]
 pub struct CardInfo {
     suit: Option<Suit>,
@@ -429,21 +945,108 @@ pub fn get_card_info(req: JsValue) -> Result<JsValue, JsValue> {
     .map_err(|e| e.to_string())?)
 }
 
+/// Decompresses a message from the server and hands it back as JSON, however
+/// it was encoded on the wire.
 #[wasm_bindgen]
-pub fn zstd_decompress(req: &[u8]) -> Result<String, JsValue> {
+pub fn decode_wire_format(req: &[u8]) -> Result<String, JsValue> {
+    console_error_panic_hook::set_once();
+
+    let data = zstd_decompress_versioned(req)?;
+    if data.first() == Some(&b'{') {
+        return Ok(String::from_utf8(data).map_err(|_| "Failed to parse utf-8")?);
+    }
+    let msg = WireFormat::decode(&data).map_err(|e| format!("Failed to decode message {e:?}"))?;
+    Ok(serde_json::to_string(&msg).map_err(|e| e.to_string())?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApplyStateDiffRequest {
+    state: serde_json::Value,
+    diff: Vec<StateDiffOp>,
+}
+
+/// Applies a `GameMessage::StateDiff` to the last state the client has.
+#[wasm_bindgen]
+pub fn apply_state_diff(req: JsValue) -> Result<JsValue, JsValue> {
+    let ApplyStateDiffRequest { mut state, diff } = req.into_serde().map_err(|e| e.to_string())?;
+    state_diff::apply(&mut state, diff).map_err(|e| format!("Failed to apply diff {:?}", e))?;
+
+    Ok(JsValue::from_serde(&state).map_err(|e| e.to_string())?)
+}
+
+/// The IDs of the zstd dictionaries we can decompress messages with, which
+/// clients send when they join a room.
+#[wasm_bindgen]
+pub fn zstd_dictionary_ids() -> Vec<u32> {
+    ZSTD_DICTS.iter().map(|(id, _)| *id).collect()
+}
+
+/// Decompresses a zstd frame with the dictionary named in its header.
+#[wasm_bindgen]
+pub fn zstd_decompress_versioned(req: &[u8]) -> Result<Vec<u8>, JsValue> {
     console_error_panic_hook::set_once();
 
     let mut reader = Cursor::new(req);
     ZSTD_DECODER.with(|frame_decoder| {
-        let mut decoder =
-            StreamingDecoder::new_with_decoder(&mut reader, frame_decoder.take().unwrap())
-                .map_err(|_| "Failed to construct decoder")?;
+        let mut frame_decoder = frame_decoder.borrow_mut();
+        // Fails if the frame names a dictionary we don't have.
+        let mut decoder = StreamingDecoder::new_with_decoder(&mut reader, &mut *frame_decoder)
+            .map_err(|e| format!("Failed to construct decoder {:?}", e))?;
         let mut v = Vec::new();
         decoder
             .read_to_end(&mut v)
             .map_err(|e| format!("Failed to decode data {:?}", e))?;
-        *(frame_decoder.borrow_mut()) = Some(decoder.inner());
 
-        Ok(String::from_utf8(v).map_err(|_| "Failed to parse utf-8")?)
+        Ok(v)
     })
 }
+
+/// Every request and response type exchanged over the wasm boundary. This is
+/// the single source of truth for the generated TypeScript bindings: any new
+/// endpoint should add its types here.
+#[derive(JsonSchema)]
+pub struct WasmTypes {
+    pub find_viable_plays_request: FindViablePlaysRequest,
+    pub find_viable_plays_response: FindViablePlaysResult,
+    pub found_viable_play: FoundViablePlay,
+    pub poll_find_viable_plays_response: PollFindViablePlaysResponse,
+    pub decompose_trick_format_request: DecomposeTrickFormatRequest,
+    pub decompose_trick_format_response: DecomposeTrickFormatResponse,
+    pub decomposed_trick_format: DecomposedTrickFormat,
+    pub find_legal_plays_request: FindLegalPlaysRequest,
+    pub find_legal_plays_response: FindLegalPlaysResponse,
+    pub get_hints_request: GetHintsRequest,
+    pub get_hints_response: GetHintsResponse,
+    pub annotated_hint: AnnotatedHint,
+    pub check_puzzle_attempt_request: CheckPuzzleAttemptRequest,
+    pub check_puzzle_attempt_response: PuzzleResult,
+    pub parse_play_notation_request: ParsePlayNotationRequest,
+    pub parse_play_notation_response: ParsePlayNotationResponse,
+    pub can_play_cards_request: CanPlayCardsRequest,
+    pub can_play_cards_response: CanPlayCardsResponse,
+    pub simulate_trick_request: SimulateTrickRequest,
+    pub simulate_trick_response: TrickSimulationResult,
+    pub find_valid_bids_request: FindValidBidsRequest,
+    pub find_valid_bids_response: FindValidBidsResult,
+    pub sort_and_group_cards_request: SortAndGroupCardsRequest,
+    pub sort_and_group_cards_response: SortAndGroupCardsResponse,
+    pub suit_group: SuitGroup,
+    pub next_threshold_reachable_request: NextThresholdReachableRequest,
+    pub next_threshold_reachable_response: NextThresholdReachability,
+    pub explain_scoring_request: ExplainScoringRequest,
+    pub explain_scoring_response: ExplainScoringResponse,
+    pub annotated_scoring_explanation: AnnotatedScoringExplanation,
+    pub score_segment: ScoreSegment,
+    pub shutout_segment: ShutoutSegment,
+    pub kitty_multiplier: KittyMultiplier,
+    pub compute_score_request: ComputeScoreRequest,
+    pub compute_score_response: ComputeScoreResponse,
+    pub compute_kitty_bonus_request: ComputeKittyBonusRequest,
+    pub compute_kitty_bonus_response: ComputeKittyBonusResponse,
+    pub card_info_request: CardInfoRequest,
+    pub card_info: CardInfo,
+    pub apply_state_diff_request: ApplyStateDiffRequest,
+    pub create_local_game_request: local_game::CreateLocalGameRequest,
+    pub local_game_action_request: local_game::LocalGameActionRequest,
+    pub local_game_response: local_game::LocalGameResponse,
+}
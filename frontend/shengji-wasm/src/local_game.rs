@@ -0,0 +1,178 @@
+//! Games which are played entirely in the browser, against bots, so that
+//! players can practice without a server. They run the same engine as the
+//! server does, and report back the same messages.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use gloo_utils::format::JsValueSerdeExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use shengji_core::bot::SimpleBot;
+use shengji_core::game_state::{initialize_phase::InitializePhase, GameState};
+use shengji_core::interactive::{Action, BroadcastMessage, InteractiveGame};
+use shengji_core::replay::quiet;
+use shengji_core::settings::PropagatedState;
+use shengji_mechanics::types::PlayerID;
+use shengji_types::GameMessage;
+use wasm_bindgen::prelude::*;
+
+struct LocalGame {
+    game: InteractiveGame,
+    player_id: PlayerID,
+}
+
+thread_local! {
+    static LOCAL_GAMES: RefCell<(u32, HashMap<u32, LocalGame>)> =
+        RefCell::new((0, HashMap::new()));
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CreateLocalGameRequest {
+    name: String,
+    /// How many bots to seat alongside the player.
+    num_bots: usize,
+    /// The settings to start from, e.g. those of a room the player has
+    /// played in. The players in them are ignored.
+    #[serde(default)]
+    settings: Option<PropagatedState>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LocalGameActionRequest {
+    handle: u32,
+    action: Action,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LocalGameResponse {
+    handle: u32,
+    player_id: PlayerID,
+    /// Whether anything happened. Bots which have nothing to do don't act.
+    acted: bool,
+    /// The state of the game, as the player can see it.
+    state: GameState,
+    messages: Vec<GameMessage>,
+}
+
+fn respond(
+    handle: u32,
+    local: &LocalGame,
+    msgs: Option<Vec<(BroadcastMessage, String)>>,
+) -> Result<JsValue, JsValue> {
+    let acted = msgs.is_some();
+    let state = local
+        .game
+        .dump_state_for_player(local.player_id)
+        .map_err(|e| e.to_string())?;
+    let messages = msgs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(data, message)| {
+            let (data, message) = data
+                .redacted_for_player(&state, local.player_id)
+                .unwrap_or((data, message));
+            GameMessage::Broadcast { data, message }
+        })
+        .collect();
+    let response = LocalGameResponse {
+        handle,
+        player_id: local.player_id,
+        acted,
+        state,
+        messages,
+    };
+    Ok(JsValue::from_serde(&response).map_err(|e| e.to_string())?)
+}
+
+fn with_local_game(
+    handle: u32,
+    f: impl FnOnce(&mut LocalGame) -> Result<JsValue, JsValue>,
+) -> Result<JsValue, JsValue> {
+    LOCAL_GAMES.with(|games| {
+        let (_, ref mut games) = *games.borrow_mut();
+        let local = games.get_mut(&handle).ok_or("No local game for handle")?;
+        f(local)
+    })
+}
+
+/// Sets up a game with the player and `num_bots` bots, with the player as the
+/// host. Returns a handle for the game.
+#[wasm_bindgen]
+pub fn create_local_game(req: JsValue) -> Result<JsValue, JsValue> {
+    let CreateLocalGameRequest {
+        name,
+        num_bots,
+        settings,
+    } = req.into_serde().map_err(|e| e.to_string())?;
+    let init = match settings {
+        Some(settings) => InitializePhase::from_settings(settings).map_err(|e| e.to_string())?,
+        None => InitializePhase::new(),
+    };
+    let mut game = InteractiveGame::new_from_state(GameState::Initialize(init));
+    let (player_id, mut msgs) = game.register(name).map_err(|e| e.to_string())?;
+    for _ in 0..num_bots {
+        msgs.extend(
+            game.interact(Action::AddBot, player_id, &quiet())
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    let local = LocalGame { game, player_id };
+
+    let handle = LOCAL_GAMES.with(|games| {
+        let (ref mut next_handle, _) = *games.borrow_mut();
+        let handle = *next_handle;
+        *next_handle = next_handle.wrapping_add(1);
+        handle
+    });
+    let response = respond(handle, &local, Some(msgs))?;
+    LOCAL_GAMES.with(|games| games.borrow_mut().1.insert(handle, local));
+    Ok(response)
+}
+
+/// Takes an action on behalf of the player.
+#[wasm_bindgen]
+pub fn local_game_act(req: JsValue) -> Result<JsValue, JsValue> {
+    let LocalGameActionRequest { handle, action } = req.into_serde().map_err(|e| e.to_string())?;
+    with_local_game(handle, |local| {
+        let msgs = local
+            .game
+            .interact(action, local.player_id, &quiet())
+            .map_err(|e| e.to_string())?;
+        respond(handle, local, Some(msgs))
+    })
+}
+
+/// Has the first bot with something to do take its action. Bots only take
+/// one action at a time, so that the player can watch them play.
+#[wasm_bindgen]
+pub fn local_game_step_bots(handle: u32) -> Result<JsValue, JsValue> {
+    with_local_game(handle, |local| {
+        let msgs = local
+            .game
+            .act_for_bots(&SimpleBot, &mut None, 0, &quiet())
+            .ok();
+        respond(handle, local, msgs)
+    })
+}
+
+/// The current state of the game, as the player can see it.
+#[wasm_bindgen]
+pub fn local_game_state(handle: u32) -> Result<JsValue, JsValue> {
+    with_local_game(handle, |local| respond(handle, local, None))
+}
+
+/// The recent tricks of the game, as the player can see them, like the
+/// server's `GameMessage::TrickHistory`.
+#[wasm_bindgen]
+pub fn local_game_trick_history(handle: u32) -> Result<JsValue, JsValue> {
+    with_local_game(handle, |local| {
+        let tricks = local.game.trick_history_for_player(local.player_id);
+        Ok(JsValue::from_serde(&tricks).map_err(|e| e.to_string())?)
+    })
+}
+
+#[wasm_bindgen]
+pub fn drop_local_game(handle: u32) {
+    LOCAL_GAMES.with(|games| games.borrow_mut().1.remove(&handle));
+}
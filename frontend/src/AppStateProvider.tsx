@@ -1,7 +1,7 @@
 import * as React from "react";
 import gameStatistics, { GameStatistics } from "./state/GameStatistics";
 import settings, { Settings } from "./state/Settings";
-import { GameState } from "./gen-types";
+import { CareerCard, GameState } from "./gen-types";
 import { Message } from "./ChatMessage";
 import { State, combineState, noPersistence } from "./State";
 import {
@@ -9,6 +9,14 @@ import {
   numberLocalStorageState,
 } from "./localStorageState";
 
+export interface TurnTimer {
+  playerId: number;
+  /** As reported by `performance.now()`. */
+  deadline: number;
+  /** How long was left when the game was paused, if it is. */
+  pausedWithMs: number | null;
+}
+
 export interface AppState {
   settings: Settings;
   gameStatistics: GameStatistics;
@@ -21,6 +29,10 @@ export interface AppState {
   errors: string[];
   messages: Message[];
   confetti: string | null;
+  turnTimer: TurnTimer | null;
+  careerStats: { [name: string]: CareerCard };
+  /** Round-trip times in milliseconds, by player ID. */
+  latencies: { [playerId: number]: number };
   changeLogLastViewed: number;
 }
 
@@ -37,6 +49,9 @@ const appState: State<AppState> = combineState({
   errors: noPersistence(() => []),
   messages: noPersistence(() => []),
   confetti: noPersistence(() => null),
+  turnTimer: noPersistence(() => null),
+  careerStats: noPersistence(() => ({})),
+  latencies: noPersistence(() => ({})),
 });
 
 interface Context {
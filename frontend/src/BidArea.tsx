@@ -8,6 +8,7 @@ import {
   BidPolicy,
   BidReinforcementPolicy,
   JokerBidPolicy,
+  SuitOrder,
 } from "./gen-types";
 import { WebsocketContext } from "./WebsocketProvider";
 import LabeledPlay from "./LabeledPlay";
@@ -25,9 +26,13 @@ interface IBidAreaProps {
   prefixButtons?: JSX.Element | JSX.Element[];
   suffixButtons?: JSX.Element | JSX.Element[];
   bidTakeBacksEnabled: boolean;
+  /** Set once it's too late to take back the last bid. */
+  bidTakeBackExpired?: boolean;
   bidPolicy: BidPolicy;
   bidReinforcementPolicy: BidReinforcementPolicy;
   jokerBidPolicy: JokerBidPolicy;
+  noTrumpOnlyFrom?: string | null;
+  suitOrder?: SuitOrder;
   hands: Hands;
   numDecks: number;
 }
@@ -90,6 +95,7 @@ const BidArea = (props: IBidAreaProps): JSX.Element => {
       bid_policy: props.bidPolicy,
       bid_reinforcement_policy: props.bidReinforcementPolicy,
       joker_bid_policy: props.jokerBidPolicy,
+      no_trump_only_from: props.noTrumpOnlyFrom,
       num_decks: props.numDecks,
     });
     const levelId =
@@ -157,6 +163,7 @@ const BidArea = (props: IBidAreaProps): JSX.Element => {
             onClick={takeBackBid}
             disabled={
               props.bids.length === 0 ||
+              props.bidTakeBackExpired === true ||
               props.bids[props.bids.length - 1].id !== playerId ||
               props.bids[props.bids.length - 1].epoch !== props.epoch
             }
@@ -184,7 +191,12 @@ const BidArea = (props: IBidAreaProps): JSX.Element => {
             />
           );
         })}
-        <Cards hands={props.hands} playerId={playerId} trump={trump} />
+        <Cards
+          hands={props.hands}
+          playerId={playerId}
+          trump={trump}
+          suitOrder={props.suitOrder}
+        />
       </div>
     );
   }
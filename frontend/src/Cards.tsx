@@ -1,7 +1,7 @@
 import * as React from "react";
 import classNames from "classnames";
 import Card from "./Card";
-import { Trump, Hands } from "./gen-types";
+import { Trump, Hands, SuitOrder } from "./gen-types";
 import ArrayUtils from "./util/array";
 import WasmContext from "./WasmContext";
 import { SettingsContext } from "./AppStateProvider";
@@ -9,6 +9,7 @@ import { SettingsContext } from "./AppStateProvider";
 interface IProps {
   hands: Hands;
   trump: Trump;
+  suitOrder?: SuitOrder;
   playerId: number;
   selectedCards?: string[];
   onSelect?: (selected: string[]) => void;
@@ -60,6 +61,7 @@ const Cards = (props: IProps): JSX.Element => {
       ? sortAndGroupCards({
           cards: props.selectedCards,
           trump: props.trump,
+          suit_order: props.suitOrder,
         }).map((g) =>
           g.cards.map((c) => ({
             card: c,
@@ -71,6 +73,7 @@ const Cards = (props: IProps): JSX.Element => {
   let unselectedCardGroups = sortAndGroupCards({
     cards: unselected,
     trump: props.trump,
+    suit_order: props.suitOrder,
   }).map((g) =>
     g.cards.map((c) => ({
       card: c,
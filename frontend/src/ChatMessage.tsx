@@ -41,13 +41,51 @@ const renderMessage = (message: Message): JSX.Element => {
           in kitty
         </span>
       );
+    case "KittyCardRevealed":
+      return (
+        <span>
+          <InlineCard card={variant.card} /> in kitty, worth {variant.points}{" "}
+          points (×{variant.multiplier})
+        </span>
+      );
     case "GameScoringParametersChanged":
       return renderScoringMessage(message);
+    case "EndOfGameSummary":
+      return renderSummaryMessage(message);
     default:
       return <span>{message.message}</span>;
   }
 };
 
+const renderSummaryMessage = (message: Message): JSX.Element => {
+  const variant = message.data?.variant;
+  if (variant?.type !== "EndOfGameSummary") {
+    return null;
+  }
+  const pace = variant.pace;
+  const seconds = (ms: number): string => `${(ms / 1000).toFixed(1)}s`;
+  return (
+    <span>
+      {message.message}
+      {pace !== undefined && pace.players.length > 0 ? (
+        <span title="Average time per bid or play">
+          {" "}
+          (
+          {pace.players
+            .map((p) => `${p.name}: ${seconds(p.average_ms)}`)
+            .join(", ")}
+          {pace.slowest_trick !== undefined && pace.slowest_trick !== null
+            ? `; slowest trick was #${
+                pace.slowest_trick.trick
+              } at ${seconds(pace.slowest_trick.ms)}`
+            : ""}
+          )
+        </span>
+      ) : null}
+    </span>
+  );
+};
+
 const renderScoringMessage = (message: Message): JSX.Element => {
   const changes = [];
   const variant = message.data?.variant;
@@ -111,6 +149,14 @@ const renderScoringMessage = (message: Message): JSX.Element => {
         changes.push(
           <span key={changes.length}>small-team bonus enabled</span>
         );
+      } else if (
+        variant.parameters.bonus_level_policy === "ScaleByLandlordTeamSize"
+      ) {
+        changes.push(
+          <span key={changes.length}>
+            small-team levels scaled by team size
+          </span>
+        );
       } else {
         changes.push(
           <span key={changes.length}>small-team bonus disabled</span>
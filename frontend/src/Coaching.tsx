@@ -0,0 +1,129 @@
+import * as React from "react";
+import Cards from "./Cards";
+import { WebsocketContext } from "./WebsocketProvider";
+import { GameState, Hands, Trump } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+const handsAndTrump = (state: GameState): [Hands, Trump] | null => {
+  if ("Draw" in state) {
+    return [state.Draw.hands, { NoTrump: {} }];
+  } else if ("Exchange" in state) {
+    return [state.Exchange.hands, state.Exchange.trump];
+  } else if ("Play" in state) {
+    return [state.Play.hands, state.Play.trump];
+  }
+  return null;
+};
+
+const Coaching = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const propagated = propagatedState(props.state);
+  const coaches = propagated.coaches ?? [];
+  const everyone = [
+    ...propagated.players,
+    ...propagated.observers,
+    ...(propagated.spectators ?? []),
+  ];
+  const nameOf = (id: number): string =>
+    everyone.find((p) => p.id === id)?.name ?? "Someone";
+
+  const spectator = (propagated.spectators ?? []).find(
+    (p) => p.name === props.name
+  );
+  if (spectator !== undefined) {
+    const coaching = coaches.find((c) => c.coach === spectator.id);
+    if (coaching === undefined) {
+      return (
+        <div className="reset-block">
+          <select
+            value=""
+            onChange={(evt) =>
+              send({
+                Action: { RequestToCoach: parseInt(evt.target.value, 10) },
+              })
+            }
+            title="Ask a player to let you see their hand, so you can coach them"
+          >
+            <option value="">Coach a player...</option>
+            {propagated.players.map((p) => (
+              <option key={p.id} value={p.id}>
+                {p.name}
+              </option>
+            ))}
+          </select>
+        </div>
+      );
+    }
+    const studentHand = handsAndTrump(props.state);
+    return (
+      <div className="reset-block">
+        <p>
+          {coaching.approved
+            ? `Coaching ${nameOf(coaching.student)}`
+            : `Waiting for ${nameOf(
+                coaching.student
+              )} to let you see their hand`}
+        </p>
+        {coaching.approved && studentHand !== null ? (
+          <Cards
+            hands={studentHand[0]}
+            trump={studentHand[1]}
+            suitOrder={propagated.suit_order}
+            playerId={coaching.student}
+          />
+        ) : null}
+        <button
+          className="normal"
+          onClick={() => send({ Action: "StopCoaching" })}
+        >
+          {coaching.approved ? "Stop coaching" : "Cancel"}
+        </button>
+      </div>
+    );
+  }
+
+  const me = propagated.players.find((p) => p.name === props.name);
+  if (me === undefined) {
+    return null;
+  }
+  const myCoaches = coaches.filter((c) => c.student === me.id);
+  if (myCoaches.length === 0) {
+    return null;
+  }
+  return (
+    <div className="reset-block">
+      {myCoaches.map((c) => (
+        <p key={c.coach}>
+          {c.approved
+            ? `${nameOf(c.coach)} can see your hand `
+            : `${nameOf(c.coach)} would like to see your hand to coach you `}
+          {c.approved ? null : (
+            <button
+              className="normal"
+              onClick={() =>
+                send({ Action: { RespondToCoach: [c.coach, true] } })
+              }
+            >
+              Allow
+            </button>
+          )}
+          <button
+            className="normal"
+            onClick={() =>
+              send({ Action: { RespondToCoach: [c.coach, false] } })
+            }
+          >
+            {c.approved ? "Stop" : "Don't allow"}
+          </button>
+        </p>
+      ))}
+    </div>
+  );
+};
+
+export default Coaching;
@@ -0,0 +1,52 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+
+interface IProps {
+  dealSeed: number | null | undefined;
+}
+
+const DealSeedSettings = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const [seed, setSeed] = React.useState<string>("");
+
+  const setDealSeed = (seed: number | null): void => {
+    send({ Action: { SetDealSeed: seed } });
+    setSeed("");
+  };
+
+  return (
+    <p>
+      Fixed deal:{" "}
+      {props.dealSeed !== undefined && props.dealSeed !== null ? (
+        <>
+          <code>{props.dealSeed}</code>{" "}
+          <button onClick={() => setDealSeed(null)}>remove</button>
+        </>
+      ) : (
+        <form
+          style={{ display: "inline" }}
+          onSubmit={(evt) => {
+            evt.preventDefault();
+            const parsed = parseInt(seed, 10);
+            if (Number.isSafeInteger(parsed) && parsed >= 0) {
+              setDealSeed(parsed);
+            }
+          }}
+        >
+          <input
+            type="text"
+            inputMode="numeric"
+            style={{ width: "200px" }}
+            value={seed}
+            onChange={(evt) => setSeed(evt.target.value)}
+            placeholder="seed to deal every game with"
+            title="Every game in a room with the same seed is dealt the same way"
+          />
+          <input type="submit" value="set" />
+        </form>
+      )}
+    </p>
+  );
+};
+
+export default DealSeedSettings;
@@ -145,6 +145,8 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
         <Players
           players={this.props.state.propagated.players}
           observers={this.props.state.propagated.observers}
+          spectators={this.props.state.propagated.spectators}
+          idle={this.props.state.propagated.idle_players}
           landlord={landlord}
           next={next}
           name={this.props.name}
@@ -163,6 +165,8 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
             this.props.state.propagated.bid_reinforcement_policy
           }
           jokerBidPolicy={this.props.state.propagated.joker_bid_policy}
+          noTrumpOnlyFrom={this.props.state.propagated.no_trump_only_from}
+          suitOrder={this.props.state.propagated.suit_order}
           numDecks={this.props.state.num_decks}
           header={
             <>
@@ -179,6 +183,18 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
                   have been removed from the deck
                 </p>
               ) : null}
+              {(landlord === null || landlord === undefined) &&
+              this.props.state.dealer !== null &&
+              this.props.state.dealer !== undefined &&
+              this.props.state.dealer_card !== null &&
+              this.props.state.dealer_card !== undefined &&
+              players[this.props.state.dealer] !== undefined ? (
+                <p>
+                  {players[this.props.state.dealer].name} drew{" "}
+                  <InlineCard card={this.props.state.dealer_card} /> and will be
+                  the landlord if nobody bids
+                </p>
+              ) : null}
             </>
           }
           prefixButtons={
@@ -215,6 +231,10 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
                     !(landlord !== null && players[landlord].level === "NT")) ||
                   (landlord !== null && landlord !== playerId) ||
                   (landlord === null &&
+                    this.props.state.autobid !== null &&
+                    this.props.state.autobid.id !== playerId) ||
+                  (landlord === null &&
+                    this.props.state.autobid === null &&
                     ((this.props.state.propagated
                       .first_landlord_selection_policy === "ByWinningBid" &&
                       this.props.state.bids[this.props.state.bids.length - 1]
@@ -230,8 +250,9 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
               <button
                 onClick={this.revealCard}
                 disabled={
-                  landlord === null ||
-                  landlord === undefined ||
+                  ((landlord === null || landlord === undefined) &&
+                    (this.props.state.dealer === null ||
+                      this.props.state.dealer === undefined)) ||
                   this.props.state.deck.length > 0 ||
                   this.props.state.bids.length > 0 ||
                   this.props.state.autobid !== null ||
@@ -252,6 +273,10 @@ class Draw extends React.Component<IDrawProps, IDrawState> {
             this.props.state.propagated.bid_takeback_policy ===
             "AllowBidTakeback"
           }
+          bidTakeBackExpired={
+            this.props.state.last_bid_deck_len !==
+            this.props.state.deck.length
+          }
         />
         <LabeledPlay
           className="kitty"
@@ -144,6 +144,16 @@ class Exchange extends React.Component<IExchangeProps, IExchangeState> {
     const kittyTheftEnabled =
       this.props.state.propagated.kitty_theft_policy === "AllowKittyTheft";
 
+    const buryPolicy = this.props.state.propagated.kitty_bury_policy;
+    const buryRestriction =
+      buryPolicy === "NoPointCards"
+        ? "point cards"
+        : buryPolicy === "NoTrumpCards"
+        ? "trump cards"
+        : buryPolicy === "NoPointOrTrumpCards"
+        ? "point or trump cards"
+        : null;
+
     const nextPlayer =
       kittyTheftEnabled &&
       !this.props.state.finalized &&
@@ -160,11 +170,25 @@ class Exchange extends React.Component<IExchangeProps, IExchangeState> {
             playerId={playerId}
             onCardClick={(c) => this.moveCardToKitty(c)}
             trump={this.props.state.trump}
+            suitOrder={this.props.state.propagated.suit_order}
           />
           <h2>
             Discarded cards {this.props.state.kitty.length} /{" "}
             {this.props.state.kitty_size}
           </h2>
+          {buryRestriction !== null ? (
+            <p>
+              You may not bury {buryRestriction} unless you have no other cards
+              left to bury.
+            </p>
+          ) : null}
+          {this.props.state.propagated.max_kitty_points !== null &&
+          this.props.state.propagated.max_kitty_points !== undefined ? (
+            <p>
+              You may not bury more than{" "}
+              {this.props.state.propagated.max_kitty_points} points.
+            </p>
+          ) : null}
           <div className="kitty">
             {this.props.state.kitty.map((c, idx) => (
               <Card
@@ -224,6 +248,8 @@ class Exchange extends React.Component<IExchangeProps, IExchangeState> {
               this.props.state.propagated.bid_reinforcement_policy
             }
             jokerBidPolicy={this.props.state.propagated.joker_bid_policy}
+            noTrumpOnlyFrom={this.props.state.propagated.no_trump_only_from}
+            suitOrder={this.props.state.propagated.suit_order}
             numDecks={this.props.state.num_decks}
             header={
               <h2>Bids (round {this.props.state.epoch + 1} of bidding)</h2>
@@ -295,6 +321,8 @@ class Exchange extends React.Component<IExchangeProps, IExchangeState> {
         <Players
           players={this.props.state.propagated.players}
           observers={this.props.state.propagated.observers}
+          spectators={this.props.state.propagated.spectators}
+          idle={this.props.state.propagated.idle_players}
           landlord={this.props.state.landlord}
           next={this.props.state.landlord}
           name={this.props.name}
@@ -317,6 +345,7 @@ class Exchange extends React.Component<IExchangeProps, IExchangeState> {
               hands={this.props.state.hands}
               playerId={playerId}
               trump={this.props.state.trump}
+              suitOrder={this.props.state.propagated.suit_order}
             />
             <p>Waiting...</p>
           </>
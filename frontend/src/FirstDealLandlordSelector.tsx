@@ -0,0 +1,55 @@
+import * as React from "react";
+import { FirstDealLandlordPolicy, Player } from "./gen-types";
+import { ICardInfo } from "./types";
+import preloadedCards from "./preloadedCards";
+
+interface IProps {
+  policy: FirstDealLandlordPolicy;
+  onChange: (newPolicy: FirstDealLandlordPolicy) => void;
+  players: Player[];
+}
+const FirstDealLandlordSelector = (props: IProps): JSX.Element => {
+  const handleChange = (e: React.ChangeEvent<HTMLSelectElement>): void => {
+    const [kind, value] = e.target.value.split(":");
+    if (kind === "player") {
+      props.onChange({ AssignedByHost: { player: parseInt(value, 10) } });
+    } else if (kind === "card") {
+      props.onChange({ FirstToDrawCard: { card: value } });
+    } else {
+      props.onChange("Random");
+    }
+  };
+
+  let value = "";
+  if (typeof props.policy !== "string") {
+    if ("AssignedByHost" in props.policy) {
+      value = `player:${props.policy.AssignedByHost.player}`;
+    } else if ("FirstToDrawCard" in props.policy) {
+      value = `card:${props.policy.FirstToDrawCard.card}`;
+    }
+  }
+
+  return (
+    <div className="landlord-picker">
+      <label>
+        First landlord if nobody bids:{" "}
+        <select value={value} onChange={handleChange}>
+          <option value="">a random player, who draws first</option>
+          {props.players.map((player: Player) => (
+            <option value={`player:${player.id}`} key={player.id}>
+              {player.name}, who draws first
+            </option>
+          ))}
+          {preloadedCards.map((c: ICardInfo) => (
+            <option value={`card:${c.value}`} key={c.value}>
+              the first player to draw{" "}
+              {c.number !== null ? `${c.number}${c.typ}` : c.display_value}
+            </option>
+          ))}
+        </select>
+      </label>
+    </div>
+  );
+};
+
+export default FirstDealLandlordSelector;
@@ -4,18 +4,25 @@ import * as ReactModal from "react-modal";
 import { EmojiStyle } from "emoji-picker-react";
 import ReadyCheck from "./ReadyCheck";
 import LandlordSelector from "./LandlordSelector";
+import FirstDealLandlordSelector from "./FirstDealLandlordSelector";
 import NumDecksSelector from "./NumDecksSelector";
 import KittySizeSelector from "./KittySizeSelector";
 import RankSelector from "./RankSelector";
 import Kicker from "./Kicker";
+import WebhookSettings from "./WebhookSettings";
+import DealSeedSettings from "./DealSeedSettings";
+import SettingsPresets from "./SettingsPresets";
 import ArrayUtils from "./util/array";
 import { RandomizePlayersButton } from "./RandomizePlayersButton";
 import {
+  FirstDealLandlordPolicy,
   InitializePhase,
   Player,
   PropagatedState,
   Deck,
+  ThrowPenalty,
   TractorRequirements,
+  TurnTimers,
 } from "./gen-types";
 import { WebsocketContext } from "./WebsocketProvider";
 
@@ -31,12 +38,19 @@ interface IDifficultyProps {
   setMultipleJoinPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setAdvancementPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setMaxRank: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setRankSequence: (v: React.FocusEvent<HTMLInputElement>) => void;
+  setMatchTargetRank: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setRematchPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setHideLandlordsPoints: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setAttackingPointsVisibility: (
+    v: React.ChangeEvent<HTMLSelectElement>
+  ) => void;
   setHidePlayedCards: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setKittyPenalty: (v: React.ChangeEvent<HTMLSelectElement>) => void;
-  setThrowPenalty: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setThrowPenalty: (penalty: ThrowPenalty) => void;
   setPlayTakebackPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setBidTakebackPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setTurnTimers: (timers: TurnTimers) => void;
 }
 
 const contentStyle: React.CSSProperties = {
@@ -49,6 +63,19 @@ const contentStyle: React.CSSProperties = {
 
 const DifficultySettings = (props: IDifficultyProps): JSX.Element => {
   const [modalOpen, setModalOpen] = React.useState<boolean>(false);
+  const timers: TurnTimers = props.state.propagated.turn_timers ?? {};
+  const setTimerSecs =
+    (key: "bid_secs" | "exchange_secs" | "play_secs") =>
+    (evt: React.FocusEvent<HTMLInputElement>): void => {
+      const secs = parseInt(evt.target.value, 10);
+      const value = isNaN(secs) ? null : secs;
+      if (value !== (timers[key] ?? null)) {
+        props.setTurnTimers({ ...timers, [key]: value });
+      }
+    };
+  const rankSequence = (props.state.propagated.rank_sequence ?? []).join(", ");
+  const throwPenalty = (props.state.propagated.throw_penalty ??
+    "None") as ThrowPenalty;
   const s = (
     <>
       <div>
@@ -112,6 +139,100 @@ const DifficultySettings = (props: IDifficultyProps): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Ranks to play, in order:{" "}
+          <input
+            type="text"
+            key={rankSequence}
+            placeholder="All ranks"
+            defaultValue={rankSequence}
+            onBlur={props.setRankSequence}
+          />
+        </label>
+      </div>
+      <div>
+        <label>
+          Match:{" "}
+          <select
+            value={props.state.propagated.match_target_rank ?? ""}
+            onChange={props.setMatchTargetRank}
+          >
+            <option value="">Keep playing indefinitely</option>
+            {(props.state.propagated.rank_sequence ?? ["A", "NT"]).map(
+              (rank) => (
+                <option key={rank} value={rank}>
+                  First team to defend {rank === "NT" ? "no trump" : rank} wins
+                </option>
+              )
+            )}
+          </select>
+        </label>
+      </div>
+      {props.state.propagated.match_target_rank !== undefined &&
+        props.state.propagated.match_target_rank !== null && (
+          <div>
+            <label>
+              After the match:{" "}
+              <select
+                value={props.state.propagated.rematch_policy}
+                onChange={props.setRematchPolicy}
+              >
+                <option value="KeepRanks">Keep everyone&apos;s ranks</option>
+                <option value="ResetRanks">Reset ranks for a rematch</option>
+              </select>
+            </label>
+          </div>
+        )}
+      <div>
+        <label>
+          Turn timers (seconds, blank for none): bidding{" "}
+          <input
+            type="number"
+            min="5"
+            max="600"
+            style={{ width: "4em" }}
+            defaultValue={timers.bid_secs ?? ""}
+            onBlur={setTimerSecs("bid_secs")}
+          />{" "}
+          exchanging{" "}
+          <input
+            type="number"
+            min="5"
+            max="600"
+            style={{ width: "4em" }}
+            defaultValue={timers.exchange_secs ?? ""}
+            onBlur={setTimerSecs("exchange_secs")}
+          />{" "}
+          playing{" "}
+          <input
+            type="number"
+            min="5"
+            max="600"
+            style={{ width: "4em" }}
+            defaultValue={timers.play_secs ?? ""}
+            onBlur={setTimerSecs("play_secs")}
+          />
+        </label>
+      </div>
+      <div>
+        <label>
+          When a turn times out:{" "}
+          <select
+            value={timers.timeout_policy ?? "MarkIdle"}
+            onChange={(evt) =>
+              props.setTurnTimers({
+                ...timers,
+                timeout_policy:
+                  evt.target.value === "AutoPlay" ? "AutoPlay" : "MarkIdle",
+              })
+            }
+          >
+            <option value="MarkIdle">Mark the player as idle</option>
+            <option value="AutoPlay">Play for them</option>
+          </select>
+        </label>
+      </div>
       <div>
         <label>
           Point visibility:{" "}
@@ -126,6 +247,21 @@ const DifficultySettings = (props: IDifficultyProps): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Attacking team&apos;s points until the end of the game:{" "}
+          <select
+            value={props.state.propagated.attacking_points_visibility}
+            onChange={props.setAttackingPointsVisibility}
+          >
+            <option value="Visible">Visible to everyone</option>
+            <option value="HiddenFromLandlordTeam">
+              Hidden from the landlord&apos;s team
+            </option>
+            <option value="HiddenFromAll">Hidden from everyone</option>
+          </select>
+        </label>
+      </div>
       <div>
         <label>
           Played card visibility (in chat):{" "}
@@ -149,6 +285,8 @@ const DifficultySettings = (props: IDifficultyProps): JSX.Element => {
             <option value="Power">
               Two to the power of the size of the last trick
             </option>
+            <option value="Double">Twice the points</option>
+            <option value="NoBonus">No penalty</option>
           </select>
         </label>
       </div>
@@ -156,14 +294,39 @@ const DifficultySettings = (props: IDifficultyProps): JSX.Element => {
         <label>
           Penalty for incorrect throws:{" "}
           <select
-            value={props.state.propagated.throw_penalty}
-            onChange={props.setThrowPenalty}
+            value={
+              typeof throwPenalty === "string" ? throwPenalty : "PointsPerCard"
+            }
+            onChange={(evt) => {
+              evt.preventDefault();
+              props.setThrowPenalty(
+                evt.target.value === "PointsPerCard"
+                  ? { PointsPerCard: { points: 5 } }
+                  : (evt.target.value as ThrowPenalty)
+              );
+            }}
           >
             <option value="None">No penalty</option>
             <option value="TenPointsPerAttempt">
               Ten points per bad throw
             </option>
+            <option value="PointsPerCard">
+              Points per card taken back, to the other team
+            </option>
           </select>
+          {typeof throwPenalty !== "string" && (
+            <input
+              type="number"
+              style={{ width: "3em" }}
+              onChange={(v) =>
+                props.setThrowPenalty({
+                  PointsPerCard: { points: v.target.valueAsNumber },
+                })
+              }
+              value={throwPenalty.PointsPerCard.points}
+              min="1"
+            />
+          )}
         </label>
       </div>
       <div>
@@ -228,7 +391,13 @@ interface IDeckSettings {
 const DeckSettings = (props: IDeckSettings): JSX.Element => {
   const [modalOpen, setModalOpen] = React.useState<boolean>(false);
   const isNotDefault = (d: Deck): boolean =>
-    !(d.min === "2" && !d.exclude_big_joker && !d.exclude_small_joker);
+    !(
+      d.min === "2" &&
+      !d.exclude_big_joker &&
+      !d.exclude_small_joker &&
+      (d.exclude_numbers ?? []).length === 0 &&
+      (d.exclude_suits ?? []).length === 0
+    );
   const onChange = (decks: Deck[]): void => {
     // exclude the decks that are the same as default
     const filtered = decks.filter((d) => isNotDefault(d));
@@ -255,6 +424,13 @@ const DeckSettings = (props: IDeckSettings): JSX.Element => {
     "K",
     "A",
   ];
+  const suits = ["♤", "♡", "♢", "♧"];
+  const toggle = (xs: string[] | undefined, x: string): string[] => {
+    const current = xs ?? [];
+    return current.includes(x)
+      ? current.filter((y) => y !== x)
+      : [...current, x];
+  };
 
   const s = (
     <>
@@ -312,6 +488,42 @@ const DeckSettings = (props: IDeckSettings): JSX.Element => {
                 ))}
               </select>
             </label>
+            <label style={{ display: "block" }}>
+              Remove ranks:{" "}
+              {numbers.map((n) => (
+                <label key={n}>
+                  <input
+                    type="checkbox"
+                    checked={(d.exclude_numbers ?? []).includes(n)}
+                    onChange={() =>
+                      setDeckAtIndex(
+                        { ...d, exclude_numbers: toggle(d.exclude_numbers, n) },
+                        i
+                      )
+                    }
+                  />
+                  {n}{" "}
+                </label>
+              ))}
+            </label>
+            <label style={{ display: "block" }}>
+              Remove suits:{" "}
+              {suits.map((suit) => (
+                <label key={suit}>
+                  <input
+                    type="checkbox"
+                    checked={(d.exclude_suits ?? []).includes(suit)}
+                    onChange={() =>
+                      setDeckAtIndex(
+                        { ...d, exclude_suits: toggle(d.exclude_suits, suit) },
+                        i
+                      )
+                    }
+                  />
+                  {suit}{" "}
+                </label>
+              ))}
+            </label>
           </form>
         </div>
       ))}
@@ -385,6 +597,21 @@ const TractorRequirementsE = (
         max="12"
       />
       <label> tuples long</label>
+      <div>
+        <label>
+          <input
+            type="checkbox"
+            onChange={(v) =>
+              props.onChange({
+                ...props.tractorRequirements,
+                trump_rank_breaks_tractors: !v.target.checked,
+              })
+            }
+            checked={!props.tractorRequirements.trump_rank_breaks_tractors}
+          />{" "}
+          Tractors can skip over the trump rank (e.g. 7-9 when 8 is trump)
+        </label>
+      </div>
     </div>
   );
 };
@@ -417,6 +644,7 @@ const ScoringSettings = (props: IScoringSettings): JSX.Element => {
         >
           <GameScoringSettings
             params={props.state.propagated.game_scoring_parameters}
+            kittyPenalty={props.state.propagated.kitty_penalty}
             decks={props.decks}
           />
         </ReactModal>
@@ -431,16 +659,22 @@ interface IUncommonSettings {
   setBidPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setBidReinforcementPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setJokerBidPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setJokersOnlyTrumpPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setShouldRevealKittyAtEndOfGame: (
     v: React.ChangeEvent<HTMLSelectElement>
   ) => void;
+  setRevealKittyCardByCard: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setFirstLandlordSelectionPolicy: (
     v: React.ChangeEvent<HTMLSelectElement>
   ) => void;
+  setLandlordRotationPolicy: (
+    v: React.ChangeEvent<HTMLSelectElement>
+  ) => void;
   setGameStartPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setGameShadowingPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setKittyBidPolicy: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setHideThrowHaltingPlayer: (v: React.ChangeEvent<HTMLSelectElement>) => void;
+  setFaceDownDiscards: (v: React.ChangeEvent<HTMLSelectElement>) => void;
   setTractorRequirements: (v: TractorRequirements) => void;
 }
 
@@ -496,6 +730,22 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Next landlord when the landlord's team loses:{" "}
+          <select
+            value={props.state.propagated.landlord_rotation_policy}
+            onChange={props.setLandlordRotationPolicy}
+          >
+            <option value="NextOnTeam">Next player on the winning team</option>
+            <option value="Clockwise">Next player clockwise</option>
+            <option value="WinnerOfLastTrick">
+              Winner of the last trick
+            </option>
+            <option value="HighestBidder">Highest bidder</option>
+          </select>
+        </label>
+      </div>
       <div>
         <label>
           Trump policy for cards revealed from the bottom:{" "}
@@ -507,6 +757,9 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
             <option value="FirstCardOfLevelOrHighest">
               First card revealed of the appropriate rank
             </option>
+            <option value="FirstSuitedCard">
+              Suit of the first non-joker card revealed
+            </option>
           </select>
         </label>
       </div>
@@ -527,6 +780,9 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
             <option value="GreaterLength">
               All bids must have more cards than the previous bids
             </option>
+            <option value="FirstBidWins">
+              The first bid wins, with no overbidding
+            </option>
           </select>
         </label>
       </div>
@@ -569,6 +825,26 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Jokers-only trump:{" "}
+          <select
+            value={
+              props.state.propagated.jokers_only_trump_policy ??
+              "NoTrumpRankOnly"
+            }
+            onChange={props.setJokersOnlyTrumpPolicy}
+          >
+            <option value="NoTrumpRankOnly">
+              Only when playing no-trump rank
+            </option>
+            <option value="BigJokerBid">
+              Also when the winning bid is made with big jokers
+            </option>
+            <option value="Always">In every round</option>
+          </select>
+        </label>
+      </div>
       <TractorRequirementsE
         tractorRequirements={props.state.propagated.tractor_requirements}
         numDecks={props.numDecksEffective}
@@ -594,6 +870,24 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Reveal kitty one card at a time:{" "}
+          <select
+            value={
+              props.state.propagated.reveal_kitty_card_by_card === true
+                ? "on"
+                : "off"
+            }
+            onChange={props.setRevealKittyCardByCard}
+          >
+            <option value="off">Reveal the kitty all at once</option>
+            <option value="on">
+              Reveal each card in the kitty in turn, with the points it adds
+            </option>
+          </select>
+        </label>
+      </div>
       <div>
         <label>
           Show player which defeats throw:{" "}
@@ -612,6 +906,22 @@ const UncommonSettings = (props: IUncommonSettings): JSX.Element => {
           </select>
         </label>
       </div>
+      <div>
+        <label>
+          Discards:{" "}
+          <select
+            value={
+              props.state.propagated.face_down_discards === true ? "on" : "off"
+            }
+            onChange={props.setFaceDownDiscards}
+          >
+            <option value="off">Discards are played face-up</option>
+            <option value="on">
+              Discards are played face-down until the trick is won
+            </option>
+          </select>
+        </label>
+      </div>
     </>
   );
   return (
@@ -720,13 +1030,21 @@ const Initialize = (props: IProps): JSX.Element => {
   const setFirstLandlordSelectionPolicy = onSelectString(
     "SetFirstLandlordSelectionPolicy"
   );
+  const setLandlordRotationPolicy = onSelectString(
+    "SetLandlordRotationPolicy"
+  );
   const setBidPolicy = onSelectString("SetBidPolicy");
   const setBidReinforcementPolicy = onSelectString("SetBidReinforcementPolicy");
   const setJokerBidPolicy = onSelectString("SetJokerBidPolicy");
+  const setJokersOnlyTrumpPolicy = onSelectString("SetJokersOnlyTrumpPolicy");
   const setKittyTheftPolicy = onSelectString("SetKittyTheftPolicy");
+  const setKittyBuryPolicy = onSelectString("SetKittyBuryPolicy");
+  const setSuitOrder = onSelectString("SetSuitOrder");
   const setKittyBidPolicy = onSelectString("SetKittyBidPolicy");
   const setTrickDrawPolicy = onSelectString("SetTrickDrawPolicy");
   const setThrowEvaluationPolicy = onSelectString("SetThrowEvaluationPolicy");
+  const setThrowTiePolicy = onSelectString("SetThrowTiePolicy");
+  const setTrickPlayPolicy = onSelectString("SetTrickPlayPolicy");
   const setPlayTakebackPolicy = onSelectString("SetPlayTakebackPolicy");
   const setGameShadowingPolicy = onSelectString("SetGameShadowingPolicy");
   const setGameStartPolicy = onSelectString("SetGameStartPolicy");
@@ -745,6 +1063,16 @@ const Initialize = (props: IProps): JSX.Element => {
       });
     }
   };
+  const setRevealKittyCardByCard = (
+    evt: React.ChangeEvent<HTMLSelectElement>
+  ): void => {
+    evt.preventDefault();
+    send({
+      Action: {
+        SetRevealKittyCardByCard: evt.target.value === "on",
+      },
+    });
+  };
   const setHideThrowHaltingPlayer = (
     evt: React.ChangeEvent<HTMLSelectElement>
   ): void => {
@@ -757,6 +1085,16 @@ const Initialize = (props: IProps): JSX.Element => {
       });
     }
   };
+  const setFaceDownDiscards = (
+    evt: React.ChangeEvent<HTMLSelectElement>
+  ): void => {
+    evt.preventDefault();
+    send({
+      Action: {
+        SetFaceDownDiscards: evt.target.value === "on",
+      },
+    });
+  };
 
   const setKittyPenalty = onSelectStringDefault("SetKittyPenalty", null);
   const setAdvancementPolicy = onSelectStringDefault(
@@ -764,7 +1102,17 @@ const Initialize = (props: IProps): JSX.Element => {
     "Unrestricted"
   );
   const setMaxRank = onSelectStringDefault("SetMaxRank", "NT");
-  const setThrowPenalty = onSelectStringDefault("SetThrowPenalty", null);
+  const setMatchTargetRank = onSelectStringDefault("SetMatchTargetRank", null);
+  const setRematchPolicy = onSelectString("SetRematchPolicy");
+  const setRankSequence = (evt: React.FocusEvent<HTMLInputElement>): void => {
+    const ranks = evt.target.value
+      .split(/[\s,]+/)
+      .filter((r) => r !== "")
+      .map((r) => r.toUpperCase());
+    send({ Action: { SetRankSequence: ranks.length > 0 ? ranks : null } });
+  };
+  const setThrowPenalty = (penalty: ThrowPenalty): void =>
+    send({ Action: { SetThrowPenalty: penalty } });
 
   const setHideLandlordsPoints = (
     evt: React.ChangeEvent<HTMLSelectElement>
@@ -773,6 +1121,10 @@ const Initialize = (props: IProps): JSX.Element => {
     send({ Action: { SetHideLandlordsPoints: evt.target.value === "hide" } });
   };
 
+  const setAttackingPointsVisibility = onSelectString(
+    "SetAttackingPointsVisibility"
+  );
+
   const setHidePlayedCards = (
     evt: React.ChangeEvent<HTMLSelectElement>
   ): void => {
@@ -835,6 +1187,10 @@ const Initialize = (props: IProps): JSX.Element => {
     };
   }
 
+  const host =
+    props.state.propagated.host ?? props.state.propagated.players[0]?.id;
+  const isHost = host !== undefined && host === currentPlayer.id;
+
   const landlordIndex = props.state.propagated.players.findIndex(
     (p: Player) => p.id === props.state.propagated.landlord
   );
@@ -904,6 +1260,13 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "first_deal_landlord_policy":
+            send({
+              Action: {
+                SetFirstDealLandlordPolicy: value,
+              },
+            });
+            break;
           case "first_landlord_selection_policy":
             send({
               Action: {
@@ -911,6 +1274,20 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "landlord_rotation_policy":
+            send({
+              Action: {
+                SetLandlordRotationPolicy: value,
+              },
+            });
+            break;
+          case "attacking_points_visibility":
+            send({
+              Action: {
+                SetAttackingPointsVisibility: value,
+              },
+            });
+            break;
           case "hide_landlord_points":
             send({
               Action: {
@@ -928,6 +1305,55 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "advancement_gates":
+            send({
+              Action: {
+                SetAdvancementGates: value,
+              },
+            });
+            break;
+          case "match_target_rank":
+            send({
+              Action: {
+                SetMatchTargetRank: value,
+              },
+            });
+            break;
+          case "rematch_policy":
+            send({
+              Action: {
+                SetRematchPolicy: value,
+              },
+            });
+            break;
+          case "turn_timers":
+            send({
+              Action: {
+                SetTurnTimers: value,
+              },
+            });
+            break;
+          case "spectator_delay_secs":
+            send({
+              Action: {
+                SetSpectatorDelay: value,
+              },
+            });
+            break;
+          case "ranked":
+            send({
+              Action: {
+                SetRanked: value,
+              },
+            });
+            break;
+          case "rank_sequence":
+            send({
+              Action: {
+                SetRankSequence: value,
+              },
+            });
+            break;
           case "max_rank":
             send({
               Action: {
@@ -949,6 +1375,34 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "kitty_bury_policy":
+            send({
+              Action: {
+                SetKittyBuryPolicy: value,
+              },
+            });
+            break;
+          case "max_kitty_points":
+            send({
+              Action: {
+                SetMaxKittyPoints: value,
+              },
+            });
+            break;
+          case "trick_history_length":
+            send({
+              Action: {
+                SetTrickHistoryLength: value,
+              },
+            });
+            break;
+          case "suit_order":
+            send({
+              Action: {
+                SetSuitOrder: value,
+              },
+            });
+            break;
           case "kitty_theft_policy":
             send({
               Action: {
@@ -977,6 +1431,20 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "throw_tie_policy":
+            send({
+              Action: {
+                SetThrowTiePolicy: value,
+              },
+            });
+            break;
+          case "trick_play_policy":
+            send({
+              Action: {
+                SetTrickPlayPolicy: value,
+              },
+            });
+            break;
           case "landlord_emoji":
             send({
               Action: {
@@ -1005,6 +1473,13 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "jokers_only_trump_policy":
+            send({
+              Action: {
+                SetJokersOnlyTrumpPolicy: value,
+              },
+            });
+            break;
           case "should_reveal_kitty_at_end_of_game":
             send({
               Action: {
@@ -1012,9 +1487,15 @@ const Initialize = (props: IProps): JSX.Element => {
               },
             });
             break;
+          case "reveal_kitty_card_by_card":
+            send({ Action: { SetRevealKittyCardByCard: value } });
+            break;
           case "hide_throw_halting_player":
             send({ Action: { SetHideThrowHaltingPlayer: value } });
             break;
+          case "face_down_discards":
+            send({ Action: { SetFaceDownDiscards: value } });
+            break;
           case "game_scoring_parameters":
             send({
               Action: {
@@ -1109,6 +1590,16 @@ const Initialize = (props: IProps): JSX.Element => {
     }
   };
 
+  const loadPresetSettings = (preset: PropagatedState): void => {
+    const fetchAsync = async (): Promise<void> => {
+      const fetchResult = await fetch("default_settings.json");
+      const fetchJSON = await fetchResult.json();
+      setGameSettings({ ...fetchJSON, ...preset });
+    };
+
+    fetchAsync().catch((e) => console.error(e));
+  };
+
   const resetGameSettings = (evt: React.SyntheticEvent): void => {
     evt.preventDefault();
 
@@ -1130,6 +1621,7 @@ const Initialize = (props: IProps): JSX.Element => {
       <Players
         players={props.state.propagated.players}
         observers={props.state.propagated.observers}
+        spectators={props.state.propagated.spectators}
         landlord={props.state.propagated.landlord}
         next={null}
         movable={true}
@@ -1141,6 +1633,29 @@ const Initialize = (props: IProps): JSX.Element => {
           <code>{window.location.href}</code>
         </a>
       </p>
+      {isHost ? (
+        <WebhookSettings webhookUrl={props.state.propagated.webhook_url} />
+      ) : null}
+      {isHost ? (
+        <DealSeedSettings dealSeed={props.state.propagated.deal_seed} />
+      ) : null}
+      {(props.state.propagated.num_games_finished ?? 0) > 0 ? (
+        <p>
+          <a
+            href={`game_log/${window.location.hash.slice(1, 17)}`}
+            download="game_log.json"
+          >
+            Download a log of the last game
+          </a>
+        </p>
+      ) : null}
+      {props.state.propagated.shuffle_seed !== undefined &&
+      props.state.propagated.shuffle_seed !== null ? (
+        <p>
+          The last game was dealt with seed{" "}
+          <code>{props.state.propagated.shuffle_seed}</code>
+        </p>
+      ) : null}
       {props.state.propagated.players.length >= 4 ? (
         <>
           <button
@@ -1163,9 +1678,23 @@ const Initialize = (props: IProps): JSX.Element => {
       <RandomizePlayersButton players={props.state.propagated.players}>
         Randomize player order
       </RandomizePlayersButton>
+      {isHost ? (
+        <button
+          onClick={() => send({ Action: "AddBot" })}
+          title="Seat a player whose moves are made by the server"
+        >
+          Add a bot
+        </button>
+      ) : null}
       <Kicker
         players={props.state.propagated.players}
         onKick={(playerId: number) => send({ Kick: playerId })}
+        onVoteKick={(playerId: number) =>
+          send({ Action: { StartKickVote: playerId } })
+        }
+        onBan={
+          isHost ? (playerId: number) => send({ Ban: playerId }) : undefined
+        }
       />
       <div className="game-settings">
         <h3>Game settings</h3>
@@ -1230,6 +1759,87 @@ const Initialize = (props: IProps): JSX.Element => {
             </select>
           </label>
         </div>
+        <div>
+          <label>
+            Cards that can be buried in the bottom:{" "}
+            <select
+              value={props.state.propagated.kitty_bury_policy}
+              onChange={setKittyBuryPolicy}
+            >
+              <option value="Unrestricted">Any card</option>
+              <option value="NoPointCards">No point cards</option>
+              <option value="NoTrumpCards">No trump cards</option>
+              <option value="NoPointOrTrumpCards">
+                No point or trump cards
+              </option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Most points that can be buried in the bottom:{" "}
+            <select
+              value={props.state.propagated.max_kitty_points ?? ""}
+              onChange={(evt) =>
+                send({
+                  Action: {
+                    SetMaxKittyPoints:
+                      evt.target.value === ""
+                        ? null
+                        : parseInt(evt.target.value, 10),
+                  },
+                })
+              }
+            >
+              <option value="">No limit</option>
+              <option value={10}>10</option>
+              <option value={20}>20</option>
+              <option value={30}>30</option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Past tricks players can look back over:{" "}
+            <select
+              value={props.state.propagated.trick_history_length ?? ""}
+              onChange={(evt) =>
+                send({
+                  Action: {
+                    SetTrickHistoryLength:
+                      evt.target.value === ""
+                        ? null
+                        : parseInt(evt.target.value, 10),
+                  },
+                })
+              }
+            >
+              <option value="">Every trick</option>
+              <option value={0}>None</option>
+              <option value={1}>The last trick</option>
+              <option value={3}>The last 3 tricks</option>
+              <option value={5}>The last 5 tricks</option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Order of suits in sorted hands:{" "}
+            <select
+              value={props.state.propagated.suit_order}
+              onChange={setSuitOrder}
+            >
+              <option value="TrumpLast">Trump after the other suits</option>
+              <option value="TrumpFirst">Trump before the other suits</option>
+              <option value="FixedSuitsTrumpLast">
+                Fixed suit order, trump last
+              </option>
+              <option value="FixedSuitsTrumpFirst">
+                Fixed suit order, trump first
+              </option>
+            </select>
+          </label>
+        </div>
         <div>
           <label>
             Card protection policy:{" "}
@@ -1251,6 +1861,9 @@ const Initialize = (props: IProps): JSX.Element => {
               <option value="NoFormatBasedDraw">
                 No format-based requirements (pairs do not draw pairs)
               </option>
+              <option value="OnlyLargestThrownUnitDrawn">
+                Throws only draw out their largest unit
+              </option>
             </select>
           </label>
         </div>
@@ -1270,6 +1883,35 @@ const Initialize = (props: IProps): JSX.Element => {
               <option value="TrickUnitLength">
                 Subsequent throw must beat largest component to win
               </option>
+              <option value="AnyUnit">
+                Subsequent throw must beat any one component to win
+              </option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Tied plays:{" "}
+            <select
+              value={props.state.propagated.throw_tie_policy}
+              onChange={setThrowTiePolicy}
+            >
+              <option value="LeaderWins">Earlier play wins ties</option>
+              <option value="FollowerWins">Later play wins ties</option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Following:{" "}
+            <select
+              value={props.state.propagated.trick_play_policy}
+              onChange={setTrickPlayPolicy}
+            >
+              <option value="AnyLegalPlay">Any legal play is allowed</option>
+              <option value="MustBeatIfAble">
+                Must beat the winning play if able
+              </option>
             </select>
           </label>
         </div>
@@ -1280,9 +1922,13 @@ const Initialize = (props: IProps): JSX.Element => {
           setBidPolicy={setBidPolicy}
           setBidReinforcementPolicy={setBidReinforcementPolicy}
           setJokerBidPolicy={setJokerBidPolicy}
+          setJokersOnlyTrumpPolicy={setJokersOnlyTrumpPolicy}
           setShouldRevealKittyAtEndOfGame={setShouldRevealKittyAtEndOfGame}
+          setRevealKittyCardByCard={setRevealKittyCardByCard}
           setHideThrowHaltingPlayer={setHideThrowHaltingPlayer}
+          setFaceDownDiscards={setFaceDownDiscards}
           setFirstLandlordSelectionPolicy={setFirstLandlordSelectionPolicy}
+          setLandlordRotationPolicy={setLandlordRotationPolicy}
           setGameStartPolicy={setGameStartPolicy}
           setGameShadowingPolicy={setGameShadowingPolicy}
           setKittyBidPolicy={setKittyBidPolicy}
@@ -1296,12 +1942,19 @@ const Initialize = (props: IProps): JSX.Element => {
           setMultipleJoinPolicy={setMultipleJoinPolicy}
           setAdvancementPolicy={setAdvancementPolicy}
           setMaxRank={setMaxRank}
+          setRankSequence={setRankSequence}
+          setMatchTargetRank={setMatchTargetRank}
+          setRematchPolicy={setRematchPolicy}
           setHideLandlordsPoints={setHideLandlordsPoints}
+          setAttackingPointsVisibility={setAttackingPointsVisibility}
           setHidePlayedCards={setHidePlayedCards}
           setKittyPenalty={setKittyPenalty}
           setThrowPenalty={setThrowPenalty}
           setPlayTakebackPolicy={setPlayTakebackPolicy}
           setBidTakebackPolicy={setBidTakebackPolicy}
+          setTurnTimers={(timers) =>
+            send({ Action: { SetTurnTimers: timers } })
+          }
         />
         <div>
           <label>
@@ -1315,6 +1968,47 @@ const Initialize = (props: IProps): JSX.Element => {
             </select>
           </label>
         </div>
+        <div>
+          <label>
+            Spectator delay:{" "}
+            <select
+              value={props.state.propagated.spectator_delay_secs ?? 0}
+              onChange={(evt) =>
+                send({
+                  Action: {
+                    SetSpectatorDelay: parseInt(evt.target.value, 10),
+                  },
+                })
+              }
+            >
+              <option value={0}>Spectators watch live</option>
+              <option value={10}>10 seconds</option>
+              <option value={30}>30 seconds</option>
+              <option value={60}>1 minute</option>
+              <option value={120}>2 minutes</option>
+            </select>
+          </label>
+        </div>
+        <div>
+          <label>
+            Ranked:{" "}
+            <select
+              value={props.state.propagated.ranked === true ? "on" : "off"}
+              onChange={(evt) =>
+                send({
+                  Action: {
+                    SetRanked: evt.target.value === "on",
+                  },
+                })
+              }
+            >
+              <option value="off">Games don&apos;t affect ratings</option>
+              <option value="on">
+                Games change the ratings of players with claimed names
+              </option>
+            </select>
+          </label>
+        </div>
         <h3>Continuation settings</h3>
         <LandlordSelector
           players={props.state.propagated.players}
@@ -1323,8 +2017,21 @@ const Initialize = (props: IProps): JSX.Element => {
             send({ Action: { SetLandlord: newLandlord } })
           }
         />
+        {props.state.propagated.landlord === null ||
+        props.state.propagated.landlord === undefined ? (
+          <FirstDealLandlordSelector
+            players={props.state.propagated.players}
+            policy={
+              props.state.propagated.first_deal_landlord_policy ?? "Random"
+            }
+            onChange={(policy: FirstDealLandlordPolicy) =>
+              send({ Action: { SetFirstDealLandlordPolicy: policy } })
+            }
+          />
+        ) : null}
         <RankSelector
           rank={currentPlayer.level}
+          ranks={props.state.propagated.rank_sequence}
           metaRank={currentPlayer.metalevel}
           onChangeRank={(newRank: string) =>
             send({ Action: { SetRank: newRank } })
@@ -1407,6 +2114,7 @@ const Initialize = (props: IProps): JSX.Element => {
             </ReactTooltip>
           </label>
         </div>
+        <SettingsPresets isHost={isHost} onLoad={loadPresetSettings} />
       </div>
     </div>
   );
@@ -1,8 +1,22 @@
 import * as React from "react";
 import { WebsocketContext } from "./WebsocketProvider";
 import { TimerContext } from "./TimerProvider";
+import WasmContext from "./WasmContext";
 import LabeledPlay from "./LabeledPlay";
 import PublicRoomsPane from "./PublicRoomsPane";
+import { reconnectTokenKey } from "./websocketHandler";
+
+// A random secret which identifies this device when claiming a name.
+const getDeviceKey = (): string => {
+  let key = window.localStorage.getItem("device_key");
+  if (key === null) {
+    const arr = new Uint8Array(32);
+    window.crypto.getRandomValues(arr);
+    key = Array.from(arr, (d) => ("0" + d.toString(16)).substr(-2)).join("");
+    window.localStorage.setItem("device_key", key);
+  }
+  return key;
+};
 
 interface IProps {
   name: string;
@@ -18,6 +32,13 @@ const JoinRoom = (props: IProps): JSX.Element => {
   );
   const { send } = React.useContext(WebsocketContext);
   const { setTimeout } = React.useContext(TimerContext);
+  const { zstdDictionaryIds } = React.useContext(WasmContext);
+  const [restoreError, setRestoreError] = React.useState<string | null>(
+    null
+  );
+  const [claimName, setClaimName] = React.useState<boolean>(
+    window.localStorage.getItem("claim_name") === "true"
+  );
 
   const handleChange = (event: React.ChangeEvent<HTMLInputElement>): void =>
     props.setName(event.target.value.trim());
@@ -25,16 +46,28 @@ const JoinRoom = (props: IProps): JSX.Element => {
   const handleRoomChange = (event: React.ChangeEvent<HTMLInputElement>): void =>
     props.setRoomName(event.target.value.trim());
 
-  const handleSubmit = (event: React.SyntheticEvent): void => {
-    event.preventDefault();
+  const join = (spectate: boolean): void => {
     if (props.name.length > 0 && props.room_name.length === 16) {
       send({
         room_name: props.room_name,
         name: props.name,
+        reconnect_token: window.sessionStorage.getItem(
+          reconnectTokenKey(props.room_name)
+        ),
+        spectate,
+        device_key: claimName ? getDeviceKey() : null,
+        wire_format: "MessagePack",
+        zstd_dictionaries: zstdDictionaryIds(),
+        state_diffs: true,
       });
     }
   };
 
+  const handleSubmit = (event: React.SyntheticEvent): void => {
+    event.preventDefault();
+    join(false);
+  };
+
   const editableRoomName = (
     <input
       type="text"
@@ -69,6 +102,21 @@ const JoinRoom = (props: IProps): JSX.Element => {
     setTimeout(generateRoomName, 0);
   }
 
+  const restoreGame = async (file: File): Promise<void> => {
+    const fetchResult = await fetch("api/saved_games", {
+      method: "POST",
+      headers: { "Content-Type": "application/octet-stream" },
+      body: file,
+    });
+    if (!fetchResult.ok) {
+      setRestoreError(await fetchResult.text());
+      return;
+    }
+    setRestoreError(null);
+    const { room_name: roomName } = await fetchResult.json();
+    props.setRoomName(roomName);
+  };
+
   return (
     <div>
       <LabeledPlay
@@ -110,7 +158,35 @@ const JoinRoom = (props: IProps): JSX.Element => {
               props.name.length === 0 ||
               props.name.length > 32
             }
-          />
+          />{" "}
+          <button
+            type="button"
+            onClick={() => join(true)}
+            disabled={
+              props.room_name.length !== 16 ||
+              props.name.length === 0 ||
+              props.name.length > 32
+            }
+          >
+            Just watch
+          </button>
+        </div>
+        <div>
+          <label>
+            <input
+              type="checkbox"
+              checked={claimName}
+              onChange={(evt) => {
+                setClaimName(evt.target.checked);
+                window.localStorage.setItem(
+                  "claim_name",
+                  evt.target.checked ? "true" : "false"
+                );
+              }}
+            />{" "}
+            Claim this name, so that only this device can use it and your
+            rank follows you between rooms
+          </label>
         </div>
       </form>
       <div>
@@ -130,6 +206,27 @@ const JoinRoom = (props: IProps): JSX.Element => {
           Once you are in the game, share the room link with at least three
           friends to start playing!
         </p>
+        <p>
+          <label>
+            Continue a saved game:{" "}
+            <input
+              type="file"
+              accept=".game"
+              onChange={(evt) => {
+                const file = evt.target.files?.[0];
+                if (file !== undefined) {
+                  restoreGame(file).catch((e) => console.error(e));
+                }
+              }}
+            />
+          </label>
+          {restoreError !== null ? (
+            <span className="red"> {restoreError}</span>
+          ) : null}
+          <br />
+          Everyone gets their seat back by joining the new room with the name
+          they had when the game was saved.
+        </p>
         <p>
           This is a game with many house rules, so be sure to check out the game
           settings to see if your favorite rules are implemented. There&apos;s
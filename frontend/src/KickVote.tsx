@@ -0,0 +1,56 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+const KickVote = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const propagated = propagatedState(props.state);
+  const vote = propagated.kick_vote;
+  if (vote === undefined || vote === null) {
+    return null;
+  }
+
+  const everyone = [...propagated.players, ...propagated.observers];
+  const target = everyone.find((p) => p.id === vote.target);
+  const me = everyone.find((p) => p.name === props.name);
+  if (target === undefined) {
+    return null;
+  }
+
+  const voted =
+    me !== undefined &&
+    (vote.approvals.includes(me.id) || vote.rejections.includes(me.id));
+
+  return (
+    <div className="reset-block">
+      <p>
+        Vote to kick {target.name}: {vote.approvals.length} for,{" "}
+        {vote.rejections.length} against
+      </p>
+      {me === undefined || me.id === target.id || voted ? null : (
+        <>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnKick: true } })}
+          >
+            Kick
+          </button>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnKick: false } })}
+          >
+            Don&apos;t kick
+          </button>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default KickVote;
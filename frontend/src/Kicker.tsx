@@ -3,6 +3,9 @@ import { Player } from "./gen-types";
 
 interface IProps {
   onKick: (playerId: number) => void;
+  onVoteKick: (playerId: number) => void;
+  // Only provided to the host.
+  onBan?: (playerId: number) => void;
   players: Player[];
 }
 const Kicker = (props: IProps): JSX.Element => {
@@ -34,6 +37,25 @@ const Kicker = (props: IProps): JSX.Element => {
         >
           Kick
         </button>
+        <button
+          className="normal"
+          onClick={() => props.onVoteKick(selection)}
+          disabled={selection === null}
+        >
+          Vote to kick
+        </button>
+        {props.onBan !== undefined ? (
+          <button
+            className="normal"
+            onClick={() =>
+              confirm("Ban this player from the room?") &&
+              props.onBan(selection)
+            }
+            disabled={selection === null}
+          >
+            Ban
+          </button>
+        ) : null}
       </label>
     </div>
   );
@@ -0,0 +1,64 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+const PauseButton = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const propagated = propagatedState(props.state);
+  const me = propagated.players.find((p) => p.name === props.name);
+  if (me === undefined) {
+    return null;
+  }
+
+  const pause = propagated.pause;
+  if (pause === undefined || pause === null) {
+    return (
+      <div className="reset-block">
+        <a
+          href={window.location.href}
+          onClick={(evt) => {
+            evt.preventDefault();
+            send({ Action: "RequestPause" });
+          }}
+          title="Stop the game and its turn timers until everyone is back"
+        >
+          Pause game
+        </a>
+      </div>
+    );
+  }
+
+  const requester = propagated.players.find(
+    (p) => p.id === pause.requested_by
+  );
+  const ready = pause.resume_votes.includes(me.id);
+  return (
+    <div className="reset-block">
+      <p>
+        {requester !== undefined ? requester.name : "Someone"} paused the game
+        ({pause.resume_votes.length} of {propagated.players.length} ready to
+        resume)
+      </p>
+      {ready ? null : (
+        <a
+          href={window.location.href}
+          onClick={(evt) => {
+            evt.preventDefault();
+            send({ Action: "VoteToResume" });
+          }}
+          title="Resume once a majority of players are ready"
+        >
+          Ready to resume
+        </a>
+      )}
+    </div>
+  );
+};
+
+export default PauseButton;
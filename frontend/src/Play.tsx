@@ -8,6 +8,7 @@ import {
   TrickDrawPolicy,
   FoundViablePlay,
   SuitGroup,
+  LandlordTeamSize,
 } from "./gen-types";
 import Header from "./Header";
 import Beeper from "./Beeper";
@@ -137,6 +138,9 @@ const Play = (props: IProps): JSX.Element => {
         hands: playPhase.hands,
         cards: selected,
         trick_draw_policy: playPhase.propagated.trick_draw_policy,
+        trick_play_policy: playPhase.propagated.trick_play_policy,
+        throw_eval_policy: playPhase.propagated.throw_evaluation_policy,
+        throw_tie_policy: playPhase.propagated.throw_tie_policy,
       });
       // In order to play the first trick, the grouping must be disambiguated!
       if (lastPlay === undefined) {
@@ -151,6 +155,9 @@ const Play = (props: IProps): JSX.Element => {
     playPhase.hands,
     selected,
     playPhase.propagated.trick_draw_policy,
+    playPhase.propagated.trick_play_policy,
+    playPhase.propagated.throw_evaluation_policy,
+    playPhase.propagated.throw_tie_policy,
     isSpectator,
     lastPlay,
     playPhase.game_ended_early,
@@ -191,7 +198,7 @@ const Play = (props: IProps): JSX.Element => {
       params: playPhase.propagated.game_scoring_parameters,
       non_landlord_points: nonLandlordPointsWithPenalties,
       observed_points: totalPointsPlayed,
-    });
+    }).reachable;
 
   const landlordSuffix =
     playPhase.propagated.landlord_emoji !== undefined &&
@@ -200,15 +207,15 @@ const Play = (props: IProps): JSX.Element => {
       ? playPhase.propagated.landlord_emoji
       : "(当庄)";
 
-  const landlordTeamSize = playPhase.landlords_team.length;
-  let configFriendTeamSize = 0;
-  let smallerTeamSize = false;
+  const landlordTeamSize: LandlordTeamSize = {
+    actual: playPhase.landlords_team.length,
+    expected: playPhase.landlords_team.length,
+  };
   if (playPhase.game_mode !== "Tractor") {
-    configFriendTeamSize =
+    landlordTeamSize.expected =
       playPhase.game_mode.FindingFriends.num_friends != null
         ? playPhase.game_mode.FindingFriends.num_friends + 1
         : playPhase.propagated.players.length / 2;
-    smallerTeamSize = landlordTeamSize < configFriendTeamSize;
   }
 
   const getCardsFromHand = (pid: number): SuitGroup[] => {
@@ -221,6 +228,7 @@ const Play = (props: IProps): JSX.Element => {
     return sortAndGroupCards({
       cards: cardsInHand,
       trump: props.playPhase.trump,
+      suit_order: props.playPhase.propagated.suit_order,
     });
   };
 
@@ -234,6 +242,8 @@ const Play = (props: IProps): JSX.Element => {
       <Players
         players={playPhase.propagated.players}
         observers={playPhase.propagated.observers}
+        spectators={playPhase.propagated.spectators}
+        idle={playPhase.propagated.idle_players}
         landlord={playPhase.landlord}
         landlords_team={playPhase.landlords_team}
         name={props.name}
@@ -250,7 +260,7 @@ const Play = (props: IProps): JSX.Element => {
           have been removed from the deck
         </p>
       ) : null}
-      {settings.showPointsAboveGame && (
+      {settings.showPointsAboveGame && !playPhase.attacking_points_hidden && (
         <ProgressBarDisplay
           points={playPhase.points}
           penalties={playPhase.penalties}
@@ -261,7 +271,7 @@ const Play = (props: IProps): JSX.Element => {
           landlord={playPhase.landlord}
           hideLandlordPoints={playPhase.propagated.hide_landlord_points}
           gameScoringParameters={playPhase.propagated.game_scoring_parameters}
-          smallerTeamSize={smallerTeamSize}
+          landlordTeamSize={landlordTeamSize}
         />
       )}
       <Trick
@@ -380,6 +390,7 @@ const Play = (props: IProps): JSX.Element => {
             hands={playPhase.hands}
             playerId={currentPlayer.id}
             trump={playPhase.trump}
+            suitOrder={playPhase.propagated.suit_order}
             selectedCards={selected}
             onSelect={(newSelected) => {
               setSelected(newSelected);
@@ -420,8 +431,9 @@ const Play = (props: IProps): JSX.Element => {
         landlord={playPhase.landlord}
         trump={playPhase.trump}
         hideLandlordPoints={playPhase.propagated.hide_landlord_points}
+        hideAttackingPoints={playPhase.attacking_points_hidden}
         gameScoringParameters={playPhase.propagated.game_scoring_parameters}
-        smallerTeamSize={smallerTeamSize}
+        landlordTeamSize={landlordTeamSize}
       />
       <LabeledPlay
         trump={playPhase.trump}
@@ -2,16 +2,48 @@ import * as React from "react";
 
 import classNames from "classnames";
 import { MovePlayerLeft, MovePlayerRight } from "./MovePlayerButton";
-import { Player } from "./gen-types";
+import { CareerCard, Player } from "./gen-types";
 import { WebsocketContext } from "./WebsocketProvider";
+import { AppStateContext } from "./AppStateProvider";
+
+const formatCareer = (stats: CareerCard): string => {
+  const lines = [`${stats.rounds_played} rounds played`];
+  if (stats.rating !== null && stats.rating !== undefined) {
+    lines.unshift(`Rated ${Math.round(stats.rating)}`);
+  }
+  if (
+    stats.landlord_defense_rate !== null &&
+    stats.landlord_defense_rate !== undefined
+  ) {
+    lines.push(
+      `Defended ${Math.round(stats.landlord_defense_rate * 100)}% as landlord`
+    );
+  }
+  if (
+    stats.average_points_captured !== null &&
+    stats.average_points_captured !== undefined
+  ) {
+    lines.push(
+      `Captures ${stats.average_points_captured.toFixed(1)} points a round`
+    );
+  }
+  if (stats.biggest_throw_forfeited > 0) {
+    lines.push(
+      `Biggest throw forfeited: ${stats.biggest_throw_forfeited} cards`
+    );
+  }
+  return lines.join("\n");
+};
 
 interface IProps {
   players: Player[];
   observers: Player[];
+  spectators?: Player[];
   landlord?: number | null;
   landlords_team?: number[];
   movable?: boolean;
   next?: number | null;
+  idle?: number[];
   name: string;
 }
 
@@ -19,14 +51,21 @@ const Players = (props: IProps): JSX.Element => {
   const {
     players,
     observers,
+    spectators,
     landlord,
     // eslint-disable-next-line @typescript-eslint/naming-convention
     landlords_team,
     movable,
     next,
+    idle,
     name,
   } = props;
   const { send } = React.useContext(WebsocketContext);
+  const { state } = React.useContext(AppStateContext);
+  const careerTitle = (p: Player): string | undefined => {
+    const stats = state.careerStats[p.name];
+    return stats !== undefined ? formatCareer(stats) : undefined;
+  };
 
   const makeDescriptor = (p: Player): Array<JSX.Element | string> => {
     if (p.metalevel <= 1) {
@@ -41,91 +80,116 @@ const Players = (props: IProps): JSX.Element => {
   };
 
   return (
-    <table className="players">
-      <tbody>
-        <tr>
-          {players.map((player) => {
-            const className = classNames("player", {
-              landlord:
-                player.id === landlord || landlords_team?.includes(player.id),
-              movable,
-              next: player.id === next,
-            });
+    <>
+      <table className="players">
+        <tbody>
+          <tr>
+            {players.map((player) => {
+              const className = classNames("player", {
+                landlord:
+                  player.id === landlord || landlords_team?.includes(player.id),
+                movable,
+                next: player.id === next,
+              });
 
-            const descriptor = makeDescriptor(player);
+              const descriptor = makeDescriptor(player);
 
-            if (player.id === landlord) {
-              descriptor.push(" (当庄)");
-            }
-            if (player.name === name) {
-              descriptor.push(" (You!)");
-            }
+              if (player.id === landlord) {
+                descriptor.push(" (当庄)");
+              }
+              if (player.name === name) {
+                descriptor.push(" (You!)");
+              }
+              if (idle?.includes(player.id)) {
+                descriptor.push(" (idle)");
+              }
+              const latency = state.latencies[player.id];
+              if (latency !== undefined) {
+                descriptor.push(` (${latency}ms)`);
+              }
 
-            return (
-              <td key={player.id} className={className}>
-                {descriptor}
-                {movable && (
-                  <span
-                    style={{
-                      display: "block",
-                      marginTop: "6px",
-                      textAlign: "center",
-                      width: "100%",
-                    }}
-                  >
-                    <MovePlayerLeft players={players} player={player} />
+              return (
+                <td
+                  key={player.id}
+                  className={className}
+                  title={careerTitle(player)}
+                >
+                  {descriptor}
+                  {movable && (
                     <span
-                      style={{ cursor: "pointer" }}
-                      onClick={(_) => {
-                        send({ Action: { MakeObserver: player.id } });
+                      style={{
+                        display: "block",
+                        marginTop: "6px",
+                        textAlign: "center",
+                        width: "100%",
                       }}
                     >
-                      ✔️
+                      <MovePlayerLeft players={players} player={player} />
+                      <span
+                        style={{ cursor: "pointer" }}
+                        onClick={(_) => {
+                          send({ Action: { MakeObserver: player.id } });
+                        }}
+                      >
+                        ✔️
+                      </span>
+                      <MovePlayerRight players={players} player={player} />
                     </span>
-                    <MovePlayerRight players={players} player={player} />
-                  </span>
-                )}
-              </td>
-            );
-          })}
-          {observers.map((player) => {
-            const className = classNames("player observer", { movable });
-            const descriptor = makeDescriptor(player);
+                  )}
+                </td>
+              );
+            })}
+            {observers.map((player) => {
+              const className = classNames("player observer", { movable });
+              const descriptor = makeDescriptor(player);
 
-            if (player.name === name) {
-              descriptor.push(" (You!)");
-            }
+              if (player.name === name) {
+                descriptor.push(" (You!)");
+              }
 
-            return (
-              <td key={player.id} className={className}>
-                <span style={{ textDecoration: "line-through" }}>
-                  {descriptor}
-                </span>
-                {movable && (
-                  <span
-                    style={{
-                      display: "block",
-                      marginTop: "6px",
-                      textAlign: "center",
-                      width: "100%",
-                    }}
-                  >
+              return (
+                <td
+                  key={player.id}
+                  className={className}
+                  title={careerTitle(player)}
+                >
+                  <span style={{ textDecoration: "line-through" }}>
+                    {descriptor}
+                  </span>
+                  {movable && (
                     <span
-                      style={{ cursor: "pointer" }}
-                      onClick={(_) => {
-                        send({ Action: { MakePlayer: player.id } });
+                      style={{
+                        display: "block",
+                        marginTop: "6px",
+                        textAlign: "center",
+                        width: "100%",
                       }}
                     >
-                      💤
+                      <span
+                        style={{ cursor: "pointer" }}
+                        onClick={(_) => {
+                          send({ Action: { MakePlayer: player.id } });
+                        }}
+                      >
+                        💤
+                      </span>
                     </span>
-                  </span>
-                )}
-              </td>
-            );
-          })}
-        </tr>
-      </tbody>
-    </table>
+                  )}
+                </td>
+              );
+            })}
+          </tr>
+        </tbody>
+      </table>
+      {spectators !== undefined && spectators.length > 0 && (
+        <p className="spectators">
+          Spectating:{" "}
+          {spectators
+            .map((p) => (p.name === name ? `${p.name} (You!)` : p.name))
+            .join(", ")}
+        </p>
+      )}
+    </>
   );
 };
 
@@ -1,6 +1,12 @@
 import * as React from "react";
 import ProgressBar from "./ProgressBar";
-import { Player, GameScoringParameters, Deck, Trump } from "./gen-types";
+import {
+  Player,
+  GameScoringParameters,
+  Deck,
+  LandlordTeamSize,
+  Trump,
+} from "./gen-types";
 import ArrayUtils from "./util/array";
 import ObjectUtils from "./util/object";
 import LabeledPlay from "./LabeledPlay";
@@ -18,7 +24,8 @@ interface IProps {
   landlord: number;
   trump: Trump;
   hideLandlordPoints: boolean;
-  smallerTeamSize: boolean;
+  hideAttackingPoints?: boolean;
+  landlordTeamSize: LandlordTeamSize;
   gameScoringParameters: GameScoringParameters;
 }
 
@@ -82,7 +89,7 @@ const Points = (props: IProps): JSX.Element => {
   const { score, next_threshold: nextThreshold } = computeScore({
     params: props.gameScoringParameters,
     decks: props.decks,
-    smaller_landlord_team_size: props.smallerTeamSize,
+    landlord_team_size: props.landlordTeamSize,
     non_landlord_points: nonLandlordPointsWithPenalties,
   });
 
@@ -95,6 +102,8 @@ const Points = (props: IProps): JSX.Element => {
 
     if (props.hideLandlordPoints && onLandlordTeam) {
       return null;
+    } else if (props.hideAttackingPoints && !onLandlordTeam) {
+      return null;
     } else {
       return (
         <LabeledPlay
@@ -132,14 +141,14 @@ const Points = (props: IProps): JSX.Element => {
   const { results: scoreTransitions, total_points: totalPoints } =
     explainScoring({
       params: props.gameScoringParameters,
-      smaller_landlord_team_size: props.smallerTeamSize,
+      landlord_team_size: props.landlordTeamSize,
       decks: props.decks,
     });
 
   return (
     <div className="points">
       <h2>Points</h2>
-      {!settings.showPointsAboveGame && (
+      {!settings.showPointsAboveGame && !props.hideAttackingPoints && (
         <ProgressBar
           checkpoints={scoreTransitions
             .map((transition) => transition.point_threshold)
@@ -150,13 +159,20 @@ const Points = (props: IProps): JSX.Element => {
           hideLandlordPoints={props.hideLandlordPoints}
         />
       )}
-      <p>
-        {penaltyDelta === 0
-          ? nonLandlordPoints
-          : `${nonLandlordPoints} + ${penaltyDelta}`}
-        分{props.hideLandlordPoints ? null : ` / ${totalPointsPlayed}分`} stolen
-        from {landlord.name}&apos;s team. {thresholdStr}
-      </p>
+      {props.hideAttackingPoints ? (
+        <p>
+          The attacking team&apos;s points are hidden until the end of the
+          game.
+        </p>
+      ) : (
+        <p>
+          {penaltyDelta === 0
+            ? nonLandlordPoints
+            : `${nonLandlordPoints} + ${penaltyDelta}`}
+          分{props.hideLandlordPoints ? null : ` / ${totalPointsPlayed}分`}{" "}
+          stolen from {landlord.name}&apos;s team. {thresholdStr}
+        </p>
+      )}
       {playerPointElements}
     </div>
   );
@@ -178,7 +194,7 @@ export const ProgressBarDisplay = (props: IProps): JSX.Element => {
   const { results: scoreTransitions, total_points: totalPoints } =
     explainScoring({
       params: props.gameScoringParameters,
-      smaller_landlord_team_size: props.smallerTeamSize,
+      landlord_team_size: props.landlordTeamSize,
       decks: props.decks,
     });
   return (
@@ -2,6 +2,7 @@ import * as React from "react";
 
 interface IProps {
   rank: string;
+  ranks?: string[] | null;
   metaRank: number;
   onChangeRank: (newRank: string) => void;
   onChangeMetaRank: (newMetaRank: number) => void;
@@ -42,7 +43,7 @@ const RankSelector = (props: IProps): JSX.Element => {
       <label>
         Your rank:{" "}
         <select value={props.rank} onChange={handleChange}>
-          {allRanks.map((rank) => (
+          {(props.ranks ?? allRanks).map((rank) => (
             <option value={rank} key={rank}>
               {rank}
             </option>
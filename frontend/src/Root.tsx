@@ -13,6 +13,13 @@ import Play from "./Play";
 import DebugInfo from "./DebugInfo";
 import TitleHandler from "./TitleHandler";
 import ResetButton from "./ResetButton";
+import KickVote from "./KickVote";
+import TurnTimer from "./TurnTimer";
+import PauseButton from "./PauseButton";
+import SaveGameButton from "./SaveGameButton";
+import UndoVote from "./UndoVote";
+import SettingsProposal from "./SettingsProposal";
+import Coaching from "./Coaching";
 
 const Confetti = React.lazy(async () => await import("./Confetti"));
 
@@ -107,9 +114,30 @@ const Root = (): JSX.Element => {
             </React.Suspense>
           ) : null}
           <div className="game">
+            <KickVote state={state.gameState} name={state.name} />
+            <TurnTimer
+              state={state.gameState}
+              timer={state.turnTimer}
+              name={state.name}
+            />
             {"Initialize" in state.gameState ? null : (
               <ResetButton state={state.gameState} name={state.name} />
             )}
+            {"Initialize" in state.gameState ? null : (
+              <PauseButton state={state.gameState} name={state.name} />
+            )}
+            {"Initialize" in state.gameState ? null : (
+              <SaveGameButton state={state.gameState} name={state.name} />
+            )}
+            {"Initialize" in state.gameState ? null : (
+              <UndoVote state={state.gameState} name={state.name} />
+            )}
+            {"Initialize" in state.gameState ? null : (
+              <SettingsProposal state={state.gameState} name={state.name} />
+            )}
+            {"Initialize" in state.gameState ? null : (
+              <Coaching state={state.gameState} name={state.name} />
+            )}
             {"Initialize" in state.gameState ? (
               <Initialize
                 state={state.gameState.Initialize}
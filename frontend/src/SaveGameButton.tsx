@@ -0,0 +1,65 @@
+import * as React from "react";
+import { AppStateContext } from "./AppStateProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+import { reconnectTokenKey } from "./websocketHandler";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+const SaveGameButton = (props: IProps): JSX.Element => {
+  const { state } = React.useContext(AppStateContext);
+  const [error, setError] = React.useState<string | null>(null);
+  const propagated = propagatedState(props.state);
+  const me = propagated.players.find((p) => p.name === props.name);
+  const host = propagated.host ?? propagated.players[0]?.id;
+  if (me === undefined || host !== me.id) {
+    return null;
+  }
+
+  const saveGame = async (): Promise<void> => {
+    const fetchResult = await fetch(
+      `api/rooms/${encodeURIComponent(state.roomName)}/save`,
+      {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          token: window.sessionStorage.getItem(
+            reconnectTokenKey(state.roomName)
+          ),
+        }),
+      }
+    );
+    if (!fetchResult.ok) {
+      setError(await fetchResult.text());
+      return;
+    }
+    setError(null);
+    const url = URL.createObjectURL(await fetchResult.blob());
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = `shengji-${state.roomName}.game`;
+    link.click();
+    URL.revokeObjectURL(url);
+  };
+
+  return (
+    <div className="reset-block">
+      <a
+        href={window.location.href}
+        onClick={(evt) => {
+          evt.preventDefault();
+          saveGame().catch((e) => console.error(e));
+        }}
+        title="Download a file which the game can be restored from later, even on another day"
+      >
+        Save game
+      </a>
+      {error !== null ? <p className="red">{error}</p> : null}
+    </div>
+  );
+};
+
+export default SaveGameButton;
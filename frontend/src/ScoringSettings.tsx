@@ -1,10 +1,17 @@
 import * as React from "react";
-import { GameScoringParameters, Deck, ScoreSegment } from "./gen-types";
+import {
+  BonusLevelPolicy,
+  GameScoringParameters,
+  Deck,
+  KittyPenalty,
+  ScoreSegment,
+} from "./gen-types";
 import { WebsocketContext } from "./WebsocketProvider";
 import { WasmContext } from "./WasmContext";
 
 interface IProps {
   params: GameScoringParameters;
+  kittyPenalty?: KittyPenalty;
   decks: Deck[];
 }
 
@@ -21,31 +28,22 @@ export const GameScoringSettings = (props: IProps): JSX.Element => {
     });
   };
 
-  const bonusEnabled =
-    props.params.bonus_level_policy === "BonusLevelForSmallerLandlordTeam";
-
   const {
     results: scoreTransitions,
+    kitty_multipliers: kittyMultipliers,
     step_size: stepSize,
     total_points: totalPoints,
+    explanations,
   } = explainScoring({
     params: props.params,
-    smaller_landlord_team_size: false,
+    landlord_team_size: { actual: 1, expected: 1 },
+    kitty_penalty: props.kittyPenalty,
     decks: props.decks,
   });
 
-  const bonusScoreTransitions = bonusEnabled
-    ? explainScoring({
-        params: props.params,
-        smaller_landlord_team_size: true,
-        decks: props.decks,
-      }).results
-    : scoreTransitions;
-
   const scoreSegments: Array<{
     span: number;
     segment: ScoreSegment;
-    bonusSegment: ScoreSegment | null;
   }> = [];
   let maxPts = 0;
   let maxLandlordDelta = 0;
@@ -65,22 +63,13 @@ export const GameScoringSettings = (props: IProps): JSX.Element => {
       segment.results.non_landlord_delta,
       maxNonLandlordDelta
     );
-    scoreSegments.push({
-      span,
-      segment,
-      bonusSegment: bonusScoreTransitions[i - 1].results.landlord_bonus
-        ? bonusScoreTransitions[i - 1]
-        : null,
-    });
+    scoreSegments.push({ span, segment });
     maxPts += span;
   }
   const last = scoreTransitions.length - 1;
   scoreSegments.push({
     span: 5 * props.decks.length,
     segment: scoreTransitions[last],
-    bonusSegment: bonusScoreTransitions[last].results.landlord_bonus
-      ? bonusScoreTransitions[last]
-      : null,
   });
   maxPts += 5 * props.decks.length;
   maxNonLandlordDelta = Math.max(
@@ -93,24 +82,11 @@ export const GameScoringSettings = (props: IProps): JSX.Element => {
   );
 
   const text = (idx: number): JSX.Element => {
-    let txt = "Attacking team wins, but doesn't level up.";
-    const segment = scoreSegments[idx];
-    if (segment.segment.results.landlord_won) {
-      txt = `Defending team wins, and goes up ${
-        segment.segment.results.landlord_delta
-      } level${segment.segment.results.landlord_delta === 1 ? "" : "s"}.`;
-      if (segment.bonusSegment !== null) {
-        txt += ` If the team is unexpectedly small, they go up ${
-          segment.bonusSegment.results.landlord_delta
-        } level${
-          segment.bonusSegment.results.landlord_delta === 1 ? "" : "s"
-        }.`;
-      }
-    } else if (segment.segment.results.non_landlord_delta > 0) {
-      txt = `Attacking team wins, and goes up ${
-        segment.segment.results.non_landlord_delta
-      } level${segment.segment.results.non_landlord_delta === 1 ? "" : "s"}.`;
-    }
+    const threshold = scoreSegments[idx].segment.point_threshold;
+    const txt = explanations
+      .filter((e) => e.explanation.threshold === threshold)
+      .map((e) => e.description)
+      .join(" ");
     return <>{txt}</>;
   };
 
@@ -172,7 +148,34 @@ export const GameScoringSettings = (props: IProps): JSX.Element => {
           ) : (
             <p>Hover over the scores above for more details.</p>
           )}
+          {explanations
+            .filter((e) => e.explanation.threshold == null)
+            .map((e) => <p key={e.explanation.reason}>{e.description}</p>)}
+          <p>
+            Points in the bottom are multiplied by{" "}
+            {kittyMultipliers
+              .map((m) => `${m.multiplier} (${m.largest_unit_size}-card unit)`)
+              .join(", ")}{" "}
+            when the attacking team wins the last trick.
+          </p>
         </div>
+        {props.params.custom_thresholds != null && (
+          <div>
+            <label>
+              This room uses a custom scoring table, so the step size settings
+              below have no effect.
+            </label>{" "}
+            <button
+              className="normal"
+              onClick={(evt) => {
+                evt.preventDefault();
+                updateSettings({ custom_thresholds: null });
+              }}
+            >
+              Use step sizes instead
+            </button>
+          </div>
+        )}
         <div>
           <label>Step size: {stepSize} points</label>
         </div>
@@ -278,20 +281,79 @@ export const GameScoringSettings = (props: IProps): JSX.Element => {
           (default: 2)
         </div>
         <div>
-          <label>Grant a bonus level for unexpectedly small team</label>{" "}
-          <input
+          <label>Bonus for an unexpectedly small team: </label>
+          <select
             id="small-team-bonus"
-            type="checkbox"
+            value={props.params.bonus_level_policy}
             onChange={(evt) => {
               evt.preventDefault();
               updateSettings({
-                bonus_level_policy: evt.target.checked
-                  ? "BonusLevelForSmallerLandlordTeam"
-                  : "NoBonusLevel",
+                bonus_level_policy: evt.target.value as BonusLevelPolicy,
               });
             }}
-            checked={bonusEnabled}
-          />
+          >
+            <option value="NoBonusLevel">None</option>
+            <option value="BonusLevelForSmallerLandlordTeam">
+              One extra level
+            </option>
+            <option value="ScaleByLandlordTeamSize">
+              Scale levels by team size
+            </option>
+          </select>
+        </div>
+        <div>
+          <label>Bonus levels when the attacking team wins no points: </label>
+          <select
+            value={`${props.params.small_shutout_bonus ?? 0}`}
+            onChange={(evt) => {
+              evt.preventDefault();
+              updateSettings({
+                small_shutout_bonus: parseInt(evt.target.value, 10),
+              });
+            }}
+          >
+            {[0, 1, 2, 3].map((n) => (
+              <option key={n}>{n}</option>
+            ))}
+          </select>{" "}
+          (default: 0)
+        </div>
+        <div>
+          <label>Bonus levels when the attacking team wins no tricks: </label>
+          <select
+            value={`${props.params.big_shutout_bonus ?? 0}`}
+            onChange={(evt) => {
+              evt.preventDefault();
+              updateSettings({
+                big_shutout_bonus: parseInt(evt.target.value, 10),
+              });
+            }}
+          >
+            {[0, 1, 2, 3].map((n) => (
+              <option key={n}>{n}</option>
+            ))}
+          </select>{" "}
+          (default: 0)
+        </div>
+        <div>
+          <label>
+            Bonus levels for each card in the landlord&apos;s bid beyond the
+            first:{" "}
+          </label>
+          <select
+            value={`${props.params.bid_defense_bonus ?? 0}`}
+            onChange={(evt) => {
+              evt.preventDefault();
+              updateSettings({
+                bid_defense_bonus: parseInt(evt.target.value, 10),
+              });
+            }}
+          >
+            {[0, 1, 2].map((n) => (
+              <option key={n}>{n}</option>
+            ))}
+          </select>{" "}
+          (default: 0)
         </div>
       </div>
     </>
@@ -0,0 +1,112 @@
+import * as React from "react";
+import { PropagatedState } from "./gen-types";
+import { AppStateContext } from "./AppStateProvider";
+import { reconnectTokenKey } from "./websocketHandler";
+
+interface IPresetSummary {
+  name: string;
+  owner: string;
+  updated_at: number;
+}
+
+interface IProps {
+  isHost: boolean;
+  onLoad: (settings: PropagatedState) => void;
+}
+
+const SettingsPresets = (props: IProps): JSX.Element => {
+  const { state } = React.useContext(AppStateContext);
+  const [presets, setPresets] = React.useState<IPresetSummary[]>([]);
+  const [selected, setSelected] = React.useState<string>("");
+  const [name, setName] = React.useState<string>("");
+  const [error, setError] = React.useState<string | null>(null);
+
+  const refresh = async (): Promise<void> => {
+    const fetchResult = await fetch("api/presets");
+    setPresets(await fetchResult.json());
+  };
+
+  React.useEffect(() => {
+    refresh().catch((e) => console.error(e));
+  }, []);
+
+  const loadPreset = async (): Promise<void> => {
+    const fetchResult = await fetch(
+      `api/presets/${encodeURIComponent(selected)}`
+    );
+    if (!fetchResult.ok) {
+      setError(await fetchResult.text());
+      return;
+    }
+    props.onLoad(await fetchResult.json());
+  };
+
+  const savePreset = async (): Promise<void> => {
+    const fetchResult = await fetch(`api/presets/${encodeURIComponent(name)}`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({
+        room_name: state.roomName,
+        token: window.sessionStorage.getItem(
+          reconnectTokenKey(state.roomName)
+        ),
+      }),
+    });
+    if (!fetchResult.ok) {
+      setError(await fetchResult.text());
+      return;
+    }
+    setError(null);
+    setName("");
+    await refresh();
+  };
+
+  return (
+    <div>
+      <label>
+        Presets:{" "}
+        <select value={selected} onChange={(e) => setSelected(e.target.value)}>
+          <option value="">choose a preset</option>
+          {presets.map((p) => (
+            <option key={p.name} value={p.name}>
+              {p.name} (by {p.owner})
+            </option>
+          ))}
+        </select>
+        <button
+          className="normal"
+          disabled={selected === ""}
+          onClick={(evt) => {
+            evt.preventDefault();
+            loadPreset().catch((e) => console.error(e));
+          }}
+        >
+          Load preset
+        </button>
+      </label>
+      {props.isHost ? (
+        <form
+          style={{ display: "inline" }}
+          onSubmit={(evt) => {
+            evt.preventDefault();
+            if (name.length > 0) {
+              savePreset().catch((e) => console.error(e));
+            }
+          }}
+        >
+          <input
+            type="text"
+            maxLength={64}
+            value={name}
+            onChange={(evt) => setName(evt.target.value)}
+            placeholder="preset name"
+          />
+          <input type="submit" value="Save as preset" />
+        </form>
+      ) : null}
+      {error !== null ? <span> {error}</span> : null}
+    </div>
+  );
+};
+
+export default SettingsPresets;
@@ -0,0 +1,112 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+interface IProposal {
+  label: string;
+  patch: { [k: string]: unknown };
+}
+
+// The changes which can be proposed once cards have been dealt. Anything
+// else would change how the cards already in everyone's hands are scored.
+const proposals: IProposal[] = [
+  {
+    label: "Show the attacking team's points to everyone",
+    patch: { attacking_points_visibility: "Visible" },
+  },
+  {
+    label: "Hide the attacking team's points from the landlord's team",
+    patch: { attacking_points_visibility: "HiddenFromLandlordTeam" },
+  },
+  {
+    label: "Hide the attacking team's points from everyone",
+    patch: { attacking_points_visibility: "HiddenFromAll" },
+  },
+  {
+    label: "Don't penalize failed throws",
+    patch: { throw_penalty: "None" },
+  },
+  {
+    label: "Penalize failed throws by 10 points",
+    patch: { throw_penalty: "TenPointsPerAttempt" },
+  },
+  {
+    label: "Turn off the turn timers",
+    patch: { turn_timers: {} },
+  },
+];
+
+const SettingsProposal = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const propagated = propagatedState(props.state);
+  const me = propagated.players.find((p) => p.name === props.name);
+  if (me === undefined) {
+    return null;
+  }
+
+  const proposal = propagated.settings_proposal;
+  if (proposal === undefined || proposal === null) {
+    return (
+      <div className="reset-block">
+        <select
+          value=""
+          onChange={(evt) => {
+            const chosen = proposals[parseInt(evt.target.value, 10)];
+            if (chosen !== undefined) {
+              send({ Action: { ProposeSettings: chosen.patch } });
+            }
+          }}
+          title="Ask everyone else to change a setting for the rest of the game"
+        >
+          <option value="">Propose a settings change...</option>
+          {proposals.map((p, i) => (
+            <option key={i} value={i}>
+              {p.label}
+            </option>
+          ))}
+        </select>
+      </div>
+    );
+  }
+
+  const proposer = propagated.players.find((p) => p.id === proposal.proposer);
+  const settings = Object.keys(proposal.patch)
+    .map((s) => s.replace(/_/g, " "))
+    .join(", the ");
+  const waiting = propagated.players.filter(
+    (p) => p.id !== proposal.proposer && !proposal.approvals.includes(p.id)
+  );
+  return (
+    <div className="reset-block">
+      <p>
+        {proposer !== undefined ? proposer.name : "Someone"} wants to change
+        the {settings} (waiting on {waiting.map((p) => p.name).join(", ")})
+      </p>
+      {me.id === proposal.proposer ||
+      proposal.approvals.includes(me.id) ? null : (
+        <>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnSettings: true } })}
+          >
+            Allow
+          </button>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnSettings: false } })}
+          >
+            Don&apos;t allow
+          </button>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default SettingsProposal;
@@ -0,0 +1,42 @@
+import * as React from "react";
+import { TimerContext } from "./TimerProvider";
+import { TurnTimer as Timer } from "./AppStateProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  timer: Timer | null;
+  name: string;
+}
+
+const TurnTimer = (props: IProps): JSX.Element => {
+  const { setInterval, clearInterval } = React.useContext(TimerContext);
+  const [now, setNow] = React.useState(performance.now());
+  React.useEffect(() => {
+    const interval = setInterval(() => setNow(performance.now()), 250);
+    return () => clearInterval(interval);
+  }, []);
+
+  if (props.timer === null || "Initialize" in props.state) {
+    return null;
+  }
+  const player = propagatedState(props.state).players.find(
+    (p) => p.id === props.timer.playerId
+  );
+  if (player === undefined) {
+    return null;
+  }
+  const { deadline, pausedWithMs } = props.timer;
+  const remainingMs = pausedWithMs ?? deadline - now;
+  const remaining = Math.max(0, Math.ceil(remainingMs / 1000));
+  const who = player.name === props.name ? "You have" : `${player.name} has`;
+  return (
+    <p>
+      {who} {remaining} second{remaining === 1 ? "" : "s"} left to act
+      {pausedWithMs !== null ? " (paused)" : ""}.
+    </p>
+  );
+};
+
+export default TurnTimer;
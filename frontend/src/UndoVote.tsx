@@ -0,0 +1,67 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+import { GameState } from "./gen-types";
+import propagatedState from "./util/propagatedState";
+
+interface IProps {
+  state: GameState;
+  name: string;
+}
+
+const UndoVote = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const propagated = propagatedState(props.state);
+  const me = propagated.players.find((p) => p.name === props.name);
+  if (me === undefined) {
+    return null;
+  }
+
+  const vote = propagated.undo_vote;
+  if (vote === undefined || vote === null) {
+    return (
+      <div className="reset-block">
+        <a
+          href={window.location.href}
+          onClick={(evt) => {
+            evt.preventDefault();
+            send({ Action: "RequestUndo" });
+          }}
+          title="Ask everyone else to let you take back your last play or bid"
+        >
+          Undo my last move
+        </a>
+      </div>
+    );
+  }
+
+  const requester = propagated.players.find((p) => p.id === vote.requester);
+  const waiting = propagated.players.filter(
+    (p) => p.id !== vote.requester && !vote.approvals.includes(p.id)
+  );
+  return (
+    <div className="reset-block">
+      <p>
+        {requester !== undefined ? requester.name : "Someone"} wants to undo
+        their last move (waiting on {waiting.map((p) => p.name).join(", ")})
+      </p>
+      {me.id === vote.requester || vote.approvals.includes(me.id) ? null : (
+        <>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnUndo: true } })}
+          >
+            Allow
+          </button>
+          <button
+            className="normal"
+            onClick={() => send({ Action: { VoteOnUndo: false } })}
+          >
+            Don&apos;t allow
+          </button>
+        </>
+      )}
+    </div>
+  );
+};
+
+export default UndoVote;
@@ -10,15 +10,28 @@ import {
   SuitGroup,
   DecomposeTrickFormatRequest,
   DecomposedTrickFormat,
+  FindLegalPlaysRequest,
+  GetHintsRequest,
+  AnnotatedHint,
+  CheckPuzzleAttemptRequest,
+  PuzzleResult,
   CanPlayCardsRequest,
   ExplainScoringRequest,
   ExplainScoringResponse,
   NextThresholdReachableRequest,
+  NextThresholdReachability,
   ComputeScoreRequest,
   ComputeScoreResponse,
+  ComputeKittyBonusRequest,
+  ComputeKittyBonusResponse,
   GameMessage,
   CardInfoRequest,
   CardInfo,
+  GameState,
+  StateDiffOp,
+  CreateLocalGameRequest,
+  LocalGameActionRequest,
+  LocalGameResponse,
 } from "./gen-types";
 
 interface Context {
@@ -32,13 +45,27 @@ interface Context {
   decomposeTrickFormat: (
     req: DecomposeTrickFormatRequest
   ) => DecomposedTrickFormat[];
+  findLegalPlays: (req: FindLegalPlaysRequest) => string[][];
+  getHints: (req: GetHintsRequest) => AnnotatedHint[];
+  checkPuzzleAttempt: (req: CheckPuzzleAttemptRequest) => PuzzleResult;
   canPlayCards: (req: CanPlayCardsRequest) => boolean;
   explainScoring: (req: ExplainScoringRequest) => ExplainScoringResponse;
-  nextThresholdReachable: (req: NextThresholdReachableRequest) => boolean;
+  nextThresholdReachable: (
+    req: NextThresholdReachableRequest
+  ) => NextThresholdReachability;
   computeScore: (req: ComputeScoreRequest) => ComputeScoreResponse;
+  computeKittyBonus: (
+    req: ComputeKittyBonusRequest
+  ) => ComputeKittyBonusResponse;
   computeDeckLen: (req: Deck[]) => number;
   getCardInfo: (req: CardInfoRequest) => CardInfo;
   decodeWireFormat: (req: Uint8Array) => GameMessage;
+  zstdDictionaryIds: () => number[];
+  applyStateDiff: (state: GameState, diff: StateDiffOp[]) => GameState;
+  createLocalGame: (req: CreateLocalGameRequest) => LocalGameResponse;
+  localGameAct: (req: LocalGameActionRequest) => LocalGameResponse;
+  localGameStepBots: (handle: number) => LocalGameResponse;
+  dropLocalGame: (handle: number) => void;
 }
 
 export const WasmContext = React.createContext<Context>({
@@ -46,9 +73,26 @@ export const WasmContext = React.createContext<Context>({
   findValidBids: (_) => [],
   sortAndGroupCards: (_) => [],
   decomposeTrickFormat: (_) => [],
+  findLegalPlays: (_) => [],
+  getHints: (_) => [],
+  checkPuzzleAttempt: (_) => {
+    throw new Error("cannot check puzzle attempt");
+  },
   canPlayCards: (_) => false,
-  explainScoring: (_) => ({ results: [], step_size: 0, total_points: 0 }),
-  nextThresholdReachable: (_) => true,
+  explainScoring: (_) => ({
+    results: [],
+    shutout_results: [],
+    kitty_multipliers: [],
+    step_size: 0,
+    total_points: 0,
+    explanations: [],
+  }),
+  nextThresholdReachable: (_) => ({
+    reachable: true,
+    threshold: 0,
+    points_needed: 0,
+    remaining_points: 0,
+  }),
   computeScore: (_) => ({
     score: {
       landlord_won: true,
@@ -58,6 +102,7 @@ export const WasmContext = React.createContext<Context>({
     },
     next_threshold: 0,
   }),
+  computeKittyBonus: (_) => ({ kitty_points: 0, multiplier: 0, bonus: 0 }),
   computeDeckLen: (_) => 0,
   getCardInfo: (req) => {
     throw new Error("cannot get card info");
@@ -65,6 +110,20 @@ export const WasmContext = React.createContext<Context>({
   decodeWireFormat: (_) => {
     throw new Error("cannot decode wire format");
   },
+  zstdDictionaryIds: () => [],
+  applyStateDiff: (_, __) => {
+    throw new Error("cannot apply state diff");
+  },
+  createLocalGame: (_) => {
+    throw new Error("cannot create local game");
+  },
+  localGameAct: (_) => {
+    throw new Error("cannot act in local game");
+  },
+  localGameStepBots: (_) => {
+    throw new Error("cannot step bots in local game");
+  },
+  dropLocalGame: (_) => {},
 });
 
 export default WasmContext;
@@ -31,6 +31,15 @@ const ShengjiProvider = (props: IProps): JSX.Element => {
         decomposeTrickFormat: (req) => {
           return Shengji.decompose_trick_format(req).results;
         },
+        findLegalPlays: (req) => {
+          return Shengji.find_legal_plays(req).plays;
+        },
+        getHints: (req) => {
+          return Shengji.get_hints(req).hints;
+        },
+        checkPuzzleAttempt: (req) => {
+          return Shengji.check_puzzle_attempt(req);
+        },
         canPlayCards: (req) => {
           return Shengji.can_play_cards(req).playable;
         },
@@ -43,6 +52,9 @@ const ShengjiProvider = (props: IProps): JSX.Element => {
         computeScore: (req) => {
           return Shengji.compute_score(req);
         },
+        computeKittyBonus: (req) => {
+          return Shengji.compute_kitty_bonus(req);
+        },
         computeDeckLen: (req) => {
           return Shengji.compute_deck_len(req);
         },
@@ -50,7 +62,25 @@ const ShengjiProvider = (props: IProps): JSX.Element => {
           return Shengji.get_card_info(req);
         },
         decodeWireFormat: (req) => {
-          return JSON.parse(Shengji.zstd_decompress(req));
+          return JSON.parse(Shengji.decode_wire_format(req));
+        },
+        zstdDictionaryIds: () => {
+          return Array.from(Shengji.zstd_dictionary_ids());
+        },
+        applyStateDiff: (state, diff) => {
+          return Shengji.apply_state_diff({ state, diff });
+        },
+        createLocalGame: (req) => {
+          return Shengji.create_local_game(req);
+        },
+        localGameAct: (req) => {
+          return Shengji.local_game_act(req);
+        },
+        localGameStepBots: (handle) => {
+          return Shengji.local_game_step_bots(handle);
+        },
+        dropLocalGame: (handle) => {
+          Shengji.drop_local_game(handle);
         },
       }}
     >
@@ -0,0 +1,50 @@
+import * as React from "react";
+import { WebsocketContext } from "./WebsocketProvider";
+
+interface IProps {
+  webhookUrl: string | null | undefined;
+}
+
+const WebhookSettings = (props: IProps): JSX.Element => {
+  const { send } = React.useContext(WebsocketContext);
+  const [url, setUrl] = React.useState<string>("");
+
+  const setWebhookUrl = (url: string | null): void => {
+    send({ Action: { SetWebhookUrl: url } });
+    setUrl("");
+  };
+
+  return (
+    <p>
+      Webhook:{" "}
+      {props.webhookUrl !== undefined && props.webhookUrl !== null ? (
+        <>
+          <code>{props.webhookUrl}</code>{" "}
+          <button onClick={() => setWebhookUrl(null)}>remove</button>
+        </>
+      ) : (
+        <form
+          style={{ display: "inline" }}
+          onSubmit={(evt) => {
+            evt.preventDefault();
+            if (url.length > 0) {
+              setWebhookUrl(url);
+            }
+          }}
+        >
+          <input
+            type="text"
+            style={{ width: "200px" }}
+            value={url}
+            onChange={(evt) => setUrl(evt.target.value)}
+            placeholder="http://... to notify about games"
+            title="Receives a JSON payload whenever a game starts or finishes"
+          />
+          <input type="submit" value="set" />
+        </form>
+      )}
+    </p>
+  );
+};
+
+export default WebhookSettings;
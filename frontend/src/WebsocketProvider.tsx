@@ -4,6 +4,7 @@ import websocketHandler from "./websocketHandler";
 import { TimerContext } from "./TimerProvider";
 import memoize from "./memoize";
 import WasmContext from "./WasmContext";
+import { GameState } from "./gen-types";
 
 interface Context {
   send: (value: any) => void;
@@ -72,9 +73,13 @@ const getBlobArrayBuffer: () => IBlobToArrayBufferQueue = memoize(() => {
   };
 });
 
+// Rooms can be hosted by a different server than the one we first connected
+// to, in which case we're told where to go instead.
+const redirectKey = (roomName: string): string => `websocket_url_${roomName}`;
+
 const WebsocketProvider: React.FunctionComponent<IProps> = (props: IProps) => {
   const { state, updateState } = React.useContext(AppStateContext);
-  const { decodeWireFormat } = React.useContext(WasmContext);
+  const { decodeWireFormat, applyStateDiff } = React.useContext(WasmContext);
   const { setTimeout, clearTimeout } = React.useContext(TimerContext);
   const [timer, setTimer] = React.useState<number | null>(null);
   const [websocket, setWebsocket] = React.useState<WebSocket | null>(null);
@@ -108,8 +113,13 @@ const WebsocketProvider: React.FunctionComponent<IProps> = (props: IProps) => {
 
   React.useEffect(() => {
     const runtimeWebsocketHost = (window as any)._WEBSOCKET_HOST;
+    const redirectedHost = window.sessionStorage.getItem(
+      redirectKey(stateRef.current.roomName)
+    );
     const uri =
-      runtimeWebsocketHost !== undefined && runtimeWebsocketHost !== null
+      redirectedHost !== null
+        ? redirectedHost
+        : runtimeWebsocketHost !== undefined && runtimeWebsocketHost !== null
         ? runtimeWebsocketHost
         : (location.protocol === "https:" ? "wss://" : "ws://") +
           location.host +
@@ -118,6 +128,9 @@ const WebsocketProvider: React.FunctionComponent<IProps> = (props: IProps) => {
 
     const ws = new WebSocket(uri);
     setWebsocket(ws);
+    // Diffs apply to the last state we received, which may not have made it
+    // into the app state yet.
+    let lastGameState: GameState | null = null;
 
     ws.addEventListener("open", () =>
       updateStateRef.current({ connected: true, everConnected: true })
@@ -132,9 +145,22 @@ const WebsocketProvider: React.FunctionComponent<IProps> = (props: IProps) => {
       setTimerRef.current(null);
 
       const f = (buf: ArrayBuffer): void => {
-        const message = decodeWireFormat(new Uint8Array(buf));
+        let message = decodeWireFormat(new Uint8Array(buf));
+        if ("State" in message) {
+          lastGameState = message.State.state;
+        } else if ("StateDiff" in message && lastGameState !== null) {
+          lastGameState = applyStateDiff(lastGameState, message.StateDiff.diff);
+          message = { State: { state: lastGameState } };
+        }
         if ("Kicked" in message) {
           ws.close();
+        } else if ("Redirect" in message) {
+          window.sessionStorage.setItem(
+            redirectKey(stateRef.current.roomName),
+            message.Redirect.websocket_url
+          );
+          ws.close();
+          window.location.reload();
         } else {
           updateStateRef.current({
             connected: true,
@@ -9,6 +9,11 @@ export type Action =
   | (
       | "CancelResetGame"
       | "ResetGame"
+      | "AddBot"
+      | "StopCoaching"
+      | "RequestPause"
+      | "VoteToResume"
+      | "RequestUndo"
       | "StartGame"
       | "DrawCard"
       | "RevealCard"
@@ -31,6 +36,28 @@ export type Action =
   | {
       SetChatLink: string | null;
     }
+  | {
+      SetWebhookUrl: string | null;
+    }
+  | {
+      StartKickVote: number;
+    }
+  | {
+      VoteOnKick: boolean;
+    }
+  | {
+      RequestToCoach: number;
+    }
+  | {
+      /**
+       * @minItems 2
+       * @maxItems 2
+       */
+      RespondToCoach: [number, boolean];
+    }
+  | {
+      VoteOnUndo: boolean;
+    }
   | {
       SetNumDecks: number | null;
     }
@@ -43,12 +70,21 @@ export type Action =
   | {
       SetFriendSelectionPolicy: FriendSelectionPolicy;
     }
+  | {
+      SetFriendSelectionRestrictions: FriendSelectionRestrictions;
+    }
   | {
       SetMultipleJoinPolicy: MultipleJoinPolicy;
     }
   | {
       SetFirstLandlordSelectionPolicy: FirstLandlordSelectionPolicy;
     }
+  | {
+      SetFirstDealLandlordPolicy: FirstDealLandlordPolicy;
+    }
+  | {
+      SetLandlordRotationPolicy: LandlordRotationPolicy;
+    }
   | {
       SetBidPolicy: BidPolicy;
     }
@@ -58,6 +94,12 @@ export type Action =
   | {
       SetJokerBidPolicy: JokerBidPolicy;
     }
+  | {
+      SetNoTrumpOnlyFrom: Number | null;
+    }
+  | {
+      SetJokersOnlyTrumpPolicy: JokersOnlyTrumpPolicy;
+    }
   | {
       SetHideLandlordsPoints: boolean;
     }
@@ -76,6 +118,9 @@ export type Action =
   | {
       SetMaxRank: MaxRank;
     }
+  | {
+      SetRankSequence: MaxRank[] | null;
+    }
   | {
       SetLandlord: number | null;
     }
@@ -85,9 +130,15 @@ export type Action =
   | {
       SetGameMode: GameModeSettings;
     }
+  | {
+      SetFixedPartnerships: boolean;
+    }
   | {
       SetAdvancementPolicy: AdvancementPolicy;
     }
+  | {
+      SetAdvancementGates: MaxRank[];
+    }
   | {
       SetGameScoringParameters: GameScoringParameters;
     }
@@ -106,6 +157,12 @@ export type Action =
   | {
       SetThrowEvaluationPolicy: ThrowEvaluationPolicy;
     }
+  | {
+      SetThrowTiePolicy: ThrowTiePolicy;
+    }
+  | {
+      SetTrickPlayPolicy: TrickPlayPolicy;
+    }
   | {
       SetPlayTakebackPolicy: PlayTakebackPolicy;
     }
@@ -115,6 +172,18 @@ export type Action =
   | {
       SetKittyTheftPolicy: KittyTheftPolicy;
     }
+  | {
+      SetKittyBuryPolicy: KittyBuryPolicy;
+    }
+  | {
+      SetMaxKittyPoints: number | null;
+    }
+  | {
+      SetTrickHistoryLength: number | null;
+    }
+  | {
+      SetSuitOrder: SuitOrder;
+    }
   | {
       SetGameShadowingPolicy: GameShadowingPolicy;
     }
@@ -124,15 +193,55 @@ export type Action =
   | {
       SetShouldRevealKittyAtEndOfGame: boolean;
     }
+  | {
+      SetRevealKittyCardByCard: boolean;
+    }
   | {
       SetHideThrowHaltingPlayer: boolean;
     }
+  | {
+      SetFaceDownDiscards: boolean;
+    }
   | {
       SetTractorRequirements: TractorRequirements;
     }
   | {
       SetGameVisibility: GameVisibility;
     }
+  | {
+      SetAttackingPointsVisibility: AttackingPointsVisibility;
+    }
+  | {
+      SetMatchTargetRank: MaxRank | null;
+    }
+  | {
+      SetRematchPolicy: RematchPolicy;
+    }
+  | {
+      SetTurnTimers: TurnTimers;
+    }
+  | {
+      SetSpectatorDelay: number;
+    }
+  | {
+      SetRanked: boolean;
+    }
+  | {
+      SetDealSeed: number | null;
+    }
+  | {
+      MergeSettings: {
+        [k: string]: unknown;
+      };
+    }
+  | {
+      ProposeSettings: {
+        [k: string]: unknown;
+      };
+    }
+  | {
+      VoteOnSettings: boolean;
+    }
   | {
       /**
        * @minItems 2
@@ -167,10 +276,40 @@ export type FriendSelectionPolicy =
   | "PointCardNotAllowed";
 export type MultipleJoinPolicy = "Unrestricted" | "NoDoubleJoin";
 export type FirstLandlordSelectionPolicy = "ByWinningBid" | "ByFirstBid";
+/**
+ * Who becomes the landlord in the first game if nobody bids.
+ */
+export type FirstDealLandlordPolicy =
+  | "Random"
+  | {
+      FirstToDrawCard: {
+        card: Card;
+        [k: string]: unknown;
+      };
+    }
+  | {
+      AssignedByHost: {
+        player: number;
+        [k: string]: unknown;
+      };
+    };
+/**
+ * Who starts the next game when the landlord's team loses. If the landlord's team wins, the next player on it always does.
+ */
+export type LandlordRotationPolicy =
+  | "NextOnTeam"
+  | "Clockwise"
+  | "WinnerOfLastTrick"
+  | "HighestBidder";
 export type BidPolicy =
-  | "JokerOrHigherSuit"
-  | "JokerOrGreaterLength"
-  | "GreaterLength";
+  | ("JokerOrHigherSuit" | "JokerOrGreaterLength" | "GreaterLength")
+  | {
+      GreaterLengthByMargin: {
+        margin: number;
+        [k: string]: unknown;
+      };
+    }
+  | "FirstBidWins";
 export type BidReinforcementPolicy =
   | "ReinforceWhileWinning"
   | "OverturnOrReinforceWhileWinning"
@@ -180,6 +319,23 @@ export type JokerBidPolicy =
   | "BothNumDecks"
   | "LJNumDecksHJNumDecksLessOne"
   | "Disabled";
+/**
+ * When a round is played with only the jokers as trump, with neither a trump suit nor a trump rank.
+ */
+export type JokersOnlyTrumpPolicy =
+  | "NoTrumpRankOnly"
+  | "BigJokerBid"
+  | "Always";
+/**
+ * The reason why a bid which the player holds the cards for can't be made.
+ */
+export type BidExclusionReason =
+  | "NoTrumpLevel"
+  | "JokerBidPolicy"
+  | "ReinforcementPolicy"
+  | "DoesNotOutbid"
+  | "NoTrumpOnlyLevel"
+  | "BiddingClosed";
 export type MaxRank = string;
 export type GameModeSettings =
   | "Tractor"
@@ -194,23 +350,93 @@ export type AdvancementPolicy =
   | "FullyUnrestricted"
   | "DefendPoints";
 export type BonusLevelPolicy =
-  | "NoBonusLevel"
-  | "BonusLevelForSmallerLandlordTeam";
-export type KittyPenalty = "Times" | "Power";
-export type KittyBidPolicy = "FirstCard" | "FirstCardOfLevelOrHighest";
+  | ("NoBonusLevel" | "BonusLevelForSmallerLandlordTeam")
+  | "ScaleByLandlordTeamSize";
+/**
+ * A game in which the landlord's team kept the attacking team from scoring.
+ */
+export type Shutout = "Small" | "Big";
+export type KittyPenalty = "Times" | "Power" | "Double" | "NoBonus";
+/**
+ * The languages which the engine can describe the game in.
+ */
+export type Language = "English" | "SimplifiedChinese";
+export type ScoringTeam = "Attacking" | "Defending" | "Winning";
+export type ScoringReason =
+  | "Points"
+  | "SmallTeamBonus"
+  | "TeamSizeScaling"
+  | "SmallShutout"
+  | "BigShutout"
+  | "BidDefense";
+export type KittyBidPolicy =
+  | "FirstCard"
+  | "FirstCardOfLevelOrHighest"
+  | "FirstSuitedCard";
 export type TrickDrawPolicy =
   | ("NoProtections" | "NoFormatBasedDraw")
   | "LongerTuplesProtected"
   | "OnlyDrawTractorOnTractor"
-  | "LongerTuplesProtectedAndOnlyDrawTractorOnTractor";
-export type ThrowPenalty = "None" | "TenPointsPerAttempt";
-export type ThrowEvaluationPolicy = "All" | "Highest" | "TrickUnitLength";
+  | "LongerTuplesProtectedAndOnlyDrawTractorOnTractor"
+  | "OnlyLargestThrownUnitDrawn";
+export type ThrowPenalty =
+  | ("None" | "TenPointsPerAttempt")
+  | {
+      PointsPerCard: {
+        points: number;
+        [k: string]: unknown;
+      };
+    };
+export type ThrowEvaluationPolicy =
+  | "All"
+  | "Highest"
+  | "TrickUnitLength"
+  | "AnyUnit";
+/**
+ * Which play wins when a subsequent play ties with the current winner.
+ */
+export type ThrowTiePolicy = "LeaderWins" | "FollowerWins";
+/**
+ * Restrictions on following a trick, beyond matching its format.
+ */
+export type TrickPlayPolicy = "AnyLegalPlay" | "MustBeatIfAble";
 export type PlayTakebackPolicy = "AllowPlayTakeback" | "NoPlayTakeback";
 export type BidTakebackPolicy = "AllowBidTakeback" | "NoBidTakeback";
 export type KittyTheftPolicy = "AllowKittyTheft" | "NoKittyTheft";
+/**
+ * Which cards the landlord may not bury in the bottom. Restricted cards can still be buried if the landlord has nothing else left to bury.
+ */
+export type KittyBuryPolicy =
+  | "Unrestricted"
+  | "NoPointCards"
+  | "NoTrumpCards"
+  | "NoPointOrTrumpCards";
+/**
+ * The order in which suits are displayed when cards are sorted.
+ */
+export type SuitOrder =
+  | "TrumpLast"
+  | "TrumpFirst"
+  | "FixedSuitsTrumpLast"
+  | "FixedSuitsTrumpFirst";
 export type GameShadowingPolicy = "AllowMultipleSessions" | "SingleSessionOnly";
 export type GameStartPolicy = "AllowAnyPlayer" | "AllowLandlordOnly";
 export type GameVisibility = "Public" | "Unlisted";
+/**
+ * Who can see the points won by the attacking team before the game ends.
+ */
+export type AttackingPointsVisibility =
+  | "Visible"
+  | "HiddenFromLandlordTeam"
+  | "HiddenFromAll";
+/**
+ * What happens to the players' ranks once a match has been won.
+ */
+export type RematchPolicy = "KeepRanks" | "ResetRanks";
+/**
+ * What the server does when a player runs out of time on their turn.
+ */
+export type TimeoutPolicy = "MarkIdle" | "AutoPlay";
 export type Card = string;
 export type TrickUnit =
   | {
@@ -227,6 +453,25 @@ export type TrickUnit =
         [k: string]: unknown;
       };
     };
+/**
+ * The shape of a unit, for describing it.
+ */
+export type UnitKind =
+  | {
+      size: number;
+      type: "Tuple";
+      [k: string]: unknown;
+    }
+  | {
+      size: number;
+      type: "Tractor";
+      [k: string]: unknown;
+    }
+  | {
+      sizes: number[];
+      type: "IrregularTractor";
+      [k: string]: unknown;
+    };
 export type Trump =
   | {
       Standard: {
@@ -249,6 +494,46 @@ export type EffectiveSuit =
   | "Spades"
   | "Hearts"
   | "Trump";
+export type PuzzleGoal =
+  | {
+      type: "WinTrick";
+      [k: string]: unknown;
+    }
+  | {
+      points: number;
+      type: "WinPoints";
+      [k: string]: unknown;
+    }
+  | {
+      points: number;
+      type: "ConcedeAtMost";
+      [k: string]: unknown;
+    };
+export type Hint =
+  | {
+      suit: EffectiveSuit;
+      type: "CanRuff";
+      [k: string]: unknown;
+    }
+  | {
+      points: number;
+      type: "TeamWinningTrick";
+      [k: string]: unknown;
+    }
+  | {
+      penalty: number;
+      type: "RiskyThrow";
+      [k: string]: unknown;
+    }
+  | {
+      remaining: number;
+      type: "DrawsLastTrumps";
+      [k: string]: unknown;
+    }
+  | {
+      type: "OthersOutOfTrump";
+      [k: string]: unknown;
+    };
 export type GameMessage =
   | {
       State: {
@@ -256,6 +541,15 @@ export type GameMessage =
         [k: string]: unknown;
       };
     }
+  | {
+      /**
+       * The changes since the last `State` (or `StateDiff`) sent to this client, which are usually much smaller than the whole state.
+       */
+      StateDiff: {
+        diff: StateDiffOp[];
+        [k: string]: unknown;
+      };
+    }
   | {
       Message: {
         from: string;
@@ -296,87 +590,307 @@ export type GameMessage =
         target: string;
         [k: string]: unknown;
       };
-    };
-export type GameState =
-  | {
-      Initialize: InitializePhase;
-    }
-  | {
-      Draw: DrawPhase;
     }
   | {
-      Exchange: ExchangePhase;
+      /**
+       * Sent to a player once they've joined, so that they can reclaim their seat if they reconnect.
+       */
+      Session: {
+        name: string;
+        player_id: number;
+        token: string;
+        [k: string]: unknown;
+      };
     }
   | {
-      Play: PlayPhase;
-    };
-export type GameMode =
-  | "Tractor"
-  | {
-      FindingFriends: {
-        friends: Friend[];
-        num_friends: number;
+      /**
+       * Sent to the rooms of a tournament's previous round once the players advancing from it have been seated at a new table.
+       */
+      TournamentTable: {
+        players: string[];
+        room_name: string;
+        round: number;
+        tournament_id: string;
         [k: string]: unknown;
       };
-    };
-export type MessageVariant =
-  | {
-      type: "ResetRequested";
-      [k: string]: unknown;
     }
   | {
-      type: "ResetCanceled";
-      [k: string]: unknown;
+      TournamentFinished: {
+        tournament_id: string;
+        winners: string[];
+        [k: string]: unknown;
+      };
     }
   | {
-      type: "ResettingGame";
-      [k: string]: unknown;
+      /**
+       * Sent to the rooms of a duplicate tournament's previous board once every table has been dealt the next one.
+       */
+      DuplicateBoard: {
+        board: number;
+        players: string[];
+        room_name: string;
+        tournament_id: string;
+        [k: string]: unknown;
+      };
     }
   | {
-      type: "StartingGame";
-      [k: string]: unknown;
+      /**
+       * How everyone in a duplicate tournament did compared to the players who sat in the same seat at the other tables, best first.
+       */
+      DuplicateReport: {
+        standings: DuplicateStanding[];
+        tournament_id: string;
+        [k: string]: unknown;
+      };
     }
   | {
-      points: number;
-      type: "TrickWon";
-      winner: number;
-      [k: string]: unknown;
+      /**
+       * How long the player whose turn it is has left to act, if anyone is being timed. The timer doesn't run while the game is paused.
+       */
+      TurnTimer: {
+        paused: boolean;
+        player_id?: number | null;
+        remaining_ms: number;
+        [k: string]: unknown;
+      };
     }
   | {
-      new_rank: MaxRank;
-      player: number;
-      type: "RankAdvanced";
-      [k: string]: unknown;
+      /**
+       * The room is hosted by another server, which the client should connect to instead.
+       */
+      Redirect: {
+        websocket_url: string;
+        [k: string]: unknown;
+      };
     }
   | {
-      player: number;
-      rank: MaxRank;
-      type: "AdvancementBlocked";
-      [k: string]: unknown;
+      /**
+       * The career statistics of a player in the room, sent when they join and after each game. Only claimed nicknames have them.
+       */
+      CareerStats: {
+        name: string;
+        stats: CareerCard;
+        [k: string]: unknown;
+      };
     }
   | {
-      landlord: number;
-      type: "NewLandlordForNextGame";
-      [k: string]: unknown;
+      /**
+       * A player in the room unlocked an achievement for the first time.
+       */
+      AchievementUnlocked: {
+        achievement: Achievement;
+        description: string;
+        name: string;
+        [k: string]: unknown;
+      };
     }
   | {
-      multiplier: number;
-      points: number;
-      type: "PointsInKitty";
-      [k: string]: unknown;
+      /**
+       * Something happened in the game which the client might want to call attention to. Sent just before the `State` it led to.
+       */
+      Notification: {
+        notification: Notification;
+        [k: string]: unknown;
+      };
     }
   | {
-      cards: Card[];
-      type: "EndOfGameKittyReveal";
-      [k: string]: unknown;
+      /**
+       * Sent every few seconds. The client should answer with a `UserMessage::Pong` carrying the same timestamp, or it'll be disconnected.
+       */
+      Ping: {
+        sent_at_ms: number;
+        [k: string]: unknown;
+      };
     }
   | {
-      player: number;
-      type: "JoinedGame";
-      [k: string]: unknown;
+      /**
+       * The round-trip times of the players connected to the room, as of their last `Pong`. Sent along with each `Ping`.
+       */
+      Latencies: {
+        latencies: PlayerLatency[];
+        [k: string]: unknown;
+      };
     }
   | {
-      game_shadowing_policy: GameShadowingPolicy;
+      /**
+       * The recent tricks of the game in progress, oldest first, in answer to a `UserMessage::RequestTrickHistory`.
+       */
+      TrickHistory: {
+        tricks: CompletedTrick[];
+        [k: string]: unknown;
+      };
+    };
+/**
+ * One change to a game state, as serialized to JSON.
+ */
+export type StateDiffOp =
+  | {
+      /**
+       * Replaces the value at `path`. If `path` ends in a new key of an object or one past the end of an array, the value is added instead.
+       */
+      Set: {
+        path: PathSegment[];
+        value: unknown;
+        [k: string]: unknown;
+      };
+    }
+  | {
+      /**
+       * Removes a key from an object.
+       */
+      Remove: {
+        path: PathSegment[];
+        [k: string]: unknown;
+      };
+    }
+  | {
+      /**
+       * Shortens the array at `path` to `len` elements.
+       */
+      Truncate: {
+        len: number;
+        path: PathSegment[];
+        [k: string]: unknown;
+      };
+    };
+/**
+ * A step into a JSON value: a key of an object, or an index into an array.
+ */
+export type PathSegment = string | number;
+/**
+ * A milestone which a player can reach over the course of a game.
+ */
+export type Achievement =
+  | "FiveCardThrow"
+  | "DefendedOnAce"
+  | "DefendersCaptured200";
+export type Notification =
+  | {
+      /**
+       * The points in the trick. Only sent if everyone can see the attacking team's points.
+       */
+      points?: number | null;
+      type: "TrickWon";
+      winner: number;
+      [k: string]: unknown;
+    }
+  | {
+      friend: number;
+      type: "FriendRevealed";
+      [k: string]: unknown;
+    }
+  | {
+      landlord_delta: number;
+      landlord_won: boolean;
+      non_landlord_delta: number;
+      non_landlords_points: number;
+      type: "ThresholdCrossed";
+      [k: string]: unknown;
+    }
+  | {
+      landlord?: number | null;
+      type: "LandlordChanged";
+      [k: string]: unknown;
+    }
+  | {
+      card: Card;
+      multiplier: number;
+      points: number;
+      type: "KittyCardRevealed";
+      [k: string]: unknown;
+    };
+export type GameState =
+  | {
+      Initialize: InitializePhase;
+    }
+  | {
+      Draw: DrawPhase;
+    }
+  | {
+      Exchange: ExchangePhase;
+    }
+  | {
+      Play: PlayPhase;
+    };
+export type GameMode =
+  | "Tractor"
+  | {
+      FindingFriends: {
+        friends: Friend[];
+        num_friends: number;
+        [k: string]: unknown;
+      };
+    };
+export type MessageVariant =
+  | {
+      type: "ResetRequested";
+      [k: string]: unknown;
+    }
+  | {
+      type: "ResetCanceled";
+      [k: string]: unknown;
+    }
+  | {
+      type: "ResettingGame";
+      [k: string]: unknown;
+    }
+  | {
+      type: "StartingGame";
+      [k: string]: unknown;
+    }
+  | {
+      points: number;
+      type: "TrickWon";
+      winner: number;
+      [k: string]: unknown;
+    }
+  | {
+      type: "TrickWonPointsHidden";
+      winner: number;
+      [k: string]: unknown;
+    }
+  | {
+      new_rank: MaxRank;
+      player: number;
+      type: "RankAdvanced";
+      [k: string]: unknown;
+    }
+  | {
+      player: number;
+      rank: MaxRank;
+      type: "AdvancementBlocked";
+      [k: string]: unknown;
+    }
+  | {
+      landlord: number;
+      type: "NewLandlordForNextGame";
+      [k: string]: unknown;
+    }
+  | {
+      multiplier: number;
+      points: number;
+      type: "PointsInKitty";
+      [k: string]: unknown;
+    }
+  | {
+      cards: Card[];
+      type: "EndOfGameKittyReveal";
+      [k: string]: unknown;
+    }
+  | {
+      card: Card;
+      multiplier: number;
+      points: number;
+      type: "KittyCardRevealed";
+      [k: string]: unknown;
+    }
+  | {
+      player: number;
+      type: "JoinedGame";
+      [k: string]: unknown;
+    }
+  | {
+      game_shadowing_policy: GameShadowingPolicy;
       player: number;
       type: "JoinedGameAgain";
       [k: string]: unknown;
@@ -392,11 +906,111 @@ export type MessageVariant =
       type: "LeftGame";
       [k: string]: unknown;
     }
+  | {
+      target: number;
+      type: "KickVoteStarted";
+      [k: string]: unknown;
+    }
+  | {
+      approve: boolean;
+      type: "KickVoteCast";
+      [k: string]: unknown;
+    }
+  | {
+      name: string;
+      type: "KickVotePassed";
+      [k: string]: unknown;
+    }
+  | {
+      target: number;
+      type: "KickVoteFailed";
+      [k: string]: unknown;
+    }
+  | {
+      name: string;
+      type: "Banned";
+      [k: string]: unknown;
+    }
+  | {
+      student: number;
+      type: "CoachingRequested";
+      [k: string]: unknown;
+    }
+  | {
+      coach: number;
+      type: "CoachingStarted";
+      [k: string]: unknown;
+    }
+  | {
+      coach: number;
+      type: "CoachingDeclined";
+      [k: string]: unknown;
+    }
+  | {
+      coach: number;
+      student: number;
+      type: "CoachingEnded";
+      [k: string]: unknown;
+    }
+  | {
+      type: "GamePaused";
+      [k: string]: unknown;
+    }
+  | {
+      type: "ResumeVoteCast";
+      [k: string]: unknown;
+    }
+  | {
+      type: "GameResumed";
+      [k: string]: unknown;
+    }
+  | {
+      type: "UndoRequested";
+      [k: string]: unknown;
+    }
+  | {
+      approve: boolean;
+      type: "UndoVoteCast";
+      [k: string]: unknown;
+    }
+  | {
+      requester: number;
+      type: "UndoApproved";
+      [k: string]: unknown;
+    }
+  | {
+      type: "UndoRejected";
+      [k: string]: unknown;
+    }
+  | {
+      changes: SettingChange[];
+      type: "SettingsProposed";
+      [k: string]: unknown;
+    }
+  | {
+      approve: boolean;
+      type: "SettingsProposalVoteCast";
+      [k: string]: unknown;
+    }
+  | {
+      changes: SettingChange[];
+      type: "SettingsProposalApproved";
+      [k: string]: unknown;
+    }
+  | {
+      type: "SettingsProposalRejected";
+      [k: string]: unknown;
+    }
   | {
       policy: AdvancementPolicy;
       type: "AdvancementPolicySet";
       [k: string]: unknown;
     }
+  | {
+      gates: MaxRank[];
+      type: "AdvancementGatesSet";
+      [k: string]: unknown;
+    }
   | {
       size?: number | null;
       type: "KittySizeSet";
@@ -407,6 +1021,11 @@ export type MessageVariant =
       type: "FriendSelectionPolicySet";
       [k: string]: unknown;
     }
+  | {
+      restrictions: FriendSelectionRestrictions;
+      type: "FriendSelectionRestrictionsSet";
+      [k: string]: unknown;
+    }
   | {
       policy: MultipleJoinPolicy;
       type: "MultipleJoinPolicySet";
@@ -417,6 +1036,22 @@ export type MessageVariant =
       type: "FirstLandlordSelectionPolicySet";
       [k: string]: unknown;
     }
+  | {
+      policy: FirstDealLandlordPolicy;
+      type: "FirstDealLandlordPolicySet";
+      [k: string]: unknown;
+    }
+  | {
+      card: Card;
+      player: number;
+      type: "FirstDealLandlordSelected";
+      [k: string]: unknown;
+    }
+  | {
+      policy: LandlordRotationPolicy;
+      type: "LandlordRotationPolicySet";
+      [k: string]: unknown;
+    }
   | {
       policy: BidPolicy;
       type: "BidPolicySet";
@@ -432,11 +1067,26 @@ export type MessageVariant =
       type: "JokerBidPolicySet";
       [k: string]: unknown;
     }
+  | {
+      level?: Number | null;
+      type: "NoTrumpOnlyFromSet";
+      [k: string]: unknown;
+    }
+  | {
+      policy: JokersOnlyTrumpPolicy;
+      type: "JokersOnlyTrumpPolicySet";
+      [k: string]: unknown;
+    }
   | {
       should_reveal: boolean;
       type: "ShouldRevealKittyAtEndOfGameSet";
       [k: string]: unknown;
     }
+  | {
+      card_by_card: boolean;
+      type: "RevealKittyCardByCardSet";
+      [k: string]: unknown;
+    }
   | {
       special_decks: Deck[];
       type: "SpecialDecksSet";
@@ -462,11 +1112,94 @@ export type MessageVariant =
       type: "KittyTheftPolicySet";
       [k: string]: unknown;
     }
+  | {
+      policy: KittyBuryPolicy;
+      type: "KittyBuryPolicySet";
+      [k: string]: unknown;
+    }
+  | {
+      length?: number | null;
+      type: "TrickHistoryLengthSet";
+      [k: string]: unknown;
+    }
+  | {
+      max?: number | null;
+      type: "MaxKittyPointsSet";
+      [k: string]: unknown;
+    }
+  | {
+      order: SuitOrder;
+      type: "SuitOrderSet";
+      [k: string]: unknown;
+    }
   | {
       type: "GameVisibilitySet";
       visibility: GameVisibility;
       [k: string]: unknown;
     }
+  | {
+      type: "AttackingPointsVisibilitySet";
+      visibility: AttackingPointsVisibility;
+      [k: string]: unknown;
+    }
+  | {
+      rank?: MaxRank | null;
+      type: "MatchTargetRankSet";
+      [k: string]: unknown;
+    }
+  | {
+      policy: RematchPolicy;
+      type: "RematchPolicySet";
+      [k: string]: unknown;
+    }
+  | {
+      timers: TurnTimers;
+      type: "TurnTimersSet";
+      [k: string]: unknown;
+    }
+  | {
+      secs: number;
+      type: "SpectatorDelaySet";
+      [k: string]: unknown;
+    }
+  | {
+      ranked: boolean;
+      type: "RankedSet";
+      [k: string]: unknown;
+    }
+  | {
+      fixed: boolean;
+      type: "DealSeedSet";
+      [k: string]: unknown;
+    }
+  | {
+      changes: SettingChange[];
+      type: "SettingsChanged";
+      [k: string]: unknown;
+    }
+  | {
+      type: "TimedOut";
+      [k: string]: unknown;
+    }
+  | {
+      type: "MarkedIdle";
+      [k: string]: unknown;
+    }
+  | {
+      enabled: boolean;
+      type: "WebhookSet";
+      [k: string]: unknown;
+    }
+  | {
+      standings: MatchStanding[];
+      type: "MatchComplete";
+      winners: number[];
+      [k: string]: unknown;
+    }
+  | {
+      type: "RanksResetForRematch";
+      [k: string]: unknown;
+    }
   | {
       type: "TookBackPlay";
       [k: string]: unknown;
@@ -521,6 +1254,11 @@ export type MessageVariant =
       type: "SetMaxRank";
       [k: string]: unknown;
     }
+  | {
+      sequence?: MaxRank[] | null;
+      type: "RankSequenceSet";
+      [k: string]: unknown;
+    }
   | {
       card: Card;
       count: number;
@@ -552,6 +1290,16 @@ export type MessageVariant =
       type: "ThrowEvaluationPolicySet";
       [k: string]: unknown;
     }
+  | {
+      policy: ThrowTiePolicy;
+      type: "ThrowTiePolicySet";
+      [k: string]: unknown;
+    }
+  | {
+      policy: TrickPlayPolicy;
+      type: "TrickPlayPolicySet";
+      [k: string]: unknown;
+    }
   | {
       policy: PlayTakebackPolicy;
       type: "PlayTakebackPolicySet";
@@ -605,9 +1353,26 @@ export type MessageVariant =
       type: "BonusLevelEarned";
       [k: string]: unknown;
     }
+  | {
+      levels: number;
+      shutout: Shutout;
+      type: "ShutoutBonusEarned";
+      [k: string]: unknown;
+    }
+  | {
+      bid_size: number;
+      landlord_won: boolean;
+      levels: number;
+      type: "BidDefenseBonusEarned";
+      [k: string]: unknown;
+    }
   | {
       landlord_won: boolean;
       non_landlords_points: number;
+      /**
+       * How long each player took over their bids and plays.
+       */
+      pace?: PaceSummary;
       type: "EndOfGameSummary";
       [k: string]: unknown;
     }
@@ -616,6 +1381,22 @@ export type MessageVariant =
       type: "HideThrowHaltingPlayer";
       [k: string]: unknown;
     }
+  | {
+      face_down: boolean;
+      type: "FaceDownDiscardsSet";
+      [k: string]: unknown;
+    }
+  | {
+      cards: Card[];
+      player: number;
+      type: "FaceDownCardsRevealed";
+      [k: string]: unknown;
+    }
+  | {
+      set: boolean;
+      type: "FixedPartnershipsSet";
+      [k: string]: unknown;
+    }
   | {
       tractor_requirements: TractorRequirements;
       type: "TractorRequirementsChanged";
@@ -624,38 +1405,41 @@ export type MessageVariant =
 
 export interface _Combined {
   action: Action;
-  can_play_cards_request: CanPlayCardsRequest;
-  can_play_cards_response: CanPlayCardsResponse;
-  card_info: CardInfo;
-  card_info_request: CardInfoRequest;
-  compute_score_request: ComputeScoreRequest;
-  compute_score_response: ComputeScoreResponse;
-  decompose_trick_format_request: DecomposeTrickFormatRequest;
-  decompose_trick_format_response: DecomposeTrickFormatResponse;
-  decomposed_trick_format: DecomposedTrickFormat;
-  explain_scoring_request: ExplainScoringRequest;
-  explain_scoring_response: ExplainScoringResponse;
-  find_valid_bids_request: FindValidBidsRequest;
-  find_valid_bids_response: FindValidBidsResult;
-  find_viable_plays_request: FindViablePlaysRequest;
-  find_viable_plays_response: FindViablePlaysResult;
-  found_viable_play: FoundViablePlay;
   game_message: GameMessage;
-  next_threshold_reachable_request: NextThresholdReachableRequest;
-  score_segment: ScoreSegment;
-  sort_and_group_cards_request: SortAndGroupCardsRequest;
-  sort_and_group_cards_response: SortAndGroupCardsResponse;
-  suit_group: SuitGroup;
+  wasm: WasmTypes;
   [k: string]: unknown;
 }
 export interface Deck {
   exclude_big_joker: boolean;
+  /**
+   * Numbers which are removed in every suit, in addition to those below `min`.
+   */
+  exclude_numbers?: Number[];
   exclude_small_joker: boolean;
+  /**
+   * Suits which are removed from the deck entirely.
+   */
+  exclude_suits?: Suit[];
+  /**
+   * The lowest number included in the deck; cards below it are removed in every suit.
+   */
   min: Number;
   [k: string]: unknown;
 }
 export interface GameScoringParameters {
+  /**
+   * Additional levels for the winning team for each card in the landlord's bid beyond the first, so that bigger bids carry bigger swings in both directions.
+   */
+  bid_defense_bonus?: number;
+  /**
+   * Additional levels for the landlord's team when the attacking team wins no tricks. Replaces the small shutout bonus.
+   */
+  big_shutout_bonus?: number;
   bonus_level_policy: BonusLevelPolicy;
+  /**
+   * An explicit table of point thresholds to use instead of the step-based windows above.
+   */
+  custom_thresholds?: ScoringThreshold[] | null;
   /**
    * Number of steps (as a fraction of the overall number in the deck) that control is turned over, but neither side goes up a level.
    */
@@ -670,6 +1454,10 @@ export interface GameScoringParameters {
   step_adjustments: {
     [k: string]: number;
   };
+  /**
+   * Additional levels for the landlord's team when the attacking team wins no points.
+   */
+  small_shutout_bonus?: number;
   /**
    * Number of points per "step" in the deck.
    */
@@ -677,6 +1465,15 @@ export interface GameScoringParameters {
   truncate_zero_crossing_window: boolean;
   [k: string]: unknown;
 }
+/**
+ * A row of a user-defined scoring table. From `points` up to the next row's threshold, the landlord's team goes up `landlord_delta` levels and the attacking team goes up `non_landlord_delta` levels. The landlord's team wins the game whenever `landlord_delta` is nonzero.
+ */
+export interface ScoringThreshold {
+  landlord_delta: number;
+  non_landlord_delta: number;
+  points: number;
+  [k: string]: unknown;
+}
 export interface TractorRequirements {
   /**
    * The minimum number of cards in each unit of the tractor
@@ -686,6 +1483,10 @@ export interface TractorRequirements {
    * The minimum length of the tractor
    */
   min_length: number;
+  /**
+   * Whether the trump rank breaks up tractors in the rest of its suit, e.g. whether 7-9 is not a tractor when 8 is trump. By default, the removed rank is a gap that tractors can span.
+   */
+  trump_rank_breaks_tractors?: boolean;
   [k: string]: unknown;
 }
 export interface FriendSelection {
@@ -701,12 +1502,51 @@ export interface OrderedCard {
   trump: Trump;
   [k: string]: unknown;
 }
+export interface ApplyStateDiffRequest {
+  diff: StateDiffOp[];
+  state: unknown;
+  [k: string]: unknown;
+}
+export interface CreateLocalGameRequest {
+  name: string;
+  /**
+   * How many bots to seat alongside the player.
+   */
+  num_bots: number;
+  /**
+   * The settings to start from, e.g. those of a room the player has played in. The players in them are ignored.
+   */
+  settings?: PropagatedState | null;
+  [k: string]: unknown;
+}
+export interface LocalGameActionRequest {
+  action: Action;
+  handle: number;
+  [k: string]: unknown;
+}
+export interface LocalGameResponse {
+  /**
+   * Whether anything happened. Bots which have nothing to do don't act.
+   */
+  acted: boolean;
+  handle: number;
+  messages: GameMessage[];
+  player_id: number;
+  /**
+   * The state of the game, as the player can see it.
+   */
+  state: GameState;
+  [k: string]: unknown;
+}
 export interface CanPlayCardsRequest {
   cards: Card[];
   hands: Hands;
   id: number;
+  throw_eval_policy?: ThrowEvaluationPolicy & string;
+  throw_tie_policy?: ThrowTiePolicy & string;
   trick: Trick;
   trick_draw_policy: TrickDrawPolicy;
+  trick_play_policy?: TrickPlayPolicy & string;
   [k: string]: unknown;
 }
 export interface Hands {
@@ -741,12 +1581,59 @@ export interface PlayedCards {
 }
 export interface TrickFormat {
   suit: EffectiveSuit;
+  tractor_requirements?: TractorRequirements;
   trump: Trump;
   units: TrickUnit[];
   [k: string]: unknown;
 }
-export interface CanPlayCardsResponse {
-  playable: boolean;
+export interface CanPlayCardsResponse {
+  playable: boolean;
+  [k: string]: unknown;
+}
+export interface SimulateTrickRequest {
+  assumptions: OpponentAssumptions;
+  cards: Card[];
+  hands: Hands;
+  id: number;
+  num_simulations: number;
+  teammates: number[];
+  throw_eval_policy: ThrowEvaluationPolicy;
+  throw_tie_policy?: ThrowTiePolicy & string;
+  tractor_requirements: TractorRequirements;
+  trick: Trick;
+  trick_draw_policy: TrickDrawPolicy;
+  trick_play_policy?: TrickPlayPolicy & string;
+  [k: string]: unknown;
+}
+/**
+ * Assumptions about the cards held by the players who have yet to play in the trick.
+ */
+export interface OpponentAssumptions {
+  /**
+   * The number of cards held by each of the players who have yet to play.
+   */
+  hand_sizes: {
+    [k: string]: number;
+  };
+  /**
+   * Cards which the caller hasn't seen, and which may be held by any of the other players.
+   */
+  unseen_cards: Card[];
+  [k: string]: unknown;
+}
+export interface TrickSimulationResult {
+  /**
+   * The average number of points won by the opposing team.
+   */
+  expected_points_conceded: number;
+  /**
+   * The number of simulated completions of the trick.
+   */
+  num_simulations: number;
+  /**
+   * The fraction of simulations in which the caller's team won the trick.
+   */
+  win_probability: number;
   [k: string]: unknown;
 }
 export interface CardInfo {
@@ -765,10 +1652,20 @@ export interface CardInfoRequest {
   [k: string]: unknown;
 }
 export interface ComputeScoreRequest {
+  bid_size?: number;
   decks: Deck[];
+  landlord_team_size: LandlordTeamSize;
   non_landlord_points: number;
   params: GameScoringParameters;
-  smaller_landlord_team_size: boolean;
+  shutout?: Shutout | null;
+  [k: string]: unknown;
+}
+/**
+ * How many players ended up on the landlord's team, against how many the game mode called for.
+ */
+export interface LandlordTeamSize {
+  actual: number;
+  expected: number;
   [k: string]: unknown;
 }
 export interface ComputeScoreResponse {
@@ -776,6 +1673,18 @@ export interface ComputeScoreResponse {
   score: GameScoreResult;
   [k: string]: unknown;
 }
+export interface ComputeKittyBonusRequest {
+  kitty: Card[];
+  kitty_penalty: KittyPenalty;
+  winning_unit: TrickUnit;
+  [k: string]: unknown;
+}
+export interface ComputeKittyBonusResponse {
+  bonus: number;
+  kitty_points: number;
+  multiplier: number;
+  [k: string]: unknown;
+}
 export interface GameScoreResult {
   landlord_bonus: boolean;
   landlord_delta: number;
@@ -785,6 +1694,7 @@ export interface GameScoreResult {
 }
 export interface DecomposeTrickFormatRequest {
   hands: Hands;
+  language?: Language & string;
   player_id: number;
   trick_draw_policy: TrickDrawPolicy;
   trick_format: TrickFormat;
@@ -799,6 +1709,98 @@ export interface DecomposedTrickFormat {
   format: UnitLike[];
   more_than_one: boolean;
   playable: Card[];
+  units: UnitDescription[];
+  [k: string]: unknown;
+}
+/**
+ * A unit, in a form which can be described in any supported language.
+ */
+export interface UnitDescription {
+  kind: UnitKind;
+  /**
+   * The number of adjacent tuples in the unit.
+   */
+  length: number;
+  /**
+   * The highest card in the unit, when it's made up of specific cards.
+   */
+  top_card?: Card | null;
+  [k: string]: unknown;
+}
+export interface FindLegalPlaysRequest {
+  hands: Hands;
+  player_id: number;
+  throw_eval_policy?: ThrowEvaluationPolicy & string;
+  throw_tie_policy?: ThrowTiePolicy & string;
+  trick: Trick;
+  trick_draw_policy: TrickDrawPolicy;
+  trick_play_policy?: TrickPlayPolicy & string;
+  [k: string]: unknown;
+}
+export interface FindLegalPlaysResponse {
+  plays: Card[][];
+  [k: string]: unknown;
+}
+export interface GetHintsRequest {
+  player_id: number;
+  selected: Card[];
+  /**
+   * The play phase, as seen by `player_id`.
+   */
+  state: PlayPhase;
+  [k: string]: unknown;
+}
+export interface GetHintsResponse {
+  hints: AnnotatedHint[];
+  [k: string]: unknown;
+}
+export interface AnnotatedHint {
+  description: string;
+  hint: Hint;
+  [k: string]: unknown;
+}
+export interface CheckPuzzleAttemptRequest {
+  cards: Card[];
+  puzzle: Puzzle;
+  [k: string]: unknown;
+}
+export interface Puzzle {
+  goal: PuzzleGoal;
+  /**
+   * The game when the puzzle starts, with every hand visible. It has to be the solver's turn.
+   */
+  position: PlayPhase;
+  solver: number;
+  [k: string]: unknown;
+}
+export interface PuzzleResult {
+  /**
+   * How the rest of the table could play to keep the goal from being met, if the attempt doesn't solve the puzzle.
+   */
+  refutation?: PuzzlePlay[] | null;
+  solved: boolean;
+  [k: string]: unknown;
+}
+/**
+ * A play made by one of the players in the rest of the trick.
+ */
+export interface PuzzlePlay {
+  cards: Card[];
+  player: number;
+  [k: string]: unknown;
+}
+export interface ParsePlayNotationRequest {
+  notation: string;
+  /**
+   * If provided, the notation is also parsed into specific cards.
+   */
+  trump?: Trump | null;
+  [k: string]: unknown;
+}
+export interface ParsePlayNotationResponse {
+  description: string;
+  format: UnitLike[];
+  units?: TrickUnit[] | null;
   [k: string]: unknown;
 }
 export interface UnitLike {
@@ -807,12 +1809,17 @@ export interface UnitLike {
 }
 export interface ExplainScoringRequest {
   decks: Deck[];
+  kitty_penalty?: KittyPenalty & string;
+  landlord_team_size: LandlordTeamSize;
+  language?: Language & string;
   params: GameScoringParameters;
-  smaller_landlord_team_size: boolean;
   [k: string]: unknown;
 }
 export interface ExplainScoringResponse {
+  explanations: AnnotatedScoringExplanation[];
+  kitty_multipliers: KittyMultiplier[];
   results: ScoreSegment[];
+  shutout_results: ShutoutSegment[];
   step_size: number;
   total_points: number;
   [k: string]: unknown;
@@ -822,6 +1829,34 @@ export interface ScoreSegment {
   results: GameScoreResult;
   [k: string]: unknown;
 }
+export interface ShutoutSegment {
+  results: GameScoreResult;
+  shutout: Shutout;
+  [k: string]: unknown;
+}
+export interface KittyMultiplier {
+  largest_unit_size: number;
+  multiplier: number;
+  [k: string]: unknown;
+}
+export interface AnnotatedScoringExplanation {
+  description: string;
+  explanation: ScoringExplanation;
+  [k: string]: unknown;
+}
+/**
+ * One outcome of the scoring rules, in a form which can be described in any supported language.
+ */
+export interface ScoringExplanation {
+  delta: number;
+  reason: ScoringReason;
+  team: ScoringTeam;
+  /**
+   * The attacking team's points from which this applies, if it depends on them.
+   */
+  threshold?: number | null;
+  [k: string]: unknown;
+}
 export interface FindValidBidsRequest {
   bid_policy: BidPolicy;
   bid_reinforcement_policy: BidReinforcementPolicy;
@@ -831,6 +1866,7 @@ export interface FindValidBidsRequest {
   id: number;
   joker_bid_policy: JokerBidPolicy;
   landlord?: number | null;
+  no_trump_only_from?: Number | null;
   num_decks: number;
   players: Player[];
   [k: string]: unknown;
@@ -850,11 +1886,18 @@ export interface Player {
   [k: string]: unknown;
 }
 export interface FindValidBidsResult {
+  excluded: ExcludedBid[];
   results: Bid[];
   [k: string]: unknown;
 }
+export interface ExcludedBid {
+  bid: Bid;
+  reason: BidExclusionReason;
+  [k: string]: unknown;
+}
 export interface FindViablePlaysRequest {
   cards: Card[];
+  language?: Language & string;
   tractor_requirements: TractorRequirements;
   trump: Trump;
   [k: string]: unknown;
@@ -866,6 +1909,12 @@ export interface FindViablePlaysResult {
 export interface FoundViablePlay {
   description: string;
   grouping: TrickUnit[];
+  units: UnitDescription[];
+  [k: string]: unknown;
+}
+export interface PollFindViablePlaysResponse {
+  done: boolean;
+  results: FoundViablePlay[];
   [k: string]: unknown;
 }
 export interface InitializePhase {
@@ -873,13 +1922,46 @@ export interface InitializePhase {
   [k: string]: unknown;
 }
 export interface PropagatedState {
+   * How many seconds behind the table spectators see the game, so that they can't coach the players.
+   * Players who ran out of time, until they next take an action.
+   * Players whose moves are made by the server.
+   * Receives a JSON payload as games in the room start and finish. Only the host can see it, since it may embed a secret.
+   * The first user to join the room, who can ban other users from it.
+   * Users who are only watching. Unlike observers, spectators never join the game, and can't see any player's hand.
+   * When set, the first team to defend a game at this rank wins the match.
+   */
+   */
+   */
+   */
+   */
+   */
+   */
+  /**
+  /**
+  /**
+  /**
+  /**
+  /**
+  /**
+  advancement_gates?: MaxRank[];
   advancement_policy?: AdvancementPolicy & string;
+  attacking_points_visibility?: AttackingPointsVisibility & string;
   bid_policy?: BidPolicy & string;
   bid_reinforcement_policy?: BidReinforcementPolicy & string;
   bid_takeback_policy?: BidTakebackPolicy & string;
+  bots?: number[];
   chat_link?: string | null;
+  coaches?: Coaching[];
+  deal_seed?: number | null;
+  /**
+   * Players who can't follow suit or trump play their discards face-down, so that nobody else sees them until the trick is won.
+   */
+  face_down_discards?: boolean;
+  first_deal_landlord_policy?: FirstDealLandlordPolicy;
   first_landlord_selection_policy?: FirstLandlordSelectionPolicy & string;
+  fixed_partnerships?: boolean;
   friend_selection_policy?: FriendSelectionPolicy & string;
+  friend_selection_restrictions?: FriendSelectionRestrictions;
   game_mode: GameModeSettings;
   game_scoring_parameters?: GameScoringParameters;
   game_shadowing_policy?: GameShadowingPolicy & string;
@@ -888,37 +1970,197 @@ export interface PropagatedState {
   hide_landlord_points?: boolean;
   hide_played_cards?: boolean;
   hide_throw_halting_player?: boolean;
+  host?: number | null;
+  idle_players?: number[];
   joker_bid_policy?: JokerBidPolicy & string;
+  jokers_only_trump_policy?: JokersOnlyTrumpPolicy & string;
+  kick_vote?: KickVote | null;
   kitty_bid_policy?: KittyBidPolicy & string;
+  kitty_bury_policy?: KittyBuryPolicy & string;
   kitty_penalty?: KittyPenalty & string;
   kitty_size?: number | null;
   kitty_theft_policy?: KittyTheftPolicy & string;
   landlord?: number | null;
   landlord_emoji?: string | null;
+  landlord_rotation_policy?: LandlordRotationPolicy & string;
+  match_target_rank?: MaxRank | null;
+  /**
+   * The most points which may be buried in the bottom altogether. Like the kitty bury policy, this doesn't apply if the landlord has no other cards left to bury.
+   */
+  max_kitty_points?: number | null;
   max_player_id: number;
   max_rank?: MaxRank & string;
   multiple_join_policy?: MultipleJoinPolicy & string;
+  no_trump_only_from?: Number | null;
   num_decks?: number | null;
   num_games_finished?: number;
+  num_matches_finished?: number;
   observers: Player[];
+  /**
+   * How long everyone has taken over their moves in the current or most recent game.
+   */
+  pace?: PaceTracker;
+  pause?: Pause | null;
   play_takeback_policy?: PlayTakebackPolicy & string;
   players: Player[];
+  rank_sequence?: MaxRank[] | null;
+  ranked?: boolean;
+  rematch_policy?: RematchPolicy & string;
+  /**
+   * Announce the cards in the bottom one at a time at the end of the game, each with the points it adds after the kitty multiplier, so that clients can animate the reveal.
+   */
+  reveal_kitty_card_by_card?: boolean;
+  settings_proposal?: SettingsProposal | null;
   should_reveal_kitty_at_end_of_game?: boolean;
+  shuffle_seed?: number | null;
   special_decks?: Deck[];
+  spectator_delay_secs?: number;
+  spectators?: Player[];
+  suit_order?: SuitOrder & string;
   throw_evaluation_policy?: ThrowEvaluationPolicy & string;
   throw_penalty?: ThrowPenalty & string;
+  throw_tie_policy?: ThrowTiePolicy & string;
   tractor_requirements?: TractorRequirements;
   trick_draw_policy?: TrickDrawPolicy & string;
+  /**
+   * How many of the game's most recent tricks are kept for players to look back over, or all of them if unset.
+   */
+  trick_history_length?: number | null;
+  trick_play_policy?: TrickPlayPolicy & string;
+  turn_timers?: TurnTimers;
+  undo_vote?: UndoVote | null;
+  webhook_url?: string | null;
+  [k: string]: unknown;
+}
+export interface PaceSummary {
+  players: PlayerPace[];
+  slowest_trick?: TrickPace | null;
+  [k: string]: unknown;
+}
+export interface PlayerPace {
+  average_ms: number;
+  /**
+   * The number of bids and plays the player made.
+   */
+  moves: number;
+  name: string;
+  player: number;
+  slowest_ms: number;
+  [k: string]: unknown;
+}
+export interface TrickPace {
+  ms: number;
+  /**
+   * Counting from 1.
+   */
+  trick: number;
+  [k: string]: unknown;
+}
+/**
+ * Timings for the game in progress. Each move is timed from the one before it, whoever made it.
+ */
+export interface PaceTracker {
+  last_move_ms?: number | null;
+  moves: MoveTimes[];
+  slowest_trick?: TrickPace | null;
+  trick_started_ms?: number | null;
+  tricks: number;
+  [k: string]: unknown;
+}
+export interface MoveTimes {
+  moves: number;
+  player: number;
+  slowest_ms: number;
+  total_ms: number;
+  [k: string]: unknown;
+}
+/**
+ * A spectator who can see a single player's hand, e.g. to teach them the game. The coach only sees the hand once the player approves.
+ */
+export interface Coaching {
+  approved: boolean;
+  coach: number;
+  student: number;
+  [k: string]: unknown;
+}
+/**
+ * A vote to kick a player out of the room.
+ */
+export interface KickVote {
+  approvals: number[];
+  rejections: number[];
+  target: number;
+  [k: string]: unknown;
+}
+/**
+ * A pause requested by one of the players. Nobody can play until a majority of the players vote to resume.
+ */
+export interface Pause {
+  requested_by: number;
+  resume_votes: number[];
+  [k: string]: unknown;
+}
+/**
+ * A proposal to change some of the settings in the middle of a game, which goes ahead once every other player approves.
+ */
+export interface SettingsProposal {
+  approvals: number[];
+  patch: {
+    [k: string]: unknown;
+  };
+  proposer: number;
+  [k: string]: unknown;
+}
+/**
+ * A request to undo a player's most recent play or bid, which goes ahead once every other player approves.
+ */
+export interface UndoVote {
+  approvals: number[];
+  requester: number;
+  [k: string]: unknown;
+}
+/**
+ * How many seconds players get for their turn in each phase. Phases without a limit are untimed.
+ */
+export interface TurnTimers {
+  bid_secs?: number | null;
+  exchange_secs?: number | null;
+  play_secs?: number | null;
+  timeout_policy?: TimeoutPolicy & string;
+  [k: string]: unknown;
+}
+/**
+ * Restrictions on friend selection which apply in addition to the `FriendSelectionPolicy`.
+ */
+export interface FriendSelectionRestrictions {
+  /**
+   * The largest N for which the Nth played copy of a card may be selected as a friend. Defaults to the number of decks.
+   */
+  max_occurrence?: number | null;
+  no_point_cards?: boolean;
+  no_trumps?: boolean;
   [k: string]: unknown;
 }
 export interface DrawPhase {
   autobid?: Bid | null;
   bids: Bid[];
+  /**
+   * The player who becomes the landlord if nobody bids and there's no landlord yet. This is the player who drew first, unless someone else drew the card picked by the first-deal landlord policy.
+   */
+  dealer?: number | null;
+  /**
+   * The card which made the dealer the landlord-in-waiting, if the landlord is picked by the first player to draw a specific card.
+   */
+  dealer_card?: Card | null;
   deck: Card[];
   decks?: Deck[];
   game_mode: GameMode;
   hands: Hands;
   kitty: Card[];
+  /**
+   * How many cards were left in the deck when the last bid was made. A bid can only be taken back until the next card is drawn.
+   */
+  last_bid_deck_len?: number | null;
   level?: MaxRank | null;
   num_decks: number;
   player_requested_reset?: number | null;
@@ -954,7 +2196,63 @@ export interface ExchangePhase {
   trump: Trump;
   [k: string]: unknown;
 }
+/**
+ * A player's result over all of the boards of a duplicate tournament.
+ */
+export interface DuplicateStanding {
+  name: string;
+  /**
+   * The levels the player's team gained (or conceded, if negative) on each board, less the average for the same seat across every table.
+   */
+  score: number;
+  seat: number;
+  table: number;
+  [k: string]: unknown;
+}
+/**
+ * A compact summary of a player's career, for showing alongside their name.
+ */
+export interface CareerCard {
+  average_points_captured?: number | null;
+  biggest_throw_forfeited: number;
+  landlord_defense_rate?: number | null;
+  /**
+   * From games in ranked rooms, if the player has played in any.
+   */
+  rating?: number | null;
+  rounds_played: number;
+  [k: string]: unknown;
+}
+export interface PlayerLatency {
+  player_id: number;
+  rtt_ms: number;
+  [k: string]: unknown;
+}
+/**
+ * A trick which has been won, as kept in the trick history.
+ */
+export interface CompletedTrick {
+  /**
+   * The players who discarded face-down in the trick.
+   */
+  face_down?: number[];
+  played_cards: PlayedCards[];
+  /**
+   * The points the winner took, including any from the kitty.
+   */
+  points: number;
+  winner: number;
+  [k: string]: unknown;
+}
 export interface PlayPhase {
+  /**
+   * Set when redacting for a player who can't see the attacking team's points yet.
+   */
+  attacking_points_hidden?: boolean;
+  /**
+   * The cards played in every trick which has been finished so far.
+   */
+  cards_played?: Card[];
   decks?: Deck[];
   exchanger: number;
   game_ended_early: boolean;
@@ -962,7 +2260,17 @@ export interface PlayPhase {
   hands: Hands;
   kitty: Card[];
   landlord: number;
+  /**
+   * The number of cards in the bid which set the trump, if any.
+   */
+  landlord_bid_size?: number;
   landlords_team: number[];
+  /**
+   * The most cards each player has had to take back from a failed throw.
+   */
+  largest_failed_throws?: {
+    [k: string]: number;
+  };
   last_trick?: Trick | null;
   num_decks: number;
   penalties: {
@@ -975,6 +2283,13 @@ export interface PlayPhase {
   propagated: PropagatedState;
   removed_cards?: Card[];
   trick: Trick;
+  /**
+   * The most recent tricks, oldest first, up to the trick history length. This isn't sent along with the rest of the state; players fetch it when they want to look back over the game.
+   */
+  trick_history?: CompletedTrick[];
+  tricks_won?: {
+    [k: string]: number;
+  };
   trump: Trump;
   [k: string]: unknown;
 }
@@ -988,11 +2303,38 @@ export interface PlayerGameFinishedResult {
   confetti: boolean;
   is_defending: boolean;
   is_landlord: boolean;
+  /**
+   * The most cards the player has had to take back from a failed throw.
+   */
+  largest_failed_throw?: number;
+  /**
+   * The points in the tricks the player won.
+   */
+  points_captured?: number;
   rank: MaxRank;
   ranks_up: number;
   won_game: boolean;
   [k: string]: unknown;
 }
+/**
+ * A player's position at the end of a match.
+ */
+export interface MatchStanding {
+  metalevel: number;
+  player: number;
+  rank: MaxRank;
+  won_match: boolean;
+  [k: string]: unknown;
+}
+/**
+ * A setting which differs between two sets of settings.
+ */
+export interface SettingChange {
+  after: unknown;
+  before: unknown;
+  setting: string;
+  [k: string]: unknown;
+}
 export interface NextThresholdReachableRequest {
   decks: Deck[];
   non_landlord_points: number;
@@ -1000,8 +2342,28 @@ export interface NextThresholdReachableRequest {
   params: GameScoringParameters;
   [k: string]: unknown;
 }
+export interface NextThresholdReachability {
+  /**
+   * How many more points the attacking team needs to reach `threshold`.
+   */
+  points_needed: number;
+  /**
+   * Whether the attacking team can still reach the next threshold.
+   */
+  reachable: boolean;
+  /**
+   * How many points have not yet been played.
+   */
+  remaining_points: number;
+  /**
+   * The next point threshold which would change the outcome of the game.
+   */
+  threshold: number;
+  [k: string]: unknown;
+}
 export interface SortAndGroupCardsRequest {
   cards: Card[];
+  suit_order?: SuitOrder & string;
   trump: Trump;
   [k: string]: unknown;
 }
@@ -1014,3 +2376,49 @@ export interface SuitGroup {
   suit: EffectiveSuit;
   [k: string]: unknown;
 }
+export interface WasmTypes {
+  annotated_hint: AnnotatedHint;
+  annotated_scoring_explanation: AnnotatedScoringExplanation;
+  apply_state_diff_request: ApplyStateDiffRequest;
+  can_play_cards_request: CanPlayCardsRequest;
+  can_play_cards_response: CanPlayCardsResponse;
+  card_info: CardInfo;
+  card_info_request: CardInfoRequest;
+  check_puzzle_attempt_request: CheckPuzzleAttemptRequest;
+  check_puzzle_attempt_response: PuzzleResult;
+  compute_kitty_bonus_request: ComputeKittyBonusRequest;
+  compute_kitty_bonus_response: ComputeKittyBonusResponse;
+  compute_score_request: ComputeScoreRequest;
+  compute_score_response: ComputeScoreResponse;
+  create_local_game_request: CreateLocalGameRequest;
+  decompose_trick_format_request: DecomposeTrickFormatRequest;
+  decompose_trick_format_response: DecomposeTrickFormatResponse;
+  decomposed_trick_format: DecomposedTrickFormat;
+  explain_scoring_request: ExplainScoringRequest;
+  explain_scoring_response: ExplainScoringResponse;
+  find_legal_plays_request: FindLegalPlaysRequest;
+  find_legal_plays_response: FindLegalPlaysResponse;
+  find_valid_bids_request: FindValidBidsRequest;
+  find_valid_bids_response: FindValidBidsResult;
+  find_viable_plays_request: FindViablePlaysRequest;
+  find_viable_plays_response: FindViablePlaysResult;
+  found_viable_play: FoundViablePlay;
+  get_hints_request: GetHintsRequest;
+  get_hints_response: GetHintsResponse;
+  kitty_multiplier: KittyMultiplier;
+  local_game_action_request: LocalGameActionRequest;
+  local_game_response: LocalGameResponse;
+  next_threshold_reachable_request: NextThresholdReachableRequest;
+  next_threshold_reachable_response: NextThresholdReachability;
+  parse_play_notation_request: ParsePlayNotationRequest;
+  parse_play_notation_response: ParsePlayNotationResponse;
+  poll_find_viable_plays_response: PollFindViablePlaysResponse;
+  score_segment: ScoreSegment;
+  shutout_segment: ShutoutSegment;
+  simulate_trick_request: SimulateTrickRequest;
+  simulate_trick_response: TrickSimulationResult;
+  sort_and_group_cards_request: SortAndGroupCardsRequest;
+  sort_and_group_cards_response: SortAndGroupCardsResponse;
+  suit_group: SuitGroup;
+  [k: string]: unknown;
+}
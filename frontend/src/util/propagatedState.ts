@@ -0,0 +1,15 @@
+import { GameState, PropagatedState } from "../gen-types";
+
+const propagatedState = (state: GameState): PropagatedState => {
+  if ("Initialize" in state) {
+    return state.Initialize.propagated;
+  } else if ("Draw" in state) {
+    return state.Draw.propagated;
+  } else if ("Exchange" in state) {
+    return state.Exchange.propagated;
+  } else {
+    return state.Play.propagated;
+  }
+};
+
+export default propagatedState;
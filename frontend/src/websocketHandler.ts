@@ -77,6 +77,126 @@ const headerMessageHandler: WebsocketHandler = (
   }
 };
 
+export const reconnectTokenKey = (roomName: string): string =>
+  `reconnect_token_${roomName}`;
+
+const sessionHandler: WebsocketHandler = (
+  state: AppState,
+  message: GameMessage
+) => {
+  if ("Session" in message) {
+    // Keep the token for this tab only, so that a refresh puts us back in
+    // the same seat.
+    window.sessionStorage.setItem(
+      reconnectTokenKey(state.roomName),
+      message.Session.token
+    );
+    return { name: message.Session.name };
+  } else {
+    return null;
+  }
+};
+
+const tournamentHandler: WebsocketHandler = (
+  state: AppState,
+  message: GameMessage
+) => {
+  let text: string | null = null;
+  if ("TournamentTable" in message) {
+    const { players, room_name, round } = message.TournamentTable;
+    if (players.includes(state.name)) {
+      const link = `${window.location.origin}${window.location.pathname}#${room_name}`;
+      text = `You advanced! Your round ${round} table is at ${link}`;
+    }
+  } else if ("DuplicateBoard" in message) {
+    const { players, room_name, board } = message.DuplicateBoard;
+    if (players.includes(state.name)) {
+      const link = `${window.location.origin}${window.location.pathname}#${room_name}`;
+      text = `Board ${board} has been dealt at ${link}`;
+    }
+  } else if ("DuplicateReport" in message) {
+    const standings = message.DuplicateReport.standings;
+    const scores = standings.map(
+      (s) => `${s.name} (${s.score >= 0 ? "+" : ""}${s.score.toFixed(2)})`
+    );
+    text = `Final standings: ${scores.join(", ")}`;
+  } else if ("TournamentFinished" in message) {
+    const winners = message.TournamentFinished.winners;
+    text =
+      winners.length > 0
+        ? `The tournament is over, congratulations to ${winners.join(", ")}!`
+        : "The tournament is over.";
+  }
+  if (text === null) {
+    return null;
+  }
+  const newMessage: Message = { from: "TOURNAMENT", message: text };
+  return { messages: truncateMessages([...state.messages, newMessage]) };
+};
+
+const turnTimerHandler: WebsocketHandler = (
+  _: AppState,
+  message: GameMessage
+) => {
+  if ("TurnTimer" in message) {
+    const { player_id, remaining_ms, paused } = message.TurnTimer;
+    if (player_id === null || player_id === undefined) {
+      return { turnTimer: null };
+    }
+    return {
+      turnTimer: {
+        playerId: player_id,
+        deadline: performance.now() + remaining_ms,
+        pausedWithMs: paused ? remaining_ms : null,
+      },
+    };
+  } else {
+    return null;
+  }
+};
+
+const achievementHandler: WebsocketHandler = (
+  state: AppState,
+  message: GameMessage
+) => {
+  if ("AchievementUnlocked" in message) {
+    const { name, description } = message.AchievementUnlocked;
+    const newMessage: Message = {
+      from: "ACHIEVEMENT",
+      message: `${name} ${description}!`,
+    };
+    return { messages: truncateMessages([...state.messages, newMessage]) };
+  } else {
+    return null;
+  }
+};
+
+const latenciesHandler: WebsocketHandler = (
+  state: AppState,
+  message: GameMessage
+) => {
+  if ("Latencies" in message) {
+    const latencies: { [playerId: number]: number } = {};
+    message.Latencies.latencies.forEach((l) => {
+      latencies[l.player_id] = l.rtt_ms;
+    });
+    return { latencies };
+  }
+  return null;
+};
+
+const careerStatsHandler: WebsocketHandler = (
+  state: AppState,
+  message: GameMessage
+) => {
+  if ("CareerStats" in message) {
+    const { name, stats } = message.CareerStats;
+    return { careerStats: { ...state.careerStats, [name]: stats } };
+  } else {
+    return null;
+  }
+};
+
 let lastBeeped = performance.now();
 const beepHandler = (message: GameMessage): void => {
   if ("Beep" in message) {
@@ -90,6 +210,29 @@ const beepHandler = (message: GameMessage): void => {
   return null;
 };
 
+// Lets components react to events in the game (with sounds, toasts or
+// animations) without having to compare one state with the next.
+const notificationHandler = (message: GameMessage): void => {
+  if ("Notification" in message) {
+    window.dispatchEvent(
+      new CustomEvent("shengji:notification", {
+        detail: message.Notification.notification,
+      })
+    );
+  }
+  return null;
+};
+
+const pingHandler = (
+  message: GameMessage,
+  send: (msg: any) => void
+): void => {
+  if ("Ping" in message) {
+    send({ Pong: { sent_at_ms: message.Ping.sent_at_ms } });
+  }
+  return null;
+};
+
 let lastReadyChecked = performance.now();
 const readyCheckHandler = (
   message: GameMessage,
@@ -170,6 +313,12 @@ const allHandlers: WebsocketHandler[] = [
   errorHandler,
   stateHandler,
   headerMessageHandler,
+  sessionHandler,
+  tournamentHandler,
+  turnTimerHandler,
+  careerStatsHandler,
+  latenciesHandler,
+  achievementHandler,
   gameFinishedHandler,
 ];
 
@@ -185,6 +334,8 @@ const composedHandlers = (
     state = { ...state, ...partial };
   });
   beepHandler(message);
+  notificationHandler(message);
+  pingHandler(message, send);
   readyCheckHandler(message, send);
   return partials;
 };
@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use shengji_mechanics::trick::{TractorRequirements, TrickUnit};
+use shengji_mechanics::types::{cards::*, Card, Number, Suit, Trump};
+
+const TRUMP: Trump = Trump::Standard {
+    suit: Suit::Hearts,
+    number: Number::Two,
+};
+
+/// `num_decks - missing` copies of each card, for each `(card, missing)`.
+fn hand(cards: &[(Card, usize)], num_decks: usize) -> Vec<Card> {
+    cards
+        .iter()
+        .flat_map(|(c, missing)| std::iter::repeat_n(*c, num_decks - missing))
+        .collect()
+}
+
+fn bench_find_plays(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_plays");
+    group.sample_size(10);
+    for num_decks in 4..=6 {
+        let cases = [
+            // A long run of trump with uneven numbers of each card, so that it
+            // can be split up into tractors in many ways.
+            (
+                "trump_run",
+                hand(
+                    &[
+                        (H_3, 0),
+                        (H_4, 1),
+                        (H_5, 0),
+                        (H_6, 2),
+                        (H_7, 0),
+                        (H_8, 1),
+                        (H_9, 0),
+                        (H_10, 2),
+                        (H_J, 0),
+                        (H_Q, 1),
+                    ],
+                    num_decks,
+                ),
+            ),
+            // The top of the trump suit, where tractors can continue through
+            // the trump rank in any of the off suits.
+            (
+                "trump_top",
+                hand(
+                    &[
+                        (H_Q, 1),
+                        (H_K, 0),
+                        (H_A, 1),
+                        (S_2, 0),
+                        (C_2, 2),
+                        (D_2, 0),
+                        (H_2, 1),
+                        (Card::SmallJoker, 0),
+                        (Card::BigJoker, 1),
+                    ],
+                    num_decks,
+                ),
+            ),
+        ];
+        for (name, cards) in cases {
+            group.bench_with_input(BenchmarkId::new(name, num_decks), &cards, |b, cards| {
+                b.iter(|| {
+                    TrickUnit::find_plays(
+                        TRUMP,
+                        TractorRequirements::default(),
+                        black_box(cards.iter().copied()),
+                    )
+                    .into_iter()
+                    .count()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_find_plays);
+criterion_main!(benches);
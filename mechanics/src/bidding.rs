@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::hands::Hands;
 use crate::player::Player;
-use crate::types::{Card, PlayerID, Rank};
+use crate::types::{Card, Number, PlayerID, Rank};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum BidPolicy {
@@ -12,6 +12,12 @@ pub enum BidPolicy {
     #[default]
     JokerOrGreaterLength,
     GreaterLength,
+    /// Overbids must have at least `margin` more cards than the previous bid.
+    GreaterLengthByMargin {
+        margin: usize,
+    },
+    /// The first bid wins: nobody can overbid or reinforce it.
+    FirstBidWins,
 }
 
 crate::impl_slog_value!(BidPolicy);
@@ -27,6 +33,33 @@ pub enum JokerBidPolicy {
 
 crate::impl_slog_value!(JokerBidPolicy);
 
+/// When a round is played with only the jokers as trump, with neither a trump
+/// suit nor a trump rank.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum JokersOnlyTrumpPolicy {
+    /// Only when the landlord's rank is no-trump.
+    #[default]
+    NoTrumpRankOnly,
+    /// Also when the winning bid is made with big jokers.
+    BigJokerBid,
+    /// In every round.
+    Always,
+}
+
+crate::impl_slog_value!(JokersOnlyTrumpPolicy);
+
+impl JokersOnlyTrumpPolicy {
+    /// Whether a round won with a bid of `card` is played with only the
+    /// jokers as trump.
+    pub fn applies(self, card: Card) -> bool {
+        match self {
+            JokersOnlyTrumpPolicy::NoTrumpRankOnly => false,
+            JokersOnlyTrumpPolicy::BigJokerBid => card == Card::BigJoker,
+            JokersOnlyTrumpPolicy::Always => true,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum BidReinforcementPolicy {
     /// A bid can be reinforced when it is the winning bid.
@@ -58,6 +91,29 @@ pub struct Bid {
     pub epoch: usize,
 }
 
+/// The reason why a bid which the player holds the cards for can't be made.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Hash)]
+pub enum BidExclusionReason {
+    /// The landlord is playing no-trump, so there is no bidding.
+    NoTrumpLevel,
+    /// The joker bid policy doesn't allow bidding this many jokers.
+    JokerBidPolicy,
+    /// The player is already winning the bid, and may only reinforce it.
+    ReinforcementPolicy,
+    /// The bid doesn't defeat the current winning bid under the bid policy.
+    DoesNotOutbid,
+    /// Only no-trump (joker) bids are allowed at the bidding rank.
+    NoTrumpOnlyLevel,
+    /// Someone has already bid, and the first bid wins.
+    BiddingClosed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Hash)]
+pub struct ExcludedBid {
+    pub bid: Bid,
+    pub reason: BidExclusionReason,
+}
+
 impl Bid {
     #[allow(clippy::comparison_chain)]
     #[allow(clippy::too_many_arguments)]
@@ -71,6 +127,7 @@ impl Bid {
         bid_policy: BidPolicy,
         bid_reinforcement_policy: BidReinforcementPolicy,
         joker_bid_policy: JokerBidPolicy,
+        no_trump_only_from: Option<Number>,
         num_decks: usize,
     ) -> Result<Vec<Bid>, Error> {
         // Compute all valid bids.
@@ -85,13 +142,21 @@ impl Bid {
             // Bail early if the landlord is playing NoTrump, since there's no bidding.
             return Ok(vec![]);
         }
+        if bid_policy == BidPolicy::FirstBidWins && !bids.is_empty() {
+            return Ok(vec![]);
+        }
+        let no_trump_only = Self::is_no_trump_only(bid_level, no_trump_only_from);
 
         let valid_bid_cards = hands.counts(id).and_then(|counts| {
             let mut valid_bid_cards = vec![];
             for (card, count) in counts {
                 let consider = match bid_level {
                     _ if card.is_joker() => true,
-                    Some(Rank::Number(bid_level)) if card.number() == Some(bid_level) => true,
+                    Some(Rank::Number(bid_level))
+                        if card.number() == Some(bid_level) && !no_trump_only =>
+                    {
+                        true
+                    }
                     _ => false,
                 };
                 if consider {
@@ -111,34 +176,16 @@ impl Bid {
             let mut valid_bids = vec![];
             for (card, count) in counts {
                 for inner_count in 1..=*count {
-                    if card.is_joker() {
-                        let is_nt = bid_level == Some(Rank::NoTrump);
-                        match (card, joker_bid_policy) {
-                            // If joker bids are disabled, don't allow any joker bids.
-                            (_, JokerBidPolicy::Disabled) => continue,
-                            // If we're bidding against the no-trump rank, allow bids of one card
-                            // in the default joker bid policy.
-                            (_, JokerBidPolicy::BothTwoOrMore) if inner_count <= 1 && !is_nt => {
-                                continue
-                            }
-                            (Card::SmallJoker, JokerBidPolicy::LJNumDecksHJNumDecksLessOne)
-                            | (Card::SmallJoker, JokerBidPolicy::BothNumDecks)
-                                if inner_count < num_decks =>
-                            {
-                                continue
-                            }
-                            (Card::BigJoker, JokerBidPolicy::LJNumDecksHJNumDecksLessOne)
-                                if inner_count < num_decks - 1 =>
-                            {
-                                continue
-                            }
-                            (Card::BigJoker, JokerBidPolicy::BothNumDecks)
-                                if inner_count < num_decks =>
-                            {
-                                continue
-                            }
-                            (_, _) => (),
-                        }
+                    if card.is_joker()
+                        && !Self::joker_bid_permitted(
+                            *card,
+                            inner_count,
+                            bid_level == Some(Rank::NoTrump),
+                            joker_bid_policy,
+                            num_decks,
+                        )
+                    {
+                        continue;
                     }
                     let new_bid = Bid {
                         id,
@@ -147,7 +194,13 @@ impl Bid {
                         epoch,
                     };
                     if let Some(existing_bid) = bids.last() {
-                        if new_bid.count > existing_bid.count {
+                        let min_overbid_count = match bid_policy {
+                            BidPolicy::GreaterLengthByMargin { margin } => {
+                                existing_bid.count + margin.max(1)
+                            }
+                            _ => existing_bid.count + 1,
+                        };
+                        if new_bid.count >= min_overbid_count {
                             valid_bids.push(new_bid);
                         } else if new_bid.count == existing_bid.count {
                             match bid_policy {
@@ -241,6 +294,138 @@ impl Bid {
         }
     }
 
+    /// Whether only no-trump (joker) bids are allowed when bidding at `bid_level`.
+    pub fn is_no_trump_only(bid_level: Option<Rank>, no_trump_only_from: Option<Number>) -> bool {
+        match (bid_level, no_trump_only_from) {
+            (Some(Rank::Number(level)), Some(from)) => level >= from,
+            _ => false,
+        }
+    }
+
+    fn joker_bid_permitted(
+        card: Card,
+        count: usize,
+        is_nt: bool,
+        joker_bid_policy: JokerBidPolicy,
+        num_decks: usize,
+    ) -> bool {
+        match (card, joker_bid_policy) {
+            // If joker bids are disabled, don't allow any joker bids.
+            (_, JokerBidPolicy::Disabled) => false,
+            // If we're bidding against the no-trump rank, allow bids of one card
+            // in the default joker bid policy.
+            (_, JokerBidPolicy::BothTwoOrMore) if count <= 1 && !is_nt => false,
+            (Card::SmallJoker, JokerBidPolicy::LJNumDecksHJNumDecksLessOne)
+            | (Card::SmallJoker, JokerBidPolicy::BothNumDecks)
+                if count < num_decks =>
+            {
+                false
+            }
+            (Card::BigJoker, JokerBidPolicy::LJNumDecksHJNumDecksLessOne)
+                if count < num_decks - 1 =>
+            {
+                false
+            }
+            (Card::BigJoker, JokerBidPolicy::BothNumDecks) if count < num_decks => false,
+            (_, _) => true,
+        }
+    }
+
+    /// Computes the bids which the player holds the cards for, but which aren't in `valid_bids`,
+    /// along with the reason that each bid was excluded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn excluded_bids(
+        id: PlayerID,
+        bids: &'_ [Bid],
+        hands: &'_ Hands,
+        players: &'_ [Player],
+        landlord: Option<PlayerID>,
+        epoch: usize,
+        bid_policy: BidPolicy,
+        bid_reinforcement_policy: BidReinforcementPolicy,
+        joker_bid_policy: JokerBidPolicy,
+        no_trump_only_from: Option<Number>,
+        num_decks: usize,
+    ) -> Result<Vec<ExcludedBid>, Error> {
+        let valid_bids = Self::valid_bids(
+            id,
+            bids,
+            hands,
+            players,
+            landlord,
+            epoch,
+            bid_policy,
+            bid_reinforcement_policy,
+            joker_bid_policy,
+            no_trump_only_from,
+            num_decks,
+        )?;
+        let bid_player_id = landlord.unwrap_or(id);
+        let bid_level = players
+            .iter()
+            .find(|p| p.id == bid_player_id)
+            .map(|p| p.rank());
+        let is_nt = bid_level == Some(Rank::NoTrump);
+        let is_winning = bids
+            .iter()
+            .rev()
+            .find(|b| b.id == id)
+            .is_some_and(|b| Some(b) == bids.last());
+        let must_reinforce = is_winning
+            && matches!(
+                bid_reinforcement_policy,
+                BidReinforcementPolicy::ReinforceWhileWinning
+                    | BidReinforcementPolicy::ReinforceWhileEquivalent
+            );
+
+        let mut excluded = vec![];
+        for (card, count) in hands.counts(id).into_iter().flatten() {
+            let consider = match bid_level {
+                _ if card.is_joker() => true,
+                Some(Rank::Number(bid_level)) => card.number() == Some(bid_level),
+                _ => false,
+            };
+            if !consider {
+                continue;
+            }
+            for inner_count in 1..=*count {
+                let bid = Bid {
+                    id,
+                    card: *card,
+                    count: inner_count,
+                    epoch,
+                };
+                if valid_bids.contains(&bid) {
+                    continue;
+                }
+                let reason = if landlord.is_some() && is_nt {
+                    BidExclusionReason::NoTrumpLevel
+                } else if !card.is_joker() && Self::is_no_trump_only(bid_level, no_trump_only_from)
+                {
+                    BidExclusionReason::NoTrumpOnlyLevel
+                } else if card.is_joker()
+                    && !Self::joker_bid_permitted(
+                        *card,
+                        inner_count,
+                        is_nt,
+                        joker_bid_policy,
+                        num_decks,
+                    )
+                {
+                    BidExclusionReason::JokerBidPolicy
+                } else if bid_policy == BidPolicy::FirstBidWins && !bids.is_empty() {
+                    BidExclusionReason::BiddingClosed
+                } else if must_reinforce {
+                    BidExclusionReason::ReinforcementPolicy
+                } else {
+                    BidExclusionReason::DoesNotOutbid
+                };
+                excluded.push(ExcludedBid { bid, reason });
+            }
+        }
+        Ok(excluded)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn bid(
         id: PlayerID,
@@ -254,6 +439,7 @@ impl Bid {
         bid_policy: BidPolicy,
         bid_reinforcement_policy: BidReinforcementPolicy,
         joker_bid_policy: JokerBidPolicy,
+        no_trump_only_from: Option<Number>,
         num_decks: usize,
         epoch: usize,
     ) -> bool {
@@ -277,6 +463,7 @@ impl Bid {
             bid_policy,
             bid_reinforcement_policy,
             joker_bid_policy,
+            no_trump_only_from,
             num_decks,
         )
         .map(|b| b.contains(&new_bid))
@@ -332,10 +519,12 @@ mod tests {
     use crate::player::Player;
     use crate::types::{
         cards::{C_2, D_2, H_2, S_2},
-        Card, PlayerID,
+        Card, Number, PlayerID,
     };
 
-    use super::{Bid, BidPolicy, BidReinforcementPolicy, JokerBidPolicy};
+    use super::{
+        Bid, BidExclusionReason, BidPolicy, BidReinforcementPolicy, ExcludedBid, JokerBidPolicy,
+    };
 
     macro_rules! b {
         ($p:expr, $card:expr, $count:expr) => {
@@ -458,6 +647,7 @@ mod tests {
                     BidPolicy::JokerOrGreaterLength,
                     rpol,
                     JokerBidPolicy::BothTwoOrMore,
+                    None,
                     3,
                 )
                 .unwrap()
@@ -468,6 +658,125 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_valid_bids_greater_length_by_margin() {
+        let p = PlayerID(0);
+        let mut h = Hands::new(vec![p]);
+        h.add(
+            p,
+            vec![C_2, C_2, C_2, C_2, S_2, S_2, Card::BigJoker, Card::BigJoker],
+        )
+        .unwrap();
+        let players = vec![Player::new(p, "p0".into())];
+
+        let test_cases = vec![
+            (
+                vec![],
+                2,
+                vec![
+                    b!(p, C_2, 1),
+                    b!(p, C_2, 2),
+                    b!(p, C_2, 3),
+                    b!(p, C_2, 4),
+                    b!(p, S_2, 1),
+                    b!(p, S_2, 2),
+                    b!(p, Card::BigJoker, 2),
+                ],
+            ),
+            (
+                vec![b!(PlayerID(1), D_2, 1)],
+                2,
+                vec![b!(p, C_2, 3), b!(p, C_2, 4)],
+            ),
+            (vec![b!(PlayerID(1), D_2, 2)], 2, vec![b!(p, C_2, 4)]),
+            (vec![b!(PlayerID(1), D_2, 2)], 3, vec![]),
+            // A margin of one is the same as `GreaterLength`.
+            (
+                vec![b!(PlayerID(1), D_2, 1)],
+                1,
+                vec![
+                    b!(p, C_2, 2),
+                    b!(p, C_2, 3),
+                    b!(p, C_2, 4),
+                    b!(p, S_2, 2),
+                    b!(p, Card::BigJoker, 2),
+                ],
+            ),
+        ];
+
+        for (bids, margin, results) in test_cases {
+            assert_eq!(
+                Bid::valid_bids(
+                    p,
+                    &bids,
+                    &h,
+                    &players,
+                    None,
+                    0,
+                    BidPolicy::GreaterLengthByMargin { margin },
+                    BidReinforcementPolicy::ReinforceWhileWinning,
+                    JokerBidPolicy::BothTwoOrMore,
+                    None,
+                    4,
+                )
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>(),
+                results.into_iter().collect::<HashSet<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_bid_wins() {
+        let p = PlayerID(0);
+        let mut h = Hands::new(vec![p]);
+        h.add(p, vec![C_2, C_2, C_2, Card::BigJoker, Card::BigJoker])
+            .unwrap();
+        let players = vec![Player::new(p, "p0".into())];
+
+        let valid = |bids: Vec<Bid>| {
+            Bid::valid_bids(
+                p,
+                &bids,
+                &h,
+                &players,
+                None,
+                0,
+                BidPolicy::FirstBidWins,
+                BidReinforcementPolicy::ReinforceWhileWinning,
+                JokerBidPolicy::BothTwoOrMore,
+                None,
+                2,
+            )
+            .unwrap()
+        };
+        assert_eq!(valid(vec![]).len(), 4);
+        // Neither overbidding nor reinforcing is allowed.
+        assert_eq!(valid(vec![b!(PlayerID(1), D_2, 1)]), vec![]);
+        assert_eq!(valid(vec![b!(p, C_2, 1)]), vec![]);
+
+        let excluded = Bid::excluded_bids(
+            p,
+            &[b!(PlayerID(1), D_2, 1)],
+            &h,
+            &players,
+            None,
+            0,
+            BidPolicy::FirstBidWins,
+            BidReinforcementPolicy::ReinforceWhileWinning,
+            JokerBidPolicy::BothTwoOrMore,
+            None,
+            2,
+        )
+        .unwrap();
+        assert_eq!(excluded.len(), 5);
+        assert!(excluded
+            .iter()
+            .filter(|e| e.bid.count > 1 || !e.bid.card.is_joker())
+            .all(|e| e.reason == BidExclusionReason::BiddingClosed));
+    }
+
     #[test]
     fn test_valid_bids_joker_or_higher_suit() {
         let p = PlayerID(0);
@@ -559,6 +868,7 @@ mod tests {
                     BidPolicy::JokerOrHigherSuit,
                     rpol,
                     JokerBidPolicy::BothTwoOrMore,
+                    None,
                     3,
                 )
                 .unwrap()
@@ -568,4 +878,125 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_excluded_bids() {
+        let p = PlayerID(0);
+        let mut h = Hands::new(vec![p]);
+        h.add(p, vec![C_2, C_2, S_2, Card::SmallJoker, Card::SmallJoker])
+            .unwrap();
+        let players = vec![Player::new(p, "p0".into())];
+
+        let excluded = |bids: Vec<Bid>| {
+            Bid::excluded_bids(
+                p,
+                &bids,
+                &h,
+                &players,
+                None,
+                0,
+                BidPolicy::JokerOrGreaterLength,
+                BidReinforcementPolicy::ReinforceWhileWinning,
+                JokerBidPolicy::BothTwoOrMore,
+                None,
+                2,
+            )
+            .unwrap()
+            .into_iter()
+            .collect::<HashSet<_>>()
+        };
+        let e = |bid, reason| ExcludedBid { bid, reason };
+
+        assert_eq!(
+            excluded(vec![]),
+            vec![e(
+                b!(p, Card::SmallJoker, 1),
+                BidExclusionReason::JokerBidPolicy
+            )]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            excluded(vec![b!(p, C_2, 1)]),
+            vec![
+                e(b!(p, C_2, 1), BidExclusionReason::ReinforcementPolicy),
+                e(b!(p, S_2, 1), BidExclusionReason::ReinforcementPolicy),
+                e(
+                    b!(p, Card::SmallJoker, 1),
+                    BidExclusionReason::JokerBidPolicy
+                ),
+                e(
+                    b!(p, Card::SmallJoker, 2),
+                    BidExclusionReason::ReinforcementPolicy
+                ),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            excluded(vec![b!(PlayerID(1), H_2, 2)]),
+            vec![
+                e(b!(p, C_2, 1), BidExclusionReason::DoesNotOutbid),
+                e(b!(p, C_2, 2), BidExclusionReason::DoesNotOutbid),
+                e(b!(p, S_2, 1), BidExclusionReason::DoesNotOutbid),
+                e(
+                    b!(p, Card::SmallJoker, 1),
+                    BidExclusionReason::JokerBidPolicy
+                ),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn test_no_trump_only_bids() {
+        let p = PlayerID(0);
+        let mut h = Hands::new(vec![p]);
+        h.add(p, vec![C_2, C_2, S_2, Card::SmallJoker, Card::SmallJoker])
+            .unwrap();
+        let players = vec![Player::new(p, "p0".into())];
+
+        let valid = |no_trump_only_from| {
+            Bid::valid_bids(
+                p,
+                &[],
+                &h,
+                &players,
+                None,
+                0,
+                BidPolicy::JokerOrGreaterLength,
+                BidReinforcementPolicy::ReinforceWhileWinning,
+                JokerBidPolicy::BothTwoOrMore,
+                no_trump_only_from,
+                2,
+            )
+            .unwrap()
+            .len()
+        };
+        assert_eq!(valid(Some(Number::Three)), 4);
+        assert_eq!(valid(Some(Number::Two)), 1);
+
+        let excluded = Bid::excluded_bids(
+            p,
+            &[],
+            &h,
+            &players,
+            None,
+            0,
+            BidPolicy::JokerOrGreaterLength,
+            BidReinforcementPolicy::ReinforceWhileWinning,
+            JokerBidPolicy::BothTwoOrMore,
+            Some(Number::Two),
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            excluded
+                .iter()
+                .filter(|e| e.reason == BidExclusionReason::NoTrumpOnlyLevel)
+                .count(),
+            3
+        );
+    }
 }
@@ -2,13 +2,24 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use slog_derive::KV;
 
-use crate::types::{Card, Number, FULL_DECK};
+use crate::types::{Card, Number, Suit, ALL_SUITS, FULL_DECK};
 
 #[derive(Clone, Debug, Serialize, Deserialize, KV, JsonSchema)]
 pub struct Deck {
     pub exclude_small_joker: bool,
     pub exclude_big_joker: bool,
+    /// The lowest number included in the deck; cards below it are removed in
+    /// every suit.
     pub min: Number,
+    /// Numbers which are removed in every suit, in addition to those below
+    /// `min`.
+    #[serde(default)]
+    #[slog(skip)]
+    pub exclude_numbers: Vec<Number>,
+    /// Suits which are removed from the deck entirely.
+    #[serde(default)]
+    #[slog(skip)]
+    pub exclude_suits: Vec<Suit>,
 }
 
 impl slog::Value for Deck {
@@ -28,13 +39,20 @@ impl Default for Deck {
             exclude_small_joker: false,
             exclude_big_joker: false,
             min: Number::Two,
+            exclude_numbers: vec![],
+            exclude_suits: vec![],
         }
     }
 }
 
 impl Deck {
+    /// Whether the deck has any cards of `number`.
     pub fn includes_number(&self, number: Number) -> bool {
-        number >= self.min
+        number >= self.min && !self.exclude_numbers.contains(&number) && self.suits().count() > 0
+    }
+
+    pub fn includes_suit(&self, suit: Suit) -> bool {
+        !self.exclude_suits.contains(&suit)
     }
 
     pub fn includes_card(&self, card: Card) -> bool {
@@ -42,48 +60,40 @@ impl Deck {
             Card::BigJoker if self.exclude_big_joker => false,
             Card::SmallJoker if self.exclude_small_joker => false,
             Card::Suited { number, .. } if !self.includes_number(number) => false,
+            Card::Suited { suit, .. } if !self.includes_suit(suit) => false,
             _ => true,
         }
     }
 
-    pub fn points(&self) -> usize {
-        let mut pts = 0;
-        if self.includes_number(Number::Five) {
-            pts += 5 * 4;
-        }
-        if self.includes_number(Number::Ten) {
-            pts += 10 * 4;
-        }
-        if self.includes_number(Number::King) {
-            pts += 10 * 4;
+    /// The suits which the deck has cards in.
+    pub fn suits(&'_ self) -> impl Iterator<Item = Suit> + '_ {
+        ALL_SUITS
+            .iter()
+            .copied()
+            .filter(move |s| self.includes_suit(*s))
+    }
+
+    /// The lowest number in the deck, if it has any suited cards.
+    pub fn lowest_number(&self) -> Option<Number> {
+        let mut n = self.min;
+        loop {
+            if self.includes_number(n) {
+                return Some(n);
+            }
+            n = n.successor()?;
         }
-        pts
+    }
+
+    pub fn points(&self) -> usize {
+        self.cards().flat_map(|c| c.points()).sum()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.exclude_big_joker && self.exclude_small_joker && self.min == Number::Ace
+        self.len() == 0
     }
 
     pub fn len(&self) -> usize {
-        let mut cards = 54;
-        if self.exclude_big_joker {
-            cards -= 1;
-        }
-        if self.exclude_small_joker {
-            cards -= 1;
-        }
-
-        let mut n = Number::Two;
-        while n < self.min {
-            cards -= 4;
-            if let Some(nn) = n.successor() {
-                n = nn;
-            } else {
-                break;
-            }
-        }
-
-        cards
+        self.cards().count()
     }
 
     pub fn cards(&'_ self) -> impl Iterator<Item = Card> + '_ {
@@ -118,7 +128,7 @@ impl<'d> Iterator for DeckIterator<'d> {
 
 #[cfg(test)]
 mod tests {
-    use crate::types::Number;
+    use crate::types::{Number, Suit};
 
     use super::Deck;
 
@@ -151,14 +161,72 @@ mod tests {
                 18,
                 40,
             ),
+            (
+                Deck {
+                    exclude_big_joker: true,
+                    exclude_small_joker: true,
+                    min: Number::Ace,
+                    ..Default::default()
+                },
+                4,
+                0,
+            ),
+            (
+                Deck {
+                    exclude_numbers: vec![Number::Three, Number::Four],
+                    ..Default::default()
+                },
+                46,
+                100,
+            ),
+            (
+                Deck {
+                    exclude_numbers: vec![Number::Five, Number::King],
+                    min: Number::Four,
+                    ..Default::default()
+                },
+                38,
+                40,
+            ),
+            (
+                Deck {
+                    exclude_suits: vec![Suit::Clubs, Suit::Diamonds, Suit::Spades],
+                    ..Default::default()
+                },
+                15,
+                25,
+            ),
         ];
 
         for (deck, cards, points) in cases {
             eprintln!("testing {deck:?} {cards:?} {points:?}");
             assert_eq!(deck.points(), points);
             assert_eq!(deck.len(), cards);
+            assert!(!deck.is_empty());
             assert_eq!(deck.cards().count(), cards);
             assert_eq!(deck.cards().flat_map(|c| c.points()).sum::<usize>(), points);
         }
     }
+
+    #[test]
+    fn test_deck_exclusions() {
+        let deck = Deck {
+            exclude_numbers: vec![Number::Two, Number::Three],
+            exclude_suits: vec![Suit::Hearts],
+            ..Default::default()
+        };
+        assert!(!deck.includes_number(Number::Three));
+        assert!(deck.includes_number(Number::Five));
+        assert_eq!(deck.lowest_number(), Some(Number::Four));
+        assert_eq!(deck.suits().count(), 3);
+        assert!(deck.cards().all(|c| c.suit() != Some(Suit::Hearts)));
+
+        let jokers_only = Deck {
+            exclude_suits: vec![Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades],
+            ..Default::default()
+        };
+        assert!(!jokers_only.includes_number(Number::Ace));
+        assert_eq!(jokers_only.lowest_number(), None);
+        assert_eq!(jokers_only.len(), 2);
+    }
 }
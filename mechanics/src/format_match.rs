@@ -8,6 +8,7 @@ use crate::ordered_card::{AdjacentTupleSizes, MatchingCards, OrderedCard};
 pub fn find_format_matches(
     format: Vec<AdjacentTupleSizes>,
     cards: BTreeMap<OrderedCard, usize>,
+    trump_rank_breaks_tractors: bool,
 ) -> impl Iterator<Item = Vec<MatchingCards>> {
     let mut queue = VecDeque::new();
 
@@ -20,6 +21,7 @@ pub fn find_format_matches(
         queue,
         cards,
         visited: HashSet::new(),
+        trump_rank_breaks_tractors,
     }
 }
 
@@ -123,6 +125,7 @@ struct FormatMatchIterator {
     cards: BTreeMap<OrderedCard, usize>,
     visited: HashSet<FormatMatchState>,
     queue: VecDeque<QueueItem>,
+    trump_rank_breaks_tractors: bool,
 }
 
 impl FormatMatchIterator {
@@ -183,7 +186,7 @@ impl FormatMatchIterator {
                     // outside the trump suit -- e.g. if the trump
                     // number is 2, there are three potential 2x2
                     // tractors starting at A.
-                    for s in next_card.successor() {
+                    for s in next_card.tractor_successor(self.trump_rank_breaks_tractors) {
                         stk.push((s, remaining_tuples.clone(), seq_so_far.clone()));
                     }
                 }
@@ -344,7 +347,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let v = find_format_matches(vec![vec![1]], counts.clone()).collect::<Vec<_>>();
+        let v = find_format_matches(vec![vec![1]], counts.clone(), false).collect::<Vec<_>>();
 
         assert_eq!(
             v,
@@ -356,7 +359,7 @@ mod tests {
             ]
         );
 
-        let v = find_format_matches(vec![vec![2]], counts).collect::<Vec<_>>();
+        let v = find_format_matches(vec![vec![2]], counts, false).collect::<Vec<_>>();
 
         assert_eq!(
             v,
@@ -379,7 +382,8 @@ mod tests {
         .into_iter()
         .collect();
 
-        let v = find_format_matches(vec![vec![1], vec![1]], counts.clone()).collect::<Vec<_>>();
+        let v =
+            find_format_matches(vec![vec![1], vec![1]], counts.clone(), false).collect::<Vec<_>>();
         assert_eq!(
             v[0],
             vec![vec![(oc!(Card::BigJoker), 1)], vec![(oc!(S_5), 1)]]
@@ -396,7 +400,7 @@ mod tests {
         // 2, 2
         assert_eq!(v.len(), 9);
 
-        let v = find_format_matches(vec![vec![2], vec![2]], counts).collect::<Vec<_>>();
+        let v = find_format_matches(vec![vec![2], vec![2]], counts, false).collect::<Vec<_>>();
 
         // There are 3 unique choices of two pairsA
         // 55, 33
@@ -417,7 +421,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let v = find_format_matches(vec![vec![2, 2]], counts).collect::<Vec<_>>();
+        let v = find_format_matches(vec![vec![2, 2]], counts, false).collect::<Vec<_>>();
 
         assert_eq!(
             v,
@@ -440,7 +444,8 @@ mod tests {
         .into_iter()
         .collect();
 
-        let v = find_format_matches(vec![vec![2, 2], vec![2, 2]], counts).collect::<Vec<_>>();
+        let v =
+            find_format_matches(vec![vec![2, 2], vec![2, 2]], counts, false).collect::<Vec<_>>();
 
         assert_eq!(
             v[0],
@@ -471,7 +476,7 @@ mod tests {
         .collect();
         let fmt = vec![vec![4, 4], vec![3, 3], vec![1], vec![3]];
 
-        let v = find_format_matches(fmt, counts).collect::<Vec<_>>();
+        let v = find_format_matches(fmt, counts, false).collect::<Vec<_>>();
 
         assert_eq!(
             v[0],
@@ -501,7 +506,7 @@ mod tests {
         .collect();
         let fmt = (0..10).map(|_| vec![1]).collect();
 
-        let v = find_format_matches(fmt, counts).collect::<Vec<_>>();
+        let v = find_format_matches(fmt, counts, false).collect::<Vec<_>>();
 
         assert_eq!(
             v[0],
@@ -21,10 +21,34 @@ pub enum HandError {
     TrumpNotSet,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[derive(Clone, Debug, Serialize, JsonSchema)]
 pub struct Hands {
     hands: HashMap<PlayerID, HashMap<Card, usize>>,
     trump: Option<Trump>,
+    /// Each player's cards, split up by effective suit. Only kept once the
+    /// trump is set, and rebuilt whenever it changes.
+    #[serde(skip)]
+    suits: HashMap<PlayerID, HashMap<EffectiveSuit, HashMap<Card, usize>>>,
+}
+
+/// `Hands` as it is serialized, without the suit index.
+#[derive(Deserialize)]
+struct SerializedHands {
+    hands: HashMap<PlayerID, HashMap<Card, usize>>,
+    trump: Option<Trump>,
+}
+
+impl<'de> Deserialize<'de> for Hands {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SerializedHands { hands, trump } = SerializedHands::deserialize(deserializer)?;
+        let mut hands = Hands {
+            hands,
+            trump,
+            suits: HashMap::new(),
+        };
+        hands.rebuild_suits();
+        Ok(hands)
+    }
 }
 
 impl Hands {
@@ -32,6 +56,7 @@ impl Hands {
         Hands {
             hands: players.into_iter().map(|id| (id, HashMap::new())).collect(),
             trump: None,
+            suits: HashMap::new(),
         }
     }
 
@@ -43,6 +68,22 @@ impl Hands {
                 cards.insert(Card::Unknown, count);
             }
         }
+        self.rebuild_suits();
+    }
+
+    fn rebuild_suits(&mut self) {
+        self.suits.clear();
+        if let Some(trump) = self.trump {
+            for (id, hand) in &self.hands {
+                let suits = self.suits.entry(*id).or_default();
+                for (card, count) in hand {
+                    suits
+                        .entry(trump.effective_suit(*card))
+                        .or_default()
+                        .insert(*card, *count);
+                }
+            }
+        }
     }
 
     pub fn get(&self, id: PlayerID) -> Result<&'_ HashMap<Card, usize>, HandError> {
@@ -52,6 +93,7 @@ impl Hands {
 
     pub fn set_trump(&mut self, trump: Trump) {
         self.trump = Some(trump);
+        self.rebuild_suits();
     }
 
     pub fn trump(&self) -> Result<Trump, HandError> {
@@ -87,16 +129,24 @@ impl Hands {
     }
 
     pub fn is_void(&self, id: PlayerID, suit: EffectiveSuit) -> Result<bool, HandError> {
-        self.exists(id)?;
-        let trump = self.trump()?;
-
-        for (card, number) in &self.hands[&id] {
-            if *number > 0 && trump.effective_suit(*card) == suit {
-                return Ok(false);
-            }
-        }
+        Ok(self.cards_in_effective_suit(id, suit)?.next().is_none())
+    }
 
-        Ok(true)
+    /// The player's cards in `suit`, and how many of each they have, without
+    /// going through the rest of their hand.
+    pub fn cards_in_effective_suit(
+        &self,
+        id: PlayerID,
+        suit: EffectiveSuit,
+    ) -> Result<impl Iterator<Item = (&'_ Card, &'_ usize)> + Clone + '_, HandError> {
+        self.exists(id)?;
+        self.trump()?;
+        Ok(self
+            .suits
+            .get(&id)
+            .and_then(|suits| suits.get(&suit))
+            .into_iter()
+            .flatten())
     }
 
     pub fn counts(&self, id: PlayerID) -> Option<&'_ HashMap<Card, usize>> {
@@ -134,6 +184,16 @@ impl Hands {
         }
         for card in cards {
             *hand.entry(card).or_insert(0) += 1;
+            if let Some(trump) = self.trump {
+                *self
+                    .suits
+                    .entry(id)
+                    .or_default()
+                    .entry(trump.effective_suit(card))
+                    .or_default()
+                    .entry(card)
+                    .or_insert(0) += 1;
+            }
         }
         Ok(())
     }
@@ -146,6 +206,7 @@ impl Hands {
         self.contains(id, cards.clone())?;
 
         let hand = self.hands.get_mut(&id).unwrap();
+        let suits = self.suits.entry(id).or_default();
         for card in cards {
             if let Entry::Occupied(mut o) = hand.entry(card) {
                 *o.get_mut() -= 1;
@@ -153,6 +214,15 @@ impl Hands {
                     o.remove();
                 }
             }
+            if let Some(trump) = self.trump {
+                let suit = suits.entry(trump.effective_suit(card)).or_default();
+                if let Entry::Occupied(mut o) = suit.entry(card) {
+                    *o.get_mut() -= 1;
+                    if *o.get() == 0 {
+                        o.remove();
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -160,10 +230,12 @@ impl Hands {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::Hands;
     use crate::types::{
-        cards::{S_2, S_3, S_4, S_5},
-        PlayerID,
+        cards::{H_2, H_3, S_2, S_3, S_4, S_5},
+        Card, EffectiveSuit, Number, PlayerID, Suit, Trump,
     };
 
     const P1: PlayerID = PlayerID(1);
@@ -191,4 +263,61 @@ mod tests {
         hands.remove(P3, vec![S_2, S_3, S_4, S_5]).unwrap_err();
         assert_eq!(hands._get_cards(P3).unwrap(), hands._get_cards(P4).unwrap());
     }
+
+    #[test]
+    fn test_cards_in_effective_suit() {
+        let mut hands = Hands::new(vec![P1, P2]);
+        hands.add(P1, vec![S_2, S_3, S_3, H_2, H_3]).unwrap();
+        assert!(hands
+            .cards_in_effective_suit(P1, EffectiveSuit::Spades)
+            .is_err());
+
+        let in_suit = |hands: &Hands, suit| {
+            hands
+                .cards_in_effective_suit(P1, suit)
+                .unwrap()
+                .map(|(card, count)| (*card, *count))
+                .collect::<HashMap<_, _>>()
+        };
+        hands.set_trump(Trump::Standard {
+            suit: Suit::Spades,
+            number: Number::Two,
+        });
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Trump),
+            Card::count(vec![S_2, S_3, S_3, H_2])
+        );
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Hearts),
+            Card::count(vec![H_3])
+        );
+
+        hands.remove(P1, vec![S_3, H_3]).unwrap();
+        hands.add(P1, vec![S_4]).unwrap();
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Trump),
+            Card::count(vec![S_2, S_3, S_4, H_2])
+        );
+        assert!(hands.is_void(P1, EffectiveSuit::Hearts).unwrap());
+        assert!(hands.is_void(P2, EffectiveSuit::Trump).unwrap());
+
+        hands.set_trump(Trump::Standard {
+            suit: Suit::Hearts,
+            number: Number::Five,
+        });
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Trump),
+            Card::count(vec![H_2])
+        );
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Spades),
+            Card::count(vec![S_2, S_3, S_4])
+        );
+
+        let hands: Hands = serde_json::from_value(serde_json::to_value(&hands).unwrap()).unwrap();
+        assert_eq!(
+            in_suit(&hands, EffectiveSuit::Spades),
+            Card::count(vec![S_2, S_3, S_4])
+        );
+    }
 }
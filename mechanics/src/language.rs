@@ -0,0 +1,10 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The languages which the engine can describe the game in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, JsonSchema, Default)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+}
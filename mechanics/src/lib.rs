@@ -8,10 +8,15 @@ pub mod bidding;
 pub mod deck;
 pub mod format_match;
 pub mod hands;
+pub mod language;
 pub mod multiset_iter;
+pub mod notation;
 pub mod ordered_card;
 pub mod player;
 pub mod scoring;
+pub mod simulation;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod trick;
 pub mod types;
 
@@ -0,0 +1,311 @@
+//!
+//! A compact, human-readable notation for plays.
+//!
+//! Units are separated by whitespace or commas. Within a unit, tuples of identical cards are
+//! separated by `-`, so `AA-KK` is a tractor of pairs and `2♠2♠2♠` is a triple. Cards are written
+//! as their number followed by an optional suit (`♠♥♦♣`, `♤♡♢♧` or `SHDC`); jokers are written as
+//! `LJ` and `HJ`.
+//!
+
+use anyhow::{anyhow, bail, Error};
+
+use crate::ordered_card::OrderedCard;
+use crate::trick::{TrickUnit, UnitLike};
+use crate::types::{Card, Number, Suit, Trump};
+
+/// A card as written in the notation, which may be missing its suit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NotatedCard {
+    Joker(Card),
+    Number(Number, Option<Suit>),
+}
+
+impl NotatedCard {
+    fn card(self) -> Result<Card, Error> {
+        match self {
+            NotatedCard::Joker(card) => Ok(card),
+            NotatedCard::Number(number, Some(suit)) => Ok(Card::Suited { suit, number }),
+            NotatedCard::Number(number, None) => {
+                bail!("{} is missing a suit", number.as_str())
+            }
+        }
+    }
+
+    fn same_rank(self, other: NotatedCard) -> bool {
+        match (self, other) {
+            (NotatedCard::Joker(a), NotatedCard::Joker(b)) => a == b,
+            (NotatedCard::Number(a, _), NotatedCard::Number(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn parse_suit(c: char) -> Option<Suit> {
+    match c {
+        '♠' | 'S' | 's' => Some(Suit::Spades),
+        '♥' | 'H' | 'h' => Some(Suit::Hearts),
+        '♦' | 'D' | 'd' => Some(Suit::Diamonds),
+        '♣' | 'C' | 'c' => Some(Suit::Clubs),
+        c => Suit::from_char(c),
+    }
+}
+
+fn parse_tuple(tuple: &str) -> Result<Vec<NotatedCard>, Error> {
+    let chars = tuple.chars().collect::<Vec<_>>();
+    let mut cards = vec![];
+    let mut idx = 0;
+    while idx < chars.len() {
+        let card = match (chars[idx], chars.get(idx + 1)) {
+            ('L', Some('J')) => {
+                idx += 2;
+                NotatedCard::Joker(Card::SmallJoker)
+            }
+            ('H', Some('J')) => {
+                idx += 2;
+                NotatedCard::Joker(Card::BigJoker)
+            }
+            ('1', Some('0')) => {
+                idx += 2;
+                NotatedCard::Number(Number::Ten, None)
+            }
+            (c, _) => {
+                idx += 1;
+                let number = match c {
+                    'T' => Some(Number::Ten),
+                    c => Number::from_str(&c.to_string()),
+                };
+                NotatedCard::Number(
+                    number.ok_or_else(|| anyhow!("Unexpected character '{}' in {}", c, tuple))?,
+                    None,
+                )
+            }
+        };
+        let card = match card {
+            NotatedCard::Number(number, None) => {
+                match chars.get(idx).copied().and_then(parse_suit) {
+                    Some(suit) => {
+                        idx += 1;
+                        NotatedCard::Number(number, Some(suit))
+                    }
+                    None => NotatedCard::Number(number, None),
+                }
+            }
+            card => card,
+        };
+        cards.push(card);
+    }
+
+    match cards.first() {
+        None => bail!("Empty tuple"),
+        Some(first) if cards.iter().any(|c| !c.same_rank(*first)) => {
+            bail!("All of the cards in {} must be the same", tuple)
+        }
+        Some(_) => Ok(cards),
+    }
+}
+
+fn parse_units(notation: &str) -> Result<Vec<Vec<Vec<NotatedCard>>>, Error> {
+    let units = notation
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|u| !u.is_empty())
+        .map(|unit| unit.split('-').map(parse_tuple).collect())
+        .collect::<Result<Vec<_>, _>>()?;
+    if units.is_empty() {
+        bail!("No cards in notation");
+    }
+    Ok(units)
+}
+
+/// Parses the notation into the shapes of the units, ignoring which cards are played.
+pub fn parse_unit_likes(notation: &str) -> Result<Vec<UnitLike>, Error> {
+    Ok(parse_units(notation)?
+        .into_iter()
+        .map(|unit| UnitLike::from(&unit.iter().map(|t| t.len()).collect::<Vec<_>>()))
+        .collect())
+}
+
+/// Formats the shapes of the units, using descending ranks from the ace for
+/// adjacent tuples.
+pub fn format_unit_likes(units: &[UnitLike]) -> String {
+    units
+        .iter()
+        .map(|u| {
+            let mut number = Some(Number::Ace);
+            u.adjacent_tuples()
+                .iter()
+                .map(|len| {
+                    let n = number.unwrap_or(Number::Two);
+                    number = n.predecessor();
+                    n.as_str().repeat(*len)
+                })
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses the notation into units of specific cards. Every suited card must
+/// have a suit, and the tuples of a tractor must be adjacent under `trump`.
+pub fn parse_trick_units(notation: &str, trump: Trump) -> Result<Vec<TrickUnit>, Error> {
+    parse_units(notation)?
+        .into_iter()
+        .map(|unit| {
+            let mut tuples = unit
+                .into_iter()
+                .map(|tuple| {
+                    Ok((
+                        OrderedCard {
+                            card: tuple[0].card()?,
+                            trump,
+                        },
+                        tuple.len(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let count = tuples[0].1;
+
+            if tuples.len() == 1 {
+                return Ok(TrickUnit::Repeated {
+                    card: tuples[0].0,
+                    count,
+                });
+            }
+            if tuples.iter().any(|(_, c)| *c != count) || count < 2 {
+                bail!("The tuples in a tractor must all be the same size, and at least pairs");
+            }
+            // Allow tractors to be written in either order.
+            if tuples[0].0 > tuples[1].0 {
+                tuples.reverse();
+            }
+            for pair in tuples.windows(2) {
+                if !pair[0].0.successor().contains(&pair[1].0) {
+                    bail!("{:?} and {:?} are not adjacent", pair[0].0, pair[1].0);
+                }
+            }
+            Ok(TrickUnit::Tractor {
+                count,
+                members: tuples.into_iter().map(|(card, _)| card).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the notation into a list of cards, ignoring how they're grouped
+/// into units. Every suited card must have a suit.
+pub fn parse_cards(notation: &str) -> Result<Vec<Card>, Error> {
+    parse_units(notation)?
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(NotatedCard::card)
+        .collect()
+}
+
+/// Formats the units, with tractors written from highest to lowest.
+pub fn format_trick_units(units: &[TrickUnit]) -> String {
+    units
+        .iter()
+        .map(|u| match u {
+            TrickUnit::Repeated { card, count } => format!("{:?}", card.card).repeat(*count),
+            TrickUnit::Tractor { count, members } => members
+                .iter()
+                .rev()
+                .map(|card| format!("{:?}", card.card).repeat(*count))
+                .collect::<Vec<_>>()
+                .join("-"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ordered_card::OrderedCard;
+    use crate::trick::{TrickUnit, UnitLike};
+    use crate::types::{cards::*, Card, Number, Suit, Trump};
+
+    use super::{
+        format_trick_units, format_unit_likes, parse_cards, parse_trick_units, parse_unit_likes,
+    };
+
+    const TRUMP: Trump = Trump::Standard {
+        number: Number::Four,
+        suit: Suit::Spades,
+    };
+
+    fn oc(card: Card) -> OrderedCard {
+        OrderedCard { card, trump: TRUMP }
+    }
+
+    #[test]
+    fn test_parse_unit_likes() {
+        assert_eq!(
+            parse_unit_likes("AA-KK 5, 2♠2♠2♠").unwrap(),
+            vec![
+                UnitLike::from(&vec![2, 2]),
+                UnitLike::from(&vec![1]),
+                UnitLike::from(&vec![3]),
+            ]
+        );
+        assert_eq!(
+            parse_unit_likes("1010-99").unwrap(),
+            vec![UnitLike::from(&vec![2, 2])]
+        );
+        assert!(parse_unit_likes("AK").is_err());
+        assert!(parse_unit_likes("").is_err());
+        assert!(parse_unit_likes("XX").is_err());
+        assert_eq!(
+            format_unit_likes(&parse_unit_likes("88-77-66 3").unwrap()),
+            "AA-KK-QQ A"
+        );
+    }
+
+    #[test]
+    fn test_parse_trick_units() {
+        assert_eq!(
+            parse_trick_units("A♥A♥-K♥K♥ LJLJ 3S", TRUMP).unwrap(),
+            vec![
+                TrickUnit::Tractor {
+                    count: 2,
+                    members: vec![oc(H_K), oc(H_A)],
+                },
+                TrickUnit::Repeated {
+                    card: oc(Card::SmallJoker),
+                    count: 2,
+                },
+                TrickUnit::Repeated {
+                    card: oc(S_3),
+                    count: 1,
+                },
+            ]
+        );
+        // Tractors can skip over the trump number.
+        assert_eq!(
+            parse_trick_units("5H5H-3H3H", TRUMP).unwrap(),
+            vec![TrickUnit::Tractor {
+                count: 2,
+                members: vec![oc(H_3), oc(H_5)],
+            }]
+        );
+        assert!(parse_trick_units("AA-KK", TRUMP).is_err());
+        assert!(parse_trick_units("AHAH-QHQH", TRUMP).is_err());
+        assert!(parse_trick_units("AHAH-KHKHKH", TRUMP).is_err());
+
+        let units = parse_trick_units("AHAH-KHKH 2C", TRUMP).unwrap();
+        let formatted = format_trick_units(&units);
+        assert_eq!(formatted, "A♡A♡-K♡K♡ 2♧");
+        assert_eq!(parse_trick_units(&formatted, TRUMP).unwrap(), units);
+    }
+
+    #[test]
+    fn test_parse_cards() {
+        assert_eq!(
+            parse_cards("A♥A♥-K♥K♥ LJ, 10S").unwrap(),
+            vec![H_A, H_A, H_K, H_K, Card::SmallJoker, S_10]
+        );
+        assert_eq!(parse_cards("3♧ HJ").unwrap(), vec![C_3, Card::BigJoker]);
+        assert!(parse_cards("AA").is_err());
+        assert!(parse_cards("").is_err());
+    }
+}
@@ -38,6 +38,22 @@ impl OrderedCard {
             .collect()
     }
 
+    /// Like `successor`, but doesn't skip over the trump number within a suit
+    /// if `trump_rank_breaks_tractors` is set.
+    pub fn tractor_successor(self, trump_rank_breaks_tractors: bool) -> Vec<OrderedCard> {
+        match self.card {
+            Card::Suited { number, .. }
+                if trump_rank_breaks_tractors
+                    && number.successor().is_some()
+                    && number.successor() == self.trump.number()
+                    && number.successor().and_then(|n| n.successor()).is_some() =>
+            {
+                vec![]
+            }
+            _ => self.successor(),
+        }
+    }
+
     pub fn make_map(
         cards: impl Iterator<Item = Card>,
         trump: Trump,
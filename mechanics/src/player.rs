@@ -44,4 +44,51 @@ impl Player {
             }
         }
     }
+
+    /// Advances along `sequence` rather than the full ladder, starting over
+    /// from the first rank after the last one. Players whose rank isn't in the
+    /// sequence are moved to its first rank.
+    pub fn advance_in_sequence(&mut self, sequence: &[Rank]) {
+        match sequence.iter().position(|r| *r == self.level) {
+            Some(idx) if idx + 1 < sequence.len() => {
+                self.level = sequence[idx + 1];
+            }
+            Some(_) => {
+                self.metalevel += 1;
+                self.level = sequence[0];
+            }
+            None => {
+                if let Some(first) = sequence.first() {
+                    self.level = *first;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Number, PlayerID, Rank};
+
+    use super::Player;
+
+    #[test]
+    fn test_advance_in_sequence() {
+        let sequence = [
+            Rank::Number(Number::Two),
+            Rank::Number(Number::Five),
+            Rank::Number(Number::Ace),
+        ];
+        let mut p = Player::new(PlayerID(0), "p".into());
+        p.advance_in_sequence(&sequence);
+        assert_eq!(p.rank(), Rank::Number(Number::Five));
+        p.advance_in_sequence(&sequence);
+        assert_eq!(p.rank(), Rank::Number(Number::Ace));
+        p.advance_in_sequence(&sequence);
+        assert_eq!((p.rank(), p.metalevel), (Rank::Number(Number::Two), 2));
+
+        p.set_rank(Rank::Number(Number::Three));
+        p.advance_in_sequence(&sequence);
+        assert_eq!((p.rank(), p.metalevel), (Rank::Number(Number::Two), 2));
+    }
 }
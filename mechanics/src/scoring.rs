@@ -6,16 +6,82 @@ use serde::{Deserialize, Serialize};
 use slog_derive::KV;
 
 use crate::deck::Deck;
+use crate::language::Language;
+use crate::types::Card;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum BonusLevelPolicy {
     NoBonusLevel,
     #[default]
     BonusLevelForSmallerLandlordTeam,
+    /// Multiply the levels won by a smaller-than-expected landlord's team by
+    /// the expected team size over the actual one, rounding up.
+    ScaleByLandlordTeamSize,
 }
 
 crate::impl_slog_value!(BonusLevelPolicy);
 
+/// How many players ended up on the landlord's team, against how many the
+/// game mode called for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LandlordTeamSize {
+    pub actual: usize,
+    pub expected: usize,
+}
+
+impl LandlordTeamSize {
+    /// A landlord's team which is exactly as large as expected.
+    pub const fn full(size: usize) -> Self {
+        LandlordTeamSize {
+            actual: size,
+            expected: size,
+        }
+    }
+
+    pub fn is_smaller(self) -> bool {
+        self.actual < self.expected
+    }
+}
+
+/// A game in which the landlord's team kept the attacking team from scoring.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Shutout {
+    /// The attacking team won tricks, but no points (小光).
+    Small,
+    /// The attacking team didn't win any tricks (大光).
+    Big,
+}
+
+crate::impl_slog_value!(Shutout);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum KittyPenalty {
+    /// Twice the size of the largest unit in the last trick.
+    #[default]
+    Times,
+    /// Two to the power of the size of the largest unit in the last trick.
+    Power,
+    /// Twice the points, regardless of the last trick.
+    Double,
+    /// The points in the kitty aren't awarded at all.
+    NoBonus,
+}
+
+crate::impl_slog_value!(KittyPenalty);
+
+impl KittyPenalty {
+    /// The multiplier applied to the points in the kitty when the last trick is
+    /// won by a play whose largest unit has `largest_trick_unit_size` cards.
+    pub fn multiplier(self, largest_trick_unit_size: usize) -> usize {
+        match self {
+            KittyPenalty::Times => 2 * largest_trick_unit_size,
+            KittyPenalty::Power => 2usize.pow(largest_trick_unit_size as u32),
+            KittyPenalty::Double => 2,
+            KittyPenalty::NoBonus => 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PartialGameScoreResult {
     landlord_won: bool,
@@ -34,7 +100,7 @@ impl GameScoreResult {
     pub fn new(
         gsr: PartialGameScoreResult,
         bonus_level_policy: BonusLevelPolicy,
-        smaller_landlord_team_size: bool,
+        landlord_team_size: LandlordTeamSize,
     ) -> GameScoreResult {
         let PartialGameScoreResult {
             non_landlord_delta,
@@ -42,23 +108,23 @@ impl GameScoreResult {
             landlord_won,
         } = gsr;
 
-        if landlord_won
-            && bonus_level_policy == BonusLevelPolicy::BonusLevelForSmallerLandlordTeam
-            && smaller_landlord_team_size
-        {
-            GameScoreResult {
-                non_landlord_delta,
-                landlord_delta: landlord_delta + 1,
-                landlord_won,
-                landlord_bonus: true,
+        let bonus_landlord_delta = if landlord_won && landlord_team_size.is_smaller() {
+            match bonus_level_policy {
+                BonusLevelPolicy::NoBonusLevel => landlord_delta,
+                BonusLevelPolicy::BonusLevelForSmallerLandlordTeam => landlord_delta + 1,
+                BonusLevelPolicy::ScaleByLandlordTeamSize => (landlord_delta
+                    * landlord_team_size.expected)
+                    .div_ceil(landlord_team_size.actual.max(1)),
             }
         } else {
-            GameScoreResult {
-                non_landlord_delta,
-                landlord_delta,
-                landlord_won,
-                landlord_bonus: false,
-            }
+            landlord_delta
+        };
+
+        GameScoreResult {
+            non_landlord_delta,
+            landlord_delta: bonus_landlord_delta,
+            landlord_won,
+            landlord_bonus: bonus_landlord_delta > landlord_delta,
         }
     }
 }
@@ -78,9 +144,38 @@ pub struct GameScoringParameters {
     deadzone_size: usize,
     truncate_zero_crossing_window: bool,
     pub bonus_level_policy: BonusLevelPolicy,
+    /// Additional levels for the landlord's team when the attacking team wins
+    /// no points.
+    #[serde(default)]
+    pub small_shutout_bonus: usize,
+    /// Additional levels for the landlord's team when the attacking team wins
+    /// no tricks. Replaces the small shutout bonus.
+    #[serde(default)]
+    pub big_shutout_bonus: usize,
+    /// Additional levels for the winning team for each card in the
+    /// landlord's bid beyond the first, so that bigger bids carry bigger
+    /// swings in both directions.
+    #[serde(default)]
+    pub bid_defense_bonus: usize,
+    /// An explicit table of point thresholds to use instead of the
+    /// step-based windows above.
+    #[serde(default)]
+    #[slog(skip)]
+    pub custom_thresholds: Option<Vec<ScoringThreshold>>,
 }
 crate::impl_slog_value!(GameScoringParameters);
 
+/// A row of a user-defined scoring table. From `points` up to the next row's
+/// threshold, the landlord's team goes up `landlord_delta` levels and the
+/// attacking team goes up `non_landlord_delta` levels. The landlord's team
+/// wins the game whenever `landlord_delta` is nonzero.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoringThreshold {
+    pub points: isize,
+    pub landlord_delta: usize,
+    pub non_landlord_delta: usize,
+}
+
 impl Default for GameScoringParameters {
     fn default() -> Self {
         Self {
@@ -90,11 +185,27 @@ impl Default for GameScoringParameters {
             truncate_zero_crossing_window: true,
             step_adjustments: HashMap::new(),
             bonus_level_policy: BonusLevelPolicy::default(),
+            small_shutout_bonus: 0,
+            big_shutout_bonus: 0,
+            bid_defense_bonus: 0,
+            custom_thresholds: None,
         }
     }
 }
 
 impl GameScoringParameters {
+    pub fn shutout_bonus(&self, shutout: Option<Shutout>) -> usize {
+        match shutout {
+            None => 0,
+            Some(Shutout::Small) => self.small_shutout_bonus,
+            Some(Shutout::Big) => self.big_shutout_bonus,
+        }
+    }
+
+    pub fn bid_defense_bonus(&self, bid_size: usize) -> usize {
+        self.bid_defense_bonus * bid_size.saturating_sub(1)
+    }
+
     pub fn step_size(&self, decks: &[Deck]) -> Result<usize, Error> {
         let num_decks = decks.len();
         let total_points = decks.iter().map(|d| d.points() as isize).sum::<isize>();
@@ -118,6 +229,9 @@ impl GameScoringParameters {
     }
 
     pub fn materialize(&self, decks: &[Deck]) -> Result<MaterializedScoringParameters, Error> {
+        if let Some(ref table) = self.custom_thresholds {
+            return Self::materialize_table(table, decks);
+        }
         if self.num_steps_to_non_landlord_turnover == 0 {
             bail!("Landlord team must be able to win")
         }
@@ -178,6 +292,61 @@ impl GameScoringParameters {
             decks.iter().map(|d| d.points()).sum::<usize>() as isize,
         )
     }
+
+    fn materialize_table(
+        table: &[ScoringThreshold],
+        decks: &[Deck],
+    ) -> Result<MaterializedScoringParameters, Error> {
+        let total_points = decks.iter().map(|d| d.points()).sum::<usize>() as isize;
+        match table.first() {
+            Some(t) if t.points <= 0 => (),
+            _ => bail!("The scoring table must start at or below 0 points"),
+        }
+        // The last row extends just past the total number of points in the
+        // deck; beyond that, which is only reachable with the kitty bonus,
+        // the windows repeat as they would for the step-based scoring.
+        let ends = table
+            .iter()
+            .skip(1)
+            .map(|t| t.points)
+            .chain(std::iter::once(total_points + 5));
+
+        let mut landlord_wins = vec![];
+        let mut landlord_loses = vec![];
+        for (t, end) in table.iter().zip(ends) {
+            if t.points > total_points {
+                bail!(
+                    "Threshold of {} is more than the {} points in the deck",
+                    t.points,
+                    total_points
+                );
+            }
+            if t.landlord_delta > 0 && t.non_landlord_delta > 0 {
+                bail!("Only one team can go up levels at {} points", t.points);
+            }
+            if t.landlord_delta > 0 {
+                if !landlord_loses.is_empty() {
+                    bail!(
+                        "The landlord's team can't win at {} points after losing with fewer",
+                        t.points
+                    );
+                }
+                landlord_wins.push(LandlordWinningScoreSegment {
+                    start: t.points,
+                    end,
+                    landlord_delta: t.landlord_delta,
+                });
+            } else {
+                landlord_loses.push(LandlordLosingScoreSegment {
+                    start: t.points,
+                    end,
+                    non_landlord_delta: t.non_landlord_delta,
+                });
+            }
+        }
+
+        MaterializedScoringParameters::new(landlord_wins, landlord_loses, total_points)
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -383,7 +552,7 @@ impl Propagatable for LandlordLosingScoreSegment {
 pub fn explain_level_deltas(
     gsp: &GameScoringParameters,
     decks: &[Deck],
-    smaller_landlord_team_size: bool,
+    landlord_team_size: LandlordTeamSize,
 ) -> Result<Vec<(isize, GameScoreResult)>, Error> {
     gsp.materialize(decks)?.explain().map(|explanation| {
         explanation
@@ -391,24 +560,238 @@ pub fn explain_level_deltas(
             .map(|(pts, gsr)| {
                 (
                     pts,
-                    GameScoreResult::new(gsr, gsp.bonus_level_policy, smaller_landlord_team_size),
+                    GameScoreResult::new(gsr, gsp.bonus_level_policy, landlord_team_size),
                 )
             })
             .collect()
     })
 }
 
+/// Explains the result of each kind of shutout which earns a bonus, in the
+/// same form as `explain_level_deltas`.
+pub fn explain_shutout_bonuses(
+    gsp: &GameScoringParameters,
+    decks: &[Deck],
+    landlord_team_size: LandlordTeamSize,
+) -> Result<Vec<(Shutout, GameScoreResult)>, Error> {
+    [Shutout::Small, Shutout::Big]
+        .into_iter()
+        .filter(|s| gsp.shutout_bonus(Some(*s)) > 0)
+        .map(|s| {
+            Ok((
+                s,
+                compute_level_deltas(gsp, decks, 0, landlord_team_size, Some(s), 0)?,
+            ))
+        })
+        .collect()
+}
+
 pub fn compute_level_deltas(
     gsp: &GameScoringParameters,
     decks: &[Deck],
     non_landlords_points: isize,
-    smaller_landlord_team_size: bool,
+    landlord_team_size: LandlordTeamSize,
+    shutout: Option<Shutout>,
+    bid_size: usize,
 ) -> Result<GameScoreResult, Error> {
-    Ok(GameScoreResult::new(
+    let mut result = GameScoreResult::new(
         gsp.materialize(decks)?.score(non_landlords_points)?,
         gsp.bonus_level_policy,
-        smaller_landlord_team_size,
-    ))
+        landlord_team_size,
+    );
+    if result.landlord_won {
+        result.landlord_delta += gsp.shutout_bonus(shutout) + gsp.bid_defense_bonus(bid_size);
+    } else {
+        result.non_landlord_delta += gsp.bid_defense_bonus(bid_size);
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ScoringTeam {
+    /// The landlord's team.
+    Defending,
+    Attacking,
+    /// Whichever team wins the game.
+    Winning,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ScoringReason {
+    /// The number of points the attacking team won.
+    Points,
+    /// The bonus level for a landlord's team which is smaller than expected.
+    SmallTeamBonus,
+    /// The levels won by a landlord's team which is smaller than expected
+    /// are scaled up by how much smaller it is.
+    TeamSizeScaling,
+    /// The attacking team won no points.
+    SmallShutout,
+    /// The attacking team won no tricks.
+    BigShutout,
+    /// The levels for each card in the landlord's bid beyond the first.
+    BidDefense,
+}
+
+/// One outcome of the scoring rules, in a form which can be described in any
+/// supported language.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoringExplanation {
+    /// The attacking team's points from which this applies, if it depends on
+    /// them.
+    pub threshold: Option<isize>,
+    pub team: ScoringTeam,
+    pub delta: usize,
+    pub reason: ScoringReason,
+}
+
+impl ScoringExplanation {
+    pub fn format(&self, language: Language) -> String {
+        let n = self.delta;
+        let levels = if n == 1 { "level" } else { "levels" };
+        match (language, self.reason, self.team) {
+            (Language::English, ScoringReason::Points, ScoringTeam::Attacking) if n == 0 => {
+                "Attacking team wins, but doesn't level up.".to_string()
+            }
+            (Language::English, ScoringReason::Points, ScoringTeam::Attacking) => {
+                format!("Attacking team wins, and goes up {} {}.", n, levels)
+            }
+            (Language::English, ScoringReason::Points, _) => {
+                format!("Defending team wins, and goes up {} {}.", n, levels)
+            }
+            (Language::English, ScoringReason::SmallTeamBonus, _) => format!(
+                "If the defending team is unexpectedly small, they go up {} {}.",
+                n, levels
+            ),
+            (Language::English, ScoringReason::TeamSizeScaling, _) => {
+                "If the defending team is unexpectedly small, the levels they go up are multiplied by the expected team size over their actual size, rounding up.".to_string()
+            }
+            (Language::English, ScoringReason::SmallShutout, _) => format!(
+                "If the attacking team wins no points, the defending team goes up {} {}.",
+                n, levels
+            ),
+            (Language::English, ScoringReason::BigShutout, _) => format!(
+                "If the attacking team wins no tricks, the defending team goes up {} {}.",
+                n, levels
+            ),
+            (Language::English, ScoringReason::BidDefense, _) => format!(
+                "The winning team goes up {} more {} for each card in the landlord's bid beyond the first.",
+                n, levels
+            ),
+            (Language::SimplifiedChinese, ScoringReason::Points, ScoringTeam::Attacking)
+                if n == 0 =>
+            {
+                "闲家获胜，但不升级。".to_string()
+            }
+            (Language::SimplifiedChinese, ScoringReason::Points, ScoringTeam::Attacking) => {
+                format!("闲家获胜，升{}级。", n)
+            }
+            (Language::SimplifiedChinese, ScoringReason::Points, _) => {
+                format!("庄家获胜，升{}级。", n)
+            }
+            (Language::SimplifiedChinese, ScoringReason::SmallTeamBonus, _) => {
+                format!("如果庄家人数比预期少，升{}级。", n)
+            }
+            (Language::SimplifiedChinese, ScoringReason::TeamSizeScaling, _) => {
+                "如果庄家人数比预期少，升级数乘以预期人数与实际人数之比（向上取整）。".to_string()
+            }
+            (Language::SimplifiedChinese, ScoringReason::SmallShutout, _) => {
+                format!("如果闲家一分未得，庄家升{}级。", n)
+            }
+            (Language::SimplifiedChinese, ScoringReason::BigShutout, _) => {
+                format!("如果闲家一墩未得，庄家升{}级。", n)
+            }
+            (Language::SimplifiedChinese, ScoringReason::BidDefense, _) => {
+                format!("庄家亮主每多一张牌，获胜方多升{}级。", n)
+            }
+        }
+    }
+}
+
+/// Explains every outcome of the scoring rules: the result at each point
+/// threshold, followed by any bonuses.
+pub fn scoring_explanations(
+    gsp: &GameScoringParameters,
+    decks: &[Deck],
+) -> Result<Vec<ScoringExplanation>, Error> {
+    let mut explanations = vec![];
+    let full_team = LandlordTeamSize::full(2);
+    let deltas = explain_level_deltas(gsp, decks, full_team)?;
+    let bonus_deltas = explain_level_deltas(
+        gsp,
+        decks,
+        LandlordTeamSize {
+            actual: 1,
+            expected: 2,
+        },
+    )?;
+    for ((pts, res), (_, bonus_res)) in deltas.into_iter().zip(bonus_deltas) {
+        explanations.push(if res.landlord_won {
+            ScoringExplanation {
+                threshold: Some(pts),
+                team: ScoringTeam::Defending,
+                delta: res.landlord_delta,
+                reason: ScoringReason::Points,
+            }
+        } else {
+            ScoringExplanation {
+                threshold: Some(pts),
+                team: ScoringTeam::Attacking,
+                delta: res.non_landlord_delta,
+                reason: ScoringReason::Points,
+            }
+        });
+        if bonus_res.landlord_bonus
+            && gsp.bonus_level_policy == BonusLevelPolicy::BonusLevelForSmallerLandlordTeam
+        {
+            explanations.push(ScoringExplanation {
+                threshold: Some(pts),
+                team: ScoringTeam::Defending,
+                delta: bonus_res.landlord_delta,
+                reason: ScoringReason::SmallTeamBonus,
+            });
+        }
+    }
+    if gsp.bonus_level_policy == BonusLevelPolicy::ScaleByLandlordTeamSize {
+        explanations.push(ScoringExplanation {
+            threshold: None,
+            team: ScoringTeam::Defending,
+            delta: 0,
+            reason: ScoringReason::TeamSizeScaling,
+        });
+    }
+    for (shutout, res) in explain_shutout_bonuses(gsp, decks, full_team)? {
+        explanations.push(ScoringExplanation {
+            threshold: None,
+            team: ScoringTeam::Defending,
+            delta: res.landlord_delta,
+            reason: match shutout {
+                Shutout::Small => ScoringReason::SmallShutout,
+                Shutout::Big => ScoringReason::BigShutout,
+            },
+        });
+    }
+    if gsp.bid_defense_bonus > 0 {
+        explanations.push(ScoringExplanation {
+            threshold: None,
+            team: ScoringTeam::Winning,
+            delta: gsp.bid_defense_bonus,
+            reason: ScoringReason::BidDefense,
+        });
+    }
+    Ok(explanations)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NextThresholdReachability {
+    /// Whether the attacking team can still reach the next threshold.
+    pub reachable: bool,
+    /// The next point threshold which would change the outcome of the game.
+    pub threshold: isize,
+    /// How many more points the attacking team needs to reach `threshold`.
+    pub points_needed: isize,
+    /// How many points have not yet been played.
+    pub remaining_points: isize,
 }
 
 /// Computes whether the game can be considered "finished" (i.e. there are insufficient remaining
@@ -420,19 +803,48 @@ pub fn next_threshold_reachable(
     decks: &[Deck],
     non_landlords_points: isize,
     observed_points: isize,
-) -> Result<bool, Error> {
+) -> Result<NextThresholdReachability, Error> {
     let m = gsp.materialize(decks)?;
     let threshold = m.next_relevant_score(non_landlords_points)?.0;
     let total_points = m.total_points();
     let remaining_points = total_points - observed_points;
-    Ok(non_landlords_points + remaining_points >= threshold)
+    Ok(NextThresholdReachability {
+        reachable: non_landlords_points + remaining_points >= threshold,
+        threshold,
+        points_needed: threshold - non_landlords_points,
+        remaining_points,
+    })
+}
+
+/// Computes the bonus points awarded to the attacking team for the kitty, if they win the last
+/// trick with a play whose largest unit has `largest_trick_unit_size` cards.
+pub fn kitty_bonus(
+    kitty: &[Card],
+    kitty_penalty: KittyPenalty,
+    largest_trick_unit_size: usize,
+) -> usize {
+    kitty.iter().flat_map(|c| c.points()).sum::<usize>()
+        * kitty_penalty.multiplier(largest_trick_unit_size)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{compute_level_deltas, BonusLevelPolicy, GameScoreResult, GameScoringParameters};
+    use super::{
+        compute_level_deltas, explain_level_deltas, explain_shutout_bonuses, kitty_bonus,
+        next_threshold_reachable, scoring_explanations, BonusLevelPolicy, GameScoreResult,
+        GameScoringParameters, KittyPenalty, LandlordTeamSize, ScoringReason, ScoringThreshold,
+        Shutout,
+    };
+    use crate::language::Language;
 
     use crate::deck::Deck;
+    use crate::types::cards;
+
+    const FULL_TEAM: LandlordTeamSize = LandlordTeamSize::full(2);
+    const SMALL_TEAM: LandlordTeamSize = LandlordTeamSize {
+        actual: 1,
+        expected: 2,
+    };
 
     #[test]
     fn test_level_deltas() {
@@ -443,7 +855,7 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, -80, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, -80, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 5,
@@ -452,7 +864,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, -40, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, -40, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 4,
@@ -461,7 +873,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, -35, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, -35, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 3,
@@ -470,7 +882,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 0, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 0, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 3,
@@ -479,7 +891,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 5, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 5, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 2,
@@ -488,7 +900,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 35, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 35, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 2,
@@ -497,7 +909,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 40, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 40, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 1,
@@ -506,7 +918,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 75, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 75, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 1,
@@ -515,7 +927,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 80, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 80, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 0,
@@ -524,7 +936,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 115, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 115, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 0,
@@ -533,7 +945,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 120, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 120, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 1,
                 landlord_delta: 0,
@@ -542,7 +954,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 155, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 155, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 1,
                 landlord_delta: 0,
@@ -551,7 +963,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 160, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 160, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 2,
                 landlord_delta: 0,
@@ -560,7 +972,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 195, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 195, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 2,
                 landlord_delta: 0,
@@ -569,7 +981,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 200, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 200, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 3,
                 landlord_delta: 0,
@@ -578,7 +990,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 235, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 235, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 3,
                 landlord_delta: 0,
@@ -587,7 +999,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 240, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 240, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 4,
                 landlord_delta: 0,
@@ -596,7 +1008,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nobonus, &decks, 280, false,).unwrap(),
+            compute_level_deltas(&gsp_nobonus, &decks, 280, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 5,
                 landlord_delta: 0,
@@ -605,7 +1017,15 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&GameScoringParameters::default(), &decks, 0, true,).unwrap(),
+            compute_level_deltas(
+                &GameScoringParameters::default(),
+                &decks,
+                0,
+                SMALL_TEAM,
+                None,
+                0
+            )
+            .unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 4,
@@ -618,7 +1038,9 @@ mod tests {
                 &GameScoringParameters::default(),
                 &[Deck::default(), Deck::default(), Deck::default()],
                 0,
-                true,
+                SMALL_TEAM,
+                None,
+                0,
             )
             .unwrap(),
             (GameScoreResult {
@@ -633,7 +1055,9 @@ mod tests {
                 &GameScoringParameters::default(),
                 &[Deck::default(), Deck::default(), Deck::default()],
                 50,
-                true,
+                SMALL_TEAM,
+                None,
+                0,
             )
             .unwrap(),
             (GameScoreResult {
@@ -654,7 +1078,7 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, -80, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, -80, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 5,
@@ -663,7 +1087,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, -40, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, -40, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 4,
@@ -672,7 +1096,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, -35, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, -35, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 3,
@@ -681,7 +1105,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 0, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 0, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 3,
@@ -690,7 +1114,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 5, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 5, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 2,
@@ -699,7 +1123,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 35, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 35, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 2,
@@ -708,7 +1132,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 40, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 40, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 1,
@@ -717,7 +1141,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 75, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 75, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 0,
                 landlord_delta: 1,
@@ -726,7 +1150,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 80, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 80, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 1,
                 landlord_delta: 0,
@@ -735,7 +1159,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 115, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 115, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 1,
                 landlord_delta: 0,
@@ -744,7 +1168,7 @@ mod tests {
             })
         );
         assert_eq!(
-            compute_level_deltas(&gsp_nodeadzone, &decks, 120, false,).unwrap(),
+            compute_level_deltas(&gsp_nodeadzone, &decks, 120, FULL_TEAM, None, 0).unwrap(),
             (GameScoreResult {
                 non_landlord_delta: 2,
                 landlord_delta: 0,
@@ -753,4 +1177,225 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_team_size_scaling() {
+        let decks = [Deck::default(), Deck::default()];
+        let gsp = GameScoringParameters {
+            bonus_level_policy: BonusLevelPolicy::ScaleByLandlordTeamSize,
+            ..Default::default()
+        };
+        let deltas = |points, actual, expected| {
+            let r = compute_level_deltas(
+                &gsp,
+                &decks,
+                points,
+                LandlordTeamSize { actual, expected },
+                None,
+                0,
+            )
+            .unwrap();
+            (r.landlord_delta, r.non_landlord_delta, r.landlord_bonus)
+        };
+        assert_eq!(deltas(0, 2, 2), (3, 0, false));
+        // A 5-player game where the landlord went alone.
+        assert_eq!(deltas(0, 1, 2), (6, 0, true));
+        // A 7-player game where one friend was never revealed.
+        assert_eq!(deltas(0, 2, 3), (5, 0, true));
+        assert_eq!(deltas(40, 2, 3), (2, 0, true));
+        assert_eq!(deltas(40, 3, 3), (1, 0, false));
+        // The attacking team's levels are never scaled.
+        assert_eq!(deltas(160, 1, 2), (0, 2, false));
+    }
+
+    #[test]
+    fn test_shutout_bonus() {
+        let decks = [Deck::default(), Deck::default()];
+        let mut gsp = GameScoringParameters {
+            small_shutout_bonus: 1,
+            big_shutout_bonus: 2,
+            ..Default::default()
+        };
+        let landlord_delta = |points, shutout| {
+            compute_level_deltas(&gsp, &decks, points, FULL_TEAM, shutout, 0)
+                .unwrap()
+                .landlord_delta
+        };
+        assert_eq!(landlord_delta(0, None), 3);
+        assert_eq!(landlord_delta(0, Some(Shutout::Small)), 4);
+        assert_eq!(landlord_delta(0, Some(Shutout::Big)), 5);
+        // Penalties can hand the attacking team the game even if they were shut out.
+        assert_eq!(landlord_delta(200, Some(Shutout::Big)), 0);
+
+        assert_eq!(
+            explain_shutout_bonuses(&gsp, &decks, FULL_TEAM)
+                .unwrap()
+                .into_iter()
+                .map(|(s, r)| (s, r.landlord_delta))
+                .collect::<Vec<_>>(),
+            vec![(Shutout::Small, 4), (Shutout::Big, 5)]
+        );
+        gsp.small_shutout_bonus = 0;
+        assert_eq!(
+            explain_shutout_bonuses(&gsp, &decks, FULL_TEAM)
+                .unwrap()
+                .into_iter()
+                .map(|(s, _)| s)
+                .collect::<Vec<_>>(),
+            vec![Shutout::Big]
+        );
+    }
+
+    #[test]
+    fn test_bid_defense_bonus() {
+        let decks = [Deck::default(), Deck::default()];
+        let gsp = GameScoringParameters {
+            bid_defense_bonus: 1,
+            ..Default::default()
+        };
+        let deltas = |points, bid_size| {
+            let r = compute_level_deltas(&gsp, &decks, points, FULL_TEAM, None, bid_size).unwrap();
+            (r.landlord_delta, r.non_landlord_delta)
+        };
+        let base = |points| {
+            let r = compute_level_deltas(
+                &GameScoringParameters::default(),
+                &decks,
+                points,
+                FULL_TEAM,
+                None,
+                0,
+            )
+            .unwrap();
+            (r.landlord_delta, r.non_landlord_delta)
+        };
+        // A single-card bid (or no bid at all) doesn't change the outcome.
+        assert_eq!(deltas(0, 0), base(0));
+        assert_eq!(deltas(0, 1), base(0));
+        assert_eq!(deltas(0, 3), (base(0).0 + 2, 0));
+        assert_eq!(deltas(160, 2), (0, base(160).1 + 1));
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let decks = [Deck::default(), Deck::default()];
+        let row = |points, landlord_delta, non_landlord_delta| ScoringThreshold {
+            points,
+            landlord_delta,
+            non_landlord_delta,
+        };
+        let mut gsp = GameScoringParameters {
+            custom_thresholds: Some(vec![
+                row(0, 2, 0),
+                row(50, 1, 0),
+                row(100, 0, 0),
+                row(150, 0, 2),
+            ]),
+            ..Default::default()
+        };
+        let deltas = |gsp: &GameScoringParameters, points| {
+            let r = compute_level_deltas(gsp, &decks, points, FULL_TEAM, None, 0).unwrap();
+            (r.landlord_won, r.landlord_delta, r.non_landlord_delta)
+        };
+        assert_eq!(deltas(&gsp, 0), (true, 2, 0));
+        assert_eq!(deltas(&gsp, 45), (true, 2, 0));
+        assert_eq!(deltas(&gsp, 50), (true, 1, 0));
+        assert_eq!(deltas(&gsp, 100), (false, 0, 0));
+        assert_eq!(deltas(&gsp, 200), (false, 0, 2));
+        assert_eq!(
+            explain_level_deltas(&gsp, &decks, FULL_TEAM)
+                .unwrap()
+                .into_iter()
+                .map(|(pts, _)| pts)
+                .collect::<Vec<_>>(),
+            vec![0, 50, 100, 150, 205]
+        );
+        assert_eq!(
+            next_threshold_reachable(&gsp, &decks, 60, 100)
+                .unwrap()
+                .threshold,
+            100
+        );
+
+        for table in [
+            vec![row(10, 1, 0), row(100, 0, 1)],
+            vec![row(0, 1, 0), row(100, 0, 1), row(205, 0, 2)],
+            vec![row(0, 1, 0), row(100, 1, 1)],
+            vec![row(0, 1, 0), row(100, 0, 0), row(150, 1, 0)],
+            vec![row(0, 1, 0), row(100, 0, 1), row(100, 0, 2)],
+            vec![row(0, 1, 0)],
+        ] {
+            gsp.custom_thresholds = Some(table);
+            assert!(gsp.materialize(&decks).is_err());
+        }
+    }
+
+    #[test]
+    fn test_scoring_explanations() {
+        let decks = [Deck::default(), Deck::default()];
+        let gsp = GameScoringParameters {
+            bonus_level_policy: BonusLevelPolicy::BonusLevelForSmallerLandlordTeam,
+            big_shutout_bonus: 2,
+            bid_defense_bonus: 1,
+            ..Default::default()
+        };
+        let explanations = scoring_explanations(&gsp, &decks).unwrap();
+        let describe = |language| {
+            explanations
+                .iter()
+                .map(|e| (e.threshold, e.format(language)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            describe(Language::English)[..4],
+            [
+                (
+                    Some(0),
+                    "Defending team wins, and goes up 3 levels.".to_string()
+                ),
+                (
+                    Some(0),
+                    "If the defending team is unexpectedly small, they go up 4 levels.".to_string()
+                ),
+                (
+                    Some(5),
+                    "Defending team wins, and goes up 2 levels.".to_string()
+                ),
+                (
+                    Some(5),
+                    "If the defending team is unexpectedly small, they go up 3 levels.".to_string()
+                ),
+            ]
+        );
+        assert!(describe(Language::English).contains(&(
+            Some(80),
+            "Attacking team wins, but doesn't level up.".to_string()
+        )));
+        assert!(describe(Language::SimplifiedChinese)
+            .contains(&(Some(120), "闲家获胜，升1级。".to_string())));
+        assert_eq!(
+            explanations
+                .iter()
+                .filter(|e| e.threshold.is_none())
+                .map(|e| (e.reason, e.delta))
+                .collect::<Vec<_>>(),
+            vec![
+                (ScoringReason::BigShutout, 5),
+                (ScoringReason::BidDefense, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kitty_bonus() {
+        let kitty = [cards::S_5, cards::H_K, cards::C_3, cards::D_10];
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Times, 1), 50);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Times, 2), 100);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Power, 2), 100);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Power, 3), 200);
+        assert_eq!(kitty_bonus(&[cards::C_3], KittyPenalty::Power, 3), 0);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Double, 1), 50);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::Double, 3), 50);
+        assert_eq!(kitty_bonus(&kitty, KittyPenalty::NoBonus, 3), 0);
+    }
 }
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Error};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::hands::Hands;
+use crate::ordered_card::{MatchingCards, OrderedCard};
+use crate::trick::{
+    PlayCards, ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, Trick, TrickDrawPolicy,
+    TrickFormat, TrickPlayPolicy, UnitLike,
+};
+use crate::types::{Card, EffectiveSuit, PlayerID};
+
+/// Assumptions about the cards held by the players who have yet to play in the trick.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OpponentAssumptions {
+    /// Cards which the caller hasn't seen, and which may be held by any of the other players.
+    pub unseen_cards: Vec<Card>,
+    /// The number of cards held by each of the players who have yet to play.
+    pub hand_sizes: HashMap<PlayerID, usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TrickSimulationResult {
+    /// The number of simulated completions of the trick.
+    pub num_simulations: usize,
+    /// The fraction of simulations in which the caller's team won the trick.
+    pub win_probability: f64,
+    /// The average number of points won by the opposing team.
+    pub expected_points_conceded: f64,
+}
+
+pub struct SimulateTrick<'a> {
+    pub trick: &'a Trick,
+    pub id: PlayerID,
+    /// Must contain the caller's hand; other players' hands are ignored.
+    pub hands: &'a Hands,
+    pub cards: &'a [Card],
+    pub teammates: &'a [PlayerID],
+    pub assumptions: &'a OpponentAssumptions,
+    pub trick_draw_policy: TrickDrawPolicy,
+    pub trick_play_policy: TrickPlayPolicy,
+    pub throw_eval_policy: ThrowEvaluationPolicy,
+    pub throw_tie_policy: ThrowTiePolicy,
+    pub tractor_requirements: TractorRequirements,
+    pub num_simulations: usize,
+}
+
+///
+/// Simulates completions of the trick after the caller plays `cards`, by randomly dealing the
+/// unseen cards to the remaining players and having each of them make a simple greedy play.
+///
+/// Simulations in which a randomly-dealt player can't find a legal play are discarded.
+///
+pub fn simulate_trick(
+    args: SimulateTrick<'_>,
+    rng: &mut impl Rng,
+) -> Result<TrickSimulationResult, Error> {
+    let SimulateTrick {
+        trick,
+        id,
+        hands,
+        cards,
+        teammates,
+        assumptions,
+        trick_draw_policy,
+        trick_play_policy,
+        throw_eval_policy,
+        throw_tie_policy,
+        tractor_requirements,
+        num_simulations,
+    } = args;
+
+    if trick.next_player() != Some(id) {
+        bail!("It's not the caller's turn to play");
+    }
+    let others = trick.player_queue().skip(1).collect::<Vec<_>>();
+    let num_needed = others
+        .iter()
+        .map(|p| assumptions.hand_sizes.get(p).copied().unwrap_or(0))
+        .sum::<usize>();
+    if num_needed > assumptions.unseen_cards.len() {
+        bail!("Not enough unseen cards to deal to the remaining players");
+    }
+    let own_cards = Card::cards(hands.get(id)?.iter())
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut completed = 0;
+    let mut won = 0;
+    let mut points_conceded = 0;
+    let mut unseen = assumptions.unseen_cards.clone();
+
+    'simulation: for _ in 0..num_simulations {
+        unseen.shuffle(rng);
+        let mut deal = unseen.iter().copied();
+
+        let mut sim_hands = Hands::new(trick.player_queue());
+        sim_hands.set_trump(trick.trump());
+        sim_hands.add(id, own_cards.iter().copied())?;
+        for p in &others {
+            let n = assumptions.hand_sizes.get(p).copied().unwrap_or(0);
+            sim_hands.add(*p, deal.by_ref().take(n).collect::<Vec<_>>())?;
+        }
+
+        let mut sim_trick = trick.clone();
+        sim_trick.play_cards(PlayCards {
+            id,
+            hands: &mut sim_hands,
+            cards,
+            trick_draw_policy,
+            trick_play_policy,
+            throw_eval_policy,
+            throw_tie_policy,
+            format_hint: None,
+            hide_throw_halting_player: false,
+            tractor_requirements,
+        })?;
+
+        while let Some(next) = sim_trick.next_player() {
+            let tf = sim_trick
+                .trick_format()
+                .ok_or_else(|| anyhow!("Trick format should be set after the first play"))?;
+            let mut play = choose_greedy_follow(tf, sim_hands.get(next)?, trick_draw_policy);
+            if trick_play_policy == TrickPlayPolicy::MustBeatIfAble {
+                // If the greedy play doesn't beat the winner and something
+                // else would, play the lowest thing that does.
+                if sim_trick
+                    .can_play_cards(
+                        next,
+                        &sim_hands,
+                        &play,
+                        trick_draw_policy,
+                        trick_play_policy,
+                        throw_eval_policy,
+                        throw_tie_policy,
+                    )
+                    .is_err()
+                {
+                    let lowest = sim_trick
+                        .legal_follows(
+                            next,
+                            &sim_hands,
+                            trick_draw_policy,
+                            trick_play_policy,
+                            throw_eval_policy,
+                            throw_tie_policy,
+                        )?
+                        .next();
+                    if let Some(lowest) = lowest {
+                        play = lowest;
+                    }
+                }
+            }
+            if sim_trick
+                .play_cards(PlayCards {
+                    id: next,
+                    hands: &mut sim_hands,
+                    cards: &play,
+                    trick_draw_policy,
+                    trick_play_policy,
+                    throw_eval_policy,
+                    throw_tie_policy,
+                    format_hint: None,
+                    hide_throw_halting_player: false,
+                    tractor_requirements,
+                })
+                .is_err()
+            {
+                continue 'simulation;
+            }
+        }
+
+        let ended = sim_trick.complete()?;
+        completed += 1;
+        if ended.winner == id || teammates.contains(&ended.winner) {
+            won += 1;
+        } else {
+            points_conceded += ended.points.iter().flat_map(|c| c.points()).sum::<usize>();
+        }
+    }
+
+    Ok(TrickSimulationResult {
+        num_simulations: completed,
+        win_probability: if completed > 0 {
+            won as f64 / completed as f64
+        } else {
+            0.0
+        },
+        expected_points_conceded: if completed > 0 {
+            points_conceded as f64 / completed as f64
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Picks a play following `tf`: the first satisfiable decomposition of the format if possible,
+/// and otherwise the lowest cards available. If void in the suit, tries to trump in with the same
+/// format.
+pub fn choose_greedy_follow(
+    tf: &TrickFormat,
+    hand: &HashMap<Card, usize>,
+    trick_draw_policy: TrickDrawPolicy,
+) -> Vec<Card> {
+    let trump = tf.trump();
+    let size = tf.size();
+    let mut cards = Card::cards(hand.iter()).copied().collect::<Vec<_>>();
+    cards.sort_by(|a, b| trump.compare(*a, *b));
+    let (in_suit, off_suit): (Vec<Card>, Vec<Card>) = cards
+        .into_iter()
+        .partition(|c| trump.effective_suit(*c) == tf.suit());
+
+    let flatten = |units: Vec<MatchingCards>| {
+        units
+            .into_iter()
+            .flat_map(|u| {
+                u.into_iter()
+                    .flat_map(|(card, count)| std::iter::repeat_n(card.card, count))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    if in_suit.len() <= size {
+        if in_suit.is_empty() && tf.suit() != EffectiveSuit::Trump {
+            let trumps = off_suit
+                .iter()
+                .filter(|c| trump.effective_suit(**c) == EffectiveSuit::Trump)
+                .copied();
+            if let Some(format) = tf.decomposition(trick_draw_policy).next() {
+                if let Some(units) = UnitLike::check_play(
+                    OrderedCard::make_map(trumps, trump),
+                    format.into_iter(),
+                    trick_draw_policy,
+                    tf.tractor_requirements(),
+                )
+                .next()
+                {
+                    return flatten(units);
+                }
+            }
+        }
+        let num_off_suit = size - in_suit.len();
+        let mut play = in_suit;
+        play.extend(off_suit.into_iter().take(num_off_suit));
+        return play;
+    }
+
+    for format in tf.decomposition(trick_draw_policy) {
+        if let Some(units) = UnitLike::check_play(
+            OrderedCard::make_map(in_suit.iter().copied(), trump),
+            format.into_iter(),
+            trick_draw_policy,
+            tf.tractor_requirements(),
+        )
+        .next()
+        {
+            let mut play = flatten(units);
+            let mut remaining = in_suit.clone();
+            for card in &play {
+                if let Some(idx) = remaining.iter().position(|c| c == card) {
+                    remaining.remove(idx);
+                }
+            }
+            let num_remaining = size.saturating_sub(play.len());
+            play.extend(remaining.into_iter().take(num_remaining));
+            return play;
+        }
+    }
+    in_suit.into_iter().take(size).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::hands::Hands;
+    use crate::trick::{
+        ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, Trick, TrickDrawPolicy,
+        TrickPlayPolicy,
+    };
+    use crate::types::{cards::*, Card, Number, PlayerID, Suit, Trump};
+
+    use super::{simulate_trick, OpponentAssumptions, SimulateTrick};
+
+    const TRUMP: Trump = Trump::Standard {
+        number: Number::Four,
+        suit: Suit::Spades,
+    };
+    const P1: PlayerID = PlayerID(1);
+    const P2: PlayerID = PlayerID(2);
+    const P3: PlayerID = PlayerID(3);
+    const P4: PlayerID = PlayerID(4);
+
+    fn run(cards: &[Card], teammates: &[PlayerID], unseen_cards: Vec<Card>) -> (f64, f64) {
+        let trick = Trick::new(TRUMP, vec![P1, P2, P3, P4]);
+        let mut hands = Hands::new(vec![P1]);
+        hands.add(P1, vec![Card::BigJoker, H_3, H_K]).unwrap();
+        let assumptions = OpponentAssumptions {
+            unseen_cards,
+            hand_sizes: vec![(P2, 2), (P3, 2), (P4, 2)]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        };
+        let res = simulate_trick(
+            SimulateTrick {
+                trick: &trick,
+                id: P1,
+                hands: &hands,
+                cards,
+                teammates,
+                assumptions: &assumptions,
+                trick_draw_policy: TrickDrawPolicy::NoProtections,
+                trick_play_policy: TrickPlayPolicy::AnyLegalPlay,
+                throw_eval_policy: ThrowEvaluationPolicy::All,
+                throw_tie_policy: ThrowTiePolicy::LeaderWins,
+                tractor_requirements: TractorRequirements::default(),
+                num_simulations: 50,
+            },
+            &mut StdRng::seed_from_u64(0),
+        )
+        .unwrap();
+        assert_eq!(res.num_simulations, 50);
+        (res.win_probability, res.expected_points_conceded)
+    }
+
+    #[test]
+    fn test_simulate_unbeatable_lead() {
+        let unseen = vec![H_5, H_10, H_2, C_3, D_3, D_6];
+        assert_eq!(run(&[Card::BigJoker], &[], unseen), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_simulate_losing_lead() {
+        // Every opponent holds a heart higher than the three, and nobody can trump in.
+        let unseen = vec![H_5, H_5, H_10, H_10, H_A, H_A];
+        let (win_probability, _) = run(&[H_3], &[], unseen);
+        assert_eq!(win_probability, 0.0);
+    }
+}
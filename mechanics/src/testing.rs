@@ -0,0 +1,298 @@
+//! Proptest strategies for the types in this crate, so that code built on top
+//! of it can property-test play legality without writing its own generators.
+//! Enabled by the `testing` feature.
+
+pub use proptest;
+
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+
+use crate::bidding::{BidPolicy, BidReinforcementPolicy, BidTakebackPolicy, JokerBidPolicy};
+use crate::hands::Hands;
+use crate::scoring::{BonusLevelPolicy, KittyPenalty};
+use crate::trick::{
+    ThrowEvaluationPolicy, ThrowTiePolicy, TractorRequirements, TrickDrawPolicy, TrickFormat,
+    TrickPlayPolicy,
+};
+use crate::types::{Card, Number, PlayerID, Suit, Trump, ALL_SUITS, FULL_DECK};
+
+/// Implements `Arbitrary` for an enum whose variants don't carry any data,
+/// picking each of the listed variants with equal probability.
+#[macro_export]
+macro_rules! impl_arbitrary_enum {
+    ($x: ident { $($variant: ident),* $(,)? }) => {
+        impl $crate::testing::proptest::arbitrary::Arbitrary for $x {
+            type Parameters = ();
+            type Strategy = $crate::testing::proptest::sample::Select<$x>;
+
+            fn arbitrary_with(_: ()) -> Self::Strategy {
+                $crate::testing::proptest::sample::select(vec![$($x::$variant),*])
+            }
+        }
+    };
+}
+
+impl Arbitrary for Suit {
+    type Parameters = ();
+    type Strategy = proptest::sample::Select<Suit>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        select(&ALL_SUITS[..])
+    }
+}
+
+impl Arbitrary for Number {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Number>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (1u32..=13)
+            .prop_map(|n| Number::from_u32(n).unwrap())
+            .boxed()
+    }
+}
+
+/// Any card which can be dealt, i.e. never `Card::Unknown`.
+impl Arbitrary for Card {
+    type Parameters = ();
+    type Strategy = proptest::sample::Select<Card>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        select(&FULL_DECK[..])
+    }
+}
+
+impl Arbitrary for Trump {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Trump>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            (any::<Suit>(), any::<Number>())
+                .prop_map(|(suit, number)| Trump::Standard { suit, number }),
+            proptest::option::of(any::<Number>()).prop_map(|number| Trump::NoTrump { number }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for TractorRequirements {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<TractorRequirements>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (2usize..=4, 2usize..=4, any::<bool>())
+            .prop_map(
+                |(min_count, min_length, trump_rank_breaks_tractors)| TractorRequirements {
+                    min_count,
+                    min_length,
+                    trump_rank_breaks_tractors,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for BidPolicy {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BidPolicy>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(BidPolicy::JokerOrHigherSuit),
+            Just(BidPolicy::JokerOrGreaterLength),
+            Just(BidPolicy::GreaterLength),
+            (1usize..=3).prop_map(|margin| BidPolicy::GreaterLengthByMargin { margin }),
+            Just(BidPolicy::FirstBidWins),
+        ]
+        .boxed()
+    }
+}
+
+impl_arbitrary_enum!(TrickDrawPolicy {
+    NoProtections,
+    LongerTuplesProtected,
+    OnlyDrawTractorOnTractor,
+    LongerTuplesProtectedAndOnlyDrawTractorOnTractor,
+    NoFormatBasedDraw,
+    OnlyLargestThrownUnitDrawn,
+});
+impl_arbitrary_enum!(ThrowEvaluationPolicy {
+    All,
+    Highest,
+    TrickUnitLength,
+    AnyUnit,
+});
+impl_arbitrary_enum!(ThrowTiePolicy {
+    LeaderWins,
+    FollowerWins,
+});
+impl_arbitrary_enum!(TrickPlayPolicy {
+    AnyLegalPlay,
+    MustBeatIfAble,
+});
+impl_arbitrary_enum!(JokerBidPolicy {
+    BothTwoOrMore,
+    BothNumDecks,
+    LJNumDecksHJNumDecksLessOne,
+    Disabled,
+});
+impl_arbitrary_enum!(BidReinforcementPolicy {
+    ReinforceWhileWinning,
+    OverturnOrReinforceWhileWinning,
+    ReinforceWhileEquivalent,
+});
+impl_arbitrary_enum!(BidTakebackPolicy {
+    AllowBidTakeback,
+    NoBidTakeback,
+});
+impl_arbitrary_enum!(BonusLevelPolicy {
+    NoBonusLevel,
+    BonusLevelForSmallerLandlordTeam,
+    ScaleByLandlordTeamSize,
+});
+impl_arbitrary_enum!(KittyPenalty {
+    Times,
+    Power,
+    Double,
+    NoBonus,
+});
+
+fn decks(num_decks: usize) -> Vec<Card> {
+    (0..num_decks)
+        .flat_map(|_| FULL_DECK.iter().copied())
+        .collect()
+}
+
+/// Up to `max_cards` cards drawn from `num_decks` decks, so no card appears
+/// more than `num_decks` times.
+pub fn cards(num_decks: usize, max_cards: usize) -> impl Strategy<Value = Vec<Card>> {
+    let deck = decks(num_decks);
+    let max_cards = max_cards.min(deck.len());
+    subsequence(deck, 0..=max_cards).prop_shuffle()
+}
+
+/// Up to `max_cards` cards from `num_decks` decks which all have the same
+/// effective suit under `trump`, i.e. cards which could be led together.
+pub fn cards_in_one_suit(
+    trump: Trump,
+    num_decks: usize,
+    max_cards: usize,
+) -> impl Strategy<Value = Vec<Card>> {
+    let suits = FULL_DECK
+        .iter()
+        .map(|c| trump.effective_suit(*c))
+        .collect::<Vec<_>>();
+    (select(suits), cards(num_decks, 4 * max_cards)).prop_filter_map(
+        "no cards in the suit",
+        move |(suit, cards)| {
+            let cards = cards
+                .into_iter()
+                .filter(|c| trump.effective_suit(*c) == suit)
+                .take(max_cards)
+                .collect::<Vec<_>>();
+            Some(cards).filter(|c| !c.is_empty())
+        },
+    )
+}
+
+/// Deals `hand_size` cards from `num_decks` shuffled decks to each of
+/// `num_players` players, with an arbitrary trump.
+pub fn hands(
+    num_players: usize,
+    num_decks: usize,
+    hand_size: usize,
+) -> impl Strategy<Value = Hands> {
+    let deck = decks(num_decks);
+    assert!(
+        num_players * hand_size <= deck.len(),
+        "not enough cards to deal"
+    );
+    (Just(deck).prop_shuffle(), any::<Trump>()).prop_map(move |(deck, trump)| {
+        let mut hands = Hands::new((0..num_players).map(PlayerID));
+        for (id, hand) in deck.chunks(hand_size).take(num_players).enumerate() {
+            hands.add(PlayerID(id), hand.iter().copied()).unwrap();
+        }
+        hands.set_trump(trump);
+        hands
+    })
+}
+
+/// A trump, tractor requirements, and up to `max_cards` cards from
+/// `num_decks` decks which could be led together under that trump.
+pub fn lead(
+    num_decks: usize,
+    max_cards: usize,
+) -> impl Strategy<Value = (Trump, TractorRequirements, Vec<Card>)> {
+    (any::<Trump>(), any::<TractorRequirements>()).prop_flat_map(move |(trump, requirements)| {
+        (
+            Just(trump),
+            Just(requirements),
+            cards_in_one_suit(trump, num_decks, max_cards),
+        )
+    })
+}
+
+/// The format of a trick led with up to `max_cards` cards from `num_decks`
+/// decks.
+pub fn trick_format(num_decks: usize, max_cards: usize) -> impl Strategy<Value = TrickFormat> {
+    lead(num_decks, max_cards).prop_filter_map(
+        "not a valid lead",
+        |(trump, requirements, cards)| {
+            TrickFormat::from_cards(trump, requirements, &cards, None).ok()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::ordered_card::OrderedCard;
+    use crate::trick::{TrickDrawPolicy, TrickFormat, TrickUnit, UnitLike};
+    use crate::types::{Card, PlayerID};
+
+    use super::{hands, lead};
+
+    proptest! {
+            /// Every way that `find_plays` splits up a set of cards must be
+            /// accepted by `check_play` as a way of playing those cards.
+            #[test]
+            fn find_plays_round_trips_through_check_play(
+    (trump, requirements, cards) in lead(2, 10)) {
+                let plays = TrickUnit::find_plays(trump, requirements, cards.iter().copied())
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                prop_assert!(!plays.is_empty());
+                for units in plays {
+                    prop_assert_eq!(units.iter().map(|u| u.size()).sum::<usize>(), cards.len());
+                    let matches = UnitLike::check_play(
+                        OrderedCard::make_map(cards.iter().copied(), trump),
+                        units.iter().map(UnitLike::from),
+                        TrickDrawPolicy::NoProtections,
+                        requirements,
+                    );
+                    prop_assert!(matches.count() > 0, "{:?} doesn't match {:?}", units, cards);
+                }
+            }
+
+            /// The cards a trick was led with always match its format.
+            #[test]
+            fn trick_format_matches_lead((trump, requirements, cards) in lead(2, 10)) {
+                let format = TrickFormat::from_cards(trump, requirements, &cards, None).unwrap();
+                prop_assert!(format.matches(&cards).is_ok());
+                let hand = Card::count(cards.iter().copied());
+                prop_assert!(format.is_legal_play(&hand, &cards, TrickDrawPolicy::NoProtections));
+            }
+
+            #[test]
+            fn hands_are_dealt_from_the_decks(hands in hands(4, 2, 25)) {
+                prop_assert!(hands.trump().is_ok());
+                for id in 0..4 {
+                    let hand = hands.get(PlayerID(id)).unwrap();
+                    prop_assert_eq!(hand.values().sum::<usize>(), 25);
+                    prop_assert!(hand.values().all(|n| *n <= 2));
+                }
+            }
+        }
+}
@@ -1,11 +1,14 @@
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
 
+use itertools::Either;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::hands::{HandError, Hands};
+use crate::language::Language;
 use crate::ordered_card::{
     subsequent_decomposition_ordering, AdjacentTupleSizes, MatchingCards, MatchingCardsRef,
     OrderedCard,
@@ -41,6 +44,8 @@ pub enum TrickError {
     NonMatchingPlay,
     #[error("the proposed grouping is invalid")]
     NonMatchingProposal,
+    #[error("you have to beat the winning play if you can")]
+    MustBeat,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
@@ -55,6 +60,9 @@ pub enum TrickDrawPolicy {
     /// Both `LongerTuplesProtected` and `OnlyDrawTractorOnTractor`
     LongerTuplesProtectedAndOnlyDrawTractorOnTractor,
     NoFormatBasedDraw,
+    /// When following a throw, only its largest unit has to be drawn out;
+    /// the rest of the throw can be followed with any cards in the suit.
+    OnlyLargestThrownUnitDrawn,
 }
 
 crate::impl_slog_value!(TrickDrawPolicy);
@@ -65,16 +73,49 @@ pub enum ThrowEvaluationPolicy {
     All,
     Highest,
     TrickUnitLength,
+    /// A subsequent throw wins if it beats any one of the units in the
+    /// current winning play.
+    AnyUnit,
 }
 
 crate::impl_slog_value!(ThrowEvaluationPolicy);
 
+/// Which play wins when a subsequent play ties with the current winner.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum ThrowTiePolicy {
+    /// The earlier play keeps the trick.
+    #[default]
+    LeaderWins,
+    /// The later play takes the trick.
+    FollowerWins,
+}
+
+crate::impl_slog_value!(ThrowTiePolicy);
+
+/// Restrictions on following a trick, beyond matching its format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum TrickPlayPolicy {
+    /// Any play which follows the format is allowed.
+    #[default]
+    AnyLegalPlay,
+    /// A follower who can beat the play which is currently winning the trick
+    /// has to do so.
+    MustBeatIfAble,
+}
+
+crate::impl_slog_value!(TrickPlayPolicy);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TractorRequirements {
     /// The minimum number of cards in each unit of the tractor
     pub min_count: usize,
     /// The minimum length of the tractor
     pub min_length: usize,
+    /// Whether the trump rank breaks up tractors in the rest of its suit, e.g.
+    /// whether 7-9 is not a tractor when 8 is trump. By default, the removed
+    /// rank is a gap that tractors can span.
+    #[serde(default)]
+    pub trump_rank_breaks_tractors: bool,
 }
 
 impl Default for TractorRequirements {
@@ -82,6 +123,7 @@ impl Default for TractorRequirements {
         Self {
             min_count: 2,
             min_length: 2,
+            trump_rank_breaks_tractors: false,
         }
     }
 }
@@ -136,6 +178,13 @@ impl TrickUnit {
         }
     }
 
+    pub fn describe(&self) -> UnitDescription {
+        UnitDescription {
+            top_card: Some(self.last_card().card),
+            ..UnitLike::from(self).describe()
+        }
+    }
+
     pub fn find_plays(
         trump: Trump,
         tractor_requirements: TractorRequirements,
@@ -149,7 +198,19 @@ impl TrickUnit {
             original_num_cards += 1;
         }
 
-        find_plays_inner(&mut counts, original_num_cards, tractor_requirements, None)
+        if original_num_cards == 0 {
+            return vec![];
+        }
+        find_plays_inner(
+            &mut counts,
+            original_num_cards,
+            tractor_requirements,
+            None,
+            &mut HashMap::new(),
+        )
+        .iter()
+        .map(PlayNode::to_units)
+        .collect()
     }
 
     pub fn cards(&self) -> Vec<Card> {
@@ -176,9 +237,15 @@ pub struct TrickFormat {
     suit: EffectiveSuit,
     trump: Trump,
     units: Units,
+    #[serde(default)]
+    tractor_requirements: TractorRequirements,
 }
 
 impl TrickFormat {
+    pub fn tractor_requirements(&self) -> TractorRequirements {
+        self.tractor_requirements
+    }
+
     pub fn trump(&self) -> Trump {
         self.trump
     }
@@ -195,13 +262,20 @@ impl TrickFormat {
         &self,
         trick_draw_policy: TrickDrawPolicy,
     ) -> impl Iterator<Item = Vec<UnitLike>> {
-        let units = self.units.iter().map(UnitLike::from).collect();
-        let adj_tuples = self
-            .units
-            .iter()
-            .map(UnitLike::from)
-            .map(|u| u.adjacent_tuples)
-            .collect();
+        let units = match self.largest_thrown_unit(trick_draw_policy) {
+            // Only the largest unit is drawn out, and everything else in the
+            // throw can be matched by single cards.
+            Some(largest) => std::iter::once(UnitLike::from(largest))
+                .chain(
+                    std::iter::repeat_with(|| UnitLike {
+                        adjacent_tuples: vec![1],
+                    })
+                    .take(self.size() - largest.size()),
+                )
+                .collect::<Vec<_>>(),
+            None => self.units.iter().map(UnitLike::from).collect(),
+        };
+        let adj_tuples = units.iter().map(|u| u.adjacent_tuples.clone()).collect();
 
         // Include the current trick-format, and then the subsequent decomposition if we get that
         // far. Compute the latter lazily, since we usually won't.
@@ -225,6 +299,22 @@ impl TrickFormat {
         )
     }
 
+    /// The unit which constrains followers on its own, if this is a throw
+    /// and the policy draws out only its largest unit. Ties go to the first
+    /// such unit.
+    fn largest_thrown_unit(&self, trick_draw_policy: TrickDrawPolicy) -> Option<&TrickUnit> {
+        if trick_draw_policy != TrickDrawPolicy::OnlyLargestThrownUnitDrawn || self.units.len() < 2
+        {
+            return None;
+        }
+        self.units
+            .iter()
+            .fold(None, |largest: Option<&TrickUnit>, u| match largest {
+                Some(l) if l.size() >= u.size() => Some(l),
+                _ => Some(u),
+            })
+    }
+
     pub fn is_legal_play(
         &self,
         hand: &HashMap<Card, usize>,
@@ -272,6 +362,7 @@ impl TrickFormat {
                     OrderedCard::make_map(proposed.iter().copied(), self.trump),
                     requirement.iter().cloned(),
                     TrickDrawPolicy::NoProtections,
+                    self.tractor_requirements,
                 )
                 .next()
                 .is_some();
@@ -284,6 +375,7 @@ impl TrickFormat {
                     OrderedCard::make_map(available_cards.iter().copied(), self.trump),
                     requirement.iter().cloned(),
                     trick_draw_policy,
+                    self.tractor_requirements,
                 )
                 .next()
                 .is_some();
@@ -298,6 +390,100 @@ impl TrickFormat {
         }
     }
 
+    /// Every set of cards in `hand` which could legally be played to follow
+    /// this format, with the cards of each play in trump order. There can be
+    /// exponentially many, e.g. any ten of forty off-suit cards when the hand
+    /// is void, so they're produced lazily and callers should only take as
+    /// many as they can afford.
+    pub fn legal_plays<'a>(
+        &'a self,
+        hand: &'a HashMap<Card, usize>,
+        trick_draw_policy: TrickDrawPolicy,
+    ) -> impl Iterator<Item = Vec<Card>> + 'a {
+        let required = self.size();
+        let mut cards = hand
+            .iter()
+            .filter(|(c, ct)| **c != Card::Unknown && **ct > 0)
+            .map(|(c, ct)| (*c, *ct))
+            .collect::<Vec<_>>();
+        cards.sort_by(|(a, _), (b, _)| self.trump.compare(*a, *b));
+        let (in_suit, off_suit): (Vec<_>, Vec<_>) = cards
+            .into_iter()
+            .partition(|(c, _)| self.trump.effective_suit(*c) == self.suit);
+        let num_in_suit = in_suit.iter().map(|(_, ct)| *ct).sum::<usize>();
+
+        let expand = |combination: Vec<((Card, usize), usize)>| {
+            combination
+                .into_iter()
+                .flat_map(|((card, _), count)| std::iter::repeat_n(card, count))
+                .collect::<Vec<_>>()
+        };
+        if num_in_suit >= required {
+            // Only cards in the suit can be played, but whether they follow the
+            // format closely enough depends on the rest of the hand.
+            Either::Left(
+                crate::multiset_iter::multiset_k_combination_iter(in_suit, |(_, ct)| *ct, required)
+                    .map(expand)
+                    .filter(move |play| self.is_legal_play(hand, play, trick_draw_policy)),
+            )
+        } else {
+            // The whole suit has to be played, along with any of the other
+            // cards.
+            let suit = expand(in_suit.iter().map(|&(card, ct)| ((card, ct), ct)).collect());
+            Either::Right(
+                crate::multiset_iter::multiset_k_combination_iter(
+                    off_suit,
+                    |(_, ct)| *ct,
+                    required - num_in_suit,
+                )
+                .map(move |others| {
+                    let mut play = suit.clone();
+                    play.extend(expand(others));
+                    play
+                }),
+            )
+        }
+    }
+
+    /// The legal plays in `hand` which could win the trick: ones entirely in
+    /// the suit, or entirely trump if the hand is void in the suit, since
+    /// plays which mix suits can't win. There can be exponentially many, so
+    /// they're produced lazily, lowest first.
+    pub fn legal_plays_which_could_win<'a>(
+        &'a self,
+        hand: &'a HashMap<Card, usize>,
+        trick_draw_policy: TrickDrawPolicy,
+    ) -> impl Iterator<Item = Vec<Card>> + 'a {
+        let required = self.size();
+        let cards_in = |suit| {
+            hand.iter()
+                .filter(move |(c, ct)| {
+                    **c != Card::Unknown && **ct > 0 && self.trump.effective_suit(**c) == suit
+                })
+                .map(|(c, ct)| (*c, *ct))
+                .collect::<Vec<_>>()
+        };
+        let in_suit = cards_in(self.suit);
+        let num_in_suit = in_suit.iter().map(|(_, ct)| *ct).sum::<usize>();
+        let mut cards = if num_in_suit >= required {
+            in_suit
+        } else if num_in_suit == 0 {
+            cards_in(EffectiveSuit::Trump)
+        } else {
+            vec![]
+        };
+        cards.sort_by(|(a, _), (b, _)| self.trump.compare(*a, *b));
+
+        crate::multiset_iter::multiset_k_combination_iter(cards, |(_, ct)| *ct, required)
+            .map(|combination| {
+                combination
+                    .into_iter()
+                    .flat_map(|((card, _), count)| std::iter::repeat_n(card, count))
+                    .collect::<Vec<_>>()
+            })
+            .filter(move |play| self.is_legal_play(hand, play, trick_draw_policy))
+    }
+
     pub fn matches(&self, cards: &[Card]) -> Result<impl Iterator<Item = Units> + '_, TrickError> {
         let suit = self.trump.effective_suit(cards[0]);
         for card in cards {
@@ -318,6 +504,7 @@ impl TrickFormat {
             OrderedCard::make_map(cards.iter().copied(), self.trump),
             self.units.iter().map(UnitLike::from),
             TrickDrawPolicy::NoProtections,
+            self.tractor_requirements,
         )
         .peekable();
 
@@ -381,6 +568,7 @@ impl TrickFormat {
                             suit,
                             units: proposed,
                             trump,
+                            tractor_requirements,
                         });
                     }
                 }
@@ -394,6 +582,7 @@ impl TrickFormat {
                     suit,
                     units: sort(units),
                     trump,
+                    tractor_requirements,
                 })
             }
         }
@@ -413,7 +602,9 @@ pub struct PlayCards<'a, 'b, 'c> {
     pub hands: &'a mut Hands,
     pub cards: &'b [Card],
     pub trick_draw_policy: TrickDrawPolicy,
+    pub trick_play_policy: TrickPlayPolicy,
     pub throw_eval_policy: ThrowEvaluationPolicy,
+    pub throw_tie_policy: ThrowTiePolicy,
     pub format_hint: Option<&'c [TrickUnit]>,
     pub hide_throw_halting_player: bool,
     pub tractor_requirements: TractorRequirements,
@@ -467,27 +658,44 @@ impl Trick {
         self.trick_format.as_ref()
     }
 
+    /// The player whose cards are winning the trick so far.
+    pub fn current_winner(&self) -> Option<PlayerID> {
+        self.current_winner
+    }
+
     ///
     /// Determines whether the player can play the cards.
     ///
     /// Note: this does not account for throw validity, nor is it intended to
     /// catch all illegal plays.
     ///
+    #[allow(clippy::too_many_arguments)]
     pub fn can_play_cards(
         &self,
         id: PlayerID,
         hands: &Hands,
         cards: &[Card],
         trick_draw_policy: TrickDrawPolicy,
+        trick_play_policy: TrickPlayPolicy,
+        throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
     ) -> Result<(), TrickError> {
         hands.contains(id, cards.iter().cloned())?;
         match self.trick_format.as_ref() {
             Some(tf) => {
-                if tf.is_legal_play(hands.get(id)?, cards, trick_draw_policy) {
-                    Ok(())
-                } else {
-                    Err(TrickError::IllegalPlay)
+                let hand = hands.get(id)?;
+                if !tf.is_legal_play(hand, cards, trick_draw_policy) {
+                    return Err(TrickError::IllegalPlay);
                 }
+                if trick_play_policy == TrickPlayPolicy::MustBeatIfAble
+                    && !self.would_win(id, cards, throw_eval_policy, throw_tie_policy)
+                    && tf
+                        .legal_plays_which_could_win(hand, trick_draw_policy)
+                        .any(|play| self.would_win(id, &play, throw_eval_policy, throw_tie_policy))
+                {
+                    return Err(TrickError::MustBeat);
+                }
+                Ok(())
             }
             None => {
                 let num_suits = cards
@@ -504,6 +712,88 @@ impl Trick {
         }
     }
 
+    /// Every set of cards the player could legally play to follow the trick.
+    /// Leads aren't restricted to a format, so this is an error if nobody has
+    /// led yet. Like [`TrickFormat::legal_plays`], there can be exponentially
+    /// many, so callers should only take as many as they can afford.
+    pub fn legal_follows<'a>(
+        &'a self,
+        id: PlayerID,
+        hands: &'a Hands,
+        trick_draw_policy: TrickDrawPolicy,
+        trick_play_policy: TrickPlayPolicy,
+        throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
+    ) -> Result<impl Iterator<Item = Vec<Card>> + 'a, TrickError> {
+        let tf = self.trick_format.as_ref().ok_or(TrickError::OutOfOrder)?;
+        let hand = hands.get(id)?;
+        if trick_play_policy == TrickPlayPolicy::MustBeatIfAble {
+            let mut winning = tf
+                .legal_plays_which_could_win(hand, trick_draw_policy)
+                .filter(move |play| self.would_win(id, play, throw_eval_policy, throw_tie_policy))
+                .peekable();
+            if winning.peek().is_some() {
+                return Ok(Either::Left(winning));
+            }
+        }
+        Ok(Either::Right(tf.legal_plays(hand, trick_draw_policy)))
+    }
+
+    /// Whether `cards`, played to follow the trick, are a discard: none of
+    /// them are in the led suit or trump, so they can't win the trick.
+    pub fn is_discard(&self, cards: &[Card]) -> bool {
+        match self.trick_format.as_ref() {
+            Some(tf) => cards.iter().all(|c| {
+                let suit = self.trump.effective_suit(*c);
+                suit != tf.suit() && suit != EffectiveSuit::Trump
+            }),
+            None => false,
+        }
+    }
+
+    /// Hides the cards in other players' discards, for games where discards
+    /// are played face-down until the trick ends.
+    pub fn destructively_redact_discards_except_for_player(&mut self, id: PlayerID) {
+        let discards = self
+            .played_cards
+            .iter()
+            .map(|played| self.is_discard(&played.cards))
+            .collect::<Vec<_>>();
+        for (played, is_discard) in self.played_cards.iter_mut().zip(discards) {
+            if played.id != id && is_discard {
+                for card in &mut played.cards {
+                    *card = Card::Unknown;
+                }
+            }
+        }
+    }
+
+    /// Whether `cards` would take the trick from the current winner if `id`
+    /// played them next.
+    fn would_win(
+        &self,
+        id: PlayerID,
+        cards: &[Card],
+        throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
+    ) -> bool {
+        let mut cards = cards.to_vec();
+        cards.sort_by(|a, b| self.trump.compare(*a, *b));
+        let mut played_cards = self.played_cards.clone();
+        played_cards.push(PlayedCards {
+            id,
+            cards,
+            bad_throw_cards: vec![],
+            better_player: None,
+        });
+        Self::winner(
+            self.trick_format.as_ref(),
+            &played_cards,
+            throw_eval_policy,
+            throw_tie_policy,
+        ) == Some(id)
+    }
+
     ///
     /// Actually plays the cards, if possible. On error, does not modify any state.
     ///
@@ -518,7 +808,9 @@ impl Trick {
             hands,
             cards,
             trick_draw_policy,
+            trick_play_policy,
             throw_eval_policy,
+            throw_tie_policy,
             format_hint,
             hide_throw_halting_player,
             tractor_requirements,
@@ -527,7 +819,15 @@ impl Trick {
         if self.player_queue.front().cloned() != Some(id) {
             return Err(TrickError::OutOfOrder);
         }
-        self.can_play_cards(id, hands, cards, trick_draw_policy)?;
+        self.can_play_cards(
+            id,
+            hands,
+            cards,
+            trick_draw_policy,
+            trick_play_policy,
+            throw_eval_policy,
+            throw_tie_policy,
+        )?;
         let mut msgs = vec![];
         let mut cards = cards.to_vec();
         cards.sort_by(|a, b| self.trump.compare(*a, *b));
@@ -582,6 +882,7 @@ impl Trick {
                                         TractorRequirements {
                                             min_count: *count,
                                             min_length: members.len(),
+                                            ..tractor_requirements
                                         },
                                     );
                                     if !higher_tractors.is_empty() {
@@ -669,6 +970,7 @@ impl Trick {
             self.trick_format.as_ref(),
             &self.played_cards,
             throw_eval_policy,
+            throw_tie_policy,
         );
 
         Ok(msgs)
@@ -682,6 +984,7 @@ impl Trick {
         id: PlayerID,
         hands: &'_ mut Hands,
         throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
     ) -> Result<(), TrickError> {
         if self.played_cards.last().map(|p| p.id) == Some(id) {
             let played = self.played_cards.pop().unwrap();
@@ -696,6 +999,7 @@ impl Trick {
                 self.trick_format.as_ref(),
                 &self.played_cards,
                 throw_eval_policy,
+                throw_tie_policy,
             );
             Ok(())
         } else {
@@ -733,12 +1037,25 @@ impl Trick {
         }
     }
 
-    fn _defeats(m: &Units, winner: &Units, throw_eval_policy: ThrowEvaluationPolicy) -> bool {
+    fn _defeats(
+        m: &Units,
+        winner: &Units,
+        throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
+    ) -> bool {
+        let beats = |ord: Ordering| match throw_tie_policy {
+            ThrowTiePolicy::LeaderWins => ord == Ordering::Greater,
+            ThrowTiePolicy::FollowerWins => ord != Ordering::Less,
+        };
         match throw_eval_policy {
             ThrowEvaluationPolicy::All => m
                 .iter()
                 .zip(winner.iter())
-                .all(|(n, w)| n.first_card().cmp_effective(w.first_card()) == Ordering::Greater),
+                .all(|(n, w)| beats(n.first_card().cmp_effective(w.first_card()))),
+            ThrowEvaluationPolicy::AnyUnit => m
+                .iter()
+                .zip(winner.iter())
+                .any(|(n, w)| beats(n.first_card().cmp_effective(w.first_card()))),
             ThrowEvaluationPolicy::Highest => {
                 let n_max = m
                     .iter()
@@ -750,7 +1067,7 @@ impl Trick {
                     .map(|u| u.last_card())
                     .max()
                     .expect("trick format cannot be empty");
-                n_max.cmp_effective(w_max) == Ordering::Greater
+                beats(n_max.cmp_effective(w_max))
             }
             ThrowEvaluationPolicy::TrickUnitLength => {
                 // Don't worry about single cards if this is a throw with at
@@ -771,7 +1088,8 @@ impl Trick {
                     match iter.next() {
                         Some(Ordering::Equal) => {}
                         Some(Ordering::Greater) => break true,
-                        Some(Ordering::Less) | None => break false,
+                        Some(Ordering::Less) => break false,
+                        None => break beats(Ordering::Equal),
                     }
                 }
             }
@@ -782,6 +1100,7 @@ impl Trick {
         trick_format: Option<&'_ TrickFormat>,
         played_cards: &'_ [PlayedCards],
         throw_eval_policy: ThrowEvaluationPolicy,
+        throw_tie_policy: ThrowTiePolicy,
     ) -> Option<PlayerID> {
         match trick_format {
             Some(tf) => {
@@ -789,7 +1108,9 @@ impl Trick {
 
                 for (idx, pc) in played_cards.iter().enumerate().skip(1) {
                     if let Ok(mut mm) = tf.matches(&pc.cards) {
-                        let greater = mm.find(|m| Self::_defeats(m, &winner.1, throw_eval_policy));
+                        let greater = mm.find(|m| {
+                            Self::_defeats(m, &winner.1, throw_eval_policy, throw_tie_policy)
+                        });
                         if let Some(m) = greater {
                             winner = (idx, m);
                         }
@@ -815,62 +1136,37 @@ pub struct UnitLike {
 }
 
 impl UnitLike {
+    pub fn adjacent_tuples(&self) -> &[usize] {
+        &self.adjacent_tuples
+    }
+
     pub fn multi_description(iter: impl Iterator<Item = UnitLike>) -> String {
-        let mut counts = BTreeMap::new();
-        for u in iter {
-            *counts.entry(u.description()).or_default() += 1;
-        }
-        if counts.len() == 1 {
-            let (desc, ct) = counts
-                .into_iter()
-                .next()
-                .expect("only one item in description");
-            if ct == 1 {
-                format!("a {desc}")
-            } else {
-                format!("{ct} {desc}")
-            }
-        } else {
-            let mut s =
-                counts
-                    .into_iter()
-                    .fold(String::new(), |mut s, (desc, ct): (String, usize)| {
-                        use std::fmt::Write;
-                        let _ = write!(s, "{ct} {desc}, ");
-                        s
-                    });
-            s.pop();
-            s.pop();
-            s
-        }
+        UnitDescription::format_many(iter.map(|u| u.describe()), Language::English)
     }
 
     pub fn description(&self) -> String {
+        self.describe().format(Language::English)
+    }
+
+    pub fn describe(&self) -> UnitDescription {
         let length = self.adjacent_tuples.len();
-        if length == 1 {
-            Self::tuple_description(self.adjacent_tuples[0])
+        let kind = if length == 1 {
+            UnitKind::Tuple {
+                size: self.adjacent_tuples[0],
+            }
         } else if self.rectangular() {
-            let count = self.adjacent_tuples[0];
-
-            if length == 2 {
-                if count == 2 {
-                    "tractor".to_string()
-                } else {
-                    format!("tractor of {}s", Self::tuple_description(count))
-                }
-            } else {
-                format!("{}-tractor of {}s", length, Self::tuple_description(count))
+            UnitKind::Tractor {
+                size: self.adjacent_tuples[0],
             }
         } else {
-            let tuples = self.adjacent_tuples[1..length]
-                .iter()
-                .map(|l| Self::tuple_description(*l))
-                .collect::<Vec<_>>();
-            format!(
-                "{} followed immediately by a {}",
-                Self::tuple_description(self.adjacent_tuples[0]),
-                tuples.join(", ")
-            )
+            UnitKind::IrregularTractor {
+                sizes: self.adjacent_tuples.clone(),
+            }
+        };
+        UnitDescription {
+            kind,
+            length,
+            top_card: None,
         }
     }
 
@@ -895,16 +1191,28 @@ impl UnitLike {
         }
     }
 
+    fn tuple_description_zh(len: usize) -> String {
+        match len {
+            1 => "单张".to_string(),
+            2 => "对子".to_string(),
+            3 => "三条".to_string(),
+            4 => "四条".to_string(),
+            count => format!("{count}条"),
+        }
+    }
+
     pub fn check_play(
         counts: BTreeMap<OrderedCard, usize>,
         units: impl Iterator<Item = UnitLike>,
         trick_draw_policy: TrickDrawPolicy,
+        tractor_requirements: TractorRequirements,
     ) -> impl Iterator<Item = Vec<MatchingCards>> {
         let counts_ = counts.clone();
         let filter_func = move |matching: &MatchingCardsRef| match trick_draw_policy {
             TrickDrawPolicy::NoFormatBasedDraw
             | TrickDrawPolicy::NoProtections
-            | TrickDrawPolicy::OnlyDrawTractorOnTractor => true,
+            | TrickDrawPolicy::OnlyDrawTractorOnTractor
+            | TrickDrawPolicy::OnlyLargestThrownUnitDrawn => true,
             TrickDrawPolicy::LongerTuplesProtected
             | TrickDrawPolicy::LongerTuplesProtectedAndOnlyDrawTractorOnTractor => !matching
                 .iter()
@@ -915,8 +1223,113 @@ impl UnitLike {
             .map(|u| u.adjacent_tuples)
             .collect::<Vec<_>>();
 
-        crate::format_match::find_format_matches(units, counts)
-            .filter(move |m| m.iter().all(|mm| filter_func(mm)))
+        crate::format_match::find_format_matches(
+            units,
+            counts,
+            tractor_requirements.trump_rank_breaks_tractors,
+        )
+        .filter(move |m| m.iter().all(|mm| filter_func(mm)))
+    }
+}
+
+/// The shape of a unit, for describing it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum UnitKind {
+    /// Copies of a single card.
+    Tuple { size: usize },
+    /// Adjacent tuples which are all the same size.
+    Tractor { size: usize },
+    /// Adjacent tuples of different sizes, from the lowest card up.
+    IrregularTractor { sizes: Vec<usize> },
+}
+
+/// A unit, in a form which can be described in any supported language.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct UnitDescription {
+    pub kind: UnitKind,
+    /// The number of adjacent tuples in the unit.
+    pub length: usize,
+    /// The highest card in the unit, when it's made up of specific cards.
+    pub top_card: Option<Card>,
+}
+
+impl UnitDescription {
+    pub fn format(&self, language: Language) -> String {
+        let tuple = |size: usize| match language {
+            Language::English => UnitLike::tuple_description(size),
+            Language::SimplifiedChinese => UnitLike::tuple_description_zh(size),
+        };
+        match (language, &self.kind) {
+            (_, UnitKind::Tuple { size }) => tuple(*size),
+            (Language::English, UnitKind::Tractor { size: 2 }) if self.length == 2 => {
+                "tractor".to_string()
+            }
+            (Language::English, UnitKind::Tractor { size }) if self.length == 2 => {
+                format!("tractor of {}s", tuple(*size))
+            }
+            (Language::English, UnitKind::Tractor { size }) => {
+                format!("{}-tractor of {}s", self.length, tuple(*size))
+            }
+            (Language::English, UnitKind::IrregularTractor { sizes }) => format!(
+                "{} followed immediately by a {}",
+                tuple(sizes[0]),
+                sizes[1..]
+                    .iter()
+                    .map(|l| tuple(*l))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            (Language::SimplifiedChinese, UnitKind::Tractor { size: 2 }) if self.length == 2 => {
+                "拖拉机".to_string()
+            }
+            (Language::SimplifiedChinese, UnitKind::Tractor { size }) => {
+                format!("{}连{}拖拉机", self.length, tuple(*size))
+            }
+            (Language::SimplifiedChinese, UnitKind::IrregularTractor { sizes }) => format!(
+                "{}紧接着{}",
+                tuple(sizes[0]),
+                sizes[1..]
+                    .iter()
+                    .map(|l| tuple(*l))
+                    .collect::<Vec<_>>()
+                    .join("、")
+            ),
+        }
+    }
+
+    /// Describes a play made up of several units, e.g. "2 single, a pair".
+    pub fn format_many(
+        descriptions: impl IntoIterator<Item = UnitDescription>,
+        language: Language,
+    ) -> String {
+        let mut counts = BTreeMap::new();
+        for d in descriptions {
+            *counts.entry(d.format(language)).or_default() += 1;
+        }
+        match language {
+            Language::English if counts.len() == 1 => {
+                let (desc, ct) = counts
+                    .into_iter()
+                    .next()
+                    .expect("only one item in description");
+                if ct == 1 {
+                    format!("a {desc}")
+                } else {
+                    format!("{ct} {desc}")
+                }
+            }
+            Language::English => counts
+                .into_iter()
+                .map(|(desc, ct): (String, usize)| format!("{ct} {desc}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Language::SimplifiedChinese => counts
+                .into_iter()
+                .map(|(desc, ct): (String, usize)| format!("{ct}个{desc}"))
+                .collect::<Vec<_>>()
+                .join("，"),
+        }
     }
 }
 
@@ -924,7 +1337,7 @@ impl<'a> From<&'a TrickUnit> for UnitLike {
     fn from(u: &'a TrickUnit) -> Self {
         match u {
             TrickUnit::Tractor { ref members, count } => UnitLike {
-                adjacent_tuples: std::iter::repeat(*count).take(members.len()).collect(),
+                adjacent_tuples: std::iter::repeat_n(*count, members.len()).collect(),
             },
             TrickUnit::Repeated { count, .. } => UnitLike {
                 adjacent_tuples: vec![*count],
@@ -956,45 +1369,49 @@ fn without_trick_unit<T>(
     unit: &TrickUnit,
     mut f: impl FnMut(&mut BTreeMap<OrderedCard, usize>) -> T,
 ) -> T {
+    remove_trick_unit(counts, unit);
+
+    let res = f(counts);
+
     match unit {
         TrickUnit::Repeated { card, count } => {
-            let c = counts.get_mut(card).unwrap();
-            if *c == *count {
-                counts.remove(card);
-            } else {
-                *c -= count;
-            }
+            *counts.entry(*card).or_insert(0) += count;
         }
         TrickUnit::Tractor {
             ref members, count, ..
         } => {
             for card in members {
-                let c = counts.get_mut(card).unwrap();
-                if *c == *count {
-                    counts.remove(card);
-                } else {
-                    *c -= count;
-                }
+                *counts.entry(*card).or_insert(0) += count;
             }
         }
     }
 
-    let res = f(counts);
+    res
+}
 
+fn remove_trick_unit(counts: &mut BTreeMap<OrderedCard, usize>, unit: &TrickUnit) {
     match unit {
         TrickUnit::Repeated { card, count } => {
-            *counts.entry(*card).or_insert(0) += count;
+            let c = counts.get_mut(card).unwrap();
+            if *c == *count {
+                counts.remove(card);
+            } else {
+                *c -= count;
+            }
         }
         TrickUnit::Tractor {
             ref members, count, ..
         } => {
             for card in members {
-                *counts.entry(*card).or_insert(0) += count;
+                let c = counts.get_mut(card).unwrap();
+                if *c == *count {
+                    counts.remove(card);
+                } else {
+                    *c -= count;
+                }
             }
         }
     }
-
-    res
 }
 
 fn find_tractors_from_start(
@@ -1010,7 +1427,7 @@ fn find_tractors_from_start(
     }
 
     let mut next_cards: Vec<(OrderedCard, Members)> = card
-        .successor()
+        .tractor_successor(tractor_requirements.trump_rank_breaks_tractors)
         .into_iter()
         .map(|c| (c, vec![card]))
         .collect();
@@ -1031,8 +1448,12 @@ fn find_tractors_from_start(
                         count: min_count,
                     });
                 }
-                next_next_cards
-                    .extend(next_card.successor().into_iter().map(|n| (n, path.clone())));
+                next_next_cards.extend(
+                    next_card
+                        .tractor_successor(tractor_requirements.trump_rank_breaks_tractors)
+                        .into_iter()
+                        .map(|n| (n, path.clone())),
+                );
             }
         }
         next_cards = next_next_cards;
@@ -1043,16 +1464,11 @@ fn find_tractors_from_start(
     potential_starts
 }
 
-fn find_plays_inner(
-    counts: &mut BTreeMap<OrderedCard, usize>,
-    num_cards: usize,
+fn find_potential_starts(
+    counts: &BTreeMap<OrderedCard, usize>,
     tractor_requirements: TractorRequirements,
     min_start: Option<OrderedCard>,
-) -> Vec<Units> {
-    if num_cards == 0 {
-        return vec![];
-    }
-
+) -> Units {
     let mut iter = match min_start {
         Some(c) => counts.range(c..),
         None => counts.range(..),
@@ -1082,9 +1498,58 @@ fn find_plays_inner(
             });
         }
     }
+    potential_starts
+}
+
+/// A play under construction: the unit chosen at this point of the search,
+/// followed by the units chosen for the rest of the cards. Plays which only
+/// differ in their first units share the rest.
+struct PlayNode {
+    unit: TrickUnit,
+    rest: Option<Rc<PlayNode>>,
+}
+
+impl PlayNode {
+    /// Lists the units in the same order as `find_plays` always has, i.e.
+    /// from the last unit chosen to the first.
+    fn to_units(node: &Rc<PlayNode>) -> Units {
+        let mut units = vec![];
+        let mut node = Some(node);
+        while let Some(n) = node {
+            units.push(n.unit.clone());
+            node = n.rest.as_ref();
+        }
+        units.reverse();
+        units
+    }
+}
+
+/// The plays found for each set of remaining cards. Since starts are picked
+/// from the lowest card up, the remaining cards are never lower than
+/// `min_start`, so the plays only depend on which cards remain. With several
+/// decks, the same cards remain after many different combinations of units,
+/// e.g. a tractor of pairs followed by a pair, or a pair followed by a tractor
+/// of pairs, so each of them is only searched once.
+type FindPlaysMemo = HashMap<BTreeMap<OrderedCard, usize>, Rc<Vec<Rc<PlayNode>>>>;
 
-    if let Some(start) = potential_starts.iter().find(|u| u.size() == num_cards) {
-        vec![vec![start.clone()]]
+fn find_plays_inner(
+    counts: &mut BTreeMap<OrderedCard, usize>,
+    num_cards: usize,
+    tractor_requirements: TractorRequirements,
+    min_start: Option<OrderedCard>,
+    memo: &mut FindPlaysMemo,
+) -> Rc<Vec<Rc<PlayNode>>> {
+    if let Some(plays) = memo.get(counts) {
+        return plays.clone();
+    }
+
+    let potential_starts = find_potential_starts(counts, tractor_requirements, min_start);
+
+    let plays = if let Some(start) = potential_starts.iter().find(|u| u.size() == num_cards) {
+        vec![Rc::new(PlayNode {
+            unit: start.clone(),
+            rest: None,
+        })]
     } else {
         let mut plays = vec![];
         for start in potential_starts {
@@ -1094,14 +1559,115 @@ fn find_plays_inner(
                     num_cards - start.size(),
                     tractor_requirements,
                     Some(start.first_card()),
+                    memo,
                 );
-                plays.extend(sub_plays.into_iter().map(|mut play| {
-                    play.push(start.clone());
-                    play
+                plays.extend(sub_plays.iter().map(|rest| {
+                    Rc::new(PlayNode {
+                        unit: start.clone(),
+                        rest: Some(rest.clone()),
+                    })
                 }));
             });
         }
         plays
+    };
+    let plays = Rc::new(plays);
+    memo.insert(counts.clone(), plays.clone());
+    plays
+}
+
+/// A resumable equivalent of `TrickUnit::find_plays`, which walks the search
+/// tree with an explicit stack so that callers can bound the amount of work
+/// done at a time. Plays are produced in the same order as `find_plays`.
+#[derive(Debug, Clone)]
+pub struct FindPlaysSearch {
+    tractor_requirements: TractorRequirements,
+    stack: Vec<FindPlaysFrame>,
+    plays: Vec<Units>,
+}
+
+#[derive(Debug, Clone)]
+struct FindPlaysFrame {
+    counts: BTreeMap<OrderedCard, usize>,
+    num_cards: usize,
+    min_start: Option<OrderedCard>,
+    chosen: Units,
+}
+
+impl FindPlaysSearch {
+    pub fn new(
+        trump: Trump,
+        tractor_requirements: TractorRequirements,
+        iter: impl IntoIterator<Item = Card>,
+    ) -> Self {
+        let counts = OrderedCard::make_map(iter.into_iter(), trump);
+        let num_cards = counts.values().sum::<usize>();
+        let stack = if num_cards == 0 {
+            vec![]
+        } else {
+            vec![FindPlaysFrame {
+                counts,
+                num_cards,
+                min_start: None,
+                chosen: vec![],
+            }]
+        };
+        FindPlaysSearch {
+            tractor_requirements,
+            stack,
+            plays: vec![],
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Expands at most `max_steps` nodes of the search tree. Returns whether
+    /// the search is complete.
+    pub fn step(&mut self, max_steps: usize) -> bool {
+        for _ in 0..max_steps {
+            let frame = match self.stack.pop() {
+                Some(frame) => frame,
+                None => break,
+            };
+            let potential_starts =
+                find_potential_starts(&frame.counts, self.tractor_requirements, frame.min_start);
+
+            if let Some(start) = potential_starts
+                .iter()
+                .find(|u| u.size() == frame.num_cards)
+            {
+                let mut play = vec![start.clone()];
+                play.extend(frame.chosen.into_iter().rev());
+                self.plays.push(play);
+            } else {
+                // Push in reverse so that the first start is explored first.
+                for start in potential_starts.into_iter().rev() {
+                    let mut counts = frame.counts.clone();
+                    remove_trick_unit(&mut counts, &start);
+                    let mut chosen = frame.chosen.clone();
+                    let num_cards = frame.num_cards - start.size();
+                    let min_start = Some(start.first_card());
+                    chosen.push(start);
+                    self.stack.push(FindPlaysFrame {
+                        counts,
+                        num_cards,
+                        min_start,
+                        chosen,
+                    });
+                }
+            }
+        }
+        self.is_done()
+    }
+
+    pub fn plays(&self) -> &[Units] {
+        &self.plays
+    }
+
+    pub fn into_plays(self) -> Vec<Units> {
+        self.plays
     }
 }
 
@@ -1111,11 +1677,13 @@ mod tests {
     use std::iter::FromIterator;
 
     use crate::hands::Hands;
+    use crate::language::Language;
     use crate::types::{cards::*, Card, EffectiveSuit, Number, PlayerID, Suit, Trump};
 
     use super::{
-        OrderedCard, PlayCards, ThrowEvaluationPolicy, TractorRequirements, Trick, TrickDrawPolicy,
-        TrickEnded, TrickError, TrickFormat, TrickUnit, UnitLike,
+        FindPlaysSearch, OrderedCard, PlayCards, ThrowEvaluationPolicy, ThrowTiePolicy,
+        TractorRequirements, Trick, TrickDrawPolicy, TrickEnded, TrickError, TrickFormat,
+        TrickPlayPolicy, TrickUnit, UnitDescription, UnitKind, UnitLike,
     };
 
     const TRUMP: Trump = Trump::Standard {
@@ -1149,8 +1717,10 @@ mod tests {
                 hands: $hands,
                 cards: $cards,
                 trick_draw_policy: $tdp,
+                trick_play_policy: TrickPlayPolicy::default(),
                 throw_eval_policy: $tep,
                 format_hint: $fmt,
+                throw_tie_policy: ThrowTiePolicy::default(),
                 hide_throw_halting_player: $h,
                 tractor_requirements: TractorRequirements::default(),
             }
@@ -1161,8 +1731,10 @@ mod tests {
                 hands: $hands,
                 cards: $cards,
                 trick_draw_policy: $tdp,
+                trick_play_policy: TrickPlayPolicy::default(),
                 throw_eval_policy: $tep,
                 format_hint: None,
+                throw_tie_policy: ThrowTiePolicy::default(),
                 hide_throw_halting_player: false,
                 tractor_requirements: TractorRequirements::default(),
             }
@@ -1173,8 +1745,10 @@ mod tests {
                 hands: $hands,
                 cards: $cards,
                 trick_draw_policy: TrickDrawPolicy::NoProtections,
+                trick_play_policy: TrickPlayPolicy::default(),
                 throw_eval_policy: $tep,
                 format_hint: None,
+                throw_tie_policy: ThrowTiePolicy::default(),
                 hide_throw_halting_player: false,
                 tractor_requirements: TractorRequirements::default(),
             }
@@ -1185,8 +1759,10 @@ mod tests {
                 hands: $hands,
                 cards: $cards,
                 trick_draw_policy: TrickDrawPolicy::NoProtections,
+                trick_play_policy: TrickPlayPolicy::default(),
                 throw_eval_policy: ThrowEvaluationPolicy::All,
                 format_hint: None,
+                throw_tie_policy: ThrowTiePolicy::default(),
                 hide_throw_halting_player: false,
                 tractor_requirements: TractorRequirements::default(),
             }
@@ -1207,7 +1783,7 @@ mod tests {
                     HashSet::from_iter(vec![$(vec![$(vec![$($y),+]),+]),+])
                 );
                 for u in units {
-                    let mut iter = UnitLike::check_play(OrderedCard::make_map(cards.iter().copied(), TRUMP), u.iter().map(UnitLike::from), TrickDrawPolicy::NoProtections);
+                    let mut iter = UnitLike::check_play(OrderedCard::make_map(cards.iter().copied(), TRUMP), u.iter().map(UnitLike::from), TrickDrawPolicy::NoProtections, TractorRequirements::default());
                     let play = iter.next().unwrap();
                     assert_eq!(
                         u.iter().map(UnitLike::from).collect::<HashSet<_>>(),
@@ -1223,18 +1799,22 @@ mod tests {
         test_eq!(H_2, H_2, H_3, H_3; [[H_3, H_3], [H_2, H_2]]; TractorRequirements {
             min_length: 3,
             min_count: 2,
+            ..Default::default()
         });
         test_eq!(H_2, H_2, H_3, H_3, H_5, H_5; [[H_2, H_2, H_3, H_3, H_5, H_5]]; TractorRequirements {
             min_length: 3,
             min_count: 2,
+            ..Default::default()
         });
         test_eq!(H_2, H_2, H_3, H_3; [[H_3, H_3], [H_2, H_2]]; TractorRequirements {
             min_length: 3,
             min_count: 3,
+            ..Default::default()
         });
         test_eq!(H_2, H_2, H_2, H_3, H_3, H_3; [[H_2, H_2, H_2, H_3, H_3, H_3]]; TractorRequirements {
             min_length: 2,
             min_count: 3,
+            ..Default::default()
         });
         test_eq!(H_2, H_2, H_2, H_3, H_3; [[H_2], [H_2, H_2, H_3, H_3]], [[H_3, H_3], [H_2, H_2, H_2]]; TractorRequirements::default());
         test_eq!(H_2, H_2, H_3, H_3, H_3; [[H_3], [H_2, H_2, H_3, H_3]], [[H_3, H_3, H_3], [H_2, H_2]]; TractorRequirements::default());
@@ -1251,6 +1831,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trump_rank_breaks_tractors() {
+        let breaks = TractorRequirements {
+            trump_rank_breaks_tractors: true,
+            ..Default::default()
+        };
+        let cards = [H_3, H_3, H_5, H_5];
+        let num_tractors = |reqs| {
+            TrickUnit::find_plays(TRUMP, reqs, cards.iter().copied())
+                .into_iter()
+                .flatten()
+                .filter(|u| u.is_tractor())
+                .count()
+        };
+        // The four is trump, so 33-55 spans the gap by default.
+        assert_eq!(num_tractors(TractorRequirements::default()), 1);
+        assert_eq!(num_tractors(breaks), 0);
+
+        let can_play = |reqs| {
+            UnitLike::check_play(
+                OrderedCard::make_map(cards.iter().copied(), TRUMP),
+                std::iter::once(UnitLike::from(&vec![2, 2])),
+                TrickDrawPolicy::NoProtections,
+                reqs,
+            )
+            .next()
+            .is_some()
+        };
+        assert!(can_play(TractorRequirements::default()));
+        assert!(!can_play(breaks));
+
+        // Tractors that don't span the trump rank are unaffected.
+        assert_eq!(
+            TrickUnit::find_plays(TRUMP, breaks, [H_5, H_5, H_6, H_6].into_iter())
+                .into_iter()
+                .flatten()
+                .filter(|u| u.is_tractor())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_find_plays_search_matches_find_plays() {
+        let hands = vec![
+            vec![H_2, H_3, H_7],
+            vec![H_2, H_2, H_3, H_3, H_3, H_5, H_5, H_5],
+            vec![S_Q, S_Q, S_K, S_K, S_A, H_4, H_4, S_4, S_4],
+            vec![],
+        ];
+        for cards in hands {
+            let expected =
+                TrickUnit::find_plays(TRUMP, TractorRequirements::default(), cards.iter().copied())
+                    .into_iter()
+                    .collect::<Vec<_>>();
+
+            let mut search =
+                FindPlaysSearch::new(TRUMP, TractorRequirements::default(), cards.iter().copied());
+            while !search.step(1) {}
+            assert_eq!(search.into_plays(), expected);
+        }
+    }
+
     #[test]
     fn test_play_singles_trick() {
         let run = |tep: ThrowEvaluationPolicy| {
@@ -1278,6 +1921,7 @@ mod tests {
         run(ThrowEvaluationPolicy::All);
         run(ThrowEvaluationPolicy::Highest);
         run(ThrowEvaluationPolicy::TrickUnitLength);
+        run(ThrowEvaluationPolicy::AnyUnit);
     }
 
     #[test]
@@ -1307,6 +1951,7 @@ mod tests {
         run(ThrowEvaluationPolicy::All);
         run(ThrowEvaluationPolicy::Highest);
         run(ThrowEvaluationPolicy::TrickUnitLength);
+        run(ThrowEvaluationPolicy::AnyUnit);
     }
 
     #[test]
@@ -1344,6 +1989,7 @@ mod tests {
         run(ThrowEvaluationPolicy::All);
         run(ThrowEvaluationPolicy::Highest);
         run(ThrowEvaluationPolicy::TrickUnitLength);
+        run(ThrowEvaluationPolicy::AnyUnit);
     }
 
     #[test]
@@ -1381,6 +2027,7 @@ mod tests {
         run(ThrowEvaluationPolicy::All);
         run(ThrowEvaluationPolicy::Highest);
         run(ThrowEvaluationPolicy::TrickUnitLength);
+        run(ThrowEvaluationPolicy::AnyUnit);
     }
 
     #[test]
@@ -1524,6 +2171,7 @@ mod tests {
         let expected_tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Repeated {
                 count: 3,
                 card: oc!(S_2),
@@ -1550,6 +2198,7 @@ mod tests {
         let expected_tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Tractor {
                 count: 3,
                 members: vec![oc!(S_2), oc!(S_3), oc!(S_5)],
@@ -1582,6 +2231,7 @@ mod tests {
         let expected_tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![
                 TrickUnit::Tractor {
                     count: 2,
@@ -1627,6 +2277,7 @@ mod tests {
         let expected_tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![
                 TrickUnit::Repeated {
                     count: 1,
@@ -1659,11 +2310,251 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn test_legal_plays() {
+        let tf = TrickFormat {
+            suit: EffectiveSuit::Trump,
+            trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
+            units: vec![TrickUnit::Repeated {
+                count: 2,
+                card: oc!(S_3),
+            }],
+        };
+
+        // Pairs have to be played if there are any.
+        let hand = Card::count(vec![S_2, S_2, S_3, S_5, S_5, H_6]);
+        assert_eq!(
+            tf.legal_plays(&hand, TrickDrawPolicy::NoProtections)
+                .collect::<Vec<_>>(),
+            vec![vec![S_2, S_2], vec![S_5, S_5]]
+        );
+        assert_eq!(
+            tf.legal_plays(&hand, TrickDrawPolicy::NoFormatBasedDraw)
+                .count(),
+            5
+        );
+
+        // Without enough trump, all of it has to be played.
+        let hand = Card::count(vec![S_3, H_6, H_7, H_7]);
+        assert_eq!(
+            tf.legal_plays(&hand, TrickDrawPolicy::NoProtections)
+                .collect::<Vec<_>>(),
+            vec![vec![S_3, H_6], vec![S_3, H_7]]
+        );
+
+        for policy in [
+            TrickDrawPolicy::NoProtections,
+            TrickDrawPolicy::LongerTuplesProtected,
+            TrickDrawPolicy::NoFormatBasedDraw,
+        ] {
+            let hand = Card::count(vec![S_2, S_2, S_2, S_3, S_5, S_5, H_6, H_7]);
+            for play in tf.legal_plays(&hand, policy) {
+                assert!(tf.is_legal_play(&hand, &play, policy));
+            }
+        }
+    }
+
+    #[test]
+    fn test_must_beat_if_able() {
+        let mut hands = Hands::new(vec![P1, P2, P3, P4]);
+        hands.add(P1, vec![H_7]).unwrap();
+        hands.add(P2, vec![H_6, H_8]).unwrap();
+        hands.add(P3, vec![H_5, S_2]).unwrap();
+        hands.add(P4, vec![C_3, S_2]).unwrap();
+        let mut trick = Trick::new(TRUMP, vec![P1, P2, P3, P4]);
+        let check = |trick: &Trick, hands: &Hands, id, cards: &[Card]| {
+            trick.can_play_cards(
+                id,
+                hands,
+                cards,
+                TrickDrawPolicy::NoProtections,
+                TrickPlayPolicy::MustBeatIfAble,
+                ThrowEvaluationPolicy::All,
+                ThrowTiePolicy::default(),
+            )
+        };
+        let follows = |trick: &Trick, hands: &Hands, id| {
+            trick
+                .legal_follows(
+                    id,
+                    hands,
+                    TrickDrawPolicy::NoProtections,
+                    TrickPlayPolicy::MustBeatIfAble,
+                    ThrowEvaluationPolicy::All,
+                    ThrowTiePolicy::default(),
+                )
+                .unwrap()
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing to beat on the lead.
+        assert!(check(&trick, &hands, P1, &[H_7]).is_ok());
+        trick.play_cards(pc!(P1, &mut hands, &[H_7])).unwrap();
+
+        assert!(matches!(
+            check(&trick, &hands, P2, &[H_6]),
+            Err(TrickError::MustBeat)
+        ));
+        assert!(check(&trick, &hands, P2, &[H_8]).is_ok());
+        assert_eq!(follows(&trick, &hands, P2), vec![vec![H_8]]);
+        trick.play_cards(pc!(P2, &mut hands, &[H_8])).unwrap();
+
+        // Following suit comes first, even if trumping would win.
+        assert!(check(&trick, &hands, P3, &[H_5]).is_ok());
+        assert_eq!(follows(&trick, &hands, P3), vec![vec![H_5]]);
+        trick.play_cards(pc!(P3, &mut hands, &[H_5])).unwrap();
+
+        // Out of the suit, trumping is the only way to win.
+        assert!(matches!(
+            check(&trick, &hands, P4, &[C_3]),
+            Err(TrickError::MustBeat)
+        ));
+        assert_eq!(follows(&trick, &hands, P4), vec![vec![S_2]]);
+
+        // The default policy allows either.
+        assert!(trick
+            .can_play_cards(
+                P4,
+                &hands,
+                &[C_3],
+                TrickDrawPolicy::NoProtections,
+                TrickPlayPolicy::AnyLegalPlay,
+                ThrowEvaluationPolicy::All,
+                ThrowTiePolicy::default(),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_must_beat_if_able_large_throw() {
+        let mut hands = Hands::new(vec![P1, P2]);
+        hands
+            .add(P1, vec![H_3, H_5, H_6, H_7, H_8, H_9, H_10, H_J, H_Q, H_K])
+            .unwrap();
+        // Forty off-suit cards have far too many combinations to enumerate,
+        // but only trump could beat the throw.
+        let off_suit = [
+            C_3, C_5, C_6, C_7, C_8, C_9, C_10, C_J, C_Q, C_K, D_3, D_5, D_6, D_7, D_8, D_9, D_10,
+            D_J, D_Q, D_K,
+        ];
+        hands.add(P2, off_suit.iter().copied()).unwrap();
+        hands.add(P2, off_suit.iter().copied()).unwrap();
+        hands.add(P2, vec![S_3, S_5]).unwrap();
+        let mut trick = Trick::new(TRUMP, vec![P1, P2]);
+        trick
+            .play_cards(pc!(
+                P1,
+                &mut hands,
+                &[H_3, H_5, H_6, H_7, H_8, H_9, H_10, H_J, H_Q, H_K]
+            ))
+            .unwrap();
+
+        let tf = trick.trick_format().unwrap();
+        assert_eq!(
+            tf.legal_plays_which_could_win(hands.get(P2).unwrap(), TrickDrawPolicy::NoProtections)
+                .next(),
+            None
+        );
+        assert!(trick
+            .can_play_cards(
+                P2,
+                &hands,
+                &off_suit[..10],
+                TrickDrawPolicy::NoProtections,
+                TrickPlayPolicy::MustBeatIfAble,
+                ThrowEvaluationPolicy::All,
+                ThrowTiePolicy::default(),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_redact_discards() {
+        let mut hands = Hands::new(vec![P1, P2, P3, P4]);
+        hands.add(P1, vec![H_7]).unwrap();
+        hands.add(P2, vec![C_3]).unwrap();
+        hands.add(P3, vec![S_2]).unwrap();
+        hands.add(P4, vec![H_8]).unwrap();
+        let mut trick = Trick::new(TRUMP, vec![P1, P2, P3, P4]);
+        trick.play_cards(pc!(P1, &mut hands, &[H_7])).unwrap();
+        assert!(!trick.is_discard(&[H_8]));
+        assert!(!trick.is_discard(&[S_2]));
+        assert!(trick.is_discard(&[C_3]));
+        trick.play_cards(pc!(P2, &mut hands, &[C_3])).unwrap();
+        trick.play_cards(pc!(P3, &mut hands, &[S_2])).unwrap();
+        trick.play_cards(pc!(P4, &mut hands, &[H_8])).unwrap();
+
+        let mut redacted = trick.clone();
+        redacted.destructively_redact_discards_except_for_player(P1);
+        assert_eq!(
+            redacted
+                .played_cards()
+                .iter()
+                .map(|pc| pc.cards.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![H_7], vec![Card::Unknown], vec![S_2], vec![H_8]]
+        );
+
+        // The player who discarded can still see their own cards.
+        let mut redacted = trick.clone();
+        redacted.destructively_redact_discards_except_for_player(P2);
+        assert_eq!(redacted.played_cards()[1].cards, vec![C_3]);
+    }
+
+    #[test]
+    fn test_only_largest_thrown_unit_drawn() {
+        let tf = TrickFormat {
+            suit: EffectiveSuit::Hearts,
+            trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
+            units: vec![
+                TrickUnit::Repeated {
+                    count: 3,
+                    card: oc!(H_A),
+                },
+                TrickUnit::Repeated {
+                    count: 2,
+                    card: oc!(H_Q),
+                },
+            ],
+        };
+        let policy = TrickDrawPolicy::OnlyLargestThrownUnitDrawn;
+        let hand = Card::count(vec![H_5, H_5, H_5, H_8, H_8, H_9, C_3]);
+
+        // Across the whole format, both the triple and the pair are drawn.
+        assert!(!tf.is_legal_play(
+            &hand,
+            &[H_5, H_5, H_5, H_8, H_9],
+            TrickDrawPolicy::NoProtections
+        ));
+        assert!(tf.is_legal_play(
+            &hand,
+            &[H_5, H_5, H_5, H_8, H_8],
+            TrickDrawPolicy::NoProtections
+        ));
+
+        // Only the triple is drawn when it's the largest unit on its own.
+        assert!(tf.is_legal_play(&hand, &[H_5, H_5, H_5, H_8, H_9], policy));
+        assert!(tf.is_legal_play(&hand, &[H_5, H_5, H_5, H_8, H_8], policy));
+        assert!(!tf.is_legal_play(&hand, &[H_5, H_5, H_8, H_8, H_9], policy));
+        assert_eq!(
+            tf.decomposition(policy)
+                .next()
+                .unwrap()
+                .iter()
+                .map(|u| u.num_cards())
+                .collect::<Vec<_>>(),
+            vec![3, 1, 1]
+        );
+    }
+
     #[test]
     fn test_legal_play_pairs() {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Repeated {
                 count: 2,
                 card: oc!(S_3),
@@ -1687,6 +2578,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Repeated {
                 count: 3,
                 card: oc!(S_3),
@@ -1703,6 +2595,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Repeated {
                 count: 5,
                 card: oc!(S_3),
@@ -1744,6 +2637,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Tractor {
                 count: 2,
                 members: vec![oc!(S_2), oc!(S_3)],
@@ -1849,6 +2743,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![
                 TrickUnit::Repeated {
                     count: 2,
@@ -1892,6 +2787,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Repeated {
                 card: oc!(S_3),
                 count: 3,
@@ -1930,6 +2826,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Trump,
             trump: TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![TrickUnit::Tractor {
                 members: vec![oc!(S_6), oc!(S_7)],
                 count: 2,
@@ -1968,6 +2865,7 @@ mod tests {
         let tf = TrickFormat {
             suit: EffectiveSuit::Spades,
             trump: HEART_TRUMP,
+            tractor_requirements: TractorRequirements::default(),
             units: vec![
                 TrickUnit::Tractor {
                     members: vec![oc!(S_9, HEART_TRUMP), oc!(S_9, HEART_TRUMP)],
@@ -2065,6 +2963,7 @@ mod tests {
             TrickDrawPolicy::NoFormatBasedDraw,
             TrickDrawPolicy::OnlyDrawTractorOnTractor,
             TrickDrawPolicy::LongerTuplesProtectedAndOnlyDrawTractorOnTractor,
+            TrickDrawPolicy::OnlyLargestThrownUnitDrawn,
         ] {
             let mut hands = Hands::new(vec![P1, P2, P3, P4]);
 
@@ -2100,7 +2999,8 @@ mod tests {
                 TrickDrawPolicy::LongerTuplesProtected
                 | TrickDrawPolicy::NoProtections
                 | TrickDrawPolicy::OnlyDrawTractorOnTractor
-                | TrickDrawPolicy::LongerTuplesProtectedAndOnlyDrawTractorOnTractor => {
+                | TrickDrawPolicy::LongerTuplesProtectedAndOnlyDrawTractorOnTractor
+                | TrickDrawPolicy::OnlyLargestThrownUnitDrawn => {
                     // This play should not succeed, because P2 also has S_K, S_K which is a pair.
                     if let Err(TrickError::IllegalPlay) = trick.play_cards(pc!(
                         P2,
@@ -2188,6 +3088,63 @@ mod tests {
         assert_eq!(run(ThrowEvaluationPolicy::All), P3);
         // In the "highest" case, P4 wins because P4 played a higher card.
         assert_eq!(run(ThrowEvaluationPolicy::Highest), P4);
+        // In the "any unit" case, P4 wins because the big joker beats one of P3's cards.
+        assert_eq!(run(ThrowEvaluationPolicy::AnyUnit), P4);
+    }
+
+    #[test]
+    fn test_throw_tie_policy() {
+        let run = |tep: ThrowEvaluationPolicy, ttp: ThrowTiePolicy| {
+            let mut hands = Hands::new(vec![P1, P2, P3, P4]);
+            let mut trick = Trick::new(TRUMP, vec![P1, P2, P3, P4]);
+
+            for (id, cards) in [
+                (P1, [H_2, H_3]),
+                (P2, [H_5, H_7]),
+                (P3, [H_5, H_6]),
+                (P4, [H_5, H_7]),
+            ] {
+                // Deal each hand just before it's played, so that the leading throw succeeds.
+                hands.add(id, cards).unwrap();
+                trick
+                    .play_cards(PlayCards {
+                        id,
+                        hands: &mut hands,
+                        cards: &cards,
+                        trick_draw_policy: TrickDrawPolicy::NoProtections,
+                        trick_play_policy: TrickPlayPolicy::default(),
+                        throw_eval_policy: tep,
+                        throw_tie_policy: ttp,
+                        format_hint: None,
+                        hide_throw_halting_player: false,
+                        tractor_requirements: TractorRequirements::default(),
+                    })
+                    .unwrap();
+            }
+            trick.complete().unwrap().winner
+        };
+
+        // P4 ties P2 exactly, so the earlier play keeps the trick unless ties go to the follower.
+        assert_eq!(
+            run(ThrowEvaluationPolicy::All, ThrowTiePolicy::LeaderWins),
+            P2
+        );
+        assert_eq!(
+            run(ThrowEvaluationPolicy::All, ThrowTiePolicy::FollowerWins),
+            P4
+        );
+        assert_eq!(
+            run(
+                ThrowEvaluationPolicy::TrickUnitLength,
+                ThrowTiePolicy::FollowerWins
+            ),
+            P4
+        );
+        // P3 only ties one of P2's cards, which isn't enough to take the trick.
+        assert_eq!(
+            run(ThrowEvaluationPolicy::AnyUnit, ThrowTiePolicy::LeaderWins),
+            P2
+        );
     }
 
     #[test]
@@ -2342,4 +3299,55 @@ mod tests {
         let TrickEnded { winner, .. } = f(ThrowEvaluationPolicy::TrickUnitLength);
         assert_eq!(winner, P4);
     }
+
+    #[test]
+    fn test_unit_descriptions() {
+        let tractor = TrickUnit::Tractor {
+            count: 2,
+            members: vec![oc!(S_5), oc!(S_6)],
+        };
+        let single = TrickUnit::Repeated {
+            count: 1,
+            card: oc!(S_A),
+        };
+        assert_eq!(
+            tractor.describe(),
+            UnitDescription {
+                kind: UnitKind::Tractor { size: 2 },
+                length: 2,
+                top_card: Some(S_6),
+            }
+        );
+
+        let describe = |units: &[UnitLike], language| {
+            UnitDescription::format_many(units.iter().map(|u| u.describe()), language)
+        };
+        let throw = [
+            UnitLike::from(&tractor),
+            UnitLike::from(&single),
+            UnitLike::from(&single),
+        ];
+        assert_eq!(
+            describe(&throw, Language::English),
+            UnitLike::multi_description(throw.iter().cloned())
+        );
+        assert_eq!(describe(&throw, Language::English), "2 single, 1 tractor");
+        assert_eq!(
+            describe(&throw, Language::SimplifiedChinese),
+            "2个单张，1个拖拉机"
+        );
+        for (tuples, english, chinese) in [
+            (vec![3], "a triple", "1个三条"),
+            (vec![3, 3, 3], "a 3-tractor of triples", "1个3连三条拖拉机"),
+            (
+                vec![3, 2],
+                "a triple followed immediately by a pair",
+                "1个三条紧接着对子",
+            ),
+        ] {
+            let unit = [UnitLike::from(&tuples)];
+            assert_eq!(describe(&unit, Language::English), english);
+            assert_eq!(describe(&unit, Language::SimplifiedChinese), chinese);
+        }
+    }
 }
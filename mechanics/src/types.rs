@@ -302,6 +302,67 @@ pub enum EffectiveSuit {
     Trump,
 }
 
+/// The order in which suits are displayed when cards are sorted.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, JsonSchema, Hash, Eq, PartialEq, Default)]
+pub enum SuitOrder {
+    /// Alternating colors, ending with the trump suit and then the trump
+    /// cards.
+    #[default]
+    TrumpLast,
+    /// The trump cards, and then alternating colors starting with the trump
+    /// suit.
+    TrumpFirst,
+    /// ♧ ♢ ♤ ♡ regardless of the trump suit, and then the trump cards.
+    FixedSuitsTrumpLast,
+    /// The trump cards, and then ♧ ♢ ♤ ♡ regardless of the trump suit.
+    FixedSuitsTrumpFirst,
+}
+
+crate::impl_slog_value!(SuitOrder);
+
+impl SuitOrder {
+    fn trump_first(self) -> bool {
+        matches!(
+            self,
+            SuitOrder::TrumpFirst | SuitOrder::FixedSuitsTrumpFirst
+        )
+    }
+
+    /// The position of `suit` when displaying cards with `trump`.
+    pub fn ordinal(self, trump: Trump, suit: EffectiveSuit) -> usize {
+        let suit_position = |s: Suit| match s {
+            Suit::Clubs => 0,
+            Suit::Diamonds => 1,
+            Suit::Spades => 2,
+            Suit::Hearts => 3,
+        };
+        let rotation = match (self, trump.suit()) {
+            (SuitOrder::TrumpLast, Some(s)) => suit_position(s) + 1,
+            (SuitOrder::TrumpFirst, Some(s)) => suit_position(s),
+            _ => 0,
+        };
+        let offset = if self.trump_first() { 2 } else { 1 };
+        let position = match suit {
+            EffectiveSuit::Unknown => return 0,
+            EffectiveSuit::Trump if self.trump_first() => return 1,
+            EffectiveSuit::Trump => return 5,
+            EffectiveSuit::Clubs => suit_position(Suit::Clubs),
+            EffectiveSuit::Diamonds => suit_position(Suit::Diamonds),
+            EffectiveSuit::Spades => suit_position(Suit::Spades),
+            EffectiveSuit::Hearts => suit_position(Suit::Hearts),
+        };
+        offset + (position + 4 - rotation) % 4
+    }
+
+    /// Compares cards for display, first by suit, and then by their value
+    /// within the suit.
+    pub fn compare(self, trump: Trump, card1: Card, card2: Card) -> Ordering {
+        self.ordinal(trump, trump.effective_suit(card1))
+            .cmp(&self.ordinal(trump, trump.effective_suit(card2)))
+            .then(trump.compare(card1, card2))
+    }
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
 pub struct CardInfo {
     pub value: char,
@@ -1117,7 +1178,7 @@ impl Rank {
 
 #[cfg(test)]
 mod tests {
-    use super::{cards, Card, Number, Rank, Suit, Trump, FULL_DECK};
+    use super::{cards, Card, Number, Rank, Suit, SuitOrder, Trump, ALL_SUITS, FULL_DECK};
 
     #[test]
     fn test_char_roundtrip() {
@@ -1162,6 +1223,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_suit_order() {
+        let trumps = ALL_SUITS
+            .iter()
+            .map(|suit| Trump::Standard {
+                suit: *suit,
+                number: Number::Two,
+            })
+            .chain(std::iter::once(Trump::NoTrump {
+                number: Some(Number::Two),
+            }));
+        for trump in trumps {
+            for a in FULL_DECK.iter() {
+                for b in FULL_DECK.iter() {
+                    assert_eq!(
+                        SuitOrder::TrumpLast.compare(trump, *a, *b),
+                        trump.compare(*a, *b)
+                    );
+                }
+            }
+        }
+
+        let trump = Trump::Standard {
+            number: Number::Two,
+            suit: Suit::Spades,
+        };
+        let hand = [
+            cards::H_4,
+            cards::S_3,
+            cards::C_4,
+            cards::H_2,
+            cards::D_3,
+            Card::BigJoker,
+        ];
+        let sorted = |order: SuitOrder| {
+            let mut hand = hand;
+            hand.sort_by(|a, b| order.compare(trump, *a, *b));
+            hand.iter().map(|c| format!("{c:?}")).collect::<String>()
+        };
+        assert_eq!(sorted(SuitOrder::TrumpLast), "4♡4♧3♢3♤2♡HJ");
+        assert_eq!(sorted(SuitOrder::TrumpFirst), "3♤2♡HJ4♡4♧3♢");
+        assert_eq!(sorted(SuitOrder::FixedSuitsTrumpLast), "4♧3♢4♡3♤2♡HJ");
+        assert_eq!(sorted(SuitOrder::FixedSuitsTrumpFirst), "3♤2♡HJ4♧3♢4♡");
+    }
+
     #[test]
     fn test_adjacent() {
         let trump = Trump::Standard {
@@ -29,6 +29,41 @@ impl<S: State> HashMapStorage<S> {
         }
     }
 
+    /// Drops states which haven't been updated in `idle_after` from memory,
+    /// returning their keys. Unlike pruning, subscribers are kept, so they
+    /// hear about the state if it's brought back.
+    pub(crate) async fn evict_idle(self, idle_after: Duration) -> Vec<Vec<u8>> {
+        let mut m = self.state_map.lock().await;
+        let idle = m
+            .iter()
+            .filter(|(_, (_, t))| t.elapsed() >= idle_after)
+            .map(|(k, _)| k.to_vec())
+            .collect::<Vec<_>>();
+        for k in &idle {
+            m.remove(k);
+        }
+        idle
+    }
+
+    /// Drops the state for `key` and its subscribers, whose channels close.
+    pub(crate) async fn remove(self, key: &[u8]) {
+        self.state_map.lock().await.remove(key);
+        self.subscribers.lock().await.remove(key);
+    }
+
+    /// Puts `state` into memory unless there's already a state for its key,
+    /// returning whether it was put.
+    pub(crate) async fn put_if_absent(self, state: S) -> bool {
+        let mut m = self.state_map.lock().await;
+        if m.contains_key(state.key()) {
+            return false;
+        }
+        *self.num_games_created.lock().await += 1;
+        info!(self.logger, "Initializing state"; "key" => stringify(state.key()));
+        m.insert(state.key().to_vec(), (state, Instant::now()));
+        true
+    }
+
     fn publish(
         s: &mut HashMap<Vec<u8>, HashMap<usize, mpsc::UnboundedSender<S::Message>>>,
         key: &[u8],
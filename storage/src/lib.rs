@@ -1,7 +1,23 @@
 mod hash_map_storage;
+mod redis_rooms;
 mod redis_storage;
+mod sqlite_game_history;
+mod sqlite_game_results;
+mod sqlite_presets;
+mod sqlite_storage;
+mod sqlite_users;
 mod storage;
 
 pub use crate::hash_map_storage::HashMapStorage;
+pub use crate::redis_rooms::RedisRooms;
 pub use crate::redis_storage::{RedisStorage, RedisStorageError};
+pub use crate::sqlite_game_history::{FinishedGame, GameHistoryError, PastGame, SqliteGameHistory};
+pub use crate::sqlite_game_results::{
+    GameResult, GameResultError, LevelChange, PlayerStats, SqliteGameResults,
+};
+pub use crate::sqlite_presets::{Preset, PresetError, SqlitePresets};
+pub use crate::sqlite_storage::{ArchivePolicy, ArchivedState, SqliteStorage, SqliteStorageError};
+pub use crate::sqlite_users::{
+    CareerRound, CareerStats, SqliteUsers, UnlockedAchievement, User, UserError, INITIAL_RATING,
+};
 pub use crate::storage::{State, Storage};
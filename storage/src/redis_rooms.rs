@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use redis::{aio::ConnectionManager, Script};
+
+use crate::redis_storage::RedisStorageError;
+
+/// Claims the room for the instance if nobody has it, refreshes the claim if
+/// the instance already has it, and returns whoever has it either way.
+const CLAIM_SCRIPT: &str = r#"
+local owner = redis.call('GET', KEYS[1])
+if not owner then
+    redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[2])
+    return ARGV[1]
+end
+if owner == ARGV[1] then
+    redis.call('EXPIRE', KEYS[1], ARGV[2])
+end
+return owner
+"#;
+
+/// Tracks which server instance hosts each room, so that several instances
+/// can share the rooms between them. A room belongs to the first instance
+/// to claim it, until that instance stops refreshing its claim.
+#[derive(Clone)]
+pub struct RedisRooms {
+    connection_manager: ConnectionManager,
+    ttl: Duration,
+}
+
+impl RedisRooms {
+    pub async fn new(client: redis::Client, ttl: Duration) -> Result<Self, RedisStorageError> {
+        Ok(Self {
+            connection_manager: client.get_tokio_connection_manager().await?,
+            ttl,
+        })
+    }
+
+    fn room_key(room: &[u8]) -> Vec<u8> {
+        let mut key = b"room-owner-".to_vec();
+        key.extend_from_slice(room);
+        key
+    }
+
+    fn snapshot_key(room: &[u8]) -> Vec<u8> {
+        let mut key = b"room-snapshot-".to_vec();
+        key.extend_from_slice(room);
+        key
+    }
+
+    /// Claims `room` for `instance`, unless another instance already hosts
+    /// it. Returns the instance which hosts the room.
+    pub async fn claim(&self, room: &[u8], instance: &str) -> Result<String, RedisStorageError> {
+        Ok(Script::new(CLAIM_SCRIPT)
+            .key(Self::room_key(room))
+            .arg(instance)
+            .arg(self.ttl.as_secs().max(1))
+            .invoke_async(&mut self.connection_manager.clone())
+            .await?)
+    }
+
+    /// Saves the latest state of `room`, so that whichever instance hosts it
+    /// next can pick up where this one left off. It's kept for `ttl`.
+    pub async fn save_snapshot(
+        &self,
+        room: &[u8],
+        snapshot: Vec<u8>,
+        ttl: Duration,
+    ) -> Result<(), RedisStorageError> {
+        Ok(redis::cmd("SET")
+            .arg(Self::snapshot_key(room))
+            .arg(snapshot)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async::<_, ()>(&mut self.connection_manager.clone())
+            .await?)
+    }
+
+    /// The state of `room` which was last saved by whichever instance hosted
+    /// it.
+    pub async fn snapshot(&self, room: &[u8]) -> Result<Option<Vec<u8>>, RedisStorageError> {
+        Ok(redis::cmd("GET")
+            .arg(Self::snapshot_key(room))
+            .query_async(&mut self.connection_manager.clone())
+            .await?)
+    }
+}
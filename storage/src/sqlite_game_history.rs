@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use thiserror::Error;
+
+/// A finished game, to be kept in its players' histories.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FinishedGame {
+    pub room_name: String,
+    pub players: Vec<String>,
+    pub winners: Vec<String>,
+    pub landlord: Option<String>,
+    /// A summary of the game's settings, serialized by the caller.
+    pub settings: String,
+    /// The points collected by the team attacking the landlord.
+    pub attacking_points: i64,
+    /// The game's replay, serialized by the caller, if it was recorded.
+    pub replay: Option<String>,
+}
+
+/// A game in a player's history, without its replay.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PastGame {
+    pub id: i64,
+    pub room_name: String,
+    pub finished_at: i64,
+    pub players: Vec<String>,
+    pub winners: Vec<String>,
+    pub landlord: Option<String>,
+    pub settings: serde_json::Value,
+    pub attacking_points: i64,
+    pub has_replay: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum GameHistoryError {
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("failed to compress or decompress replay")]
+    CompressionError(#[from] std::io::Error),
+    #[error("malformed game record")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// Every finished game along with its replay, so that players can look back
+/// over, or share, the games they've played.
+#[derive(Clone)]
+pub struct SqliteGameHistory {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteGameHistory {
+    pub(crate) fn new(connection: Arc<Mutex<Connection>>) -> Result<Self, rusqlite::Error> {
+        connection.lock().unwrap().execute_batch(
+            "CREATE TABLE IF NOT EXISTS game_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                room_name TEXT NOT NULL,
+                finished_at INTEGER NOT NULL,
+                players TEXT NOT NULL,
+                winners TEXT NOT NULL,
+                landlord TEXT,
+                settings TEXT NOT NULL,
+                attacking_points INTEGER NOT NULL,
+                replay BLOB
+            );
+            CREATE TABLE IF NOT EXISTS game_history_players (
+                player TEXT NOT NULL,
+                game_id INTEGER NOT NULL REFERENCES game_history (id),
+                PRIMARY KEY (player, game_id)
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Records a game in each of its players' histories, returning its id.
+    pub fn record(&self, game: &FinishedGame) -> Result<i64, GameHistoryError> {
+        // Replays are mostly repeated card names, so they compress well.
+        let replay = match &game.replay {
+            Some(replay) => Some(zstd::encode_all(replay.as_bytes(), 3)?),
+            None => None,
+        };
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO game_history (
+                room_name, finished_at, players, winners, landlord, settings,
+                attacking_points, replay
+             ) VALUES (?1, strftime('%s', 'now'), ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                game.room_name,
+                serde_json::to_string(&game.players)?,
+                serde_json::to_string(&game.winners)?,
+                game.landlord,
+                game.settings,
+                game.attacking_points,
+                replay,
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+        for player in &game.players {
+            transaction.execute(
+                "INSERT OR IGNORE INTO game_history_players (player, game_id) VALUES (?1, ?2)",
+                params![player, id],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// The player's most recent games, newest first. Pass the id of the last
+    /// game of a page as `before` to get the next one.
+    pub fn games_for_player(
+        &self,
+        player: &str,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<PastGame>, GameHistoryError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT g.id, g.room_name, g.finished_at, g.players, g.winners, g.landlord,
+                g.settings, g.attacking_points, g.replay IS NOT NULL
+             FROM game_history_players p JOIN game_history g ON g.id = p.game_id
+             WHERE p.player = ?1 AND (?2 IS NULL OR g.id < ?2)
+             ORDER BY g.id DESC
+             LIMIT ?3",
+        )?;
+        let rows = statement
+            .query_map(params![player, before, limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, i64>(7)?,
+                    row.get::<_, bool>(8)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(
+                |(
+                    id,
+                    room_name,
+                    finished_at,
+                    players,
+                    winners,
+                    landlord,
+                    settings,
+                    attacking_points,
+                    has_replay,
+                )| {
+                    Ok(PastGame {
+                        id,
+                        room_name,
+                        finished_at,
+                        players: serde_json::from_str(&players)?,
+                        winners: serde_json::from_str(&winners)?,
+                        landlord,
+                        settings: serde_json::from_str(&settings)?,
+                        attacking_points,
+                        has_replay,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// The game's replay, as it was serialized when the game was recorded.
+    pub fn replay(&self, id: i64) -> Result<Option<String>, GameHistoryError> {
+        let connection = self.connection.lock().unwrap();
+        let data: Option<Option<Vec<u8>>> = connection
+            .query_row(
+                "SELECT replay FROM game_history WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match data.flatten() {
+            Some(data) => {
+                let replay = zstd::decode_all(&data[..])?;
+                Ok(Some(String::from_utf8_lossy(&replay).into_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+}
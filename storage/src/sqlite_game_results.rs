@@ -0,0 +1,191 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use thiserror::Error;
+
+/// How a game went for one of the players in it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GameResult {
+    pub room_name: String,
+    pub player: String,
+    pub is_landlord: bool,
+    /// Whether the player was on the landlord's team.
+    pub is_defending: bool,
+    pub won: bool,
+    pub ranks_up: u64,
+    /// The player's rank once the game was scored, serialized by the caller.
+    pub level: String,
+    /// The points collected by the team attacking the landlord.
+    pub attacking_points: i64,
+}
+
+#[derive(Error, Debug)]
+pub enum GameResultError {
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+}
+
+/// A player's statistics, aggregated over every game they've finished.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlayerStats {
+    pub player: String,
+    pub games_played: u64,
+    pub games_as_landlord: u64,
+    pub games_won_as_landlord: u64,
+    /// Games on the landlord's team, including those as the landlord.
+    pub games_defending: u64,
+    pub games_won_defending: u64,
+    pub games_attacking: u64,
+    pub games_won_attacking: u64,
+    /// The average number of points the attacking team collected in the
+    /// games where the player was defending.
+    pub average_points_conceded: Option<f64>,
+}
+
+impl PlayerStats {
+    pub fn landlord_win_rate(&self) -> Option<f64> {
+        rate(self.games_won_as_landlord, self.games_as_landlord)
+    }
+
+    pub fn defending_win_rate(&self) -> Option<f64> {
+        rate(self.games_won_defending, self.games_defending)
+    }
+
+    pub fn attacking_win_rate(&self) -> Option<f64> {
+        rate(self.games_won_attacking, self.games_attacking)
+    }
+}
+
+fn rate(won: u64, played: u64) -> Option<f64> {
+    if played == 0 {
+        None
+    } else {
+        Some(won as f64 / played as f64)
+    }
+}
+
+/// A player's rank after one of their games.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LevelChange {
+    pub room_name: String,
+    pub finished_at: i64,
+    pub level: String,
+    pub ranks_up: u64,
+}
+
+/// The results of every finished game, one row per player, for computing
+/// statistics across rooms.
+#[derive(Clone)]
+pub struct SqliteGameResults {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteGameResults {
+    pub(crate) fn new(connection: Arc<Mutex<Connection>>) -> Result<Self, rusqlite::Error> {
+        connection.lock().unwrap().execute_batch(
+            "CREATE TABLE IF NOT EXISTS game_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                room_name TEXT NOT NULL,
+                player TEXT NOT NULL,
+                is_landlord INTEGER NOT NULL,
+                is_defending INTEGER NOT NULL,
+                won INTEGER NOT NULL,
+                ranks_up INTEGER NOT NULL,
+                level TEXT NOT NULL,
+                attacking_points INTEGER NOT NULL,
+                finished_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS game_results_by_player
+                ON game_results (player, finished_at);
+            CREATE INDEX IF NOT EXISTS game_results_by_room
+                ON game_results (room_name, finished_at);",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Records the results of a game, for all of its players at once.
+    pub fn record(&self, results: &[GameResult]) -> Result<(), GameResultError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for r in results {
+            transaction.execute(
+                "INSERT INTO game_results (
+                    room_name, player, is_landlord, is_defending, won, ranks_up, level,
+                    attacking_points, finished_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, strftime('%s', 'now'))",
+                params![
+                    r.room_name,
+                    r.player,
+                    r.is_landlord,
+                    r.is_defending,
+                    r.won,
+                    r.ranks_up as i64,
+                    r.level,
+                    r.attacking_points,
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn player_stats(&self, player: &str) -> Result<PlayerStats, GameResultError> {
+        let connection = self.connection.lock().unwrap();
+        Ok(connection.query_row(
+            "SELECT
+                COUNT(*),
+                COALESCE(SUM(is_landlord), 0),
+                COALESCE(SUM(is_landlord AND won), 0),
+                COALESCE(SUM(is_defending), 0),
+                COALESCE(SUM(is_defending AND won), 0),
+                COALESCE(SUM(NOT is_defending), 0),
+                COALESCE(SUM(NOT is_defending AND won), 0),
+                AVG(CASE WHEN is_defending THEN attacking_points END)
+             FROM game_results WHERE player = ?1",
+            params![player],
+            |row| {
+                Ok(PlayerStats {
+                    player: player.to_string(),
+                    games_played: row.get::<_, i64>(0)? as u64,
+                    games_as_landlord: row.get::<_, i64>(1)? as u64,
+                    games_won_as_landlord: row.get::<_, i64>(2)? as u64,
+                    games_defending: row.get::<_, i64>(3)? as u64,
+                    games_won_defending: row.get::<_, i64>(4)? as u64,
+                    games_attacking: row.get::<_, i64>(5)? as u64,
+                    games_won_attacking: row.get::<_, i64>(6)? as u64,
+                    average_points_conceded: row.get(7)?,
+                })
+            },
+        )?)
+    }
+
+    /// The player's rank after each of their most recent `limit` games,
+    /// oldest first.
+    pub fn level_progression(
+        &self,
+        player: &str,
+        limit: usize,
+    ) -> Result<Vec<LevelChange>, GameResultError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT room_name, finished_at, level, ranks_up FROM (
+                SELECT id, room_name, finished_at, level, ranks_up FROM game_results
+                WHERE player = ?1
+                ORDER BY finished_at DESC, id DESC
+                LIMIT ?2
+             ) ORDER BY finished_at, id",
+        )?;
+        let changes = statement
+            .query_map(params![player, limit as i64], |row| {
+                Ok(LevelChange {
+                    room_name: row.get(0)?,
+                    finished_at: row.get(1)?,
+                    level: row.get(2)?,
+                    ranks_up: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(changes)
+    }
+}
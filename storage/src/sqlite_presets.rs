@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use thiserror::Error;
+
+/// A named bundle of game settings, which hosts can load into new rooms.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Preset {
+    pub name: String,
+    /// The nickname of whoever saved the preset; only they can overwrite it.
+    pub owner: String,
+    /// The settings, serialized by the caller.
+    pub settings: String,
+    pub updated_at: i64,
+}
+
+#[derive(Error, Debug)]
+pub enum PresetError {
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("A preset with this name belongs to someone else")]
+    NameTaken,
+}
+
+/// Game settings presets, keyed by name and shared across the server.
+#[derive(Clone)]
+pub struct SqlitePresets {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqlitePresets {
+    pub(crate) fn new(connection: Arc<Mutex<Connection>>) -> Result<Self, rusqlite::Error> {
+        connection.lock().unwrap().execute_batch(
+            "CREATE TABLE IF NOT EXISTS presets (
+                name TEXT PRIMARY KEY,
+                owner TEXT NOT NULL,
+                settings TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Saves the preset, replacing any earlier version saved by the same
+    /// owner.
+    pub fn save(&self, name: &str, owner: &str, settings: &str) -> Result<Preset, PresetError> {
+        let connection = self.connection.lock().unwrap();
+        let existing: Option<String> = connection
+            .query_row(
+                "SELECT owner FROM presets WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if existing.map(|o| o != owner).unwrap_or(false) {
+            return Err(PresetError::NameTaken);
+        }
+        connection.execute(
+            "INSERT OR REPLACE INTO presets (name, owner, settings, updated_at)
+             VALUES (?1, ?2, ?3, strftime('%s', 'now'))",
+            params![name, owner, settings],
+        )?;
+        Ok(Self::read(&connection, name)?.unwrap())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Preset>, PresetError> {
+        Ok(Self::read(&self.connection.lock().unwrap(), name)?)
+    }
+
+    /// Every preset, in alphabetical order.
+    pub fn list(&self) -> Result<Vec<Preset>, PresetError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT name, owner, settings, updated_at FROM presets ORDER BY name")?;
+        let presets = statement
+            .query_map([], Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(presets)
+    }
+
+    fn read(connection: &Connection, name: &str) -> Result<Option<Preset>, rusqlite::Error> {
+        connection
+            .query_row(
+                "SELECT name, owner, settings, updated_at FROM presets WHERE name = ?1",
+                params![name],
+                Self::from_row,
+            )
+            .optional()
+    }
+
+    fn from_row(row: &rusqlite::Row<'_>) -> Result<Preset, rusqlite::Error> {
+        Ok(Preset {
+            name: row.get(0)?,
+            owner: row.get(1)?,
+            settings: row.get(2)?,
+            updated_at: row.get(3)?,
+        })
+    }
+}
@@ -0,0 +1,583 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use slog::{debug, error, info, Logger};
+use thiserror::Error;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::hash_map_storage::HashMapStorage;
+use crate::sqlite_game_history::SqliteGameHistory;
+use crate::sqlite_game_results::SqliteGameResults;
+use crate::sqlite_presets::SqlitePresets;
+use crate::sqlite_users::SqliteUsers;
+use crate::storage::{State, Storage};
+
+/// States which haven't been checkpointed in this long are deleted from the
+/// database when pruning, unless there's an archive policy.
+const RETENTION_SECS: i64 = 24 * 3600;
+
+/// Controls when idle states are moved out of memory and into compressed
+/// archives, which are kept until they're purged.
+#[derive(Clone)]
+pub struct ArchivePolicy {
+    /// States which haven't changed in this long are archived.
+    pub idle_after: Duration,
+    /// The zstd dictionary to compress archives with. Archives can only be
+    /// restored with the same dictionary.
+    pub dictionary: Vec<u8>,
+}
+
+/// A state which was archived for being idle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedState {
+    pub key: Vec<u8>,
+    pub version: u64,
+    /// The size of the state before compression, in bytes.
+    pub size: usize,
+    pub compressed_size: usize,
+    pub archived_at: i64,
+}
+
+/// Storage which keeps live states in memory, and periodically checkpoints
+/// them into a SQLite database so that they survive restarts. States which
+/// aren't in memory are lazily loaded from the database when they're next
+/// accessed.
+pub struct SqliteStorage<S: State> {
+    logger: Logger,
+    memory: HashMapStorage<S>,
+    connection: Arc<std::sync::Mutex<Connection>>,
+    dirty: Arc<Mutex<HashSet<Vec<u8>>>>,
+    users: SqliteUsers,
+    presets: SqlitePresets,
+    game_results: SqliteGameResults,
+    game_history: SqliteGameHistory,
+    archive_policy: Option<Arc<ArchivePolicy>>,
+}
+
+#[derive(Error, Debug)]
+pub enum SqliteStorageError {
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("Serialization error")]
+    SerDeError(#[from] serde_json::Error),
+    #[error("Race detected")]
+    RaceDetected,
+    #[error("Failed to publish message")]
+    PublishError,
+    #[error("Compression error")]
+    CompressionError(#[from] std::io::Error),
+    #[error("Database task failed")]
+    TaskFailed(#[from] tokio::task::JoinError),
+}
+
+/// Distinguishes failures of the in-memory storage from failures of the
+/// operation being executed against it.
+enum OperationError<E> {
+    Storage,
+    Operation(E),
+}
+
+impl<E> From<()> for OperationError<E> {
+    fn from(_: ()) -> Self {
+        OperationError::Storage
+    }
+}
+
+impl<S: State> SqliteStorage<S> {
+    /// Opens (or creates) the database at the provided path.
+    pub fn open(logger: Logger, path: impl AsRef<Path>) -> Result<Self, SqliteStorageError> {
+        Self::from_connection(logger, Connection::open(path)?)
+    }
+
+    pub fn from_connection(
+        logger: Logger,
+        connection: Connection,
+    ) -> Result<Self, SqliteStorageError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS states (
+                key BLOB PRIMARY KEY,
+                version INTEGER NOT NULL,
+                state BLOB NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS archived_states (
+                key BLOB PRIMARY KEY,
+                version INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                state BLOB NOT NULL,
+                archived_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS secrets (
+                name TEXT PRIMARY KEY,
+                secret BLOB NOT NULL
+            );",
+        )?;
+        let connection = Arc::new(std::sync::Mutex::new(connection));
+        Ok(Self {
+            memory: HashMapStorage::new(logger.clone()),
+            logger,
+            users: SqliteUsers::new(Arc::clone(&connection))?,
+            presets: SqlitePresets::new(Arc::clone(&connection))?,
+            game_results: SqliteGameResults::new(Arc::clone(&connection))?,
+            game_history: SqliteGameHistory::new(Arc::clone(&connection))?,
+            connection,
+            dirty: Arc::new(Mutex::new(HashSet::new())),
+            archive_policy: None,
+        })
+    }
+
+    /// Archives states once they've been idle for long enough, instead of
+    /// deleting them after a day. Without a policy, archived states can't be
+    /// restored, but can still be listed and purged.
+    pub fn with_archive_policy(mut self, policy: ArchivePolicy) -> Self {
+        self.archive_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// User accounts, stored in the same database.
+    pub fn users(&self) -> SqliteUsers {
+        self.users.clone()
+    }
+
+    /// Game settings presets, stored in the same database.
+    pub fn presets(&self) -> SqlitePresets {
+        self.presets.clone()
+    }
+
+    /// The results of finished games, stored in the same database.
+    pub fn game_results(&self) -> SqliteGameResults {
+        self.game_results.clone()
+    }
+
+    /// Every finished game and its replay, stored in the same database.
+    pub fn game_history(&self) -> SqliteGameHistory {
+        self.game_history.clone()
+    }
+
+    /// The secret stored under `name`, storing `generate()` first if there
+    /// isn't one yet, so that the secret stays the same across restarts.
+    pub fn secret(
+        &self,
+        name: &str,
+        generate: impl FnOnce() -> Vec<u8>,
+    ) -> Result<Vec<u8>, SqliteStorageError> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR IGNORE INTO secrets (name, secret) VALUES (?1, ?2)",
+            params![name, generate()],
+        )?;
+        Ok(connection.query_row(
+            "SELECT secret FROM secrets WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Runs `f` against the database on a thread where blocking is
+    /// allowed, so that it doesn't hold up the other tasks.
+    async fn with_connection<T: Send + 'static>(
+        self,
+        f: impl FnOnce(&mut Connection) -> Result<T, SqliteStorageError> + Send + 'static,
+    ) -> Result<T, SqliteStorageError> {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || f(&mut connection.lock().unwrap())).await?
+    }
+
+    async fn read(self, key: &[u8]) -> Result<Option<S>, SqliteStorageError> {
+        let key = key.to_vec();
+        let data: Option<Vec<u8>> = self
+            .with_connection(move |connection| {
+                Ok(connection
+                    .query_row(
+                        "SELECT state FROM states WHERE key = ?1",
+                        params![key],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+        Ok(match data {
+            Some(data) => Some(serde_json::from_slice(&data)?),
+            None => None,
+        })
+    }
+
+    /// Restores an archived state, removing it from the archive.
+    async fn unarchive(self, key: &[u8]) -> Result<Option<S>, SqliteStorageError> {
+        let policy = match self.archive_policy {
+            Some(ref policy) => Arc::clone(policy),
+            None => return Ok(None),
+        };
+        let key = key.to_vec();
+        let data = self
+            .with_connection(move |connection| {
+                let archived: Option<(Vec<u8>, i64)> = connection
+                    .query_row(
+                        "SELECT state, size FROM archived_states WHERE key = ?1",
+                        params![key],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                let (data, size) = match archived {
+                    Some(archived) => archived,
+                    None => return Ok(None),
+                };
+                let data = zstd::bulk::Decompressor::with_dictionary(&policy.dictionary)?
+                    .decompress(&data, size as usize)?;
+                connection.execute("DELETE FROM archived_states WHERE key = ?1", params![key])?;
+                Ok(Some(data))
+            })
+            .await?;
+        Ok(match data {
+            Some(data) => Some(serde_json::from_slice(&data)?),
+            None => None,
+        })
+    }
+
+    async fn write(self, state: S) -> Result<(), SqliteStorageError> {
+        let data = serde_json::to_vec(&state)?;
+        let key = state.key().to_vec();
+        let version = state.version() as i64;
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO states (key, version, state, updated_at)
+                 VALUES (?1, ?2, ?3, strftime('%s', 'now'))
+                 ON CONFLICT(key) DO UPDATE SET
+                    version = excluded.version,
+                    state = excluded.state,
+                    updated_at = excluded.updated_at",
+                params![key, version, data],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Makes sure that the state for the key is in memory, loading it from
+    /// the database if it was checkpointed previously.
+    async fn load(self, key: &[u8]) -> Result<(), SqliteStorageError> {
+        // The in-memory storage hands out a version-zero state for keys it
+        // doesn't know about.
+        let in_memory = self.memory.clone().get(key.to_vec()).await.unwrap();
+        if in_memory.version() != 0 {
+            return Ok(());
+        }
+        // Someone else might have put a newer state into memory while this
+        // was being read, in which case theirs wins.
+        if let Some(state) = self.clone().read(key).await? {
+            if self.memory.clone().put_if_absent(state).await {
+                info!(self.logger, "Loaded state from database"; "key" => stringify(key));
+            }
+        } else if let Some(state) = self.clone().unarchive(key).await? {
+            if self.memory.clone().put_if_absent(state).await {
+                info!(self.logger, "Restored state from archive"; "key" => stringify(key));
+            }
+            // The archive is gone, so it needs to be written out again.
+            self.dirty.lock().await.insert(key.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn checkpoint_key(self, key: &[u8]) -> Result<(), SqliteStorageError> {
+        if self.dirty.lock().await.remove(key) {
+            let state = self.memory.clone().get(key.to_vec()).await.unwrap();
+            if state.version() != 0 {
+                self.clone().write(state).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes every state that changed since the last checkpoint to the
+    /// database, returning the number of states written.
+    pub async fn checkpoint(self) -> Result<usize, SqliteStorageError> {
+        let keys = self.dirty.lock().await.drain().collect::<Vec<_>>();
+        let mut num_written = 0;
+        for key in keys {
+            let state = self.memory.clone().get(key).await.unwrap();
+            if state.version() != 0 {
+                self.clone().write(state).await?;
+                num_written += 1;
+            }
+        }
+        debug!(self.logger, "Checkpointed states"; "num_states" => num_written);
+        Ok(num_written)
+    }
+
+    /// Moves every state which has been idle for longer than the archive
+    /// policy allows out of memory and into the archive, returning the number
+    /// of states archived.
+    pub async fn archive_idle(self) -> Result<usize, SqliteStorageError> {
+        let policy = match self.archive_policy {
+            Some(ref policy) => Arc::clone(policy),
+            None => return Ok(0),
+        };
+        self.clone().checkpoint().await?;
+        let evicted = self.memory.clone().evict_idle(policy.idle_after).await;
+        // Anything which is still in memory was used since it was last
+        // checkpointed.
+        let in_memory = self
+            .memory
+            .clone()
+            .get_all_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let num_archived = self
+            .clone()
+            .with_connection(move |connection| {
+                let mut compressor =
+                    zstd::bulk::Compressor::with_dictionary(3, &policy.dictionary)?;
+                let tx = connection.transaction()?;
+                let idle = {
+                    let mut statement = tx.prepare(
+                        "SELECT key, version, state FROM states
+                         WHERE updated_at <= strftime('%s', 'now') - ?1",
+                    )?;
+                    let rows = statement
+                        .query_map(params![policy.idle_after.as_secs() as i64], |row| {
+                            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                        })?
+                        .collect::<Result<Vec<(Vec<u8>, i64, Vec<u8>)>, _>>()?;
+                    rows
+                };
+                let mut num_archived = 0;
+                for (key, version, data) in idle {
+                    if in_memory.contains(&key) {
+                        continue;
+                    }
+                    tx.execute(
+                        "INSERT OR REPLACE INTO archived_states
+                            (key, version, size, state, archived_at)
+                         VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))",
+                        params![key, version, data.len() as i64, compressor.compress(&data)?],
+                    )?;
+                    tx.execute("DELETE FROM states WHERE key = ?1", params![key])?;
+                    num_archived += 1;
+                }
+                tx.commit()?;
+                Ok(num_archived)
+            })
+            .await?;
+        info!(self.logger, "Archived idle states";
+            "num_evicted" => evicted.len(),
+            "num_archived" => num_archived
+        );
+        Ok(num_archived)
+    }
+
+    /// Forgets the state for `key` entirely, in memory, in the database and
+    /// in the archive, and drops its subscribers. For states which are kept
+    /// somewhere else now.
+    pub async fn evict(self, key: &[u8]) -> Result<(), SqliteStorageError> {
+        self.dirty.lock().await.remove(key);
+        self.memory.clone().remove(key).await;
+        let key = key.to_vec();
+        self.with_connection(move |connection| {
+            connection.execute("DELETE FROM states WHERE key = ?1", params![key])?;
+            connection.execute("DELETE FROM archived_states WHERE key = ?1", params![key])?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Every archived state, most recently archived first.
+    pub fn list_archived(&self) -> Result<Vec<ArchivedState>, SqliteStorageError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT key, version, size, length(state), archived_at FROM archived_states
+             ORDER BY archived_at DESC, key",
+        )?;
+        let archived = statement
+            .query_map([], |row| {
+                Ok(ArchivedState {
+                    key: row.get(0)?,
+                    version: row.get::<_, i64>(1)? as u64,
+                    size: row.get::<_, i64>(2)? as usize,
+                    compressed_size: row.get::<_, i64>(3)? as usize,
+                    archived_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(archived)
+    }
+
+    /// Permanently deletes an archived state, returning whether there was one
+    /// to delete.
+    pub fn purge_archived(&self, key: &[u8]) -> Result<bool, SqliteStorageError> {
+        let n = self
+            .connection
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM archived_states WHERE key = ?1", params![key])?;
+        Ok(n > 0)
+    }
+
+    /// Permanently deletes every state which was archived more than `age`
+    /// ago, returning the number of states deleted.
+    pub fn purge_archived_older_than(&self, age: Duration) -> Result<usize, SqliteStorageError> {
+        Ok(self.connection.lock().unwrap().execute(
+            "DELETE FROM archived_states WHERE archived_at < strftime('%s', 'now') - ?1",
+            params![age.as_secs() as i64],
+        )?)
+    }
+}
+
+impl<S: State> Clone for SqliteStorage<S> {
+    fn clone(&self) -> Self {
+        Self {
+            logger: self.logger.clone(),
+            memory: self.memory.clone(),
+            connection: Arc::clone(&self.connection),
+            dirty: Arc::clone(&self.dirty),
+            users: self.users.clone(),
+            presets: self.presets.clone(),
+            game_results: self.game_results.clone(),
+            game_history: self.game_history.clone(),
+            archive_policy: self.archive_policy.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: State> Storage<S, SqliteStorageError> for SqliteStorage<S> {
+    async fn put(self, state: S) -> Result<(), SqliteStorageError> {
+        let key = state.key().to_vec();
+        self.memory.clone().put(state).await.unwrap();
+        self.dirty.lock().await.insert(key);
+        Ok(())
+    }
+
+    async fn put_cas(self, expected_version: u64, state: S) -> Result<(), SqliteStorageError> {
+        let key = state.key().to_vec();
+        self.clone().load(&key).await?;
+        self.memory
+            .clone()
+            .put_cas(expected_version, state)
+            .await
+            .map_err(|()| SqliteStorageError::RaceDetected)?;
+        self.dirty.lock().await.insert(key);
+        Ok(())
+    }
+
+    async fn get(self, key: Vec<u8>) -> Result<S, SqliteStorageError> {
+        self.clone().load(&key).await?;
+        Ok(self.memory.clone().get(key).await.unwrap())
+    }
+
+    async fn execute_operation_with_messages<E2, F>(
+        self,
+        key: Vec<u8>,
+        operation: F,
+    ) -> Result<u64, E2>
+    where
+        E2: From<SqliteStorageError> + Send,
+        F: FnOnce(S) -> Result<(S, Vec<S::Message>), E2> + Send + 'static,
+    {
+        self.clone().load(&key).await?;
+        let new_v = self
+            .memory
+            .clone()
+            .execute_operation_with_messages(key.clone(), move |s| {
+                operation(s).map_err(OperationError::Operation)
+            })
+            .await;
+        match new_v {
+            Ok(new_v) => {
+                self.dirty.lock().await.insert(key);
+                Ok(new_v)
+            }
+            Err(OperationError::Operation(e)) => Err(e),
+            Err(OperationError::Storage) => Err(SqliteStorageError::RaceDetected.into()),
+        }
+    }
+
+    async fn subscribe(
+        self,
+        key: Vec<u8>,
+        subscriber_id: usize,
+    ) -> Result<mpsc::UnboundedReceiver<S::Message>, SqliteStorageError> {
+        self.clone().load(&key).await?;
+        Ok(self
+            .memory
+            .clone()
+            .subscribe(key, subscriber_id)
+            .await
+            .unwrap())
+    }
+
+    async fn publish(self, key: Vec<u8>, message: S::Message) -> Result<(), SqliteStorageError> {
+        self.memory.clone().publish(key, message).await.unwrap();
+        Ok(())
+    }
+
+    async fn publish_to_single_subscriber(
+        self,
+        key: Vec<u8>,
+        subscriber_id: usize,
+        message: S::Message,
+    ) -> Result<(), SqliteStorageError> {
+        self.memory
+            .clone()
+            .publish_to_single_subscriber(key, subscriber_id, message)
+            .await
+            .map_err(|()| SqliteStorageError::PublishError)
+    }
+
+    async fn unsubscribe(self, key: Vec<u8>, subscriber_id: usize) {
+        // The in-memory storage drops the state once the last subscriber
+        // leaves, so make sure it's been written out first.
+        if let Err(e) = self.clone().checkpoint_key(&key).await {
+            error!(self.logger, "Failed to checkpoint state"; "key" => stringify(&key), "error" => format!("{e:?}"));
+        }
+        self.memory.clone().unsubscribe(key, subscriber_id).await;
+    }
+
+    async fn get_all_keys(self) -> Result<Vec<Vec<u8>>, SqliteStorageError> {
+        Ok(self.memory.clone().get_all_keys().await.unwrap())
+    }
+
+    async fn get_states_created(self) -> Result<u64, SqliteStorageError> {
+        Ok(self.memory.clone().get_states_created().await.unwrap())
+    }
+
+    async fn prune(self) {
+        if let Err(e) = self.clone().checkpoint().await {
+            error!(self.logger, "Failed to checkpoint states"; "error" => format!("{e:?}"));
+        }
+        if let Err(e) = self.clone().archive_idle().await {
+            error!(self.logger, "Failed to archive states"; "error" => format!("{e:?}"));
+        }
+        self.memory.clone().prune().await;
+        if self.archive_policy.is_some() {
+            return;
+        }
+        let pruned = self
+            .clone()
+            .with_connection(|connection| {
+                Ok(connection.execute(
+                    "DELETE FROM states WHERE updated_at < strftime('%s', 'now') - ?1",
+                    params![RETENTION_SECS],
+                )?)
+            })
+            .await;
+        match pruned {
+            Ok(n) => debug!(self.logger, "Pruned database"; "num_states_pruned" => n),
+            Err(e) => error!(self.logger, "Failed to prune database"; "error" => format!("{e:?}")),
+        }
+    }
+
+    async fn stats(self) -> Result<(usize, usize), SqliteStorageError> {
+        Ok(self.memory.clone().stats().await.unwrap())
+    }
+}
+
+fn stringify(str_like: &[u8]) -> &str {
+    std::str::from_utf8(str_like).unwrap_or("not utf-8")
+}
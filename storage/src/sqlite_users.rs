@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use thiserror::Error;
+
+/// A claimed nickname, and the statistics which follow it across rooms.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct User {
+    pub nickname: String,
+    /// The player's rank at the end of their last game, serialized by the
+    /// caller.
+    pub level: Option<String>,
+    pub games_played: u64,
+    pub games_won: u64,
+    pub ranks_up: u64,
+}
+
+/// A claimed nickname's record across every round it has played, as shown
+/// to the other players in its rooms.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CareerStats {
+    pub nickname: String,
+    pub rounds_played: u64,
+    pub rounds_as_landlord: u64,
+    /// Rounds as the landlord in which the landlord's team won.
+    pub landlord_defenses: u64,
+    /// The points in all of the tricks the player has won.
+    pub points_captured: u64,
+    /// The most cards the player has had to take back from a failed throw.
+    pub biggest_throw_forfeited: u64,
+    /// The player's rating from ranked rooms, if they've played in one.
+    pub rating: Option<f64>,
+}
+
+impl CareerStats {
+    pub fn landlord_defense_rate(&self) -> Option<f64> {
+        if self.rounds_as_landlord == 0 {
+            None
+        } else {
+            Some(self.landlord_defenses as f64 / self.rounds_as_landlord as f64)
+        }
+    }
+
+    pub fn average_points_captured(&self) -> Option<f64> {
+        if self.rounds_played == 0 {
+            None
+        } else {
+            Some(self.points_captured as f64 / self.rounds_played as f64)
+        }
+    }
+}
+
+/// How a single round went for a player, for their career statistics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CareerRound {
+    pub is_landlord: bool,
+    pub won: bool,
+    pub points_captured: usize,
+    pub largest_failed_throw: usize,
+}
+
+/// An achievement which a claimed nickname has unlocked.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UnlockedAchievement {
+    /// The achievement's key, as chosen by the caller.
+    pub achievement: String,
+    pub unlocked_at: i64,
+}
+
+/// The rating players start with, and which players who haven't claimed
+/// their nicknames are always treated as having.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far a single game can move a player's rating.
+const RATING_K_FACTOR: f64 = 32.0;
+
+#[derive(Error, Debug)]
+pub enum UserError {
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("This name belongs to someone else")]
+    NicknameClaimed,
+}
+
+/// Persistent user accounts, keyed by nickname. Accounts are email-less:
+/// the first device to claim a nickname owns it, and proves that it's the
+/// owner by presenting the same key on later joins.
+#[derive(Clone)]
+pub struct SqliteUsers {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteUsers {
+    pub(crate) fn new(connection: Arc<Mutex<Connection>>) -> Result<Self, rusqlite::Error> {
+        connection.lock().unwrap().execute_batch(
+            "CREATE TABLE IF NOT EXISTS users (
+                nickname TEXT PRIMARY KEY,
+                key_hash BLOB NOT NULL,
+                level TEXT,
+                games_played INTEGER NOT NULL DEFAULT 0,
+                games_won INTEGER NOT NULL DEFAULT 0,
+                ranks_up INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS ratings (
+                nickname TEXT PRIMARY KEY REFERENCES users (nickname),
+                rating REAL NOT NULL,
+                rated_games INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS achievements (
+                nickname TEXT NOT NULL REFERENCES users (nickname),
+                achievement TEXT NOT NULL,
+                unlocked_at INTEGER NOT NULL,
+                PRIMARY KEY (nickname, achievement)
+            );
+            CREATE TABLE IF NOT EXISTS career_stats (
+                nickname TEXT PRIMARY KEY REFERENCES users (nickname),
+                rounds_played INTEGER NOT NULL DEFAULT 0,
+                rounds_as_landlord INTEGER NOT NULL DEFAULT 0,
+                landlord_defenses INTEGER NOT NULL DEFAULT 0,
+                points_captured INTEGER NOT NULL DEFAULT 0,
+                biggest_throw_forfeited INTEGER NOT NULL DEFAULT 0
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Checks whether the holder of `key_hash` may use `nickname`.
+    ///
+    /// Anyone may use an unclaimed nickname; presenting a key claims it.
+    /// Claimed nicknames may only be used with the key that claimed them.
+    pub fn authenticate(
+        &self,
+        nickname: &str,
+        key_hash: Option<&[u8]>,
+    ) -> Result<Option<User>, UserError> {
+        let connection = self.connection.lock().unwrap();
+        let existing: Option<Vec<u8>> = connection
+            .query_row(
+                "SELECT key_hash FROM users WHERE nickname = ?1",
+                params![nickname],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match (existing, key_hash) {
+            (None, None) => Ok(None),
+            (None, Some(key_hash)) => {
+                connection.execute(
+                    "INSERT INTO users (nickname, key_hash, created_at)
+                     VALUES (?1, ?2, strftime('%s', 'now'))",
+                    params![nickname, key_hash],
+                )?;
+                Ok(Self::read(&connection, nickname)?)
+            }
+            (Some(existing), Some(key_hash)) if existing == key_hash => {
+                Ok(Self::read(&connection, nickname)?)
+            }
+            (Some(_), _) => Err(UserError::NicknameClaimed),
+        }
+    }
+
+    pub fn get(&self, nickname: &str) -> Result<Option<User>, UserError> {
+        Ok(Self::read(&self.connection.lock().unwrap(), nickname)?)
+    }
+
+    /// Updates the statistics for a claimed nickname. Does nothing if the
+    /// nickname hasn't been claimed.
+    pub fn record_game(
+        &self,
+        nickname: &str,
+        won: bool,
+        ranks_up: usize,
+        level: &str,
+    ) -> Result<(), UserError> {
+        self.connection.lock().unwrap().execute(
+            "UPDATE users SET
+                games_played = games_played + 1,
+                games_won = games_won + ?2,
+                ranks_up = ranks_up + ?3,
+                level = ?4
+             WHERE nickname = ?1",
+            params![nickname, won as i64, ranks_up as i64, level],
+        )?;
+        Ok(())
+    }
+
+    /// Adds a round to the career statistics for a claimed nickname. Does
+    /// nothing if the nickname hasn't been claimed.
+    pub fn record_round(&self, nickname: &str, round: CareerRound) -> Result<(), UserError> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO career_stats (
+                nickname, rounds_played, rounds_as_landlord, landlord_defenses,
+                points_captured, biggest_throw_forfeited
+             )
+             SELECT nickname, 1, ?2, ?3, ?4, ?5 FROM users WHERE nickname = ?1
+             ON CONFLICT (nickname) DO UPDATE SET
+                rounds_played = rounds_played + 1,
+                rounds_as_landlord = rounds_as_landlord + excluded.rounds_as_landlord,
+                landlord_defenses = landlord_defenses + excluded.landlord_defenses,
+                points_captured = points_captured + excluded.points_captured,
+                biggest_throw_forfeited =
+                    MAX(biggest_throw_forfeited, excluded.biggest_throw_forfeited)",
+            params![
+                nickname,
+                round.is_landlord as i64,
+                (round.is_landlord && round.won) as i64,
+                round.points_captured as i64,
+                round.largest_failed_throw as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The career statistics for a claimed nickname, or `None` if it hasn't
+    /// been claimed.
+    pub fn career(&self, nickname: &str) -> Result<Option<CareerStats>, UserError> {
+        Ok(self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT
+                    users.nickname,
+                    COALESCE(rounds_played, 0),
+                    COALESCE(rounds_as_landlord, 0),
+                    COALESCE(landlord_defenses, 0),
+                    COALESCE(points_captured, 0),
+                    COALESCE(biggest_throw_forfeited, 0),
+                    rating
+                 FROM users
+                    LEFT JOIN career_stats USING (nickname)
+                    LEFT JOIN ratings USING (nickname)
+                 WHERE users.nickname = ?1",
+                params![nickname],
+                |row| {
+                    Ok(CareerStats {
+                        nickname: row.get(0)?,
+                        rounds_played: row.get::<_, i64>(1)? as u64,
+                        rounds_as_landlord: row.get::<_, i64>(2)? as u64,
+                        landlord_defenses: row.get::<_, i64>(3)? as u64,
+                        points_captured: row.get::<_, i64>(4)? as u64,
+                        biggest_throw_forfeited: row.get::<_, i64>(5)? as u64,
+                        rating: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// Updates the ratings of the claimed nicknames on both teams of a
+    /// ranked game, Elo-style: each team is rated as the average of its
+    /// players, and every player on a team moves by the same amount. Players
+    /// who haven't claimed their nicknames count towards their team's rating
+    /// at `INITIAL_RATING`, but aren't rated themselves.
+    ///
+    /// Returns the new ratings of the claimed nicknames.
+    pub fn record_rated_game(
+        &self,
+        defenders: &[String],
+        attackers: &[String],
+        defenders_won: bool,
+    ) -> Result<HashMap<String, f64>, UserError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        let mut ratings = HashMap::new();
+        for nickname in defenders.iter().chain(attackers) {
+            let rating: Option<f64> = transaction
+                .query_row(
+                    "SELECT COALESCE(rating, ?2)
+                     FROM users LEFT JOIN ratings USING (nickname)
+                     WHERE users.nickname = ?1",
+                    params![nickname, INITIAL_RATING],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(rating) = rating {
+                ratings.insert(nickname.clone(), rating);
+            }
+        }
+        let team_rating = |team: &[String]| {
+            if team.is_empty() {
+                INITIAL_RATING
+            } else {
+                team.iter()
+                    .map(|n| ratings.get(n).copied().unwrap_or(INITIAL_RATING))
+                    .sum::<f64>()
+                    / team.len() as f64
+            }
+        };
+        let expected =
+            1.0 / (1.0 + 10f64.powf((team_rating(attackers) - team_rating(defenders)) / 400.0));
+        let score = if defenders_won { 1.0 } else { 0.0 };
+        let defender_delta = RATING_K_FACTOR * (score - expected);
+
+        let mut new_ratings = HashMap::new();
+        for (team, delta) in [(defenders, defender_delta), (attackers, -defender_delta)] {
+            for nickname in team {
+                let rating = match ratings.get(nickname) {
+                    Some(rating) => rating + delta,
+                    None => continue,
+                };
+                transaction.execute(
+                    "INSERT INTO ratings (nickname, rating, rated_games) VALUES (?1, ?2, 1)
+                     ON CONFLICT (nickname) DO UPDATE SET
+                        rating = excluded.rating,
+                        rated_games = rated_games + 1",
+                    params![nickname, rating],
+                )?;
+                new_ratings.insert(nickname.clone(), rating);
+            }
+        }
+        transaction.commit()?;
+        Ok(new_ratings)
+    }
+
+    /// Unlocks an achievement for a claimed nickname. Returns whether it was
+    /// newly unlocked, which it can't be if it already was or if the
+    /// nickname hasn't been claimed.
+    pub fn unlock_achievement(&self, nickname: &str, achievement: &str) -> Result<bool, UserError> {
+        let inserted = self.connection.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO achievements (nickname, achievement, unlocked_at)
+             SELECT nickname, ?2, strftime('%s', 'now') FROM users WHERE nickname = ?1",
+            params![nickname, achievement],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// The achievements a nickname has unlocked, in the order it unlocked
+    /// them.
+    pub fn achievements(&self, nickname: &str) -> Result<Vec<UnlockedAchievement>, UserError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT achievement, unlocked_at FROM achievements
+             WHERE nickname = ?1
+             ORDER BY unlocked_at, rowid",
+        )?;
+        let achievements = statement
+            .query_map(params![nickname], |row| {
+                Ok(UnlockedAchievement {
+                    achievement: row.get(0)?,
+                    unlocked_at: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(achievements)
+    }
+
+    fn read(connection: &Connection, nickname: &str) -> Result<Option<User>, rusqlite::Error> {
+        connection
+            .query_row(
+                "SELECT nickname, level, games_played, games_won, ranks_up
+                 FROM users WHERE nickname = ?1",
+                params![nickname],
+                |row| {
+                    Ok(User {
+                        nickname: row.get(0)?,
+                        level: row.get(1)?,
+                        games_played: row.get::<_, i64>(2)? as u64,
+                        games_won: row.get::<_, i64>(3)? as u64,
+                        ranks_up: row.get::<_, i64>(4)? as u64,
+                    })
+                },
+            )
+            .optional()
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use slog::{o, Drain, Logger};
+
+use storage::{FinishedGame, SqliteStorage, State};
+
+struct NoOpDrain;
+
+impl Drain for NoOpDrain {
+    type Ok = ();
+    type Err = ();
+    fn log(
+        &self,
+        record: &slog::Record,
+        values: &slog::OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        println!("{:?}, {:?}", record.msg(), values);
+        Ok(())
+    }
+}
+
+fn make_logger() -> Logger {
+    let drain = Mutex::new(NoOpDrain).fuse();
+    Logger::root(drain, o!())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct VersionedState {
+    key: Vec<u8>,
+    version: u64,
+}
+
+impl State for VersionedState {
+    type Message = ();
+
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+    fn version(&self) -> u64 {
+        self.version
+    }
+    fn new_from_key(key: Vec<u8>) -> Self {
+        VersionedState { key, version: 0 }
+    }
+}
+
+fn make_game_history() -> storage::SqliteGameHistory {
+    let storage: SqliteStorage<VersionedState> = SqliteStorage::from_connection(
+        make_logger(),
+        rusqlite::Connection::open_in_memory().unwrap(),
+    )
+    .unwrap();
+    storage.game_history()
+}
+
+fn game(players: &[&str], replay: Option<&str>) -> FinishedGame {
+    FinishedGame {
+        room_name: "room".to_string(),
+        players: players.iter().map(|p| p.to_string()).collect(),
+        winners: vec![players[0].to_string()],
+        landlord: Some(players[0].to_string()),
+        settings: "{\"num_decks\":2}".to_string(),
+        attacking_points: 40,
+        replay: replay.map(|r| r.to_string()),
+    }
+}
+
+#[test]
+fn test_games_for_player() {
+    let history = make_game_history();
+    let first = history
+        .record(&game(&["alice", "bob"], Some("{\"seed\":1}")))
+        .unwrap();
+    let second = history.record(&game(&["bob", "carol"], None)).unwrap();
+    let third = history
+        .record(&game(&["alice", "carol"], Some("{\"seed\":3}")))
+        .unwrap();
+
+    let alice = history.games_for_player("alice", None, 10).unwrap();
+    assert_eq!(
+        alice.iter().map(|g| g.id).collect::<Vec<_>>(),
+        vec![third, first]
+    );
+    assert_eq!(alice[0].players, vec!["alice", "carol"]);
+    assert_eq!(alice[0].winners, vec!["alice"]);
+    assert_eq!(alice[0].settings["num_decks"], 2);
+    assert!(alice[0].has_replay);
+
+    let bob = history.games_for_player("bob", None, 1).unwrap();
+    assert_eq!(bob.len(), 1);
+    assert_eq!(bob[0].id, second);
+    assert!(!bob[0].has_replay);
+    let bob = history.games_for_player("bob", Some(second), 1).unwrap();
+    assert_eq!(bob[0].id, first);
+    assert!(history
+        .games_for_player("dave", None, 10)
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        history.replay(first).unwrap().as_deref(),
+        Some("{\"seed\":1}")
+    );
+    assert_eq!(history.replay(second).unwrap(), None);
+    assert_eq!(history.replay(1000).unwrap(), None);
+}
@@ -0,0 +1,140 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use slog::{o, Drain, Logger};
+
+use storage::{GameResult, SqliteStorage, State};
+
+struct NoOpDrain;
+
+impl Drain for NoOpDrain {
+    type Ok = ();
+    type Err = ();
+    fn log(
+        &self,
+        record: &slog::Record,
+        values: &slog::OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        println!("{:?}, {:?}", record.msg(), values);
+        Ok(())
+    }
+}
+
+fn make_logger() -> Logger {
+    let drain = Mutex::new(NoOpDrain).fuse();
+    Logger::root(drain, o!())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct VersionedState {
+    key: Vec<u8>,
+    version: u64,
+}
+
+impl State for VersionedState {
+    type Message = ();
+
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+    fn version(&self) -> u64 {
+        self.version
+    }
+    fn new_from_key(key: Vec<u8>) -> Self {
+        VersionedState { key, version: 0 }
+    }
+}
+
+fn make_game_results() -> storage::SqliteGameResults {
+    let storage: SqliteStorage<VersionedState> = SqliteStorage::from_connection(
+        make_logger(),
+        rusqlite::Connection::open_in_memory().unwrap(),
+    )
+    .unwrap();
+    storage.game_results()
+}
+
+fn result(player: &str, is_landlord: bool, is_defending: bool, won: bool) -> GameResult {
+    GameResult {
+        room_name: "room".to_string(),
+        player: player.to_string(),
+        is_landlord,
+        is_defending,
+        won,
+        ranks_up: if won { 1 } else { 0 },
+        level: "\"2\"".to_string(),
+        attacking_points: 0,
+    }
+}
+
+#[test]
+fn test_player_stats() {
+    let game_results = make_game_results();
+    let empty = game_results.player_stats("alice").unwrap();
+    assert_eq!(empty.games_played, 0);
+    assert_eq!(empty.landlord_win_rate(), None);
+    assert_eq!(empty.average_points_conceded, None);
+
+    game_results
+        .record(&[
+            GameResult {
+                attacking_points: 40,
+                ..result("alice", true, true, true)
+            },
+            result("bob", false, false, false),
+        ])
+        .unwrap();
+    game_results
+        .record(&[
+            GameResult {
+                attacking_points: 120,
+                ..result("alice", true, true, false)
+            },
+            result("bob", false, false, true),
+        ])
+        .unwrap();
+    game_results
+        .record(&[
+            result("alice", false, false, true),
+            GameResult {
+                attacking_points: 90,
+                ..result("bob", true, true, false)
+            },
+        ])
+        .unwrap();
+
+    let alice = game_results.player_stats("alice").unwrap();
+    assert_eq!(alice.games_played, 3);
+    assert_eq!(alice.games_as_landlord, 2);
+    assert_eq!(alice.games_won_as_landlord, 1);
+    assert_eq!(alice.landlord_win_rate(), Some(0.5));
+    assert_eq!(alice.defending_win_rate(), Some(0.5));
+    assert_eq!(alice.attacking_win_rate(), Some(1.0));
+    assert_eq!(alice.average_points_conceded, Some(80.0));
+
+    let bob = game_results.player_stats("bob").unwrap();
+    assert_eq!(bob.games_attacking, 2);
+    assert_eq!(bob.games_won_attacking, 1);
+    assert_eq!(bob.average_points_conceded, Some(90.0));
+}
+
+#[test]
+fn test_level_progression() {
+    let game_results = make_game_results();
+    for level in ["\"3\"", "\"4\"", "\"5\""] {
+        game_results
+            .record(&[GameResult {
+                level: level.to_string(),
+                ..result("alice", true, true, true)
+            }])
+            .unwrap();
+    }
+    let levels = game_results
+        .level_progression("alice", 2)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.level)
+        .collect::<Vec<_>>();
+    assert_eq!(levels, vec!["\"4\"", "\"5\""]);
+    assert!(game_results.level_progression("bob", 2).unwrap().is_empty());
+}
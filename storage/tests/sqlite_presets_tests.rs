@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use slog::{o, Drain, Logger};
+
+use storage::{PresetError, SqliteStorage, State};
+
+struct NoOpDrain;
+
+impl Drain for NoOpDrain {
+    type Ok = ();
+    type Err = ();
+    fn log(
+        &self,
+        record: &slog::Record,
+        values: &slog::OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        println!("{:?}, {:?}", record.msg(), values);
+        Ok(())
+    }
+}
+
+fn make_logger() -> Logger {
+    let drain = Mutex::new(NoOpDrain).fuse();
+    Logger::root(drain, o!())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct VersionedState {
+    key: Vec<u8>,
+    version: u64,
+}
+
+impl State for VersionedState {
+    type Message = ();
+
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+    fn version(&self) -> u64 {
+        self.version
+    }
+    fn new_from_key(key: Vec<u8>) -> Self {
+        VersionedState { key, version: 0 }
+    }
+}
+
+fn make_presets() -> storage::SqlitePresets {
+    let storage: SqliteStorage<VersionedState> = SqliteStorage::from_connection(
+        make_logger(),
+        rusqlite::Connection::open_in_memory().unwrap(),
+    )
+    .unwrap();
+    storage.presets()
+}
+
+#[test]
+fn test_save_and_load_presets() {
+    let presets = make_presets();
+    assert_eq!(presets.get("casual").unwrap(), None);
+    presets
+        .save("casual", "alice", "{\"num_decks\":2}")
+        .unwrap();
+    presets.save("tournament", "bob", "{}").unwrap();
+
+    let casual = presets.get("casual").unwrap().unwrap();
+    assert_eq!(casual.owner, "alice");
+    assert_eq!(casual.settings, "{\"num_decks\":2}");
+    let names = presets
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|p| p.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["casual", "tournament"]);
+}
+
+#[test]
+fn test_only_owner_can_overwrite_preset() {
+    let presets = make_presets();
+    presets.save("casual", "alice", "{}").unwrap();
+    assert!(matches!(
+        presets.save("casual", "bob", "{\"num_decks\":3}"),
+        Err(PresetError::NameTaken)
+    ));
+    presets
+        .save("casual", "alice", "{\"num_decks\":3}")
+        .unwrap();
+    assert_eq!(
+        presets.get("casual").unwrap().unwrap().settings,
+        "{\"num_decks\":3}"
+    );
+}
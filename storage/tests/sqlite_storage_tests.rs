@@ -0,0 +1,229 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use slog::{o, Drain, Logger};
+
+use storage::{ArchivePolicy, SqliteStorage, SqliteStorageError, State, Storage};
+
+struct NoOpDrain;
+
+impl Drain for NoOpDrain {
+    type Ok = ();
+    type Err = ();
+    fn log(
+        &self,
+        record: &slog::Record,
+        values: &slog::OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        println!("{:?}, {:?}", record.msg(), values);
+        Ok(())
+    }
+}
+
+fn make_logger() -> Logger {
+    let drain = Mutex::new(NoOpDrain).fuse();
+    Logger::root(drain, o!())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct VersionedState {
+    key: Vec<u8>,
+    version: u64,
+}
+
+impl State for VersionedState {
+    type Message = ();
+
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+    fn version(&self) -> u64 {
+        self.version
+    }
+    fn new_from_key(key: Vec<u8>) -> Self {
+        Self { key, version: 0 }
+    }
+}
+
+macro_rules! vs {
+    ($key: expr, $version: expr) => {
+        VersionedState {
+            key: $key.as_bytes().to_vec(),
+            version: $version,
+        }
+    };
+}
+
+fn db_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "shengji-storage-{}-{}.sqlite",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn test_basic_kv() {
+    let path = db_path("basic-kv");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+
+    // Get a non-existent value
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 0)
+    );
+
+    // Put a real value there.
+    s.clone().put(vs!("test", 1)).await.unwrap();
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 1)
+    );
+
+    // Try to race with compare-and-set
+    s.clone().put_cas(0, vs!("test", 2)).await.unwrap_err();
+
+    // Try to successfully compare-and-set
+    s.clone().put_cas(1, vs!("test", 2)).await.unwrap();
+
+    assert_eq!(
+        s.clone().get_all_keys().await.unwrap(),
+        vec![b"test".to_vec()]
+    );
+    assert_eq!(s.clone().stats().await.unwrap(), (1, 0));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_survives_restart() {
+    let path = db_path("restart");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+
+    s.clone().put(vs!("test", 1)).await.unwrap();
+    s.clone()
+        .execute_operation_with_messages::<SqliteStorageError, _>(b"test".to_vec(), |state| {
+            Ok((
+                VersionedState {
+                    version: state.version + 1,
+                    ..state
+                },
+                vec![],
+            ))
+        })
+        .await
+        .unwrap();
+    assert_eq!(s.clone().checkpoint().await.unwrap(), 1);
+    // Nothing changed since the last checkpoint.
+    assert_eq!(s.clone().checkpoint().await.unwrap(), 0);
+    drop(s);
+
+    // The state is loaded lazily when it's next requested.
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+    assert_eq!(s.clone().stats().await.unwrap(), (0, 0));
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 2)
+    );
+    assert_eq!(s.clone().stats().await.unwrap(), (1, 0));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_secret_survives_restart() {
+    let path = db_path("secret");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+    assert_eq!(s.secret("test", || vec![1, 2, 3]).unwrap(), vec![1, 2, 3]);
+    assert_eq!(s.secret("test", || vec![4, 5, 6]).unwrap(), vec![1, 2, 3]);
+    assert_eq!(s.secret("other", || vec![7]).unwrap(), vec![7]);
+    drop(s);
+
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+    assert_eq!(s.secret("test", || vec![4, 5, 6]).unwrap(), vec![1, 2, 3]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_evict() {
+    let path = db_path("evict");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+
+    let mut subscription = s.clone().subscribe(b"test".to_vec(), 0).await.unwrap();
+    s.clone().put(vs!("test", 1)).await.unwrap();
+    s.clone().checkpoint().await.unwrap();
+
+    // Subscribers are dropped, and the state isn't loaded back in.
+    s.clone().evict(b"test").await.unwrap();
+    assert_eq!(subscription.recv().await, None);
+    assert_eq!(s.clone().stats().await.unwrap(), (0, 0));
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 0)
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_checkpoint_on_last_unsubscribe() {
+    let path = db_path("unsubscribe");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path).unwrap();
+
+    let _subscription = s.clone().subscribe(b"test".to_vec(), 0).await.unwrap();
+    s.clone().put(vs!("test", 1)).await.unwrap();
+
+    // The last subscriber leaving evicts the state from memory, but it's
+    // written out first.
+    s.clone().unsubscribe(b"test".to_vec(), 0).await;
+    assert_eq!(s.clone().stats().await.unwrap(), (0, 0));
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 1)
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_archive_idle_states() {
+    let path = db_path("archive");
+    let s: SqliteStorage<VersionedState> = SqliteStorage::open(make_logger(), &path)
+        .unwrap()
+        .with_archive_policy(ArchivePolicy {
+            idle_after: Duration::ZERO,
+            dictionary: br#"{"key":[],"version":0}"#.to_vec(),
+        });
+
+    s.clone().put(vs!("test", 1)).await.unwrap();
+    s.clone().put(vs!("other", 3)).await.unwrap();
+    assert_eq!(s.clone().archive_idle().await.unwrap(), 2);
+    assert_eq!(s.clone().stats().await.unwrap(), (0, 0));
+    let archived = s.list_archived().unwrap();
+    assert_eq!(archived.len(), 2);
+    assert!(archived
+        .iter()
+        .any(|a| a.key == b"test".to_vec() && a.version == 1));
+
+    // Archived states are restored when they're next requested.
+    assert_eq!(
+        s.clone().get(b"test".to_vec()).await.unwrap(),
+        vs!("test", 1)
+    );
+    assert_eq!(s.list_archived().unwrap().len(), 1);
+    assert_eq!(s.clone().checkpoint().await.unwrap(), 1);
+
+    // Purged states are gone for good.
+    assert!(s.purge_archived(b"other").unwrap());
+    assert!(!s.purge_archived(b"other").unwrap());
+    assert_eq!(
+        s.clone().get(b"other".to_vec()).await.unwrap(),
+        vs!("other", 0)
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,219 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use slog::{o, Drain, Logger};
+
+use storage::{CareerRound, SqliteStorage, State, UserError, INITIAL_RATING};
+
+struct NoOpDrain;
+
+impl Drain for NoOpDrain {
+    type Ok = ();
+    type Err = ();
+    fn log(
+        &self,
+        record: &slog::Record,
+        values: &slog::OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        println!("{:?}, {:?}", record.msg(), values);
+        Ok(())
+    }
+}
+
+fn make_logger() -> Logger {
+    let drain = Mutex::new(NoOpDrain).fuse();
+    Logger::root(drain, o!())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct VersionedState {
+    key: Vec<u8>,
+    version: u64,
+}
+
+impl State for VersionedState {
+    type Message = ();
+
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+    fn version(&self) -> u64 {
+        self.version
+    }
+    fn new_from_key(key: Vec<u8>) -> Self {
+        VersionedState { key, version: 0 }
+    }
+}
+
+fn make_users() -> storage::SqliteUsers {
+    let storage: SqliteStorage<VersionedState> = SqliteStorage::from_connection(
+        make_logger(),
+        rusqlite::Connection::open_in_memory().unwrap(),
+    )
+    .unwrap();
+    storage.users()
+}
+
+#[test]
+fn test_unclaimed_nicknames_are_open() {
+    let users = make_users();
+    assert_eq!(users.authenticate("alice", None).unwrap(), None);
+    assert_eq!(users.authenticate("alice", None).unwrap(), None);
+}
+
+#[test]
+fn test_claimed_nicknames_require_key() {
+    let users = make_users();
+    let user = users.authenticate("alice", Some(b"key")).unwrap().unwrap();
+    assert_eq!(user.nickname, "alice");
+    assert_eq!(user.games_played, 0);
+
+    assert!(users.authenticate("alice", Some(b"key")).unwrap().is_some());
+    assert!(matches!(
+        users.authenticate("alice", Some(b"other")),
+        Err(UserError::NicknameClaimed)
+    ));
+    assert!(matches!(
+        users.authenticate("alice", None),
+        Err(UserError::NicknameClaimed)
+    ));
+}
+
+#[test]
+fn test_record_game() {
+    let users = make_users();
+    users.authenticate("alice", Some(b"key")).unwrap();
+    users.record_game("alice", true, 2, "\"4\"").unwrap();
+    users.record_game("alice", false, 0, "\"4\"").unwrap();
+    // Unclaimed nicknames aren't tracked.
+    users.record_game("bob", true, 1, "\"3\"").unwrap();
+
+    let alice = users.get("alice").unwrap().unwrap();
+    assert_eq!(alice.games_played, 2);
+    assert_eq!(alice.games_won, 1);
+    assert_eq!(alice.ranks_up, 2);
+    assert_eq!(alice.level.as_deref(), Some("\"4\""));
+    assert_eq!(users.get("bob").unwrap(), None);
+}
+
+#[test]
+fn test_record_round() {
+    let users = make_users();
+    users.authenticate("alice", Some(b"key")).unwrap();
+    assert_eq!(users.career("alice").unwrap().unwrap().rounds_played, 0);
+
+    users
+        .record_round(
+            "alice",
+            CareerRound {
+                is_landlord: true,
+                won: true,
+                points_captured: 20,
+                largest_failed_throw: 0,
+            },
+        )
+        .unwrap();
+    users
+        .record_round(
+            "alice",
+            CareerRound {
+                is_landlord: true,
+                won: false,
+                points_captured: 0,
+                largest_failed_throw: 3,
+            },
+        )
+        .unwrap();
+    users
+        .record_round(
+            "alice",
+            CareerRound {
+                is_landlord: false,
+                won: true,
+                points_captured: 40,
+                largest_failed_throw: 2,
+            },
+        )
+        .unwrap();
+    // Unclaimed nicknames aren't tracked.
+    users
+        .record_round(
+            "bob",
+            CareerRound {
+                is_landlord: true,
+                won: true,
+                points_captured: 0,
+                largest_failed_throw: 0,
+            },
+        )
+        .unwrap();
+
+    let alice = users.career("alice").unwrap().unwrap();
+    assert_eq!(alice.rounds_played, 3);
+    assert_eq!(alice.rounds_as_landlord, 2);
+    assert_eq!(alice.landlord_defenses, 1);
+    assert_eq!(alice.biggest_throw_forfeited, 3);
+    assert_eq!(alice.landlord_defense_rate(), Some(0.5));
+    assert_eq!(alice.average_points_captured(), Some(20.0));
+    assert_eq!(users.career("bob").unwrap(), None);
+}
+
+#[test]
+fn test_record_rated_game() {
+    let users = make_users();
+    users.authenticate("alice", Some(b"key")).unwrap();
+    users.authenticate("bob", Some(b"key")).unwrap();
+    assert_eq!(users.career("alice").unwrap().unwrap().rating, None);
+
+    // Evenly matched teams move by half the K-factor.
+    let ratings = users
+        .record_rated_game(
+            &["alice".to_string(), "carol".to_string()],
+            &["bob".to_string(), "dave".to_string()],
+            true,
+        )
+        .unwrap();
+    assert_eq!(ratings.len(), 2);
+    assert_eq!(ratings["alice"], INITIAL_RATING + 16.0);
+    assert_eq!(ratings["bob"], INITIAL_RATING - 16.0);
+    assert_eq!(
+        users.career("alice").unwrap().unwrap().rating,
+        Some(INITIAL_RATING + 16.0)
+    );
+
+    // An upset moves the ratings further than an expected result.
+    let ratings = users
+        .record_rated_game(&["bob".to_string()], &["alice".to_string()], true)
+        .unwrap();
+    let alice_delta = ratings["alice"] - (INITIAL_RATING + 16.0);
+    let bob_delta = ratings["bob"] - (INITIAL_RATING - 16.0);
+    assert!(bob_delta > 16.0);
+    // Whatever one team gains, the other loses.
+    assert!((alice_delta + bob_delta).abs() < 1e-9);
+}
+
+#[test]
+fn test_unlock_achievement() {
+    let users = make_users();
+    users.authenticate("alice", Some(b"key")).unwrap();
+    assert!(users
+        .unlock_achievement("alice", "defended_on_ace")
+        .unwrap());
+    assert!(!users
+        .unlock_achievement("alice", "defended_on_ace")
+        .unwrap());
+    assert!(users
+        .unlock_achievement("alice", "five_card_throw")
+        .unwrap());
+    // Unclaimed nicknames can't unlock anything.
+    assert!(!users.unlock_achievement("bob", "five_card_throw").unwrap());
+
+    let achievements = users
+        .achievements("alice")
+        .unwrap()
+        .into_iter()
+        .map(|a| a.achievement)
+        .collect::<Vec<_>>();
+    assert_eq!(achievements, vec!["defended_on_ace", "five_card_throw"]);
+    assert!(users.achievements("bob").unwrap().is_empty());
+}